@@ -1,78 +1,156 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use task_processing_system_rs::{Calculator, Operation};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use task_processing_system_rs::{
+    Calculator, Operation, PrimeCheckAlgorithm, QueuePollingConfig, Task, TaskData, TaskPriority, Worker,
+};
+use tokio_util::sync::CancellationToken;
+
+/// Tasks per burst in the batch dequeue benchmark: 0.1s worth at 50k tasks/s
+const BURST: usize = 5_000;
 
 /// Benchmark factorial calculations
 fn benchmark_factorial(c: &mut Criterion) {
+    let calculator = Calculator::default();
     c.bench_function("factorial_5", |b| {
-        b.iter(|| Calculator::calculate(black_box(Operation::Factorial), black_box(5)))
+        b.iter(|| calculator.calculate(black_box(Operation::Factorial), black_box(5)))
     });
 
     c.bench_function("factorial_10", |b| {
-        b.iter(|| Calculator::calculate(black_box(Operation::Factorial), black_box(10)))
+        b.iter(|| calculator.calculate(black_box(Operation::Factorial), black_box(10)))
     });
 
     c.bench_function("factorial_20", |b| {
-        b.iter(|| Calculator::calculate(black_box(Operation::Factorial), black_box(20)))
+        b.iter(|| calculator.calculate(black_box(Operation::Factorial), black_box(20)))
     });
 }
 
 /// Benchmark fibonacci calculations
 fn benchmark_fibonacci(c: &mut Criterion) {
+    let calculator = Calculator::default();
     c.bench_function("fibonacci_10", |b| {
-        b.iter(|| Calculator::calculate(black_box(Operation::Fibonacci), black_box(10)))
+        b.iter(|| calculator.calculate(black_box(Operation::Fibonacci), black_box(10)))
     });
 
     c.bench_function("fibonacci_30", |b| {
-        b.iter(|| Calculator::calculate(black_box(Operation::Fibonacci), black_box(30)))
+        b.iter(|| calculator.calculate(black_box(Operation::Fibonacci), black_box(30)))
     });
 
     c.bench_function("fibonacci_50", |b| {
-        b.iter(|| Calculator::calculate(black_box(Operation::Fibonacci), black_box(50)))
+        b.iter(|| calculator.calculate(black_box(Operation::Fibonacci), black_box(50)))
     });
 }
 
 /// Benchmark prime check calculations
 fn benchmark_prime_check(c: &mut Criterion) {
+    let calculator = Calculator::default();
     c.bench_function("prime_check_small", |b| {
-        b.iter(|| Calculator::calculate(black_box(Operation::PrimeCheck), black_box(17)))
+        b.iter(|| calculator.calculate(black_box(Operation::PrimeCheck), black_box(17)))
     });
 
     c.bench_function("prime_check_medium", |b| {
-        b.iter(|| Calculator::calculate(black_box(Operation::PrimeCheck), black_box(982451653)))
+        b.iter(|| calculator.calculate(black_box(Operation::PrimeCheck), black_box(982451653)))
     });
 
     c.bench_function("prime_check_large", |b| {
-        b.iter(|| Calculator::calculate(black_box(Operation::PrimeCheck), black_box(9999991)))
+        b.iter(|| calculator.calculate(black_box(Operation::PrimeCheck), black_box(9999991)))
     });
 }
 
+/// Miller-Rabin against trial division on growing primes
+fn benchmark_prime_check_algorithms(c: &mut Criterion) {
+    let mut group = c.benchmark_group("prime_check_algorithms");
+    let (calculator, cancel) = (Calculator::default(), CancellationToken::new());
+    for prime in [982451653u64, 4294967291, 999999999989] {
+        for algorithm in [PrimeCheckAlgorithm::MillerRabin, PrimeCheckAlgorithm::TrialDivision] {
+            group.bench_with_input(BenchmarkId::new(algorithm.to_string(), prime), &prime, |b, &prime| {
+                b.iter(|| calculator.prime_check_with(algorithm, black_box(prime), &cancel))
+            });
+        }
+    }
+    group.finish();
+}
+
 /// Mixed operations benchmark
 fn benchmark_mixed_operations(c: &mut Criterion) {
+    let calculator = Calculator::default();
     let mut group = c.benchmark_group("mixed_operations");
     
     group.bench_function("factorial_vs_fibonacci", |b| {
         b.iter(|| {
-            let _ = Calculator::calculate(black_box(Operation::Factorial), black_box(10));
-            let _ = Calculator::calculate(black_box(Operation::Fibonacci), black_box(20));
+            let _ = calculator.calculate(black_box(Operation::Factorial), black_box(10));
+            let _ = calculator.calculate(black_box(Operation::Fibonacci), black_box(20));
         })
     });
 
     group.bench_function("all_operations", |b| {
         b.iter(|| {
-            let _ = Calculator::calculate(black_box(Operation::Factorial), black_box(8));
-            let _ = Calculator::calculate(black_box(Operation::Fibonacci), black_box(25));
-            let _ = Calculator::calculate(black_box(Operation::PrimeCheck), black_box(97));
+            let _ = calculator.calculate(black_box(Operation::Factorial), black_box(8));
+            let _ = calculator.calculate(black_box(Operation::Fibonacci), black_box(25));
+            let _ = calculator.calculate(black_box(Operation::PrimeCheck), black_box(97));
         })
     });
 
     group.finish();
 }
 
+/// Time a 4-thread worker taking a burst of tiny tasks off its queue with `batch_size`
+async fn drain_burst(batch_size: usize) -> Duration {
+    let polling = QueuePollingConfig {
+        batch_size,
+        ..QueuePollingConfig::default()
+    };
+    let worker = Arc::new(Worker::new(0, 4).with_queue_polling(polling));
+    let runner = Arc::clone(&worker);
+    tokio::spawn(async move { runner.start().await });
+    worker.wait_running().await;
+
+    let tasks: Vec<Task> = (0..BURST)
+        .map(|_| Task::new("bench".to_string(), TaskPriority::Medium, TaskData::new(5, Operation::Factorial)))
+        .collect();
+    let start = Instant::now();
+    for task in tasks {
+        worker.add_task(task).await.unwrap();
+    }
+    while worker.get_stats().await.tasks_processed < BURST as u64 {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+    let elapsed = start.elapsed();
+    worker.stop().await;
+    elapsed
+}
+
+/// Queue throughput on factorial(5) by dequeue batch size (0 = one task per lock)
+fn benchmark_batch_dequeue(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut group = c.benchmark_group("batch_dequeue");
+    group.throughput(Throughput::Elements(BURST as u64));
+    group.sample_size(10);
+
+    for batch_size in [0, 16, 64] {
+        group.bench_with_input(BenchmarkId::from_parameter(batch_size), &batch_size, |b, &batch_size| {
+            b.iter_custom(|iters| {
+                runtime.block_on(async {
+                    let mut total = Duration::ZERO;
+                    for _ in 0..iters {
+                        total += drain_burst(batch_size).await;
+                    }
+                    total
+                })
+            })
+        });
+    }
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     benchmark_factorial,
     benchmark_fibonacci,
     benchmark_prime_check,
-    benchmark_mixed_operations
+    benchmark_prime_check_algorithms,
+    benchmark_mixed_operations,
+    benchmark_batch_dequeue
 );
 criterion_main!(benches);
\ No newline at end of file
@@ -48,7 +48,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
 /// Check if system is healthy
 async fn check_health(client: &Client, base_url: &str) -> bool {
-    match client.get(&format!("{}/health", base_url)).send().await {
+    match client.get(format!("{}/health", base_url)).send().await {
         Ok(response) => response.status().is_success(),
         Err(_) => false,
     }
@@ -77,7 +77,7 @@ async fn sequential_load_test(
         });
 
         match client
-            .post(&format!("{}/task/create", base_url))
+            .post(format!("{}/task/create", base_url))
             .json(&payload)
             .send()
             .await
@@ -137,7 +137,7 @@ async fn concurrent_load_test(
             });
 
             client
-                .post(&format!("{}/task/create", base_url))
+                .post(format!("{}/task/create", base_url))
                 .json(&payload)
                 .send()
                 .await
@@ -199,7 +199,7 @@ async fn priority_load_test(
         });
 
         let _ = client
-            .post(&format!("{}/task/create", base_url))
+            .post(format!("{}/task/create", base_url))
             .json(&payload)
             .send()
             .await;
@@ -251,7 +251,7 @@ async fn mixed_operations_test(
         });
 
         let _ = client
-            .post(&format!("{}/task/create", base_url))
+            .post(format!("{}/task/create", base_url))
             .json(&payload)
             .send()
             .await;
@@ -274,7 +274,7 @@ async fn mixed_operations_test(
 /// Print system statistics
 async fn print_system_stats(client: &Client, base_url: &str) -> Result<(), Box<dyn Error>> {
     let response = client
-        .get(&format!("{}/stats", base_url))
+        .get(format!("{}/stats", base_url))
         .send()
         .await?;
 
@@ -0,0 +1,227 @@
+//! Operator actions on workers (`/admin/...` and `task-processor admin`)
+//!
+//! Workers are fixed at startup, so "scaling" pauses or resumes them: a
+//! paused worker gets no new tasks and stops taking tasks off its queue.
+//! Rebalancing moves queued tasks off paused and overloaded workers so every
+//! active worker has an even share.
+
+use crate::types::*;
+use crate::worker::Worker;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// What pausing one worker stops
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseMode {
+    /// Take the worker out of service (`/admin/workers/{id}/pause`): it gets
+    /// no new tasks, scheduled ones included, stops taking tasks off its
+    /// queue, and rebalancing moves its queued tasks to active workers
+    Worker,
+    /// Hold only its queue (`/workers/{id}/pause`): it keeps getting new
+    /// tasks, which wait queued with the rest until processing resumes
+    Processing,
+}
+
+/// Pause (or resume, when `pause` is false) `worker` as `mode` says; returns the reply message
+pub fn set_paused(worker: &Worker, mode: PauseMode, pause: bool) -> String {
+    let done = if pause { "paused" } else { "resumed" };
+    match (mode, pause) {
+        (PauseMode::Worker, true) => worker.pause(),
+        (PauseMode::Worker, false) => worker.resume(),
+        (PauseMode::Processing, true) => worker.pause_processing(),
+        (PauseMode::Processing, false) => worker.resume_processing(),
+    }
+    match mode {
+        PauseMode::Worker => format!("Worker {} {}", worker.id, done),
+        PauseMode::Processing => format!("Processing {} on worker {}", done, worker.id),
+    }
+}
+
+/// First unpaused worker at or after `start`, in round-robin order
+pub fn next_active_worker(workers: &[Arc<Worker>], start: usize) -> Option<&Arc<Worker>> {
+    (0..workers.len())
+        .map(|offset| &workers[(start + offset) % workers.len()])
+        .find(|worker| !worker.is_paused())
+}
+
+pub async fn worker_stats(workers: &[Arc<Worker>]) -> Vec<WorkerStats> {
+    let mut stats = Vec::with_capacity(workers.len());
+    for worker in workers {
+        stats.push(worker.get_stats().await);
+    }
+    stats
+}
+
+/// Keep the first `active` workers taking tasks and pause the rest
+pub fn scale(workers: &[Arc<Worker>], active: usize) -> Result<(), ValidationError> {
+    if active == 0 || active > workers.len() {
+        return Err(ValidationError::InvalidScale {
+            requested: active,
+            max: workers.len(),
+        });
+    }
+    for worker in workers {
+        match (worker.id < active, worker.is_paused()) {
+            (true, true) => worker.resume(),
+            (false, false) => worker.pause(),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Remove every stored task in `status` (completed, failed or cancelled only)
+pub fn purge(workers: &[Arc<Worker>], status: &TaskStatus) -> Result<usize, ValidationError> {
+    if !status.is_terminal() {
+        return Err(ValidationError::UnpurgeableStatus(status.clone()));
+    }
+    Ok(workers.iter().map(|worker| worker.purge(status)).sum())
+}
+
+/// Queue length each worker should end up with: an even split over the active workers
+fn rebalance_targets(paused: &[bool], total: usize) -> Vec<usize> {
+    let active = paused.iter().filter(|&&paused| !paused).count();
+    if active == 0 {
+        return vec![0; paused.len()];
+    }
+    let mut extra = total % active;
+    paused
+        .iter()
+        .map(|&paused| {
+            if paused {
+                return 0;
+            }
+            let share = total / active + usize::from(extra > 0);
+            extra = extra.saturating_sub(1);
+            share
+        })
+        .collect()
+}
+
+/// Move queued tasks so every active worker has an even share; returns how many moved
+///
+/// Nothing moves while every worker is paused.
+pub async fn rebalance(workers: &[Arc<Worker>]) -> usize {
+    let paused: Vec<bool> = workers.iter().map(|worker| worker.is_paused()).collect();
+    if paused.iter().all(|&paused| paused) {
+        return 0;
+    }
+    let mut loads = Vec::with_capacity(workers.len());
+    for worker in workers {
+        loads.push(worker.queue_len().await);
+    }
+    let targets = rebalance_targets(&paused, loads.iter().sum());
+
+    // Queues keep draining meanwhile, so take what is there rather than trusting `loads`
+    let mut surplus = Vec::new();
+    for (index, worker) in workers.iter().enumerate() {
+        if loads[index] > targets[index] {
+            let taken = worker.take_queued(loads[index] - targets[index]).await;
+            surplus.extend(taken.into_iter().map(|(task, buffer)| (index, task, buffer)));
+        }
+    }
+
+    // Top up the workers below their target; anything left over (queues drained
+    // meanwhile) goes to another active worker
+    let active: Vec<usize> = (0..workers.len()).filter(|&index| !paused[index]).collect();
+    let mut deficits = active
+        .iter()
+        .flat_map(|&index| std::iter::repeat_n(index, targets[index].saturating_sub(loads[index])));
+
+    let mut moved = 0;
+    for (source, task, buffer) in surplus {
+        let target = deficits
+            .next()
+            .or_else(|| active.iter().copied().find(|&index| index != source))
+            .unwrap_or(source);
+        let task_id = task.id.clone();
+        match workers[target].requeue(task.clone(), buffer).await {
+            Ok(()) => {
+                workers[source].release(&task_id);
+                moved += 1;
+            }
+            // Target's queue full, or same ID already on it: put it back where it was
+            Err(e) => {
+                warn!("Cannot move task {} to worker {}: {}", task_id, target, e);
+                workers[source].put_back(task).await;
+            }
+        }
+    }
+
+    if moved > 0 {
+        info!("Rebalanced {} queued tasks", moved);
+    }
+    moved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queued_task(input: u64) -> Task {
+        Task::new("Queued".to_string(), TaskPriority::Low, TaskData::new(input, Operation::Factorial))
+    }
+
+    #[test]
+    fn test_rebalance_targets() {
+        assert_eq!(rebalance_targets(&[false, false, false], 7), vec![3, 2, 2]);
+        assert_eq!(rebalance_targets(&[false, true, false], 5), vec![3, 0, 2]);
+        assert_eq!(rebalance_targets(&[true, true], 4), vec![0, 0]);
+    }
+
+    #[tokio::test]
+    async fn test_rebalance_empties_paused_worker() {
+        // Workers aren't started, so queues only change through rebalancing
+        let workers: Vec<Arc<Worker>> = (0..3).map(|id| Arc::new(Worker::new(id, 1))).collect();
+        for input in 1..=6 {
+            workers[0].add_task(queued_task(input)).await.unwrap();
+        }
+        workers[0].pause();
+
+        assert_eq!(rebalance(&workers).await, 6);
+        let loads: Vec<usize> = worker_stats(&workers).await.iter().map(|w| w.current_load).collect();
+        assert_eq!(loads, vec![0, 3, 3]);
+        // A moved task is only stored on its new worker
+        let moved = workers[1].queue_snapshot().await[0].id.clone();
+        assert!(workers[0].get_task(&moved).is_none());
+
+        // Already balanced: nothing to do
+        assert_eq!(rebalance(&workers).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_scale_and_selection() {
+        let workers: Vec<Arc<Worker>> = (0..3).map(|id| Arc::new(Worker::new(id, 1))).collect();
+        assert!(scale(&workers, 0).is_err());
+        assert!(scale(&workers, 4).is_err());
+
+        scale(&workers, 1).unwrap();
+        assert!(!workers[0].is_paused() && workers[1].is_paused() && workers[2].is_paused());
+        assert_eq!(next_active_worker(&workers, 2).unwrap().id, 0);
+
+        scale(&workers, 3).unwrap();
+        assert_eq!(next_active_worker(&workers, 2).unwrap().id, 2);
+
+        workers.iter().for_each(|worker| worker.pause());
+        assert!(next_active_worker(&workers, 0).is_none());
+    }
+
+    #[test]
+    fn test_pause_modes() {
+        let worker = Worker::new(0, 1);
+        assert_eq!(set_paused(&worker, PauseMode::Processing, true), "Processing paused on worker 0");
+        assert!(worker.is_processing_paused() && !worker.is_paused());
+
+        assert_eq!(set_paused(&worker, PauseMode::Worker, true), "Worker 0 paused");
+        assert!(worker.is_paused());
+        set_paused(&worker, PauseMode::Processing, false);
+        assert!(!worker.is_processing_paused() && worker.is_paused());
+    }
+
+    #[test]
+    fn test_purge_only_finished() {
+        let workers = vec![Arc::new(Worker::new(0, 1))];
+        assert!(purge(&workers, &TaskStatus::Pending).is_err());
+        assert_eq!(purge(&workers, &TaskStatus::Failed).unwrap(), 0);
+    }
+}
@@ -0,0 +1,308 @@
+//! Local SQLite analytics of finished calculations
+//!
+//! With `[analytics]` configured, every worker records one row per task whose
+//! calculation finished, successfully or not, in the `task_analytics` table
+//! of a local SQLite file: the operation, input and its magnitude, the worker,
+//! and how long the task queued and took to process. Rows are written by a
+//! background thread, so workers never wait on the disk. Unlike the tasks, the
+//! rows survive restarts; `task-processor analyze` summarizes them.
+
+use crate::types::{Task, TaskStatus};
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OpenFlags};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::warn;
+
+const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS task_analytics (
+    task_id TEXT NOT NULL,
+    operation TEXT NOT NULL,
+    input TEXT NOT NULL,
+    input_digits INTEGER NOT NULL,
+    worker INTEGER NOT NULL,
+    outcome TEXT NOT NULL,
+    queue_ms REAL NOT NULL,
+    processing_ms REAL NOT NULL,
+    finished_at TEXT NOT NULL
+)";
+
+/// One finished calculation
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskRecord {
+    pub task_id: String,
+    pub operation: String,
+    pub input: u64,
+    pub worker: usize,
+    /// `completed` when the calculation succeeded, `failed` otherwise
+    pub outcome: &'static str,
+    /// From creation until a thread picked the task up
+    pub queue_ms: f64,
+    pub processing_ms: f64,
+    pub finished_at: DateTime<Utc>,
+}
+
+impl TaskRecord {
+    pub fn new(task: &Task, worker: usize, queued: Duration, processing: Duration) -> Self {
+        Self {
+            task_id: task.id.clone(),
+            operation: task.data.operation.to_string(),
+            input: task.data.input,
+            worker,
+            outcome: if task.status == TaskStatus::Failed { "failed" } else { "completed" },
+            queue_ms: queued.as_secs_f64() * 1000.0,
+            processing_ms: processing.as_secs_f64() * 1000.0,
+            finished_at: Utc::now(),
+        }
+    }
+}
+
+/// Decimal digits of `input`, the magnitude failure rates are grouped by
+pub fn input_digits(input: u64) -> u32 {
+    input.checked_ilog10().unwrap_or(0) + 1
+}
+
+/// Inputs with `digits` decimal digits, e.g. `10-99` for 2
+pub fn input_range(digits: u32) -> String {
+    let low = if digits <= 1 { 0 } else { 10u128.pow(digits - 1) };
+    let high = (10u128.pow(digits) - 1).min(u64::MAX as u128);
+    format!("{}-{}", low, high)
+}
+
+enum Message {
+    Record(TaskRecord),
+    /// Answered once everything queued before it is written
+    Flush(mpsc::Sender<()>),
+}
+
+/// Writes [`TaskRecord`]s to the analytics database on a background thread
+pub struct AnalyticsSink {
+    sender: Mutex<Option<mpsc::Sender<Message>>>,
+    writer: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl AnalyticsSink {
+    /// Open (creating if needed) the database at `path`
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.execute(CREATE_TABLE, [])?;
+        let (sender, receiver) = mpsc::channel();
+        let writer = std::thread::spawn(move || write_records(connection, receiver));
+        Ok(Self {
+            sender: Mutex::new(Some(sender)),
+            writer: Mutex::new(Some(writer)),
+        })
+    }
+
+    /// Queue `record` for writing (dropped once the sink is closed)
+    pub fn record(&self, record: TaskRecord) {
+        if let Some(sender) = self.sender.lock().as_ref() {
+            let _ = sender.send(Message::Record(record));
+        }
+    }
+
+    /// Wait until everything recorded so far is written
+    pub fn flush(&self) {
+        let (done, written) = mpsc::channel();
+        if let Some(sender) = self.sender.lock().as_ref() {
+            if sender.send(Message::Flush(done)).is_ok() {
+                let _ = written.recv();
+            }
+        }
+    }
+
+    /// Write everything queued so far and stop the writer
+    pub fn close(&self) {
+        self.sender.lock().take();
+        if let Some(writer) = self.writer.lock().take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// Insert records as they arrive, everything already queued in one transaction
+fn write_records(mut connection: Connection, receiver: mpsc::Receiver<Message>) {
+    while let Ok(first) = receiver.recv() {
+        let mut records = Vec::new();
+        let mut flushes = Vec::new();
+        for message in std::iter::once(first).chain(receiver.try_iter()) {
+            match message {
+                Message::Record(record) => records.push(record),
+                Message::Flush(done) => flushes.push(done),
+            }
+        }
+        if !records.is_empty() {
+            if let Err(e) = insert(&mut connection, &records) {
+                warn!("Failed to write {} analytics rows: {}", records.len(), e);
+            }
+        }
+        for done in flushes {
+            let _ = done.send(());
+        }
+    }
+}
+
+fn insert(connection: &mut Connection, records: &[TaskRecord]) -> rusqlite::Result<()> {
+    let transaction = connection.transaction()?;
+    {
+        let mut statement = transaction.prepare_cached(
+            "INSERT INTO task_analytics
+                (task_id, operation, input, input_digits, worker, outcome, queue_ms, processing_ms, finished_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        )?;
+        for record in records {
+            statement.execute(params![
+                record.task_id,
+                record.operation,
+                record.input.to_string(),
+                input_digits(record.input),
+                record.worker as i64,
+                record.outcome,
+                record.queue_ms,
+                record.processing_ms,
+                record.finished_at.to_rfc3339(),
+            ])?;
+        }
+    }
+    transaction.commit()
+}
+
+/// Processing times of one operation
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationTiming {
+    pub operation: String,
+    pub tasks: u64,
+    pub mean_processing_ms: f64,
+    pub max_processing_ms: f64,
+    pub mean_queue_ms: f64,
+}
+
+/// Failures of one operation on inputs of one magnitude
+#[derive(Debug, Clone, Serialize)]
+pub struct FailureRate {
+    pub operation: String,
+    pub input_range: String,
+    pub tasks: u64,
+    pub failed: u64,
+    pub failure_rate: f64,
+}
+
+/// What `task-processor analyze` prints
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalyticsReport {
+    pub tasks: u64,
+    /// Slowest mean processing time first
+    pub slowest_operations: Vec<OperationTiming>,
+    /// By operation, then input range
+    pub failure_rates: Vec<FailureRate>,
+}
+
+/// Summarize the analytics database at `path` (which must exist)
+pub fn analyze(path: &Path) -> rusqlite::Result<AnalyticsReport> {
+    let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+
+    let mut statement = connection.prepare(
+        "SELECT operation, COUNT(*), AVG(processing_ms), MAX(processing_ms), AVG(queue_ms)
+         FROM task_analytics GROUP BY operation ORDER BY AVG(processing_ms) DESC, operation",
+    )?;
+    let slowest_operations = statement
+        .query_map([], |row| {
+            Ok(OperationTiming {
+                operation: row.get(0)?,
+                tasks: row.get::<_, i64>(1)? as u64,
+                mean_processing_ms: row.get(2)?,
+                max_processing_ms: row.get(3)?,
+                mean_queue_ms: row.get(4)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut statement = connection.prepare(
+        "SELECT operation, input_digits, COUNT(*), SUM(outcome = 'failed')
+         FROM task_analytics GROUP BY operation, input_digits ORDER BY operation, input_digits",
+    )?;
+    let failure_rates = statement
+        .query_map([], |row| {
+            let tasks = row.get::<_, i64>(2)? as u64;
+            let failed = row.get::<_, i64>(3)? as u64;
+            Ok(FailureRate {
+                operation: row.get(0)?,
+                input_range: input_range(row.get(1)?),
+                tasks,
+                failed,
+                failure_rate: failed as f64 / tasks as f64,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(AnalyticsReport {
+        tasks: slowest_operations.iter().map(|timing| timing.tasks).sum(),
+        slowest_operations,
+        failure_rates,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Operation, TaskData, TaskPriority};
+
+    fn record(operation: Operation, input: u64, failed: bool, processing_ms: u64) -> TaskRecord {
+        let mut task = Task::new("Analyzed".to_string(), TaskPriority::Low, TaskData::new(input, operation));
+        if failed {
+            task.set_failed("too large".to_string());
+        }
+        TaskRecord::new(&task, 0, Duration::from_millis(1), Duration::from_millis(processing_ms))
+    }
+
+    #[test]
+    fn test_input_ranges() {
+        assert_eq!(input_digits(0), 1);
+        assert_eq!(input_digits(9), 1);
+        assert_eq!(input_digits(10), 2);
+        assert_eq!(input_digits(u64::MAX), 20);
+        assert_eq!(input_range(1), "0-9");
+        assert_eq!(input_range(3), "100-999");
+        assert_eq!(input_range(20), format!("10000000000000000000-{}", u64::MAX));
+    }
+
+    #[test]
+    fn test_report() {
+        let dir = std::env::temp_dir().join(format!("analytics-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("analytics.db");
+
+        let sink = AnalyticsSink::open(&path).unwrap();
+        sink.record(record(Operation::Factorial, 5, false, 1));
+        sink.record(record(Operation::Factorial, 25, true, 1));
+        sink.record(record(Operation::Factorial, 30, true, 1));
+        sink.record(record(Operation::PrimeCheck, 1_000_003, false, 40));
+        sink.flush();
+        assert_eq!(analyze(&path).unwrap().tasks, 4);
+        sink.close();
+        // Closed sinks drop records
+        sink.record(record(Operation::PrimeCheck, 7, false, 1));
+
+        let report = analyze(&path).unwrap();
+        assert_eq!(report.tasks, 4);
+        assert_eq!(report.slowest_operations[0].operation, "prime_check");
+        assert_eq!(report.slowest_operations[1].tasks, 3);
+
+        let factorial: Vec<_> = report.failure_rates.iter().filter(|rate| rate.operation == "factorial").collect();
+        assert_eq!(factorial.len(), 2);
+        assert_eq!((factorial[0].input_range.as_str(), factorial[0].failed), ("0-9", 0));
+        assert_eq!((factorial[1].input_range.as_str(), factorial[1].failure_rate), ("10-99", 1.0));
+
+        // Reopening appends to the same table
+        let sink = AnalyticsSink::open(&path).unwrap();
+        sink.record(record(Operation::Fibonacci, 10, false, 1));
+        sink.close();
+        assert_eq!(analyze(&path).unwrap().tasks, 5);
+
+        assert!(analyze(&dir.join("missing.db")).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
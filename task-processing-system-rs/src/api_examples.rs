@@ -0,0 +1,638 @@
+use crate::api_schemas::Body;
+use crate::field_selection::FieldSelection;
+use crate::latency::LatencySummary;
+use crate::runtime_metrics::RuntimeStats;
+use crate::stats_delta::{StatsDelta, WorkerDelta};
+use crate::types::*;
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use warp::http::StatusCode;
+
+/// Canonical request/response pair for one API route
+#[derive(Debug, Clone, Serialize)]
+pub struct RouteExample {
+    pub method: &'static str,
+    pub path: &'static str,
+    pub description: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request: Option<Value>,
+    /// Schema of `request`, one of the [`component_schemas`](crate::api_schemas::component_schemas)
+    #[serde(skip)]
+    pub request_schema: Option<&'static str>,
+    pub status: u16,
+    #[serde(skip)]
+    pub response_schema: Body,
+    pub response: Value,
+}
+
+/// Fixed timestamp so the examples are stable across calls
+fn example_time() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 15, 10, 30, 0).unwrap()
+}
+
+/// Latency of the single task in the stats example
+fn example_latency() -> LatencySummary {
+    LatencySummary {
+        count: 1,
+        min_ms: 0.412,
+        mean_ms: 0.412,
+        p50_ms: 0.412,
+        p90_ms: 0.412,
+        p95_ms: 0.412,
+        p99_ms: 0.412,
+        p999_ms: 0.412,
+        max_ms: 0.412,
+    }
+}
+
+fn to_value<T: Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+fn example_task() -> Task {
+    let mut task = Task::with_id(
+        "task-001".to_string(),
+        "Process calculation".to_string(),
+        TaskPriority::High,
+        TaskData::new(10, Operation::Factorial),
+    );
+    task.created_at = example_time();
+    task
+}
+
+/// Build the examples for every route served by the orchestrator
+///
+/// Bodies are produced by serializing the same types the handlers use, so
+/// they stay in sync with the server.
+pub fn route_examples() -> Vec<RouteExample> {
+    let create_request = CreateTaskRequest {
+        id: "task-001".to_string(),
+        title: "Process calculation".to_string(),
+        priority: TaskPriority::High,
+        data: TaskData::new(10, Operation::Factorial),
+        expected_result: Some("3628800".to_string()),
+        namespace: None,
+        callback_url: None,
+        run_at: None,
+        delay_seconds: None,
+        idempotency_key: None,
+        depends_on: Vec::new(),
+        labels: BTreeMap::from([("team".to_string(), "payments".to_string())]),
+        timeout_ms: None,
+    };
+
+    let mut updated_task = example_task();
+    updated_task.title = "Urgent calculation".to_string();
+    updated_task.queue_position = Some(1);
+
+    let mut accepted_task = example_task();
+    accepted_task.expected_result = Some("3628800".to_string());
+    accepted_task.labels = create_request.labels.clone();
+    accepted_task.queue_position = Some(1);
+
+    let mut processing_task = example_task();
+    processing_task.expected_result = Some("3628800".to_string());
+    processing_task.labels = create_request.labels.clone();
+    processing_task.attempts = 1;
+    processing_task.worker_id = Some(0);
+    processing_task.started_at = Some(example_time());
+    processing_task.processing_duration_ms = Some(2);
+    processing_task.set_processing("3628800".to_string());
+    processing_task.check_expected_result();
+
+    let stats = SystemStats::from_workers(
+        vec![WorkerStats {
+            id: 0,
+            tasks_processed: 1,
+            tasks_completed: 0,
+            tasks_failed: 0,
+            tasks_cancelled: 0,
+            tasks_deleted: 0,
+            task_retries: 0,
+            result_mismatches: 0,
+            storage_evictions: 0,
+            tasks_expired: 0,
+            wedged_threads: 0,
+            threads_respawned: 0,
+            current_load: 0,
+            queued_by_priority: QueueDepth::default(),
+            uptime_seconds: 60,
+            is_healthy: true,
+            paused: false,
+            processing_paused: false,
+            speed_factor: 1.0,
+            latency: example_latency(),
+            processing_by_operation: BTreeMap::from([("factorial".to_string(), example_latency())]),
+            restarts: 0,
+            tasks_stolen: 0,
+            tasks_stolen_from: 0,
+        }],
+        1,
+        60,
+    )
+    .with_latency(example_latency())
+    .with_processing_latency(BTreeMap::from([("factorial".to_string(), example_latency())]));
+
+    vec![
+        RouteExample {
+            method: "POST",
+            path: "/task/create",
+            description: "Create a new task (Location points at it)",
+            request: Some(to_value(&create_request)),
+            request_schema: Some("CreateTaskRequest"),
+            status: 201,
+            response_schema: Body::Schema("Task"),
+            response: to_value(&accepted_task),
+        },
+        RouteExample {
+            method: "POST",
+            path: "/task/create",
+            description: "Create a task with invalid input",
+            request: Some(to_value(&CreateTaskRequest {
+                data: TaskData::new(25, Operation::Factorial),
+                expected_result: None,
+                namespace: None,
+                callback_url: None,
+                run_at: None,
+                delay_seconds: None,
+                idempotency_key: None,
+                depends_on: Vec::new(),
+                labels: BTreeMap::new(),
+                timeout_ms: None,
+                ..create_request.clone()
+            })),
+            request_schema: Some("CreateTaskRequest"),
+            status: 400,
+            response_schema: Body::Schema("ErrorResponse"),
+            response: to_value(
+                &ErrorResponse::new(
+                    StatusCode::BAD_REQUEST,
+                    ValidationError::InputTooLarge {
+                        operation: Operation::Factorial,
+                        input: 25,
+                        max_allowed: 20,
+                    }
+                    .to_string(),
+                )
+                .with_reason("validation_failed"),
+            ),
+        },
+        RouteExample {
+            method: "POST",
+            path: "/task/create",
+            description: "Create a task whose ID is already taken (Location points at it)",
+            request: Some(to_value(&create_request)),
+            request_schema: Some("CreateTaskRequest"),
+            status: 409,
+            response_schema: Body::Schema("DuplicateTaskResponse"),
+            response: to_value(&DuplicateTaskResponse::new(&processing_task, true)),
+        },
+        RouteExample {
+            method: "POST",
+            path: "/task/execute",
+            description: "Create a task and wait for its result",
+            request: Some(to_value(&ExecuteTaskRequest {
+                task: create_request.clone(),
+                max_wait_ms: Some(2_000),
+            })),
+            request_schema: Some("ExecuteTaskRequest"),
+            status: 200,
+            response_schema: Body::Schema("Task"),
+            response: to_value(&processing_task),
+        },
+        RouteExample {
+            method: "POST",
+            path: "/task/execute",
+            description: "Task not finished within max_wait_ms (poll the Location header)",
+            request: Some(to_value(&ExecuteTaskRequest {
+                task: create_request.clone(),
+                max_wait_ms: Some(50),
+            })),
+            request_schema: Some("ExecuteTaskRequest"),
+            status: 202,
+            response_schema: Body::Schema("TaskCreatedResponse"),
+            response: to_value(&TaskCreatedResponse::still_processing("task-001".to_string(), 50)),
+        },
+        RouteExample {
+            method: "GET",
+            path: "/task/{id}",
+            description: "Get task information",
+            request: None,
+            request_schema: None,
+            status: 200,
+            response_schema: Body::Schema("Task"),
+            response: to_value(&processing_task),
+        },
+        RouteExample {
+            method: "GET",
+            path: "/task/{id}",
+            description: "Only the fields listed in ?fields=id,status,result (also on /tasks)",
+            request: None,
+            request_schema: None,
+            status: 200,
+            response_schema: Body::Fields("Task"),
+            response: FieldSelection::parse(Some("id,status,result"))
+                .map(|selection| selection.apply(&processing_task))
+                .unwrap_or_default(),
+        },
+        RouteExample {
+            method: "GET",
+            path: "/tasks",
+            description: "Newest tasks, filtered by ?status= and ?label=key:value and paged by ?limit=, ?offset= and ?sort=",
+            request: None,
+            request_schema: None,
+            status: 200,
+            response_schema: Body::ArrayOf("Task"),
+            response: to_value(&vec![processing_task.clone()]),
+        },
+        RouteExample {
+            method: "GET",
+            path: "/task/{id}/wait",
+            description: "Wait up to ?timeout_ms= (default 5000) for the task to be calculated; 202 if it wasn't",
+            request: None,
+            request_schema: None,
+            status: 200,
+            response_schema: Body::Schema("Task"),
+            response: to_value(&processing_task),
+        },
+        RouteExample {
+            method: "POST",
+            path: "/task/{id}/complete",
+            description: "Mark a processed task as completed",
+            request: None,
+            request_schema: None,
+            status: 200,
+            response_schema: Body::Schema("TaskCompletionResponse"),
+            response: to_value(&TaskCompletionResponse {
+                id: "task-001".to_string(),
+                status: TaskStatus::Completed,
+                message: "Task completed successfully".to_string(),
+            }),
+        },
+        RouteExample {
+            method: "POST",
+            path: "/task/{id}/cancel",
+            description: "Cancel a pending or processed task, stopping its calculation",
+            request: None,
+            request_schema: None,
+            status: 200,
+            response_schema: Body::Schema("TaskCompletionResponse"),
+            response: to_value(&TaskCompletionResponse {
+                id: "task-001".to_string(),
+                status: TaskStatus::Cancelled,
+                message: "Task cancelled".to_string(),
+            }),
+        },
+        RouteExample {
+            method: "PATCH",
+            path: "/task/{id}",
+            description: "Change the priority and/or title of a queued task",
+            request: Some(to_value(&TaskUpdateRequest {
+                priority: Some(TaskPriority::High),
+                title: Some("Urgent calculation".to_string()),
+            })),
+            request_schema: Some("TaskUpdateRequest"),
+            status: 200,
+            response_schema: Body::Schema("Task"),
+            response: to_value(&updated_task),
+        },
+        RouteExample {
+            method: "DELETE",
+            path: "/task/{id}",
+            description: "Remove a waiting, pending or finished task (?force=true for processed ones too)",
+            request: None,
+            request_schema: None,
+            status: 200,
+            response_schema: Body::Schema("TaskCompletionResponse"),
+            response: to_value(&TaskCompletionResponse {
+                id: "task-001".to_string(),
+                status: TaskStatus::Completed,
+                message: "Task deleted".to_string(),
+            }),
+        },
+        RouteExample {
+            method: "GET",
+            path: "/stats",
+            description: "System and per-worker statistics",
+            request: None,
+            request_schema: None,
+            status: 200,
+            response_schema: Body::Schema("SystemStats"),
+            response: to_value(&stats),
+        },
+        RouteExample {
+            method: "GET",
+            path: "/stats/delta?since=3f9c2a1b-41",
+            description: "Counters changed since a previous /stats/delta token",
+            request: None,
+            request_schema: None,
+            status: 200,
+            response_schema: Body::Schema("StatsDelta"),
+            response: to_value(&StatsDelta {
+                token: "3f9c2a1b-42".to_string(),
+                full: false,
+                totals: BTreeMap::from([("total_tasks_processed".to_string(), 43)]),
+                workers: vec![WorkerDelta {
+                    id: 1,
+                    counters: BTreeMap::from([
+                        ("current_load".to_string(), 0),
+                        ("tasks_processed".to_string(), 15),
+                    ]),
+                }],
+            }),
+        },
+        RouteExample {
+            method: "GET",
+            path: "/stats/runtime",
+            description: "Tokio runtime health and scheduler delay",
+            request: None,
+            request_schema: None,
+            status: 200,
+            response_schema: Body::Schema("RuntimeStats"),
+            response: to_value(&RuntimeStats {
+                runtime_workers: 4,
+                alive_tasks: 17,
+                global_queue_depth: 0,
+                scheduler_delay_ms: 0.042,
+                max_scheduler_delay_ms: 12.5,
+                scheduler_stalls: 1,
+                probes: 240,
+                blocked_processing_threads: 0,
+            }),
+        },
+        RouteExample {
+            method: "POST",
+            path: "/subscriptions",
+            description: "Register a webhook subscription",
+            request: Some(serde_json::json!({
+                "url": "https://hooks.internal/factorials",
+                "events": ["task.completed"],
+                "filter": {"operations": ["factorial"], "priorities": [3]},
+                "secret": "s3cret"
+            })),
+            request_schema: Some("SubscriptionRequest"),
+            status: 201,
+            response_schema: Body::Schema("Subscription"),
+            response: to_value(&Subscription {
+                id: "7d0c4b6e-2f1a-4c8e-9b3d-5a6f7e8d9c0b".to_string(),
+                url: "https://hooks.internal/factorials".to_string(),
+                events: vec![WebhookEventType::TaskCompleted],
+                filter: SubscriptionFilter {
+                    operations: vec![Operation::Factorial],
+                    priorities: vec![TaskPriority::High],
+                },
+                secret: None,
+                has_secret: true,
+                created_at: example_time(),
+            }),
+        },
+        RouteExample {
+            method: "POST",
+            path: "/admin/workers/{id}/pause",
+            description: "Pause a worker: no new tasks, its queue waits",
+            request: None,
+            request_schema: None,
+            status: 200,
+            response_schema: Body::Schema("AdminResponse"),
+            response: to_value(&AdminResponse {
+                message: "Worker 0 paused".to_string(),
+                tasks_affected: 0,
+                workers: vec![WorkerStats {
+                    paused: true,
+                    ..stats.workers[0].clone()
+                }],
+            }),
+        },
+        RouteExample {
+            method: "POST",
+            path: "/admin/pause",
+            description: "Pause processing on every worker: submissions still queue until /admin/resume",
+            request: None,
+            request_schema: None,
+            status: 200,
+            response_schema: Body::Schema("AdminResponse"),
+            response: to_value(&AdminResponse {
+                message: "Processing paused on 1 workers".to_string(),
+                tasks_affected: 0,
+                workers: vec![WorkerStats {
+                    processing_paused: true,
+                    ..stats.workers[0].clone()
+                }],
+            }),
+        },
+        RouteExample {
+            method: "POST",
+            path: "/admin/scale",
+            description: "Keep the first N workers active and pause the rest",
+            request: Some(to_value(&ScaleRequest { workers: 1 })),
+            request_schema: Some("ScaleRequest"),
+            status: 200,
+            response_schema: Body::Schema("AdminResponse"),
+            response: to_value(&AdminResponse {
+                message: "1 of 1 workers active".to_string(),
+                tasks_affected: 0,
+                workers: stats.workers.clone(),
+            }),
+        },
+        RouteExample {
+            method: "POST",
+            path: "/task/{id}/priority",
+            description: "Operator priority override of a queued task, recorded in the audit history",
+            request: Some(to_value(&PriorityChangeRequest {
+                priority: TaskPriority::High,
+                reason: Some("customer escalation".to_string()),
+            })),
+            request_schema: Some("PriorityChangeRequest"),
+            status: 200,
+            response_schema: Body::Schema("Task"),
+            response: to_value(&Task {
+                priority: TaskPriority::High,
+                queue_position: Some(1),
+                ..example_task()
+            }),
+        },
+        RouteExample {
+            method: "GET",
+            path: "/admin/audit",
+            description: "Recorded operator changes, newest first, filtered by ?task_id= or ?actor=",
+            request: None,
+            request_schema: None,
+            status: 200,
+            response_schema: Body::ArrayOf("AuditEntry"),
+            response: to_value(&vec![AuditEntry {
+                at: example_time(),
+                actor: "ops".to_string(),
+                task_id: "task-001".to_string(),
+                change: AuditChange::PriorityChanged {
+                    from: TaskPriority::Low,
+                    to: TaskPriority::High,
+                },
+                reason: Some("customer escalation".to_string()),
+            }]),
+        },
+        RouteExample {
+            method: "GET",
+            path: "/admin/limits",
+            description: "Every limit in effect: input ranges, queue, timeouts, retention",
+            request: None,
+            request_schema: None,
+            status: 200,
+            response_schema: Body::Schema("EffectiveLimits"),
+            response: to_value(&EffectiveLimits {
+                operations: vec![OperationLimits {
+                    operation: Operation::Factorial,
+                    min_input: 0,
+                    max_input: 20,
+                    max_big_input: Some(449),
+                }],
+                max_pipeline_stages: MAX_PIPELINE_STAGES,
+                max_task_retries: MAX_TASK_RETRIES,
+                max_retry_backoff_ms: MAX_RETRY_BACKOFF_MS,
+                validators: vec!["large_input_priority".to_string()],
+                queue: QueueLimits {
+                    polling: QueuePollingMode::NotifyWithFallback,
+                    fallback_interval_ms: Some(100),
+                    batch_sizes: vec![0],
+                    max_queue_size: 0,
+                },
+                timeouts: TimeoutLimits {
+                    default_execute_wait_ms: DEFAULT_EXECUTE_WAIT_MS,
+                    max_execute_wait_ms: MAX_EXECUTE_WAIT_MS,
+                    prime_generation_ms: 1000,
+                    webhook_delivery_ms: 5000,
+                    slow_request_threshold_ms: 0,
+                    default_task_ms: 0,
+                },
+                retention: RetentionLimits {
+                    max_tasks_per_worker: 0,
+                    completed_task_ttl_seconds: 0,
+                    max_list_limit: MAX_LIST_LIMIT,
+                },
+            }),
+        },
+        RouteExample {
+            method: "GET",
+            path: "/health",
+            description: "Health check",
+            request: None,
+            request_schema: None,
+            status: 200,
+            response_schema: Body::Schema("HealthResponse"),
+            response: to_value(&HealthResponse {
+                status: "healthy".to_string(),
+                timestamp: example_time(),
+            }),
+        },
+        RouteExample {
+            method: "GET",
+            path: "/healthz",
+            description: "Liveness probe: the process is serving requests",
+            request: None,
+            request_schema: None,
+            status: 200,
+            response_schema: Body::Schema("HealthResponse"),
+            response: to_value(&HealthResponse {
+                status: "alive".to_string(),
+                timestamp: example_time(),
+            }),
+        },
+        RouteExample {
+            method: "GET",
+            path: "/readyz",
+            description: "Readiness probe: accepting tasks, all workers running, no queue full",
+            request: None,
+            request_schema: None,
+            status: 200,
+            response_schema: Body::Schema("ReadinessResponse"),
+            response: to_value(&ReadinessResponse {
+                status: "ready".to_string(),
+                accepting_tasks: true,
+                workers_running: 3,
+                workers_total: 3,
+                full_queues: 0,
+                reasons: Vec::new(),
+                timestamp: example_time(),
+            }),
+        },
+        RouteExample {
+            method: "GET",
+            path: "/readyz",
+            description: "Not ready: a worker isn't running",
+            request: None,
+            request_schema: None,
+            status: 503,
+            response_schema: Body::Schema("ReadinessResponse"),
+            response: to_value(&ReadinessResponse {
+                status: "not_ready".to_string(),
+                accepting_tasks: true,
+                workers_running: 2,
+                workers_total: 3,
+                full_queues: 0,
+                reasons: vec!["2 of 3 workers running".to_string()],
+                timestamp: example_time(),
+            }),
+        },
+        RouteExample {
+            method: "GET",
+            path: "/metrics",
+            description: "Prometheus text-format counters",
+            request: None,
+            request_schema: None,
+            status: 200,
+            response_schema: Body::Text,
+            response: Value::String(
+                "http_rejections_total{reason=\"not_found\"} 1\n".to_string(),
+            ),
+        },
+        RouteExample {
+            method: "GET",
+            path: "/unknown",
+            description: "Rejected request (unknown route)",
+            request: None,
+            request_schema: None,
+            status: 404,
+            response_schema: Body::Schema("ErrorResponse"),
+            response: to_value(&ErrorResponse::new(StatusCode::NOT_FOUND, "Not found").with_reason("not_found")),
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calculations::Calculator;
+
+    #[test]
+    fn test_examples_round_trip() {
+        let examples = route_examples();
+        assert!(examples.iter().any(|e| e.path == "/task/create"));
+
+        // The create example must be accepted by the real request parser
+        let create = examples.iter().find(|e| e.path == "/task/create").unwrap();
+        let request: CreateTaskRequest =
+            serde_json::from_value(create.request.clone().unwrap()).unwrap();
+        assert!(request.into_task(&Calculator::default()).is_ok());
+        // ...and answered with the whole task
+        let created: Task = serde_json::from_value(create.response.clone()).unwrap();
+        assert_eq!((create.status, created.id.as_str()), (201, "task-001"));
+
+        // The execute body is a create body plus max_wait_ms
+        let execute = examples.iter().find(|e| e.path == "/task/execute").unwrap();
+        let request: ExecuteTaskRequest =
+            serde_json::from_value(execute.request.clone().unwrap()).unwrap();
+        assert_eq!(request.max_wait_ms().unwrap(), 2_000);
+        assert_eq!(request.task.id, "task-001");
+        assert!(request.task.into_task(&Calculator::default()).is_ok());
+
+        let task = examples.iter().find(|e| e.path == "/task/{id}").unwrap();
+        assert_eq!(task.response["status"], "processing");
+        assert_eq!(task.response["result"], "3628800");
+
+        let subscribe = examples.iter().find(|e| e.path == "/subscriptions").unwrap();
+        let request: SubscriptionRequest =
+            serde_json::from_value(subscribe.request.clone().unwrap()).unwrap();
+        assert!(request.validate().is_ok());
+    }
+}
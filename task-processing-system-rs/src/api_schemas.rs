@@ -0,0 +1,603 @@
+//! Schemas of the API's request and response bodies, for `GET /openapi.json`
+//!
+//! Written out by hand like the config schema: every field the types can
+//! serialize or accept is listed, optional ones included, with `required`
+//! naming the fields a request must carry (or a response always has) and
+//! enums spelled out. The tests check each schema against the type's serde
+//! fields and the route examples.
+
+use serde_json::{json, Map, Value};
+
+/// Schema of a request or response body
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Body {
+    /// One of the [`component_schemas`]
+    Schema(&'static str),
+    /// An array of one
+    ArrayOf(&'static str),
+    /// Some fields of one, as picked by `?fields=`
+    Fields(&'static str),
+    /// Plain text (`/metrics`)
+    Text,
+}
+
+/// `#/components/schemas/...` reference to `name`
+pub fn reference(name: &str) -> Value {
+    json!({"$ref": format!("#/components/schemas/{}", name)})
+}
+
+fn array_of(name: &str) -> Value {
+    json!({"type": "array", "items": reference(name)})
+}
+
+fn string_enum(description: &str, values: &[&str]) -> Value {
+    json!({"type": "string", "enum": values, "description": description})
+}
+
+/// Non-negative integer; an empty `description` is left out
+fn integer(description: &str) -> Value {
+    let mut schema = json!({"type": "integer", "minimum": 0});
+    if !description.is_empty() {
+        schema["description"] = json!(description);
+    }
+    schema
+}
+
+/// Object whose every property is always present
+fn all_required(description: &str, properties: Map<String, Value>) -> Value {
+    let required: Vec<String> = properties.keys().cloned().collect();
+    json!({"type": "object", "description": description, "required": required, "properties": properties})
+}
+
+fn counters(fields: &[(&str, &str)]) -> Map<String, Value> {
+    fields.iter().map(|(name, description)| (name.to_string(), integer(description))).collect()
+}
+
+fn task_data() -> Value {
+    json!({
+        "type": "object",
+        "description": "What to calculate",
+        "required": ["type", "operation"],
+        "properties": {
+            "type": string_enum("Task kind", &["calculation"]),
+            "input": integer("Input of the operation, ignored when inputs is given (default 0)"),
+            "inputs": {"type": "array", "items": {"type": "integer", "minimum": 0}, "maxItems": 1000, "description": "Calculate the operation for each of these instead; the result is an array"},
+            "parallel": {"type": "boolean", "default": false, "description": "Spread inputs over several threads"},
+            "input2": integer("Second operand of gcd and lcm"),
+            "operation": reference("Operation"),
+            "algorithm": reference("FibonacciAlgorithm"),
+            "prime_check_algorithm": reference("PrimeCheckAlgorithm"),
+            "pipeline": {"type": "array", "items": reference("Operation"), "maxItems": 10, "description": "Further operations, each taking the previous result as input"},
+            "precision": reference("Precision"),
+            "max_retries": {"type": "integer", "minimum": 0, "maximum": 10, "default": 0, "description": "Times a failed calculation is queued again before the task fails"},
+            "retry_backoff_ms": {"type": "integer", "minimum": 0, "default": 500, "description": "Wait before the first retry; doubled for every further one"}
+        }
+    })
+}
+
+fn create_task_request() -> Value {
+    json!({
+        "type": "object",
+        "required": ["title", "data"],
+        "properties": {
+            "id": {"type": "string", "description": "Task ID (default: a generated UUID)"},
+            "title": {"type": "string", "minLength": 1},
+            "priority": reference("TaskPriority"),
+            "data": reference("TaskData"),
+            "expected_result": {"type": "string", "description": "Result the caller expects; compared after processing"},
+            "namespace": {"type": "string", "description": "Target namespace (default: the API key's first namespace, or \"default\")"},
+            "callback_url": {"type": "string", "pattern": "^https?://", "description": "POSTed the task when it's calculated, completed or fails"},
+            "run_at": {"type": "string", "format": "date-time", "description": "Hold the task until this time before queueing it"},
+            "delay_seconds": {"type": "integer", "minimum": 0, "maximum": 604800, "description": "Hold the task this many seconds before queueing it (instead of run_at)"},
+            "idempotency_key": {"type": "string", "description": "Repeats with the same key return the task created first"},
+            "depends_on": {"type": "array", "items": {"type": "string"}, "description": "Hold the task until each of these tasks is completed"},
+            "labels": {"type": "object", "additionalProperties": {"type": "string"}, "description": "Key/value tags stored on the task as given"},
+            "timeout_ms": {"type": "integer", "minimum": 1, "description": "Fail the task if a calculation attempt takes longer (server default when unset)"}
+        }
+    })
+}
+
+fn execute_task_request() -> Value {
+    let mut schema = create_task_request();
+    schema["description"] = json!("A create request plus how long to wait for the result");
+    schema["properties"]["max_wait_ms"] =
+        json!({"type": "integer", "minimum": 1, "maximum": 60000, "default": 5000, "description": "Wait this long before answering 202"});
+    schema
+}
+
+fn task() -> Value {
+    json!({
+        "type": "object",
+        "required": ["id", "title", "priority", "namespace", "created_at", "data", "status"],
+        "properties": {
+            "id": {"type": "string"},
+            "title": {"type": "string"},
+            "priority": reference("TaskPriority"),
+            "namespace": {"type": "string"},
+            "created_at": reference("Timestamp"),
+            "data": reference("TaskData"),
+            "status": reference("TaskStatus"),
+            "result": reference("TaskResult"),
+            "error_message": {"type": "string"},
+            "completed_at": reference("Timestamp"),
+            "algorithm": reference("FibonacciAlgorithm"),
+            "stages": {"type": "array", "items": reference("StageResult"), "description": "Per-stage results for pipeline tasks"},
+            "expected_result": {"type": "string"},
+            "callback_url": {"type": "string"},
+            "run_at": reference("Timestamp"),
+            "depends_on": {"type": "array", "items": {"type": "string"}},
+            "labels": {"type": "object", "additionalProperties": {"type": "string"}},
+            "timeout_ms": integer("Milliseconds one calculation attempt may take"),
+            "attempts": integer("Calculation attempts so far, retries included"),
+            "matched": {"type": "boolean", "description": "Whether the result matched expected_result"},
+            "result_signature": {"type": "string", "description": "Hex HMAC-SHA256 of the result, when result signing is enabled"},
+            "queue_position": {"type": "integer", "minimum": 1, "description": "1-based place in the worker's queue (pending tasks)"},
+            "worker_id": integer("Worker that took the task off its queue"),
+            "started_at": reference("Timestamp"),
+            "processing_duration_ms": integer("How long the latest calculation attempt took")
+        }
+    })
+}
+
+fn stage_result() -> Value {
+    json!({
+        "type": "object",
+        "required": ["stage", "operation", "input"],
+        "properties": {
+            "stage": integer("Position in the pipeline"),
+            "operation": reference("Operation"),
+            "input": integer("Input of this stage"),
+            "result": reference("TaskResult"),
+            "error": {"type": "string"}
+        }
+    })
+}
+
+fn error_response() -> Value {
+    json!({
+        "type": "object",
+        "description": "RFC 7807 problem",
+        "required": ["type", "title", "status", "detail", "error"],
+        "properties": {
+            "type": {"type": "string", "default": "about:blank"},
+            "title": {"type": "string", "description": "The status code's reason phrase"},
+            "status": {"type": "integer"},
+            "detail": {"type": "string"},
+            "error": {"type": "string", "description": "Same as detail, for clients of the original error body"},
+            "reason": {"type": "string", "description": "Stable machine-readable error code, e.g. not_found or duplicate_task"}
+        }
+    })
+}
+
+fn duplicate_task_response() -> Value {
+    let mut schema = error_response();
+    schema["description"] = json!("Problem naming the task that holds the ID");
+    schema["properties"]["existing"] = reference("ExistingTask");
+    schema
+}
+
+fn latency_summary() -> Value {
+    let properties: Map<String, Value> = std::iter::once(("count".to_string(), integer("Samples")))
+        .chain(
+            ["min_ms", "mean_ms", "p50_ms", "p90_ms", "p95_ms", "p99_ms", "p999_ms", "max_ms"]
+                .iter()
+                .map(|name| (name.to_string(), json!({"type": "number"}))),
+        )
+        .collect();
+    all_required("Latency percentiles in milliseconds", properties)
+}
+
+fn worker_stats() -> Value {
+    let mut properties = counters(&[
+        ("id", "Worker ID"),
+        ("tasks_processed", ""),
+        ("tasks_completed", ""),
+        ("tasks_failed", ""),
+        ("tasks_cancelled", ""),
+        ("tasks_deleted", ""),
+        ("task_retries", "Failed calculations queued again"),
+        ("result_mismatches", "Tasks whose result differed from expected_result"),
+        ("storage_evictions", "Finished tasks dropped to stay within max_tasks_per_worker"),
+        ("tasks_expired", "Finished tasks dropped after completed_task_ttl_seconds"),
+        ("wedged_threads", "Times a processing thread was found stuck"),
+        ("threads_respawned", "Replacement threads started for wedged ones"),
+        ("current_load", "Queued tasks"),
+        ("uptime_seconds", ""),
+        ("restarts", "Supervisor restarts after a panic"),
+        ("tasks_stolen", "Queued tasks taken from busier peers"),
+        ("tasks_stolen_from", "Queued tasks idle peers took from this worker"),
+    ]);
+    properties.insert("queued_by_priority".to_string(), reference("QueueDepth"));
+    properties.insert("is_healthy".to_string(), json!({"type": "boolean"}));
+    properties.insert("paused".to_string(), json!({"type": "boolean", "description": "Takes no new tasks (/admin/workers/{id}/pause)"}));
+    properties.insert("processing_paused".to_string(), json!({"type": "boolean", "description": "Takes new tasks but leaves them queued"}));
+    properties.insert("speed_factor".to_string(), json!({"type": "number", "description": "Multiplier on simulated processing time"}));
+    properties.insert("latency".to_string(), reference("LatencySummary"));
+    properties.insert(
+        "processing_by_operation".to_string(),
+        json!({"type": "object", "additionalProperties": reference("LatencySummary")}),
+    );
+    all_required("One worker's counters and latencies", properties)
+}
+
+fn system_stats() -> Value {
+    let mut properties = counters(&[
+        ("total_tasks_processed", ""),
+        ("total_tasks_completed", ""),
+        ("total_tasks_failed", ""),
+        ("total_tasks_cancelled", ""),
+        ("total_tasks_deleted", ""),
+        ("total_result_mismatches", ""),
+        ("total_storage_evictions", ""),
+        ("total_tasks_expired", ""),
+        ("total_wedged_threads", ""),
+        ("total_threads_respawned", ""),
+        ("scheduled_tasks", "Tasks held until their run_at time"),
+        ("total_scheduled_dispatched", "Scheduled tasks handed to a worker once due"),
+        ("blocked_tasks", "Tasks held until their dependencies complete"),
+        ("worker_restarts", ""),
+        ("total_workers", ""),
+        ("uptime_seconds", ""),
+        ("process_uptime", "Seconds since this process started"),
+    ]);
+    properties.insert("processing_paused".to_string(), json!({"type": "boolean", "description": "Every worker's processing is paused"}));
+    properties.insert("cumulative".to_string(), reference("CumulativeStats"));
+    properties.insert("latency".to_string(), reference("LatencySummary"));
+    properties.insert(
+        "processing_by_operation".to_string(),
+        json!({"type": "object", "additionalProperties": reference("LatencySummary")}),
+    );
+    properties.insert("workers".to_string(), array_of("WorkerStats"));
+    all_required("System totals and per-worker statistics", properties)
+}
+
+fn cumulative_stats() -> Value {
+    let mut properties = counters(&[
+        ("uptime_seconds", "Seconds running, over every run"),
+        ("tasks_processed", ""),
+        ("tasks_completed", ""),
+        ("tasks_failed", ""),
+        ("tasks_cancelled", ""),
+        ("tasks_deleted", ""),
+        ("worker_restarts", ""),
+    ]);
+    properties.insert(
+        "workers".to_string(),
+        json!({"type": "object", "additionalProperties": reference("WorkerCounters"), "description": "Task counters by worker ID"}),
+    );
+    all_required("Counters over this run and every earlier one recorded by [stats_checkpoint]", properties)
+}
+
+fn effective_limits() -> Value {
+    json!({
+        "type": "object",
+        "required": ["operations", "max_pipeline_stages", "max_task_retries", "max_retry_backoff_ms", "validators", "queue", "timeouts", "retention"],
+        "properties": {
+            "operations": array_of("OperationLimits"),
+            "max_pipeline_stages": integer(""),
+            "max_task_retries": integer("Most max_retries a task may ask for"),
+            "max_retry_backoff_ms": integer("Cap on the wait before any retry"),
+            "validators": {"type": "array", "items": {"type": "string"}, "description": "Custom validators run after the built-in checks"},
+            "queue": reference("QueueLimits"),
+            "timeouts": reference("TimeoutLimits"),
+            "retention": reference("RetentionLimits")
+        }
+    })
+}
+
+/// Every named schema, keyed by type name, for `components/schemas`
+pub fn component_schemas() -> Map<String, Value> {
+    let schemas = [
+        ("TaskPriority", json!({"type": "integer", "enum": [1, 2, 3], "description": "1 = low, 2 = medium (default), 3 = high"})),
+        (
+            "TaskStatus",
+            string_enum("Task state", &["scheduled", "blocked", "pending", "processing", "completed", "failed", "cancelled"]),
+        ),
+        (
+            "Operation",
+            string_enum("Calculation", &["factorial", "fibonacci", "prime_check", "generate_prime", "gcd", "lcm"]),
+        ),
+        (
+            "FibonacciAlgorithm",
+            string_enum("Fibonacci algorithm (default: the configured one)", &["iterative", "fast_doubling", "matrix"]),
+        ),
+        (
+            "PrimeCheckAlgorithm",
+            string_enum("Prime check algorithm (default: the configured one)", &["miller_rabin", "trial_division"]),
+        ),
+        ("Precision", string_enum("Arithmetic for factorial and fibonacci (default native)", &["native", "big"])),
+        ("WebhookEventType", string_enum("Webhook event", &["task.created", "task.completed"])),
+        (
+            "Timestamp",
+            json!({
+                "description": "RFC 3339, or milliseconds since the Unix epoch with timestamp_format = \"epoch_millis\" or an x-timestamp-format header",
+                "oneOf": [{"type": "string", "format": "date-time"}, {"type": "integer"}]
+            }),
+        ),
+        (
+            "TaskResult",
+            json!({
+                "description": "The result string, or with result_format = \"typed\" a tagged value",
+                "oneOf": [
+                    {"type": "string"},
+                    {
+                        "type": "object",
+                        "required": ["type", "value"],
+                        "properties": {
+                            "type": {"type": "string", "enum": ["integer", "boolean", "json"]},
+                            "value": {"description": "Decimal digits as a string for integers"}
+                        }
+                    }
+                ]
+            }),
+        ),
+        ("TaskData", task_data()),
+        ("StageResult", stage_result()),
+        ("Task", task()),
+        ("CreateTaskRequest", create_task_request()),
+        ("ExecuteTaskRequest", execute_task_request()),
+        (
+            "TaskUpdateRequest",
+            json!({
+                "type": "object",
+                "description": "The fields to change, at least one",
+                "minProperties": 1,
+                "properties": {"priority": reference("TaskPriority"), "title": {"type": "string", "minLength": 1}}
+            }),
+        ),
+        (
+            "PriorityChangeRequest",
+            json!({
+                "type": "object",
+                "required": ["priority"],
+                "properties": {
+                    "priority": reference("TaskPriority"),
+                    "reason": {"type": "string", "description": "Kept in the audit history"}
+                }
+            }),
+        ),
+        (
+            "ScaleRequest",
+            json!({
+                "type": "object",
+                "required": ["workers"],
+                "properties": {"workers": {"type": "integer", "minimum": 1, "description": "Workers that keep taking tasks; the rest are paused"}}
+            }),
+        ),
+        (
+            "SubscriptionFilter",
+            json!({
+                "type": "object",
+                "description": "Which tasks the subscription hears about (empty lists match everything)",
+                "properties": {
+                    "operations": array_of("Operation"),
+                    "priorities": array_of("TaskPriority")
+                }
+            }),
+        ),
+        (
+            "SubscriptionRequest",
+            json!({
+                "type": "object",
+                "required": ["url"],
+                "properties": {
+                    "url": {"type": "string", "pattern": "^https?://"},
+                    "events": {"type": "array", "items": reference("WebhookEventType"), "minItems": 1, "description": "Event types to deliver (default: all)"},
+                    "filter": reference("SubscriptionFilter"),
+                    "secret": {"type": "string", "description": "HMAC-SHA256 key for the X-Webhook-Signature header"}
+                }
+            }),
+        ),
+        (
+            "Subscription",
+            json!({
+                "type": "object",
+                "required": ["id", "url", "events", "filter", "has_secret", "created_at"],
+                "properties": {
+                    "id": {"type": "string"},
+                    "url": {"type": "string"},
+                    "events": array_of("WebhookEventType"),
+                    "filter": reference("SubscriptionFilter"),
+                    "has_secret": {"type": "boolean"},
+                    "created_at": {"type": "string", "format": "date-time"}
+                }
+            }),
+        ),
+        (
+            "TaskCreatedResponse",
+            json!({
+                "type": "object",
+                "required": ["id", "status", "message"],
+                "properties": {"id": {"type": "string"}, "status": reference("TaskStatus"), "message": {"type": "string"}}
+            }),
+        ),
+        (
+            "TaskCompletionResponse",
+            json!({
+                "type": "object",
+                "required": ["id", "status", "message"],
+                "properties": {"id": {"type": "string"}, "status": reference("TaskStatus"), "message": {"type": "string"}}
+            }),
+        ),
+        ("ErrorResponse", error_response()),
+        ("DuplicateTaskResponse", duplicate_task_response()),
+        (
+            "ExistingTask",
+            json!({
+                "type": "object",
+                "description": "The task holding the ID, left out when it's in another namespace",
+                "required": ["id", "status", "created_at", "url"],
+                "properties": {
+                    "id": {"type": "string"},
+                    "status": reference("TaskStatus"),
+                    "created_at": reference("Timestamp"),
+                    "url": {"type": "string", "description": "Canonical GET path of the task"}
+                }
+            }),
+        ),
+        ("LatencySummary", latency_summary()),
+        (
+            "QueueDepth",
+            all_required("Queued tasks of each priority", counters(&[("high", ""), ("medium", ""), ("low", "")])),
+        ),
+        ("WorkerStats", worker_stats()),
+        ("SystemStats", system_stats()),
+        ("CumulativeStats", cumulative_stats()),
+        (
+            "WorkerCounters",
+            all_required(
+                "One worker's counters in CumulativeStats",
+                counters(&[("tasks_processed", ""), ("tasks_completed", ""), ("tasks_failed", ""), ("tasks_cancelled", "")]),
+            ),
+        ),
+        (
+            "StatsDelta",
+            json!({
+                "type": "object",
+                "required": ["token", "full", "totals", "workers"],
+                "properties": {
+                    "token": {"type": "string", "description": "Pass as since on the next request"},
+                    "full": {"type": "boolean", "description": "Every counter is included"},
+                    "totals": {"type": "object", "additionalProperties": {"type": "integer"}, "description": "Changed system totals"},
+                    "workers": array_of("WorkerDelta")
+                }
+            }),
+        ),
+        (
+            "WorkerDelta",
+            json!({
+                "type": "object",
+                "description": "A worker's changed counters, next to its id",
+                "required": ["id"],
+                "properties": {"id": integer("Worker ID")},
+                "additionalProperties": {"type": "integer"}
+            }),
+        ),
+        (
+            "RuntimeStats",
+            all_required(
+                "Tokio runtime health",
+                counters(&[
+                    ("runtime_workers", "Runtime worker threads"),
+                    ("alive_tasks", "Tasks alive on the runtime"),
+                    ("global_queue_depth", "Tasks waiting in the runtime's global queue"),
+                    ("scheduler_stalls", "Probes delayed by more than the stall threshold"),
+                    ("probes", ""),
+                    ("blocked_processing_threads", "Processing threads inside a calculation"),
+                ])
+                .into_iter()
+                .chain([
+                    ("scheduler_delay_ms".to_string(), json!({"type": "number", "description": "Spawn-to-poll delay of the latest probe"})),
+                    ("max_scheduler_delay_ms".to_string(), json!({"type": "number"})),
+                ])
+                .collect(),
+            ),
+        ),
+        (
+            "AdminResponse",
+            json!({
+                "type": "object",
+                "required": ["message", "tasks_affected", "workers"],
+                "properties": {
+                    "message": {"type": "string"},
+                    "tasks_affected": integer("Tasks moved by a rebalance or removed by a purge"),
+                    "workers": array_of("WorkerStats")
+                }
+            }),
+        ),
+        (
+            "AuditEntry",
+            json!({
+                "type": "object",
+                "required": ["at", "actor", "task_id", "action", "from", "to"],
+                "properties": {
+                    "at": reference("Timestamp"),
+                    "actor": {"type": "string", "description": "Name of the API key that made the change (anonymous without auth)"},
+                    "task_id": {"type": "string"},
+                    "action": string_enum("What changed", &["priority_changed"]),
+                    "from": reference("TaskPriority"),
+                    "to": reference("TaskPriority"),
+                    "reason": {"type": "string"}
+                }
+            }),
+        ),
+        ("EffectiveLimits", effective_limits()),
+        (
+            "OperationLimits",
+            json!({
+                "type": "object",
+                "required": ["operation", "min_input", "max_input"],
+                "properties": {
+                    "operation": reference("Operation"),
+                    "min_input": integer(""),
+                    "max_input": integer(""),
+                    "max_big_input": integer("Largest input with precision \"big\", for operations that have it")
+                }
+            }),
+        ),
+        (
+            "QueueLimits",
+            json!({
+                "type": "object",
+                "required": ["polling", "batch_sizes", "max_queue_size"],
+                "properties": {
+                    "polling": string_enum("How idle threads notice new tasks", &["notify", "notify_with_fallback"]),
+                    "fallback_interval_ms": integer("Queue check interval, when polling falls back to it"),
+                    "batch_sizes": {"type": "array", "items": {"type": "integer", "minimum": 0}, "description": "Tasks taken per wakeup, by worker ID (0 = one at a time)"},
+                    "max_queue_size": integer("Queued tasks per worker before submissions get 429 (0 = unlimited)")
+                }
+            }),
+        ),
+        (
+            "TimeoutLimits",
+            all_required(
+                "Timeouts in milliseconds",
+                counters(&[
+                    ("default_execute_wait_ms", "POST /task/execute wait when max_wait_ms isn't given"),
+                    ("max_execute_wait_ms", ""),
+                    ("prime_generation_ms", "Time allowed for one generate_prime calculation"),
+                    ("webhook_delivery_ms", ""),
+                    ("slow_request_threshold_ms", "Requests slower than this are logged (0 = disabled)"),
+                    ("default_task_ms", "Time allowed for one attempt of a task without timeout_ms (0 = unlimited)"),
+                ]),
+            ),
+        ),
+        (
+            "RetentionLimits",
+            all_required(
+                "How long tasks are kept",
+                counters(&[
+                    ("max_tasks_per_worker", "Tasks stored per worker before finished ones are evicted (0 = unlimited)"),
+                    ("completed_task_ttl_seconds", "Seconds finished tasks are kept (0 = forever)"),
+                    ("max_list_limit", "Most tasks one GET /tasks page returns"),
+                ]),
+            ),
+        ),
+        (
+            "HealthResponse",
+            json!({
+                "type": "object",
+                "required": ["status", "timestamp"],
+                "properties": {"status": {"type": "string"}, "timestamp": {"type": "string", "format": "date-time"}}
+            }),
+        ),
+        (
+            "ReadinessResponse",
+            json!({
+                "type": "object",
+                "required": ["status", "accepting_tasks", "workers_running", "workers_total", "full_queues", "timestamp"],
+                "properties": {
+                    "status": string_enum("ready (200) or not_ready (503)", &["ready", "not_ready"]),
+                    "accepting_tasks": {"type": "boolean", "description": "New tasks are accepted (not stopping or draining)"},
+                    "workers_running": integer(""),
+                    "workers_total": integer(""),
+                    "full_queues": integer("Workers whose queue is at max_queue_size"),
+                    "reasons": {"type": "array", "items": {"type": "string"}, "description": "Why the instance isn't ready"},
+                    "timestamp": {"type": "string", "format": "date-time"}
+                }
+            }),
+        ),
+    ];
+
+    schemas.into_iter().map(|(name, schema)| (name.to_string(), schema)).collect()
+}
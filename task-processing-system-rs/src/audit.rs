@@ -0,0 +1,116 @@
+//! History of operator changes to individual tasks
+//!
+//! Changes made through operator routes such as `POST /task/{id}/priority`
+//! are recorded with who made them and why, and served by
+//! `GET /admin/audit`. Like the tasks, the history lives in memory; the last
+//! [`AUDIT_HISTORY`] entries are kept.
+
+use crate::query::{ListParams, ListSpec, QueryError, Sort};
+use crate::types::{AuditEntry, MAX_LIST_LIMIT};
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+
+/// Entries kept before the oldest are dropped
+pub const AUDIT_HISTORY: usize = 1000;
+
+/// Parameters of `GET /admin/audit`
+pub const AUDIT_LIST: ListSpec = ListSpec {
+    default_limit: 100,
+    max_limit: MAX_LIST_LIMIT,
+    sort_fields: &["at"],
+    default_sort: Sort {
+        field: "at",
+        descending: true,
+    },
+    filters: &["task_id", "actor"],
+};
+
+#[derive(Debug, Default)]
+pub struct AuditLog {
+    entries: Mutex<VecDeque<AuditEntry>>,
+}
+
+impl AuditLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, entry: AuditEntry) {
+        let mut entries = self.entries.lock();
+        if entries.len() == AUDIT_HISTORY {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// Every kept entry, oldest first
+    pub fn entries(&self) -> Vec<AuditEntry> {
+        self.entries.lock().iter().cloned().collect()
+    }
+
+    /// One page of entries for `GET /admin/audit`, optionally for one `task_id` or `actor`
+    pub fn page(&self, params: &ListParams) -> Result<Vec<AuditEntry>, QueryError> {
+        let task_id = params.filter::<String>("task_id")?;
+        let actor = params.filter::<String>("actor")?;
+        // Recording order breaks ties between entries with the same timestamp
+        let entries: Vec<(usize, AuditEntry)> = self
+            .entries()
+            .into_iter()
+            .enumerate()
+            .filter(|(_, entry)| task_id.as_ref().is_none_or(|id| &entry.task_id == id))
+            .filter(|(_, entry)| actor.as_ref().is_none_or(|actor| &entry.actor == actor))
+            .collect();
+        let page = params.apply(entries, |_, (a_seq, a), (b_seq, b)| (a.at, a_seq).cmp(&(b.at, b_seq)));
+        Ok(page.into_iter().map(|(_, entry)| entry).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{AuditChange, TaskPriority};
+    use chrono::Utc;
+    use std::collections::HashMap;
+
+    fn entry(task_id: &str, actor: &str) -> AuditEntry {
+        AuditEntry {
+            at: Utc::now(),
+            actor: actor.to_string(),
+            task_id: task_id.to_string(),
+            change: AuditChange::PriorityChanged {
+                from: TaskPriority::Low,
+                to: TaskPriority::High,
+            },
+            reason: None,
+        }
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let log = AuditLog::new();
+        for i in 0..AUDIT_HISTORY + 5 {
+            log.record(entry(&i.to_string(), "ops"));
+        }
+        let entries = log.entries();
+        assert_eq!(entries.len(), AUDIT_HISTORY);
+        assert_eq!(entries[0].task_id, "5");
+    }
+
+    #[test]
+    fn test_page_filters() {
+        let log = AuditLog::new();
+        log.record(entry("a", "ops"));
+        log.record(entry("b", "ops"));
+        log.record(entry("a", "oncall"));
+
+        let params = |pairs: &[(&str, &str)]| {
+            let query: HashMap<String, String> = pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+            AUDIT_LIST.parse(&query).unwrap()
+        };
+        let for_a = log.page(&params(&[("task_id", "a")])).unwrap();
+        assert_eq!(for_a.len(), 2);
+        // Newest first by default
+        assert_eq!(for_a[0].actor, "oncall");
+        assert_eq!(log.page(&params(&[("actor", "ops")])).unwrap().len(), 2);
+    }
+}
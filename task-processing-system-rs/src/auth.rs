@@ -0,0 +1,199 @@
+//! API keys scoped to task namespaces
+//!
+//! Auth is off until `api_keys` are configured. With keys, every route except
+//! `/health` needs `Authorization: Bearer <key>` (or `X-Api-Key: <key>`).
+//! Each key lists the namespaces it may use; tasks it creates land in one of
+//! them, and reading or completing a task in any other namespace is a 403.
+
+use crate::types::{ApiKeyConfig, DEFAULT_NAMESPACE};
+use std::collections::HashMap;
+use std::sync::Arc;
+use warp::http::HeaderMap;
+use warp::reject::Reject;
+use warp::{Filter, Rejection};
+
+/// Alternative to `Authorization: Bearer <key>`
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Namespace entry granting access to every namespace
+pub const ALL_NAMESPACES: &str = "*";
+
+/// Missing or unknown API key (401)
+#[derive(Debug)]
+pub struct Unauthorized;
+
+impl Reject for Unauthorized {}
+
+/// Valid key, but not for this namespace (403)
+#[derive(Debug)]
+pub struct Forbidden {
+    pub namespace: String,
+}
+
+impl Reject for Forbidden {}
+
+/// Caller identified by its API key
+#[derive(Debug, Clone)]
+pub struct Principal {
+    pub name: String,
+    /// `None` means every namespace
+    namespaces: Option<Vec<String>>,
+}
+
+impl Principal {
+    /// Caller with access to everything (auth disabled or a `"*"` key)
+    pub fn unrestricted(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            namespaces: None,
+        }
+    }
+
+    fn from_config(config: &ApiKeyConfig) -> Self {
+        let unrestricted = config.namespaces.iter().any(|namespace| namespace == ALL_NAMESPACES);
+        Self {
+            name: config.name.clone(),
+            namespaces: (!unrestricted).then(|| config.namespaces.clone()),
+        }
+    }
+
+    pub fn allows(&self, namespace: &str) -> bool {
+        self.namespaces
+            .as_ref()
+            .is_none_or(|namespaces| namespaces.iter().any(|allowed| allowed == namespace))
+    }
+
+    /// Reject with 403 unless the caller may use `namespace`
+    pub fn authorize(&self, namespace: &str) -> Result<(), Rejection> {
+        if self.allows(namespace) {
+            Ok(())
+        } else {
+            Err(warp::reject::custom(Forbidden {
+                namespace: namespace.to_string(),
+            }))
+        }
+    }
+
+    /// Reject with 403 unless the key covers every namespace
+    pub fn require_unrestricted(&self) -> Result<(), Rejection> {
+        match self.namespaces {
+            None => Ok(()),
+            Some(_) => Err(warp::reject::custom(Forbidden {
+                namespace: ALL_NAMESPACES.to_string(),
+            })),
+        }
+    }
+
+    /// Namespace for a new task: the requested one if allowed, else the key's first
+    pub fn namespace_for(&self, requested: Option<&str>) -> Result<String, Rejection> {
+        match (requested, &self.namespaces) {
+            (Some(namespace), _) => self.authorize(namespace).map(|_| namespace.to_string()),
+            (None, Some(namespaces)) => Ok(namespaces[0].clone()),
+            (None, None) => Ok(DEFAULT_NAMESPACE.to_string()),
+        }
+    }
+}
+
+/// Configured API keys, looked up by key
+#[derive(Debug, Default)]
+pub struct ApiKeys {
+    keys: HashMap<String, Principal>,
+}
+
+impl ApiKeys {
+    pub fn new(configs: &[ApiKeyConfig]) -> Self {
+        Self {
+            keys: configs
+                .iter()
+                .map(|config| (config.key.clone(), Principal::from_config(config)))
+                .collect(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    /// Identify the caller from the request headers
+    pub fn authenticate(&self, headers: &HeaderMap) -> Result<Principal, Rejection> {
+        if !self.is_enabled() {
+            return Ok(Principal::unrestricted("anonymous"));
+        }
+        presented_key(headers)
+            .and_then(|key| self.keys.get(key))
+            .cloned()
+            .ok_or_else(|| warp::reject::custom(Unauthorized))
+    }
+}
+
+fn presented_key(headers: &HeaderMap) -> Option<&str> {
+    let bearer = headers
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    bearer.or_else(|| headers.get(API_KEY_HEADER).and_then(|value| value.to_str().ok()))
+}
+
+/// Filter extracting the authenticated caller, or rejecting with [`Unauthorized`]
+pub fn authenticate(keys: Arc<ApiKeys>) -> impl Filter<Extract = (Principal,), Error = Rejection> + Clone {
+    warp::header::headers_cloned().and_then(move |headers: HeaderMap| {
+        let result = keys.authenticate(&headers);
+        async move { result }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys() -> ApiKeys {
+        ApiKeys::new(&[
+            ApiKeyConfig {
+                name: "team-a".to_string(),
+                key: "key-a".to_string(),
+                namespaces: vec!["team-a".to_string(), "shared".to_string()],
+            },
+            ApiKeyConfig {
+                name: "admin".to_string(),
+                key: "key-admin".to_string(),
+                namespaces: vec![ALL_NAMESPACES.to_string()],
+            },
+        ])
+    }
+
+    fn headers(name: &str, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            warp::http::header::HeaderName::from_bytes(name.as_bytes()).unwrap(),
+            value.parse().unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_authenticate() {
+        let keys = keys();
+        assert_eq!(keys.authenticate(&headers("authorization", "Bearer key-a")).unwrap().name, "team-a");
+        assert_eq!(keys.authenticate(&headers(API_KEY_HEADER, "key-admin")).unwrap().name, "admin");
+        assert!(keys.authenticate(&headers("authorization", "Bearer wrong")).is_err());
+        assert!(keys.authenticate(&HeaderMap::new()).is_err());
+
+        // Without keys everyone is let in
+        assert!(ApiKeys::default().authenticate(&HeaderMap::new()).unwrap().allows("anything"));
+    }
+
+    #[test]
+    fn test_namespace_scoping() {
+        let keys = keys();
+        let team = keys.authenticate(&headers(API_KEY_HEADER, "key-a")).unwrap();
+        assert!(team.allows("shared"));
+        assert!(!team.allows("team-b"));
+        assert_eq!(team.namespace_for(None).unwrap(), "team-a");
+        assert_eq!(team.namespace_for(Some("shared")).unwrap(), "shared");
+        assert!(team.namespace_for(Some("team-b")).is_err());
+
+        let admin = keys.authenticate(&headers(API_KEY_HEADER, "key-admin")).unwrap();
+        assert!(admin.allows("team-b"));
+        assert_eq!(admin.namespace_for(None).unwrap(), DEFAULT_NAMESPACE);
+    }
+}
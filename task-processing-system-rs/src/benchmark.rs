@@ -0,0 +1,178 @@
+use crate::calculations::Calculator;
+use crate::types::{Operation, SystemError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::hint::black_box;
+use std::path::Path;
+use std::time::Instant;
+
+/// A single calculator benchmark case
+#[derive(Debug, Clone)]
+pub struct BenchCase {
+    pub name: &'static str,
+    pub operation: Operation,
+    pub input: u64,
+}
+
+/// Benchmark cases run by the regression gate
+///
+/// Names match the criterion benchmarks in `benches/task_benchmarks.rs`.
+pub fn default_cases() -> Vec<BenchCase> {
+    vec![
+        BenchCase { name: "factorial_5", operation: Operation::Factorial, input: 5 },
+        BenchCase { name: "factorial_10", operation: Operation::Factorial, input: 10 },
+        BenchCase { name: "factorial_20", operation: Operation::Factorial, input: 20 },
+        BenchCase { name: "fibonacci_10", operation: Operation::Fibonacci, input: 10 },
+        BenchCase { name: "fibonacci_30", operation: Operation::Fibonacci, input: 30 },
+        BenchCase { name: "fibonacci_50", operation: Operation::Fibonacci, input: 50 },
+        BenchCase { name: "prime_check_small", operation: Operation::PrimeCheck, input: 17 },
+        BenchCase { name: "prime_check_medium", operation: Operation::PrimeCheck, input: 982451653 },
+        BenchCase { name: "prime_check_large", operation: Operation::PrimeCheck, input: 9999991 },
+    ]
+}
+
+/// Mean time per calculation (nanoseconds) keyed by benchmark name
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BenchReport {
+    pub results: BTreeMap<String, f64>,
+}
+
+impl BenchReport {
+    /// Load a report previously written with `save`
+    pub fn load(path: &Path) -> Result<Self, SystemError> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Write the report as pretty-printed JSON
+    pub fn save(&self, path: &Path) -> Result<(), SystemError> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// A benchmark that got slower than the allowed threshold
+#[derive(Debug, Clone)]
+pub struct Regression {
+    pub name: String,
+    pub baseline_ns: f64,
+    pub current_ns: f64,
+    pub change_percent: f64,
+}
+
+/// Run every case `iterations` times and record the mean time per call
+pub fn run_benchmarks(cases: &[BenchCase], iterations: u32) -> BenchReport {
+    let iterations = iterations.max(1);
+    let mut report = BenchReport::default();
+    let calculator = Calculator::default();
+
+    for case in cases {
+        // Warm up caches and branch predictors before timing
+        for _ in 0..(iterations / 10).max(1) {
+            let _ = black_box(calculator.calculate(
+                black_box(case.operation.clone()),
+                black_box(case.input),
+            ));
+        }
+
+        let start = Instant::now();
+        for _ in 0..iterations {
+            let _ = black_box(calculator.calculate(
+                black_box(case.operation.clone()),
+                black_box(case.input),
+            ));
+        }
+        let mean_ns = start.elapsed().as_nanos() as f64 / iterations as f64;
+        report.results.insert(case.name.to_string(), mean_ns);
+    }
+
+    report
+}
+
+/// Compare a run against a baseline
+///
+/// Benchmarks missing from either side are ignored.
+pub fn find_regressions(
+    baseline: &BenchReport,
+    current: &BenchReport,
+    threshold_percent: f64,
+) -> Vec<Regression> {
+    let mut regressions = Vec::new();
+
+    for (name, &current_ns) in &current.results {
+        let Some(&baseline_ns) = baseline.results.get(name) else {
+            continue;
+        };
+        if baseline_ns <= 0.0 {
+            continue;
+        }
+
+        let change_percent = (current_ns - baseline_ns) / baseline_ns * 100.0;
+        if change_percent > threshold_percent {
+            regressions.push(Regression {
+                name: name.clone(),
+                baseline_ns,
+                current_ns,
+                change_percent,
+            });
+        }
+    }
+
+    regressions
+}
+
+/// Parse a threshold such as `10%` or `7.5` into a percentage
+pub fn parse_threshold(value: &str) -> Result<f64, String> {
+    let trimmed = value.trim().trim_end_matches('%');
+    let percent: f64 = trimmed
+        .parse()
+        .map_err(|_| format!("invalid threshold '{}', expected a percentage like 10%", value))?;
+
+    if !percent.is_finite() || percent < 0.0 {
+        return Err(format!("threshold must be a non-negative percentage, got '{}'", value));
+    }
+
+    Ok(percent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(entries: &[(&str, f64)]) -> BenchReport {
+        BenchReport {
+            results: entries.iter().map(|(k, v)| (k.to_string(), *v)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_parse_threshold() {
+        assert_eq!(parse_threshold("10%").unwrap(), 10.0);
+        assert_eq!(parse_threshold("7.5").unwrap(), 7.5);
+        assert!(parse_threshold("-5%").is_err());
+        assert!(parse_threshold("fast").is_err());
+    }
+
+    #[test]
+    fn test_find_regressions() {
+        let baseline = report(&[("factorial_5", 100.0), ("fibonacci_10", 100.0)]);
+        let current = report(&[
+            ("factorial_5", 109.0),
+            ("fibonacci_10", 125.0),
+            ("prime_check_small", 500.0),
+        ]);
+
+        let regressions = find_regressions(&baseline, &current, 10.0);
+        assert_eq!(regressions.len(), 1);
+        assert_eq!(regressions[0].name, "fibonacci_10");
+        assert!((regressions[0].change_percent - 25.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_run_benchmarks_covers_cases() {
+        let cases = default_cases();
+        let report = run_benchmarks(&cases[..2], 10);
+        assert_eq!(report.results.len(), 2);
+        assert!(report.results.contains_key("factorial_5"));
+    }
+}
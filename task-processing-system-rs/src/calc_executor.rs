@@ -0,0 +1,149 @@
+//! Where calculations run
+//!
+//! A processing thread is a tokio task, so a long calculation (a large
+//! `prime_check`, a big-precision factorial) run on it holds a runtime thread
+//! that HTTP handlers also need. [`CalculationPool`] moves the calculation off
+//! the runtime as chosen by `calculation_executor`:
+//!
+//! - `blocking` (default): on tokio's blocking thread pool (`spawn_blocking`)
+//! - `dedicated`: on the worker's own OS threads, `threads_per_worker` of them
+//! - `inline`: directly on the runtime thread, as before
+//!
+//! A panicking calculation panics the processing thread awaiting it, whichever
+//! strategy ran it.
+
+use crate::types::CalculationExecutor;
+use parking_lot::Mutex;
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use tokio::sync::oneshot;
+use tracing::debug;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Runs a worker's calculations with the configured strategy
+pub struct CalculationPool {
+    strategy: CalculationExecutor,
+    /// Jobs for the dedicated threads, which exit once the pool is dropped
+    jobs: Option<Mutex<mpsc::Sender<Job>>>,
+}
+
+impl CalculationPool {
+    /// A pool running calculations as `strategy` says; `dedicated` starts
+    /// `threads` OS threads named after `worker_id`
+    pub fn new(strategy: CalculationExecutor, worker_id: usize, threads: usize) -> Self {
+        let jobs = (strategy == CalculationExecutor::Dedicated).then(|| {
+            let (sender, receiver) = mpsc::channel::<Job>();
+            let receiver = Arc::new(Mutex::new(receiver));
+            for thread_id in 0..threads.max(1) {
+                let receiver = Arc::clone(&receiver);
+                thread::Builder::new()
+                    .name(format!("calc-{}-{}", worker_id, thread_id))
+                    .spawn(move || loop {
+                        // The lock is released before running the job
+                        let job = receiver.lock().recv();
+                        match job {
+                            Ok(job) => job(),
+                            Err(_) => break,
+                        }
+                    })
+                    .expect("failed to spawn calculation thread");
+            }
+            debug!("Worker {} calculates on {} dedicated threads", worker_id, threads.max(1));
+            Mutex::new(sender)
+        });
+        Self { strategy, jobs }
+    }
+
+    pub fn strategy(&self) -> CalculationExecutor {
+        self.strategy
+    }
+
+    /// Run `job` and wait for its result without holding a runtime thread
+    /// (unless the strategy is `inline`)
+    pub async fn run<T, F>(&self, job: F) -> T
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        match (self.strategy, &self.jobs) {
+            (CalculationExecutor::Dedicated, Some(jobs)) => {
+                let (sender, receiver) = oneshot::channel::<Result<T, Box<dyn Any + Send>>>();
+                let job: Job = Box::new(move || {
+                    let _ = sender.send(panic::catch_unwind(AssertUnwindSafe(job)));
+                });
+                jobs.lock().send(job).expect("calculation threads exited");
+                match receiver.await.expect("calculation thread dropped its job") {
+                    Ok(result) => result,
+                    Err(payload) => panic::resume_unwind(payload),
+                }
+            }
+            (CalculationExecutor::Inline, _) => job(),
+            _ => match tokio::task::spawn_blocking(job).await {
+                Ok(result) => result,
+                Err(e) => panic::resume_unwind(e.into_panic()),
+            },
+        }
+    }
+}
+
+impl Default for CalculationPool {
+    fn default() -> Self {
+        Self::new(CalculationExecutor::default(), 0, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn thread_name() -> String {
+        thread::current().name().unwrap_or_default().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_strategies_run_where_configured() {
+        let dedicated = CalculationPool::new(CalculationExecutor::Dedicated, 3, 2);
+        assert!(dedicated.run(thread_name).await.starts_with("calc-3-"));
+
+        let runtime_thread = thread::current().id();
+        let inline = CalculationPool::new(CalculationExecutor::Inline, 0, 2);
+        assert_eq!(inline.run(move || thread::current().id()).await, runtime_thread);
+
+        let blocking = CalculationPool::new(CalculationExecutor::Blocking, 0, 2);
+        assert_ne!(blocking.run(move || thread::current().id()).await, runtime_thread);
+        assert_eq!(blocking.run(|| 6 * 7).await, 42);
+    }
+
+    #[tokio::test]
+    async fn test_runtime_stays_responsive() {
+        // One runtime thread: an inline calculation would hold it throughout
+        let pool = Arc::new(CalculationPool::new(CalculationExecutor::Dedicated, 0, 1));
+        let busy = tokio::spawn({
+            let pool = Arc::clone(&pool);
+            async move { pool.run(|| thread::sleep(Duration::from_millis(300))).await }
+        });
+        let started = Instant::now();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(started.elapsed() < Duration::from_millis(200));
+        busy.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_panic_reaches_caller() {
+        for strategy in [CalculationExecutor::Blocking, CalculationExecutor::Dedicated] {
+            let pool = Arc::new(CalculationPool::new(strategy, 0, 1));
+            let task = tokio::spawn({
+                let pool = Arc::clone(&pool);
+                async move { pool.run(|| panic!("calculation failed")).await }
+            });
+            assert!(task.await.unwrap_err().is_panic());
+            // The pool keeps working after a panic
+            assert_eq!(pool.run(|| 1).await, 1);
+        }
+    }
+}
@@ -1,71 +1,276 @@
 #![allow(warnings)]
-use crate::types::{Operation, TaskError};
+use crate::math_utils::{checked_pow_mod, checked_product, checked_sum, isqrt, mul_mod};
+use crate::types::{
+    FibonacciAlgorithm, Operation, OperationLimitsConfig, OrchestratorConfig, PrimeCheckAlgorithm, PrimeGenerationConfig, TaskError,
+};
+use num_bigint::BigUint;
+use parking_lot::Mutex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
 use tracing::debug;
 
+/// Loop iterations between cancellation checks in long-running calculations
+const CANCEL_CHECK_INTERVAL: u64 = 4096;
+
+/// Largest big-precision inputs whose results fit in `max_digits` digits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BigPrecisionLimits {
+    pub max_digits: usize,
+    pub factorial: u64,
+    pub fibonacci: u64,
+}
+
+impl BigPrecisionLimits {
+    pub fn new(max_digits: usize) -> Self {
+        let fits = |log10: f64| log10.floor() as usize + 1 <= max_digits;
+
+        // log10(n!) = log10(2) + ... + log10(n)
+        let (mut factorial, mut log10) = (1u64, 0.0f64);
+        while fits(log10 + ((factorial + 1) as f64).log10()) {
+            factorial += 1;
+            log10 += (factorial as f64).log10();
+        }
+
+        // F(n) is the integer nearest phi^n / sqrt(5)
+        let phi = (1.0 + 5f64.sqrt()) / 2.0;
+        let mut fibonacci = 1u64;
+        while fits((fibonacci + 1) as f64 * phi.log10() - 5f64.sqrt().log10()) {
+            fibonacci += 1;
+        }
+
+        Self {
+            max_digits,
+            factorial,
+            fibonacci,
+        }
+    }
+
+    /// Largest input of `operation`, `0` for operations without a big-precision mode
+    pub fn max_input(&self, operation: &Operation) -> u64 {
+        match operation {
+            Operation::Factorial => self.factorial,
+            Operation::Fibonacci => self.fibonacci,
+            _ => 0,
+        }
+    }
+}
+
 /// Mathematical calculations module
 /// 
-/// This module provides implementations for the three supported operations:
+/// This module provides implementations for the supported operations:
 /// - Factorial: calculates n!
 /// - Fibonacci: calculates the nth Fibonacci number
 /// - Prime check: determines if a number is prime
-pub struct Calculator;
+/// - Generate prime: returns a random prime of the requested bit length
+/// - GCD / LCM: of `input` and `input2` (see [`Calculator::calculate_pair`])
+///
+/// Settings (default algorithms, input limits, prime generation, big-precision limits)
+/// belong to the instance: an orchestrator builds one from its config and
+/// shares it with its workers, so orchestrators in one process don't affect
+/// each other. Calculations that need no settings are associated functions.
+#[derive(Debug)]
+pub struct Calculator {
+    /// Fibonacci algorithm used when a task doesn't choose one
+    fibonacci_algorithm: FibonacciAlgorithm,
+    /// Primality test used when a task doesn't choose one
+    prime_check_algorithm: PrimeCheckAlgorithm,
+    /// Largest inputs of factorial, fibonacci and prime_check
+    operation_limits: OperationLimitsConfig,
+    prime_generation: PrimeGenerationConfig,
+    big_precision: BigPrecisionLimits,
+    /// Entropy source of `generate_prime` when a fixed seed is configured
+    seeded_rng: Mutex<Option<StdRng>>,
+}
+
+impl Default for Calculator {
+    fn default() -> Self {
+        Self::new(&OrchestratorConfig::default())
+    }
+}
 
 impl Calculator {
+    /// Calculator with the algorithms, input limits, prime generation and big-precision settings of `config`
+    pub fn new(config: &OrchestratorConfig) -> Self {
+        Self {
+            fibonacci_algorithm: config.fibonacci_algorithm,
+            prime_check_algorithm: config.prime_check_algorithm,
+            operation_limits: config.operation_limits,
+            prime_generation: config.prime_generation.clone(),
+            big_precision: BigPrecisionLimits::new(config.big_precision.max_digits),
+            seeded_rng: Mutex::new(config.prime_generation.seed.map(StdRng::seed_from_u64)),
+        }
+    }
+
+    /// Fibonacci algorithm of tasks that don't choose one
+    pub fn fibonacci_algorithm(&self) -> FibonacciAlgorithm {
+        self.fibonacci_algorithm
+    }
+
+    /// Primality test of tasks that don't choose one
+    pub fn prime_check_algorithm(&self) -> PrimeCheckAlgorithm {
+        self.prime_check_algorithm
+    }
+
+    pub fn prime_generation(&self) -> &PrimeGenerationConfig {
+        &self.prime_generation
+    }
+
+    /// Largest inputs of `precision: "big"` tasks
+    pub fn big_precision_limits(&self) -> BigPrecisionLimits {
+        self.big_precision
+    }
+
+    /// Largest inputs factorial, fibonacci and prime_check accept
+    pub fn operation_limits(&self) -> OperationLimitsConfig {
+        self.operation_limits
+    }
+
     /// Perform calculation based on operation type
-    pub fn calculate(operation: Operation, input: u64) -> Result<String, TaskError> {
+    pub fn calculate(&self, operation: Operation, input: u64) -> Result<String, TaskError> {
+        self.calculate_cancellable(operation, input, &CancellationToken::new())
+    }
+
+    /// Perform calculation, giving up with [`TaskError::Cancelled`] once `cancel` is cancelled
+    ///
+    /// Prime generation and trial-division prime checks poll `cancel` as they
+    /// loop; Miller-Rabin prime checks, factorial and fibonacci are bounded and
+    /// always finish.
+    pub fn calculate_cancellable(&self, operation: Operation, input: u64, cancel: &CancellationToken) -> Result<String, TaskError> {
         debug!("Calculating {} for input {}", operation, input);
         
         let result = match operation {
-            Operation::Factorial => Self::factorial(input)?,
-            Operation::Fibonacci => Self::fibonacci(input)?,
-            Operation::PrimeCheck => Self::prime_check(input)?,
+            Operation::Factorial => self.factorial(input)?,
+            Operation::Fibonacci => self.fibonacci_with(self.fibonacci_algorithm, input)?,
+            Operation::PrimeCheck => self.prime_check_with(self.prime_check_algorithm, input, cancel)?,
+            Operation::GeneratePrime => self.generate_prime(input, cancel)?,
+            Operation::Gcd | Operation::Lcm => {
+                return Err(TaskError::CalculationError {
+                    message: format!("Operation {} needs a second input", operation),
+                })
+            }
         };
         
         debug!("Calculation result: {}", result);
         Ok(result)
     }
 
-    /// Calculate factorial of n
-    /// 
-    /// Constraints: n <= 20 (to prevent overflow)
-    /// Returns: n! as a string
-    fn factorial(n: u64) -> Result<String, TaskError> {
-        if n > 20 {
+    /// Perform a two-input calculation (`gcd` or `lcm`) of `a` and `b`
+    pub fn calculate_pair(operation: Operation, a: u64, b: u64) -> Result<String, TaskError> {
+        debug!("Calculating {} for inputs {} and {}", operation, a, b);
+        match operation {
+            Operation::Gcd => Ok(Self::gcd(a, b).to_string()),
+            Operation::Lcm => Ok(Self::lcm(a, b).to_string()),
+            _ => Err(TaskError::CalculationError {
+                message: format!("Operation {} takes a single input", operation),
+            }),
+        }
+    }
+
+    /// Factorial or fibonacci with arbitrary precision (`precision: "big"`)
+    ///
+    /// Inputs are limited so results have at most the configured
+    /// `big_precision.max_digits` digits. Fibonacci always uses fast doubling.
+    pub fn calculate_big(&self, operation: Operation, input: u64, cancel: &CancellationToken) -> Result<String, TaskError> {
+        let limits = self.big_precision;
+        if !operation.supports_big_precision() {
             return Err(TaskError::CalculationError {
-                message: format!("Factorial input {} too large, maximum is 20", n),
+                message: format!("Operation {} has no big precision mode", operation),
             });
         }
+        if input > limits.max_input(&operation) {
+            return Err(TaskError::CalculationError {
+                message: format!(
+                    "{} input {} too large, the result would exceed {} digits",
+                    operation, input, limits.max_digits
+                ),
+            });
+        }
+        debug!("Calculating {} for input {} with big precision", operation, input);
+
+        let result = match operation {
+            Operation::Factorial => Self::big_factorial(input, cancel)?,
+            _ => Self::big_fibonacci(input),
+        };
+        Ok(result.to_string())
+    }
+
+    fn big_factorial(n: u64, cancel: &CancellationToken) -> Result<BigUint, TaskError> {
+        let mut result = BigUint::from(1u32);
+        for i in 2..=n {
+            if i % CANCEL_CHECK_INTERVAL == 0 && cancel.is_cancelled() {
+                return Err(TaskError::Cancelled);
+            }
+            result *= i;
+        }
+        Ok(result)
+    }
+
+    /// Fast doubling, as in [`fibonacci_fast_doubling`](Self::fibonacci_fast_doubling)
+    fn big_fibonacci(n: u64) -> BigUint {
+        let (mut a, mut b) = (BigUint::from(0u32), BigUint::from(1u32));
+        for bit in (0..64 - n.leading_zeros()).rev() {
+            let c = &a * (&b * 2u32 - &a);
+            let d = &a * &a + &b * &b;
+            if (n >> bit) & 1 == 0 {
+                a = c;
+                b = d;
+            } else {
+                b = &c + &d;
+                a = d;
+            }
+        }
+        a
+    }
+
+    /// Calculate the nth Fibonacci number with a specific algorithm
+    pub fn fibonacci_with(&self, algorithm: FibonacciAlgorithm, n: u64) -> Result<String, TaskError> {
+        match algorithm {
+            FibonacciAlgorithm::Iterative => self.fibonacci(n),
+            FibonacciAlgorithm::FastDoubling => self.fibonacci_fast_doubling(n),
+            FibonacciAlgorithm::Matrix => self.fibonacci_matrix(n),
+        }
+    }
+
+    /// Fail inputs above the configured `operation_limits` of `operation`
+    fn check_limit(&self, operation: Operation, n: u64) -> Result<(), TaskError> {
+        match self.operation_limits.max_input(&operation) {
+            Some(max) if n > max => Err(TaskError::CalculationError {
+                message: format!("{} input {} too large, maximum is {}", operation, n, max),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Calculate factorial of n
+    /// 
+    /// Constraints: n <= `operation_limits.max_factorial_input` (at most 34, to prevent overflow)
+    /// Returns: n! as a string
+    fn factorial(&self, n: u64) -> Result<String, TaskError> {
+        self.check_limit(Operation::Factorial, n)?;
 
         if n == 0 || n == 1 {
             return Ok("1".to_string());
         }
 
         // Use u128 to handle larger factorials safely
-        let mut result: u128 = 1;
-        for i in 2..=n {
-            result = match result.checked_mul(i as u128) {
-                Some(val) => val,
-                None => {
-                    return Err(TaskError::CalculationError {
-                        message: format!("Factorial overflow for input {}", n),
-                    });
-                }
-            };
-        }
+        let result = checked_product((2..=n).map(|i| i as u128)).ok_or_else(|| {
+            TaskError::CalculationError {
+                message: format!("Factorial overflow for input {}", n),
+            }
+        })?;
 
         Ok(result.to_string())
     }
 
     /// Calculate nth Fibonacci number
     /// 
-    /// Constraints: n <= 93 (largest Fibonacci number that fits in u64)
+    /// Constraints: n <= `operation_limits.max_fibonacci_input` (at most 93,
+    /// the largest Fibonacci number that fits in u64)
     /// Returns: F(n) as a string
-    fn fibonacci(n: u64) -> Result<String, TaskError> {
-        if n > 93 {
-            return Err(TaskError::CalculationError {
-                message: format!("Fibonacci input {} too large, maximum is 93", n),
-            });
-        }
+    fn fibonacci(&self, n: u64) -> Result<String, TaskError> {
+        self.check_limit(Operation::Fibonacci, n)?;
 
         match n {
             0 => Ok("0".to_string()),
@@ -75,7 +280,7 @@ impl Calculator {
                 let mut b: u64 = 1;
                 
                 for _ in 2..=n {
-                    let next = match a.checked_add(b) {
+                    let next = match checked_sum(a, b) {
                         Some(val) => val,
                         None => {
                             return Err(TaskError::CalculationError {
@@ -92,11 +297,84 @@ impl Calculator {
         }
     }
 
-    /// Check if a number is prime
+    /// Calculate nth Fibonacci number using fast doubling
+    ///
+    /// F(2k) = F(k) * (2F(k+1) - F(k)), F(2k+1) = F(k)^2 + F(k+1)^2
+    /// Intermediates use u128 since F(n+1) is computed alongside F(n).
+    fn fibonacci_fast_doubling(&self, n: u64) -> Result<String, TaskError> {
+        self.check_limit(Operation::Fibonacci, n)?;
+
+        // Walk the bits of n from the most significant down
+        let (mut a, mut b): (u128, u128) = (0, 1);
+        for bit in (0..64 - n.leading_zeros()).rev() {
+            let c = a * (2 * b - a);
+            let d = a * a + b * b;
+            if (n >> bit) & 1 == 0 {
+                a = c;
+                b = d;
+            } else {
+                a = d;
+                b = c + d;
+            }
+        }
+
+        Ok(a.to_string())
+    }
+
+    /// Calculate nth Fibonacci number using 2x2 matrix exponentiation
+    ///
+    /// [[1, 1], [1, 0]]^n = [[F(n+1), F(n)], [F(n), F(n-1)]]
+    fn fibonacci_matrix(&self, n: u64) -> Result<String, TaskError> {
+        self.check_limit(Operation::Fibonacci, n)?;
+
+        type Matrix = [[u128; 2]; 2];
+
+        fn multiply(x: &Matrix, y: &Matrix) -> Matrix {
+            [
+                [
+                    x[0][0] * y[0][0] + x[0][1] * y[1][0],
+                    x[0][0] * y[0][1] + x[0][1] * y[1][1],
+                ],
+                [
+                    x[1][0] * y[0][0] + x[1][1] * y[1][0],
+                    x[1][0] * y[0][1] + x[1][1] * y[1][1],
+                ],
+            ]
+        }
+
+        let mut result: Matrix = [[1, 0], [0, 1]];
+        let mut base: Matrix = [[1, 1], [1, 0]];
+        let mut exp = n;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = multiply(&result, &base);
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = multiply(&base, &base);
+            }
+        }
+
+        Ok(result[0][1].to_string())
+    }
+
+    /// Check if a number is prime with the given algorithm
+    ///
+    /// Constraints: n <= `operation_limits.max_prime_check_input`
+    /// Returns: "true" if prime, "false" if not prime
+    pub fn prime_check_with(&self, algorithm: PrimeCheckAlgorithm, n: u64, cancel: &CancellationToken) -> Result<String, TaskError> {
+        self.check_limit(Operation::PrimeCheck, n)?;
+        match algorithm {
+            PrimeCheckAlgorithm::MillerRabin => Ok(Self::is_prime_miller_rabin(n).to_string()),
+            PrimeCheckAlgorithm::TrialDivision => Self::prime_check(n, cancel),
+        }
+    }
+
+    /// Check if a number is prime by trial division
     /// 
     /// Uses optimized trial division with early termination
     /// Returns: "true" if prime, "false" if not prime
-    fn prime_check(n: u64) -> Result<String, TaskError> {
+    fn prime_check(n: u64, cancel: &CancellationToken) -> Result<String, TaskError> {
         if n < 2 {
             return Ok("false".to_string());
         }
@@ -110,8 +388,11 @@ impl Calculator {
         }
 
         // Check odd divisors up to sqrt(n)
-        let limit = ((n as f64).sqrt() as u64) + 1;
-        for i in (3..=limit).step_by(2) {
+        let limit = isqrt(n);
+        for (step, i) in (3..=limit).step_by(2).enumerate() {
+            if step as u64 % CANCEL_CHECK_INTERVAL == 0 && cancel.is_cancelled() {
+                return Err(TaskError::Cancelled);
+            }
             if n % i == 0 {
                 return Ok("false".to_string());
             }
@@ -120,11 +401,119 @@ impl Calculator {
         Ok("true".to_string())
     }
 
+    /// Greatest common divisor (Euclid); `gcd(0, 0)` is 0
+    fn gcd(mut a: u64, mut b: u64) -> u64 {
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        a
+    }
+
+    /// Least common multiple, 0 if either input is 0
+    ///
+    /// Computed in u128, where the product of two u64 values always fits.
+    fn lcm(a: u64, b: u64) -> u128 {
+        if a == 0 || b == 0 {
+            return 0;
+        }
+        a as u128 / Self::gcd(a, b) as u128 * b as u128
+    }
+
+    /// Generate a random prime with exactly `bits` bits
+    ///
+    /// Constraints: 2 <= bits <= configured max_bits (at most 64)
+    /// Returns: the prime as a string
+    fn generate_prime(&self, bits: u64, cancel: &CancellationToken) -> Result<String, TaskError> {
+        let config = &self.prime_generation;
+        if bits < 2 || bits > config.max_bits as u64 {
+            return Err(TaskError::CalculationError {
+                message: format!(
+                    "Prime bit length {} out of range, must be between 2 and {}",
+                    bits, config.max_bits
+                ),
+            });
+        }
+
+        let deadline = Instant::now() + Duration::from_millis(config.time_limit_ms);
+        let low: u64 = 1 << (bits - 1);
+        let high: u64 = if bits == 64 { u64::MAX } else { (1 << bits) - 1 };
+
+        loop {
+            if cancel.is_cancelled() {
+                return Err(TaskError::Cancelled);
+            }
+            if Instant::now() > deadline {
+                return Err(TaskError::CalculationError {
+                    message: format!(
+                        "Prime generation for {} bits exceeded time limit of {}ms",
+                        bits, config.time_limit_ms
+                    ),
+                });
+            }
+
+            // Force the top bit so the result has exactly `bits` bits, and make it odd
+            let candidate = self.random_in_range(low, high) | low;
+            let candidate = if bits > 2 { candidate | 1 } else { candidate };
+            if Self::is_prime_miller_rabin(candidate) {
+                return Ok(candidate.to_string());
+            }
+        }
+    }
+
+    /// Draw a random number from the configured entropy source
+    fn random_in_range(&self, low: u64, high: u64) -> u64 {
+        match self.seeded_rng.lock().as_mut() {
+            Some(rng) => rng.gen_range(low..=high),
+            None => rand::thread_rng().gen_range(low..=high),
+        }
+    }
+
+    /// Deterministic Miller-Rabin primality test for all u64 values
+    fn is_prime_miller_rabin(n: u64) -> bool {
+        const WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+        if n < 2 {
+            return false;
+        }
+        for &p in &WITNESSES {
+            if n % p == 0 {
+                return n == p;
+            }
+        }
+
+        // Write n - 1 as d * 2^r with d odd
+        let mut d = n - 1;
+        let mut r = 0;
+        while d % 2 == 0 {
+            d /= 2;
+            r += 1;
+        }
+
+        'witness: for &a in &WITNESSES {
+            let mut x = match checked_pow_mod(a, d, n) {
+                Some(x) => x,
+                None => return false,
+            };
+            if x == 1 || x == n - 1 {
+                continue;
+            }
+            for _ in 1..r {
+                x = mul_mod(x, x, n);
+                if x == n - 1 {
+                    continue 'witness;
+                }
+            }
+            return false;
+        }
+
+        true
+    }
+
     /// Benchmark a calculation (for performance testing)
     #[cfg(test)]
-    pub fn benchmark_calculation(operation: Operation, input: u64) -> Result<(String, std::time::Duration), TaskError> {
+    pub fn benchmark_calculation(&self, operation: Operation, input: u64) -> Result<(String, std::time::Duration), TaskError> {
         let start = std::time::Instant::now();
-        let result = Self::calculate(operation, input)?;
+        let result = self.calculate(operation, input)?;
         let duration = start.elapsed();
         Ok((result, duration))
     }
@@ -134,83 +523,239 @@ impl Calculator {
 mod tests {
     use super::*;
 
+    fn uncancelled() -> CancellationToken {
+        CancellationToken::new()
+    }
+
     #[test]
     fn test_factorial_basic() {
-        assert_eq!(Calculator::factorial(0).unwrap(), "1");
-        assert_eq!(Calculator::factorial(1).unwrap(), "1");
-        assert_eq!(Calculator::factorial(5).unwrap(), "120");
-        assert_eq!(Calculator::factorial(10).unwrap(), "3628800");
+        assert_eq!(Calculator::default().factorial(0).unwrap(), "1");
+        assert_eq!(Calculator::default().factorial(1).unwrap(), "1");
+        assert_eq!(Calculator::default().factorial(5).unwrap(), "120");
+        assert_eq!(Calculator::default().factorial(10).unwrap(), "3628800");
     }
 
     #[test]
     fn test_factorial_large() {
         // 20! = 2432902008176640000
-        assert_eq!(Calculator::factorial(20).unwrap(), "2432902008176640000");
+        assert_eq!(Calculator::default().factorial(20).unwrap(), "2432902008176640000");
     }
 
     #[test]
     fn test_factorial_overflow() {
-        let result = Calculator::factorial(21);
+        let result = Calculator::default().factorial(21);
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("too large"));
     }
 
     #[test]
     fn test_fibonacci_basic() {
-        assert_eq!(Calculator::fibonacci(0).unwrap(), "0");
-        assert_eq!(Calculator::fibonacci(1).unwrap(), "1");
-        assert_eq!(Calculator::fibonacci(2).unwrap(), "1");
-        assert_eq!(Calculator::fibonacci(10).unwrap(), "55");
-        assert_eq!(Calculator::fibonacci(20).unwrap(), "6765");
+        assert_eq!(Calculator::default().fibonacci(0).unwrap(), "0");
+        assert_eq!(Calculator::default().fibonacci(1).unwrap(), "1");
+        assert_eq!(Calculator::default().fibonacci(2).unwrap(), "1");
+        assert_eq!(Calculator::default().fibonacci(10).unwrap(), "55");
+        assert_eq!(Calculator::default().fibonacci(20).unwrap(), "6765");
     }
 
     #[test]
     fn test_fibonacci_large() {
         // F(50) = 12586269025
-        assert_eq!(Calculator::fibonacci(50).unwrap(), "12586269025");
+        assert_eq!(Calculator::default().fibonacci(50).unwrap(), "12586269025");
         // F(93) = 12200160415121876738 (largest that fits in u64)
-        assert_eq!(Calculator::fibonacci(93).unwrap(), "12200160415121876738");
+        assert_eq!(Calculator::default().fibonacci(93).unwrap(), "12200160415121876738");
+    }
+
+    #[test]
+    fn test_fibonacci_algorithms_agree() {
+        let calculator = Calculator::default();
+        for n in 0..=93 {
+            let expected = calculator.fibonacci(n).unwrap();
+            assert_eq!(calculator.fibonacci_fast_doubling(n).unwrap(), expected, "fast doubling n={}", n);
+            assert_eq!(calculator.fibonacci_matrix(n).unwrap(), expected, "matrix n={}", n);
+        }
+        assert!(calculator.fibonacci_with(FibonacciAlgorithm::Matrix, 94).is_err());
+        assert!(calculator.fibonacci_with(FibonacciAlgorithm::FastDoubling, 94).is_err());
     }
 
     #[test]
     fn test_fibonacci_overflow() {
-        let result = Calculator::fibonacci(94);
+        let result = Calculator::default().fibonacci(94);
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("too large"));
     }
 
     #[test]
     fn test_prime_check_basic() {
-        assert_eq!(Calculator::prime_check(0).unwrap(), "false");
-        assert_eq!(Calculator::prime_check(1).unwrap(), "false");
-        assert_eq!(Calculator::prime_check(2).unwrap(), "true");
-        assert_eq!(Calculator::prime_check(3).unwrap(), "true");
-        assert_eq!(Calculator::prime_check(4).unwrap(), "false");
-        assert_eq!(Calculator::prime_check(17).unwrap(), "true");
-        assert_eq!(Calculator::prime_check(25).unwrap(), "false");
+        assert_eq!(Calculator::prime_check(0, &uncancelled()).unwrap(), "false");
+        assert_eq!(Calculator::prime_check(1, &uncancelled()).unwrap(), "false");
+        assert_eq!(Calculator::prime_check(2, &uncancelled()).unwrap(), "true");
+        assert_eq!(Calculator::prime_check(3, &uncancelled()).unwrap(), "true");
+        assert_eq!(Calculator::prime_check(4, &uncancelled()).unwrap(), "false");
+        assert_eq!(Calculator::prime_check(17, &uncancelled()).unwrap(), "true");
+        assert_eq!(Calculator::prime_check(25, &uncancelled()).unwrap(), "false");
     }
 
     #[test]
     fn test_prime_check_large() {
         // Large prime: 982451653
-        assert_eq!(Calculator::prime_check(982451653).unwrap(), "true");
+        assert_eq!(Calculator::prime_check(982451653, &uncancelled()).unwrap(), "true");
         // Large composite: 982451654 = 2 × 491225827
-        assert_eq!(Calculator::prime_check(982451654).unwrap(), "false");
+        assert_eq!(Calculator::prime_check(982451654, &uncancelled()).unwrap(), "false");
+    }
+
+    #[test]
+    fn test_miller_rabin() {
+        for n in [2u64, 3, 5, 17, 982451653, 18446744073709551557] {
+            assert!(Calculator::is_prime_miller_rabin(n), "{} should be prime", n);
+        }
+        for n in [0u64, 1, 4, 25, 561, 982451654, 18446744073709551615] {
+            assert!(!Calculator::is_prime_miller_rabin(n), "{} should not be prime", n);
+        }
+    }
+
+    #[test]
+    fn test_generate_prime_bit_length() {
+        for bits in [2u64, 8, 32, 64] {
+            let prime: u64 = Calculator::default().generate_prime(bits, &uncancelled()).unwrap().parse().unwrap();
+            assert_eq!(64 - prime.leading_zeros() as u64, bits);
+            assert!(Calculator::is_prime_miller_rabin(prime));
+        }
+    }
+
+    #[test]
+    fn test_generate_prime_out_of_range() {
+        assert!(Calculator::default().generate_prime(1, &uncancelled()).is_err());
+        assert!(Calculator::default().generate_prime(65, &uncancelled()).is_err());
+    }
+
+    #[test]
+    fn test_big_precision() {
+        let (calculator, cancel) = (Calculator::default(), uncancelled());
+        assert_eq!(calculator.calculate_big(Operation::Factorial, 20, &cancel).unwrap(), "2432902008176640000");
+        assert_eq!(
+            calculator.calculate_big(Operation::Factorial, 25, &cancel).unwrap(),
+            "15511210043330985984000000"
+        );
+        assert_eq!(calculator.calculate_big(Operation::Fibonacci, 0, &cancel).unwrap(), "0");
+        assert_eq!(calculator.calculate_big(Operation::Fibonacci, 93, &cancel).unwrap(), "12200160415121876738");
+        assert_eq!(
+            calculator.calculate_big(Operation::Fibonacci, 100, &cancel).unwrap(),
+            "354224848179261915075"
+        );
+        assert!(calculator.calculate_big(Operation::PrimeCheck, 7, &cancel).is_err());
+    }
+
+    #[test]
+    fn test_settings_are_per_instance() {
+        let config = |seed: Option<u64>, max_bits: u32, max_digits: usize| {
+            let mut config = OrchestratorConfig::default();
+            config.prime_generation.seed = seed;
+            config.prime_generation.max_bits = max_bits;
+            config.big_precision.max_digits = max_digits;
+            config.fibonacci_algorithm = FibonacciAlgorithm::Matrix;
+            config.operation_limits.max_factorial_input = 10;
+            config
+        };
+        let narrow = Calculator::new(&config(Some(7), 16, 10));
+        let default = Calculator::default();
+        assert!(narrow.generate_prime(32, &uncancelled()).is_err());
+        assert!(default.generate_prime(32, &uncancelled()).is_ok());
+        assert!(narrow.calculate_big(Operation::Factorial, 20, &uncancelled()).is_err());
+        assert!(default.calculate_big(Operation::Factorial, 20, &uncancelled()).is_ok());
+        assert_eq!(narrow.fibonacci_algorithm(), FibonacciAlgorithm::Matrix);
+        assert_eq!(default.fibonacci_algorithm(), FibonacciAlgorithm::default());
+        assert!(narrow.calculate(Operation::Factorial, 12).is_err());
+        assert_eq!(default.calculate(Operation::Factorial, 12).unwrap(), "479001600");
+        assert_eq!(narrow.operation_limits().max_factorial_input, 10);
+        assert_eq!(default.operation_limits(), OperationLimitsConfig::default());
+
+        // Equally seeded calculators draw the same primes, unaffected by each other
+        let again = Calculator::new(&config(Some(7), 16, 10));
+        let draw = |calculator: &Calculator| calculator.generate_prime(16, &uncancelled()).unwrap();
+        assert_eq!(draw(&narrow), draw(&again));
+        Calculator::new(&config(Some(8), 16, 10)).generate_prime(16, &uncancelled()).unwrap();
+        assert_eq!(draw(&narrow), draw(&again));
+    }
+
+    #[test]
+    fn test_big_precision_limits() {
+        // 3! = 6, 4! = 24; F(6) = 8, F(7) = 13
+        let limits = BigPrecisionLimits::new(1);
+        assert_eq!((limits.factorial, limits.fibonacci), (3, 6));
+        // Largest inputs fit exactly, the next ones don't
+        let limits = BigPrecisionLimits::new(1000);
+        let digits = |operation: Operation, input: u64| match operation {
+            Operation::Factorial => Calculator::big_factorial(input, &uncancelled()).unwrap().to_string().len(),
+            _ => Calculator::big_fibonacci(input).to_string().len(),
+        };
+        for operation in [Operation::Factorial, Operation::Fibonacci] {
+            let max = limits.max_input(&operation);
+            assert!(digits(operation.clone(), max) <= 1000);
+            assert!(digits(operation.clone(), max + 1) > 1000);
+        }
+    }
+
+    #[test]
+    fn test_cancelled_calculations() {
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        // Largest u64 prime: trial division would run for a long time
+        let result = Calculator::default().prime_check_with(PrimeCheckAlgorithm::TrialDivision, 18446744073709551557, &cancel);
+        assert!(matches!(result, Err(TaskError::Cancelled)));
+        assert!(matches!(Calculator::default().generate_prime(32, &cancel), Err(TaskError::Cancelled)));
+        // Bounded calculations finish regardless
+        assert_eq!(Calculator::default().calculate_cancellable(Operation::Factorial, 5, &cancel).unwrap(), "120");
+    }
+
+    #[test]
+    fn test_prime_check_algorithms_agree() {
+        let (miller_rabin, trial_division) = (PrimeCheckAlgorithm::MillerRabin, PrimeCheckAlgorithm::TrialDivision);
+        let calculator = Calculator::default();
+        for n in (0..2000).chain([561, 1105, 3215031751, 982451653, 982451654]) {
+            assert_eq!(
+                calculator.prime_check_with(miller_rabin, n, &uncancelled()).unwrap(),
+                calculator.prime_check_with(trial_division, n, &uncancelled()).unwrap(),
+                "algorithms disagree on {}",
+                n
+            );
+        }
+
+        // Near the input limit, where trial division takes seconds
+        let start = std::time::Instant::now();
+        assert_eq!(calculator.prime_check_with(miller_rabin, 9223372036854775783, &uncancelled()).unwrap(), "true");
+        assert_eq!(calculator.prime_check_with(miller_rabin, u64::MAX / 2, &uncancelled()).unwrap(), "false");
+        assert!(start.elapsed() < std::time::Duration::from_millis(10));
+    }
+
+    #[test]
+    fn test_gcd_and_lcm() {
+        assert_eq!(Calculator::calculate_pair(Operation::Gcd, 48, 18).unwrap(), "6");
+        assert_eq!(Calculator::calculate_pair(Operation::Gcd, 0, 7).unwrap(), "7");
+        assert_eq!(Calculator::calculate_pair(Operation::Gcd, 0, 0).unwrap(), "0");
+        assert_eq!(Calculator::calculate_pair(Operation::Lcm, 4, 6).unwrap(), "12");
+        assert_eq!(Calculator::calculate_pair(Operation::Lcm, 0, 6).unwrap(), "0");
+        // Coprime u64::MAX and u64::MAX - 1: the result only fits in u128
+        assert_eq!(
+            Calculator::calculate_pair(Operation::Lcm, u64::MAX, u64::MAX - 1).unwrap(),
+            (u64::MAX as u128 * (u64::MAX - 1) as u128).to_string()
+        );
+        assert!(Calculator::calculate_pair(Operation::Factorial, 4, 6).is_err());
+        assert!(Calculator::default().calculate(Operation::Gcd, 4).is_err());
     }
 
     #[test]
     fn test_calculate_integration() {
         // Test the main interface
         assert_eq!(
-            Calculator::calculate(Operation::Factorial, 5).unwrap(),
+            Calculator::default().calculate(Operation::Factorial, 5).unwrap(),
             "120"
         );
         assert_eq!(
-            Calculator::calculate(Operation::Fibonacci, 10).unwrap(),
+            Calculator::default().calculate(Operation::Fibonacci, 10).unwrap(),
             "55"
         );
         assert_eq!(
-            Calculator::calculate(Operation::PrimeCheck, 17).unwrap(),
+            Calculator::default().calculate(Operation::PrimeCheck, 17).unwrap(),
             "true"
         );
     }
@@ -218,9 +763,9 @@ mod tests {
     #[test]
     fn test_edge_cases() {
         // Test edge cases for each operation
-        assert_eq!(Calculator::factorial(0).unwrap(), "1");
-        assert_eq!(Calculator::fibonacci(0).unwrap(), "0");
-        assert_eq!(Calculator::prime_check(2).unwrap(), "true");
+        assert_eq!(Calculator::default().factorial(0).unwrap(), "1");
+        assert_eq!(Calculator::default().fibonacci(0).unwrap(), "0");
+        assert_eq!(Calculator::prime_check(2, &uncancelled()).unwrap(), "true");
     }
 
     #[test]
@@ -229,15 +774,15 @@ mod tests {
         
         // Test that calculations complete within reasonable time
         let start = std::time::Instant::now();
-        let _ = Calculator::factorial(20);
+        let _ = Calculator::default().factorial(20);
         assert!(start.elapsed() < Duration::from_millis(10));
 
         let start = std::time::Instant::now();
-        let _ = Calculator::fibonacci(50);
+        let _ = Calculator::default().fibonacci(50);
         assert!(start.elapsed() < Duration::from_millis(10));
 
         let start = std::time::Instant::now();
-        let _ = Calculator::prime_check(982451653);
+        let _ = Calculator::prime_check(982451653, &uncancelled());
         assert!(start.elapsed() < Duration::from_millis(100));
     }
 }
\ No newline at end of file
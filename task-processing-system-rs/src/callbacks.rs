@@ -0,0 +1,280 @@
+//! Per-task callbacks
+//!
+//! A task submitted with a `callback_url` is POSTed there, as the same JSON
+//! `GET /task/{id}` returns, each time it's calculated (status `processing`),
+//! completed or fails. Each callback is delivered on its own background task:
+//! failed attempts (connection errors, timeouts, 429 and 5xx answers) are
+//! retried with exponential backoff up to `callbacks.max_attempts`, while
+//! other answers are final. Callbacks for one task may therefore arrive out
+//! of order when one of them is retried; receivers should go by `status`.
+
+use crate::events::{EventBus, TaskEvent};
+use crate::http_client::{self, HttpClient};
+use crate::timestamps::WireFormat;
+use crate::types::{CallbackConfig, Task};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Events that trigger a callback
+pub const CALLBACK_EVENTS: [TaskEvent; 3] = [TaskEvent::Processed, TaskEvent::Completed, TaskEvent::Failed];
+
+/// Outcome of one delivery attempt
+#[derive(Debug, PartialEq)]
+enum Attempt {
+    Delivered,
+    /// Worth trying again
+    Retry(String),
+    /// The receiver rejected the callback
+    Rejected(String),
+}
+
+/// POSTs tasks to their `callback_url`
+pub struct CallbackDispatcher {
+    config: CallbackConfig,
+    client: HttpClient,
+    delivered: AtomicU64,
+    failed: AtomicU64,
+    format: WireFormat,
+}
+
+impl CallbackDispatcher {
+    pub fn new(config: CallbackConfig) -> Self {
+        let client = http_client::with_timeout(Duration::from_millis(config.timeout_ms));
+        Self {
+            config,
+            client,
+            delivered: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
+            format: WireFormat::default(),
+        }
+    }
+
+    /// Write the delivered tasks in `format`
+    pub fn with_wire_format(mut self, format: WireFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Deliver callbacks for the [`CALLBACK_EVENTS`] published on `events`
+    pub fn subscribe(self: &Arc<Self>, events: &EventBus) {
+        for event in CALLBACK_EVENTS {
+            let dispatcher = Arc::clone(self);
+            events.register(event, move |task: Task| {
+                dispatcher.notify(task);
+                async {}
+            });
+        }
+    }
+
+    /// Start delivering `task` to its `callback_url` (no-op without one)
+    pub fn notify(self: &Arc<Self>, task: Task) {
+        if task.callback_url.is_none() {
+            return;
+        }
+        let dispatcher = Arc::clone(self);
+        tokio::spawn(async move { dispatcher.deliver(&task).await });
+    }
+
+    /// POST `task` until it's accepted, rejected or out of attempts
+    async fn deliver(&self, task: &Task) -> bool {
+        let Some(url) = &task.callback_url else {
+            return false;
+        };
+        let body = match self.format.apply(|| serde_json::to_vec(task)) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("Cannot serialize callback for task {}: {}", task.id, e);
+                return false;
+            }
+        };
+
+        let mut attempt = 1;
+        loop {
+            let error = match self.post(url, &body).await {
+                Attempt::Delivered => {
+                    debug!("Delivered {} callback for task {} to {}", task.status, task.id, url);
+                    self.delivered.fetch_add(1, Ordering::Relaxed);
+                    return true;
+                }
+                Attempt::Retry(error) if attempt < self.config.max_attempts => {
+                    let backoff = self.config.backoff(attempt);
+                    debug!(
+                        "Callback for task {} to {} failed ({}), retrying in {:?}",
+                        task.id, url, error, backoff
+                    );
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                    continue;
+                }
+                Attempt::Retry(error) | Attempt::Rejected(error) => error,
+            };
+            warn!(
+                "Giving up on {} callback for task {} to {} after {} attempts: {}",
+                task.status, task.id, url, attempt, error
+            );
+            self.failed.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+    }
+
+    #[cfg(feature = "client")]
+    async fn post(&self, url: &str, body: &[u8]) -> Attempt {
+        let request = self
+            .client
+            .post(url)
+            .header("content-type", "application/json")
+            .body(body.to_vec());
+        match request.send().await {
+            Ok(response) if response.status().is_success() => Attempt::Delivered,
+            Ok(response) if response.status().is_server_error() || response.status().as_u16() == 429 => {
+                Attempt::Retry(format!("HTTP {}", response.status()))
+            }
+            Ok(response) => Attempt::Rejected(format!("HTTP {}", response.status())),
+            Err(e) => Attempt::Retry(e.to_string()),
+        }
+    }
+
+    #[cfg(not(feature = "client"))]
+    async fn post(&self, _url: &str, _body: &[u8]) -> Attempt {
+        Attempt::Rejected(http_client::UNAVAILABLE.to_string())
+    }
+
+    /// Callbacks delivered so far
+    pub fn delivered(&self) -> u64 {
+        self.delivered.load(Ordering::Relaxed)
+    }
+
+    /// Callbacks given up on so far
+    pub fn failed(&self) -> u64 {
+        self.failed.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Operation, TaskData, TaskPriority};
+    use parking_lot::Mutex;
+    use std::net::TcpListener;
+    use warp::Filter;
+
+    fn free_port() -> u16 {
+        TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+    }
+
+    fn config() -> CallbackConfig {
+        CallbackConfig {
+            timeout_ms: 1000,
+            max_attempts: 3,
+            initial_backoff_ms: 10,
+            max_backoff_ms: 20,
+        }
+    }
+
+    fn task(url: String) -> Task {
+        let mut task = Task::new("Called back".to_string(), TaskPriority::Medium, TaskData::new(5, Operation::Factorial));
+        task.callback_url = Some(url);
+        task.set_processing("120".to_string());
+        task
+    }
+
+    /// Receiver answering each request with the next of `statuses` (then 200)
+    fn spawn_receiver(port: u16, statuses: Vec<u16>) -> Arc<Mutex<Vec<serde_json::Value>>> {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&received);
+        let statuses = Arc::new(Mutex::new(statuses.into_iter()));
+        let route = warp::post().and(warp::body::json()).map(move |body: serde_json::Value| {
+            sink.lock().push(body);
+            let status = statuses.lock().next().unwrap_or(200);
+            warp::reply::with_status(warp::reply(), warp::http::StatusCode::from_u16(status).unwrap())
+        });
+        tokio::spawn(warp::serve(route).run(([127, 0, 0, 1], port)));
+        received
+    }
+
+    #[test]
+    fn test_backoff() {
+        let config = CallbackConfig {
+            initial_backoff_ms: 100,
+            max_backoff_ms: 1000,
+            ..CallbackConfig::default()
+        };
+        let waits: Vec<u64> = (1..=6).map(|attempt| config.backoff(attempt).as_millis() as u64).collect();
+        assert_eq!(waits, vec![100, 200, 400, 800, 1000, 1000]);
+        assert_eq!(config.backoff(200), Duration::from_millis(1000));
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_retries_until_delivered() {
+        let port = free_port();
+        let received = spawn_receiver(port, vec![503, 500]);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let dispatcher = CallbackDispatcher::new(config());
+        assert!(dispatcher.deliver(&task(format!("http://127.0.0.1:{}/hook", port))).await);
+        assert_eq!(dispatcher.delivered(), 1);
+
+        let received = received.lock();
+        assert_eq!(received.len(), 3);
+        assert_eq!(received[2]["status"], "processing");
+        assert_eq!(received[2]["result"], "120");
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_gives_up() {
+        let port = free_port();
+        let received = spawn_receiver(port, vec![500, 500, 500, 500]);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let dispatcher = CallbackDispatcher::new(config());
+        let url = format!("http://127.0.0.1:{}/hook", port);
+        assert!(!dispatcher.deliver(&task(url.clone())).await);
+        assert_eq!(received.lock().len(), 3);
+
+        // Client errors aren't retried
+        let port = free_port();
+        let rejected = spawn_receiver(port, vec![404]);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!dispatcher.deliver(&task(format!("http://127.0.0.1:{}/hook", port))).await);
+        assert_eq!(rejected.lock().len(), 1);
+        assert_eq!(dispatcher.failed(), 2);
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_events_trigger_callbacks() {
+        let port = free_port();
+        let received = spawn_receiver(port, Vec::new());
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let bus = Arc::new(EventBus::new());
+        let dispatcher = Arc::new(CallbackDispatcher::new(config()));
+        dispatcher.subscribe(&bus);
+        let handle = bus.start().unwrap();
+
+        let mut called_back = task(format!("http://127.0.0.1:{}/hook", port));
+        bus.emit(TaskEvent::Created, &called_back);
+        bus.emit(TaskEvent::Processed, &called_back);
+        called_back.set_completed().unwrap();
+        bus.emit(TaskEvent::Completed, &called_back);
+        let mut silent = task(String::new());
+        silent.callback_url = None;
+        bus.emit(TaskEvent::Processed, &silent);
+
+        for _ in 0..100 {
+            if dispatcher.delivered() == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(dispatcher.delivered(), 2);
+        let mut statuses: Vec<String> = received.lock().iter().map(|body| body["status"].as_str().unwrap().to_string()).collect();
+        statuses.sort();
+        assert_eq!(statuses, vec!["completed", "processing"]);
+        handle.abort();
+    }
+}
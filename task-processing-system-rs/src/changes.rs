@@ -0,0 +1,229 @@
+//! Ordered feed of task state changes (`GET /changes`)
+//!
+//! Every lifecycle event published on the [`EventBus`] is appended to the
+//! feed under the next sequence number, so a downstream system can poll
+//! `GET /changes?since=<last sequence seen>` and apply each change once, in
+//! order, without missing any between polls. Like the tasks, the feed lives
+//! in memory: the last [`CHANGE_HISTORY`] records are kept, and sequence
+//! numbers start over with the process. A consumer whose `since` is no longer
+//! covered gets `truncated` and should resync from `GET /tasks`.
+
+use crate::events::{EventBus, TaskEvent};
+use crate::query::QueryError;
+use crate::types::{Task, TaskStatus, MAX_LIST_LIMIT};
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// Records kept before the oldest are dropped
+pub const CHANGE_HISTORY: usize = 10_000;
+
+/// Records returned when `limit` isn't given
+pub const DEFAULT_CHANGES_LIMIT: usize = 100;
+
+/// Query of `GET /changes`
+#[derive(Debug, Default, Deserialize)]
+pub struct ChangesQuery {
+    /// Last sequence number already seen (0 or unset for everything kept)
+    pub since: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+impl ChangesQuery {
+    /// Requested page size, defaulted and checked against [`MAX_LIST_LIMIT`]
+    pub fn limit(&self) -> Result<usize, QueryError> {
+        match self.limit.unwrap_or(DEFAULT_CHANGES_LIMIT) {
+            limit @ 1..=MAX_LIST_LIMIT => Ok(limit),
+            limit => Err(QueryError::LimitOutOfRange {
+                limit,
+                max: MAX_LIST_LIMIT,
+            }),
+        }
+    }
+}
+
+/// One task state change
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangeRecord {
+    /// Increases by one with every change, across all tasks
+    pub sequence: u64,
+    #[serde(with = "crate::timestamps::timestamp")]
+    pub at: DateTime<Utc>,
+    pub event: TaskEvent,
+    pub task_id: String,
+    pub namespace: String,
+    /// The task's status after the change
+    pub status: TaskStatus,
+    #[serde(with = "crate::results::optional_result", skip_serializing_if = "Option::is_none")]
+    pub result: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_message: Option<String>,
+}
+
+/// Response of `GET /changes`
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangesPage {
+    /// Oldest first
+    pub changes: Vec<ChangeRecord>,
+    /// Pass as `since` on the next request
+    pub next: u64,
+    /// Changes after `since` were dropped (or the server restarted) before
+    /// this request; resync from `GET /tasks`
+    pub truncated: bool,
+}
+
+#[derive(Debug, Default)]
+struct FeedState {
+    records: VecDeque<ChangeRecord>,
+    last_sequence: u64,
+}
+
+#[derive(Debug)]
+pub struct ChangeFeed {
+    state: Mutex<FeedState>,
+    capacity: usize,
+}
+
+impl Default for ChangeFeed {
+    fn default() -> Self {
+        Self::with_capacity(CHANGE_HISTORY)
+    }
+}
+
+impl ChangeFeed {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            state: Mutex::new(FeedState::default()),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Record every lifecycle event published on `events`
+    pub fn subscribe(self: &Arc<Self>, events: &EventBus) {
+        for event in TaskEvent::ALL {
+            let feed = Arc::clone(self);
+            events.register(event, move |task: Task| {
+                feed.record(event, &task);
+                async {}
+            });
+        }
+    }
+
+    /// Append `event` for `task` under the next sequence number
+    pub fn record(&self, event: TaskEvent, task: &Task) {
+        let mut state = self.state.lock();
+        state.last_sequence += 1;
+        let record = ChangeRecord {
+            sequence: state.last_sequence,
+            at: Utc::now(),
+            event,
+            task_id: task.id.clone(),
+            namespace: task.namespace.clone(),
+            status: task.status.clone(),
+            result: task.result.clone(),
+            error_message: task.error_message.clone(),
+        };
+        if state.records.len() == self.capacity {
+            state.records.pop_front();
+        }
+        state.records.push_back(record);
+    }
+
+    /// Up to `limit` changes after `since` in namespaces passing `visible`
+    pub fn page(&self, since: u64, limit: usize, visible: impl Fn(&str) -> bool) -> ChangesPage {
+        let state = self.state.lock();
+        // Ahead of the feed: numbering started over with a restart
+        let (since, restarted) = if since > state.last_sequence { (0, true) } else { (since, false) };
+        let oldest = state.records.front().map_or(state.last_sequence + 1, |record| record.sequence);
+
+        let mut changes = Vec::new();
+        let mut next = since;
+        for record in state.records.iter().filter(|record| record.sequence > since) {
+            if changes.len() == limit {
+                break;
+            }
+            // Hidden changes are still passed over, so `next` moves on
+            next = record.sequence;
+            if visible(&record.namespace) {
+                changes.push(record.clone());
+            }
+        }
+        ChangesPage {
+            changes,
+            next,
+            truncated: restarted || since + 1 < oldest,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Operation, TaskData, TaskPriority};
+
+    fn task(id: &str, namespace: &str) -> Task {
+        let mut task = Task::with_id(id.to_string(), "fed".to_string(), TaskPriority::Medium, TaskData::new(5, Operation::Factorial));
+        task.namespace = namespace.to_string();
+        task
+    }
+
+    fn sequences(page: &ChangesPage) -> Vec<u64> {
+        page.changes.iter().map(|record| record.sequence).collect()
+    }
+
+    #[test]
+    fn test_resumable_pages() {
+        let feed = ChangeFeed::new();
+        let mut a = task("a", "default");
+        feed.record(TaskEvent::Created, &a);
+        a.set_processing("120".to_string());
+        feed.record(TaskEvent::Processed, &a);
+        feed.record(TaskEvent::Created, &task("b", "team-b"));
+
+        let page = feed.page(0, 2, |_| true);
+        assert_eq!(sequences(&page), vec![1, 2]);
+        assert_eq!(page.changes[1].status, TaskStatus::Processing);
+        assert_eq!(page.changes[1].result.as_deref(), Some("120"));
+        assert!(!page.truncated);
+
+        let page = feed.page(page.next, 2, |_| true);
+        assert_eq!(sequences(&page), vec![3]);
+        let page = feed.page(page.next, 2, |_| true);
+        assert!(page.changes.is_empty());
+        assert_eq!(page.next, 3);
+
+        // Other namespaces are skipped without holding the cursor back
+        let page = feed.page(0, 10, |namespace| namespace == "team-b");
+        assert_eq!((sequences(&page), page.next), (vec![3], 3));
+    }
+
+    #[test]
+    fn test_truncation() {
+        let feed = ChangeFeed::with_capacity(2);
+        for id in ["a", "b", "c"] {
+            feed.record(TaskEvent::Created, &task(id, "default"));
+        }
+        let page = feed.page(0, 10, |_| true);
+        assert_eq!(sequences(&page), vec![2, 3]);
+        assert!(page.truncated);
+        assert!(!feed.page(1, 10, |_| true).truncated);
+
+        // A cursor from before a restart starts over
+        let page = feed.page(50, 10, |_| true);
+        assert_eq!((sequences(&page), page.truncated), (vec![2, 3], true));
+    }
+
+    #[test]
+    fn test_limit() {
+        assert_eq!(ChangesQuery::default().limit(), Ok(DEFAULT_CHANGES_LIMIT));
+        let query = |limit| ChangesQuery { since: None, limit: Some(limit) };
+        assert!(query(0).limit().is_err());
+        assert!(query(MAX_LIST_LIMIT + 1).limit().is_err());
+    }
+}
@@ -0,0 +1,88 @@
+//! Exit codes and error output shared by the CLI subcommands
+//!
+//! | code | meaning                                               |
+//! |------|-------------------------------------------------------|
+//! | 0    | success                                               |
+//! | 1    | the command ran but failed its check, or was declined |
+//! | 2    | invalid arguments, configuration or request           |
+//! | 3    | task, worker or baseline not found                    |
+//! | 4    | server unreachable                                    |
+//! | 5    | server error or unexpected response                   |
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    Failed,
+    Validation,
+    NotFound,
+    Unreachable,
+    Server,
+}
+
+impl ErrorKind {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorKind::Failed => 1,
+            ErrorKind::Validation => 2,
+            ErrorKind::NotFound => 3,
+            ErrorKind::Unreachable => 4,
+            ErrorKind::Server => 5,
+        }
+    }
+}
+
+/// Error ending a CLI subcommand
+#[derive(Debug, Clone, Serialize)]
+pub struct CliError {
+    pub kind: ErrorKind,
+    pub error: String,
+    pub exit_code: i32,
+}
+
+impl CliError {
+    pub fn new(kind: ErrorKind, error: impl Into<String>) -> Self {
+        Self {
+            kind,
+            error: error.into(),
+            exit_code: kind.exit_code(),
+        }
+    }
+
+    /// Print to stderr, as one JSON object per line with `--json-errors`
+    pub fn report(&self, json: bool) {
+        if json {
+            eprintln!("{}", serde_json::to_string(self).unwrap_or_default());
+        } else {
+            eprintln!("{}", self.error);
+        }
+    }
+}
+
+/// Print the error, if any, and turn the outcome into the process exit code
+pub fn exit_code(result: Result<(), CliError>, json_errors: bool) -> i32 {
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            e.report(json_errors);
+            e.exit_code
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_shape() {
+        let error = CliError::new(ErrorKind::NotFound, "Task t1 not found");
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({"kind": "not_found", "error": "Task t1 not found", "exit_code": 3})
+        );
+        assert_eq!(exit_code(Err(CliError::new(ErrorKind::Unreachable, "down")), true), 4);
+        assert_eq!(exit_code(Ok(()), true), 0);
+    }
+}
@@ -0,0 +1,286 @@
+//! HTTP clients for a running orchestrator
+//!
+//! [`ApiClient`] is behind the CLI's server commands (`get`, `list`, `stats`,
+//! `admin`); [`TaskClient`] is the typed client for applications submitting
+//! tasks:
+//!
+//! ```rust,no_run
+//! use task_processing_system_rs::{CreateTaskRequest, TaskClient, TaskData, TaskPriority, Operation};
+//! use std::time::Duration;
+//!
+//! # async fn run() -> Result<(), task_processing_system_rs::client::ClientError> {
+//! let client = TaskClient::new("http://localhost:7000");
+//! let task = client
+//!     .create_task(&CreateTaskRequest::new("Calculate 10!".to_string(), TaskPriority::High, TaskData::new(10, Operation::Factorial)))
+//!     .await?;
+//! let task = client.wait_for_completion(&task.id, Duration::from_secs(10)).await?;
+//! client.complete_task(&task.id).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::cli_error::{CliError, ErrorKind};
+use crate::types::*;
+use serde::de::DeserializeOwned;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("{action} failed, server unreachable: {message}")]
+    Unreachable { action: String, message: String },
+
+    #[error("{action} rejected ({status}): {message}")]
+    Rejected {
+        action: String,
+        status: u16,
+        reason: Option<String>,
+        message: String,
+    },
+
+    #[error("Invalid {action} response: {message}")]
+    InvalidResponse { action: String, message: String },
+}
+
+impl From<ClientError> for CliError {
+    fn from(error: ClientError) -> Self {
+        let kind = match &error {
+            ClientError::Unreachable { .. } => ErrorKind::Unreachable,
+            ClientError::Rejected { status: 404, .. } => ErrorKind::NotFound,
+            ClientError::Rejected { status, .. } if *status < 500 => ErrorKind::Validation,
+            ClientError::Rejected { .. } | ClientError::InvalidResponse { .. } => ErrorKind::Server,
+        };
+        CliError::new(kind, error.to_string())
+    }
+}
+
+/// Client for a running orchestrator
+pub struct ApiClient {
+    client: reqwest::Client,
+    base: String,
+    api_key: Option<String>,
+}
+
+impl ApiClient {
+    pub fn new(base: impl Into<String>, api_key: Option<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base: base.into(),
+            api_key,
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base.trim_end_matches('/'), path)
+    }
+
+    /// Submit a task, returning it as accepted
+    pub async fn create_task(&self, request: &CreateTaskRequest) -> Result<Task, ClientError> {
+        let request = self.client.post(self.url("/task/create")).json(request);
+        self.send("POST /task/create", request).await
+    }
+
+    pub async fn get_task(&self, id: &str) -> Result<Task, ClientError> {
+        let path = format!("/task/{}", id);
+        self.send(&format!("GET {}", path), self.client.get(self.url(&path))).await
+    }
+
+    /// Long-poll `GET /task/{id}/wait`: the task once calculated, or as it stands after `timeout_ms`
+    pub async fn wait_task(&self, id: &str, timeout_ms: u64) -> Result<Task, ClientError> {
+        let path = format!("/task/{}/wait", id);
+        let query = WaitQuery { timeout_ms: Some(timeout_ms) };
+        self.send(&format!("GET {}", path), self.client.get(self.url(&path)).query(&query)).await
+    }
+
+    pub async fn complete_task(&self, id: &str) -> Result<TaskCompletionResponse, ClientError> {
+        let path = format!("/task/{}/complete", id);
+        self.send(&format!("POST {}", path), self.client.post(self.url(&path))).await
+    }
+
+    pub async fn list_tasks(&self, query: &TaskListQuery) -> Result<Vec<Task>, ClientError> {
+        let request = self.client.get(self.url("/tasks")).query(query);
+        self.send("GET /tasks", request).await
+    }
+
+    pub async fn stats(&self) -> Result<SystemStats, ClientError> {
+        self.send("GET /stats", self.client.get(self.url("/stats"))).await
+    }
+
+    pub async fn workers(&self) -> Result<Vec<WorkerStats>, ClientError> {
+        self.send("GET /admin/workers", self.client.get(self.url("/admin/workers"))).await
+    }
+
+    pub async fn pause_worker(&self, id: usize) -> Result<AdminResponse, ClientError> {
+        let path = format!("/admin/workers/{}/pause", id);
+        self.send(&format!("POST {}", path), self.client.post(self.url(&path))).await
+    }
+
+    pub async fn resume_worker(&self, id: usize) -> Result<AdminResponse, ClientError> {
+        let path = format!("/admin/workers/{}/resume", id);
+        self.send(&format!("POST {}", path), self.client.post(self.url(&path))).await
+    }
+
+    /// Stop every worker taking tasks off its queue; submissions still queue
+    pub async fn pause_processing(&self) -> Result<AdminResponse, ClientError> {
+        self.send("POST /admin/pause", self.client.post(self.url("/admin/pause"))).await
+    }
+
+    pub async fn resume_processing(&self) -> Result<AdminResponse, ClientError> {
+        self.send("POST /admin/resume", self.client.post(self.url("/admin/resume"))).await
+    }
+
+    pub async fn scale(&self, workers: usize) -> Result<AdminResponse, ClientError> {
+        let request = self.client.post(self.url("/admin/scale")).json(&ScaleRequest { workers });
+        self.send("POST /admin/scale", request).await
+    }
+
+    pub async fn purge(&self, status: TaskStatus) -> Result<AdminResponse, ClientError> {
+        let request = self.client.post(self.url("/admin/purge")).query(&PurgeQuery { status });
+        self.send("POST /admin/purge", request).await
+    }
+
+    pub async fn rebalance(&self) -> Result<AdminResponse, ClientError> {
+        self.send("POST /admin/rebalance", self.client.post(self.url("/admin/rebalance"))).await
+    }
+
+    async fn send<T: DeserializeOwned>(&self, action: &str, request: reqwest::RequestBuilder) -> Result<T, ClientError> {
+        let request = match &self.api_key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        };
+        let response = request.send().await.map_err(|e| ClientError::Unreachable {
+            action: action.to_string(),
+            message: e.to_string(),
+        })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let (message, reason) = match response.json::<ErrorResponse>().await {
+                Ok(body) => (body.error, body.reason),
+                Err(_) => (status.to_string(), None),
+            };
+            return Err(ClientError::Rejected {
+                action: action.to_string(),
+                status: status.as_u16(),
+                reason,
+                message,
+            });
+        }
+        response.json().await.map_err(|e| ClientError::InvalidResponse {
+            action: action.to_string(),
+            message: e.to_string(),
+        })
+    }
+}
+
+/// Typed client for submitting tasks to a running orchestrator and following them
+pub struct TaskClient {
+    api: ApiClient,
+}
+
+impl TaskClient {
+    /// Client for the server at `base_url` (e.g. `http://localhost:7000`)
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            api: ApiClient::new(base_url, None),
+        }
+    }
+
+    /// Send `key` as a bearer token, for servers with `[[api_keys]]` configured
+    pub fn with_api_key(self, key: impl Into<String>) -> Self {
+        Self {
+            api: ApiClient::new(self.api.base, Some(key.into())),
+        }
+    }
+
+    /// The underlying client, for the calls not wrapped here (listing, admin)
+    pub fn api(&self) -> &ApiClient {
+        &self.api
+    }
+
+    /// Submit a task, returning it as accepted (with its generated ID if the request had none)
+    pub async fn create_task(&self, request: &CreateTaskRequest) -> Result<Task, ClientError> {
+        self.api.create_task(request).await
+    }
+
+    pub async fn get_task(&self, id: &str) -> Result<Task, ClientError> {
+        self.api.get_task(id).await
+    }
+
+    /// Wait up to `timeout` for the task to be calculated (status `processing`
+    /// and awaiting [`complete_task`](Self::complete_task)) or to end otherwise
+    ///
+    /// Returns the task as it stands after `timeout` if it's still waiting to
+    /// run, so check its `status`. Longer waits are split into several
+    /// `GET /task/{id}/wait` requests of at most [`MAX_WAIT_TIMEOUT_MS`].
+    pub async fn wait_for_completion(&self, id: &str, timeout: Duration) -> Result<Task, ClientError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now()).as_millis() as u64;
+            let task = self.api.wait_task(id, remaining.clamp(1, MAX_WAIT_TIMEOUT_MS)).await?;
+            let waiting = matches!(task.status, TaskStatus::Scheduled | TaskStatus::Blocked | TaskStatus::Pending);
+            if !waiting || Instant::now() >= deadline {
+                return Ok(task);
+            }
+        }
+    }
+
+    pub async fn complete_task(&self, id: &str) -> Result<TaskCompletionResponse, ClientError> {
+        self.api.complete_task(id).await
+    }
+
+    pub async fn stats(&self) -> Result<SystemStats, ClientError> {
+        self.api.stats().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::TestServer;
+
+    fn rejected(status: u16) -> ClientError {
+        ClientError::Rejected {
+            action: "GET /task/t1".to_string(),
+            status,
+            reason: None,
+            message: "Not found".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        let code = |error: ClientError| CliError::from(error).exit_code;
+        assert_eq!(code(rejected(404)), 3);
+        assert_eq!(code(rejected(400)), 2);
+        assert_eq!(code(rejected(403)), 2);
+        assert_eq!(code(rejected(503)), 5);
+        let unreachable = ClientError::Unreachable {
+            action: "GET /stats".to_string(),
+            message: "connection refused".to_string(),
+        };
+        assert_eq!(code(unreachable), 4);
+    }
+
+    #[tokio::test]
+    async fn test_task_client_round_trip() {
+        let server = TestServer::start().await.unwrap();
+        let client = TaskClient::new(server.base_url());
+
+        let request = CreateTaskRequest::new("Client round trip".to_string(), TaskPriority::High, TaskData::new(10, Operation::Factorial));
+        let created = client.create_task(&request).await.unwrap();
+        assert_eq!(created.id, request.id);
+
+        let calculated = client.wait_for_completion(&created.id, Duration::from_secs(5)).await.unwrap();
+        assert_eq!(calculated.status, TaskStatus::Processing);
+        assert_eq!(calculated.result.as_deref(), Some("3628800"));
+        assert_eq!(client.complete_task(&created.id).await.unwrap().status, TaskStatus::Completed);
+        assert_eq!(client.get_task(&created.id).await.unwrap().status, TaskStatus::Completed);
+        assert_eq!(client.stats().await.unwrap().total_tasks_completed, 1);
+
+        match client.get_task("missing").await {
+            Err(ClientError::Rejected { status: 404, .. }) => {}
+            other => panic!("expected a 404, got {:?}", other.map(|task| task.id)),
+        }
+        server.stop().await;
+    }
+}
@@ -0,0 +1,430 @@
+//! JSON Schema for the configuration file (`task-processor config schema`)
+//!
+//! Types, bounds and descriptions are written out here; defaults are filled in
+//! by serializing the config structs' `Default` values, and the tests check the
+//! property names against the structs' serde fields.
+
+use crate::sizing;
+use crate::types::OrchestratorConfig;
+use serde_json::{json, Value};
+
+const DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+fn count_or_auto(max: u64, description: &str) -> Value {
+    json!({
+        "description": description,
+        "oneOf": [
+            {"type": "integer", "minimum": 1, "maximum": max},
+            {"const": "auto"}
+        ]
+    })
+}
+
+fn operation() -> Value {
+    json!({"enum": ["factorial", "fibonacci", "prime_check", "generate_prime", "gcd", "lcm"]})
+}
+
+fn prime_generation() -> Value {
+    json!({
+        "type": "object",
+        "description": "Settings for the generate_prime operation",
+        "properties": {
+            "max_bits": {"type": "integer", "minimum": 2, "maximum": 64, "description": "Largest bit length a task may request"},
+            "seed": {"type": ["integer", "null"], "minimum": 0, "description": "Fixed RNG seed for reproducible fixtures (default: OS entropy)"},
+            "time_limit_ms": {"type": "integer", "minimum": 1, "description": "Give up on a single generation after this long"}
+        }
+    })
+}
+
+fn big_precision() -> Value {
+    json!({
+        "type": "object",
+        "description": "Result size limit for factorial and fibonacci tasks with precision \"big\"",
+        "properties": {
+            "max_digits": {"type": "integer", "minimum": 1, "maximum": 100000, "description": "Most decimal digits a result may have; each operation's largest input follows from it"}
+        }
+    })
+}
+
+fn operation_limits() -> Value {
+    json!({
+        "type": "object",
+        "description": "Largest inputs of the operations calculated with fixed precision",
+        "properties": {
+            "max_factorial_input": {"type": "integer", "minimum": 0, "maximum": 34, "description": "Largest factorial input (34! is the largest that fits in 128 bits)"},
+            "max_fibonacci_input": {"type": "integer", "minimum": 0, "maximum": 93, "description": "Largest fibonacci input (F(93) is the largest that fits in 64 bits)"},
+            "max_prime_check_input": {"type": "integer", "minimum": 0, "description": "Largest prime_check input"}
+        }
+    })
+}
+
+fn webhooks() -> Value {
+    json!({
+        "type": "object",
+        "description": "Completion webhooks",
+        "properties": {
+            "endpoints": {"type": "array", "items": {"type": "string", "pattern": "^https?://"}, "description": "URLs that receive task.completed events"},
+            "batch_size": {"type": "integer", "minimum": 1, "description": "Send a batch once this many events are queued (1 disables batching)"},
+            "flush_interval_ms": {"type": "integer", "minimum": 1, "description": "Send a partial batch after at most this long"},
+            "timeout_ms": {"type": "integer", "minimum": 1, "description": "Timeout for each delivery request"}
+        }
+    })
+}
+
+fn callbacks() -> Value {
+    json!({
+        "type": "object",
+        "description": "Delivery of tasks to the callback_url they were submitted with",
+        "properties": {
+            "timeout_ms": {"type": "integer", "minimum": 1, "description": "Timeout for each delivery attempt"},
+            "max_attempts": {"type": "integer", "minimum": 1, "description": "Attempts per callback, including the first"},
+            "initial_backoff_ms": {"type": "integer", "minimum": 1, "description": "Wait before the first retry; doubled after every further failure"},
+            "max_backoff_ms": {"type": "integer", "minimum": 1, "description": "Longest wait between two attempts"}
+        }
+    })
+}
+
+fn queue_polling() -> Value {
+    json!({
+        "type": "object",
+        "description": "How processing threads wait for and take queued tasks",
+        "properties": {
+            "mode": {"enum": ["notify", "notify_with_fallback"], "description": "Sleep until a task is queued, or also check the queue every fallback_interval_ms"},
+            "fallback_interval_ms": {"type": "integer", "minimum": 1, "description": "Queue check interval for notify_with_fallback"},
+            "batch_size": {"type": "integer", "minimum": 0, "description": "Tasks a thread takes off the queue at once per wakeup (0 means one at a time until the queue is empty)"}
+        }
+    })
+}
+
+fn watchdog() -> Value {
+    json!({
+        "type": "object",
+        "description": "Detection of processing threads stuck on one task",
+        "properties": {
+            "threshold_ms": {"type": "integer", "minimum": 0, "description": "A thread busy with the same task for longer than this is flagged (0 disables the watchdog)"},
+            "check_interval_ms": {"type": "integer", "minimum": 1, "description": "How often each worker checks its threads"},
+            "respawn": {"type": "boolean", "description": "Start a replacement for a flagged thread; the stuck one exits once its task finishes"}
+        }
+    })
+}
+
+fn work_stealing() -> Value {
+    json!({
+        "type": "object",
+        "description": "Idle processing threads taking queued tasks from the most loaded peer",
+        "properties": {
+            "enabled": {"type": "boolean"},
+            "interval_ms": {"type": "integer", "minimum": 1, "description": "How often an idle thread looks for a peer to steal from"},
+            "min_queue": {"type": "integer", "minimum": 0, "description": "Only peers with at least this many queued tasks are stolen from"},
+            "max_batch": {"type": "integer", "minimum": 1, "description": "Most tasks taken in one steal (half the peer's queue otherwise)"}
+        }
+    })
+}
+
+fn listeners() -> Value {
+    json!({
+        "type": "array",
+        "description": "Further addresses serving the same API, each optionally over TLS",
+        "items": {
+            "type": "object",
+            "required": ["address"],
+            "properties": {
+                "address": {"type": "string", "description": "host:port to bind, e.g. 0.0.0.0:7443 or [::1]:7001"},
+                "tls": {
+                    "type": ["object", "null"],
+                    "required": ["cert_path", "key_path"],
+                    "properties": {
+                        "cert_path": {"type": "string", "description": "PEM certificate chain"},
+                        "key_path": {"type": "string", "description": "PEM private key"}
+                    }
+                }
+            }
+        }
+    })
+}
+
+fn api_keys() -> Value {
+    json!({
+        "type": "array",
+        "description": "API keys and the namespaces they may use (auth disabled when empty)",
+        "items": {
+            "type": "object",
+            "required": ["name", "key", "namespaces"],
+            "properties": {
+                "name": {"type": "string"},
+                "key": {"type": "string", "minLength": 1},
+                "namespaces": {"type": "array", "minItems": 1, "items": {"type": "string", "pattern": "^([a-z0-9_-]{1,64}|\\*)$"}}
+            }
+        }
+    })
+}
+
+fn rate_limit() -> Value {
+    json!({
+        "type": "object",
+        "description": "Token-bucket limits on each client's requests, counted by API key or IP address",
+        "properties": {
+            "requests_per_second": {"type": "number", "minimum": 0, "description": "Sustained requests per second per client on routes without their own rule (0 disables)"},
+            "burst": {"type": "integer", "minimum": 1, "description": "Requests a client may make in a burst before the rate applies"},
+            "routes": {
+                "type": "array",
+                "description": "Routes with a limit of their own, replacing the default one",
+                "items": {
+                    "type": "object",
+                    "required": ["route", "requests_per_second"],
+                    "properties": {
+                        "route": {"type": "string", "pattern": "^/", "description": "Route pattern, e.g. /task/create or /task/{id}"},
+                        "requests_per_second": {"type": "number", "minimum": 0, "description": "Sustained requests per second per client (0 exempts the route)"},
+                        "burst": {"type": "integer", "minimum": 1, "default": 20, "description": "Requests a client may make in a burst"}
+                    }
+                }
+            }
+        }
+    })
+}
+
+fn inject_failures() -> Value {
+    json!({
+        "type": "array",
+        "description": "Calculations forced to fail (debug builds only)",
+        "items": {
+            "type": "object",
+            "required": ["operation", "input"],
+            "properties": {
+                "operation": operation(),
+                "input": {"type": "integer", "minimum": 0},
+                "message": {"type": ["string", "null"]},
+                "panic": {"type": "boolean", "default": false, "description": "Panic instead of failing, crashing the worker"}
+            }
+        }
+    })
+}
+
+fn statsd() -> Value {
+    json!({
+        "type": ["object", "null"],
+        "description": "Push metrics to a statsd daemon (disabled when unset)",
+        "required": ["address"],
+        "properties": {
+            "address": {"type": "string", "description": "host:port of the daemon, e.g. 127.0.0.1:8125"},
+            "prefix": {"type": "string", "default": "task_processor", "description": "Prepended to every metric name (empty for none)"}
+        }
+    })
+}
+
+fn analytics() -> Value {
+    json!({
+        "type": ["object", "null"],
+        "description": "Record finished calculations in a local SQLite file (disabled when unset)",
+        "required": ["path"],
+        "properties": {
+            "path": {"type": "string", "description": "Database file, created if missing; rows are appended across restarts"}
+        }
+    })
+}
+
+fn task_store() -> Value {
+    json!({
+        "type": ["object", "null"],
+        "description": "Keep tasks in a local SQLite file so they survive restarts (in memory only when unset)",
+        "required": ["path"],
+        "properties": {
+            "path": {"type": "string", "description": "Database file, created if missing; tasks found in it are restored on start"}
+        }
+    })
+}
+
+fn stats_checkpoint() -> Value {
+    json!({
+        "type": ["object", "null"],
+        "description": "Save cumulative statistics to a JSON file so they survive restarts (counters start from zero with every process when unset)",
+        "required": ["path"],
+        "properties": {
+            "path": {"type": "string", "description": "JSON file, created if missing; its counters are added to this run's"},
+            "interval_seconds": {"type": "integer", "minimum": 1, "default": 60, "description": "Seconds between saves; the counters are also saved on stop"}
+        }
+    })
+}
+
+fn federation() -> Value {
+    json!({
+        "type": "object",
+        "description": "Peer orchestrators receiving tasks this one can't take (disabled without peers)",
+        "properties": {
+            "peers": {
+                "type": "array",
+                "description": "Tried round-robin for each forwarded task",
+                "items": {
+                    "type": "object",
+                    "required": ["name", "url"],
+                    "properties": {
+                        "name": {"type": "string", "minLength": 1, "description": "Unique name shown for the tasks it holds"},
+                        "url": {"type": "string", "pattern": "^https?://", "description": "Base URL of its REST API"},
+                        "api_key": {"type": ["string", "null"], "description": "Sent as a bearer token when the peer requires API keys"}
+                    }
+                }
+            },
+            "timeout_ms": {"type": "integer", "minimum": 1, "description": "Timeout for each request to a peer"}
+        }
+    })
+}
+
+fn worker_labels() -> Value {
+    json!({
+        "type": "array",
+        "description": "Per-worker labels reported in worker snapshots, indexed by worker id",
+        "maxItems": 50,
+        "items": {
+            "type": "object",
+            "propertyNames": {"minLength": 1},
+            "additionalProperties": {"type": "string"}
+        }
+    })
+}
+
+/// Copy each property's value in `defaults` into its schema as `default`
+fn with_defaults(mut schema: Value, defaults: &Value) -> Value {
+    if let (Some(properties), Some(defaults)) = (schema["properties"].as_object_mut(), defaults.as_object()) {
+        for (name, property) in properties {
+            if let Some(default) = defaults.get(name) {
+                property["default"] = default.clone();
+            }
+        }
+    }
+    schema
+}
+
+/// Schema for the whole config file (orchestrator settings plus `log_level`)
+pub fn config_schema() -> Value {
+    let mut defaults = serde_json::to_value(OrchestratorConfig::default()).unwrap_or(Value::Null);
+    // The binary sizes workers from the available CPUs unless told otherwise
+    defaults["num_workers"] = json!(sizing::AUTO);
+    defaults["threads_per_worker"] = json!(sizing::AUTO);
+    let schema = json!({
+        "$schema": DRAFT,
+        "title": "task-processor configuration",
+        "type": "object",
+        "required": ["orchestrator_port"],
+        "properties": {
+            "num_workers": count_or_auto(50, "Number of worker nodes, or \"auto\" to size from available CPUs"),
+            "threads_per_worker": count_or_auto(32, "Processing threads per worker, or \"auto\""),
+            "orchestrator_port": {
+                "description": "Orchestrator API port, or 0 to bind any free port",
+                "oneOf": [
+                    {"const": 0},
+                    {"type": "integer", "minimum": 1025, "maximum": 65535}
+                ]
+            },
+            "listeners": listeners(),
+            "tls_cert_path": {"type": ["string", "null"], "description": "PEM certificate chain; with tls_key_path the main listener serves HTTPS"},
+            "tls_key_path": {"type": ["string", "null"], "description": "PEM private key for tls_cert_path"},
+            "bind_address": {"type": "string", "description": "IP address the main listener binds, e.g. 127.0.0.1, 0.0.0.0, ::1 or :: (dual-stack)"},
+            "port_file": {"type": ["string", "null"], "description": "File the bound address is written to once listening (removed on stop)"},
+            "base_port": {"type": ["integer", "null"], "minimum": 1025, "maximum": 65535, "description": "First per-worker diagnostics port (disabled when unset)"},
+            "log_level": {"enum": ["error", "warn", "info", "debug", "trace"], "default": "info"},
+            "prime_generation": with_defaults(prime_generation(), &defaults["prime_generation"]),
+            "big_precision": with_defaults(big_precision(), &defaults["big_precision"]),
+            "operation_limits": with_defaults(operation_limits(), &defaults["operation_limits"]),
+            "fibonacci_algorithm": {"enum": ["iterative", "fast_doubling", "matrix"], "description": "Fibonacci algorithm used when a task doesn't specify one"},
+            "prime_check_algorithm": {"enum": ["miller_rabin", "trial_division"], "description": "Primality test used by prime_check (trial_division is kept for comparison benchmarks)"},
+            "slow_request_threshold_ms": {"type": "integer", "minimum": 0, "description": "Requests slower than this are logged and counted (0 disables)"},
+            "stats_stream_interval_seconds": {"type": "integer", "minimum": 1, "maximum": 3600, "description": "Seconds between the snapshots GET /stats/stream pushes"},
+            "idempotency_window_seconds": {"type": "integer", "minimum": 0, "default": 86400, "description": "Seconds an idempotency key keeps returning the task it created (0 ignores idempotency keys)"},
+            "stats_cache_ms": {"type": "integer", "minimum": 0, "description": "How long GET /stats reuses its aggregated result (0 disables caching)"},
+            "result_signing_key": {"type": ["string", "null"], "description": "HMAC key used to sign results (signing disabled when unset)"},
+            "primary_url": {"type": ["string", "null"], "pattern": "^https?://", "description": "Base URL of the primary; when set this instance is a read-only replica"},
+            "timestamp_format": {"enum": ["rfc3339", "epoch_millis"], "description": "Default format for created_at/completed_at"},
+            "result_format": {"enum": ["plain", "typed"], "description": "Write results as bare strings, or as {\"type\", \"value\"} objects"},
+            "calculation_executor": {"enum": ["blocking", "dedicated", "inline"], "description": "Run calculations on tokio's blocking pool, on threads_per_worker dedicated threads per worker, or inline on the async runtime"},
+            "simulated_processing_ms": {"type": "integer", "minimum": 0, "description": "Simulated processing time added to every task"},
+            "worker_speed_factors": {"type": "array", "items": {"type": "number", "exclusiveMinimum": 0}, "maxItems": 50, "description": "Per-worker multipliers on simulated processing time"},
+            "load_balancing": {"enum": ["round_robin", "least_loaded", "weighted_round_robin", "random"], "description": "How new tasks are spread over the workers"},
+            "worker_weights": {"type": "array", "items": {"type": "integer", "minimum": 1}, "maxItems": 50, "description": "Per-worker shares under weighted_round_robin (missing entries mean 1)"},
+            "worker_labels": worker_labels(),
+            "max_tasks_per_worker": {"type": "integer", "minimum": 0, "description": "Most tasks a worker stores, oldest completed or failed evicted first (0 means unlimited)"},
+            "max_queue_size": {"type": "integer", "minimum": 0, "description": "Most tasks waiting in a worker's queue; submissions past it get 429 (0 means unlimited)"},
+            "completed_task_ttl_seconds": {"type": "integer", "minimum": 0, "description": "Seconds completed, failed and cancelled tasks are kept before workers drop them (0 means forever)"},
+            "shutdown_drain_timeout_seconds": {"type": "integer", "minimum": 0, "default": 30, "description": "Seconds a shutdown waits for queued tasks to be processed (0 stops right away)"},
+            "default_task_timeout_ms": {"type": "integer", "minimum": 0, "default": 0, "description": "Milliseconds a calculation attempt may take for tasks without timeout_ms (0 means no limit)"},
+            "queue_polling": with_defaults(queue_polling(), &defaults["queue_polling"]),
+            "worker_batch_sizes": {"type": "array", "items": {"type": "integer", "minimum": 0}, "maxItems": 50, "description": "Per-worker queue_polling.batch_size overrides"},
+            "watchdog": with_defaults(watchdog(), &defaults["watchdog"]),
+            "work_stealing": with_defaults(work_stealing(), &defaults["work_stealing"]),
+            "webhooks": with_defaults(webhooks(), &defaults["webhooks"]),
+            "callbacks": with_defaults(callbacks(), &defaults["callbacks"]),
+            "statsd": statsd(),
+            "analytics": analytics(),
+            "task_store": task_store(),
+            "stats_checkpoint": stats_checkpoint(),
+            "federation": with_defaults(federation(), &defaults["federation"]),
+            "api_keys": api_keys(),
+            "rate_limit": with_defaults(rate_limit(), &defaults["rate_limit"]),
+            "inject_failures": inject_failures()
+        }
+    });
+    with_defaults(schema, &defaults)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        BigPrecisionConfig, CallbackConfig, OperationLimitsConfig, PrimeGenerationConfig, QueuePollingConfig, RateLimitConfig, WatchdogConfig,
+        WebhookConfig, WorkStealingConfig,
+    };
+    use std::collections::BTreeSet;
+
+    /// Fields that are never serialized (secrets), so get no default
+    const SECRET_FIELDS: [&str; 1] = ["result_signing_key"];
+
+    fn property_names(schema: &Value) -> BTreeSet<String> {
+        schema["properties"].as_object().unwrap().keys().cloned().collect()
+    }
+
+    fn field_names<T: serde::Serialize>(value: T) -> BTreeSet<String> {
+        serde_json::to_value(value).unwrap().as_object().unwrap().keys().cloned().collect()
+    }
+
+    #[test]
+    fn test_schema_matches_structs() {
+        let schema = config_schema();
+        let mut fields = field_names(OrchestratorConfig::default());
+        fields.extend(SECRET_FIELDS.iter().map(|field| field.to_string()));
+        fields.insert("log_level".to_string());
+        assert_eq!(property_names(&schema), fields);
+
+        assert_eq!(property_names(&schema["properties"]["webhooks"]), field_names(WebhookConfig::default()));
+        assert_eq!(property_names(&schema["properties"]["callbacks"]), field_names(CallbackConfig::default()));
+        assert_eq!(
+            property_names(&schema["properties"]["prime_generation"]),
+            field_names(PrimeGenerationConfig::default())
+        );
+        assert_eq!(
+            property_names(&schema["properties"]["big_precision"]),
+            field_names(BigPrecisionConfig::default())
+        );
+        assert_eq!(
+            property_names(&schema["properties"]["operation_limits"]),
+            field_names(OperationLimitsConfig::default())
+        );
+        assert_eq!(
+            property_names(&schema["properties"]["queue_polling"]),
+            field_names(QueuePollingConfig::default())
+        );
+        assert_eq!(property_names(&schema["properties"]["watchdog"]), field_names(WatchdogConfig::default()));
+        assert_eq!(property_names(&schema["properties"]["rate_limit"]), field_names(RateLimitConfig::default()));
+        assert_eq!(
+            property_names(&schema["properties"]["work_stealing"]),
+            field_names(WorkStealingConfig::default())
+        );
+    }
+
+    #[test]
+    fn test_defaults_from_structs() {
+        let schema = config_schema();
+        assert_eq!(schema["properties"]["num_workers"]["default"], "auto");
+        assert_eq!(schema["properties"]["fibonacci_algorithm"]["default"], "iterative");
+        assert_eq!(schema["properties"]["webhooks"]["properties"]["timeout_ms"]["default"], 5000);
+        assert!(schema["properties"]["result_signing_key"].get("default").is_none());
+    }
+}
@@ -0,0 +1,129 @@
+//! Task dependencies (`depends_on`)
+//!
+//! A task submitted with `depends_on` is held by the scheduler in status
+//! `blocked` until every task it names is completed, then queued like any
+//! other. If one of them fails or is cancelled, the blocked task fails too
+//! (and, in turn, whatever depends on it), as it does when one is deleted
+//! before it finishes. Dependencies must name tasks already submitted, so
+//! every one of them can still finish and the graph can't have cycles.
+//!
+//! [`DependencyGraph`] only keeps the edges still open: from each blocked
+//! task to the dependencies it waits for, and back.
+
+use std::collections::{HashMap, HashSet};
+
+/// Most tasks one task may depend on
+pub const MAX_DEPENDENCIES: usize = 64;
+
+/// Open dependencies of the blocked tasks
+#[derive(Debug, Default)]
+pub struct DependencyGraph {
+    /// Blocked task to the dependencies it still waits for
+    waiting: HashMap<String, HashSet<String>>,
+    /// Dependency to the blocked tasks waiting for it
+    dependents: HashMap<String, HashSet<String>>,
+}
+
+impl DependencyGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `task_id` waits for each of `dependencies`
+    pub fn add(&mut self, task_id: &str, dependencies: impl IntoIterator<Item = String>) {
+        for dependency in dependencies {
+            self.dependents.entry(dependency.clone()).or_default().insert(task_id.to_string());
+            self.waiting.entry(task_id.to_string()).or_default().insert(dependency);
+        }
+    }
+
+    /// `dependency` completed: the tasks that no longer wait for anything
+    pub fn resolve(&mut self, dependency: &str) -> Vec<String> {
+        let mut ready = Vec::new();
+        for task_id in self.dependents.remove(dependency).unwrap_or_default() {
+            let Some(waiting) = self.waiting.get_mut(&task_id) else {
+                continue;
+            };
+            waiting.remove(dependency);
+            if waiting.is_empty() {
+                self.waiting.remove(&task_id);
+                ready.push(task_id);
+            }
+        }
+        ready
+    }
+
+    /// `dependency` failed or was cancelled: the tasks waiting for it, which
+    /// stop waiting for anything else
+    pub fn fail(&mut self, dependency: &str) -> Vec<String> {
+        let failed: Vec<String> = self.dependents.remove(dependency).unwrap_or_default().into_iter().collect();
+        for task_id in &failed {
+            self.remove(task_id);
+        }
+        failed
+    }
+
+    /// Forget a blocked task (cancelled, or failed on another dependency)
+    pub fn remove(&mut self, task_id: &str) {
+        for dependency in self.waiting.remove(task_id).unwrap_or_default() {
+            if let Some(dependents) = self.dependents.get_mut(&dependency) {
+                dependents.remove(task_id);
+                if dependents.is_empty() {
+                    self.dependents.remove(&dependency);
+                }
+            }
+        }
+    }
+
+    /// Blocked tasks
+    pub fn len(&self) -> usize {
+        self.waiting.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.waiting.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_resolve_releases_when_all_done() {
+        let mut graph = DependencyGraph::new();
+        graph.add("c", ids(&["a", "b"]));
+        graph.add("d", ids(&["a"]));
+        assert_eq!(graph.len(), 2);
+
+        let mut ready = graph.resolve("a");
+        ready.sort();
+        assert_eq!(ready, ids(&["d"]));
+        assert_eq!(graph.resolve("b"), ids(&["c"]));
+        assert!(graph.is_empty());
+        assert!(graph.resolve("a").is_empty());
+    }
+
+    #[test]
+    fn test_fail_drops_other_dependencies() {
+        let mut graph = DependencyGraph::new();
+        graph.add("c", ids(&["a", "b"]));
+        assert_eq!(graph.fail("a"), ids(&["c"]));
+        // "c" no longer waits for "b"
+        assert!(graph.resolve("b").is_empty());
+        assert!(graph.is_empty());
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut graph = DependencyGraph::new();
+        graph.add("c", ids(&["a"]));
+        graph.remove("c");
+        assert!(graph.is_empty());
+        assert!(graph.resolve("a").is_empty());
+    }
+}
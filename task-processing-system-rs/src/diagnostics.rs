@@ -0,0 +1,78 @@
+//! Per-worker diagnostics servers (`base_port` in the config)
+//!
+//! The C++ server gives every worker its own port; here workers are internal,
+//! so this optional mode lets ops look at a single worker directly. Each
+//! worker listens on `127.0.0.1:{base_port + id}` and serves read-only
+//! `GET /stats` and `GET /queue`.
+
+use crate::types::SystemError;
+use crate::worker::Worker;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tracing::info;
+use warp::{Filter, Rejection, Reply};
+
+/// Routes served on one worker's diagnostics port
+pub fn routes(worker: Arc<Worker>) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    let worker_for_stats = Arc::clone(&worker);
+    let stats = warp::path!("stats").and(warp::get()).and_then(move || {
+        let worker = Arc::clone(&worker_for_stats);
+        async move { Ok::<_, Rejection>(warp::reply::json(&worker.get_stats().await)) }
+    });
+
+    let queue = warp::path!("queue").and(warp::get()).and_then(move || {
+        let worker = Arc::clone(&worker);
+        async move { Ok::<_, Rejection>(warp::reply::json(&worker.queue_snapshot().await)) }
+    });
+
+    stats.or(queue)
+}
+
+/// Bind a worker's diagnostics server on `port` and run it in the background
+pub fn serve(worker: Arc<Worker>, port: u16) -> Result<(SocketAddr, JoinHandle<()>), SystemError> {
+    let id = worker.id;
+    let (addr, server) = warp::serve(routes(worker))
+        .try_bind_ephemeral(([127, 0, 0, 1], port))
+        .map_err(|e| SystemError::Worker {
+            message: format!("Worker {} diagnostics cannot bind port {}: {}", id, port, e),
+        })?;
+
+    info!("Worker {} diagnostics listening on {}", id, addr);
+    Ok((addr, tokio::spawn(server)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::*;
+
+    #[tokio::test]
+    async fn test_stats_and_queue() {
+        let worker = Arc::new(Worker::new(4, 1));
+        let task = Task::new("Queued".to_string(), TaskPriority::High, TaskData::new(5, Operation::Factorial));
+        let task_id = task.id.clone();
+        worker.add_task(task).await.unwrap();
+        let routes = routes(worker);
+
+        let stats = warp::test::request().path("/stats").reply(&routes).await;
+        assert_eq!(stats.status(), 200);
+        let stats: WorkerStats = serde_json::from_slice(stats.body()).unwrap();
+        assert_eq!(stats.id, 4);
+        assert_eq!(stats.current_load, 1);
+
+        let queue = warp::test::request().path("/queue").reply(&routes).await;
+        let queue: Vec<QueuedTask> = serde_json::from_slice(queue.body()).unwrap();
+        assert_eq!(queue[0].id, task_id);
+
+        let unknown = warp::test::request().method("POST").path("/queue").reply(&routes).await;
+        assert_eq!(unknown.status(), 405);
+    }
+
+    #[tokio::test]
+    async fn test_port_in_use() {
+        let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = taken.local_addr().unwrap().port();
+        assert!(serve(Arc::new(Worker::new(0, 1)), port).is_err());
+    }
+}
@@ -0,0 +1,161 @@
+//! In-process task lifecycle hooks for library users
+//!
+//! The orchestrator and its workers publish lifecycle events on an
+//! [`EventBus`]; hooks registered with `TaskOrchestrator::on_task_created`
+//! and friends run on a single background task, one at a time and in the
+//! order the events happened. A slow hook delays later ones but never the
+//! workers, and a panicking hook is logged and skipped. The orchestrator's
+//! own `GET /changes` feed is recorded by hooks on the same bus.
+
+use crate::types::Task;
+use futures::future::{BoxFuture, FutureExt};
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::error;
+
+/// Points in a task's life that hooks can observe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskEvent {
+    /// Accepted and queued on a worker
+    Created,
+    /// Calculated successfully, waiting for `POST /task/{id}/complete`
+    Processed,
+    /// Marked completed
+    Completed,
+    /// The calculation (or a pipeline stage) failed
+    Failed,
+    /// Cancelled through `POST /task/{id}/cancel` before it was completed
+    Cancelled,
+}
+
+impl TaskEvent {
+    /// Every event, in lifecycle order
+    pub const ALL: [TaskEvent; 5] = [
+        TaskEvent::Created,
+        TaskEvent::Processed,
+        TaskEvent::Completed,
+        TaskEvent::Failed,
+        TaskEvent::Cancelled,
+    ];
+}
+
+/// Registered callback; receives a copy of the task as of the event
+pub type TaskHook = Arc<dyn Fn(Task) -> BoxFuture<'static, ()> + Send + Sync>;
+
+pub struct EventBus {
+    hooks: RwLock<Vec<(TaskEvent, TaskHook)>>,
+    sender: mpsc::UnboundedSender<(TaskEvent, Task)>,
+    receiver: Mutex<Option<mpsc::UnboundedReceiver<(TaskEvent, Task)>>>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Self {
+            hooks: RwLock::new(Vec::new()),
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+        }
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call `hook` for every `event`
+    pub fn register<F, Fut>(&self, event: TaskEvent, hook: F)
+    where
+        F: Fn(Task) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let hook: TaskHook = Arc::new(move |task| hook(task).boxed());
+        self.hooks.write().push((event, hook));
+    }
+
+    fn hooks_for(&self, event: TaskEvent) -> Vec<TaskHook> {
+        self.hooks
+            .read()
+            .iter()
+            .filter(|(registered, _)| *registered == event)
+            .map(|(_, hook)| Arc::clone(hook))
+            .collect()
+    }
+
+    /// Queue `event` for the hooks (no-op, without copying the task, when none listen)
+    pub fn emit(&self, event: TaskEvent, task: &Task) {
+        if self.hooks.read().iter().any(|(registered, _)| *registered == event) {
+            let _ = self.sender.send((event, task.clone()));
+        }
+    }
+
+    /// Start running hooks; returns `None` if already started
+    pub fn start(self: &Arc<Self>) -> Option<JoinHandle<()>> {
+        let mut receiver = self.receiver.lock().take()?;
+        let bus = Arc::clone(self);
+        Some(tokio::spawn(async move {
+            while let Some((event, task)) = receiver.recv().await {
+                for hook in bus.hooks_for(event) {
+                    if AssertUnwindSafe(hook(task.clone())).catch_unwind().await.is_err() {
+                        error!("{:?} hook panicked for task {}", event, task.id);
+                    }
+                }
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Operation, TaskData, TaskPriority};
+    use std::time::Duration;
+
+    fn task(id: &str) -> Task {
+        Task::with_id(id.to_string(), "hooked".to_string(), TaskPriority::Medium, TaskData::new(5, Operation::Factorial))
+    }
+
+    #[tokio::test]
+    async fn test_hooks_run_in_order() {
+        let bus = Arc::new(EventBus::new());
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        for event in [TaskEvent::Created, TaskEvent::Processed] {
+            let sender = sender.clone();
+            bus.register(event, move |task: Task| {
+                let sender = sender.clone();
+                async move {
+                    let _ = sender.send((event, task.id));
+                }
+            });
+        }
+        bus.register(TaskEvent::Failed, |_| async { panic!("hook failure") });
+
+        // Events before start are kept
+        bus.emit(TaskEvent::Created, &task("a"));
+        let handle = bus.start().unwrap();
+        assert!(bus.start().is_none());
+        bus.emit(TaskEvent::Failed, &task("a"));
+        bus.emit(TaskEvent::Processed, &task("a"));
+        bus.emit(TaskEvent::Completed, &task("a"));
+
+        let mut received = Vec::new();
+        for _ in 0..2 {
+            let event = tokio::time::timeout(Duration::from_secs(2), receiver.recv()).await.unwrap();
+            received.push(event.unwrap());
+        }
+        assert_eq!(
+            received,
+            vec![(TaskEvent::Created, "a".to_string()), (TaskEvent::Processed, "a".to_string())]
+        );
+        // The panicking hook didn't stop the bus
+        assert!(!handle.is_finished());
+        handle.abort();
+    }
+}
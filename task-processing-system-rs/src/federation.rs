@@ -0,0 +1,373 @@
+//! Forwarding of tasks to peer orchestrators
+//!
+//! With `[[federation.peers]]` configured, `POST /task/create` and
+//! `POST /task/execute` hand a task to a peer instead of rejecting it when its
+//! operation is unknown here or no local worker can take it (all paused, or
+//! storage full). Peers are tried round-robin over their REST API, so a peer
+//! may be another Rust instance or the C++ server. Forwarded tasks are tracked
+//! as remote tasks: `GET /task/{id}` and `POST /task/{id}/complete` are proxied
+//! to the peer holding them, and `GET /admin/federation` lists them. A remote
+//! task is forgotten once its peer reports it finished or gone, and the oldest
+//! are forgotten first beyond `max_remote_tasks`.
+//!
+//! Forwarded requests carry [`FORWARDED_HEADER`] and are never forwarded
+//! again, so two instances naming each other can't pass a task back and forth.
+
+use crate::http_client::{self, HttpClient};
+use crate::types::{FederationConfig, Operation, PeerConfig, TaskStatus};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+use warp::http::Method;
+
+/// Request header marking a task forwarded by another orchestrator
+pub const FORWARDED_HEADER: &str = "x-forwarded-by";
+
+/// Why a task was forwarded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ForwardReason {
+    /// Its operation (or a pipeline stage) isn't one this server calculates
+    UnknownOperation,
+    /// Every worker is paused, or the chosen worker's storage or queue is full
+    NoCapacity,
+}
+
+impl fmt::Display for ForwardReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            ForwardReason::UnknownOperation => "unknown_operation",
+            ForwardReason::NoCapacity => "no_capacity",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// A task held by a peer on behalf of this orchestrator
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteTask {
+    pub id: String,
+    /// Name of the peer holding it
+    pub peer: String,
+    pub namespace: String,
+    pub reason: ForwardReason,
+    pub forwarded_at: DateTime<Utc>,
+}
+
+/// Response of `GET /admin/federation`
+#[derive(Debug, Clone, Serialize)]
+pub struct FederationStatus {
+    pub peers: Vec<PeerConfig>,
+    /// Oldest first
+    pub remote_tasks: Vec<RemoteTask>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum FederationError {
+    #[error("No federation peers are configured")]
+    NoPeers,
+
+    #[error("Peer {peer} unreachable: {message}")]
+    Unreachable { peer: String, message: String },
+
+    #[error("Peer {peer} rejected the task ({status}): {message}")]
+    Rejected { peer: String, status: u16, message: String },
+}
+
+/// The first operation named in a task submission body that isn't an [`Operation`]
+///
+/// Checks `data.operation` and each `data.pipeline` stage; other problems with
+/// the body are left to the usual validation.
+pub fn unknown_operation(body: &Value) -> Option<&str> {
+    let data = body.get("data")?;
+    let pipeline = data.get("pipeline").and_then(Value::as_array).into_iter().flatten();
+    std::iter::once(data.get("operation")?)
+        .chain(pipeline)
+        .filter_map(Value::as_str)
+        .find(|name| serde_json::from_value::<Operation>(Value::String(name.to_string())).is_err())
+}
+
+/// ID a peer assigned in its `POST /task/create` response: `id` from this
+/// server, `task_id` from the C++ one
+fn created_id(response: &Value) -> Option<&str> {
+    response.get("id").or_else(|| response.get("task_id")).and_then(Value::as_str)
+}
+
+fn endpoint(peer: &PeerConfig, path: &str) -> String {
+    format!("{}{}", peer.url.trim_end_matches('/'), path)
+}
+
+/// Whether a peer's answer to `method` on a task means it no longer holds
+/// the task unfinished: gone, deleted, or reported completed, failed or cancelled
+fn finished_on_peer(method: &Method, status: u16, response: &Value) -> bool {
+    if status == 404 {
+        return true;
+    }
+    if !(200..300).contains(&status) {
+        return false;
+    }
+    *method == Method::DELETE
+        || response
+            .get("status")
+            .and_then(|status| serde_json::from_value::<TaskStatus>(status.clone()).ok())
+            .is_some_and(|status| status.is_terminal())
+}
+
+/// Forwards tasks to the configured peers and remembers where each went
+pub struct Federation {
+    peers: Vec<PeerConfig>,
+    client: HttpClient,
+    remote: DashMap<String, RemoteTask>,
+    /// ID and forwarding time of every remote task, oldest first
+    forwarded_order: Mutex<VecDeque<(String, DateTime<Utc>)>>,
+    max_remote_tasks: usize,
+    next_peer: AtomicUsize,
+}
+
+impl Federation {
+    pub fn new(config: &FederationConfig) -> Self {
+        let client = http_client::with_timeout(Duration::from_millis(config.timeout_ms));
+        Self {
+            peers: config.peers.clone(),
+            client,
+            remote: DashMap::new(),
+            forwarded_order: Mutex::new(VecDeque::new()),
+            max_remote_tasks: config.max_remote_tasks,
+            next_peer: AtomicUsize::new(0),
+        }
+    }
+
+    /// Whether any peer is configured
+    pub fn is_enabled(&self) -> bool {
+        !self.peers.is_empty()
+    }
+
+    /// Submit `body` (a `POST /task/create` request with its `id` and
+    /// `namespace` filled in) to the next peer that accepts it
+    pub async fn forward(&self, body: &Value, reason: ForwardReason) -> Result<RemoteTask, FederationError> {
+        let start = self.next_peer.fetch_add(1, Ordering::Relaxed);
+        let mut last_error = FederationError::NoPeers;
+        for offset in 0..self.peers.len() {
+            let peer = &self.peers[(start + offset) % self.peers.len()];
+            match self.send(peer, Method::POST, "/task/create", Some(body)).await {
+                Ok((status, response)) if (200..300).contains(&status) => {
+                    let id = created_id(&response)
+                        .or_else(|| body.get("id").and_then(Value::as_str))
+                        .unwrap_or_default()
+                        .to_string();
+                    let remote = RemoteTask {
+                        id: id.clone(),
+                        peer: peer.name.clone(),
+                        namespace: body.get("namespace").and_then(Value::as_str).unwrap_or_default().to_string(),
+                        reason,
+                        forwarded_at: Utc::now(),
+                    };
+                    info!("Forwarded task {} to peer {} ({})", id, peer.name, reason);
+                    self.remember(remote.clone());
+                    return Ok(remote);
+                }
+                Ok((status, response)) => {
+                    let message = response.get("error").and_then(Value::as_str).unwrap_or_default().to_string();
+                    last_error = FederationError::Rejected {
+                        peer: peer.name.clone(),
+                        status,
+                        message,
+                    };
+                }
+                Err(e) => last_error = e,
+            }
+            warn!("Forwarding to peer {} failed: {}", peer.name, last_error);
+        }
+        Err(last_error)
+    }
+
+    /// Track `remote`, forgetting the oldest remote tasks beyond `max_remote_tasks`
+    fn remember(&self, remote: RemoteTask) {
+        // Held throughout, so concurrent forwards can't overshoot the cap
+        let mut order = self.forwarded_order.lock();
+        order.push_back((remote.id.clone(), remote.forwarded_at));
+        self.remote.insert(remote.id.clone(), remote);
+        while self.max_remote_tasks > 0 && self.remote.len() > self.max_remote_tasks {
+            let Some((oldest, forwarded_at)) = order.pop_front() else {
+                break;
+            };
+            // Entries for tasks forwarded again since are skipped
+            if self.remote.remove_if(&oldest, |_, task| task.forwarded_at == forwarded_at).is_some() {
+                debug!("Forgot remote task {}: over {} remote tasks", oldest, self.max_remote_tasks);
+            }
+        }
+    }
+
+    /// Stop tracking the remote task `task`
+    fn forget(&self, task: &RemoteTask) {
+        let mut order = self.forwarded_order.lock();
+        if self.remote.remove_if(&task.id, |_, remote| remote.forwarded_at == task.forwarded_at).is_some() {
+            order.retain(|(id, _)| *id != task.id);
+            debug!("Forgot remote task {}: finished or gone on peer {}", task.id, task.peer);
+        }
+    }
+
+    /// The forwarded task with `id`, if any
+    pub fn remote_task(&self, id: &str) -> Option<RemoteTask> {
+        self.remote.get(id).map(|entry| entry.value().clone())
+    }
+
+    /// Every forwarded task, oldest first
+    pub fn remote_tasks(&self) -> Vec<RemoteTask> {
+        let mut tasks: Vec<RemoteTask> = self.remote.iter().map(|entry| entry.value().clone()).collect();
+        tasks.sort_by(|a, b| a.forwarded_at.cmp(&b.forwarded_at).then_with(|| a.id.cmp(&b.id)));
+        tasks
+    }
+
+    pub fn status(&self) -> FederationStatus {
+        FederationStatus {
+            peers: self.peers.clone(),
+            remote_tasks: self.remote_tasks(),
+        }
+    }
+
+    /// Send `method path` to the peer holding `task`, returning its status and JSON body
+    ///
+    /// The task is forgotten once the answer shows it finished or gone on the peer.
+    pub async fn proxy(&self, task: &RemoteTask, method: Method, path: &str) -> Result<(u16, Value), FederationError> {
+        let peer = self
+            .peers
+            .iter()
+            .find(|peer| peer.name == task.peer)
+            .ok_or(FederationError::NoPeers)?;
+        let (status, response) = self.send(peer, method.clone(), path, None).await?;
+        if finished_on_peer(&method, status, &response) {
+            self.forget(task);
+        }
+        Ok((status, response))
+    }
+
+    #[cfg(feature = "client")]
+    async fn send(&self, peer: &PeerConfig, method: Method, path: &str, body: Option<&Value>) -> Result<(u16, Value), FederationError> {
+        // reqwest has its own `Method`, from a newer `http` than warp's
+        let method = reqwest::Method::from_bytes(method.as_str().as_bytes()).unwrap_or(reqwest::Method::GET);
+        let mut request = self
+            .client
+            .request(method, endpoint(peer, path))
+            .header(FORWARDED_HEADER, "task-processor");
+        if let Some(key) = &peer.api_key {
+            request = request.bearer_auth(key);
+        }
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        let unreachable = |e: reqwest::Error| FederationError::Unreachable {
+            peer: peer.name.clone(),
+            message: e.to_string(),
+        };
+        let response = request.send().await.map_err(unreachable)?;
+        let status = response.status().as_u16();
+        let body = response.json().await.unwrap_or(Value::Null);
+        Ok((status, body))
+    }
+
+    #[cfg(not(feature = "client"))]
+    async fn send(&self, peer: &PeerConfig, _method: Method, _path: &str, _body: Option<&Value>) -> Result<(u16, Value), FederationError> {
+        Err(FederationError::Unreachable {
+            peer: peer.name.clone(),
+            message: http_client::UNAVAILABLE.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_unknown_operation() {
+        let body = |data: Value| json!({"title": "t", "data": data});
+        assert_eq!(unknown_operation(&body(json!({"input": 4, "operation": "factorial"}))), None);
+        assert_eq!(unknown_operation(&body(json!({"input": 4, "operation": "sqrt"}))), Some("sqrt"));
+        let pipeline = body(json!({"input": 4, "operation": "factorial", "pipeline": ["prime_check", "cube"]}));
+        assert_eq!(unknown_operation(&pipeline), Some("cube"));
+        // Missing or mistyped fields are ordinary validation errors
+        assert_eq!(unknown_operation(&body(json!({"input": 4}))), None);
+        assert_eq!(unknown_operation(&body(json!({"input": 4, "operation": 7}))), None);
+    }
+
+    #[test]
+    fn test_created_id() {
+        assert_eq!(created_id(&json!({"id": "rs-1", "status": "pending"})), Some("rs-1"));
+        assert_eq!(created_id(&json!({"task_id": "cpp-1", "status": "pending"})), Some("cpp-1"));
+        assert_eq!(created_id(&json!({"message": "ok"})), None);
+    }
+
+    #[tokio::test]
+    async fn test_forward_without_reachable_peers() {
+        let federation = Federation::new(&FederationConfig::default());
+        assert!(!federation.is_enabled());
+        assert!(matches!(
+            federation.forward(&json!({"id": "t"}), ForwardReason::NoCapacity).await,
+            Err(FederationError::NoPeers)
+        ));
+
+        // Nothing listens on port 9 (discard)
+        let federation = Federation::new(&FederationConfig {
+            peers: vec![PeerConfig {
+                name: "gone".to_string(),
+                url: "http://127.0.0.1:9".to_string(),
+                api_key: None,
+            }],
+            timeout_ms: 500,
+            ..Default::default()
+        });
+        let error = federation.forward(&json!({"id": "t"}), ForwardReason::NoCapacity).await.unwrap_err();
+        assert!(matches!(error, FederationError::Unreachable { .. }), "{}", error);
+        assert!(federation.remote_tasks().is_empty());
+    }
+
+    fn remote(id: &str, seconds: i64) -> RemoteTask {
+        RemoteTask {
+            id: id.to_string(),
+            peer: "cpp".to_string(),
+            namespace: "default".to_string(),
+            reason: ForwardReason::NoCapacity,
+            forwarded_at: DateTime::from_timestamp(seconds, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_remote_tasks_are_capped() {
+        let federation = Federation::new(&FederationConfig {
+            max_remote_tasks: 2,
+            ..Default::default()
+        });
+        federation.remember(remote("a", 1));
+        federation.remember(remote("b", 2));
+        federation.remember(remote("c", 3));
+        let ids = |federation: &Federation| federation.remote_tasks().into_iter().map(|task| task.id).collect::<Vec<_>>();
+        assert_eq!(ids(&federation), ["b", "c"]);
+
+        federation.forget(&remote("b", 2));
+        assert_eq!(ids(&federation), ["c"]);
+        // Forwarded again since: the newer record stays
+        federation.remember(remote("c", 4));
+        federation.forget(&remote("c", 3));
+        assert_eq!(federation.remote_task("c").unwrap().forwarded_at.timestamp(), 4);
+    }
+
+    #[test]
+    fn test_finished_on_peer() {
+        assert!(finished_on_peer(&Method::GET, 404, &Value::Null));
+        assert!(finished_on_peer(&Method::GET, 200, &json!({"id": "t", "status": "completed"})));
+        assert!(finished_on_peer(&Method::POST, 200, &json!({"id": "t", "status": "cancelled"})));
+        assert!(finished_on_peer(&Method::DELETE, 200, &json!({"id": "t", "status": "pending"})));
+        assert!(!finished_on_peer(&Method::GET, 200, &json!({"id": "t", "status": "processing"})));
+        assert!(!finished_on_peer(&Method::POST, 409, &json!({"error": "not completable"})));
+        assert!(!finished_on_peer(&Method::GET, 502, &Value::Null));
+    }
+}
@@ -0,0 +1,138 @@
+//! `?fields=` projection of task responses
+//!
+//! `GET /task/{id}` and `GET /tasks` accept `?fields=id,status,result` to
+//! return only those top-level fields of each task, for dashboards polling
+//! many tasks. Fields a task doesn't have yet (e.g. `result` while pending)
+//! are left out as usual; names that aren't task fields are rejected.
+
+use crate::types::ValidationError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+/// Top-level fields of a serialized `Task`
+pub const TASK_FIELDS: [&str; 19] = [
+    "id",
+    "title",
+    "priority",
+    "namespace",
+    "created_at",
+    "data",
+    "status",
+    "result",
+    "error_message",
+    "completed_at",
+    "algorithm",
+    "stages",
+    "expected_result",
+    "matched",
+    "result_signature",
+    "queue_position",
+    "worker_id",
+    "started_at",
+    "processing_duration_ms",
+];
+
+#[derive(Debug, Default, Deserialize)]
+pub struct FieldsQuery {
+    /// Comma-separated field names (all fields when unset)
+    #[serde(default)]
+    pub fields: Option<String>,
+}
+
+/// Fields to keep in a response; `None` keeps everything
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FieldSelection {
+    fields: Option<BTreeSet<String>>,
+}
+
+impl FieldSelection {
+    /// Parse `id,status,result`, rejecting names that aren't task fields
+    pub fn parse(fields: Option<&str>) -> Result<Self, ValidationError> {
+        let Some(fields) = fields else {
+            return Ok(Self::default());
+        };
+        let mut selected = BTreeSet::new();
+        for field in fields.split(',').map(str::trim).filter(|field| !field.is_empty()) {
+            if !TASK_FIELDS.contains(&field) {
+                return Err(ValidationError::UnknownField(field.to_string()));
+            }
+            selected.insert(field.to_string());
+        }
+        if selected.is_empty() {
+            return Err(ValidationError::UnknownField(fields.to_string()));
+        }
+        Ok(Self { fields: Some(selected) })
+    }
+
+    /// `value` serialized, keeping only the selected fields of the object
+    pub fn apply<T: Serialize>(&self, value: &T) -> Value {
+        let mut value = serde_json::to_value(value).unwrap_or(Value::Null);
+        if let (Some(fields), Value::Object(object)) = (&self.fields, &mut value) {
+            object.retain(|name, _| fields.contains(name));
+        }
+        value
+    }
+
+    /// Each of `values` with [`apply`](Self::apply)
+    pub fn apply_all<T: Serialize>(&self, values: &[T]) -> Value {
+        Value::Array(values.iter().map(|value| self.apply(value)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Operation, Task, TaskData, TaskPriority};
+    use std::collections::BTreeSet;
+
+    fn task() -> Task {
+        let mut task = Task::with_id("t-1".to_string(), "Fields".to_string(), TaskPriority::Low, TaskData::new(5, Operation::Factorial));
+        task.set_processing("120".to_string());
+        task
+    }
+
+    #[test]
+    fn test_selects_fields() {
+        let selection = FieldSelection::parse(Some("id, status,result")).unwrap();
+        let json = selection.apply(&task());
+        assert_eq!(json, serde_json::json!({"id": "t-1", "status": "processing", "result": "120"}));
+
+        // Missing optional fields stay missing
+        let json = FieldSelection::parse(Some("id,error_message")).unwrap().apply(&task());
+        assert_eq!(json, serde_json::json!({"id": "t-1"}));
+
+        let all = FieldSelection::parse(None).unwrap().apply_all(&[task()]);
+        assert_eq!(all[0]["title"], "Fields");
+    }
+
+    #[test]
+    fn test_rejects_unknown_fields() {
+        assert!(matches!(
+            FieldSelection::parse(Some("id,colour")),
+            Err(ValidationError::UnknownField(field)) if field == "colour"
+        ));
+        assert!(FieldSelection::parse(Some(",")).is_err());
+    }
+
+    #[test]
+    fn test_known_fields_match_task() {
+        let mut task = task();
+        task.error_message = Some("none".to_string());
+        task.completed_at = Some(task.created_at);
+        task.algorithm = Some(crate::types::FibonacciAlgorithm::Iterative);
+        task.expected_result = Some("120".to_string());
+        task.matched = Some(true);
+        task.result_signature = Some("sig".to_string());
+        task.queue_position = Some(1);
+        task.worker_id = Some(0);
+        task.started_at = Some(task.created_at);
+        task.processing_duration_ms = Some(3);
+        let serialized: BTreeSet<String> = serde_json::to_value(&task).unwrap().as_object().unwrap().keys().cloned().collect();
+        let known: BTreeSet<String> = TASK_FIELDS.iter().map(|field| field.to_string()).collect();
+        // `stages` is only written for pipelines
+        let missing: Vec<&String> = known.difference(&serialized).collect();
+        assert_eq!(missing, vec!["stages"]);
+        assert!(serialized.is_subset(&known));
+    }
+}
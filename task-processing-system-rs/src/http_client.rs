@@ -0,0 +1,30 @@
+//! HTTP client behind the server's outgoing requests
+//!
+//! Webhooks, callbacks and federation send their requests with an
+//! [`HttpClient`]: a `reqwest::Client` with the `client` feature (on by
+//! default). Built without it, there is no HTTP client and every outgoing
+//! request fails with [`UNAVAILABLE`], so the server runs without them.
+
+use std::time::Duration;
+
+/// Why an outgoing request failed when built without the `client` feature
+pub const UNAVAILABLE: &str = "built without the client feature, so no HTTP requests can be sent";
+
+#[cfg(feature = "client")]
+pub type HttpClient = reqwest::Client;
+
+/// Stand-in for the HTTP client when built without the `client` feature
+#[cfg(not(feature = "client"))]
+#[derive(Debug, Clone, Default)]
+pub struct HttpClient;
+
+/// Client whose requests give up after `timeout`
+#[cfg(feature = "client")]
+pub fn with_timeout(timeout: Duration) -> HttpClient {
+    reqwest::Client::builder().timeout(timeout).build().unwrap_or_default()
+}
+
+#[cfg(not(feature = "client"))]
+pub fn with_timeout(_timeout: Duration) -> HttpClient {
+    HttpClient
+}
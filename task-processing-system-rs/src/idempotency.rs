@@ -0,0 +1,133 @@
+//! Idempotency keys for `POST /task/create`
+//!
+//! A client sends the same `Idempotency-Key` header (or `idempotency_key`
+//! field) when it retries a submission. The first request with a key claims
+//! it for the task it creates; repeats within `idempotency_window_seconds` get
+//! that task back instead of creating another one. Keys are scoped to the
+//! task's namespace, and only the key is compared, not the rest of the body.
+
+use crate::types::ValidationError;
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use std::time::{Duration, Instant};
+
+/// Longest key accepted
+pub const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+/// Request header carrying the key
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Response header set when a reply is for an earlier request with the same key
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// Expired keys are swept at most this often
+const PRUNE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Keys claimed within the window, with the task each one created
+pub struct IdempotencyKeys {
+    window: Duration,
+    /// `(namespace, key)` to the task id and when it was claimed
+    claimed: DashMap<(String, String), (String, Instant)>,
+    last_prune: Mutex<Instant>,
+}
+
+impl IdempotencyKeys {
+    /// Remember keys for `window` (zero turns idempotency keys off)
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            claimed: DashMap::new(),
+            last_prune: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.window.is_zero()
+    }
+
+    /// Claim `key` for the task `task_id` is about to create, or the id of the
+    /// task that already holds it
+    pub fn claim(&self, namespace: &str, key: &str, task_id: &str) -> Option<String> {
+        let now = Instant::now();
+        self.prune(now);
+        match self.claimed.entry((namespace.to_string(), key.to_string())) {
+            Entry::Occupied(entry) if now.duration_since(entry.get().1) < self.window => Some(entry.get().0.clone()),
+            Entry::Occupied(mut entry) => {
+                entry.insert((task_id.to_string(), now));
+                None
+            }
+            Entry::Vacant(entry) => {
+                entry.insert((task_id.to_string(), now));
+                None
+            }
+        }
+    }
+
+    /// Give up the claim `task_id` holds on `key` (its submission failed), so
+    /// a retry can use the key again
+    pub fn release(&self, namespace: &str, key: &str, task_id: &str) {
+        self.claimed
+            .remove_if(&(namespace.to_string(), key.to_string()), |_, (claimed_by, _)| claimed_by == task_id);
+    }
+
+    fn prune(&self, now: Instant) {
+        {
+            let mut last_prune = self.last_prune.lock();
+            if now.duration_since(*last_prune) < PRUNE_INTERVAL {
+                return;
+            }
+            *last_prune = now;
+        }
+        self.claimed.retain(|_, (_, claimed_at)| now.duration_since(*claimed_at) < self.window);
+    }
+}
+
+/// Check a key's length: 1 to [`MAX_IDEMPOTENCY_KEY_LEN`] characters
+pub fn validate_key(key: &str) -> Result<(), ValidationError> {
+    if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LEN {
+        return Err(ValidationError::InvalidIdempotencyKey { max_len: MAX_IDEMPOTENCY_KEY_LEN });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_claim_and_repeat() {
+        let keys = IdempotencyKeys::new(Duration::from_secs(60));
+        assert_eq!(keys.claim("default", "order-1", "task-a"), None);
+        assert_eq!(keys.claim("default", "order-1", "task-b").as_deref(), Some("task-a"));
+        // Scoped to the namespace
+        assert_eq!(keys.claim("team-b", "order-1", "task-c"), None);
+        assert_eq!(keys.claim("team-b", "order-1", "task-d").as_deref(), Some("task-c"));
+    }
+
+    #[test]
+    fn test_release_only_by_holder() {
+        let keys = IdempotencyKeys::new(Duration::from_secs(60));
+        keys.claim("default", "order-1", "task-a");
+        keys.release("default", "order-1", "task-b");
+        assert_eq!(keys.claim("default", "order-1", "task-b").as_deref(), Some("task-a"));
+        keys.release("default", "order-1", "task-a");
+        assert_eq!(keys.claim("default", "order-1", "task-b"), None);
+    }
+
+    #[test]
+    fn test_keys_expire() {
+        let keys = IdempotencyKeys::new(Duration::from_millis(20));
+        keys.claim("default", "order-1", "task-a");
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(keys.claim("default", "order-1", "task-b"), None);
+        assert_eq!(keys.claim("default", "order-1", "task-c").as_deref(), Some("task-b"));
+    }
+
+    #[test]
+    fn test_validate_key() {
+        assert!(validate_key("order-1").is_ok());
+        assert!(validate_key("").is_err());
+        assert!(validate_key(&"k".repeat(MAX_IDEMPOTENCY_KEY_LEN + 1)).is_err());
+    }
+}
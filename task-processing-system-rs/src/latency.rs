@@ -0,0 +1,214 @@
+//! Task latency percentiles backed by HDR histograms
+//!
+//! Each worker records how long its tasks took from creation until the result
+//! was ready. Histograms merge exactly, so the orchestrator reports system-wide
+//! percentiles by adding the workers' histograms instead of averaging their
+//! summaries. Calculation time alone is also kept per operation, so a slow
+//! operation doesn't hide behind fast ones.
+
+use hdrhistogram::Histogram;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Significant decimal digits kept per recorded value (0.1% relative error)
+const SIGNIFICANT_DIGITS: u8 = 3;
+
+/// Latency histogram in microseconds, shared by a worker's processing threads
+#[derive(Debug)]
+pub struct LatencyHistogram {
+    histogram: Mutex<Histogram<u64>>,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            histogram: Mutex::new(empty_histogram()),
+        }
+    }
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, latency: Duration) {
+        // Auto-resizing histograms grow to fit any value, so recording can't fail
+        let _ = self.histogram.lock().record(micros(latency));
+    }
+
+    /// Copy of the recorded values, for merging
+    pub fn snapshot(&self) -> Histogram<u64> {
+        self.histogram.lock().clone()
+    }
+
+    pub fn summary(&self) -> LatencySummary {
+        LatencySummary::from_histogram(&self.histogram.lock())
+    }
+}
+
+/// Calculation-time histograms by operation name, shared by a worker's processing threads
+#[derive(Debug, Default)]
+pub struct OperationLatencies {
+    histograms: Mutex<BTreeMap<String, Histogram<u64>>>,
+}
+
+impl OperationLatencies {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, operation: &str, duration: Duration) {
+        let mut histograms = self.histograms.lock();
+        let histogram = histograms.entry(operation.to_string()).or_insert_with(empty_histogram);
+        let _ = histogram.record(micros(duration));
+    }
+
+    /// Copy of each operation's recorded values, for merging
+    pub fn snapshot(&self) -> BTreeMap<String, Histogram<u64>> {
+        self.histograms.lock().clone()
+    }
+
+    pub fn summaries(&self) -> BTreeMap<String, LatencySummary> {
+        summarize(&self.histograms.lock())
+    }
+}
+
+/// Add several sets of per-operation histograms, operation by operation
+pub fn merge_by_operation(
+    sets: impl IntoIterator<Item = BTreeMap<String, Histogram<u64>>>,
+) -> BTreeMap<String, Histogram<u64>> {
+    let mut merged: BTreeMap<String, Histogram<u64>> = BTreeMap::new();
+    for set in sets {
+        for (operation, histogram) in set {
+            let into = merged.entry(operation).or_insert_with(empty_histogram);
+            into.add(&histogram).expect("auto-resizing histogram");
+        }
+    }
+    merged
+}
+
+/// Percentiles of each operation's histogram
+pub fn summarize(histograms: &BTreeMap<String, Histogram<u64>>) -> BTreeMap<String, LatencySummary> {
+    histograms
+        .iter()
+        .map(|(operation, histogram)| (operation.clone(), LatencySummary::from_histogram(histogram)))
+        .collect()
+}
+
+fn micros(duration: Duration) -> u64 {
+    u64::try_from(duration.as_micros()).unwrap_or(u64::MAX)
+}
+
+fn empty_histogram() -> Histogram<u64> {
+    Histogram::new(SIGNIFICANT_DIGITS).expect("3 significant digits is a valid precision")
+}
+
+/// Add several histograms into one
+pub fn merge(histograms: impl IntoIterator<Item = Histogram<u64>>) -> Histogram<u64> {
+    let mut merged = empty_histogram();
+    for histogram in histograms {
+        // Auto-resizing histograms accept any value range
+        merged.add(&histogram).expect("auto-resizing histogram");
+    }
+    merged
+}
+
+/// Latency percentiles in milliseconds
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencySummary {
+    pub count: u64,
+    pub min_ms: f64,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    #[serde(default)]
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub p999_ms: f64,
+    pub max_ms: f64,
+}
+
+impl LatencySummary {
+    pub fn from_histogram(histogram: &Histogram<u64>) -> Self {
+        if histogram.is_empty() {
+            return Self::default();
+        }
+        let ms = |micros: u64| micros as f64 / 1000.0;
+        Self {
+            count: histogram.len(),
+            min_ms: ms(histogram.min()),
+            mean_ms: histogram.mean() / 1000.0,
+            p50_ms: ms(histogram.value_at_quantile(0.50)),
+            p90_ms: ms(histogram.value_at_quantile(0.90)),
+            p95_ms: ms(histogram.value_at_quantile(0.95)),
+            p99_ms: ms(histogram.value_at_quantile(0.99)),
+            p999_ms: ms(histogram.value_at_quantile(0.999)),
+            max_ms: ms(histogram.max()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_summary() {
+        assert_eq!(LatencyHistogram::new().summary(), LatencySummary::default());
+    }
+
+    #[test]
+    fn test_percentiles_under_skew() {
+        // 99 fast tasks and one very slow one: the mean hides the tail, p99/max don't
+        let histogram = LatencyHistogram::new();
+        for _ in 0..99 {
+            histogram.record(Duration::from_millis(1));
+        }
+        histogram.record(Duration::from_secs(2));
+
+        let summary = histogram.summary();
+        assert_eq!(summary.count, 100);
+        assert!((summary.p50_ms - 1.0).abs() < 0.01);
+        assert!((summary.p99_ms - 1.0).abs() < 0.01);
+        assert!((summary.max_ms / 2000.0 - 1.0).abs() < 0.002);
+        assert!(summary.mean_ms > 20.0);
+    }
+
+    #[test]
+    fn test_merge_matches_single_histogram() {
+        let (fast, slow, combined) = (LatencyHistogram::new(), LatencyHistogram::new(), LatencyHistogram::new());
+        for ms in 1..=50 {
+            fast.record(Duration::from_millis(ms));
+            combined.record(Duration::from_millis(ms));
+        }
+        for ms in 500..=549 {
+            slow.record(Duration::from_millis(ms));
+            combined.record(Duration::from_millis(ms));
+        }
+
+        let merged = LatencySummary::from_histogram(&merge([fast.snapshot(), slow.snapshot()]));
+        assert_eq!(merged, combined.summary());
+        assert_eq!(merged.count, 100);
+    }
+
+    #[test]
+    fn test_operations_kept_apart() {
+        let (a, b) = (OperationLatencies::new(), OperationLatencies::new());
+        for ms in 1..=100 {
+            a.record("factorial", Duration::from_millis(ms));
+        }
+        b.record("factorial", Duration::from_millis(1));
+        b.record("prime_check", Duration::from_secs(1));
+
+        let summaries = a.summaries();
+        assert_eq!(summaries.len(), 1);
+        assert!((summaries["factorial"].p95_ms - 95.0).abs() < 0.1);
+
+        let merged = summarize(&merge_by_operation([a.snapshot(), b.snapshot()]));
+        assert_eq!((merged["factorial"].count, merged["prime_check"].count), (101, 1));
+        assert!((merged["prime_check"].min_ms - 1000.0).abs() < 1.0);
+    }
+}
@@ -5,7 +5,7 @@
 //!
 //! ## Features
 //!
-//! - **Mathematical Operations**: Supports factorial, fibonacci, and prime_check calculations  
+//! - **Mathematical Operations**: Supports factorial, fibonacci, prime_check, generate_prime, gcd and lcm calculations
 //! - **Multi-threaded Workers**: Configurable number of workers and threads per worker
 //! - **Round-Robin Load Balancing**: Distributes tasks across workers
 //! - **REST API**: Complete HTTP API for task management
@@ -35,15 +35,80 @@
 //! - **Types**: Core data structures and error types
 
 #![allow(warnings)]
+// The config schema is one large `json!` literal
+#![recursion_limit = "256"]
+pub mod admin;
+pub mod analytics;
+pub mod api_examples;
+pub mod api_schemas;
+pub mod audit;
+pub mod auth;
+pub mod benchmark;
+pub mod calc_executor;
 pub mod calculations;
+pub mod callbacks;
+pub mod changes;
+pub mod cli_error;
+#[cfg(feature = "client")]
+pub mod client;
+pub mod config_schema;
+pub mod dependencies;
+pub mod diagnostics;
+pub mod events;
+pub mod federation;
+pub mod field_selection;
+pub mod http_client;
+pub mod idempotency;
+pub mod latency;
+pub mod listeners;
+pub mod live_events;
+pub mod load_balancer;
+pub mod math_utils;
+pub mod metrics;
+pub mod migration;
+pub mod openapi;
 pub mod orchestrator;
+pub mod output;
+pub mod probes;
+pub mod query;
+pub mod rate_limit;
+pub mod result_stream;
+pub mod results;
+pub mod runtime_metrics;
+pub mod scheduler;
+pub mod service;
+pub mod signing;
+pub mod sizing;
+pub mod stats_cache;
+pub mod stats_checkpoint;
+pub mod stats_delta;
+pub mod subscriptions;
+pub mod supervisor;
+pub mod table;
+pub mod task_index;
+pub mod task_queue;
+pub mod task_store;
+pub mod test_server;
+pub mod timestamps;
 pub mod types;
+pub mod validation;
+pub mod watchdog;
+pub mod webhooks;
+pub mod work_stealing;
 pub mod worker;
 
 // Re-export main types for convenience
 pub use calculations::Calculator;
+#[cfg(feature = "client")]
+pub use client::TaskClient;
+pub use events::TaskEvent;
+pub use metrics::{MetricsSink, NoopSink, PrometheusSink, StatsdSink};
 pub use orchestrator::TaskOrchestrator;
+pub use results::ResultValue;
+pub use signing::ResultSigner;
+pub use test_server::TestServer;
 pub use types::*;
+pub use validation::{TaskValidator, ValidatorChain};
 pub use worker::Worker;
 
 /// Result type alias for system operations
@@ -58,28 +123,28 @@ pub type ValidationResult<T> = Result<T, ValidationError>;
 #[cfg(test)]
 mod integration_tests {
     use super::*;
-    use tokio::time::{sleep, Duration};
+    use std::collections::BTreeMap;
+    use tokio::time::Duration;
 
     fn create_test_config() -> OrchestratorConfig {
         OrchestratorConfig {
             num_workers: 2,
             threads_per_worker: 2,
-            orchestrator_port: 19999,
+            orchestrator_port: 0,
+            ..OrchestratorConfig::default()
         }
     }
 
     #[tokio::test]
     async fn test_system_integration() {
         let config = create_test_config();
-        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        let orchestrator = std::sync::Arc::new(TaskOrchestrator::new(config).unwrap());
 
-        // Start orchestrator
-        let orchestrator_handle = tokio::spawn(async move {
-            orchestrator.start().await
-        });
-
-        // Give system time to start
-        sleep(Duration::from_millis(100)).await;
+        // Start orchestrator in the background, as an embedder would
+        let starting = std::sync::Arc::clone(&orchestrator);
+        let orchestrator_handle = tokio::spawn(async move { starting.start().await });
+        orchestrator.wait_ready(Duration::from_secs(5)).await.unwrap();
+        assert!(orchestrator.bound_addr().is_some());
 
         // Test creating a task
         let request = CreateTaskRequest {
@@ -87,23 +152,44 @@ mod integration_tests {
             title: "Integration Test".to_string(),
             priority: TaskPriority::High,
             data: TaskData::new(5, Operation::Factorial),
+            expected_result: None,
+            namespace: None,
+            callback_url: None,
+            run_at: None,
+            delay_seconds: None,
+            idempotency_key: None,
+            depends_on: Vec::new(),
+            labels: BTreeMap::new(),
+            timeout_ms: None,
         };
-        orchestrator_handle.abort();
+        assert_eq!(orchestrator.create_task(request).await.unwrap(), "integration-test-001");
+
+        orchestrator_handle.await.unwrap().unwrap();
+        orchestrator.stop().await;
+        assert!(!orchestrator.is_ready());
+    }
+
+    #[tokio::test]
+    async fn test_wait_ready_times_out() {
+        let orchestrator = TaskOrchestrator::new(create_test_config()).unwrap();
+        let error = orchestrator.wait_ready(Duration::from_millis(20)).await.unwrap_err();
+        assert!(error.to_string().contains("not ready"), "{}", error);
     }
 
     #[test]
     fn test_calculation_correctness() {
+        let calculator = Calculator::default();
         // Test all supported operations
         assert_eq!(
-            Calculator::calculate(Operation::Factorial, 5).unwrap(),
+            calculator.calculate(Operation::Factorial, 5).unwrap(),
             "120"
         );
         assert_eq!(
-            Calculator::calculate(Operation::Fibonacci, 10).unwrap(), 
+            calculator.calculate(Operation::Fibonacci, 10).unwrap(), 
             "55"
         );
         assert_eq!(
-            Calculator::calculate(Operation::PrimeCheck, 17).unwrap(),
+            calculator.calculate(Operation::PrimeCheck, 17).unwrap(),
             "true"
         );
     }
@@ -132,6 +218,14 @@ mod integration_tests {
         assert!(task.completed_at.is_some());
     }
 
+    #[test]
+    fn test_cache_control_by_status() {
+        assert_eq!(TaskStatus::Pending.cache_control(), "no-store");
+        assert_eq!(TaskStatus::Processing.cache_control(), "no-store");
+        assert_eq!(TaskStatus::Completed.cache_control(), "private, max-age=60");
+        assert_eq!(TaskStatus::Failed.cache_control(), "no-cache");
+    }
+
     #[test]
     fn test_priority_ordering() {
         let high = TaskPriority::High;
@@ -145,20 +239,125 @@ mod integration_tests {
 
     #[test]
     fn test_error_handling() {
+        let calculator = Calculator::default();
         // Test validation errors
         let invalid_task_data = TaskData {
             task_type: "invalid".to_string(),
             input: 10,
+            inputs: Vec::new(),
+            parallel: false,
+            input2: None,
             operation: Operation::Factorial,
+            algorithm: None,
+            prime_check_algorithm: None,
+            pipeline: Vec::new(),
+            precision: Precision::Native,
+            max_retries: 0,
+            retry_backoff_ms: 500,
         };
         
-        assert!(invalid_task_data.validate().is_err());
+        assert!(invalid_task_data.validate(&calculator).is_err());
 
         // Test calculation errors
-        let result = Calculator::calculate(Operation::Factorial, 25);
+        let result = calculator.calculate(Operation::Factorial, 25);
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_big_precision_validation() {
+        let calculator = Calculator::default();
+        let big = |input, operation| TaskData::new(input, operation).with_precision(Precision::Big);
+        assert!(big(25, Operation::Factorial).validate(&calculator).is_ok());
+        assert!(big(4000, Operation::Fibonacci).validate(&calculator).is_ok());
+        // Limited by the default of 1000 result digits
+        assert!(matches!(
+            big(1000, Operation::Factorial).validate(&calculator),
+            Err(ValidationError::TooManyDigits { max_digits: 1000, .. })
+        ));
+        assert!(matches!(
+            big(17, Operation::PrimeCheck).validate(&calculator),
+            Err(ValidationError::PrecisionNotSupported(Operation::PrimeCheck))
+        ));
+        // A big-precision stage later in the pipeline is enough
+        assert!(big(4, Operation::PrimeCheck).with_pipeline(vec![Operation::Factorial]).validate(&calculator).is_ok());
+    }
+
+    #[test]
+    fn test_second_input_validation() {
+        let calculator = Calculator::default();
+        assert!(TaskData::new(12, Operation::Gcd).with_input2(18).validate(&calculator).is_ok());
+        assert!(matches!(
+            TaskData::new(12, Operation::Lcm).validate(&calculator),
+            Err(ValidationError::MissingSecondInput(Operation::Lcm))
+        ));
+        assert!(matches!(
+            TaskData::new(5, Operation::Factorial).with_input2(3).validate(&calculator),
+            Err(ValidationError::UnexpectedSecondInput(Operation::Factorial))
+        ));
+        // A gcd stage later in the pipeline takes input2 as well
+        let pipeline = TaskData::new(5, Operation::Factorial).with_pipeline(vec![Operation::Gcd]);
+        assert!(matches!(
+            pipeline.clone().validate(&calculator),
+            Err(ValidationError::MissingSecondInput(Operation::Gcd))
+        ));
+        assert!(pipeline.with_input2(36).validate(&calculator).is_ok());
+    }
+
+    #[test]
+    fn test_inputs_validation() {
+        let calculator = Calculator::default();
+        let batch = TaskData::batch(vec![2, 3, 4], Operation::PrimeCheck);
+        assert_eq!(batch.all_inputs(), &[2, 3, 4]);
+        assert!(batch.clone().in_parallel().validate(&calculator).is_ok());
+        assert_eq!(TaskData::new(7, Operation::PrimeCheck).all_inputs(), &[7]);
+        // Every input is checked against the operation's range
+        assert!(matches!(
+            TaskData::batch(vec![5, 21], Operation::Factorial).validate(&calculator),
+            Err(ValidationError::InputTooLarge { input: 21, .. })
+        ));
+        assert!(matches!(
+            TaskData::batch(vec![1; MAX_TASK_INPUTS + 1], Operation::PrimeCheck).validate(&calculator),
+            Err(ValidationError::TooManyInputs { .. })
+        ));
+        assert!(matches!(
+            batch.with_pipeline(vec![Operation::Factorial]).validate(&calculator),
+            Err(ValidationError::InputsWithPipeline)
+        ));
+        assert!(matches!(
+            TaskData::new(7, Operation::PrimeCheck).in_parallel().validate(&calculator),
+            Err(ValidationError::ParallelWithoutInputs)
+        ));
+        // input may be left out when inputs are given
+        let parsed: TaskData =
+            serde_json::from_str(r#"{"type": "calculation", "inputs": [10, 20], "operation": "fibonacci"}"#).unwrap();
+        assert_eq!(parsed.all_inputs(), &[10, 20]);
+    }
+
+    #[test]
+    fn test_label_validation() {
+        let calculator = Calculator::default();
+        let mut task = Task::new("Labelled".to_string(), TaskPriority::Low, TaskData::new(5, Operation::Factorial));
+        task.labels = BTreeMap::from([("team".to_string(), "payments".to_string()), ("k8s.io/zone".to_string(), String::new())]);
+        assert!(task.validate(&calculator).is_ok());
+        assert!(task.has_labels(&[("team".to_string(), "payments".to_string())]));
+        assert!(!task.has_labels(&[("team".to_string(), "search".to_string())]));
+
+        for (key, value) in [("", "x"), ("has space", "x"), ("team", "a,b")] {
+            let mut bad = task.clone();
+            bad.labels.insert(key.to_string(), value.to_string());
+            assert!(matches!(bad.validate(&calculator), Err(ValidationError::InvalidLabel(_))), "{key}={value}");
+        }
+        task.labels = (0..=MAX_TASK_LABELS).map(|i| (format!("k{}", i), String::new())).collect();
+        assert!(matches!(task.validate(&calculator), Err(ValidationError::TooManyLabels { .. })));
+
+        assert_eq!(
+            parse_label_selector("team:payments, env:prod"),
+            Some(vec![("team".to_string(), "payments".to_string()), ("env".to_string(), "prod".to_string())])
+        );
+        assert_eq!(parse_label_selector("team"), None);
+        assert_eq!(parse_label_selector(":x"), None);
+    }
+
     #[test]
     fn test_configuration_validation() {
         // Valid config
@@ -166,6 +365,7 @@ mod integration_tests {
             num_workers: 3,
             threads_per_worker: 4,
             orchestrator_port: 7000,
+            ..OrchestratorConfig::default()
         };
         assert!(valid_config.validate().is_ok());
 
@@ -0,0 +1,216 @@
+//! Binding the API on its listen addresses (`orchestrator_port` plus `[[listeners]]`)
+//!
+//! Every listener serves the same routes. Plain listeners go straight through
+//! warp; TLS listeners accept and handshake connections here and hand the
+//! encrypted streams to warp.
+
+use crate::types::{ListenerConfig, SystemError, TlsConfig};
+use futures::stream;
+use rustls_pki_types::pem::PemObject;
+use rustls_pki_types::{CertificateDer, PrivateKeyDer};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_rustls::rustls::{self, crypto::ring};
+use tokio_rustls::TlsAcceptor;
+use tracing::{debug, info, warn};
+use warp::filters::BoxedFilter;
+use warp::reply::Response;
+
+/// The full API, shared by every listener
+pub type ApiRoutes = BoxedFilter<(Response,)>;
+
+/// Clients that haven't finished the TLS handshake by then are dropped
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Handshaken connections waiting for warp to pick them up
+const ACCEPT_BACKLOG: usize = 64;
+
+/// Parse `--bind`: an IPv4 or IPv6 address, the latter optionally in brackets (`[::1]`)
+pub fn parse_bind_address(value: &str) -> Result<IpAddr, String> {
+    let trimmed = value.trim();
+    let unbracketed = trimmed
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .unwrap_or(trimmed);
+    unbracketed.parse().map_err(|_| {
+        format!(
+            "invalid bind address '{}', expected an IP address such as 127.0.0.1, 0.0.0.0, ::1 or :: (no port)",
+            value
+        )
+    })
+}
+
+/// Build a TLS acceptor from the listener's PEM certificate chain and key
+pub fn tls_acceptor(tls: &TlsConfig) -> Result<TlsAcceptor, SystemError> {
+    let error = |message: String| SystemError::Orchestrator { message };
+    let certs = CertificateDer::pem_file_iter(&tls.cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| error(format!("Cannot read certificate {}: {}", tls.cert_path.display(), e)))?;
+    if certs.is_empty() {
+        return Err(error(format!("No certificate in {}", tls.cert_path.display())));
+    }
+    let key = PrivateKeyDer::from_pem_file(&tls.key_path)
+        .map_err(|e| error(format!("Cannot read private key {}: {}", tls.key_path.display(), e)))?;
+
+    let config = rustls::ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+        .map_err(|e| error(format!("Invalid TLS setup for {}: {}", tls.cert_path.display(), e)))?;
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// Kind of the I/O error behind a failed warp bind
+fn io_kind(error: &warp::Error) -> std::io::ErrorKind {
+    let mut source = std::error::Error::source(error);
+    while let Some(cause) = source {
+        if let Some(io) = cause.downcast_ref::<std::io::Error>() {
+            return io.kind();
+        }
+        source = cause.source();
+    }
+    std::io::ErrorKind::Other
+}
+
+/// `SystemError::Io` naming the address, keeping the underlying error kind
+fn bind_error(address: SocketAddr, kind: std::io::ErrorKind, error: impl std::fmt::Display) -> SystemError {
+    let hint = match kind {
+        std::io::ErrorKind::AddrInUse => "; is another instance running?",
+        std::io::ErrorKind::PermissionDenied => "; use a port above 1024",
+        _ => "",
+    };
+    SystemError::Io(std::io::Error::new(kind, format!("Cannot bind {}: {}{}", address, error, hint)))
+}
+
+/// Bind `listener.address` and serve `routes` on it in the background
+///
+/// Binding happens before this returns, so a taken port is reported here
+/// rather than from the spawned task.
+pub fn serve(routes: ApiRoutes, listener: &ListenerConfig) -> Result<(SocketAddr, JoinHandle<()>), SystemError> {
+    let Some(tls) = &listener.tls else {
+        let (addr, server) = warp::serve(routes)
+            .try_bind_ephemeral(listener.address)
+            .map_err(|e| bind_error(listener.address, io_kind(&e), e))?;
+        info!("API listening on http://{}", addr);
+        return Ok((addr, tokio::spawn(server)));
+    };
+
+    let acceptor = tls_acceptor(tls)?;
+    let socket = std::net::TcpListener::bind(listener.address).map_err(|e| bind_error(listener.address, e.kind(), e))?;
+    let addr = socket.local_addr()?;
+    socket.set_nonblocking(true)?;
+    let socket = tokio::net::TcpListener::from_std(socket)?;
+
+    let (sender, receiver) = mpsc::channel(ACCEPT_BACKLOG);
+    let accept = async move {
+        loop {
+            let (connection, peer) = match socket.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    // Usually out of file descriptors; back off instead of spinning
+                    warn!("Listener {} failed to accept: {}", addr, e);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+            let acceptor = acceptor.clone();
+            let sender = sender.clone();
+            tokio::spawn(async move {
+                match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(connection)).await {
+                    Ok(Ok(stream)) => {
+                        let _ = sender.send(stream).await;
+                    }
+                    Ok(Err(e)) => debug!("TLS handshake with {} failed: {}", peer, e),
+                    Err(_) => debug!("TLS handshake with {} timed out", peer),
+                }
+            });
+        }
+    };
+    let incoming = stream::unfold(receiver, |mut receiver| async move {
+        let stream = receiver.recv().await?;
+        Some((Ok::<_, std::io::Error>(stream), receiver))
+    });
+    let server = warp::serve(routes).run_incoming(incoming);
+
+    info!("API listening on https://{}", addr);
+    Ok((
+        addr,
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = accept => {}
+                _ = server => {}
+            }
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use warp::Filter;
+
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+    }
+
+    fn localhost_tls() -> TlsConfig {
+        TlsConfig {
+            cert_path: fixture("localhost.crt"),
+            key_path: fixture("localhost.key"),
+        }
+    }
+
+    fn acceptor_error(tls: &TlsConfig) -> String {
+        tls_acceptor(tls).err().expect("TLS setup fails").to_string()
+    }
+
+    #[test]
+    fn test_parse_bind_address() {
+        assert_eq!(parse_bind_address("0.0.0.0"), Ok("0.0.0.0".parse().unwrap()));
+        assert_eq!(parse_bind_address("::1"), Ok("::1".parse().unwrap()));
+        assert_eq!(parse_bind_address("[::1]"), Ok("::1".parse().unwrap()));
+        assert_eq!(parse_bind_address("::"), Ok("::".parse().unwrap()));
+        assert!(parse_bind_address("[::1]:7000").is_err());
+        assert!(parse_bind_address("127.0.0.1:7000").is_err());
+        assert!(parse_bind_address("localhost").is_err());
+    }
+
+    #[test]
+    fn test_tls_acceptor() {
+        assert!(tls_acceptor(&localhost_tls()).is_ok());
+
+        let swapped = TlsConfig {
+            cert_path: fixture("localhost.key"),
+            key_path: fixture("localhost.crt"),
+        };
+        let error = acceptor_error(&swapped);
+        assert!(error.contains("No certificate in"), "{}", error);
+
+        let missing = TlsConfig {
+            cert_path: fixture("missing.crt"),
+            key_path: fixture("localhost.key"),
+        };
+        assert!(acceptor_error(&missing).contains("missing.crt"));
+    }
+
+    #[tokio::test]
+    async fn test_serve_reports_bound_address() {
+        let routes = warp::any().map(|| Response::new("ok".into())).boxed();
+        let listener = ListenerConfig {
+            address: "127.0.0.1:0".parse().unwrap(),
+            tls: Some(localhost_tls()),
+        };
+        let (addr, handle) = serve(routes.clone(), &listener).unwrap();
+        assert_ne!(addr.port(), 0);
+
+        // The same address again is taken
+        let taken = ListenerConfig { address: addr, tls: None };
+        let error = serve(routes, &taken).unwrap_err().to_string();
+        assert!(error.contains(&format!("Cannot bind {}", addr)), "{}", error);
+        assert!(error.contains("another instance"), "{}", error);
+        handle.abort();
+    }
+}
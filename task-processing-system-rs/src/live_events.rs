@@ -0,0 +1,259 @@
+//! Live task events over a WebSocket (`GET /ws/events`)
+//!
+//! Every lifecycle event published on the [`EventBus`] is copied into a tokio
+//! broadcast channel, and each connected socket receives the events for tasks
+//! in the caller's namespaces as JSON text messages, e.g.
+//! `{"event": "task_completed", "timestamp": "...", "task": {...}}`. Nothing
+//! is replayed: a client sees what happens after it connects, and one that
+//! falls more than [`LIVE_EVENT_CAPACITY`] events behind is sent
+//! `{"event": "lagged", "missed": n}` and carries on from the oldest event
+//! still buffered. Consumers that must not miss changes poll `GET /changes`.
+
+use crate::auth::Principal;
+use crate::events::{EventBus, TaskEvent};
+use crate::timestamps::WireFormat;
+use crate::types::Task;
+use chrono::{DateTime, Utc};
+use futures::{SinkExt, StreamExt};
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::debug;
+use warp::ws::{Message, WebSocket, Ws};
+use warp::{Filter, Rejection, Reply};
+
+/// Events buffered for slow clients before they are told they lagged
+pub const LIVE_EVENT_CAPACITY: usize = 1024;
+
+/// Kind of a live event
+// Variant names are the `task_*` event names sent to clients
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LiveEventType {
+    TaskCreated,
+    /// Calculated, waiting for `POST /task/{id}/complete` (status `processing`)
+    TaskProcessing,
+    TaskCompleted,
+    TaskFailed,
+    TaskCancelled,
+}
+
+impl From<TaskEvent> for LiveEventType {
+    fn from(event: TaskEvent) -> Self {
+        match event {
+            TaskEvent::Created => Self::TaskCreated,
+            TaskEvent::Processed => Self::TaskProcessing,
+            TaskEvent::Completed => Self::TaskCompleted,
+            TaskEvent::Failed => Self::TaskFailed,
+            TaskEvent::Cancelled => Self::TaskCancelled,
+        }
+    }
+}
+
+/// One message sent to WebSocket clients
+#[derive(Debug, Clone, Serialize)]
+pub struct LiveEvent {
+    pub event: LiveEventType,
+    #[serde(with = "crate::timestamps::timestamp")]
+    pub timestamp: DateTime<Utc>,
+    /// The task as of the event
+    pub task: Task,
+}
+
+/// Sent instead of the events a slow client missed
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Notice {
+    Lagged { missed: u64 },
+}
+
+/// Fans lifecycle events out to WebSocket clients
+pub struct LiveEvents {
+    sender: broadcast::Sender<Arc<LiveEvent>>,
+    format: WireFormat,
+}
+
+impl Default for LiveEvents {
+    fn default() -> Self {
+        Self::with_capacity(LIVE_EVENT_CAPACITY)
+    }
+}
+
+impl LiveEvents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self {
+            sender,
+            format: WireFormat::default(),
+        }
+    }
+
+    /// Write the tasks in sent events in `format`
+    pub fn with_wire_format(mut self, format: WireFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Forward every lifecycle event published on `events`
+    pub fn subscribe(self: &Arc<Self>, events: &EventBus) {
+        for event in TaskEvent::ALL {
+            let live = Arc::clone(self);
+            events.register(event, move |task: Task| {
+                live.publish(event, task);
+                async {}
+            });
+        }
+    }
+
+    /// Send `event` to connected clients (dropped when there are none)
+    pub fn publish(&self, event: TaskEvent, task: Task) {
+        let _ = self.sender.send(Arc::new(LiveEvent {
+            event: event.into(),
+            timestamp: Utc::now(),
+            task,
+        }));
+    }
+
+    /// Connected clients
+    pub fn clients(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+/// `GET /ws/events`, for callers passing `authenticated`
+pub fn route(
+    live: Arc<LiveEvents>,
+    authenticated: impl Filter<Extract = (Principal,), Error = Rejection> + Clone + Send + Sync + 'static,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::path!("ws" / "events")
+        .and(authenticated)
+        .and(warp::ws())
+        .map(move |principal: Principal, ws: Ws| {
+            // Subscribed before the upgrade, so nothing published meanwhile is missed
+            let receiver = live.sender.subscribe();
+            let format = live.format;
+            ws.on_upgrade(move |socket| stream_events(socket, receiver, principal, format))
+        })
+}
+
+/// Send events the caller may see until either side goes away
+async fn stream_events(socket: WebSocket, mut receiver: broadcast::Receiver<Arc<LiveEvent>>, principal: Principal, format: WireFormat) {
+    let (mut outgoing, mut incoming) = socket.split();
+    loop {
+        tokio::select! {
+            received = receiver.recv() => {
+                let text = match received {
+                    Ok(event) if principal.allows(&event.task.namespace) => format.apply(|| serde_json::to_string(&*event)),
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(missed)) => serde_json::to_string(&Notice::Lagged { missed }),
+                    Err(RecvError::Closed) => break,
+                };
+                let Ok(text) = text else { continue };
+                if outgoing.send(Message::text(text)).await.is_err() {
+                    break;
+                }
+            }
+            // Clients have nothing to say; anything but a close is ignored
+            message = incoming.next() => match message {
+                Some(Ok(message)) if !message.is_close() => {}
+                _ => break,
+            },
+        }
+    }
+    debug!("Event stream for {} closed", principal.name);
+    let _ = outgoing.close().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{self, ApiKeys, API_KEY_HEADER};
+    use crate::types::{ApiKeyConfig, Operation, TaskData, TaskPriority};
+    use serde_json::Value;
+
+    fn task(namespace: &str) -> Task {
+        let mut task = Task::new("Live".to_string(), TaskPriority::Medium, TaskData::new(5, Operation::Factorial));
+        task.namespace = namespace.to_string();
+        task
+    }
+
+    fn keys() -> Arc<ApiKeys> {
+        Arc::new(ApiKeys::new(&[ApiKeyConfig {
+            name: "team-a".to_string(),
+            key: "key-a".to_string(),
+            namespaces: vec!["team-a".to_string()],
+        }]))
+    }
+
+    async fn next_event(client: &mut warp::test::WsClient) -> Value {
+        let message = client.recv().await.unwrap();
+        serde_json::from_str(message.to_str().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_streams_visible_events() {
+        let live = Arc::new(LiveEvents::new());
+        let route = route(Arc::clone(&live), auth::authenticate(keys()));
+
+        let mut client = warp::test::ws()
+            .path("/ws/events")
+            .header(API_KEY_HEADER, "key-a")
+            .handshake(route.clone())
+            .await
+            .unwrap();
+        assert_eq!(live.clients(), 1);
+
+        live.publish(TaskEvent::Created, task("team-b"));
+        let mut processed = task("team-a");
+        processed.set_processing("120".to_string());
+        live.publish(TaskEvent::Processed, processed.clone());
+
+        let event = next_event(&mut client).await;
+        assert_eq!(event["event"], "task_processing");
+        assert_eq!(event["task"]["id"], processed.id.as_str());
+        assert_eq!(event["task"]["result"], "120");
+
+        // Without a key there is no upgrade
+        assert!(warp::test::ws().path("/ws/events").handshake(route).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_slow_client_told_it_lagged() {
+        let live = Arc::new(LiveEvents::with_capacity(2));
+        let route = route(Arc::clone(&live), auth::authenticate(Arc::new(ApiKeys::new(&[]))));
+        let mut client = warp::test::ws().path("/ws/events").handshake(route).await.unwrap();
+
+        let tasks: Vec<Task> = (0..5).map(|_| task("default")).collect();
+        for task in &tasks {
+            live.publish(TaskEvent::Created, task.clone());
+        }
+
+        assert_eq!(next_event(&mut client).await, serde_json::json!({"event": "lagged", "missed": 3}));
+        assert_eq!(next_event(&mut client).await["task"]["id"], tasks[3].id.as_str());
+        assert_eq!(next_event(&mut client).await["task"]["id"], tasks[4].id.as_str());
+    }
+
+    #[tokio::test]
+    async fn test_bus_events_forwarded() {
+        let bus = Arc::new(EventBus::new());
+        let live = Arc::new(LiveEvents::new());
+        live.subscribe(&bus);
+        let handle = bus.start().unwrap();
+        let route = route(Arc::clone(&live), auth::authenticate(Arc::new(ApiKeys::new(&[]))));
+        let mut client = warp::test::ws().path("/ws/events").handshake(route).await.unwrap();
+
+        let mut failed = task("default");
+        failed.set_failed("boom".to_string());
+        bus.emit(TaskEvent::Failed, &failed);
+
+        let event = next_event(&mut client).await;
+        assert_eq!(event["event"], "task_failed");
+        assert_eq!(event["task"]["error_message"], "boom");
+        handle.abort();
+    }
+}
@@ -0,0 +1,178 @@
+//! Choosing the worker a new task goes to (`load_balancing` in the config)
+//!
+//! - `round_robin` (default): each worker in turn
+//! - `least_loaded`: the worker with the fewest queued and running tasks right
+//!   now, ties going round-robin
+//! - `weighted_round_robin`: in turn, but worker `i` gets `worker_weights[i]`
+//!   tasks per round (missing weights mean 1), interleaved so a heavy worker
+//!   doesn't get its share in one burst
+//! - `random`: uniformly at random
+//!
+//! Paused workers are skipped whatever the strategy.
+
+use crate::types::LoadBalancingStrategy;
+use crate::worker::Worker;
+use rand::Rng;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Picks the worker for each new task
+pub struct LoadBalancer {
+    strategy: LoadBalancingStrategy,
+    /// Worker ids in the order `weighted_round_robin` visits them in one round
+    rotation: Vec<usize>,
+    next: AtomicUsize,
+}
+
+impl LoadBalancer {
+    /// A balancer over `workers` workers; `weights` is only used by `weighted_round_robin`
+    pub fn new(strategy: LoadBalancingStrategy, workers: usize, weights: &[u32]) -> Self {
+        let rotation = match strategy {
+            LoadBalancingStrategy::WeightedRoundRobin => weighted_rotation(workers, weights),
+            _ => (0..workers).collect(),
+        };
+        Self {
+            strategy,
+            rotation,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn strategy(&self) -> LoadBalancingStrategy {
+        self.strategy
+    }
+
+    /// The active worker the next task goes to (none when all are paused)
+    pub async fn pick<'a>(&self, workers: &'a [Arc<Worker>]) -> Option<&'a Arc<Worker>> {
+        if workers.is_empty() {
+            return None;
+        }
+        match self.strategy {
+            LoadBalancingStrategy::LeastLoaded => {
+                let start = self.next.fetch_add(1, Ordering::Relaxed);
+                let mut least: Option<(usize, &Arc<Worker>)> = None;
+                for offset in 0..workers.len() {
+                    let worker = &workers[(start + offset) % workers.len()];
+                    if worker.is_paused() {
+                        continue;
+                    }
+                    let load = worker.queue_len().await + worker.busy_threads();
+                    if least.is_none_or(|(fewest, _)| load < fewest) {
+                        least = Some((load, worker));
+                    }
+                }
+                least.map(|(_, worker)| worker)
+            }
+            LoadBalancingStrategy::Random => {
+                let active: Vec<&Arc<Worker>> = workers.iter().filter(|worker| !worker.is_paused()).collect();
+                if active.is_empty() {
+                    return None;
+                }
+                Some(active[rand::thread_rng().gen_range(0..active.len())])
+            }
+            // Round-robin over the rotation, skipping paused (or since removed) workers
+            _ => {
+                let start = self.next.fetch_add(1, Ordering::Relaxed);
+                (0..self.rotation.len())
+                    .filter_map(|offset| workers.get(self.rotation[(start + offset) % self.rotation.len()]))
+                    .find(|worker| !worker.is_paused())
+            }
+        }
+    }
+}
+
+/// One round of smooth weighted round-robin: worker `i` appears `weights[i]`
+/// times (1 when missing), spread out rather than back to back
+fn weighted_rotation(workers: usize, weights: &[u32]) -> Vec<usize> {
+    let weights: Vec<i64> = (0..workers).map(|id| i64::from(weights.get(id).copied().unwrap_or(1))).collect();
+    let total: i64 = weights.iter().sum();
+    let mut current = vec![0i64; workers];
+    let mut rotation = Vec::with_capacity(total as usize);
+    for _ in 0..total {
+        for (credit, weight) in current.iter_mut().zip(&weights) {
+            *credit += weight;
+        }
+        let Some(chosen) = (0..workers).max_by_key(|&id| (current[id], std::cmp::Reverse(id))) else {
+            break;
+        };
+        current[chosen] -= total;
+        rotation.push(chosen);
+    }
+    rotation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Operation, Task, TaskData, TaskPriority};
+
+    fn workers(count: usize) -> Vec<Arc<Worker>> {
+        (0..count).map(|id| Arc::new(Worker::new(id, 1))).collect()
+    }
+
+    async fn picks(balancer: &LoadBalancer, workers: &[Arc<Worker>], count: usize) -> Vec<usize> {
+        let mut ids = Vec::new();
+        for _ in 0..count {
+            ids.push(balancer.pick(workers).await.unwrap().id);
+        }
+        ids
+    }
+
+    #[test]
+    fn test_weighted_rotation() {
+        assert_eq!(weighted_rotation(3, &[5, 1, 1]), vec![0, 0, 1, 0, 2, 0, 0]);
+        assert_eq!(weighted_rotation(3, &[2]), vec![0, 1, 2, 0]);
+        assert_eq!(weighted_rotation(2, &[]), vec![0, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_round_robin_skips_paused() {
+        let workers = workers(3);
+        let balancer = LoadBalancer::new(LoadBalancingStrategy::RoundRobin, 3, &[]);
+        assert_eq!(picks(&balancer, &workers, 4).await, vec![0, 1, 2, 0]);
+
+        workers[2].pause();
+        assert_eq!(picks(&balancer, &workers, 3).await, vec![1, 0, 0]);
+        workers[0].pause();
+        workers[1].pause();
+        assert!(balancer.pick(&workers).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_weighted_round_robin() {
+        let workers = workers(2);
+        let balancer = LoadBalancer::new(LoadBalancingStrategy::WeightedRoundRobin, 2, &[3, 1]);
+        let ids = picks(&balancer, &workers, 8).await;
+        assert_eq!(ids.iter().filter(|&&id| id == 0).count(), 6);
+
+        workers[0].pause();
+        assert_eq!(picks(&balancer, &workers, 2).await, vec![1, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_least_loaded_uses_queue_length() {
+        // Not started, so queued tasks stay queued
+        let workers = workers(3);
+        let balancer = LoadBalancer::new(LoadBalancingStrategy::LeastLoaded, 3, &[]);
+        for (worker, queued) in [(0, 2), (1, 0), (2, 1)] {
+            for input in 0..queued {
+                let task = Task::new("Queued".to_string(), TaskPriority::Low, TaskData::new(input + 1, Operation::Factorial));
+                workers[worker].add_task(task).await.unwrap();
+            }
+        }
+        assert_eq!(balancer.pick(&workers).await.unwrap().id, 1);
+
+        workers[1].pause();
+        assert_eq!(balancer.pick(&workers).await.unwrap().id, 2);
+    }
+
+    #[tokio::test]
+    async fn test_random_only_active_workers() {
+        let workers = workers(3);
+        let balancer = LoadBalancer::new(LoadBalancingStrategy::Random, 3, &[]);
+        workers[1].pause();
+        let ids = picks(&balancer, &workers, 50).await;
+        assert!(ids.iter().all(|&id| id != 1));
+        assert!(ids.contains(&0) && ids.contains(&2));
+    }
+}
@@ -1,27 +1,103 @@
+// The config schema is one large `json!` literal
+#![recursion_limit = "256"]
+// The library's modules are compiled in again below; what only library
+// users and tests call goes unused here
+#![allow(dead_code)]
+mod admin;
+mod analytics;
+mod api_examples;
+mod api_schemas;
+mod audit;
+mod auth;
+mod benchmark;
+mod calc_executor;
 mod calculations;
+mod callbacks;
+mod changes;
+mod cli_error;
+#[cfg(feature = "client")]
+mod client;
+mod config_schema;
+mod dependencies;
+mod diagnostics;
+mod events;
+mod federation;
+mod field_selection;
+mod http_client;
+mod idempotency;
+mod latency;
+mod listeners;
+mod live_events;
+mod load_balancer;
+mod math_utils;
+mod metrics;
+mod migration;
+mod openapi;
 mod orchestrator;
+mod output;
+mod probes;
+mod query;
+mod rate_limit;
+mod result_stream;
+mod results;
+mod runtime_metrics;
+mod scheduler;
+mod service;
+mod signing;
+mod sizing;
+mod stats_cache;
+mod stats_checkpoint;
+mod stats_delta;
+mod subscriptions;
+mod supervisor;
+mod table;
+mod task_index;
+mod task_queue;
+mod task_store;
+mod test_server;
+mod timestamps;
 mod types;
+mod validation;
+mod watchdog;
+mod webhooks;
+mod work_stealing;
 mod worker;
 
+use crate::cli_error::{CliError, ErrorKind};
 use crate::orchestrator::TaskOrchestrator;
-use crate::types::{OrchestratorConfig};
-use clap::{Arg, ArgMatches, Command};
+use crate::output::OutputFormat;
+use crate::types::{Operation, OrchestratorConfig};
+#[cfg(feature = "client")]
+use crate::types::{
+    AdminResponse, CreateTaskRequest, Task, TaskData, TaskListQuery, TaskPriority, TaskStatus, MAX_WAIT_TIMEOUT_MS,
+};
+use clap::{Arg, ArgAction, ArgMatches, Command};
 use config::{Config, ConfigError, File};
-use serde::Deserialize;
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+#[cfg(feature = "client")]
+use std::time::Duration;
 use tokio::signal;
 use tracing::{error, info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 /// Application configuration that can be loaded from file or CLI args
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 struct AppConfig {
     #[serde(flatten)]
     orchestrator: OrchestratorConfig,
     
     #[serde(default = "default_log_level")]
     log_level: String,
+
+    /// Selected profile (`--profile` or `$TASK_PROCESSOR_PROFILE`)
+    #[serde(skip)]
+    profile: Option<String>,
+
+    /// Config files merged, later ones overriding earlier ones
+    #[serde(skip)]
+    files: Vec<PathBuf>,
 }
 
 impl Default for AppConfig {
@@ -29,10 +105,15 @@ impl Default for AppConfig {
         Self {
             orchestrator: OrchestratorConfig::default(),
             log_level: default_log_level(),
+            profile: None,
+            files: Vec::new(),
         }
     }
 }
 
+/// Environment variable selecting the config profile when `--profile` isn't given
+const PROFILE_ENV: &str = "TASK_PROCESSOR_PROFILE";
+
 fn default_log_level() -> String {
     "info".to_string()
 }
@@ -40,39 +121,85 @@ fn default_log_level() -> String {
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Setup command line interface
-    let matches = create_cli().get_matches();
+    let matches = parse_cli();
+    let json_errors = matches.get_flag("json-errors");
+
+    // Standalone subcommands that don't start the server
+    let result = match matches.subcommand() {
+        Some(("bench-calculations", sub_matches)) => Some(run_bench_calculations(sub_matches)),
+        #[cfg(feature = "client")]
+        Some(("migrate-from", sub_matches)) => Some(run_migrate_from(&matches, sub_matches).await),
+        #[cfg(feature = "client")]
+        Some((command @ ("get" | "list" | "stats"), sub_matches)) => {
+            Some(run_query(&matches, command, sub_matches).await)
+        }
+        #[cfg(feature = "client")]
+        Some((command @ ("submit" | "complete"), sub_matches)) => {
+            Some(run_task_command(&matches, command, sub_matches).await)
+        }
+        #[cfg(feature = "client")]
+        Some(("watch", sub_matches)) => Some(run_watch(&matches, sub_matches).await),
+        #[cfg(feature = "client")]
+        Some(("admin", sub_matches)) => Some(run_admin(&matches, sub_matches).await),
+        Some(("config", sub_matches)) => Some(run_config(sub_matches)),
+        Some(("analyze", sub_matches)) => Some(run_analyze(&matches, sub_matches)),
+        _ => None,
+    };
+    if let Some(result) = result {
+        std::process::exit(cli_error::exit_code(result, json_errors));
+    }
+
+    // `serve` takes the same options as running without a subcommand
+    let matches = match matches.subcommand() {
+        Some(("serve", sub_matches)) => sub_matches.clone(),
+        _ => matches,
+    };
 
     // Load configuration
-    let config = load_configuration(&matches)?;
+    let mut config = load_configuration(&matches)?;
 
     // Initialize logging
     setup_logging(&config.log_level)?;
+    log_effective_config(&config);
 
-    // Print system information
-    print_system_info(&config);
+    // Under a service manager stdout goes to the journal, so skip the banners
+    let service_mode = matches.get_flag("service");
+    if service_mode && !service::SUPPORTED {
+        return Err("--service is only supported on Linux (systemd) and Windows (SCM)".into());
+    }
+    if service_mode {
+        // A bit over the drain timeout, so the SCM waits for the drain to finish
+        let stop_timeout = Duration::from_secs(config.orchestrator.shutdown_drain_timeout_seconds.saturating_add(10));
+        service::start(stop_timeout).map_err(|e| format!("Failed to connect to the service manager: {}", e))?;
+    }
 
     // Create and start orchestrator
     let orchestrator_config = config.orchestrator.clone();
     let orchestrator = Arc::new(TaskOrchestrator::new(orchestrator_config)?);
 
-    // Setup graceful shutdown
-    let shutdown_orchestrator = Arc::clone(&orchestrator);
-    tokio::spawn(async move {
-        wait_for_shutdown_signal().await;
-        info!("Shutdown signal received, stopping orchestrator...");
-        shutdown_orchestrator.stop().await;
-    });
-
     // Start the system
     info!("Starting Task Processing System...");
     match orchestrator.start().await {
         Ok(()) => {
             info!("Task Processing System started successfully!");
-            print_usage_instructions(&config);
-            
-            // Keep the main thread alive
-            tokio::signal::ctrl_c().await?;
-            info!("Task Processing System shutdown complete");
+            // Show the ports actually bound when configured with port 0
+            let addrs = orchestrator.bound_addrs();
+            if let Some(addr) = addrs.first() {
+                config.orchestrator.orchestrator_port = addr.port();
+            }
+            for (listener, addr) in config.orchestrator.listeners.iter_mut().zip(addrs.iter().skip(1)) {
+                listener.address = *addr;
+            }
+            if service_mode {
+                notify_service_manager(service::notify_ready());
+                notify_service_manager(service::notify_status(&format!(
+                    "Serving on port {} with {} workers",
+                    config.orchestrator.orchestrator_port, config.orchestrator.num_workers
+                )));
+            } else {
+                print_system_info(&config);
+                print_usage_instructions(&config);
+            }
         }
         Err(e) => {
             error!("Failed to start Task Processing System: {}", e);
@@ -80,107 +207,1098 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    // Run until a shutdown signal arrives, then stop gracefully
+    wait_for_shutdown_signal().await;
+    info!("Shutdown signal received, stopping orchestrator...");
+    if service_mode {
+        notify_service_manager(service::notify_stopping());
+    }
+    orchestrator.shutdown().await;
+    info!("Task Processing System shutdown complete");
+    if service_mode {
+        if let Err(e) = service::notify_stopped() {
+            warn!("Failed to notify service manager: {}", e);
+        }
+    }
+
     Ok(())
 }
 
+/// Log (but otherwise ignore) failures talking to the service manager
+fn notify_service_manager(result: std::io::Result<bool>) {
+    match result {
+        Ok(true) => {}
+        Ok(false) => warn!("Service mode enabled but no service manager is listening (NOTIFY_SOCKET unset)"),
+        Err(e) => warn!("Failed to notify service manager: {}", e),
+    }
+}
+
+/// Parse the command line, exiting with code 2 on a usage error
+///
+/// Help and version output keep clap's own handling; with `--json-errors` a
+/// usage error is printed as a JSON object like any other CLI error.
+fn parse_cli() -> ArgMatches {
+    let error = match create_cli().try_get_matches() {
+        Ok(matches) => return matches,
+        Err(error) => error,
+    };
+    let json_errors = std::env::args_os().any(|arg| arg == "--json-errors");
+    if !json_errors || !error.use_stderr() {
+        error.exit();
+    }
+    // First line of clap's message, without the "error: " prefix or usage
+    let rendered = error.render().to_string();
+    let message = rendered.lines().next().unwrap_or_default().trim_start_matches("error: ");
+    std::process::exit(cli_error::exit_code(Err(CliError::new(ErrorKind::Validation, message)), true));
+}
+
 /// Create command line interface
 fn create_cli() -> Command {
     Command::new("task-processor")
         .about("High-performance task processing system")
         .version(env!("CARGO_PKG_VERSION"))
+        .args(serve_args())
         .arg(
-            Arg::new("workers")
-                .short('w')
-                .long("workers")
-                .value_name("NUM")
-                .help("Number of worker nodes (default: 3, max: 50)")
-                .value_parser(clap::value_parser!(usize))
+            Arg::new("profile")
+                .short('p')
+                .long("profile")
+                .value_name("NAME")
+                .help("Config profile: merge config.<NAME>.toml over the base config (default: $TASK_PROCESSOR_PROFILE)")
+                .value_parser(parse_profile)
+                .global(true)
         )
         .arg(
-            Arg::new("threads")
-                .short('t')
-                .long("threads")
-                .value_name("NUM")
-                .help("Threads per worker (default: 4, max: 32)")
-                .value_parser(clap::value_parser!(usize))
+            Arg::new("json-errors")
+                .long("json-errors")
+                .help("Print subcommand errors to stderr as JSON ({\"kind\", \"error\", \"exit_code\"})")
+                .action(ArgAction::SetTrue)
+                .global(true)
         )
-        .arg(
-            Arg::new("orchestrator-port")
-                .short('o')
-                .long("orchestrator-port")
-                .value_name("NUM")
-                .help("Orchestrator port (default: 7000)")
-                .value_parser(clap::value_parser!(u16))
+        .subcommand(
+            Command::new("serve")
+                .about("Start the server (the default without a subcommand)")
+                .args(serve_args())
         )
-        .arg(
-            Arg::new("config")
-                .short('c')
-                .long("config")
-                .value_name("FILE")
-                .help("Configuration file (JSON/TOML)")
+        .subcommand(
+            Command::new("bench-calculations")
+                .about("Benchmark calculations and fail on regressions against a baseline")
+                .arg(
+                    Arg::new("baseline")
+                        .long("baseline")
+                        .value_name("FILE")
+                        .help("Baseline JSON to compare against")
+                )
+                .arg(
+                    Arg::new("fail-threshold")
+                        .long("fail-threshold")
+                        .value_name("PERCENT")
+                        .help("Allowed slowdown before failing, e.g. 10%")
+                        .default_value("10%")
+                        .value_parser(benchmark::parse_threshold)
+                )
+                .arg(
+                    Arg::new("save-baseline")
+                        .long("save-baseline")
+                        .value_name("FILE")
+                        .help("Write this run's results as a new baseline")
+                )
+                .arg(
+                    Arg::new("iterations")
+                        .long("iterations")
+                        .value_name("NUM")
+                        .help("Iterations per benchmark case")
+                        .default_value("100000")
+                        .value_parser(clap::value_parser!(u32))
+                )
         )
-        .arg(
-            Arg::new("log-level")
-                .short('l')
-                .long("log-level")
-                .value_name("LEVEL")
-                .help("Log level (error, warn, info, debug, trace)")
-                .default_value("info")
+        .subcommands(client_commands())
+        .subcommand(
+            Command::new("analyze")
+                .about("Summarize the analytics database: slowest operations, failure rates by input range")
+                .arg(
+                    Arg::new("db")
+                        .long("db")
+                        .value_name("FILE")
+                        .help("Analytics database (default: analytics.path from the config)")
+                )
+                .arg(output_arg())
+        )
+        .subcommand(
+            Command::new("config")
+                .about("Check configuration files without starting the server")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("validate")
+                        .about("Validate a config file and report every problem")
+                        .arg(Arg::new("file").value_name("FILE").required(true))
+                )
+                .subcommand(Command::new("schema").about("Print the JSON Schema of the config file format"))
         )
 }
 
+/// Subcommands talking to a running server, or to the C++ one for `migrate-from`
+#[cfg(feature = "client")]
+fn client_commands() -> Vec<Command> {
+    vec![
+        Command::new("migrate-from")
+            .about("Import tasks from a running C++ server and enqueue unfinished ones here")
+            .arg(
+                Arg::new("url")
+                    .long("url")
+                    .value_name("URL")
+                    .help("Base URL of the C++ server, e.g. http://cpp-server:7000")
+                    .required(true)
+            )
+            .arg(
+                Arg::new("target")
+                    .long("target")
+                    .value_name("URL")
+                    .help("Base URL of the local server (default: http://<bind address>:<orchestrator_port>)")
+            )
+            .arg(
+                Arg::new("id")
+                    .long("id")
+                    .value_name("ID")
+                    .help("Task ID to import (repeatable)")
+                    .action(ArgAction::Append)
+            )
+            .arg(
+                Arg::new("ids")
+                    .long("ids")
+                    .value_name("FILE")
+                    .help("File with one task ID per line ('-' for stdin)")
+            ),
+        Command::new("get")
+            .about("Show one task from a running server")
+            .visible_alias("status")
+            .arg(Arg::new("id").value_name("ID").required(true))
+            .args(server_args())
+            .arg(output_arg()),
+        Command::new("list")
+            .about("List the newest tasks on a running server")
+            .arg(
+                Arg::new("status")
+                    .long("status")
+                    .value_name("STATUS")
+                    .help("Only tasks in this status: scheduled, blocked, pending, processing, completed, failed or cancelled")
+                    .value_parser(clap::value_parser!(TaskStatus))
+            )
+            .arg(
+                Arg::new("label")
+                    .long("label")
+                    .value_name("KEY:VALUE")
+                    .help("Only tasks carrying these labels, comma-separated")
+            )
+            .arg(
+                Arg::new("limit")
+                    .long("limit")
+                    .value_name("NUM")
+                    .help("Tasks to show (max 1000)")
+                    .default_value("100")
+                    .value_parser(clap::value_parser!(usize))
+            )
+            .arg(
+                Arg::new("offset")
+                    .long("offset")
+                    .value_name("NUM")
+                    .help("Tasks to skip before the first one shown")
+                    .default_value("0")
+                    .value_parser(clap::value_parser!(usize))
+            )
+            .arg(
+                Arg::new("sort")
+                    .long("sort")
+                    .value_name("FIELD")
+                    .help("created_at, priority or id; prefix with - for descending (default -created_at)")
+                    .allow_hyphen_values(true)
+            )
+            .args(server_args())
+            .arg(output_arg()),
+        Command::new("stats")
+            .about("Show system and worker statistics from a running server")
+            .args(server_args())
+            .arg(output_arg()),
+        Command::new("submit")
+            .about("Create a task on a running server")
+            .arg(
+                Arg::new("operation")
+                    .value_name("OPERATION")
+                    .help("factorial, fibonacci, prime_check, generate_prime, gcd or lcm")
+                    .required(true)
+                    .value_parser(parse_operation)
+            )
+            .arg(
+                Arg::new("input")
+                    .value_name("INPUT")
+                    .required(true)
+                    .value_parser(clap::value_parser!(u64))
+            )
+            .arg(
+                Arg::new("input2")
+                    .long("input2")
+                    .value_name("NUM")
+                    .help("Second operand of gcd and lcm")
+                    .value_parser(clap::value_parser!(u64))
+            )
+            .arg(
+                Arg::new("id")
+                    .long("id")
+                    .value_name("ID")
+                    .help("Task ID (default: a generated UUID)")
+            )
+            .arg(
+                Arg::new("title")
+                    .long("title")
+                    .value_name("TITLE")
+                    .help("Task title (default: \"<operation>(<input>)\")")
+            )
+            .arg(
+                Arg::new("priority")
+                    .long("priority")
+                    .value_name("PRIORITY")
+                    .default_value("medium")
+                    .value_parser(["low", "medium", "high"])
+            )
+            .arg(
+                Arg::new("wait")
+                    .long("wait")
+                    .help("Wait for the result (up to 60 s) before printing the task")
+                    .action(ArgAction::SetTrue)
+            )
+            .args(server_args())
+            .arg(output_arg()),
+        Command::new("complete")
+            .about("Complete a calculated task on a running server")
+            .arg(Arg::new("id").value_name("ID").required(true))
+            .args(server_args())
+            .arg(output_arg()),
+        Command::new("watch")
+            .about("Follow a task on a running server until it is completed, failed or cancelled")
+            .arg(Arg::new("id").value_name("ID").required(true))
+            .arg(
+                Arg::new("interval")
+                    .long("interval")
+                    .value_name("MS")
+                    .help("Polling interval while the task awaits completion")
+                    .default_value("1000")
+                    .value_parser(clap::value_parser!(u64).range(10..))
+            )
+            .args(server_args()),
+        Command::new("admin")
+            .about("Operator actions on a running server's workers")
+            .subcommand_required(true)
+            .args(server_args().map(|arg| arg.global(true)))
+            .arg(
+                Arg::new("yes")
+                    .short('y')
+                    .long("yes")
+                    .help("Don't ask for confirmation")
+                    .action(ArgAction::SetTrue)
+                    .global(true)
+            )
+            .subcommand(Command::new("workers").about("Show every worker's state"))
+            .subcommand(
+                Command::new("pause-worker")
+                    .about("Stop assigning tasks to a worker; its queue waits")
+                    .arg(Arg::new("id").value_name("ID").required(true).value_parser(clap::value_parser!(usize)))
+            )
+            .subcommand(
+                Command::new("resume-worker")
+                    .about("Let a paused worker take tasks again")
+                    .arg(Arg::new("id").value_name("ID").required(true).value_parser(clap::value_parser!(usize)))
+            )
+            .subcommand(
+                Command::new("scale")
+                    .about("Keep the first N workers active and pause the rest")
+                    .arg(Arg::new("workers").value_name("N").required(true).value_parser(clap::value_parser!(usize)))
+            )
+            .subcommand(
+                Command::new("purge")
+                    .about("Delete stored tasks in a finished status")
+                    .arg(
+                        Arg::new("status")
+                            .long("status")
+                            .value_name("STATUS")
+                            .required(true)
+                            .help("completed, failed or cancelled")
+                            .value_parser(parse_purge_status)
+                    )
+            )
+            .subcommand(Command::new("rebalance").about("Spread queued tasks evenly over the active workers")),
+    ]
+}
+
+#[cfg(not(feature = "client"))]
+fn client_commands() -> Vec<Command> {
+    Vec::new()
+}
+
+/// Options of the server itself, taken with or without the `serve` subcommand
+fn serve_args() -> Vec<Arg> {
+    vec![
+        Arg::new("workers")
+            .short('w')
+            .long("workers")
+            .value_name("NUM")
+            .help("Number of worker nodes, or \"auto\" to size from available CPUs (default: auto, max: 50)")
+            .value_parser(sizing::parse_count_or_auto),
+        Arg::new("threads")
+            .short('t')
+            .long("threads")
+            .value_name("NUM")
+            .help("Threads per worker, or \"auto\" to size from available CPUs (default: auto, max: 32)")
+            .value_parser(sizing::parse_count_or_auto),
+        Arg::new("orchestrator-port")
+            .short('o')
+            .long("orchestrator-port")
+            .value_name("NUM")
+            .help("Orchestrator port, or 0 for any free port (default: 7000)")
+            .value_parser(clap::value_parser!(u16)),
+        Arg::new("bind")
+            .long("bind")
+            .value_name("ADDR")
+            .help("Address to listen on, e.g. 0.0.0.0, ::1 or :: for IPv4 and IPv6 (default: 127.0.0.1)")
+            .value_parser(listeners::parse_bind_address),
+        Arg::new("port-file")
+            .long("port-file")
+            .value_name("FILE")
+            .help("Write the bound address here once listening (removed on shutdown)"),
+        Arg::new("base-port")
+            .long("base-port")
+            .value_name("NUM")
+            .help("Serve per-worker diagnostics on consecutive ports starting here (default: off)")
+            .value_parser(clap::value_parser!(u16)),
+        Arg::new("config")
+            .short('c')
+            .long("config")
+            .value_name("FILE")
+            .help("Configuration file (JSON/TOML)"),
+        Arg::new("log-level")
+            .short('l')
+            .long("log-level")
+            .value_name("LEVEL")
+            .help("Log level (error, warn, info, debug, trace)")
+            .default_value("info"),
+        Arg::new("primary")
+            .long("primary")
+            .value_name("URL")
+            .help("Run as a read-only replica; mutations get 405 with a Location on this primary"),
+        Arg::new("service")
+            .long("service")
+            .help("Run as a service: systemd Type=notify on Linux, SCM service on Windows (no banners)")
+            .action(ArgAction::SetTrue),
+    ]
+}
+
+/// Run the calculator benchmarks and compare against a baseline
+///
+/// Fails with [`ErrorKind::Failed`] on regressions and [`ErrorKind::Validation`]
+/// when a baseline can't be read or written.
+fn run_bench_calculations(matches: &ArgMatches) -> Result<(), CliError> {
+    let iterations = *matches.get_one::<u32>("iterations").unwrap_or(&100_000);
+    let threshold = *matches.get_one::<f64>("fail-threshold").unwrap_or(&10.0);
+
+    let current = benchmark::run_benchmarks(&benchmark::default_cases(), iterations);
+    println!("=== Calculation Benchmarks ({} iterations) ===", iterations);
+    for (name, mean_ns) in &current.results {
+        println!("  {:<20} {:>12.1} ns/op", name, mean_ns);
+    }
+
+    if let Some(path) = matches.get_one::<String>("save-baseline") {
+        current.save(&PathBuf::from(path)).map_err(|e| {
+            CliError::new(ErrorKind::Validation, format!("Failed to write baseline {}: {}", path, e))
+        })?;
+        println!("Baseline written to {}", path);
+    }
+
+    let Some(path) = matches.get_one::<String>("baseline") else {
+        return Ok(());
+    };
+
+    let baseline = benchmark::BenchReport::load(&PathBuf::from(path)).map_err(|e| {
+        CliError::new(ErrorKind::Validation, format!("Failed to read baseline {}: {}", path, e))
+    })?;
+
+    let regressions = benchmark::find_regressions(&baseline, &current, threshold);
+    if regressions.is_empty() {
+        println!("No regressions above {}% against {}", threshold, path);
+        return Ok(());
+    }
+
+    for regression in &regressions {
+        eprintln!(
+            "  {:<20} {:>12.1} -> {:>12.1} ns/op (+{:.1}%)",
+            regression.name, regression.baseline_ns, regression.current_ns, regression.change_percent
+        );
+    }
+    Err(CliError::new(
+        ErrorKind::Failed,
+        format!("{} regressions above {}% against {}", regressions.len(), threshold, path),
+    ))
+}
+
+/// Pull tasks from a C++ server and enqueue the unfinished ones on the local server
+///
+/// Fails with [`ErrorKind::Failed`] when some IDs were missing or failed to
+/// import, and [`ErrorKind::Unreachable`] when the C++ server can't be reached.
+#[cfg(feature = "client")]
+async fn run_migrate_from(matches: &ArgMatches, sub_matches: &ArgMatches) -> Result<(), CliError> {
+    let source = sub_matches.get_one::<String>("url").cloned().unwrap_or_default();
+    let target = match sub_matches.get_one::<String>("target") {
+        Some(target) => target.clone(),
+        None => default_url(matches)?,
+    };
+
+    let mut ids: Vec<String> = sub_matches
+        .get_many::<String>("id")
+        .map(|ids| ids.cloned().collect())
+        .unwrap_or_default();
+    if let Some(path) = sub_matches.get_one::<String>("ids") {
+        let text = if path == "-" {
+            std::io::read_to_string(std::io::stdin())
+        } else {
+            std::fs::read_to_string(path)
+        };
+        let text = text.map_err(|e| {
+            CliError::new(ErrorKind::Validation, format!("Failed to read task IDs from {}: {}", path, e))
+        })?;
+        ids.extend(migration::parse_id_list(&text));
+    }
+    if ids.is_empty() {
+        return Err(CliError::new(
+            ErrorKind::Validation,
+            "No task IDs given; the C++ API cannot list tasks, so pass --id or --ids",
+        ));
+    }
+
+    let migrator = migration::Migrator::new(source.clone(), target.clone());
+    let stats = migrator.fetch_stats().await.map_err(|e| {
+        CliError::new(ErrorKind::Unreachable, format!("Cannot reach C++ server at {}: {}", source, e))
+    })?;
+    println!(
+        "Source {}: {} workers, {} processed, {} completed, {} failed, up {}s",
+        source,
+        stats.total_workers,
+        stats.total_tasks_processed,
+        stats.total_tasks_completed,
+        stats.total_tasks_failed,
+        stats.uptime_seconds
+    );
+
+    let report = migrator.migrate(&ids).await;
+    println!("Fetched {} of {} tasks", report.fetched, ids.len());
+    println!("  Enqueued on {}: {}", target, report.enqueued.len());
+    println!("  Already finished (skipped): {}", report.already_finished.len());
+    for id in &report.not_found {
+        eprintln!("  Not found: {}", id);
+    }
+    for (id, reason) in &report.errors {
+        eprintln!("  Failed {}: {}", id, reason);
+    }
+
+    if report.is_success() {
+        return Ok(());
+    }
+    Err(CliError::new(
+        ErrorKind::Failed,
+        format!(
+            "{} of {} tasks not migrated ({} not found, {} failed)",
+            report.not_found.len() + report.errors.len(),
+            ids.len(),
+            report.not_found.len(),
+            report.errors.len()
+        ),
+    ))
+}
+
+/// Run an operator action against a running server and print the workers as a table
+///
+/// A declined confirmation fails with [`ErrorKind::Failed`]; request errors
+/// map through [`client::ClientError`].
+#[cfg(feature = "client")]
+async fn run_admin(matches: &ArgMatches, sub_matches: &ArgMatches) -> Result<(), CliError> {
+    let (url, client) = connect(matches, sub_matches)?;
+
+    let (action, action_matches) = sub_matches.subcommand().expect("subcommand is required");
+    let prompt = match action {
+        "pause-worker" => Some(format!(
+            "Pause worker {}? Its queued tasks wait until it is resumed or rebalanced.",
+            action_matches.get_one::<usize>("id").unwrap()
+        )),
+        "scale" => Some(format!(
+            "Keep {} workers active and pause the rest?",
+            action_matches.get_one::<usize>("workers").unwrap()
+        )),
+        "purge" => Some(format!(
+            "Permanently delete every {} task?",
+            action_matches.get_one::<TaskStatus>("status").unwrap()
+        )),
+        "rebalance" => Some("Move queued tasks between workers?".to_string()),
+        _ => None,
+    };
+    if let Some(prompt) = prompt {
+        if !sub_matches.get_flag("yes") && !confirm(&format!("{} ({})", prompt, url)) {
+            return Err(CliError::new(ErrorKind::Failed, "Aborted"));
+        }
+    }
+
+    let response = match action {
+        "workers" => client.workers().await.map(|workers| AdminResponse {
+            message: format!("{} workers", workers.len()),
+            tasks_affected: 0,
+            workers,
+        }),
+        "pause-worker" => client.pause_worker(*action_matches.get_one::<usize>("id").unwrap()).await,
+        "resume-worker" => client.resume_worker(*action_matches.get_one::<usize>("id").unwrap()).await,
+        "scale" => client.scale(*action_matches.get_one::<usize>("workers").unwrap()).await,
+        "purge" => {
+            let status = action_matches.get_one::<TaskStatus>("status").unwrap().clone();
+            client.purge(status).await
+        }
+        "rebalance" => client.rebalance().await,
+        _ => unreachable!("unknown admin subcommand {}", action),
+    }?;
+
+    println!("{}", response.message);
+    println!();
+    print!("{}", output::workers_table(&response.workers));
+    Ok(())
+}
+
+/// Query a running server (`get`, `list`, `stats`) and print the result in the `--output` format
+#[cfg(feature = "client")]
+async fn run_query(matches: &ArgMatches, command: &str, sub_matches: &ArgMatches) -> Result<(), CliError> {
+    let (_, client) = connect(matches, sub_matches)?;
+    let format = *sub_matches.get_one::<OutputFormat>("output").expect("output has a default");
+
+    let rendered = match command {
+        "get" => {
+            let id = sub_matches.get_one::<String>("id").expect("id is required");
+            let task = client.get_task(id).await?;
+            output::render(&task, format, || output::task_table(&task).to_string())
+        }
+        "list" => {
+            let query = TaskListQuery {
+                status: sub_matches.get_one::<TaskStatus>("status").cloned(),
+                limit: *sub_matches.get_one::<usize>("limit").expect("limit has a default"),
+                offset: *sub_matches.get_one::<usize>("offset").expect("offset has a default"),
+                sort: sub_matches.get_one::<String>("sort").cloned(),
+                label: sub_matches.get_one::<String>("label").cloned(),
+            };
+            let tasks = client.list_tasks(&query).await?;
+            output::render(&tasks, format, || output::task_list_table(&tasks).to_string())
+        }
+        _ => {
+            let stats = client.stats().await?;
+            output::render(&stats, format, || output::stats_tables(&stats))
+        }
+    };
+
+    print!("{}", rendered);
+    Ok(())
+}
+
+/// Create (`submit`) or complete (`complete`) a task on a running server and
+/// print the reply in the `--output` format
+#[cfg(feature = "client")]
+async fn run_task_command(matches: &ArgMatches, command: &str, sub_matches: &ArgMatches) -> Result<(), CliError> {
+    let (_, client) = connect(matches, sub_matches)?;
+    let format = *sub_matches.get_one::<OutputFormat>("output").expect("output has a default");
+
+    let rendered = match command {
+        "submit" => {
+            let operation = sub_matches.get_one::<Operation>("operation").expect("operation is required").clone();
+            let input = *sub_matches.get_one::<u64>("input").expect("input is required");
+            let title = match sub_matches.get_one::<String>("title") {
+                Some(title) => title.clone(),
+                None => format!("{}({})", operation, input),
+            };
+            let priority = match sub_matches.get_one::<String>("priority").expect("priority has a default").as_str() {
+                "low" => TaskPriority::Low,
+                "high" => TaskPriority::High,
+                _ => TaskPriority::Medium,
+            };
+            let mut data = TaskData::new(input, operation);
+            data.input2 = sub_matches.get_one::<u64>("input2").copied();
+            let mut request = CreateTaskRequest::new(title, priority, data);
+            if let Some(id) = sub_matches.get_one::<String>("id") {
+                request.id = id.clone();
+            }
+
+            let mut task = client.create_task(&request).await?;
+            if sub_matches.get_flag("wait") {
+                task = client.wait_task(&task.id, MAX_WAIT_TIMEOUT_MS).await?;
+            }
+            output::render(&task, format, || output::task_table(&task).to_string())
+        }
+        _ => {
+            let id = sub_matches.get_one::<String>("id").expect("id is required");
+            let response = client.complete_task(id).await?;
+            output::render(&response, format, || format!("{}\n", response.message))
+        }
+    };
+
+    print!("{}", rendered);
+    Ok(())
+}
+
+/// Print a line each time a task's status changes, until it's completed, failed or cancelled
+///
+/// Pending tasks are long-polled; calculated ones awaiting completion are
+/// polled every `--interval`. Fails with [`ErrorKind::Failed`] when the task
+/// ends failed or cancelled.
+#[cfg(feature = "client")]
+async fn run_watch(matches: &ArgMatches, sub_matches: &ArgMatches) -> Result<(), CliError> {
+    let (_, client) = connect(matches, sub_matches)?;
+    let id = sub_matches.get_one::<String>("id").expect("id is required");
+    let interval = Duration::from_millis(*sub_matches.get_one::<u64>("interval").expect("interval has a default"));
+
+    let mut task = client.get_task(id).await?;
+    println!("{}", watch_line(&task));
+    while !task.status.is_terminal() {
+        let next = match task.status {
+            TaskStatus::Processing => {
+                tokio::time::sleep(interval).await;
+                client.get_task(id).await?
+            }
+            _ => client.wait_task(id, MAX_WAIT_TIMEOUT_MS).await?,
+        };
+        if next.status != task.status {
+            println!("{}", watch_line(&next));
+        }
+        task = next;
+    }
+
+    match task.status {
+        TaskStatus::Completed => Ok(()),
+        status => Err(CliError::new(ErrorKind::Failed, format!("Task {} is {}", id, status))),
+    }
+}
+
+/// `<time>  <status>  <result or error>` for `watch`
+#[cfg(feature = "client")]
+fn watch_line(task: &Task) -> String {
+    let detail = match task.status {
+        TaskStatus::Failed => task.error_message.as_deref().unwrap_or_default(),
+        _ => task.result.as_deref().unwrap_or_default(),
+    };
+    let line = format!("{}  {:<10}  {}", chrono::Utc::now().format("%Y-%m-%d %H:%M:%S"), task.status, detail);
+    line.trim_end().to_string()
+}
+
+/// Print summary reports from the analytics database
+///
+/// Fails with [`ErrorKind::NotFound`] when the database doesn't exist and
+/// [`ErrorKind::Validation`] when none is given or configured, or it can't be read.
+fn run_analyze(matches: &ArgMatches, sub_matches: &ArgMatches) -> Result<(), CliError> {
+    let path = match sub_matches.get_one::<String>("db") {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let config = load_configuration(matches).map_err(|e| {
+                CliError::new(ErrorKind::Validation, format!("Failed to load configuration: {}", e))
+            })?;
+            let Some(analytics) = config.orchestrator.analytics else {
+                return Err(CliError::new(ErrorKind::Validation, "No [analytics] path is configured; pass --db"));
+            };
+            analytics.path
+        }
+    };
+    if !path.exists() {
+        return Err(CliError::new(
+            ErrorKind::NotFound,
+            format!("Analytics database not found: {}", path.display()),
+        ));
+    }
+    let report = analytics::analyze(&path).map_err(|e| {
+        CliError::new(ErrorKind::Validation, format!("Cannot read analytics database {}: {}", path.display(), e))
+    })?;
+
+    let format = *sub_matches.get_one::<OutputFormat>("output").expect("output has a default");
+    print!("{}", output::render(&report, format, || output::analytics_tables(&report)));
+    Ok(())
+}
+
+/// Validate a config file offline (`config validate`) or print its JSON Schema (`config schema`)
+fn run_config(sub_matches: &ArgMatches) -> Result<(), CliError> {
+    match sub_matches.subcommand() {
+        Some(("validate", validate_matches)) => {
+            let path = PathBuf::from(validate_matches.get_one::<String>("file").expect("file is required"));
+            let profile = selected_profile(sub_matches).map_err(|e| CliError::new(ErrorKind::Validation, e))?;
+            let config = load_config_file(&path, profile.as_deref())?;
+            let files: Vec<String> = config.files.iter().map(|path| path.display().to_string()).collect();
+            println!(
+                "{} is valid: {} workers x {} threads, orchestrator port {}",
+                files.join(" + "),
+                config.orchestrator.num_workers,
+                config.orchestrator.threads_per_worker,
+                config.orchestrator.orchestrator_port
+            );
+            Ok(())
+        }
+        _ => {
+            let schema = config_schema::config_schema();
+            println!("{}", serde_json::to_string_pretty(&schema).unwrap_or_default());
+            Ok(())
+        }
+    }
+}
+
+/// Load and validate a config file (plus the profile's file) on its own, without CLI overrides
+fn load_config_file(path: &Path, profile: Option<&str>) -> Result<AppConfig, CliError> {
+    if !path.exists() {
+        return Err(CliError::new(
+            ErrorKind::NotFound,
+            format!("Config file not found: {}", path.display()),
+        ));
+    }
+    let files = config_files(Some(path.to_path_buf()), profile).map_err(|e| CliError::new(ErrorKind::NotFound, e))?;
+    let invalid = |e: ConfigError| CliError::new(ErrorKind::Validation, format!("{} is invalid: {}", path.display(), e));
+
+    let config_builder = files
+        .iter()
+        .fold(Config::builder(), |config_builder, file| config_builder.add_source(File::from(file.as_path())));
+    let mut config = resolve_auto_sizing(config_builder)
+        .and_then(|config_builder| config_builder.build())
+        .and_then(|config| config.try_deserialize::<AppConfig>())
+        .map_err(invalid)?;
+    config.orchestrator.validate().map_err(|errors| {
+        let errors = errors.with_sources(|key| file_source(&files, key));
+        CliError::new(ErrorKind::Validation, format!("{} is invalid: {}", path.display(), errors))
+    })?;
+    config.profile = profile.map(str::to_string);
+    config.files = files;
+    Ok(config)
+}
+
+/// Operation by its API name, e.g. "prime_check"
+fn parse_operation(value: &str) -> Result<Operation, String> {
+    Operation::ALL
+        .into_iter()
+        .find(|operation| operation.to_string() == value)
+        .ok_or_else(|| {
+            let names: Vec<String> = Operation::ALL.iter().map(Operation::to_string).collect();
+            format!("unknown operation \"{}\", expected one of {}", value, names.join(", "))
+        })
+}
+
+/// `admin purge --status`: only finished tasks can be purged
+#[cfg(feature = "client")]
+fn parse_purge_status(value: &str) -> Result<TaskStatus, String> {
+    let status: TaskStatus = value.parse()?;
+    if !status.is_terminal() {
+        return Err(format!("cannot purge {} tasks, expected completed, failed or cancelled", status));
+    }
+    Ok(status)
+}
+
+/// `--url` and `--api-key`, shared by the commands that talk to a running server
+fn server_args() -> [Arg; 2] {
+    [
+        Arg::new("url")
+            .long("url")
+            .value_name("URL")
+            .help("Base URL of the server (default: http://<bind address>:<orchestrator_port>)"),
+        Arg::new("api-key")
+            .long("api-key")
+            .value_name("KEY")
+            .help("API key, if the server requires one (default: $TASK_PROCESSOR_API_KEY)"),
+    ]
+}
+
+fn output_arg() -> Arg {
+    Arg::new("output")
+        .long("output")
+        .value_name("FORMAT")
+        .help("Output format: table, json or yaml")
+        .default_value("table")
+        .value_parser(output::parse_format)
+}
+
+/// Server URL and client from `--url`/`--api-key`
+#[cfg(feature = "client")]
+fn connect(matches: &ArgMatches, sub_matches: &ArgMatches) -> Result<(String, client::ApiClient), CliError> {
+    let url = match sub_matches.get_one::<String>("url") {
+        Some(url) => url.clone(),
+        None => default_url(matches)?,
+    };
+    let api_key = sub_matches
+        .get_one::<String>("api-key")
+        .cloned()
+        .or_else(|| std::env::var("TASK_PROCESSOR_API_KEY").ok());
+    Ok((url.clone(), client::ApiClient::new(url, api_key)))
+}
+
+/// `http(s)://<bind_address>:<orchestrator_port>` from the configuration (loopback
+/// when bound to every interface), or the address in `port_file` when the
+/// server was started on port 0
+fn default_url(matches: &ArgMatches) -> Result<String, CliError> {
+    let config = load_configuration(matches).map_err(|e| {
+        CliError::new(ErrorKind::Validation, format!("Failed to load configuration: {}", e))
+    })?;
+    if config.orchestrator.orchestrator_port != 0 {
+        return Ok(format!("{}://{}", config.orchestrator.url_scheme(), config.orchestrator.connect_addr()));
+    }
+    let Some(path) = &config.orchestrator.port_file else {
+        return Err(CliError::new(
+            ErrorKind::Validation,
+            "orchestrator_port is 0 and no port_file is configured; pass --url",
+        ));
+    };
+    let addr = orchestrator::read_port_file(path).map_err(|e| {
+        CliError::new(ErrorKind::Unreachable, format!("Cannot read port file {}: {}", path.display(), e))
+    })?;
+    Ok(format!("{}://{}", config.orchestrator.url_scheme(), addr))
+}
+
+/// Ask a yes/no question on stderr; anything but "y"/"yes" (including EOF) is no
+fn confirm(prompt: &str) -> bool {
+    use std::io::Write;
+
+    eprint!("{} [y/N] ", prompt);
+    let _ = std::io::stderr().flush();
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
 /// Load configuration from file and CLI arguments
 fn load_configuration(matches: &ArgMatches) -> Result<AppConfig, ConfigError> {
     let mut config_builder = Config::builder();
 
     // Load from config file if specified
+    let mut config_file = None;
     if let Some(config_path) = matches.get_one::<String>("config") {
         let path = PathBuf::from(config_path);
         if path.exists() {
             info!("Loading configuration from: {}", config_path);
-            config_builder = config_builder.add_source(File::from(path));
+            config_file = Some(path);
         } else {
             warn!("Config file not found: {}", config_path);
         }
     } else {
         // Try to load default config files
-        for config_file in &["config.toml", "config.json"] {
-            let path = PathBuf::from(config_file);
+        for default_file in &["config.toml", "config.json"] {
+            let path = PathBuf::from(default_file);
             if path.exists() {
-                info!("Loading default configuration from: {}", config_file);
-                config_builder = config_builder.add_source(File::from(path));
+                info!("Loading default configuration from: {}", default_file);
+                config_file = Some(path);
                 break;
             }
         }
     }
 
+    // Then the profile's file on top
+    let profile = selected_profile(matches).map_err(ConfigError::Message)?;
+    let files = config_files(config_file, profile.as_deref()).map_err(ConfigError::Message)?;
+    for path in &files {
+        config_builder = config_builder.add_source(File::from(path.clone()));
+    }
+
     // Override with command line arguments
-    if let Some(&workers) = matches.get_one::<usize>("workers") {
-        config_builder = config_builder.set_override("num_workers", workers as i64)?;
+    if let Some(&workers) = matches.get_one::<Option<usize>>("workers") {
+        config_builder = match workers {
+            Some(workers) => config_builder.set_override("num_workers", workers as i64)?,
+            None => config_builder.set_override("num_workers", sizing::AUTO)?,
+        };
     }
     
-    if let Some(&threads) = matches.get_one::<usize>("threads") {
-        config_builder = config_builder.set_override("threads_per_worker", threads as i64)?;
+    if let Some(&threads) = matches.get_one::<Option<usize>>("threads") {
+        config_builder = match threads {
+            Some(threads) => config_builder.set_override("threads_per_worker", threads as i64)?,
+            None => config_builder.set_override("threads_per_worker", sizing::AUTO)?,
+        };
     }
     
     if let Some(&orchestrator_port) = matches.get_one::<u16>("orchestrator-port") {
         config_builder = config_builder.set_override("orchestrator_port", orchestrator_port as i64)?;
     }
 
+    if let Some(bind) = matches.get_one::<std::net::IpAddr>("bind") {
+        config_builder = config_builder.set_override("bind_address", bind.to_string())?;
+    }
+
+    if let Some(port_file) = matches.get_one::<String>("port-file") {
+        config_builder = config_builder.set_override("port_file", port_file.as_str())?;
+    }
+
+    if let Some(&base_port) = matches.get_one::<u16>("base-port") {
+        config_builder = config_builder.set_override("base_port", base_port as i64)?;
+    }
+
+    if let Some(primary) = matches.get_one::<String>("primary") {
+        config_builder = config_builder.set_override("primary_url", primary.as_str())?;
+    }
+
     if let Some(log_level) = matches.get_one::<String>("log-level") {
         config_builder = config_builder.set_override("log_level", log_level.as_str())?;
     }
 
-    let config = config_builder.build()?.try_deserialize::<AppConfig>()?;
+    // Replace "auto" worker/thread counts with values sized to the available CPUs
+    config_builder = resolve_auto_sizing(config_builder)?;
+
+    let mut config = config_builder.build()?.try_deserialize::<AppConfig>()?;
     
     // Validate configuration
-    config.orchestrator.validate().map_err(|e| {
-        ConfigError::Message(format!("Configuration validation failed: {}", e))
+    config.orchestrator.validate().map_err(|errors| {
+        let errors = errors.with_sources(|key| config_source(matches, &files, key));
+        ConfigError::Message(format!("Configuration validation failed: {}", errors))
     })?;
 
+    config.profile = profile;
+    config.files = files;
     Ok(config)
 }
 
+/// `--profile`, or `$TASK_PROCESSOR_PROFILE` when not given
+fn selected_profile(matches: &ArgMatches) -> Result<Option<String>, String> {
+    if let Some(profile) = matches.get_one::<String>("profile") {
+        return Ok(Some(profile.clone()));
+    }
+    match std::env::var(PROFILE_ENV) {
+        Ok(profile) if !profile.is_empty() => parse_profile(&profile)
+            .map(Some)
+            .map_err(|e| format!("{}: {}", PROFILE_ENV, e)),
+        _ => Ok(None),
+    }
+}
+
+/// Profile names end up in file names, so keep them to letters, digits, '-' and '_'
+fn parse_profile(value: &str) -> Result<String, String> {
+    let valid = !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(value.to_string())
+    } else {
+        Err(format!("invalid profile '{}', use letters, digits, '-' or '_'", value))
+    }
+}
+
+/// The profile's file beside the base one: `config.toml` -> `config.prod.toml`
+fn profile_path(base: &Path, profile: &str) -> PathBuf {
+    let stem = base.file_stem().and_then(|stem| stem.to_str()).unwrap_or("config");
+    let name = match base.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) => format!("{}.{}.{}", stem, profile, extension),
+        None => format!("{}.{}", stem, profile),
+    };
+    base.with_file_name(name)
+}
+
+/// Config files to merge in order: the base file (if any), then the profile's file
+///
+/// A selected profile must have a file; without a base file it is looked up
+/// as `config.<profile>.toml` or `config.<profile>.json`.
+fn config_files(base: Option<PathBuf>, profile: Option<&str>) -> Result<Vec<PathBuf>, String> {
+    let Some(profile) = profile else {
+        return Ok(base.into_iter().collect());
+    };
+    let candidates = match &base {
+        Some(base) => vec![profile_path(base, profile)],
+        None => vec![
+            profile_path(Path::new("config.toml"), profile),
+            profile_path(Path::new("config.json"), profile),
+        ],
+    };
+    let Some(profile_file) = candidates.iter().find(|path| path.exists()) else {
+        return Err(format!(
+            "Profile '{}' selected but {} does not exist",
+            profile,
+            candidates[0].display()
+        ));
+    };
+    Ok(base.into_iter().chain(std::iter::once(profile_file.clone())).collect())
+}
+
+/// Log the profile, merged files and resulting settings (secrets are never serialized)
+fn log_effective_config(config: &AppConfig) {
+    let files: Vec<String> = config.files.iter().map(|path| path.display().to_string()).collect();
+    info!(
+        "Configuration profile: {}; files: {}",
+        config.profile.as_deref().unwrap_or("none"),
+        if files.is_empty() { "none (defaults)".to_string() } else { files.join(" + ") }
+    );
+    match serde_json::to_string(config) {
+        Ok(effective) => info!("Effective configuration: {}", effective),
+        Err(e) => warn!("Cannot serialize effective configuration: {}", e),
+    }
+}
+
+/// CLI flags that override config keys, as (flag, key)
+const CLI_OVERRIDES: [(&str, &str); 8] = [
+    ("workers", "num_workers"),
+    ("threads", "threads_per_worker"),
+    ("orchestrator-port", "orchestrator_port"),
+    ("bind", "bind_address"),
+    ("port-file", "port_file"),
+    ("base-port", "base_port"),
+    ("primary", "primary_url"),
+    ("log-level", "log_level"),
+];
+
+/// Where a top-level config key got its value: a CLI flag, a config file, or the default
+fn config_source(matches: &ArgMatches, files: &[PathBuf], key: &str) -> Option<String> {
+    let flag = CLI_OVERRIDES
+        .iter()
+        .find(|(flag, override_key)| {
+            *override_key == key && matches.value_source(flag) == Some(clap::parser::ValueSource::CommandLine)
+        })
+        .map(|(flag, _)| format!("--{}", flag));
+    if flag.is_some() {
+        return flag;
+    }
+    file_source(files, key)
+}
+
+/// The last of `files` that sets `key`, otherwise "default"
+fn file_source(files: &[PathBuf], key: &str) -> Option<String> {
+    let file = files.iter().rev().find(|path| {
+        Config::builder()
+            .add_source(File::from(path.as_path()))
+            .build()
+            .is_ok_and(|file| file.get::<config::Value>(key).is_ok())
+    });
+    match file {
+        Some(path) => Some(path.display().to_string()),
+        None => Some("default".to_string()),
+    }
+}
+
+/// Resolve `num_workers = "auto"` / `threads_per_worker = "auto"` into numbers
+///
+/// Either left unset is automatic too, so the server never starts more
+/// threads than the CPUs it may use.
+fn resolve_auto_sizing(
+    config_builder: config::ConfigBuilder<config::builder::DefaultState>,
+) -> Result<config::ConfigBuilder<config::builder::DefaultState>, ConfigError> {
+    let current = config_builder.build_cloned()?;
+
+    let read_count = |key: &str| -> Result<Option<usize>, ConfigError> {
+        match current.get_string(key) {
+            Ok(value) => sizing::parse_count_or_auto(&value)
+                .map_err(|e| ConfigError::Message(format!("{}: {}", key, e))),
+            Err(ConfigError::NotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    };
+
+    let workers = read_count("num_workers")?;
+    let threads = read_count("threads_per_worker")?;
+    if workers.is_some() && threads.is_some() {
+        return Ok(config_builder);
+    }
+
+    let cpus = sizing::available_cpus();
+    let (workers, threads) = sizing::resolve_layout(workers, threads, cpus);
+    info!(
+        "Auto-sized to {} workers x {} threads for {} available CPUs",
+        workers, threads, cpus
+    );
+
+    config_builder
+        .set_override("num_workers", workers as i64)?
+        .set_override("threads_per_worker", threads as i64)
+}
+
 /// Setup logging based on configuration
 fn setup_logging(log_level: &str) -> Result<(), Box<dyn std::error::Error>> {
     let level = match log_level.to_lowercase().as_str() {
@@ -192,11 +1310,17 @@ fn setup_logging(log_level: &str) -> Result<(), Box<dyn std::error::Error>> {
         _ => tracing::Level::INFO,
     };
 
-    tracing_subscriber::registry()
+    let registry = tracing_subscriber::registry();
+
+    // tokio-console needs the binary built with RUSTFLAGS="--cfg tokio_unstable"
+    #[cfg(feature = "tokio-console")]
+    let registry = registry.with(console_subscriber::spawn());
+
+    registry
         .with(
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| {
-                    format!("task_processing_system={},tower_http=debug,warp=info", level).into()
+                    format!("task_processor={level},task_processing_system_rs={level},tower_http=debug,warp=info", level = level).into()
                 }),
         )
         .with(tracing_subscriber::fmt::layer().with_target(false))
@@ -214,19 +1338,62 @@ fn print_system_info(config: &AppConfig) {
     println!("  Total processing threads: {}", 
              config.orchestrator.num_workers * config.orchestrator.threads_per_worker);
     println!("  Orchestrator port: {}", config.orchestrator.orchestrator_port);
+    println!("  Bind address: {}", config.orchestrator.bind_address);
+    for listener in &config.orchestrator.listeners {
+        let scheme = if listener.tls.is_some() { "https" } else { "http" };
+        println!("  Also listening on: {}://{}", scheme, listener.address);
+    }
+    if let Some(path) = &config.orchestrator.port_file {
+        println!("  Port file: {}", path.display());
+    }
     println!("  Log level: {}", config.log_level);
+    if let Some(profile) = &config.profile {
+        let files: Vec<String> = config.files.iter().map(|path| path.display().to_string()).collect();
+        println!("  Profile: {} ({})", profile, files.join(" + "));
+    }
+    if let Some(primary) = &config.orchestrator.primary_url {
+        println!("  Mode: read-only replica (primary: {})", primary);
+    }
+    if !config.orchestrator.api_keys.is_empty() {
+        println!("  API keys: {} (namespace-scoped, /health open)", config.orchestrator.api_keys.len());
+    }
     println!();
 
-    println!("Workers: {} internal processing nodes (not externally accessible)", config.orchestrator.num_workers);
+    match config.orchestrator.worker_port_range() {
+        Some((first, last)) => {
+            println!("Workers: {} internal processing nodes", config.orchestrator.num_workers);
+            println!("  Diagnostics: http://127.0.0.1:{}-{} (GET /stats, GET /queue)", first, last);
+        }
+        None => println!("Workers: {} internal processing nodes (not externally accessible)", config.orchestrator.num_workers),
+    }
     println!();
 
     println!("Orchestrator Endpoints:");
-    println!("  API Base: http://127.0.0.1:{}", config.orchestrator.orchestrator_port);
+    println!("  API Base: {}://{}", config.orchestrator.url_scheme(), config.orchestrator.connect_addr());
     println!("  Health: GET /health");
+    println!("  Probes: GET /healthz (liveness), GET /readyz (readiness)");
     println!("  Create Task: POST /task/create");
+    println!("  Execute Task: POST /task/execute (max_wait_ms)");
     println!("  Get Task: GET /task/{{id}}");
+    println!("  List Tasks: GET /tasks?status=&limit=&offset=&sort=");
+    println!("  Changes: GET /changes?since=<sequence>&limit=");
+    println!("  Live Events: GET /ws/events (WebSocket)");
+    println!("  Stream Result: GET /task/{{id}}/result/stream");
+    println!("  Wait for Task: GET /task/{{id}}/wait?timeout_ms=");
     println!("  Complete Task: POST /task/{{id}}/complete");
+    println!("  Cancel Task: POST /task/{{id}}/cancel");
     println!("  Statistics: GET /stats");
+    println!("  Stats Delta: GET /stats/delta?since=<token>");
+    println!("  Stats Stream: GET /stats/stream (server-sent events)");
+    println!("  Runtime Stats: GET /stats/runtime");
+    println!("  Webhook Stats: GET /admin/webhooks");
+    if !config.orchestrator.federation.peers.is_empty() {
+        println!("  Federation: GET /admin/federation ({} peers)", config.orchestrator.federation.peers.len());
+    }
+    println!("  Webhook Subscriptions: POST/GET /subscriptions, GET/PUT/DELETE /subscriptions/{{id}}");
+    println!("  Worker Admin: GET /admin/workers, GET /admin/limits, GET /admin/audit, POST /task/{{id}}/priority, POST /admin/workers/{{id}}/pause|resume, /admin/pause|resume, /workers/{{id}}/pause|resume, /admin/scale, /admin/purge, /admin/rebalance");
+    println!("  Metrics: GET /metrics");
+    println!("  Examples: GET /examples");
     println!();
 
     print_task_workflow_info();
@@ -309,12 +1476,27 @@ async fn wait_for_shutdown_signal() {
             .await;
     };
 
-    #[cfg(not(unix))]
+    // Console windows get close/shutdown/logoff as control events; services
+    // under the Windows SCM get stop requests through `service::stop_requested`
+    #[cfg(windows)]
+    let terminate = async {
+        let mut close = signal::windows::ctrl_close().expect("failed to install close handler");
+        let mut shutdown = signal::windows::ctrl_shutdown().expect("failed to install shutdown handler");
+        let mut logoff = signal::windows::ctrl_logoff().expect("failed to install logoff handler");
+        tokio::select! {
+            _ = close.recv() => {},
+            _ = shutdown.recv() => {},
+            _ = logoff.recv() => {},
+        }
+    };
+
+    #[cfg(not(any(unix, windows)))]
     let terminate = std::future::pending::<()>();
 
     tokio::select! {
         _ = ctrl_c => {},
         _ = terminate => {},
+        _ = service::stop_requested() => {},
     }
 }
 
@@ -343,5 +1525,163 @@ mod tests {
         assert!(args.contains(&&clap::Id::from("orchestrator-port")));
         assert!(args.contains(&&clap::Id::from("config")));
         assert!(args.contains(&&clap::Id::from("log-level")));
+        assert!(args.contains(&&clap::Id::from("service")));
+        assert!(cli.find_subcommand("bench-calculations").is_some());
+    }
+
+    #[test]
+    fn test_config_source() {
+        let matches = create_cli().try_get_matches_from(["task-processor", "--workers", "0"]).unwrap();
+        assert_eq!(config_source(&matches, &[], "num_workers").as_deref(), Some("--workers"));
+        // --log-level has a default value, which doesn't count as given
+        assert_eq!(config_source(&matches, &[], "log_level").as_deref(), Some("default"));
+
+        let path = std::env::temp_dir().join(format!("config-source-{}.toml", std::process::id()));
+        std::fs::write(&path, "threads_per_worker = 0\n").unwrap();
+        let source = config_source(&matches, std::slice::from_ref(&path), "threads_per_worker");
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(source, Some(path.display().to_string()));
+    }
+
+    #[test]
+    fn test_load_config_file() {
+        let path = std::env::temp_dir().join(format!("config-validate-{}.toml", std::process::id()));
+        std::fs::write(&path, "num_workers = \"auto\"\nthreads_per_worker = 2\norchestrator_port = 7100\n").unwrap();
+        let valid = load_config_file(&path, None);
+
+        std::fs::write(&path, "num_workers = 0\nthreads_per_worker = 0\norchestrator_port = 7100\n").unwrap();
+        let invalid = load_config_file(&path, None);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(valid.unwrap().orchestrator.orchestrator_port, 7100);
+        let error = invalid.unwrap_err();
+        assert_eq!(error.exit_code, 2);
+        assert!(error.error.contains("2 problems"));
+        assert_eq!(load_config_file(&path, None).unwrap_err().kind, ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_sizing_defaults_to_auto() {
+        let path = std::env::temp_dir().join(format!("config-auto-{}.toml", std::process::id()));
+        std::fs::write(&path, "orchestrator_port = 7100\n").unwrap();
+        let config = load_config_file(&path, None);
+        std::fs::write(&path, "threads_per_worker = 1\norchestrator_port = 7100\n").unwrap();
+        let one_thread = load_config_file(&path, None);
+        std::fs::remove_file(&path).unwrap();
+
+        let cpus = sizing::available_cpus();
+        let config = config.unwrap().orchestrator;
+        assert_eq!((config.num_workers, config.threads_per_worker), sizing::resolve_layout(None, None, cpus));
+        let config = one_thread.unwrap().orchestrator;
+        assert_eq!((config.num_workers, config.threads_per_worker), sizing::resolve_layout(None, Some(1), cpus));
+    }
+
+    #[test]
+    fn test_profile_layering() {
+        assert_eq!(profile_path(Path::new("deploy/app.toml"), "prod"), PathBuf::from("deploy/app.prod.toml"));
+        assert!(parse_profile("prod-eu_1").is_ok());
+        assert!(parse_profile("../prod").is_err());
+
+        let dir = std::env::temp_dir().join(format!("config-profile-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let base = dir.join("config.toml");
+        std::fs::write(&base, "num_workers = 3\nthreads_per_worker = 4\norchestrator_port = 7000\n").unwrap();
+        std::fs::write(dir.join("config.prod.toml"), "orchestrator_port = 80\n").unwrap();
+
+        let plain = load_config_file(&base, None);
+        let prod = load_config_file(&base, Some("prod"));
+        let missing = load_config_file(&base, Some("staging"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(plain.unwrap().orchestrator.orchestrator_port, 7000);
+        // The profile's port wins and is reported against the profile's file
+        let error = prod.unwrap_err().error;
+        assert!(error.contains("orchestrator_port (from "), "{}", error);
+        assert!(error.contains("config.prod.toml): Invalid port: 80"), "{}", error);
+        assert_eq!(missing.unwrap_err().kind, ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_bind_address() {
+        let matches = create_cli().try_get_matches_from(["task-processor", "--bind", "[::1]"]).unwrap();
+        let config = load_configuration(&matches).unwrap();
+        assert_eq!(config.orchestrator.bind_address, "::1".parse::<std::net::IpAddr>().unwrap());
+        assert_eq!(config.orchestrator.connect_addr().to_string(), "[::1]:7000");
+        assert!(create_cli().try_get_matches_from(["task-processor", "--bind", "[::1]:7000"]).is_err());
+
+        let path = std::env::temp_dir().join(format!("config-bind-{}.toml", std::process::id()));
+        std::fs::write(&path, "num_workers = 1\nthreads_per_worker = 1\norchestrator_port = 7100\nbind_address = \"::\"\n").unwrap();
+        let config = load_config_file(&path, None);
+        std::fs::remove_file(&path).unwrap();
+        let config = config.unwrap().orchestrator;
+        assert_eq!(config.listen_addr().to_string(), "[::]:7100");
+        assert_eq!(config.connect_addr().to_string(), "[::1]:7100");
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn test_client_subcommands() {
+        // `serve` takes the server options, like running without a subcommand
+        let matches = create_cli().try_get_matches_from(["task-processor", "serve", "--workers", "2"]).unwrap();
+        let (_, serve_matches) = matches.subcommand().unwrap();
+        assert_eq!(config_source(serve_matches, &[], "num_workers").as_deref(), Some("--workers"));
+
+        let matches = create_cli().try_get_matches_from(["task-processor", "status", "task-1"]).unwrap();
+        assert_eq!(matches.subcommand_name(), Some("get"));
+
+        let matches = create_cli()
+            .try_get_matches_from(["task-processor", "submit", "gcd", "12", "--input2", "18", "--priority", "high", "--wait"])
+            .unwrap();
+        let (_, submit_matches) = matches.subcommand().unwrap();
+        assert_eq!(submit_matches.get_one::<Operation>("operation"), Some(&Operation::Gcd));
+        assert_eq!(submit_matches.get_one::<u64>("input2"), Some(&18));
+        assert!(submit_matches.get_flag("wait"));
+        assert!(create_cli().try_get_matches_from(["task-processor", "submit", "square", "4"]).is_err());
+
+        assert!(create_cli().try_get_matches_from(["task-processor", "complete", "task-1"]).is_ok());
+        assert!(create_cli().try_get_matches_from(["task-processor", "watch", "task-1", "--interval", "5"]).is_err());
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn test_status_filters() {
+        for (arg, status) in [("scheduled", TaskStatus::Scheduled), ("blocked", TaskStatus::Blocked), ("failed", TaskStatus::Failed)] {
+            let matches = create_cli().try_get_matches_from(["task-processor", "list", "--status", arg]).unwrap();
+            let (_, list_matches) = matches.subcommand().unwrap();
+            assert_eq!(list_matches.get_one::<TaskStatus>("status"), Some(&status));
+        }
+        assert!(create_cli().try_get_matches_from(["task-processor", "list", "--status", "compleetd"]).is_err());
+
+        let matches = create_cli()
+            .try_get_matches_from(["task-processor", "admin", "purge", "--status", "cancelled"])
+            .unwrap();
+        let (_, admin_matches) = matches.subcommand().unwrap();
+        let (_, purge_matches) = admin_matches.subcommand().unwrap();
+        assert_eq!(purge_matches.get_one::<TaskStatus>("status"), Some(&TaskStatus::Cancelled));
+        assert!(create_cli().try_get_matches_from(["task-processor", "admin", "purge", "--status", "pending"]).is_err());
+        assert!(create_cli().try_get_matches_from(["task-processor", "admin", "purge", "--status", "bogus"]).is_err());
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn test_watch_line() {
+        let mut task = Task::new("Watched".to_string(), TaskPriority::Medium, TaskData::new(10, Operation::Factorial));
+        assert!(watch_line(&task).ends_with("  pending"));
+        task.set_processing("3628800".to_string());
+        assert!(watch_line(&task).ends_with("  processing  3628800"));
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn test_json_errors_is_global() {
+        let matches = create_cli()
+            .try_get_matches_from(["task-processor", "get", "task-1", "--json-errors"])
+            .unwrap();
+        assert!(matches.get_flag("json-errors"));
+
+        let matches = create_cli()
+            .try_get_matches_from(["task-processor", "--json-errors", "admin", "workers"])
+            .unwrap();
+        assert!(matches.get_flag("json-errors"));
     }
 }
\ No newline at end of file
@@ -0,0 +1,119 @@
+//! Overflow-safe arithmetic helpers shared by the calculator operations
+//!
+//! Every helper either widens to `u128` internally or reports overflow with
+//! `None`, so operations never wrap silently.
+
+/// Multiply every value together, returning `None` on overflow
+///
+/// An empty iterator yields 1.
+pub fn checked_product<I>(values: I) -> Option<u128>
+where
+    I: IntoIterator<Item = u128>,
+{
+    values
+        .into_iter()
+        .try_fold(1u128, |acc, value| acc.checked_mul(value))
+}
+
+/// Add two u64 values, returning `None` on overflow
+pub fn checked_sum(a: u64, b: u64) -> Option<u64> {
+    a.checked_add(b)
+}
+
+/// Full-width product of two u64 values (never overflows)
+pub fn widening_mul(a: u64, b: u64) -> u128 {
+    a as u128 * b as u128
+}
+
+/// Compute `(a * b) % modulus` without intermediate overflow
+///
+/// Panics if `modulus` is zero; use `checked_pow_mod` for untrusted moduli.
+pub fn mul_mod(a: u64, b: u64, modulus: u64) -> u64 {
+    (widening_mul(a, b) % modulus as u128) as u64
+}
+
+/// Compute `base^exp % modulus`, returning `None` for a zero modulus
+pub fn checked_pow_mod(base: u64, exp: u64, modulus: u64) -> Option<u64> {
+    if modulus == 0 {
+        return None;
+    }
+    if modulus == 1 {
+        return Some(0);
+    }
+
+    let mut result = 1;
+    let mut base = base % modulus;
+    let mut exp = exp;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, modulus);
+        }
+        base = mul_mod(base, base, modulus);
+        exp >>= 1;
+    }
+    Some(result)
+}
+
+/// Exact integer square root (largest r with r * r <= n)
+///
+/// Avoids the rounding error of `(n as f64).sqrt()` for inputs above 2^52.
+pub fn isqrt(n: u64) -> u64 {
+    if n < 2 {
+        return n;
+    }
+
+    // Start from the float estimate and correct it in integer arithmetic
+    let mut r = (n as f64).sqrt() as u64;
+    while widening_mul(r, r) > n as u128 {
+        r -= 1;
+    }
+    while widening_mul(r + 1, r + 1) <= n as u128 {
+        r += 1;
+    }
+    r
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_product_boundaries() {
+        assert_eq!(checked_product(std::iter::empty()), Some(1));
+        assert_eq!(checked_product((1..=20).map(|i| i as u128)), Some(2432902008176640000));
+        assert_eq!(checked_product([u128::MAX, 1]), Some(u128::MAX));
+        assert_eq!(checked_product([u128::MAX, 2]), None);
+        // 34! is the largest factorial that fits in u128
+        assert!(checked_product((1..=34).map(|i| i as u128)).is_some());
+        assert!(checked_product((1..=35).map(|i| i as u128)).is_none());
+    }
+
+    #[test]
+    fn test_checked_sum_and_widening() {
+        assert_eq!(checked_sum(u64::MAX - 1, 1), Some(u64::MAX));
+        assert_eq!(checked_sum(u64::MAX, 1), None);
+        assert_eq!(widening_mul(u64::MAX, u64::MAX), (u64::MAX as u128) * (u64::MAX as u128));
+    }
+
+    #[test]
+    fn test_checked_pow_mod() {
+        assert_eq!(checked_pow_mod(2, 10, 1000), Some(24));
+        assert_eq!(checked_pow_mod(5, 0, 7), Some(1));
+        assert_eq!(checked_pow_mod(5, 3, 1), Some(0));
+        assert_eq!(checked_pow_mod(5, 3, 0), None);
+        // Fermat: a^(p-1) = 1 mod p for the largest u64 prime
+        let p = 18446744073709551557;
+        assert_eq!(checked_pow_mod(u64::MAX, p - 1, p), Some(1));
+    }
+
+    #[test]
+    fn test_isqrt_boundaries() {
+        assert_eq!(isqrt(0), 0);
+        assert_eq!(isqrt(1), 1);
+        assert_eq!(isqrt(15), 3);
+        assert_eq!(isqrt(16), 4);
+        assert_eq!(isqrt(u64::MAX), 4294967295);
+        assert_eq!(isqrt(4294967295 * 4294967295), 4294967295);
+        assert_eq!(isqrt(4294967295 * 4294967295 - 1), 4294967294);
+    }
+}
@@ -0,0 +1,437 @@
+//! HTTP counters for `GET /metrics`, and the [`MetricsSink`] trait that
+//! forwards the same events (plus per-request and task metrics) to an
+//! embedder's telemetry
+//!
+//! The orchestrator reports to a [`NoopSink`] unless one is injected with
+//! `TaskOrchestrator::with_metrics_sink` or `statsd` is configured.
+//! [`PrometheusSink`] keeps everything in memory for embedders that serve
+//! their own exposition endpoint; [`StatsdSink`] pushes over UDP.
+
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::debug;
+
+/// Destination for counters, gauges and histograms
+///
+/// Labels are `(name, value)` pairs; durations are recorded in milliseconds.
+/// Implementations are called on request paths, so they should not block.
+pub trait MetricsSink: Send + Sync {
+    /// Add `value` to a monotonically increasing counter
+    fn counter(&self, name: &str, labels: &[(&str, &str)], value: u64);
+    /// Set a gauge to its current value
+    fn gauge(&self, name: &str, labels: &[(&str, &str)], value: f64);
+    /// Record one observation
+    fn histogram(&self, name: &str, labels: &[(&str, &str)], value: f64);
+}
+
+/// Discards everything (the default sink)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopSink;
+
+impl MetricsSink for NoopSink {
+    fn counter(&self, _name: &str, _labels: &[(&str, &str)], _value: u64) {}
+    fn gauge(&self, _name: &str, _labels: &[(&str, &str)], _value: f64) {}
+    fn histogram(&self, _name: &str, _labels: &[(&str, &str)], _value: f64) {}
+}
+
+/// Upper bounds of the [`PrometheusSink`] histogram buckets, in milliseconds
+pub const HISTOGRAM_BUCKETS_MS: [f64; 12] = [1.0, 2.5, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0];
+
+#[derive(Debug)]
+enum Series {
+    Counter(u64),
+    Gauge(f64),
+    Histogram {
+        buckets: [u64; HISTOGRAM_BUCKETS_MS.len()],
+        sum: f64,
+        count: u64,
+    },
+}
+
+impl Series {
+    fn kind(&self) -> &'static str {
+        match self {
+            Series::Counter(_) => "counter",
+            Series::Gauge(_) => "gauge",
+            Series::Histogram { .. } => "histogram",
+        }
+    }
+}
+
+/// In-memory registry rendered in the Prometheus text exposition format
+///
+/// A name keeps the kind it was first recorded with; later calls of another
+/// kind under the same name are ignored.
+#[derive(Debug, Default)]
+pub struct PrometheusSink {
+    series: Mutex<BTreeMap<(String, String), Series>>,
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// `a="1",b="2"` (empty without labels)
+fn render_labels(labels: &[(&str, &str)]) -> String {
+    labels
+        .iter()
+        .map(|(name, value)| format!("{}=\"{}\"", name, escape_label(value)))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// `{labels}` with `extra` appended, or nothing when both are empty
+fn braced(labels: &str, extra: &str) -> String {
+    match (labels.is_empty(), extra.is_empty()) {
+        (true, true) => String::new(),
+        (false, true) => format!("{{{}}}", labels),
+        (true, false) => format!("{{{}}}", extra),
+        (false, false) => format!("{{{},{}}}", labels, extra),
+    }
+}
+
+impl PrometheusSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply `apply` to the series, starting from `empty` if it is new
+    fn update(&self, name: &str, labels: &[(&str, &str)], empty: Series, apply: impl FnOnce(&mut Series)) {
+        let mut series = self.series.lock();
+        let kind = empty.kind();
+        if series.iter().any(|((existing, _), s)| existing == name && s.kind() != kind) {
+            return;
+        }
+        apply(series.entry((name.to_string(), render_labels(labels))).or_insert(empty));
+    }
+
+    /// Every recorded series, grouped by name
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let mut current = None;
+        for ((name, labels), series) in self.series.lock().iter() {
+            if current != Some(name) {
+                let _ = writeln!(out, "# TYPE {} {}", name, series.kind());
+                current = Some(name);
+            }
+            match series {
+                Series::Counter(value) => {
+                    let _ = writeln!(out, "{}{} {}", name, braced(labels, ""), value);
+                }
+                Series::Gauge(value) => {
+                    let _ = writeln!(out, "{}{} {}", name, braced(labels, ""), value);
+                }
+                Series::Histogram { buckets, sum, count } => {
+                    let mut cumulative = 0;
+                    for (bound, bucket) in HISTOGRAM_BUCKETS_MS.iter().zip(buckets) {
+                        cumulative += bucket;
+                        let le = format!("le=\"{}\"", bound);
+                        let _ = writeln!(out, "{}_bucket{} {}", name, braced(labels, &le), cumulative);
+                    }
+                    let _ = writeln!(out, "{}_bucket{} {}", name, braced(labels, "le=\"+Inf\""), count);
+                    let _ = writeln!(out, "{}_sum{} {}", name, braced(labels, ""), sum);
+                    let _ = writeln!(out, "{}_count{} {}", name, braced(labels, ""), count);
+                }
+            }
+        }
+        out
+    }
+}
+
+impl MetricsSink for PrometheusSink {
+    fn counter(&self, name: &str, labels: &[(&str, &str)], value: u64) {
+        self.update(name, labels, Series::Counter(0), |series| {
+            if let Series::Counter(total) = series {
+                *total += value;
+            }
+        });
+    }
+
+    fn gauge(&self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.update(name, labels, Series::Gauge(0.0), |series| {
+            if let Series::Gauge(current) = series {
+                *current = value;
+            }
+        });
+    }
+
+    fn histogram(&self, name: &str, labels: &[(&str, &str)], value: f64) {
+        let empty = Series::Histogram {
+            buckets: [0; HISTOGRAM_BUCKETS_MS.len()],
+            sum: 0.0,
+            count: 0,
+        };
+        self.update(name, labels, empty, |series| {
+            if let Series::Histogram { buckets, sum, count } = series {
+                if let Some(index) = HISTOGRAM_BUCKETS_MS.iter().position(|bound| value <= *bound) {
+                    buckets[index] += 1;
+                }
+                *sum += value;
+                *count += 1;
+            }
+        });
+    }
+}
+
+/// Pushes each metric as a statsd line over UDP (`c`, `g` and `ms` types)
+///
+/// Labels are sent as DogStatsD tags (`|#route:/stats`), which Datadog,
+/// Telegraf and the statsd exporter understand. Sends never block; a full
+/// socket buffer or an unreachable daemon drops the line.
+#[derive(Debug)]
+pub struct StatsdSink {
+    socket: UdpSocket,
+    prefix: String,
+}
+
+impl StatsdSink {
+    /// Send to `address` (`host:port`), prefixing every name with `prefix.` (unless empty)
+    pub fn new(address: &str, prefix: &str) -> io::Result<Self> {
+        let target = address
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{} resolves to no address", address)))?;
+        let local: SocketAddr = if target.is_ipv4() {
+            (Ipv4Addr::UNSPECIFIED, 0).into()
+        } else {
+            (Ipv6Addr::UNSPECIFIED, 0).into()
+        };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(target)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            prefix: prefix.to_string(),
+        })
+    }
+
+    fn line(&self, name: &str, value: &str, kind: &str, labels: &[(&str, &str)]) -> String {
+        let mut line = String::new();
+        if !self.prefix.is_empty() {
+            let _ = write!(line, "{}.", self.prefix);
+        }
+        let _ = write!(line, "{}:{}|{}", name, value, kind);
+        for (index, (label, value)) in labels.iter().enumerate() {
+            let _ = write!(line, "{}{}:{}", if index == 0 { "|#" } else { "," }, label, value);
+        }
+        line
+    }
+
+    fn send(&self, name: &str, value: &str, kind: &str, labels: &[(&str, &str)]) {
+        if let Err(e) = self.socket.send(self.line(name, value, kind, labels).as_bytes()) {
+            debug!("Dropped statsd metric {}: {}", name, e);
+        }
+    }
+}
+
+impl MetricsSink for StatsdSink {
+    fn counter(&self, name: &str, labels: &[(&str, &str)], value: u64) {
+        self.send(name, &value.to_string(), "c", labels);
+    }
+
+    fn gauge(&self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.send(name, &value.to_string(), "g", labels);
+    }
+
+    fn histogram(&self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.send(name, &value.to_string(), "ms", labels);
+    }
+}
+
+/// HTTP-level counters exposed on `GET /metrics`, also forwarded to a [`MetricsSink`]
+pub struct HttpMetrics {
+    rejections: DashMap<&'static str, AtomicU64>,
+    slow_requests: AtomicU64,
+    sink: Arc<dyn MetricsSink>,
+}
+
+impl Default for HttpMetrics {
+    fn default() -> Self {
+        Self::with_sink(Arc::new(NoopSink))
+    }
+}
+
+impl HttpMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counters that are also reported to `sink`
+    pub fn with_sink(sink: Arc<dyn MetricsSink>) -> Self {
+        Self {
+            rejections: DashMap::new(),
+            slow_requests: AtomicU64::new(0),
+            sink,
+        }
+    }
+
+    /// Count a rejected request under the given reason
+    pub fn record_rejection(&self, reason: &'static str) {
+        self.rejections
+            .entry(reason)
+            .or_insert_with(|| AtomicU64::new(0))
+            .fetch_add(1, Ordering::Relaxed);
+        self.sink.counter("http_rejections_total", &[("reason", reason)], 1);
+    }
+
+    /// Report a finished request to the sink (`route` as normalized by the orchestrator)
+    pub fn record_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        let status = status.to_string();
+        self.sink.counter(
+            "http_requests_total",
+            &[("method", method), ("route", route), ("status", &status)],
+            1,
+        );
+        self.sink.histogram(
+            "http_request_duration_ms",
+            &[("method", method), ("route", route)],
+            elapsed.as_secs_f64() * 1000.0,
+        );
+    }
+
+    /// Number of rejections recorded for a reason
+    pub fn rejection_count(&self, reason: &str) -> u64 {
+        self.rejections
+            .get(reason)
+            .map(|count| count.load(Ordering::Relaxed))
+            .unwrap_or(0)
+    }
+
+    /// Count a request that exceeded the slow-request threshold
+    pub fn record_slow_request(&self) {
+        self.slow_requests.fetch_add(1, Ordering::Relaxed);
+        self.sink.counter("slow_requests_total", &[], 1);
+    }
+
+    /// Number of slow requests seen so far
+    pub fn slow_request_count(&self) -> u64 {
+        self.slow_requests.load(Ordering::Relaxed)
+    }
+
+    /// Render all counters in the Prometheus text exposition format
+    pub fn render_prometheus(&self) -> String {
+        let mut out = String::new();
+
+        let mut rejections: Vec<(&'static str, u64)> = self
+            .rejections
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().load(Ordering::Relaxed)))
+            .collect();
+        rejections.sort_unstable();
+
+        let _ = writeln!(out, "# HELP http_rejections_total Rejected HTTP requests by reason");
+        let _ = writeln!(out, "# TYPE http_rejections_total counter");
+        for (reason, count) in rejections {
+            let _ = writeln!(out, "http_rejections_total{{reason=\"{}\"}} {}", reason, count);
+        }
+
+        let _ = writeln!(out, "# HELP slow_requests_total HTTP requests slower than the configured threshold");
+        let _ = writeln!(out, "# TYPE slow_requests_total counter");
+        let _ = writeln!(out, "slow_requests_total {}", self.slow_request_count());
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejection_counters() {
+        let metrics = HttpMetrics::new();
+        metrics.record_rejection("not_found");
+        metrics.record_rejection("not_found");
+        metrics.record_rejection("invalid_body");
+
+        assert_eq!(metrics.rejection_count("not_found"), 2);
+        assert_eq!(metrics.rejection_count("invalid_body"), 1);
+        assert_eq!(metrics.rejection_count("method_not_allowed"), 0);
+
+        let text = metrics.render_prometheus();
+        assert!(text.contains("http_rejections_total{reason=\"invalid_body\"} 1"));
+        assert!(text.contains("http_rejections_total{reason=\"not_found\"} 2"));
+    }
+
+    #[test]
+    fn test_slow_request_counter() {
+        let metrics = HttpMetrics::new();
+        assert!(metrics.render_prometheus().contains("slow_requests_total 0"));
+
+        metrics.record_slow_request();
+        assert_eq!(metrics.slow_request_count(), 1);
+        assert!(metrics.render_prometheus().contains("slow_requests_total 1"));
+    }
+
+    #[test]
+    fn test_prometheus_sink() {
+        let sink = PrometheusSink::new();
+        sink.counter("requests_total", &[("route", "/stats")], 2);
+        sink.counter("requests_total", &[("route", "/stats")], 1);
+        sink.counter("requests_total", &[("route", "/task/{id}")], 1);
+        sink.gauge("queue_depth", &[("worker", "0")], 4.0);
+        sink.gauge("queue_depth", &[("worker", "0")], 3.0);
+        sink.histogram("duration_ms", &[], 0.5);
+        sink.histogram("duration_ms", &[], 30.0);
+        sink.histogram("duration_ms", &[], 9000.0);
+        // A name keeps its first kind
+        sink.gauge("requests_total", &[("route", "/stats")], 9.0);
+
+        let text = sink.render();
+        assert!(text.contains("# TYPE requests_total counter"), "{}", text);
+        assert!(text.contains("requests_total{route=\"/stats\"} 3"), "{}", text);
+        assert!(text.contains("requests_total{route=\"/task/{id}\"} 1"), "{}", text);
+        assert!(text.contains("queue_depth{worker=\"0\"} 3"), "{}", text);
+        assert!(text.contains("duration_ms_bucket{le=\"1\"} 1"), "{}", text);
+        assert!(text.contains("duration_ms_bucket{le=\"50\"} 2"), "{}", text);
+        assert!(text.contains("duration_ms_bucket{le=\"+Inf\"} 3"), "{}", text);
+        assert!(text.contains("duration_ms_sum 9030.5"), "{}", text);
+        assert!(text.contains("duration_ms_count 3"), "{}", text);
+        assert_eq!(text.matches("# TYPE requests_total").count(), 1);
+    }
+
+    #[test]
+    fn test_statsd_sink() {
+        let daemon = UdpSocket::bind("127.0.0.1:0").unwrap();
+        daemon.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+        let sink = StatsdSink::new(&daemon.local_addr().unwrap().to_string(), "tp").unwrap();
+        let mut buffer = [0u8; 256];
+        let mut receive = || {
+            let len = daemon.recv(&mut buffer).unwrap();
+            String::from_utf8_lossy(&buffer[..len]).to_string()
+        };
+
+        sink.counter("requests_total", &[("method", "GET"), ("route", "/stats")], 1);
+        assert_eq!(receive(), "tp.requests_total:1|c|#method:GET,route:/stats");
+        sink.gauge("queue_depth", &[], 2.5);
+        assert_eq!(receive(), "tp.queue_depth:2.5|g");
+        sink.histogram("duration_ms", &[], 12.0);
+        assert_eq!(receive(), "tp.duration_ms:12|ms");
+
+        assert!(StatsdSink::new("not an address", "tp").is_err());
+    }
+
+    #[test]
+    fn test_http_metrics_forward_to_sink() {
+        let sink = Arc::new(PrometheusSink::new());
+        let metrics = HttpMetrics::with_sink(sink.clone());
+        metrics.record_rejection("not_found");
+        metrics.record_slow_request();
+        metrics.record_request("GET", "/stats", 200, Duration::from_millis(3));
+
+        let text = sink.render();
+        assert!(text.contains("http_rejections_total{reason=\"not_found\"} 1"), "{}", text);
+        assert!(text.contains("slow_requests_total 1"), "{}", text);
+        assert!(text.contains("http_requests_total{method=\"GET\",route=\"/stats\",status=\"200\"} 1"), "{}", text);
+        assert!(text.contains("http_request_duration_ms_count{method=\"GET\",route=\"/stats\"} 1"), "{}", text);
+        // The built-in counters are unaffected
+        assert_eq!(metrics.rejection_count("not_found"), 1);
+    }
+}
@@ -0,0 +1,283 @@
+//! Import tasks from a running C++ server (`task-processor migrate-from`)
+//!
+//! The C++ API has no task listing, so task IDs are supplied by the caller.
+//! Each task is fetched with `GET /task/{id}`, converted into this crate's
+//! [`Task`] model, and pending/processing tasks are re-submitted to the local
+//! server with their original ID. Finished tasks are only counted. The HTTP
+//! side, `Migrator`, needs the `client` feature.
+
+use crate::types::*;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+#[cfg(feature = "client")]
+use serde_json::Value;
+
+/// Task as returned by the C++ server's `GET /task/{id}`
+#[derive(Debug, Clone, Deserialize)]
+pub struct CppTask {
+    pub id: String,
+    pub title: String,
+    pub priority: TaskPriority,
+    #[serde(with = "crate::timestamps::timestamp")]
+    pub created_at: DateTime<Utc>,
+    pub data: TaskData,
+    pub status: TaskStatus,
+    #[serde(default)]
+    pub result: Option<String>,
+    /// The C++ server calls `error_message` just `error`
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl CppTask {
+    /// Pending or processing on the C++ side, so it still needs to run here
+    pub fn is_incomplete(&self) -> bool {
+        matches!(self.status, TaskStatus::Pending | TaskStatus::Processing)
+    }
+
+    /// Convert into this crate's task model, keeping status, result and timestamps
+    pub fn into_task(self) -> Task {
+        let mut task = Task::with_id(self.id, self.title, self.priority, self.data);
+        task.created_at = self.created_at;
+        task.status = self.status;
+        task.result = self.result;
+        task.error_message = self.error;
+        task
+    }
+}
+
+/// Request that re-creates an imported task locally under the same ID
+///
+/// The local server recalculates the result, so only the inputs are carried over.
+pub fn resubmission_request(task: &Task) -> CreateTaskRequest {
+    CreateTaskRequest {
+        id: task.id.clone(),
+        title: task.title.clone(),
+        priority: task.priority,
+        data: task.data.clone(),
+        expected_result: None,
+        namespace: None,
+        callback_url: None,
+        run_at: None,
+        delay_seconds: None,
+        idempotency_key: None,
+        depends_on: Vec::new(),
+        labels: task.labels.clone(),
+        timeout_ms: task.timeout_ms,
+    }
+}
+
+/// System statistics as returned by the C++ server's `GET /stats`
+#[derive(Debug, Clone, Deserialize)]
+pub struct CppStats {
+    pub total_tasks_processed: u64,
+    pub total_tasks_completed: u64,
+    pub total_tasks_failed: u64,
+    pub total_workers: usize,
+    pub uptime_seconds: u64,
+}
+
+/// Outcome of a migration run
+#[derive(Debug, Default)]
+pub struct MigrationReport {
+    /// Tasks fetched and converted
+    pub fetched: usize,
+    /// Incomplete tasks enqueued on the local server
+    pub enqueued: Vec<String>,
+    /// Completed or failed tasks left behind
+    pub already_finished: Vec<String>,
+    /// IDs the C++ server didn't know
+    pub not_found: Vec<String>,
+    /// IDs that could not be fetched, converted or enqueued, with the reason
+    pub errors: Vec<(String, String)>,
+}
+
+impl MigrationReport {
+    pub fn is_success(&self) -> bool {
+        self.not_found.is_empty() && self.errors.is_empty()
+    }
+}
+
+/// Parse task IDs, one per line; blank lines and `#` comments are ignored
+pub fn parse_id_list(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(feature = "client")]
+fn migration_error(message: impl Into<String>) -> SystemError {
+    SystemError::Migration {
+        message: message.into(),
+    }
+}
+
+#[cfg(feature = "client")]
+fn endpoint(base: &str, path: &str) -> String {
+    format!("{}{}", base.trim_end_matches('/'), path)
+}
+
+/// HTTP side of a migration: pulls from the C++ server, pushes to the local one
+#[cfg(feature = "client")]
+pub struct Migrator {
+    client: reqwest::Client,
+    source: String,
+    target: String,
+}
+
+#[cfg(feature = "client")]
+impl Migrator {
+    pub fn new(source: impl Into<String>, target: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            source: source.into(),
+            target: target.into(),
+        }
+    }
+
+    /// Fetch system statistics from the C++ server
+    pub async fn fetch_stats(&self) -> Result<CppStats, SystemError> {
+        let response = self
+            .client
+            .get(endpoint(&self.source, "/stats"))
+            .send()
+            .await
+            .map_err(|e| migration_error(format!("GET /stats failed: {}", e)))?;
+        response
+            .json()
+            .await
+            .map_err(|e| migration_error(format!("Invalid /stats response: {}", e)))
+    }
+
+    /// Fetch one task from the C++ server (`None` when it answers 404)
+    pub async fn fetch_task(&self, id: &str) -> Result<Option<CppTask>, SystemError> {
+        let response = self
+            .client
+            .get(endpoint(&self.source, &format!("/task/{}", id)))
+            .send()
+            .await
+            .map_err(|e| migration_error(format!("GET /task/{} failed: {}", id, e)))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        response
+            .json()
+            .await
+            .map(Some)
+            .map_err(|e| migration_error(format!("Cannot convert task {}: {}", id, e)))
+    }
+
+    /// Create the task on the local server
+    pub async fn enqueue(&self, task: &Task) -> Result<(), SystemError> {
+        let body: Value = self
+            .client
+            .post(endpoint(&self.target, "/task/create"))
+            .json(&resubmission_request(task))
+            .send()
+            .await
+            .map_err(|e| migration_error(format!("POST /task/create failed: {}", e)))?
+            .json()
+            .await
+            .map_err(|e| migration_error(format!("Invalid /task/create response: {}", e)))?;
+
+        match body.get("error").and_then(Value::as_str) {
+            Some(error) => Err(migration_error(error)),
+            None => Ok(()),
+        }
+    }
+
+    /// Import the given task IDs
+    pub async fn migrate(&self, ids: &[String]) -> MigrationReport {
+        let mut report = MigrationReport::default();
+
+        for id in ids {
+            let cpp_task = match self.fetch_task(id).await {
+                Ok(Some(task)) => task,
+                Ok(None) => {
+                    report.not_found.push(id.clone());
+                    continue;
+                }
+                Err(e) => {
+                    report.errors.push((id.clone(), e.to_string()));
+                    continue;
+                }
+            };
+            report.fetched += 1;
+
+            let incomplete = cpp_task.is_incomplete();
+            let task = cpp_task.into_task();
+            if !incomplete {
+                report.already_finished.push(task.id);
+                continue;
+            }
+
+            match self.enqueue(&task).await {
+                Ok(()) => report.enqueued.push(task.id),
+                Err(e) => report.errors.push((task.id, e.to_string())),
+            }
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calculations::Calculator;
+
+    const CPP_TASK: &str = r#"{
+        "id": "cpp-1",
+        "title": "From C++",
+        "priority": 3,
+        "created_at": "2024-01-15T10:30:00Z",
+        "data": {"type": "calculation", "input": 10, "operation": "factorial"},
+        "status": "processing",
+        "result": "3628800"
+    }"#;
+
+    #[test]
+    fn test_convert_cpp_task() {
+        let cpp_task: CppTask = serde_json::from_str(CPP_TASK).unwrap();
+        assert!(cpp_task.is_incomplete());
+
+        let task = cpp_task.into_task();
+        assert_eq!(task.id, "cpp-1");
+        assert_eq!(task.priority, TaskPriority::High);
+        assert_eq!(task.status, TaskStatus::Processing);
+        assert_eq!(task.result.as_deref(), Some("3628800"));
+        assert_eq!(task.data.operation, Operation::Factorial);
+        assert_eq!(task.created_at.to_rfc3339(), "2024-01-15T10:30:00+00:00");
+
+        let request = resubmission_request(&task);
+        assert_eq!(request.id, "cpp-1");
+        assert!(request.into_task(&Calculator::default()).is_ok());
+    }
+
+    #[test]
+    fn test_failed_task_keeps_error() {
+        let json = r#"{
+            "id": "cpp-2", "title": "t", "priority": 1,
+            "created_at": "2024-01-15T10:30:00Z",
+            "data": {"type": "calculation", "input": 1, "operation": "prime_check"},
+            "status": "failed", "error": "boom"
+        }"#;
+        let cpp_task: CppTask = serde_json::from_str(json).unwrap();
+        assert!(!cpp_task.is_incomplete());
+        assert_eq!(cpp_task.into_task().error_message.as_deref(), Some("boom"));
+    }
+
+    #[test]
+    fn test_parse_id_list() {
+        let ids = parse_id_list("a\n\n# comment\n  b  \n");
+        assert_eq!(ids, vec!["a".to_string(), "b".to_string()]);
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn test_endpoint() {
+        assert_eq!(endpoint("http://cpp:7000/", "/stats"), "http://cpp:7000/stats");
+    }
+}
@@ -0,0 +1,389 @@
+//! OpenAPI 3 document for the HTTP API (`GET /openapi.json`)
+//!
+//! Built from [`route_examples`]: every example contributes its route, status,
+//! request and response body, and names the schema of each body, which
+//! [`component_schemas`] spells out under `components/schemas`. Clients in
+//! other languages can feed it to any OpenAPI generator.
+
+use crate::api_examples::{route_examples, RouteExample};
+use crate::api_schemas::{component_schemas, reference, Body};
+use serde_json::{json, Map, Value};
+
+const OPENAPI_VERSION: &str = "3.0.3";
+
+/// The rejection example is not a route of its own
+const NOT_A_ROUTE: &str = "/unknown";
+
+const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Build the OpenAPI document for every route in [`route_examples`]
+pub fn openapi_spec() -> Value {
+    let schemas = component_schemas();
+    let mut paths = Map::new();
+    for example in route_examples().iter().filter(|e| e.path != NOT_A_ROUTE) {
+        let (path, query) = split_query(example.path);
+        let item = paths
+            .entry(path.to_string())
+            .or_insert_with(|| json!({}))
+            .as_object_mut()
+            .unwrap();
+        let operation = item
+            .entry(example.method.to_lowercase())
+            .or_insert_with(|| new_operation(example.method, path, example.description, query));
+        add_example(operation, example, &schemas);
+    }
+
+    json!({
+        "openapi": OPENAPI_VERSION,
+        "info": {
+            "title": "Task Processing System",
+            "description": env!("CARGO_PKG_DESCRIPTION"),
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": {
+            "schemas": schemas,
+            "securitySchemes": {
+                "bearerAuth": {"type": "http", "scheme": "bearer"},
+                "apiKey": {"type": "apiKey", "in": "header", "name": "X-Api-Key"}
+            }
+        }
+    })
+}
+
+/// `"/stats/delta?since=x"` to the path and its query parameter names
+fn split_query(path: &str) -> (&str, Vec<&str>) {
+    match path.split_once('?') {
+        Some((path, query)) => (path, query.split('&').filter_map(|pair| pair.split('=').next()).collect()),
+        None => (path, Vec::new()),
+    }
+}
+
+fn new_operation(method: &str, path: &str, summary: &str, query: Vec<&str>) -> Value {
+    let mut parameters: Vec<Value> = path
+        .split('/')
+        .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+        .map(|name| json!({"name": name, "in": "path", "required": true, "schema": {"type": "string"}}))
+        .collect();
+    parameters.extend(
+        query
+            .into_iter()
+            .map(|name| json!({"name": name, "in": "query", "required": false, "schema": {"type": "string"}})),
+    );
+
+    let mut operation = json!({
+        "operationId": operation_id(method, path),
+        "summary": summary,
+        "responses": {},
+    });
+    if !parameters.is_empty() {
+        operation["parameters"] = Value::Array(parameters);
+    }
+    // Keys are only checked once `[[api_keys]]` are configured
+    if path != "/health" {
+        operation["security"] = json!([{"bearerAuth": []}, {"apiKey": []}, {}]);
+    }
+    operation
+}
+
+/// `POST /task/{id}/cancel` becomes `post_task_id_cancel`
+fn operation_id(method: &str, path: &str) -> String {
+    let mut id = method.to_lowercase();
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        id.push('_');
+        id.push_str(&segment.trim_matches(|c| c == '{' || c == '}').replace('-', "_"));
+    }
+    id
+}
+
+/// Add the example's request body and response; the first example for a status wins
+fn add_example(operation: &mut Value, example: &RouteExample, schemas: &Map<String, Value>) {
+    if let (Some(request), Some(schema)) = (&example.request, example.request_schema) {
+        if operation.get("requestBody").is_none() {
+            operation["requestBody"] = json!({
+                "required": true,
+                "content": {"application/json": {"schema": reference(schema), "example": request}}
+            });
+        }
+    }
+
+    let responses = operation["responses"].as_object_mut().unwrap();
+    let status = example.status.to_string();
+    if responses.contains_key(&status) {
+        return;
+    }
+    let content_type = match example.response_schema {
+        Body::Text => "text/plain",
+        _ if example.status >= 400 => PROBLEM_JSON,
+        _ => "application/json",
+    };
+    responses.insert(
+        status,
+        json!({
+            "description": example.description,
+            "content": {content_type: {"schema": body_schema(example.response_schema, schemas), "example": example.response}}
+        }),
+    );
+}
+
+fn body_schema(body: Body, schemas: &Map<String, Value>) -> Value {
+    match body {
+        Body::Schema(name) => reference(name),
+        Body::ArrayOf(name) => json!({"type": "array", "items": reference(name)}),
+        // Any subset of the fields, so none is required
+        Body::Fields(name) => {
+            let mut schema = schemas[name].clone();
+            schema.as_object_mut().unwrap().remove("required");
+            schema
+        }
+        Body::Text => json!({"type": "string"}),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::*;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    #[test]
+    fn test_every_example_route_is_documented() {
+        let spec = openapi_spec();
+        assert_eq!(spec["openapi"], OPENAPI_VERSION);
+        for example in route_examples().iter().filter(|e| e.path != NOT_A_ROUTE) {
+            let (path, _) = split_query(example.path);
+            let operation = &spec["paths"][path][example.method.to_lowercase()];
+            assert!(
+                operation["responses"][example.status.to_string()].is_object(),
+                "{} {} {} missing",
+                example.method,
+                path,
+                example.status
+            );
+        }
+        assert!(spec["paths"][NOT_A_ROUTE].is_null());
+    }
+
+    #[test]
+    fn test_operation_details() {
+        let spec = openapi_spec();
+        let create = &spec["paths"]["/task/create"]["post"];
+        assert_eq!(create["operationId"], "post_task_create");
+        let body = &create["requestBody"]["content"]["application/json"];
+        assert_eq!(spec["components"]["schemas"]["CreateTaskRequest"]["properties"]["title"]["type"], "string");
+        assert_eq!(body["example"]["id"], "task-001");
+        assert!(create["responses"]["400"]["content"][PROBLEM_JSON].is_object());
+
+        let get_task = &spec["paths"]["/task/{id}"]["get"];
+        assert_eq!(get_task["parameters"][0]["name"], "id");
+        assert_eq!(get_task["parameters"][0]["in"], "path");
+
+        let delta = &spec["paths"]["/stats/delta"]["get"];
+        assert_eq!(delta["parameters"][0]["name"], "since");
+        assert_eq!(delta["parameters"][0]["in"], "query");
+
+        assert!(spec["paths"]["/health"]["get"]["security"].is_null());
+        assert!(spec["paths"]["/metrics"]["get"]["responses"]["200"]["content"]["text/plain"].is_object());
+    }
+
+    /// Minimal validation: `$ref`, `oneOf`, `enum`, `type`, `required`,
+    /// `properties`, `additionalProperties` and `items`
+    fn conforms(value: &Value, schema: &Value, schemas: &Map<String, Value>) -> Result<(), String> {
+        if let Some(name) = schema["$ref"].as_str() {
+            let name = name.trim_start_matches("#/components/schemas/");
+            let schema = schemas.get(name).ok_or(format!("no schema {}", name))?;
+            return conforms(value, schema, schemas);
+        }
+        if let Some(options) = schema["oneOf"].as_array() {
+            return match options.iter().any(|option| conforms(value, option, schemas).is_ok()) {
+                true => Ok(()),
+                false => Err(format!("{} matches none of {}", value, schema)),
+            };
+        }
+        if let Some(values) = schema["enum"].as_array() {
+            if !values.contains(value) {
+                return Err(format!("{} is not one of {:?}", value, values));
+            }
+        }
+        let type_matches = match schema["type"].as_str() {
+            None => true,
+            Some("object") => value.is_object(),
+            Some("array") => value.is_array(),
+            Some("string") => value.is_string(),
+            Some("integer") => value.is_u64() || value.is_i64(),
+            Some("number") => value.is_number(),
+            Some("boolean") => value.is_boolean(),
+            Some(other) => return Err(format!("unknown type {}", other)),
+        };
+        if !type_matches {
+            return Err(format!("{} is not of type {}", value, schema["type"]));
+        }
+        for item in value.as_array().into_iter().flatten() {
+            conforms(item, &schema["items"], schemas)?;
+        }
+        if let Some(fields) = value.as_object() {
+            for name in schema["required"].as_array().into_iter().flatten().filter_map(Value::as_str) {
+                if !fields.contains_key(name) {
+                    return Err(format!("required field {} missing", name));
+                }
+            }
+            for (name, field) in fields {
+                let field_schema = schema["properties"]
+                    .get(name)
+                    .or_else(|| schema.get("additionalProperties"))
+                    .ok_or(format!("field {} isn't in the schema", name))?;
+                conforms(field, field_schema, schemas).map_err(|e| format!("{}: {}", name, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn references(value: &Value, found: &mut Vec<String>) {
+        match value {
+            Value::Object(fields) => {
+                if let Some(Value::String(target)) = fields.get("$ref") {
+                    found.push(target.trim_start_matches("#/components/schemas/").to_string());
+                }
+                fields.values().for_each(|field| references(field, found));
+            }
+            Value::Array(items) => items.iter().for_each(|item| references(item, found)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_examples_match_schemas() {
+        let spec = openapi_spec();
+        let schemas = spec["components"]["schemas"].as_object().unwrap();
+        for example in route_examples() {
+            let context = format!("{} {} {}", example.method, example.path, example.status);
+            assert_eq!(example.request.is_some(), example.request_schema.is_some(), "{}", context);
+            if let (Some(request), Some(name)) = (&example.request, example.request_schema) {
+                conforms(request, &reference(name), schemas).unwrap_or_else(|e| panic!("{} request: {}", context, e));
+            }
+            conforms(&example.response, &body_schema(example.response_schema, schemas), schemas)
+                .unwrap_or_else(|e| panic!("{} response: {}", context, e));
+        }
+
+        let mut targets = Vec::new();
+        references(&spec, &mut targets);
+        for target in targets {
+            assert!(schemas.contains_key(&target), "dangling $ref to {}", target);
+        }
+    }
+
+    #[test]
+    fn test_optional_fields_and_enums_documented() {
+        let spec = openapi_spec();
+        let schemas = &spec["components"]["schemas"];
+        let create = &spec["paths"]["/task/create"]["post"]["requestBody"]["content"]["application/json"];
+        assert_eq!(create["schema"]["$ref"], "#/components/schemas/CreateTaskRequest");
+
+        let request = &schemas["CreateTaskRequest"];
+        for field in ["namespace", "callback_url", "run_at", "delay_seconds", "idempotency_key", "depends_on", "timeout_ms"] {
+            assert!(request["properties"][field].is_object(), "CreateTaskRequest.{} missing", field);
+        }
+        assert_eq!(request["required"], json!(["title", "data"]));
+        assert!(schemas["ExecuteTaskRequest"]["properties"]["timeout_ms"].is_object());
+
+        let data = &schemas["TaskData"];
+        for field in ["inputs", "parallel", "input2", "algorithm", "prime_check_algorithm", "pipeline", "precision", "max_retries"] {
+            assert!(data["properties"][field].is_object(), "TaskData.{} missing", field);
+        }
+        assert_eq!(data["required"], json!(["type", "operation"]));
+
+        let variants = |name: &str| schemas[name]["enum"].clone();
+        assert_eq!(
+            variants("TaskStatus"),
+            json!([
+                TaskStatus::Scheduled,
+                TaskStatus::Blocked,
+                TaskStatus::Pending,
+                TaskStatus::Processing,
+                TaskStatus::Completed,
+                TaskStatus::Failed,
+                TaskStatus::Cancelled,
+            ])
+        );
+        assert_eq!(variants("Operation"), json!(Operation::ALL));
+        assert_eq!(
+            variants("TaskPriority"),
+            json!([TaskPriority::Low, TaskPriority::Medium, TaskPriority::High])
+        );
+        assert_eq!(
+            variants("FibonacciAlgorithm"),
+            json!([FibonacciAlgorithm::Iterative, FibonacciAlgorithm::FastDoubling, FibonacciAlgorithm::Matrix])
+        );
+        assert_eq!(
+            variants("PrimeCheckAlgorithm"),
+            json!([PrimeCheckAlgorithm::MillerRabin, PrimeCheckAlgorithm::TrialDivision])
+        );
+        assert_eq!(variants("Precision"), json!([Precision::Native, Precision::Big]));
+        assert_eq!(variants("WebhookEventType"), json!(WebhookEventType::ALL));
+    }
+
+    fn property_names(schema: &Value) -> BTreeSet<String> {
+        schema["properties"].as_object().unwrap().keys().cloned().collect()
+    }
+
+    fn field_names<T: serde::Serialize>(value: &T) -> BTreeSet<String> {
+        serde_json::to_value(value).unwrap().as_object().unwrap().keys().cloned().collect()
+    }
+
+    #[test]
+    fn test_schemas_match_structs() {
+        let schemas = component_schemas();
+        // Every optional field set, so nothing is skipped when serialized
+        let mut data = TaskData::batch(vec![3, 4], Operation::Fibonacci).with_retries(2, 1_000);
+        data.parallel = true;
+        data.input2 = Some(6);
+        data.algorithm = Some(FibonacciAlgorithm::Matrix);
+        data.prime_check_algorithm = Some(PrimeCheckAlgorithm::TrialDivision);
+        data.pipeline = vec![Operation::PrimeCheck];
+        data.precision = Precision::Big;
+        assert_eq!(property_names(&schemas["TaskData"]), field_names(&data));
+
+        let mut request = CreateTaskRequest::new("Full".to_string(), TaskPriority::High, data.clone());
+        request.expected_result = Some("5".to_string());
+        request.namespace = Some("ops".to_string());
+        request.callback_url = Some("https://hooks.internal/done".to_string());
+        request.run_at = Some(chrono::Utc::now());
+        request.delay_seconds = Some(5);
+        request.idempotency_key = Some("key-1".to_string());
+        request.depends_on = vec!["task-000".to_string()];
+        request.labels = BTreeMap::from([("team".to_string(), "payments".to_string())]);
+        request.timeout_ms = Some(1_000);
+        assert_eq!(property_names(&schemas["CreateTaskRequest"]), field_names(&request));
+        let mut execute = field_names(&request);
+        execute.insert("max_wait_ms".to_string());
+        assert_eq!(property_names(&schemas["ExecuteTaskRequest"]), execute);
+
+        let mut task = Task::new("Full".to_string(), TaskPriority::High, data);
+        task.result = Some("5".to_string());
+        task.error_message = Some("none".to_string());
+        task.completed_at = Some(task.created_at);
+        task.algorithm = Some(FibonacciAlgorithm::Matrix);
+        task.stages = vec![StageResult {
+            stage: 1,
+            operation: Operation::PrimeCheck,
+            input: 5,
+            result: Some("true".to_string()),
+            error: Some("none".to_string()),
+        }];
+        task.expected_result = request.expected_result;
+        task.callback_url = request.callback_url;
+        task.run_at = Some(task.created_at);
+        task.depends_on = request.depends_on;
+        task.labels = request.labels;
+        task.timeout_ms = request.timeout_ms;
+        task.attempts = 1;
+        task.matched = Some(true);
+        task.result_signature = Some("ab".to_string());
+        task.queue_position = Some(1);
+        task.worker_id = Some(0);
+        task.started_at = Some(task.created_at);
+        task.processing_duration_ms = Some(2);
+        assert_eq!(property_names(&schemas["Task"]), field_names(&task));
+        assert_eq!(property_names(&schemas["StageResult"]), field_names(&task.stages[0]));
+    }
+}
@@ -1,24 +1,114 @@
 #![allow(warnings)]
+use crate::admin::{self, PauseMode};
+use crate::analytics::AnalyticsSink;
+use crate::api_examples::route_examples;
+use crate::audit::{AuditLog, AUDIT_LIST};
+use crate::auth::{self, ApiKeys, Principal};
+use crate::calculations::Calculator;
+use crate::callbacks::CallbackDispatcher;
+use crate::changes::{ChangeFeed, ChangesPage, ChangesQuery};
+use crate::diagnostics;
+use crate::events::{EventBus, TaskEvent};
+use crate::federation::{self, Federation, ForwardReason, RemoteTask, FORWARDED_HEADER};
+use crate::field_selection::{FieldSelection, FieldsQuery};
+use crate::idempotency::{self, IdempotencyKeys, IDEMPOTENCY_KEY_HEADER, IDEMPOTENT_REPLAYED_HEADER};
+use crate::latency::{self, LatencySummary};
+use crate::listeners;
+use crate::live_events::{self, LiveEvents};
+use crate::load_balancer::LoadBalancer;
+use crate::metrics::{HttpMetrics, MetricsSink, NoopSink, StatsdSink};
+use crate::openapi::openapi_spec;
+use crate::probes;
+use crate::query::{ListParams, QueryError};
+use crate::rate_limit::{self, RateLimiter};
+use crate::runtime_metrics::{RuntimeMonitor, RuntimeStats, PROBE_INTERVAL};
+use crate::scheduler::Scheduler;
+use crate::stats_cache::{StatsCache, StatsQuery};
+use crate::stats_checkpoint::{self, StatsCheckpoint};
+use crate::stats_delta::{DeltaQuery, StatsDelta, StatsHistory};
+use crate::subscriptions::{SubscriptionStore, SUBSCRIPTION_LIST};
+use crate::supervisor;
+use crate::task_index::{self, TaskIndex};
+use crate::task_store::{SqliteTaskStore, TaskStore};
+use crate::timestamps::{self, WireFormat};
 use crate::types::*;
+use crate::validation::{TaskValidator, ValidatorChain};
+use crate::webhooks::WebhookDispatcher;
+use crate::work_stealing::StealRegistry;
 use crate::worker::Worker;
 use chrono::Utc;
+use futures::stream::{BoxStream, StreamExt};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::SocketAddr;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::Duration;
 use tokio::sync::RwLock;
 use tokio::task::JoinHandle;
-use tracing::{debug, error, info};
-use warp::Filter;
+use tracing::{debug, error, info, warn};
+use warp::http::{HeaderMap, Method, StatusCode};
+use warp::path::FullPath;
+use warp::reply::Response;
+use warp::{Filter, Rejection, Reply};
+
+/// Request header selecting the timestamp format of a response (`rfc3339` or `epoch_millis`)
+pub const TIMESTAMP_FORMAT_HEADER: &str = "x-timestamp-format";
+
+/// Request headers a `GET /task/{id}` reply depends on: the caller's key and the timestamp format
+const TASK_REPLY_VARY: &str = "authorization, x-api-key, x-timestamp-format";
+
+/// Content type of every error reply
+const PROBLEM_JSON: &str = "application/problem+json";
+
+/// How often `drain` checks whether the queues are empty
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(50);
 
 /// Task orchestrator that manages multiple workers with round-robin distribution
 pub struct TaskOrchestrator {
     config: OrchestratorConfig,
     workers: Vec<Arc<Worker>>,
-    current_worker: AtomicUsize,
-    running: AtomicBool,
-    start_time: Instant,
+    /// Tasks submitted with a future `run_at`, until they're due
+    scheduler: Arc<Scheduler>,
+    validators: ValidatorChain,
+    /// Calculation settings shared by every worker
+    calculator: Arc<Calculator>,
+    /// Configured timestamp and result formats of replies and notifications
+    wire_format: WireFormat,
+    http_metrics: Arc<HttpMetrics>,
+    metrics_sink: Arc<dyn MetricsSink>,
+    events: Arc<EventBus>,
+    changes: Arc<ChangeFeed>,
+    callbacks: Arc<CallbackDispatcher>,
+    live_events: Arc<LiveEvents>,
+    runtime_monitor: Arc<RuntimeMonitor>,
+    subscriptions: Arc<SubscriptionStore>,
+    webhooks: Arc<WebhookDispatcher>,
+    audit: Arc<AuditLog>,
+    analytics: Option<Arc<AnalyticsSink>>,
+    task_store: Option<Arc<dyn TaskStore>>,
+    /// Set once stored tasks were loaded onto the workers, so a restart doesn't repeat it
+    tasks_restored: AtomicBool,
+    federation: Arc<Federation>,
+    api_keys: Arc<ApiKeys>,
+    rate_limiter: Arc<RateLimiter>,
+    stats_history: Arc<StatsHistory>,
+    stats_cache: Arc<StatsCache>,
+    balancer: Arc<LoadBalancer>,
+    idempotency: Arc<IdempotencyKeys>,
+    running: Arc<AtomicBool>,
+    /// Set by `drain`: new tasks are refused while queued ones are processed
+    draining: Arc<AtomicBool>,
+    /// Set once `start` has bound every listener and all workers run
+    ready: Arc<AtomicBool>,
+    ready_notify: tokio::sync::Notify,
+    /// Process start time and the counters of earlier runs
+    stats_checkpoint: Arc<StatsCheckpoint>,
     worker_handles: Arc<RwLock<Vec<JoinHandle<()>>>>,
-    server_handle: Arc<RwLock<Option<JoinHandle<()>>>>,
+    server_handles: Arc<RwLock<Vec<JoinHandle<()>>>>,
+    bound_addrs: parking_lot::RwLock<Vec<SocketAddr>>,
 }
 
 impl TaskOrchestrator {
@@ -31,25 +121,225 @@ impl TaskOrchestrator {
             config.num_workers, config.threads_per_worker
         );
 
-        // Create workers
+        if !config.inject_failures.is_empty() {
+            warn!("Failure injection enabled for {} calculations", config.inject_failures.len());
+        }
+
+        let analytics = match &config.analytics {
+            Some(analytics) => Some(Arc::new(AnalyticsSink::open(&analytics.path).map_err(|e| {
+                SystemError::Orchestrator {
+                    message: format!("Cannot open analytics database {}: {}", analytics.path.display(), e),
+                }
+            })?)),
+            None => None,
+        };
+        let task_store: Option<Arc<dyn TaskStore>> = match &config.task_store {
+            Some(store) => Some(Arc::new(SqliteTaskStore::open(&store.path).map_err(|e| {
+                SystemError::Orchestrator {
+                    message: format!("Cannot open task store {}: {}", store.path.display(), e),
+                }
+            })?)),
+            None => None,
+        };
+        let stats_checkpoint = match &config.stats_checkpoint {
+            Some(checkpoint) => StatsCheckpoint::open(&checkpoint.path).map_err(|e| SystemError::Orchestrator {
+                message: format!("Cannot load statistics checkpoint {}: {}", checkpoint.path.display(), e),
+            })?,
+            None => StatsCheckpoint::in_memory(),
+        };
+
+        // Create workers, all publishing on one event bus
+        let events = Arc::new(EventBus::new());
+        let stealing = config
+            .work_stealing
+            .enabled
+            .then(|| Arc::new(StealRegistry::new(config.work_stealing.clone())));
+        // ...and recording which of them holds each task
+        let task_index = Arc::new(TaskIndex::new());
+        let calculator = Arc::new(Calculator::new(&config));
+        let wire_format = WireFormat::new(config.timestamp_format, config.result_format);
         let mut workers = Vec::new();
         for i in 0..config.num_workers {
-            let worker = Arc::new(Worker::new(i, config.threads_per_worker)); // Remove port parameter
+            let mut worker = Worker::new(i, config.threads_per_worker).with_simulated_processing(
+                Duration::from_millis(config.simulated_processing_ms),
+                config.speed_factor(i),
+            );
+            if let Some(key) = &config.result_signing_key {
+                worker = worker.with_signing_key(key);
+            }
+            if !config.inject_failures.is_empty() {
+                worker = worker.with_injected_failures(config.inject_failures.clone());
+            }
+            if let Some(analytics) = &analytics {
+                worker = worker.with_analytics(Arc::clone(analytics));
+            }
+            worker = worker
+                .with_labels(config.worker_labels(i))
+                .with_queue_polling(config.queue_polling(i))
+                .with_max_tasks(config.max_tasks_per_worker)
+                .with_max_queue_size(config.max_queue_size)
+                .with_task_ttl(Duration::from_secs(config.completed_task_ttl_seconds))
+                .with_task_timeout(Duration::from_millis(config.default_task_timeout_ms))
+                .with_watchdog(config.watchdog.clone())
+                .with_calculation_executor(config.calculation_executor)
+                .with_calculator(Arc::clone(&calculator))
+                .with_events(Arc::clone(&events))
+                .with_task_index(Arc::clone(&task_index));
+            if let Some(stealing) = &stealing {
+                worker = worker.with_work_stealing(Arc::clone(stealing));
+            }
+            let worker = Arc::new(worker);
+            if let Some(stealing) = &stealing {
+                stealing.register(&worker);
+            }
             workers.push(worker);
-}
+        }
+
+        // New tasks are spread over the workers as configured
+        let balancer = Arc::new(LoadBalancer::new(config.load_balancing, workers.len(), &config.worker_weights));
+        let idempotency = Arc::new(IdempotencyKeys::new(Duration::from_secs(config.idempotency_window_seconds)));
 
+        // Every lifecycle event also goes into the GET /changes feed
+        let changes = Arc::new(ChangeFeed::new());
+        changes.subscribe(&events);
+        // Tasks submitted with a callback_url are POSTed there as they progress
+        let callbacks = Arc::new(CallbackDispatcher::new(config.callbacks.clone()).with_wire_format(wire_format));
+        callbacks.subscribe(&events);
+        // ...and is pushed to GET /ws/events clients as it happens
+        let live_events = Arc::new(LiveEvents::new().with_wire_format(wire_format));
+        live_events.subscribe(&events);
+
+        let scheduler = Arc::new(Scheduler::new(workers.clone(), Arc::clone(&events)).with_balancer(Arc::clone(&balancer)));
+        // Blocked tasks are released (or failed) as their dependencies finish
+        scheduler.subscribe(&events);
+
+        let webhook_config = config.webhooks.clone();
+        let subscriptions = Arc::new(SubscriptionStore::new());
+        let api_keys = Arc::new(ApiKeys::new(&config.api_keys));
+        let rate_limiter = Arc::new(RateLimiter::new(&config.rate_limit));
+        let stats_cache = Arc::new(StatsCache::new(Duration::from_millis(config.stats_cache_ms)));
+        let metrics_sink: Arc<dyn MetricsSink> = match &config.statsd {
+            Some(statsd) => Arc::new(StatsdSink::new(&statsd.address, &statsd.prefix).map_err(|e| {
+                SystemError::Orchestrator {
+                    message: format!("Cannot use statsd address {}: {}", statsd.address, e),
+                }
+            })?),
+            None => Arc::new(NoopSink),
+        };
+        let federation = Arc::new(Federation::new(&config.federation));
         Ok(Self {
             config,
             workers,
-            current_worker: AtomicUsize::new(0),
-            running: AtomicBool::new(false),
-            start_time: Instant::now(),
+            scheduler,
+            validators: ValidatorChain::new(),
+            calculator,
+            wire_format,
+            http_metrics: Arc::new(HttpMetrics::with_sink(Arc::clone(&metrics_sink))),
+            metrics_sink,
+            events,
+            changes,
+            callbacks,
+            live_events,
+            runtime_monitor: Arc::new(RuntimeMonitor::new()),
+            webhooks: Arc::new(WebhookDispatcher::new(webhook_config, Arc::clone(&subscriptions)).with_wire_format(wire_format)),
+            subscriptions,
+            audit: Arc::new(AuditLog::new()),
+            analytics,
+            task_store,
+            tasks_restored: AtomicBool::new(false),
+            federation,
+            api_keys,
+            rate_limiter,
+            stats_history: Arc::new(StatsHistory::new()),
+            stats_cache,
+            balancer,
+            idempotency,
+            running: Arc::new(AtomicBool::new(false)),
+            draining: Arc::new(AtomicBool::new(false)),
+            ready: Arc::new(AtomicBool::new(false)),
+            ready_notify: tokio::sync::Notify::new(),
+            stats_checkpoint: Arc::new(stats_checkpoint),
             worker_handles: Arc::new(RwLock::new(Vec::new())),
-            server_handle: Arc::new(RwLock::new(None)),
+            server_handles: Arc::new(RwLock::new(Vec::new())),
+            bound_addrs: parking_lot::RwLock::new(Vec::new()),
         })
     }
 
+    /// Register a custom intake validator (must be called before `start`)
+    pub fn with_validator<V: TaskValidator + 'static>(mut self, validator: V) -> Self {
+        self.validators.push(validator);
+        self
+    }
+
+    /// Report metrics to `sink` instead of the configured one (must be called before `start`)
+    ///
+    /// `GET /metrics` keeps serving its built-in counters either way.
+    pub fn with_metrics_sink(mut self, sink: Arc<dyn MetricsSink>) -> Self {
+        self.http_metrics = Arc::new(HttpMetrics::with_sink(Arc::clone(&sink)));
+        self.metrics_sink = sink;
+        self
+    }
+
+    /// Keep tasks in `store` instead of the configured one (must be called before `start`)
+    pub fn with_task_store(mut self, store: Arc<dyn TaskStore>) -> Self {
+        self.task_store = Some(store);
+        self
+    }
+
+    /// Call `hook` with each task accepted onto a worker's queue
+    pub fn on_task_created<F, Fut>(self, hook: F) -> Self
+    where
+        F: Fn(Task) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.events.register(TaskEvent::Created, hook);
+        self
+    }
+
+    /// Call `hook` with each successfully calculated task
+    pub fn on_task_processed<F, Fut>(self, hook: F) -> Self
+    where
+        F: Fn(Task) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.events.register(TaskEvent::Processed, hook);
+        self
+    }
+
+    /// Call `hook` with each task marked completed
+    pub fn on_task_completed<F, Fut>(self, hook: F) -> Self
+    where
+        F: Fn(Task) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.events.register(TaskEvent::Completed, hook);
+        self
+    }
+
+    /// Call `hook` with each task whose calculation (or a pipeline stage) failed
+    pub fn on_task_failed<F, Fut>(self, hook: F) -> Self
+    where
+        F: Fn(Task) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.events.register(TaskEvent::Failed, hook);
+        self
+    }
+
+    /// Call `hook` with each task cancelled before it was completed
+    pub fn on_task_cancelled<F, Fut>(self, hook: F) -> Self
+    where
+        F: Fn(Task) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.events.register(TaskEvent::Cancelled, hook);
+        self
+    }
+
     /// Start the orchestrator and all workers
+    ///
+    /// Resolves once every listener is bound (connections are accepted from
+    /// then on) and every worker is running.
     pub async fn start(&self) -> Result<(), SystemError> {
         if self.running.load(Ordering::Acquire) {
             return Err(SystemError::Orchestrator {
@@ -58,11 +348,23 @@ impl TaskOrchestrator {
         }
 
         info!("Starting task orchestrator...");
+
+        // Bind per-worker diagnostics first so a taken port fails the start cleanly
+        let mut handles = self.start_worker_diagnostics()?;
+
+        self.draining.store(false, Ordering::Release);
         self.running.store(true, Ordering::Release);
 
+        // A replica serves the primary's tasks from the task store and runs no workers
+        let workers: &[Arc<Worker>] = if self.config.is_read_only() { &[] } else { &self.workers };
+
         // Start all workers
-        let mut handles = Vec::new();
-        for worker in &self.workers {
+        for worker in workers {
+            // The sink may have been replaced after the workers were built
+            worker.set_metrics_sink(Arc::clone(&self.metrics_sink));
+            if let Some(store) = &self.task_store {
+                worker.set_task_store(Arc::clone(store));
+            }
             let worker_clone = Arc::clone(worker);
             let handle = tokio::spawn(async move {
                 if let Err(e) = worker_clone.start().await {
@@ -71,6 +373,46 @@ impl TaskOrchestrator {
             });
             handles.push(handle);
         }
+        for worker in workers {
+            worker.wait_running().await;
+        }
+        if !workers.is_empty() {
+            if let Err(e) = self.restore_tasks().await {
+                self.stop().await;
+                return Err(e);
+            }
+        }
+
+        // Measure scheduler delay so executor starvation shows up in /stats/runtime
+        handles.push(self.runtime_monitor.spawn_probe(PROBE_INTERVAL));
+
+        // Deliver webhooks to configured endpoints and subscriptions in the background
+        if let Some(handle) = self.webhooks.start() {
+            handles.push(handle);
+        }
+        // Run lifecycle hooks registered by embedders
+        if let Some(handle) = self.events.start() {
+            handles.push(handle);
+        }
+        if !workers.is_empty() {
+            // Hand scheduled tasks to workers as they fall due
+            handles.push(self.scheduler.spawn());
+            // Restart workers whose processing threads panic, moving their pending tasks
+            handles.push(supervisor::spawn(self.workers.clone(), Arc::clone(&self.running)));
+        }
+        // Save cumulative statistics so they survive a restart
+        if let Some(config) = &self.config.stats_checkpoint {
+            let workers = self.workers.clone();
+            let scheduler = Arc::clone(&self.scheduler);
+            let checkpoint = Arc::clone(&self.stats_checkpoint);
+            let num_workers = self.config.num_workers;
+            let snapshot = move || {
+                let (workers, scheduler, checkpoint) = (workers.clone(), Arc::clone(&scheduler), Arc::clone(&checkpoint));
+                async move { system_stats(&workers, &scheduler, num_workers, &checkpoint).await }
+            };
+            let interval = Duration::from_secs(config.interval_seconds);
+            handles.push(stats_checkpoint::spawn(Arc::clone(&self.stats_checkpoint), interval, Arc::clone(&self.running), snapshot));
+        }
 
         // Store worker handles
         {
@@ -78,30 +420,186 @@ impl TaskOrchestrator {
             *worker_handles = handles;
         }
 
-        // Start orchestrator HTTP server
-        let server_handle = self.start_http_server().await?;
+        // Start orchestrator HTTP listeners, undoing the rest of the start if one can't bind
+        let servers = match self.start_http_server().await {
+            Ok(servers) => servers,
+            Err(e) => {
+                self.stop().await;
+                return Err(e);
+            }
+        };
+        let (addrs, server_handles): (Vec<SocketAddr>, Vec<JoinHandle<()>>) = servers.into_iter().unzip();
+        let addr = addrs[0];
         {
-            let mut server_handle_guard = self.server_handle.write().await;
-            *server_handle_guard = Some(server_handle);
+            let mut server_handles_guard = self.server_handles.write().await;
+            *server_handles_guard = server_handles;
+        }
+        *self.bound_addrs.write() = addrs.clone();
+
+        if let Some(path) = &self.config.port_file {
+            if let Err(e) = write_port_file(path, &addrs) {
+                self.stop().await;
+                return Err(SystemError::Io(std::io::Error::new(
+                    e.kind(),
+                    format!("Cannot write port file {}: {}", path.display(), e),
+                )));
+            }
         }
 
         info!(
             "Task orchestrator started on port {} with {} workers",
-            self.config.orchestrator_port, self.config.num_workers
+            addr.port(), self.config.num_workers
         );
 
+        self.ready.store(true, Ordering::Release);
+        self.ready_notify.notify_waiters();
+        Ok(())
+    }
+
+    /// Load the task store's tasks onto the workers, round-robin, on the first start
+    ///
+    /// Pending tasks go back into the queues oldest first; calculated and
+    /// finished tasks are kept as they were, results included.
+    async fn restore_tasks(&self) -> Result<(), SystemError> {
+        let Some(store) = &self.task_store else {
+            return Ok(());
+        };
+        if self.tasks_restored.swap(true, Ordering::AcqRel) {
+            return Ok(());
+        }
+        let store = Arc::clone(store);
+        let mut tasks = tokio::task::spawn_blocking(move || store.load())
+            .await
+            .map_err(|e| SystemError::Store { message: e.to_string() })??;
+        tasks.sort_by_key(|task| task.created_at);
+        let pending = tasks.iter().filter(|task| task.status == TaskStatus::Pending).count();
+        let mut restored = 0;
+        for (index, task) in tasks.into_iter().enumerate() {
+            let worker = &self.workers[index % self.workers.len()];
+            let task_id = task.id.clone();
+            match worker.restore(task).await {
+                Ok(()) => restored += 1,
+                Err(e) => warn!("Cannot restore task {} onto worker {}: {}", task_id, worker.id, e),
+            }
+        }
+        if restored > 0 {
+            info!("Restored {} stored tasks, {} of them pending", restored, pending);
+        }
         Ok(())
     }
 
+    /// Whether `start` has finished and `stop` hasn't been called since
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::Acquire)
+    }
+
+    /// Wait until `start` (typically running in another task) has finished,
+    /// failing after `timeout`
+    pub async fn wait_ready(&self, timeout: Duration) -> Result<(), SystemError> {
+        let ready = async {
+            loop {
+                // Created before the check, so a start finishing in between still wakes it
+                let notified = self.ready_notify.notified();
+                if self.is_ready() {
+                    return;
+                }
+                notified.await;
+            }
+        };
+        tokio::time::timeout(timeout, ready).await.map_err(|_| SystemError::Orchestrator {
+            message: format!("Orchestrator not ready after {:?}", timeout),
+        })
+    }
+
+    /// Drain the queues for up to `shutdown_drain_timeout_seconds`, then stop
+    pub async fn shutdown(&self) {
+        let timeout = Duration::from_secs(self.config.shutdown_drain_timeout_seconds);
+        if !timeout.is_zero() {
+            let remaining = self.drain(timeout).await;
+            if !remaining.is_empty() && self.task_store.is_some() {
+                info!("{} undrained tasks stay in the task store and are queued again on the next start", remaining.len());
+            }
+        }
+        self.stop().await;
+    }
+
+    /// Refuse new tasks (`503`) and wait up to `timeout` for the workers to
+    /// process everything queued and running, returning the tasks still pending
+    ///
+    /// Paused workers' queues aren't waited for, and tasks scheduled for later
+    /// stay scheduled; both are among the tasks returned. Undrained tasks are
+    /// logged.
+    pub async fn drain(&self, timeout: Duration) -> Vec<Task> {
+        info!("Draining task queues for up to {:?}...", timeout);
+        self.draining.store(true, Ordering::Release);
+        self.ready.store(false, Ordering::Release);
+
+        let drained = async {
+            loop {
+                if self.queues_idle().await {
+                    return;
+                }
+                tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+            }
+        };
+        if tokio::time::timeout(timeout, drained).await.is_err() {
+            warn!("Task queues not drained within {:?}", timeout);
+        }
+
+        let remaining: Vec<Task> = self
+            .workers
+            .iter()
+            .flat_map(|worker| worker.tasks())
+            .chain(self.scheduler.tasks())
+            .filter(|task| matches!(task.status, TaskStatus::Pending | TaskStatus::Scheduled | TaskStatus::Blocked))
+            .collect();
+        for task in &remaining {
+            warn!("Task {} ({} {}) was not drained", task.id, task.data.operation, task.data.input);
+        }
+        info!("Drain finished, {} tasks not processed", remaining.len());
+        remaining
+    }
+
+    /// Whether the orchestrator is refusing new tasks while draining
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::Acquire)
+    }
+
+    /// No active worker has a queued or running task
+    async fn queues_idle(&self) -> bool {
+        for worker in self.workers.iter().filter(|worker| !worker.is_paused()) {
+            if worker.queue_len().await > 0 || worker.busy_threads() > 0 {
+                return false;
+            }
+        }
+        true
+    }
+
     /// Stop the orchestrator and all workers
     pub async fn stop(&self) {
         info!("Stopping task orchestrator...");
+        self.ready.store(false, Ordering::Release);
         self.running.store(false, Ordering::Release);
 
         // Stop all workers
         for worker in &self.workers {
             worker.stop().await;
         }
+        if let Some(analytics) = &self.analytics {
+            let analytics = Arc::clone(analytics);
+            let _ = tokio::task::spawn_blocking(move || analytics.flush()).await;
+        }
+        if let Some(store) = &self.task_store {
+            let store = Arc::clone(store);
+            let _ = tokio::task::spawn_blocking(move || store.flush()).await;
+        }
+        if self.stats_checkpoint.is_persistent() {
+            let stats = self.get_system_stats().await;
+            let checkpoint = Arc::clone(&self.stats_checkpoint);
+            if let Ok(Err(e)) = tokio::task::spawn_blocking(move || checkpoint.save(&stats)).await {
+                warn!("Cannot save statistics checkpoint: {}", e);
+            }
+        }
 
         // Wait for worker handles to complete
         {
@@ -111,17 +609,37 @@ impl TaskOrchestrator {
             }
         }
 
-        // Stop server
+        // Stop every listener, waiting until their sockets are closed
         {
-            let mut server_handle = self.server_handle.write().await;
-            if let Some(handle) = server_handle.take() {
+            let mut server_handles = self.server_handles.write().await;
+            for handle in server_handles.iter() {
                 handle.abort();
             }
+            for handle in server_handles.drain(..) {
+                let _ = handle.await;
+            }
+        }
+        if !std::mem::take(&mut *self.bound_addrs.write()).is_empty() {
+            if let Some(path) = &self.config.port_file {
+                let _ = std::fs::remove_file(path);
+            }
         }
 
         info!("Task orchestrator stopped");
     }
 
+    /// Address the main listener is bound to (the actual port when configured
+    /// with port 0), or `None` while stopped
+    pub fn bound_addr(&self) -> Option<SocketAddr> {
+        self.bound_addrs.read().first().copied()
+    }
+
+    /// Bound addresses of the main listener followed by each of `listeners`
+    /// (empty while stopped)
+    pub fn bound_addrs(&self) -> Vec<SocketAddr> {
+        self.bound_addrs.read().clone()
+    }
+
     /// Create a new task and distribute to worker
     pub async fn create_task(&self, request: CreateTaskRequest) -> Result<String, SystemError> {
         if !self.running.load(Ordering::Acquire) {
@@ -130,248 +648,3385 @@ impl TaskOrchestrator {
             });
         }
 
+        if let Some(primary) = &self.config.primary_url {
+            return Err(SystemError::Orchestrator {
+                message: format!("Read-only replica; submit tasks to {}", primary),
+            });
+        }
+        if self.is_draining() {
+            return Err(SystemError::Orchestrator {
+                message: "Shutting down, not accepting new tasks".to_string(),
+            });
+        }
+
+        // A repeat of an earlier request returns the task that one created
+        let claimed = match request.idempotency_key.clone().filter(|_| self.idempotency.is_enabled()) {
+            Some(key) => {
+                idempotency::validate_key(&key)?;
+                let namespace = request.namespace.clone().unwrap_or_else(|| DEFAULT_NAMESPACE.to_string());
+                if let Some(existing) = self.idempotency.claim(&namespace, &key, &request.id) {
+                    return Ok(existing);
+                }
+                Some((namespace, key))
+            }
+            None => None,
+        };
+        let task_id = request.id.clone();
+        let created = self.submit(request).await;
+        if let (Err(_), Some((namespace, key))) = (&created, &claimed) {
+            self.idempotency.release(namespace, key, &task_id);
+        }
+        created
+    }
+
+    /// Validate `request` and queue it on a worker (or the scheduler)
+    async fn submit(&self, request: CreateTaskRequest) -> Result<String, SystemError> {
         // Convert request to task and validate
-        let task = request.into_task()?;
+        let task = request.into_task(&self.calculator)?;
+        self.validators.validate(&task)?;
         let task_id = task.id.clone();
+        if find_task(&self.workers, &self.scheduler, &task_id).is_some() {
+            return Err(SystemError::Task(TaskError::TaskAlreadyExists { id: task_id }));
+        }
+        if task.status == TaskStatus::Blocked {
+            let task = self.scheduler.block(task).await?;
+            self.webhooks.notify(WebhookEventType::TaskCreated, task);
+            return Ok(task_id);
+        }
+        if task.status == TaskStatus::Scheduled {
+            self.scheduler.schedule(task.clone())?;
+            self.webhooks.notify(WebhookEventType::TaskCreated, task);
+            return Ok(task_id);
+        }
 
         info!(
             "Creating task {} with priority {} for operation {} on input {}",
             task_id, task.priority, task.data.operation, task.data.input
         );
 
-        // Select worker using round-robin
-        let worker_index = self.select_worker();
-        let worker = &self.workers[worker_index];
+        // Select worker as configured, skipping paused workers
+        let worker = self
+            .select_worker()
+            .await
+            .ok_or_else(|| SystemError::Orchestrator {
+                message: "All workers are paused".to_string(),
+            })?;
 
         // Add task to selected worker
-        worker.add_task(task).await.map_err(|e| SystemError::Task(e))?;
+        worker.add_task(task.clone()).await.map_err(|e| SystemError::Task(e))?;
+        report_submitted(self.metrics_sink.as_ref(), worker, &task).await;
+        self.webhooks.notify(WebhookEventType::TaskCreated, task);
 
         debug!(
             "Task {} distributed to worker {}",
-            task_id, worker_index
+            task_id, worker.id
         );
 
         Ok(task_id)
     }
 
+    /// Create a task and wait up to `max_wait` for it to finish
+    ///
+    /// Returns the task id and, if it finished in time, the processed task;
+    /// otherwise it keeps running and can be polled like any other task.
+    pub async fn execute_task(&self, request: CreateTaskRequest, max_wait: Duration) -> Result<(String, Option<Task>), SystemError> {
+        let task_id = self.create_task(request).await?;
+        match task_index::owner(&self.workers, &task_id) {
+            Some(worker) => Ok((task_id.clone(), wait_for_result(worker, &task_id, max_wait).await)),
+            None => Ok((task_id, None)),
+        }
+    }
+
     /// Get task information from any worker
+    ///
+    /// A read-only replica reads it from the task store instead.
     pub async fn get_task(&self, task_id: &str) -> Result<Task, TaskError> {
-        // Search all workers for the task
-        for worker in &self.workers {
-            if let Some(task) = worker.get_task(task_id) {
+        if let Some(store) = self.replica_store() {
+            return match stored_task(store, task_id).await {
+                Ok(Some(task)) => Ok(task),
+                Ok(None) => Err(TaskError::TaskNotFound { id: task_id.to_string() }),
+                Err(e) => {
+                    warn!("Cannot read task {} from the task store: {}", task_id, e);
+                    Err(TaskError::TaskNotFound { id: task_id.to_string() })
+                }
+            };
+        }
+        if let Some(worker) = task_index::owner(&self.workers, task_id) {
+            if let Some(task) = worker.get_task_with_position(task_id).await {
                 return Ok(task);
             }
         }
+        if let Some(task) = self.scheduler.get(task_id) {
+            return Ok(task);
+        }
 
         Err(TaskError::TaskNotFound {
             id: task_id.to_string(),
         })
     }
 
-    /// Complete a task on any worker
-    pub async fn complete_task(&self, task_id: &str) -> Result<TaskCompletionResponse, TaskError> {
-        // Try to complete task on all workers
-        for worker in &self.workers {
-            if let Ok(true) = worker.complete_task(task_id) {
-                return Ok(TaskCompletionResponse {
-                    id: task_id.to_string(),
-                    status: TaskStatus::Completed,
-                    message: "Task completed successfully".to_string(),
+    /// Newest tasks across all workers, optionally only those in `status`
+    ///
+    /// A read-only replica lists the task store's tasks instead.
+    pub async fn list_tasks(&self, query: &TaskListQuery) -> Result<Vec<Task>, QueryError> {
+        let params = query.params()?;
+        match self.replica_store() {
+            Some(store) => {
+                let tasks = stored_tasks(store).await.unwrap_or_else(|e| {
+                    warn!("Cannot list tasks from the task store: {}", e);
+                    Vec::new()
                 });
+                select_tasks(tasks, &params, |_| true)
             }
+            None => list_tasks(&self.workers, &self.scheduler, &params, |_| true),
         }
+    }
 
-        Err(TaskError::TaskNotFound {
-            id: task_id.to_string(),
-        })
+    /// The store a read-only replica reads the primary's tasks from
+    fn replica_store(&self) -> Option<&Arc<dyn TaskStore>> {
+        self.task_store.as_ref().filter(|_| self.config.is_read_only())
+    }
+
+    /// Wait up to `timeout` for a task to be calculated, fail or be cancelled,
+    /// returning it as it stands then
+    pub async fn wait_for_task(&self, task_id: &str, timeout: Duration) -> Result<Task, TaskError> {
+        wait_for_task(&self.workers, &self.scheduler, task_id, timeout)
+            .await
+            .ok_or_else(|| TaskError::TaskNotFound {
+                id: task_id.to_string(),
+            })
+    }
+
+    /// Stream a task's stage results as they are computed, ending when it finishes
+    pub fn stream_task_result(&self, task_id: &str) -> Result<BoxStream<'static, StageResult>, TaskError> {
+        task_index::owner(&self.workers, task_id)
+            .and_then(|worker| worker.result_stream(task_id))
+            .ok_or_else(|| TaskError::TaskNotFound {
+                id: task_id.to_string(),
+            })
+    }
+
+    /// Complete a task on any worker
+    pub async fn complete_task(&self, task_id: &str) -> Result<TaskCompletionResponse, TaskError> {
+        complete_task(&self.workers, &self.scheduler, &self.webhooks, task_id)
+    }
+
+    /// Cancel a pending or processed task on any worker, stopping its calculation if one is running
+    pub async fn cancel_task(&self, task_id: &str) -> Result<TaskCompletionResponse, TaskError> {
+        cancel_task(&self.workers, &self.scheduler, task_id).await
+    }
+
+    /// Remove a waiting, pending or finished task; `force` also removes
+    /// processed tasks awaiting completion
+    pub async fn delete_task(&self, task_id: &str, force: bool) -> Result<TaskCompletionResponse, TaskError> {
+        delete_task(&self.workers, &self.scheduler, task_id, force).await
+    }
+
+    /// Change a queued task's priority on behalf of `actor`, recording it in the audit history
+    pub async fn set_task_priority(&self, task_id: &str, request: PriorityChangeRequest, actor: &str) -> Result<Task, TaskError> {
+        change_priority(&self.workers, &self.audit, task_id, request, actor).await
+    }
+
+    /// Change a queued task's priority and/or title on behalf of `actor`;
+    /// priority changes are recorded in the audit history
+    pub async fn update_task(&self, task_id: &str, request: TaskUpdateRequest, actor: &str) -> Result<Task, SystemError> {
+        request.validate()?;
+        Ok(update_task(&self.workers, &self.scheduler, &self.audit, task_id, &request, actor).await?)
+    }
+
+    /// Recorded operator changes, oldest first
+    pub fn audit_log(&self) -> Vec<AuditEntry> {
+        self.audit.entries()
+    }
+
+    /// Up to `limit` task state changes after sequence number `since`, as served by `GET /changes`
+    pub fn changes(&self, since: u64, limit: usize) -> ChangesPage {
+        self.changes.page(since, limit, |_| true)
+    }
+
+    /// Tasks forwarded to federation peers, oldest first
+    pub fn remote_tasks(&self) -> Vec<RemoteTask> {
+        self.federation.remote_tasks()
     }
 
     /// Get system statistics
     pub async fn get_system_stats(&self) -> SystemStats {
-        let mut total_processed = 0;
-        let mut total_completed = 0;
-        let mut total_failed = 0;
-        let mut worker_stats = Vec::new();
+        system_stats(&self.workers, &self.scheduler, self.config.num_workers, &self.stats_checkpoint).await
+    }
+
+    /// System statistics as served by `GET /stats`: reused for `stats_cache_ms` unless `fresh`
+    pub async fn cached_system_stats(&self, fresh: bool) -> SystemStats {
+        self.stats_cache.get(fresh, || self.get_system_stats()).await
+    }
+
+    /// Every runtime limit a request can run into, as served by `GET /admin/limits`
+    pub fn effective_limits(&self) -> EffectiveLimits {
+        let config = &self.config;
+        EffectiveLimits {
+            operations: Operation::ALL
+                .iter()
+                .map(|operation| {
+                    let (min_input, max_input) = operation.input_range(&self.calculator);
+                    OperationLimits {
+                        operation: operation.clone(),
+                        min_input,
+                        max_input,
+                        max_big_input: operation
+                            .supports_big_precision()
+                            .then(|| self.calculator.big_precision_limits().max_input(operation)),
+                    }
+                })
+                .collect(),
+            max_pipeline_stages: MAX_PIPELINE_STAGES,
+            max_task_retries: MAX_TASK_RETRIES,
+            max_retry_backoff_ms: MAX_RETRY_BACKOFF_MS,
+            validators: self.validators.names(),
+            queue: QueueLimits {
+                polling: config.queue_polling.mode,
+                fallback_interval_ms: config.queue_polling.fallback_interval().map(|interval| interval.as_millis() as u64),
+                batch_sizes: (0..config.num_workers).map(|id| config.queue_polling(id).batch_size).collect(),
+                max_queue_size: config.max_queue_size,
+            },
+            timeouts: TimeoutLimits {
+                default_execute_wait_ms: DEFAULT_EXECUTE_WAIT_MS,
+                max_execute_wait_ms: MAX_EXECUTE_WAIT_MS,
+                prime_generation_ms: config.prime_generation.time_limit_ms,
+                webhook_delivery_ms: config.webhooks.timeout_ms,
+                slow_request_threshold_ms: config.slow_request_threshold_ms,
+                default_task_ms: config.default_task_timeout_ms,
+            },
+            retention: RetentionLimits {
+                max_tasks_per_worker: config.max_tasks_per_worker,
+                completed_task_ttl_seconds: config.completed_task_ttl_seconds,
+                max_list_limit: MAX_LIST_LIMIT,
+            },
+        }
+    }
 
-        // Collect stats from all workers
+    /// Counters changed since the snapshot named by `since` (all of them without one)
+    pub async fn stats_delta(&self, since: Option<&str>) -> StatsDelta {
+        self.stats_history.delta(&self.get_system_stats().await, since)
+    }
+
+    /// Health, queue depth, capabilities and labels of every worker, by id
+    pub async fn workers_snapshot(&self) -> Vec<WorkerSnapshot> {
+        let mut snapshots = Vec::with_capacity(self.workers.len());
         for worker in &self.workers {
-            let stats = worker.get_stats().await;
-            total_processed += stats.tasks_processed;
-            total_completed += stats.tasks_completed;
-            total_failed += stats.tasks_failed;
-            worker_stats.push(stats);
+            snapshots.push(worker.snapshot().await);
         }
+        snapshots
+    }
+
+    /// Every worker's statistics, including whether it is paused
+    pub async fn worker_stats(&self) -> Vec<WorkerStats> {
+        admin::worker_stats(&self.workers).await
+    }
+
+    /// Stop assigning tasks to a worker and let its queue wait
+    pub fn pause_worker(&self, id: usize) -> Result<(), SystemError> {
+        self.worker(id)?.pause();
+        Ok(())
+    }
+
+    pub fn resume_worker(&self, id: usize) -> Result<(), SystemError> {
+        self.worker(id)?.resume();
+        Ok(())
+    }
+
+    /// Leave every worker's queue untouched while still accepting submissions
+    pub fn pause_processing(&self) {
+        self.workers.iter().for_each(|worker| worker.pause_processing());
+    }
+
+    pub fn resume_processing(&self) {
+        self.workers.iter().for_each(|worker| worker.resume_processing());
+    }
+
+    /// Keep the first `active` workers taking tasks and pause the rest
+    pub fn scale_workers(&self, active: usize) -> Result<(), SystemError> {
+        Ok(admin::scale(&self.workers, active)?)
+    }
+
+    /// Remove stored completed or failed tasks, returning how many went
+    pub fn purge_tasks(&self, status: &TaskStatus) -> Result<usize, SystemError> {
+        Ok(admin::purge(&self.workers, status)?)
+    }
+
+    /// Spread queued tasks evenly over the active workers, returning how many moved
+    pub async fn rebalance_queues(&self) -> usize {
+        admin::rebalance(&self.workers).await
+    }
+
+    fn worker(&self, id: usize) -> Result<&Arc<Worker>, SystemError> {
+        self.workers.get(id).ok_or_else(|| SystemError::Worker {
+            message: format!("Unknown worker {}", id),
+        })
+    }
 
-        SystemStats {
-            total_tasks_processed: total_processed,
-            total_tasks_completed: total_completed,
-            total_tasks_failed: total_failed,
-            total_workers: self.config.num_workers,
-            uptime_seconds: self.start_time.elapsed().as_secs(),
-            workers: worker_stats,
+    /// Tokio runtime health (scheduler delay, alive tasks, blocked processing threads)
+    pub fn get_runtime_stats(&self) -> RuntimeStats {
+        let blocked = self.workers.iter().map(|w| w.busy_threads()).sum();
+        self.runtime_monitor.snapshot(blocked)
+    }
+
+    /// Webhook subscriptions managed through `/subscriptions`
+    pub fn subscriptions(&self) -> Arc<SubscriptionStore> {
+        Arc::clone(&self.subscriptions)
+    }
+
+    /// HTTP-level counters (rejections by reason)
+    pub fn http_metrics(&self) -> Arc<HttpMetrics> {
+        Arc::clone(&self.http_metrics)
+    }
+
+    /// Serve each worker's diagnostics on `base_port + id`, if configured
+    fn start_worker_diagnostics(&self) -> Result<Vec<JoinHandle<()>>, SystemError> {
+        let Some((first_port, _)) = self.config.worker_port_range() else {
+            return Ok(Vec::new());
+        };
+
+        let mut handles = Vec::new();
+        for worker in &self.workers {
+            match diagnostics::serve(Arc::clone(worker), first_port + worker.id as u16) {
+                Ok((_, handle)) => handles.push(handle),
+                Err(e) => {
+                    handles.iter().for_each(JoinHandle::abort);
+                    return Err(e);
+                }
+            }
         }
+        Ok(handles)
     }
 
-    /// Select next worker using round-robin
-    fn select_worker(&self) -> usize {
-        let current = self.current_worker.fetch_add(1, Ordering::Relaxed);
-        current % self.workers.len()
+    /// Select the worker for a new task as `load_balancing` says, skipping paused workers
+    async fn select_worker(&self) -> Option<&Arc<Worker>> {
+        self.balancer.pick(&self.workers).await
     }
 
-    /// Start the orchestrator HTTP server
-    async fn start_http_server(&self) -> Result<JoinHandle<()>, SystemError> {
-        let port = self.config.orchestrator_port;
-        
+    /// Start the orchestrator HTTP server on the main listener and each of `listeners`
+    async fn start_http_server(&self) -> Result<Vec<(SocketAddr, JoinHandle<()>)>, SystemError> {
         // Clone what we need for the server
-        let workers = self.workers.clone();
+        let submit_context = SubmitContext {
+            workers: self.workers.clone(),
+            scheduler: Arc::clone(&self.scheduler),
+            balancer: Arc::clone(&self.balancer),
+            validators: self.validators.clone(),
+            calculator: Arc::clone(&self.calculator),
+            format: self.wire_format,
+            webhooks: Arc::clone(&self.webhooks),
+            metrics: Arc::clone(&self.metrics_sink),
+            federation: Arc::clone(&self.federation),
+            draining: Arc::clone(&self.draining),
+            idempotency: Arc::clone(&self.idempotency),
+        };
+
+        // API key check shared by every route except /health, /healthz and /readyz
+        let authenticated = auth::authenticate(Arc::clone(&self.api_keys));
         
+        // Task-bearing replies use the instance's formats, with timestamps as x-timestamp-format asks
+        let wire_format = reply_format(self.wire_format);
+
         // Create task endpoint
+        let submit_context = warp::any()
+            .map(move || submit_context.clone())
+            .and(wire_format.clone())
+            .map(|mut context: SubmitContext, format: WireFormat| {
+                context.format = format;
+                context
+            });
         let create_task = warp::path!("task" / "create")
             .and(warp::post())
+            .and(authenticated.clone())
             .and(warp::body::json())
-            .and(warp::any().map(move || workers.clone()))
-            .and_then(|request: CreateTaskRequest, workers: Vec<Arc<Worker>>| async move {
-                // Simple round-robin selection
-                static COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
-                let worker_idx = COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed) % workers.len();
-                let worker = &workers[worker_idx];
-                
-                match request.into_task() {
-                    Ok(task) => {
-                        let task_id = task.id.clone();
-                        match worker.add_task(task).await {
-                            Ok(()) => Ok::<_, warp::Rejection>(warp::reply::json(&serde_json::json!({
-                                "id": task_id,
-                                "status": "pending",
-                                "message": "Task created successfully"
-                            }))),
-                            Err(e) => Ok::<_, warp::Rejection>(warp::reply::json(&serde_json::json!({
-                                "error": e.to_string()
-                            })))
+            .and(warp::header::optional::<String>(FORWARDED_HEADER))
+            .and(warp::header::optional::<String>(IDEMPOTENCY_KEY_HEADER))
+            .and(submit_context.clone())
+            .and_then(|principal: Principal, body: Value, forwarded_by: Option<String>, idempotency_key: Option<String>, context: SubmitContext| async move {
+                let may_forward = forwarded_by.is_none();
+                let mut request: CreateTaskRequest = match parse_submission(&context, &principal, body, may_forward).await? {
+                    Submission::Local(request) => request,
+                    Submission::Forwarded(reply) => return Ok(reply),
+                };
+                request.namespace = Some(principal.namespace_for(request.namespace.as_deref())?);
+                let claimed = match claim_idempotency_key(&context, idempotency_key, &request).await {
+                    Ok(claimed) => claimed,
+                    Err(reply) => return Ok(reply),
+                };
+                let submitted = submit_task(&context, request.clone()).await;
+                if let (Err(_), Some((namespace, key))) = (&submitted, &claimed) {
+                    context.idempotency.release(namespace, key, &request.id);
+                }
+                Ok::<_, warp::Rejection>(match submitted {
+                    Ok((worker, mut task)) => {
+                        // The task as accepted; a worker may already be running it
+                        if let Some(worker) = worker {
+                            task.queue_position = worker.queue_position(&task.id).await;
                         }
-                    },
-                    Err(e) => Ok::<_, warp::Rejection>(warp::reply::json(&serde_json::json!({
-                        "error": e.to_string()
-                    })))
+                        let reply = warp::reply::with_header(task_json(context.format, &task), "location", task.url());
+                        warp::reply::with_status(reply, StatusCode::CREATED).into_response()
+                    }
+                    Err(SubmitError::NoCapacity(reply)) if may_forward => {
+                        let body = serde_json::to_value(&request).unwrap_or_default();
+                        forward_submission(&context, body, ForwardReason::NoCapacity).await.unwrap_or(reply)
+                    }
+                    Err(e) => e.into_response(),
+                })
+            });
+
+        // Synchronous execute endpoint: the processed task, or 202 if it takes longer than max_wait_ms
+        let execute_task = warp::path!("task" / "execute")
+            .and(warp::post())
+            .and(authenticated.clone())
+            .and(warp::body::json())
+            .and(warp::header::optional::<String>(FORWARDED_HEADER))
+            .and(submit_context)
+            .and_then(|principal: Principal, body: Value, forwarded_by: Option<String>, context: SubmitContext| async move {
+                let may_forward = forwarded_by.is_none();
+                let mut request: ExecuteTaskRequest = match parse_submission(&context, &principal, body, may_forward).await? {
+                    Submission::Local(request) => request,
+                    Submission::Forwarded(reply) => return Ok(reply),
+                };
+                request.task.namespace = Some(principal.namespace_for(request.task.namespace.as_deref())?);
+                let max_wait_ms = match request.max_wait_ms() {
+                    Ok(max_wait_ms) => max_wait_ms,
+                    Err(e) => return Ok::<_, warp::Rejection>(error_reply(StatusCode::BAD_REQUEST, "invalid_max_wait", &e.to_string())),
+                };
+                if request.task.is_scheduled() || !request.task.depends_on.is_empty() {
+                    let message = "Scheduled tasks and tasks with dependencies can't be executed synchronously; use POST /task/create";
+                    return Ok(error_reply(StatusCode::BAD_REQUEST, "validation_failed", message));
                 }
+                let (worker, task_id) = match submit_task(&context, request.task.clone()).await {
+                    Ok((Some(worker), task)) => (worker, task.id),
+                    // Only scheduled and blocked tasks, turned away above, come back without a worker
+                    Ok((None, task)) => return Ok(task_json(context.format, &task).into_response()),
+                    Err(SubmitError::NoCapacity(reply)) if may_forward => {
+                        let body = serde_json::to_value(&request.task).unwrap_or_default();
+                        return Ok(forward_submission(&context, body, ForwardReason::NoCapacity).await.unwrap_or(reply));
+                    }
+                    Err(e) => return Ok(e.into_response()),
+                };
+                Ok(match wait_for_result(&worker, &task_id, Duration::from_millis(max_wait_ms)).await {
+                    Some(task) => task_json(context.format, &task).into_response(),
+                    None => {
+                        let reply = warp::reply::json(&TaskCreatedResponse::still_processing(task_id.clone(), max_wait_ms));
+                        let reply = warp::reply::with_header(reply, "location", format!("/task/{}", task_id));
+                        warp::reply::with_status(reply, StatusCode::ACCEPTED).into_response()
+                    }
+                })
             });
     
         // Get task endpoint
         let workers_for_get = self.workers.clone();
+        let scheduler_for_get = Arc::clone(&self.scheduler);
+        let federation_for_get = Arc::clone(&self.federation);
+        let replica_store_for_get = self.replica_store().cloned();
         let get_task = warp::path!("task" / String)
             .and(warp::get())
-            .and(warp::any().map(move || workers_for_get.clone()))
-            .and_then(|task_id: String, workers: Vec<Arc<Worker>>| async move {
-                for worker in &workers {
-                    if let Some(task) = worker.get_task(&task_id) {
-                        return Ok(warp::reply::json(&task));
-                    }
+            .and(authenticated.clone())
+            .and(warp::query::<FieldsQuery>())
+            .and(wire_format.clone())
+            .and(warp::any().map(move || {
+                (workers_for_get.clone(), Arc::clone(&scheduler_for_get), Arc::clone(&federation_for_get), replica_store_for_get.clone())
+            }))
+            .and_then(|task_id: String, principal: Principal, fields: FieldsQuery, format: WireFormat, (workers, scheduler, federation, replica_store): (Vec<Arc<Worker>>, Arc<Scheduler>, Arc<Federation>, Option<Arc<dyn TaskStore>>)| async move {
+                let selection = match FieldSelection::parse(fields.fields.as_deref()) {
+                    Ok(selection) => selection,
+                    Err(e) => return Ok::<_, Rejection>(error_reply(StatusCode::BAD_REQUEST, "invalid_fields", &e.to_string())),
+                };
+                let found = match (&replica_store, task_index::owner(&workers, &task_id)) {
+                    (Some(store), _) => match stored_task(store, &task_id).await {
+                        Ok(task) => task,
+                        Err(e) => return Ok(store_unavailable_reply(&e)),
+                    },
+                    (None, Some(worker)) => worker.get_task_with_position(&task_id).await,
+                    (None, None) => scheduler.get(&task_id),
+                };
+                if let Some(task) = found {
+                    principal.authorize(&task.namespace)?;
+                    // Selecting serializes the task, so it must happen inside the format scope
+                    let body = format.apply(|| warp::reply::json(&selection.apply(&task)));
+                    let reply = warp::reply::with_header(body, "cache-control", task.status.cache_control());
+                    return Ok(warp::reply::with_header(reply, "vary", TASK_REPLY_VARY).into_response());
                 }
-                Err(warp::reject::not_found())
-            });
-    
-        // Complete task endpoint
-        let workers_for_complete = self.workers.clone();
-        let complete_task = warp::path!("task" / String / "complete")
-            .and(warp::post())
-            .and(warp::any().map(move || workers_for_complete.clone()))
-            .and_then(|task_id: String, workers: Vec<Arc<Worker>>| async move {
-                for worker in &workers {
-                    if let Ok(true) = worker.complete_task(&task_id) {
-                        return Ok(warp::reply::json(&TaskCompletionResponse {
-                            id: task_id,
-                            status: TaskStatus::Completed,
-                            message: "Task completed successfully".to_string(),
-                        }));
-                    }
+                // Forwarded tasks are answered by the peer holding them
+                if let Some(remote) = federation.remote_task(&task_id) {
+                    principal.authorize(&remote.namespace)?;
+                    return Ok(proxy_reply(&federation, &remote, Method::GET, &format!("/task/{}", task_id), &selection).await);
                 }
-                Err(warp::reject::not_found())
+                // Answered here rather than rejected, or DELETE /task/{id} would turn it into a 405
+                let e = TaskError::TaskNotFound { id: task_id };
+                Ok(error_reply(StatusCode::NOT_FOUND, "not_found", &e.to_string()))
             });
     
-        // Stats endpoint
-        let workers_for_stats = self.workers.clone();
-        let start_time = self.start_time;
-        let config_workers = self.config.num_workers;
-        let get_stats = warp::path("stats")
+        // Task list endpoint, limited to the caller's namespaces
+        let workers_for_list = self.workers.clone();
+        let scheduler_for_list = Arc::clone(&self.scheduler);
+        let replica_store_for_list = self.replica_store().cloned();
+        let list = warp::path!("tasks")
             .and(warp::get())
-            .and(warp::any().map(move || (workers_for_stats.clone(), start_time, config_workers)))
-            .and_then(|(workers, start_time, num_workers): (Vec<Arc<Worker>>, Instant, usize)| async move {
-                let mut total_processed = 0;
-                let mut total_completed = 0;
-                let mut total_failed = 0;
-                let mut worker_stats = Vec::new();
-    
-                for worker in &workers {
-                    let stats = worker.get_stats().await;
-                    total_processed += stats.tasks_processed;
-                    total_completed += stats.tasks_completed;
-                    total_failed += stats.tasks_failed;
-                    worker_stats.push(stats);
-                }
-    
-                let system_stats = SystemStats {
-                    total_tasks_processed: total_processed,
-                    total_tasks_completed: total_completed,
-                    total_tasks_failed: total_failed,
-                    total_workers: num_workers,
-                    uptime_seconds: start_time.elapsed().as_secs(),
-                    workers: worker_stats,
+            .and(authenticated.clone())
+            .and(warp::query::<HashMap<String, String>>())
+            .and(warp::query::<FieldsQuery>())
+            .and(wire_format.clone())
+            .and(warp::any().map(move || (workers_for_list.clone(), Arc::clone(&scheduler_for_list), replica_store_for_list.clone())))
+            .and_then(|principal: Principal, query: HashMap<String, String>, fields: FieldsQuery, format: WireFormat, (workers, scheduler, replica_store): (Vec<Arc<Worker>>, Arc<Scheduler>, Option<Arc<dyn TaskStore>>)| async move {
+                let selection = match FieldSelection::parse(fields.fields.as_deref()) {
+                    Ok(selection) => selection,
+                    Err(e) => return Ok::<_, Rejection>(error_reply(StatusCode::BAD_REQUEST, "invalid_fields", &e.to_string())),
                 };
-    
-                Ok::<_, warp::Rejection>(warp::reply::json(&system_stats))
+                let params = match TASK_LIST.parse(&query) {
+                    Ok(params) => params,
+                    Err(e) => return Ok(invalid_query_reply(e)),
+                };
+                let visible = |namespace: &str| principal.allows(namespace);
+                let tasks = match &replica_store {
+                    Some(store) => match stored_tasks(store).await {
+                        Ok(stored) => select_tasks(stored, &params, visible),
+                        Err(e) => return Ok(store_unavailable_reply(&e)),
+                    },
+                    None => list_tasks(&workers, &scheduler, &params, visible),
+                };
+                Ok(match tasks {
+                    Ok(tasks) => format.apply(|| warp::reply::json(&selection.apply_all(&tasks))).into_response(),
+                    Err(e) => invalid_query_reply(e),
+                })
             });
     
-        // Health check endpoint
-        let health = warp::path("health")
+        // Resumable feed of task state changes, limited to the caller's namespaces
+        let changes_for_route = Arc::clone(&self.changes);
+        let get_changes = warp::path!("changes")
             .and(warp::get())
-            .map(|| {
-                warp::reply::json(&serde_json::json!({
-                    "status": "healthy",
-                    "timestamp": Utc::now()
-                }))
+            .and(authenticated.clone())
+            .and(warp::query::<ChangesQuery>())
+            .and(wire_format.clone())
+            .map(move |principal: Principal, query: ChangesQuery, format: WireFormat| match query.limit() {
+                Ok(limit) => {
+                    let page = changes_for_route.page(query.since.unwrap_or(0), limit, |namespace| principal.allows(namespace));
+                    task_json(format, &page).into_response()
+                }
+                Err(e) => invalid_query_reply(e),
             });
+
+        // Lifecycle events pushed over a WebSocket, limited to the caller's namespaces
+        let ws_events = live_events::route(Arc::clone(&self.live_events), authenticated.clone());
+
+        // Streaming result endpoint: one JSON stage result per line, chunked as they arrive
+        let workers_for_stream = self.workers.clone();
+        let stream_result = warp::path!("task" / String / "result" / "stream")
+            .and(warp::get())
+            .and(authenticated.clone())
+            .and_then(move |task_id: String, principal: Principal| {
+                let found = task_index::owner(&workers_for_stream, &task_id).and_then(|worker| {
+                    let task = worker.get_task(&task_id)?;
+                    Some((task.namespace, worker.result_stream(&task_id)?))
+                });
+                async move {
+                    let (namespace, stream) = found.ok_or_else(warp::reject::not_found)?;
+                    principal.authorize(&namespace)?;
+                    Ok::<_, Rejection>(result_stream_reply(stream))
+                }
+            });
+    
+        // Long-poll endpoint: the task once calculated, or 202 with it as it stands after timeout_ms
+        let workers_for_wait = self.workers.clone();
+        let scheduler_for_wait = Arc::clone(&self.scheduler);
+        let wait_task = warp::path!("task" / String / "wait")
+            .and(warp::get())
+            .and(authenticated.clone())
+            .and(warp::query::<WaitQuery>())
+            .and(wire_format.clone())
+            .and(warp::any().map(move || (workers_for_wait.clone(), Arc::clone(&scheduler_for_wait))))
+            .and_then(|task_id: String, principal: Principal, query: WaitQuery, format: WireFormat, (workers, scheduler): (Vec<Arc<Worker>>, Arc<Scheduler>)| async move {
+                let timeout_ms = match query.timeout_ms() {
+                    Ok(timeout_ms) => timeout_ms,
+                    Err(e) => return Ok::<_, Rejection>(error_reply(StatusCode::BAD_REQUEST, "invalid_timeout", &e.to_string())),
+                };
+                // Checked up front, so callers can't wait on tasks they may not see
+                let task = find_task(&workers, &scheduler, &task_id).ok_or_else(warp::reject::not_found)?;
+                principal.authorize(&task.namespace)?;
+                let task = wait_for_task(&workers, &scheduler, &task_id, Duration::from_millis(timeout_ms))
+                    .await
+                    .ok_or_else(warp::reject::not_found)?;
+                let status = match task.status {
+                    TaskStatus::Scheduled | TaskStatus::Blocked | TaskStatus::Pending => StatusCode::ACCEPTED,
+                    _ => StatusCode::OK,
+                };
+                let reply = warp::reply::with_header(task_json(format, &task), "cache-control", "no-store");
+                Ok(warp::reply::with_status(reply, status).into_response())
+            });
+
+        // Complete task endpoint
+        let workers_for_complete = self.workers.clone();
+        let scheduler_for_complete = Arc::clone(&self.scheduler);
+        let webhooks_for_complete = Arc::clone(&self.webhooks);
+        let federation_for_complete = Arc::clone(&self.federation);
+        let complete_task = warp::path!("task" / String / "complete")
+            .and(warp::post())
+            .and(authenticated.clone())
+            .and(warp::any().map(move || {
+                (
+                    workers_for_complete.clone(),
+                    Arc::clone(&scheduler_for_complete),
+                    Arc::clone(&webhooks_for_complete),
+                    Arc::clone(&federation_for_complete),
+                )
+            }))
+            .and_then(|task_id: String, principal: Principal, (workers, scheduler, webhooks, federation): (Vec<Arc<Worker>>, Arc<Scheduler>, Arc<WebhookDispatcher>, Arc<Federation>)| async move {
+                if let Some(task) = find_task(&workers, &scheduler, &task_id) {
+                    principal.authorize(&task.namespace)?;
+                } else if let Some(remote) = federation.remote_task(&task_id) {
+                    principal.authorize(&remote.namespace)?;
+                    let path = format!("/task/{}/complete", task_id);
+                    return Ok(proxy_reply(&federation, &remote, Method::POST, &path, &FieldSelection::default()).await);
+                }
+                Ok::<_, Rejection>(match complete_task(&workers, &scheduler, &webhooks, &task_id) {
+                    Ok(response) => warp::reply::json(&response).into_response(),
+                    Err(e @ TaskError::TaskNotFound { .. }) => error_reply(StatusCode::NOT_FOUND, "not_found", &e.to_string()),
+                    Err(e) => error_reply(StatusCode::CONFLICT, "not_completable", &e.to_string()),
+                })
+            });
+    
+        // Cancel task endpoint
+        let workers_for_cancel = self.workers.clone();
+        let scheduler_for_cancel = Arc::clone(&self.scheduler);
+        let federation_for_cancel = Arc::clone(&self.federation);
+        let cancel_task_route = warp::path!("task" / String / "cancel")
+            .and(warp::post())
+            .and(authenticated.clone())
+            .and(warp::any().map(move || (workers_for_cancel.clone(), Arc::clone(&scheduler_for_cancel), Arc::clone(&federation_for_cancel))))
+            .and_then(|task_id: String, principal: Principal, (workers, scheduler, federation): (Vec<Arc<Worker>>, Arc<Scheduler>, Arc<Federation>)| async move {
+                if let Some(task) = find_task(&workers, &scheduler, &task_id) {
+                    principal.authorize(&task.namespace)?;
+                } else if let Some(remote) = federation.remote_task(&task_id) {
+                    principal.authorize(&remote.namespace)?;
+                    let path = format!("/task/{}/cancel", task_id);
+                    return Ok(proxy_reply(&federation, &remote, Method::POST, &path, &FieldSelection::default()).await);
+                }
+                Ok::<_, Rejection>(match cancel_task(&workers, &scheduler, &task_id).await {
+                    Ok(response) => warp::reply::json(&response).into_response(),
+                    Err(e @ TaskError::TaskNotFound { .. }) => error_reply(StatusCode::NOT_FOUND, "not_found", &e.to_string()),
+                    Err(e) => error_reply(StatusCode::CONFLICT, "not_cancellable", &e.to_string()),
+                })
+            });
+
+        // Update task endpoint: priority and title of queued tasks
+        let workers_for_update = self.workers.clone();
+        let scheduler_for_update = Arc::clone(&self.scheduler);
+        let audit_for_update = Arc::clone(&self.audit);
+        let update_task_route = warp::path!("task" / String)
+            .and(warp::patch())
+            .and(authenticated.clone())
+            .and(warp::body::json())
+            .and(wire_format.clone())
+            .and(warp::any().map(move || (workers_for_update.clone(), Arc::clone(&scheduler_for_update), Arc::clone(&audit_for_update))))
+            .and_then(
+                |task_id: String, principal: Principal, request: TaskUpdateRequest, format: WireFormat, (workers, scheduler, audit): (Vec<Arc<Worker>>, Arc<Scheduler>, Arc<AuditLog>)| async move {
+                    if let Some(task) = find_task(&workers, &scheduler, &task_id) {
+                        principal.authorize(&task.namespace)?;
+                    }
+                    if let Err(e) = request.validate() {
+                        return Ok::<_, Rejection>(error_reply(StatusCode::BAD_REQUEST, "validation_failed", &e.to_string()));
+                    }
+                    Ok(match update_task(&workers, &scheduler, &audit, &task_id, &request, &principal.name).await {
+                        Ok(task) => task_json(format, &task).into_response(),
+                        Err(e @ TaskError::TaskNotFound { .. }) => error_reply(StatusCode::NOT_FOUND, "not_found", &e.to_string()),
+                        Err(e) => error_reply(StatusCode::CONFLICT, "not_queued", &e.to_string()),
+                    })
+                },
+            );
+
+        // Delete task endpoint
+        let workers_for_delete = self.workers.clone();
+        let scheduler_for_delete = Arc::clone(&self.scheduler);
+        let federation_for_delete = Arc::clone(&self.federation);
+        let delete_task_route = warp::path!("task" / String)
+            .and(warp::delete())
+            .and(authenticated.clone())
+            .and(warp::query::<DeleteQuery>())
+            .and(warp::any().map(move || (workers_for_delete.clone(), Arc::clone(&scheduler_for_delete), Arc::clone(&federation_for_delete))))
+            .and_then(|task_id: String, principal: Principal, query: DeleteQuery, (workers, scheduler, federation): (Vec<Arc<Worker>>, Arc<Scheduler>, Arc<Federation>)| async move {
+                if let Some(task) = find_task(&workers, &scheduler, &task_id) {
+                    principal.authorize(&task.namespace)?;
+                } else if let Some(remote) = federation.remote_task(&task_id) {
+                    principal.authorize(&remote.namespace)?;
+                    let path = format!("/task/{}?force={}", task_id, query.force);
+                    return Ok(proxy_reply(&federation, &remote, Method::DELETE, &path, &FieldSelection::default()).await);
+                }
+                Ok::<_, Rejection>(match delete_task(&workers, &scheduler, &task_id, query.force).await {
+                    Ok(response) => warp::reply::json(&response).into_response(),
+                    Err(e @ TaskError::TaskNotFound { .. }) => error_reply(StatusCode::NOT_FOUND, "not_found", &e.to_string()),
+                    Err(e) => error_reply(StatusCode::CONFLICT, "not_deletable", &e.to_string()),
+                })
+            });
+    
+        // Operator priority override, recorded in the audit history
+        let workers_for_priority = self.workers.clone();
+        let audit_for_priority = Arc::clone(&self.audit);
+        let set_priority = warp::path!("task" / String / "priority")
+            .and(warp::post())
+            .and(authenticated.clone())
+            .and(warp::body::json())
+            .and(wire_format.clone())
+            .and(warp::any().map(move || (workers_for_priority.clone(), Arc::clone(&audit_for_priority))))
+            .and_then(
+                |task_id: String, principal: Principal, request: PriorityChangeRequest, format: WireFormat, (workers, audit): (Vec<Arc<Worker>>, Arc<AuditLog>)| async move {
+                    principal.require_unrestricted()?;
+                    Ok::<_, Rejection>(match change_priority(&workers, &audit, &task_id, request, &principal.name).await {
+                        Ok(task) => task_json(format, &task).into_response(),
+                        Err(e @ TaskError::TaskNotFound { .. }) => error_reply(StatusCode::NOT_FOUND, "not_found", &e.to_string()),
+                        Err(e) => error_reply(StatusCode::CONFLICT, "not_queued", &e.to_string()),
+                    })
+                },
+            );
+
+        // Stats endpoint
+        let workers_for_stats = self.workers.clone();
+        let scheduler_for_stats = Arc::clone(&self.scheduler);
+        let checkpoint_for_stats = Arc::clone(&self.stats_checkpoint);
+        let config_workers = self.config.num_workers;
+        let stats_cache = Arc::clone(&self.stats_cache);
+        let get_stats = warp::path("stats")
+            .and(warp::get())
+            .and(warp::query::<StatsQuery>())
+            .and(warp::any().map(move || {
+                (
+                    workers_for_stats.clone(),
+                    Arc::clone(&scheduler_for_stats),
+                    Arc::clone(&stats_cache),
+                    Arc::clone(&checkpoint_for_stats),
+                )
+            }))
+            .and_then(move |query: StatsQuery, (workers, scheduler, cache, checkpoint): (Vec<Arc<Worker>>, Arc<Scheduler>, Arc<StatsCache>, Arc<StatsCheckpoint>)| async move {
+                let system_stats = cache
+                    .get(query.fresh, || system_stats(&workers, &scheduler, config_workers, &checkpoint))
+                    .await;
+                Ok::<_, warp::Rejection>(warp::reply::json(&system_stats))
+            });
+
+        // The same stats pushed as server-sent events every stats_stream_interval_seconds
+        let workers_for_stream = self.workers.clone();
+        let scheduler_for_stream = Arc::clone(&self.scheduler);
+        let cache_for_stream = Arc::clone(&self.stats_cache);
+        let running_for_stream = Arc::clone(&self.running);
+        let checkpoint_for_stream = Arc::clone(&self.stats_checkpoint);
+        let stream_interval = Duration::from_secs(self.config.stats_stream_interval_seconds);
+        let get_stats_stream = warp::path!("stats" / "stream")
+            .and(warp::get())
+            .and(warp::any().map(move || {
+                (
+                    workers_for_stream.clone(),
+                    Arc::clone(&scheduler_for_stream),
+                    Arc::clone(&cache_for_stream),
+                    Arc::clone(&running_for_stream),
+                )
+            }))
+            .map(
+                move |(workers, scheduler, cache, running): (Vec<Arc<Worker>>, Arc<Scheduler>, Arc<StatsCache>, Arc<AtomicBool>)| {
+                    let checkpoint = Arc::clone(&checkpoint_for_stream);
+                    let snapshots = StatsSnapshots { workers, scheduler, cache, running, num_workers: config_workers, checkpoint };
+                    warp::sse::reply(warp::sse::keep_alive().stream(stats_events(snapshots, stream_interval)))
+                },
+            );
+
+        // Changed counters since a previous /stats/delta token
+        let workers_for_delta = self.workers.clone();
+        let scheduler_for_delta = Arc::clone(&self.scheduler);
+        let stats_history = Arc::clone(&self.stats_history);
+        let checkpoint_for_delta = Arc::clone(&self.stats_checkpoint);
+        let get_stats_delta = warp::path!("stats" / "delta")
+            .and(warp::get())
+            .and(warp::query::<DeltaQuery>())
+            .and(warp::any().map(move || {
+                (
+                    workers_for_delta.clone(),
+                    Arc::clone(&scheduler_for_delta),
+                    Arc::clone(&stats_history),
+                    Arc::clone(&checkpoint_for_delta),
+                )
+            }))
+            .and_then(
+                move |query: DeltaQuery, (workers, scheduler, history, checkpoint): (Vec<Arc<Worker>>, Arc<Scheduler>, Arc<StatsHistory>, Arc<StatsCheckpoint>)| async move {
+                    let system_stats = system_stats(&workers, &scheduler, config_workers, &checkpoint).await;
+                    let delta = history.delta(&system_stats, query.since.as_deref());
+                    Ok::<_, warp::Rejection>(warp::reply::json(&delta))
+                },
+            );
+    
+        // Runtime metrics endpoint
+        let workers_for_runtime = self.workers.clone();
+        let runtime_monitor = Arc::clone(&self.runtime_monitor);
+        let get_runtime_stats = warp::path!("stats" / "runtime")
+            .and(warp::get())
+            .map(move || {
+                let blocked = workers_for_runtime.iter().map(|w| w.busy_threads()).sum();
+                warp::reply::json(&runtime_monitor.snapshot(blocked))
+            });
+    
+        // Webhook delivery stats endpoint
+        let webhooks_for_admin = Arc::clone(&self.webhooks);
+        let get_webhook_stats = warp::path!("admin" / "webhooks")
+            .and(warp::get())
+            .map(move || warp::reply::json(&webhooks_for_admin.stats()));
+    
+        // Webhook subscription endpoints
+        let subscriptions = Arc::clone(&self.subscriptions);
+        let with_subscriptions = warp::any().map(move || Arc::clone(&subscriptions));
+        let create_subscription = warp::path!("subscriptions")
+            .and(warp::post())
+            .and(warp::body::json())
+            .and(with_subscriptions.clone())
+            .map(|request: SubscriptionRequest, store: Arc<SubscriptionStore>| match store.create(request) {
+                Ok(subscription) => {
+                    warp::reply::with_status(warp::reply::json(&subscription), StatusCode::CREATED).into_response()
+                }
+                Err(e) => invalid_subscription_reply(e),
+            });
+        let list_subscriptions = warp::path!("subscriptions")
+            .and(warp::get())
+            .and(warp::query::<HashMap<String, String>>())
+            .and(with_subscriptions.clone())
+            .map(|query: HashMap<String, String>, store: Arc<SubscriptionStore>| {
+                match SUBSCRIPTION_LIST.parse(&query).and_then(|params| store.page(&params)) {
+                    Ok(subscriptions) => warp::reply::json(&subscriptions).into_response(),
+                    Err(e) => invalid_query_reply(e),
+                }
+            });
+        let get_subscription = warp::path!("subscriptions" / String)
+            .and(warp::get())
+            .and(with_subscriptions.clone())
+            .map(|id: String, store: Arc<SubscriptionStore>| match store.get(&id) {
+                Some(subscription) => warp::reply::json(&subscription).into_response(),
+                None => subscription_not_found_reply(&id),
+            });
+        let update_subscription = warp::path!("subscriptions" / String)
+            .and(warp::put())
+            .and(warp::body::json())
+            .and(with_subscriptions.clone())
+            .map(|id: String, request: SubscriptionRequest, store: Arc<SubscriptionStore>| match store.update(&id, request) {
+                Ok(Some(subscription)) => warp::reply::json(&subscription).into_response(),
+                Ok(None) => subscription_not_found_reply(&id),
+                Err(e) => invalid_subscription_reply(e),
+            });
+        let delete_subscription = warp::path!("subscriptions" / String)
+            .and(warp::delete())
+            .and(with_subscriptions)
+            .map(|id: String, store: Arc<SubscriptionStore>| {
+                if store.delete(&id) {
+                    StatusCode::NO_CONTENT.into_response()
+                } else {
+                    subscription_not_found_reply(&id)
+                }
+            });
+        let subscription_routes = create_subscription
+            .or(list_subscriptions)
+            .unify()
+            .or(get_subscription)
+            .unify()
+            .or(update_subscription)
+            .unify()
+            .or(delete_subscription)
+            .unify();
+    
+        // Operator endpoints: pause/resume (workers or just processing), scale, purge, rebalance, limits, audit history
+        let workers_for_admin = self.workers.clone();
+        let with_workers = warp::any().map(move || workers_for_admin.clone());
+        let list_workers = warp::path!("admin" / "workers")
+            .and(warp::get())
+            .and(with_workers.clone())
+            .and_then(|workers: Vec<Arc<Worker>>| async move {
+                Ok::<_, Rejection>(warp::reply::json(&admin::worker_stats(&workers).await).into_response())
+            });
+        // Per-worker pause, in either sense (see `PauseMode` for what each stops)
+        let set_worker_paused = warp::path!("admin" / "workers" / usize / String)
+            .map(|id: usize, action: String| (id, action, PauseMode::Worker))
+            .untuple_one()
+            .or(warp::path!("workers" / usize / String)
+                .map(|id: usize, action: String| (id, action, PauseMode::Processing))
+                .untuple_one())
+            .unify()
+            .and(warp::post())
+            .and(with_workers.clone())
+            .and_then(|id: usize, action: String, mode: PauseMode, workers: Vec<Arc<Worker>>| async move {
+                let pause = match action.as_str() {
+                    "pause" => true,
+                    "resume" => false,
+                    _ => return Err(warp::reject::not_found()),
+                };
+                let Some(worker) = workers.get(id) else {
+                    return Ok(error_reply(StatusCode::NOT_FOUND, "not_found", &format!("Worker {} not found", id)));
+                };
+                let message = admin::set_paused(worker, mode, pause);
+                Ok(admin_reply(message, 0, &workers).await)
+            });
+        // Processing-only pause: workers keep taking submissions but leave them queued
+        let pause_processing = warp::path!("admin" / "pause")
+            .and(warp::post())
+            .and(with_workers.clone())
+            .and_then(|workers: Vec<Arc<Worker>>| async move {
+                workers.iter().for_each(|worker| worker.pause_processing());
+                let message = format!("Processing paused on {} workers", workers.len());
+                Ok::<_, Rejection>(admin_reply(message, 0, &workers).await)
+            });
+        let resume_processing = warp::path!("admin" / "resume")
+            .and(warp::post())
+            .and(with_workers.clone())
+            .and_then(|workers: Vec<Arc<Worker>>| async move {
+                workers.iter().for_each(|worker| worker.resume_processing());
+                let message = format!("Processing resumed on {} workers", workers.len());
+                Ok::<_, Rejection>(admin_reply(message, 0, &workers).await)
+            });
+        let scale = warp::path!("admin" / "scale")
+            .and(warp::post())
+            .and(warp::body::json())
+            .and(with_workers.clone())
+            .and_then(|request: ScaleRequest, workers: Vec<Arc<Worker>>| async move {
+                Ok::<_, Rejection>(match admin::scale(&workers, request.workers) {
+                    Ok(()) => admin_reply(format!("{} of {} workers active", request.workers, workers.len()), 0, &workers).await,
+                    Err(e) => error_reply(StatusCode::BAD_REQUEST, "invalid_admin_request", &e.to_string()),
+                })
+            });
+        let purge = warp::path!("admin" / "purge")
+            .and(warp::post())
+            .and(warp::query::<PurgeQuery>())
+            .and(with_workers.clone())
+            .and_then(|query: PurgeQuery, workers: Vec<Arc<Worker>>| async move {
+                Ok::<_, Rejection>(match admin::purge(&workers, &query.status) {
+                    Ok(purged) => admin_reply(format!("Purged {} {} tasks", purged, query.status), purged, &workers).await,
+                    Err(e) => error_reply(StatusCode::BAD_REQUEST, "invalid_admin_request", &e.to_string()),
+                })
+            });
+        let rebalance = warp::path!("admin" / "rebalance")
+            .and(warp::post())
+            .and(with_workers)
+            .and_then(|workers: Vec<Arc<Worker>>| async move {
+                let moved = admin::rebalance(&workers).await;
+                Ok::<_, Rejection>(admin_reply(format!("Moved {} queued tasks", moved), moved, &workers).await)
+            });
+        let audit_for_admin = Arc::clone(&self.audit);
+        let get_audit = warp::path!("admin" / "audit")
+            .and(warp::get())
+            .and(warp::query::<HashMap<String, String>>())
+            .map(move |query: HashMap<String, String>| {
+                match AUDIT_LIST.parse(&query).and_then(|params| audit_for_admin.page(&params)) {
+                    Ok(entries) => warp::reply::json(&entries).into_response(),
+                    Err(e) => invalid_query_reply(e),
+                }
+            });
+        let limits = self.effective_limits();
+        let get_limits = warp::path!("admin" / "limits")
+            .and(warp::get())
+            .map(move || warp::reply::json(&limits).into_response());
+        let federation_for_admin = Arc::clone(&self.federation);
+        let get_federation = warp::path!("admin" / "federation")
+            .and(warp::get())
+            .map(move || warp::reply::json(&federation_for_admin.status()).into_response());
+        let worker_admin_routes = list_workers
+            .or(get_limits)
+            .unify()
+            .or(get_federation)
+            .unify()
+            .or(get_audit)
+            .unify()
+            .or(set_worker_paused)
+            .unify()
+            .or(pause_processing)
+            .unify()
+            .or(resume_processing)
+            .unify()
+            .or(scale)
+            .unify()
+            .or(purge)
+            .unify()
+            .or(rebalance)
+            .unify();
+    
+        // Health check endpoint
+        let health = warp::path("health")
+            .and(warp::get())
+            .map(|| {
+                warp::reply::json(&HealthResponse {
+                    status: "healthy".to_string(),
+                    timestamp: Utc::now(),
+                })
+            });
+
+        // Liveness and readiness probes, answered even while stopping
+        let healthz = warp::path("healthz")
+            .and(warp::get())
+            .map(|| {
+                warp::reply::json(&HealthResponse {
+                    status: "alive".to_string(),
+                    timestamp: Utc::now(),
+                })
+            });
+        // A replica runs no workers, so only whether it's accepting requests counts
+        let workers_for_readiness = if self.config.is_read_only() { Vec::new() } else { self.workers.clone() };
+        let ready_for_readiness = Arc::clone(&self.ready);
+        let draining_for_readiness = Arc::clone(&self.draining);
+        let readyz = warp::path("readyz")
+            .and(warp::get())
+            .and(warp::any().map(move || {
+                // Not before start has finished, nor once stop or a drain has begun
+                let accepting = ready_for_readiness.load(Ordering::Acquire) && !draining_for_readiness.load(Ordering::Acquire);
+                (workers_for_readiness.clone(), accepting)
+            }))
+            .and_then(|(workers, accepting): (Vec<Arc<Worker>>, bool)| async move {
+                let readiness = probes::readiness(&workers, accepting).await;
+                let status = if readiness.is_ready() { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+                Ok::<_, warp::Rejection>(warp::reply::with_status(warp::reply::json(&readiness), status))
+            });
+        let probe_routes = healthz.or(readyz);
+    
+        // Metrics endpoint (Prometheus text format)
+        let metrics_for_route = Arc::clone(&self.http_metrics);
+        let get_metrics = warp::path("metrics")
+            .and(warp::get())
+            .map(move || metrics_for_route.render_prometheus());
+    
+        // Canonical request/response examples for contract tests
+        let get_examples = warp::path("examples")
+            .and(warp::get())
+            .map(|| warp::reply::json(&route_examples()));
+
+        // OpenAPI 3 document built from the same examples
+        let get_openapi = warp::path("openapi.json")
+            .and(warp::get())
+            .map(|| warp::reply::json(&openapi_spec()));
     
-        let routes = create_task
-            .or(get_task)
-            .or(complete_task)
-            .or(get_stats)
-            .or(health)
-            .with(warp::cors().allow_any_origin())
-            .with(warp::log("orchestrator"));
-    
-        let server = warp::serve(routes).run(([127, 0, 0, 1], port));
+        // Read-only replicas answer every mutation with a pointer to the primary
+        let primary_url = self.config.primary_url.clone();
+        let metrics_for_read_only = Arc::clone(&self.http_metrics);
+        // Checks the method itself: a warp::post() rejection would turn every 404 into a 405
+        let read_only = warp::method()
+            .and(warp::path::full())
+            .and_then(move |method: Method, path: FullPath| {
+                let reply = primary_url
+                    .as_deref()
+                    .filter(|_| !method.is_safe())
+                    .map(|primary| {
+                        metrics_for_read_only.record_rejection("read_only");
+                        read_only_reply(primary, path.as_str())
+                    });
+                async move { reply.ok_or_else(warp::reject::not_found) }
+            });
     
-        let handle = tokio::spawn(async move {
-            info!("Orchestrator HTTP server started on port {}", port);
-            server.await;
+        // Everything is refused once `stop` has begun, until the listeners close
+        let running = Arc::clone(&self.running);
+        let metrics_for_not_running = Arc::clone(&self.http_metrics);
+        let not_running = warp::any().and_then(move || {
+            let reply = (!running.load(Ordering::Acquire)).then(|| {
+                metrics_for_not_running.record_rejection("not_running");
+                error_reply(StatusCode::SERVICE_UNAVAILABLE, "not_running", "Orchestrator not running")
+            });
+            async move { reply.ok_or_else(warp::reject::not_found) }
+        });
+
+        // Clients over their `[rate_limit]` get 429; /health is never limited
+        let rate_limiter = Arc::clone(&self.rate_limiter);
+        let keys_for_rate_limit = Arc::clone(&self.api_keys);
+        let metrics_for_rate_limit = Arc::clone(&self.http_metrics);
+        let rate_limited = warp::path::full()
+            .and(warp::header::headers_cloned())
+            .and(warp::addr::remote())
+            .and_then(move |path: FullPath, headers: HeaderMap, remote: Option<SocketAddr>| {
+                let (route, _) = route_pattern(path.as_str());
+                let reply = (rate_limiter.is_enabled() && route != "/health")
+                    .then(|| rate_limiter.check(&rate_limit_client(&keys_for_rate_limit, &headers, remote), &route))
+                    .and_then(Result::err)
+                    .map(|wait| {
+                        metrics_for_rate_limit.record_rejection("rate_limited");
+                        rate_limited_reply(wait)
+                    });
+                async move { reply.ok_or_else(warp::reject::not_found) }
+            });
+
+        // Any valid key may read system-wide data; webhook admin needs a "*" key
+        let require_key = authenticated.clone().map(|_: Principal| ()).untuple_one();
+        let require_admin = authenticated
+            .and_then(|principal: Principal| async move { principal.require_unrestricted() })
+            .untuple_one();
+        let system_routes = require_key.and(
+            get_runtime_stats
+                .or(get_stats_delta)
+                .or(get_stats_stream)
+                .or(get_stats)
+                .or(get_metrics)
+                .or(get_examples)
+                .or(get_openapi),
+        );
+        let admin_routes = require_admin.and(get_webhook_stats.or(subscription_routes).or(worker_admin_routes));
+    
+        let api = probe_routes
+            .or(not_running)
+            .or(rate_limited)
+            .or(read_only)
+            .or(create_task)
+            .or(execute_task)
+            .or(get_task)
+            .or(list)
+            .or(get_changes)
+            .or(ws_events)
+            .or(stream_result)
+            .or(wait_task)
+            .or(complete_task)
+            .or(cancel_task_route)
+            .or(update_task_route)
+            .or(delete_task_route)
+            .or(set_priority)
+            .or(health)
+            .or(system_routes)
+            .or(admin_routes)
+            .map(|reply| Ok(Reply::into_response(reply)))
+            .or_else(capture_rejection);
+    
+        // Slow-request warnings
+        let slow_threshold = std::time::Duration::from_millis(self.config.slow_request_threshold_ms);
+        let metrics_for_log = Arc::clone(&self.http_metrics);
+    
+        // Log and count rejected requests instead of letting warp answer silently
+        let metrics_for_rejections = Arc::clone(&self.http_metrics);
+        let routes = warp::path::full()
+            .and(warp::method())
+            .and(warp::addr::remote())
+            .and(api)
+            .map(move |path: FullPath, method: Method, remote: Option<SocketAddr>, result: Result<Response, Rejection>| {
+                match result {
+                    Ok(reply) => reply,
+                    Err(rejection) => rejection_reply(&metrics_for_rejections, &rejection, &method, path.as_str(), remote),
+                }
+            })
+            .with(warp::cors().allow_any_origin())
+            .with(warp::log("orchestrator"))
+            .with(warp::log::custom(move |info: warp::log::Info<'_>| {
+                let elapsed = info.elapsed();
+                let (route, task_id) = route_pattern(info.path());
+                metrics_for_log.record_request(info.method().as_str(), &route, info.status().as_u16(), elapsed);
+                if slow_threshold.is_zero() || elapsed < slow_threshold {
+                    return;
+                }
+                warn!(
+                    method = %info.method(),
+                    route = %route,
+                    task_id = task_id.unwrap_or("-"),
+                    status = info.status().as_u16(),
+                    duration_ms = elapsed.as_millis() as u64,
+                    "Slow request"
+                );
+                metrics_for_log.record_slow_request();
+            }));
+    
+        // Bind every listener before returning, so a taken port fails `start`
+        // instead of a background task
+        let routes: listeners::ApiRoutes = routes.map(Reply::into_response).boxed();
+        let main = ListenerConfig {
+            address: self.config.listen_addr(),
+            tls: self.config.tls(),
+        };
+        let mut servers = Vec::new();
+        for listener in std::iter::once(&main).chain(&self.config.listeners) {
+            match listeners::serve(routes.clone(), listener) {
+                Ok(server) => servers.push(server),
+                Err(e) => {
+                    for (_, handle) in servers {
+                        handle.abort();
+                    }
+                    return Err(match e {
+                        SystemError::Io(e) if listener.address == main.address => SystemError::Io(std::io::Error::new(
+                            e.kind(),
+                            format!("{}. Pick another port with --orchestrator-port or orchestrator_port", e),
+                        )),
+                        e => e,
+                    });
+                }
+            }
+        }
+        Ok(servers)
+    }
+
+}
+
+/// Write `addrs` to `path` one per line, via a temporary file so readers never
+/// see a partial list
+fn write_port_file(path: &std::path::Path, addrs: &[SocketAddr]) -> std::io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let contents: String = addrs.iter().map(|addr| format!("{}\n", addr)).collect();
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(&tmp, path)
+}
+
+/// Main listener's address from a server's `port_file` (its first line)
+pub fn read_port_file(path: &std::path::Path) -> std::io::Result<SocketAddr> {
+    std::fs::read_to_string(path)?
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .parse()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// What the task submission routes share
+#[derive(Clone)]
+struct SubmitContext {
+    workers: Vec<Arc<Worker>>,
+    scheduler: Arc<Scheduler>,
+    balancer: Arc<LoadBalancer>,
+    validators: ValidatorChain,
+    calculator: Arc<Calculator>,
+    /// Formats of the replies, as the request asked for
+    format: WireFormat,
+    webhooks: Arc<WebhookDispatcher>,
+    metrics: Arc<dyn MetricsSink>,
+    federation: Arc<Federation>,
+    draining: Arc<AtomicBool>,
+    idempotency: Arc<IdempotencyKeys>,
+}
+
+/// Why `submit_task` didn't queue a task, with the reply to send
+enum SubmitError {
+    /// No local worker can take it (all paused, storage or queue full); a federation peer may
+    NoCapacity(Response),
+    Rejected(Response),
+}
+
+impl SubmitError {
+    fn into_response(self) -> Response {
+        match self {
+            SubmitError::NoCapacity(reply) | SubmitError::Rejected(reply) => reply,
+        }
+    }
+}
+
+/// Validate `request` and queue it on the active worker `load_balancing`
+/// picks (paused workers are skipped)
+///
+/// Tasks scheduled for later, or waiting for dependencies, go to the
+/// scheduler instead, without a worker.
+async fn submit_task(context: &SubmitContext, request: CreateTaskRequest) -> Result<(Option<Arc<Worker>>, Task), SubmitError> {
+    if context.draining.load(Ordering::Acquire) {
+        return Err(SubmitError::Rejected(error_reply(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "draining",
+            "Shutting down, not accepting new tasks",
+        )));
+    }
+    let Some(worker) = context.balancer.pick(&context.workers).await else {
+        return Err(SubmitError::NoCapacity(error_reply(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "all_workers_paused",
+            "All workers are paused",
+        )));
+    };
+
+    let task = request
+        .into_task(&context.calculator)
+        .and_then(|task| context.validators.validate(&task).map(|_| task))
+        .map_err(|e| SubmitError::Rejected(error_reply(StatusCode::BAD_REQUEST, "validation_failed", &e.to_string())))?;
+    let task_id = task.id.clone();
+    if let Some(existing) = find_task(&context.workers, &context.scheduler, &task_id) {
+        return Err(SubmitError::Rejected(duplicate_reply(&existing, &task.namespace, context.format)));
+    }
+    if task.status == TaskStatus::Blocked {
+        let task = context.scheduler.block(task).await.map_err(|e| match e {
+            TaskError::UnknownDependency { .. } => {
+                SubmitError::Rejected(error_reply(StatusCode::BAD_REQUEST, "unknown_dependency", &e.to_string()))
+            }
+            e => SubmitError::Rejected(error_reply(StatusCode::CONFLICT, "duplicate_task", &e.to_string())),
+        })?;
+        context.webhooks.notify(WebhookEventType::TaskCreated, task.clone());
+        return Ok((None, task));
+    }
+    if task.status == TaskStatus::Scheduled {
+        context.scheduler.schedule(task.clone()).map_err(|e| {
+            SubmitError::Rejected(error_reply(StatusCode::CONFLICT, "duplicate_task", &e.to_string()))
+        })?;
+        context.webhooks.notify(WebhookEventType::TaskCreated, task.clone());
+        return Ok((None, task));
+    }
+    worker.add_task(task.clone()).await.map_err(|e| match e {
+        // Lost a race with a submission of the same ID, possibly to another worker
+        TaskError::TaskAlreadyExists { .. } => SubmitError::Rejected(match find_task(&context.workers, &context.scheduler, &task_id) {
+            Some(existing) => duplicate_reply(&existing, &task.namespace, context.format),
+            None => error_reply(StatusCode::CONFLICT, "duplicate_task", &e.to_string()),
+        }),
+        TaskError::StorageFull { .. } => {
+            SubmitError::NoCapacity(error_reply(StatusCode::SERVICE_UNAVAILABLE, "storage_full", &e.to_string()))
+        }
+        TaskError::QueueFull { worker, depth, capacity } => SubmitError::NoCapacity(problem_reply(
+            &QueueFullResponse::new(worker, depth, capacity),
+            StatusCode::TOO_MANY_REQUESTS,
+        )),
+        // The worker's own check of the input
+        e => SubmitError::Rejected(error_reply(StatusCode::BAD_REQUEST, "validation_failed", &e.to_string())),
+    })?;
+    report_submitted(context.metrics.as_ref(), worker, &task).await;
+    context.webhooks.notify(WebhookEventType::TaskCreated, task.clone());
+    Ok((Some(Arc::clone(worker)), task))
+}
+
+/// Claim the request's idempotency key (the header, or `idempotency_key` in
+/// the body) for its task, returning the `(namespace, key)` claimed
+///
+/// A key already claimed answers with the task it created (`200` and
+/// `Idempotent-Replayed: true`), or `409` if that task isn't stored (yet).
+async fn claim_idempotency_key(
+    context: &SubmitContext,
+    header: Option<String>,
+    request: &CreateTaskRequest,
+) -> Result<Option<(String, String)>, Response> {
+    if !context.idempotency.is_enabled() {
+        return Ok(None);
+    }
+    let key = match (header.as_ref(), request.idempotency_key.as_ref()) {
+        (Some(header), Some(field)) if header != field => {
+            let message = "Idempotency-Key header and idempotency_key field differ";
+            return Err(error_reply(StatusCode::BAD_REQUEST, "invalid_idempotency_key", message));
+        }
+        (Some(key), _) | (None, Some(key)) => key.clone(),
+        (None, None) => return Ok(None),
+    };
+    idempotency::validate_key(&key)
+        .map_err(|e| error_reply(StatusCode::BAD_REQUEST, "invalid_idempotency_key", &e.to_string()))?;
+
+    let namespace = request.namespace.clone().unwrap_or_else(|| DEFAULT_NAMESPACE.to_string());
+    let Some(existing_id) = context.idempotency.claim(&namespace, &key, &request.id) else {
+        return Ok(Some((namespace, key)));
+    };
+    match find_task(&context.workers, &context.scheduler, &existing_id) {
+        Some(mut task) => {
+            if let Some(worker) = task_index::owner(&context.workers, &existing_id) {
+                task.queue_position = worker.queue_position(&existing_id).await;
+            }
+            let reply = warp::reply::with_header(task_json(context.format, &task), "location", task.url());
+            Err(warp::reply::with_header(reply, IDEMPOTENT_REPLAYED_HEADER, "true").into_response())
+        }
+        None => Err(error_reply(
+            StatusCode::CONFLICT,
+            "idempotency_key_in_use",
+            &format!("Idempotency key already used for task {}, which is being created or no longer stored", existing_id),
+        )),
+    }
+}
+
+/// A submission body, or the reply of the federation peer it was forwarded to
+enum Submission<T> {
+    Local(T),
+    Forwarded(Response),
+}
+
+/// Deserialize a `POST /task/create` or `/task/execute` body, forwarding it to a
+/// federation peer when it names an operation this server doesn't calculate
+async fn parse_submission<T: DeserializeOwned>(
+    context: &SubmitContext,
+    principal: &Principal,
+    body: Value,
+    may_forward: bool,
+) -> Result<Submission<T>, Rejection> {
+    let error = match serde_json::from_value(body.clone()) {
+        Ok(request) => return Ok(Submission::Local(request)),
+        Err(e) => e,
+    };
+    let invalid = || warp::reject::custom(InvalidBody(format!("Request body deserialize error: {}", error)));
+    if !may_forward || federation::unknown_operation(&body).is_none() {
+        return Err(invalid());
+    }
+
+    // The peer sees the task as this server would have stored it
+    let namespace = principal.namespace_for(body.get("namespace").and_then(Value::as_str))?;
+    let mut body = body;
+    if let Value::Object(fields) = &mut body {
+        fields.remove("max_wait_ms");
+        fields.insert("namespace".to_string(), Value::String(namespace));
+        fields
+            .entry("id")
+            .or_insert_with(|| Value::String(uuid::Uuid::new_v4().to_string()));
+    }
+    match forward_submission(context, body, ForwardReason::UnknownOperation).await {
+        Some(reply) => Ok(Submission::Forwarded(reply)),
+        None => Err(invalid()),
+    }
+}
+
+/// Hand a task to a federation peer, answering 202 with where it went, or `None`
+/// when federation is off or no peer accepted it
+async fn forward_submission(context: &SubmitContext, body: Value, reason: ForwardReason) -> Option<Response> {
+    if !context.federation.is_enabled() {
+        return None;
+    }
+    let remote = match context.federation.forward(&body, reason).await {
+        Ok(remote) => remote,
+        Err(e) => {
+            warn!("Could not forward task ({}): {}", reason, e);
+            return None;
+        }
+    };
+    let reason = reason.to_string();
+    context
+        .metrics
+        .counter("tasks_forwarded_total", &[("peer", &remote.peer), ("reason", &reason)], 1);
+    let reply = warp::reply::with_header(warp::reply::json(&remote), "location", format!("/task/{}", remote.id));
+    Some(warp::reply::with_status(reply, StatusCode::ACCEPTED).into_response())
+}
+
+/// The answer of the peer holding `remote` to `method path`, relayed with its
+/// status and an `x-forwarded-to` header naming the peer
+async fn proxy_reply(
+    federation: &Federation,
+    remote: &RemoteTask,
+    method: Method,
+    path: &str,
+    selection: &FieldSelection,
+) -> Response {
+    match federation.proxy(remote, method, path).await {
+        Ok((status, body)) => {
+            let status = StatusCode::from_u16(status).unwrap_or(StatusCode::BAD_GATEWAY);
+            let body = if status.is_success() { selection.apply(&body) } else { body };
+            let reply = warp::reply::with_header(warp::reply::json(&body), "x-forwarded-to", remote.peer.as_str());
+            warp::reply::with_status(reply, status).into_response()
+        }
+        Err(e) => error_reply(StatusCode::BAD_GATEWAY, "peer_unreachable", &e.to_string()),
+    }
+}
+
+/// The stored task with `task_id`, on whichever worker holds it or waiting in `scheduler`
+fn find_task(workers: &[Arc<Worker>], scheduler: &Scheduler, task_id: &str) -> Option<Task> {
+    match task_index::owner(workers, task_id) {
+        Some(worker) => worker.get_task(task_id),
+        None => scheduler.get(task_id),
+    }
+}
+
+/// `task_id` as the primary last stored it, read by a replica
+async fn stored_task(store: &Arc<dyn TaskStore>, task_id: &str) -> Result<Option<Task>, SystemError> {
+    let (store, task_id) = (Arc::clone(store), task_id.to_string());
+    tokio::task::spawn_blocking(move || store.get(&task_id))
+        .await
+        .map_err(|e| SystemError::Store { message: e.to_string() })?
+}
+
+/// Every task the primary has stored, read by a replica
+async fn stored_tasks(store: &Arc<dyn TaskStore>) -> Result<Vec<Task>, SystemError> {
+    let store = Arc::clone(store);
+    tokio::task::spawn_blocking(move || store.load())
+        .await
+        .map_err(|e| SystemError::Store { message: e.to_string() })?
+}
+
+/// The instance's formats `default`, with timestamps as the request's `x-timestamp-format` asks
+fn reply_format(default: WireFormat) -> impl Filter<Extract = (WireFormat,), Error = Rejection> + Clone {
+    warp::header::optional::<TimestampFormat>(TIMESTAMP_FORMAT_HEADER).map(move |requested| default.with_timestamps(requested))
+}
+
+/// `body` (tasks, or something holding them) as JSON in `format`
+fn task_json<T: serde::Serialize>(format: WireFormat, body: &T) -> warp::reply::Json {
+    format.apply(|| warp::reply::json(body))
+}
+
+/// 503 for a replica whose task store can't be read
+fn store_unavailable_reply(e: &SystemError) -> Response {
+    error_reply(StatusCode::SERVICE_UNAVAILABLE, "store_unavailable", &e.to_string())
+}
+
+/// 409 for a submission reusing `existing`'s ID, describing it when it's in the submitter's `namespace`
+fn duplicate_reply(existing: &Task, namespace: &str, format: WireFormat) -> Response {
+    let visible = existing.namespace == namespace;
+    let reply = format.apply(|| problem_reply(&DuplicateTaskResponse::new(existing, visible), StatusCode::CONFLICT));
+    if visible {
+        warp::reply::with_header(reply, "location", existing.url()).into_response()
+    } else {
+        reply.into_response()
+    }
+}
+
+/// Count a queued task and report its worker's new queue depth
+async fn report_submitted(metrics: &dyn MetricsSink, worker: &Worker, task: &Task) {
+    let operation = task.data.operation.to_string();
+    metrics.counter("tasks_submitted_total", &[("operation", &operation)], 1);
+    let worker_id = worker.id.to_string();
+    metrics.gauge("worker_queue_depth", &[("worker", &worker_id)], worker.queue_len().await as f64);
+}
+
+/// The task once `worker` has processed it, or `None` if that takes longer than `max_wait`
+async fn wait_for_result(worker: &Worker, task_id: &str, max_wait: Duration) -> Option<Task> {
+    // The result stream ends when the task finishes
+    let stream = worker.result_stream(task_id)?;
+    tokio::time::timeout(max_wait, stream.for_each(|_| async {})).await.ok()?;
+    worker.get_task(task_id).filter(|task| task.status != TaskStatus::Pending)
+}
+
+/// `task_id` once it's calculated, fails or is cancelled, or as it stands when `timeout` runs out
+///
+/// Waits on the task's result stream, or on the scheduler while it's scheduled,
+/// so nothing is polled. `None` if neither a worker nor the scheduler holds it.
+async fn wait_for_task(workers: &[Arc<Worker>], scheduler: &Scheduler, task_id: &str, timeout: Duration) -> Option<Task> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        let Some(worker) = task_index::owner(workers, task_id) else {
+            let task = scheduler.get(task_id)?;
+            if !scheduler.wait_released(task_id, deadline).await {
+                return scheduler.get(task_id).or(Some(task));
+            }
+            continue;
+        };
+        let task = worker.get_task(task_id)?;
+        if task.status != TaskStatus::Pending {
+            return Some(task);
+        }
+        // Ends with the current attempt; a retried task gets a new stream
+        let stream = worker.result_stream(task_id)?;
+        if tokio::time::timeout_at(deadline, stream.for_each(|_| async {})).await.is_err() {
+            return find_task(workers, scheduler, task_id);
+        }
+    }
+}
+
+/// Newest tasks on `workers` and the scheduler matching `params` whose namespace passes `visible`
+fn list_tasks(workers: &[Arc<Worker>], scheduler: &Scheduler, params: &ListParams, visible: impl Fn(&str) -> bool) -> Result<Vec<Task>, QueryError> {
+    let tasks = workers.iter().flat_map(|worker| worker.tasks()).chain(scheduler.tasks());
+    select_tasks(tasks, params, visible)
+}
+
+/// Newest of `tasks` matching `params` whose namespace passes `visible`
+fn select_tasks(tasks: impl IntoIterator<Item = Task>, params: &ListParams, visible: impl Fn(&str) -> bool) -> Result<Vec<Task>, QueryError> {
+    let status = params.filter::<TaskStatus>("status")?;
+    let labels = match params.filter::<String>("label")? {
+        Some(selector) => parse_label_selector(&selector).ok_or(QueryError::InvalidFilter {
+            param: "label".to_string(),
+            value: selector,
+        })?,
+        None => Vec::new(),
+    };
+    let tasks: Vec<Task> = tasks
+        .into_iter()
+        .filter(|task| status.as_ref().map_or(true, |status| &task.status == status))
+        .filter(|task| task.has_labels(&labels))
+        .filter(|task| visible(&task.namespace))
+        .collect();
+    Ok(params.apply(tasks, |field, a, b| match field {
+        "priority" => a.priority.cmp(&b.priority),
+        "id" => a.id.cmp(&b.id),
+        _ => a.created_at.cmp(&b.created_at),
+    }))
+}
+
+/// Reprioritize a queued task on whichever worker holds it and record the change
+/// Complete `task_id` on whichever worker holds it, notifying webhooks
+fn complete_task(workers: &[Arc<Worker>], scheduler: &Scheduler, webhooks: &WebhookDispatcher, task_id: &str) -> Result<TaskCompletionResponse, TaskError> {
+    if let Some(task) = scheduler.get(task_id) {
+        return Err(TaskError::InvalidStatusTransition {
+            current: task.status,
+            requested: TaskStatus::Completed,
+        });
+    }
+    if let Some(worker) = task_index::owner(workers, task_id) {
+        if worker.complete_task(task_id)? {
+            if let Some(task) = worker.get_task(task_id) {
+                webhooks.notify_completed(task);
+            }
+            return Ok(TaskCompletionResponse {
+                id: task_id.to_string(),
+                status: TaskStatus::Completed,
+                message: "Task completed successfully".to_string(),
+            });
+        }
+    }
+    Err(TaskError::TaskNotFound {
+        id: task_id.to_string(),
+    })
+}
+
+/// Cancel `task_id` on whichever worker holds it, or before it was due
+async fn cancel_task(workers: &[Arc<Worker>], scheduler: &Scheduler, task_id: &str) -> Result<TaskCompletionResponse, TaskError> {
+    if scheduler.cancel(task_id).await.is_some() {
+        return Ok(TaskCompletionResponse {
+            id: task_id.to_string(),
+            status: TaskStatus::Cancelled,
+            message: "Scheduled task cancelled".to_string(),
+        });
+    }
+    if let Some(worker) = task_index::owner(workers, task_id) {
+        if worker.cancel_task(task_id).await? {
+            return Ok(TaskCompletionResponse {
+                id: task_id.to_string(),
+                status: TaskStatus::Cancelled,
+                message: "Task cancelled".to_string(),
+            });
+        }
+    }
+    Err(TaskError::TaskNotFound {
+        id: task_id.to_string(),
+    })
+}
+
+/// Update a queued task on whichever worker holds it, recording a priority change
+async fn update_task(
+    workers: &[Arc<Worker>],
+    scheduler: &Scheduler,
+    audit: &AuditLog,
+    task_id: &str,
+    request: &TaskUpdateRequest,
+    actor: &str,
+) -> Result<Task, TaskError> {
+    if let Some(task) = scheduler.get(task_id) {
+        return Err(TaskError::NotQueued {
+            id: task.id,
+            status: task.status,
+        });
+    }
+    let worker = task_index::owner(workers, task_id).ok_or_else(|| TaskError::TaskNotFound {
+        id: task_id.to_string(),
+    })?;
+    let (task, previous) = worker.update_task(task_id, request).await?;
+    if let Some(priority) = request.priority {
+        audit.record(AuditEntry {
+            at: Utc::now(),
+            actor: actor.to_string(),
+            task_id: task_id.to_string(),
+            change: AuditChange::PriorityChanged {
+                from: previous,
+                to: priority,
+            },
+            reason: None,
+        });
+    }
+    info!("{} updated task {}", actor, task_id);
+    Ok(task)
+}
+
+/// Delete `task_id` from whichever worker holds it, or while it waits to be queued
+async fn delete_task(workers: &[Arc<Worker>], scheduler: &Scheduler, task_id: &str, force: bool) -> Result<TaskCompletionResponse, TaskError> {
+    let deleted = match scheduler.delete(task_id) {
+        Some(task) => Some(task),
+        None => match task_index::owner(workers, task_id) {
+            Some(worker) => worker.delete_task(task_id, force).await?,
+            None => None,
+        },
+    };
+    if let Some(task) = deleted.as_ref().filter(|task| !task.status.is_terminal()) {
+        // It will never finish, so neither would the tasks waiting for it
+        scheduler.dependency_removed(&task.id).await;
+    }
+    match deleted {
+        Some(task) => Ok(TaskCompletionResponse {
+            id: task.id,
+            status: task.status,
+            message: "Task deleted".to_string(),
+        }),
+        None => Err(TaskError::TaskNotFound {
+            id: task_id.to_string(),
+        }),
+    }
+}
+
+async fn change_priority(
+    workers: &[Arc<Worker>],
+    audit: &AuditLog,
+    task_id: &str,
+    request: PriorityChangeRequest,
+    actor: &str,
+) -> Result<Task, TaskError> {
+    let worker = task_index::owner(workers, task_id).ok_or_else(|| TaskError::TaskNotFound {
+            id: task_id.to_string(),
+        })?;
+    let (previous, _) = worker.set_priority(task_id, request.priority).await?;
+    audit.record(AuditEntry {
+        at: Utc::now(),
+        actor: actor.to_string(),
+        task_id: task_id.to_string(),
+        change: AuditChange::PriorityChanged {
+            from: previous,
+            to: request.priority,
+        },
+        reason: request.reason,
+    });
+    info!("{} changed task {} priority from {} to {}", actor, task_id, previous, request.priority);
+    worker.get_task_with_position(task_id).await.ok_or_else(|| TaskError::TaskNotFound {
+        id: task_id.to_string(),
+    })
+}
+
+/// System totals and per-worker stats, shared by `/stats` and `/stats/delta`
+/// What `GET /stats/stream` needs to take a stats snapshot
+struct StatsSnapshots {
+    workers: Vec<Arc<Worker>>,
+    scheduler: Arc<Scheduler>,
+    cache: Arc<StatsCache>,
+    running: Arc<AtomicBool>,
+    num_workers: usize,
+    checkpoint: Arc<StatsCheckpoint>,
+}
+
+/// A `stats` event with the system stats right away, then every `interval`
+/// until the orchestrator stops
+///
+/// Snapshots go through the `GET /stats` cache, so many open streams cost
+/// about as much as one.
+fn stats_events(
+    snapshots: StatsSnapshots,
+    interval: Duration,
+) -> impl futures::Stream<Item = Result<warp::sse::Event, std::convert::Infallible>> {
+    let mut ticks = tokio::time::interval(interval);
+    ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    futures::stream::unfold((snapshots, ticks), |(snapshots, mut ticks)| async move {
+        ticks.tick().await;
+        if !snapshots.running.load(Ordering::Acquire) {
+            return None;
+        }
+        let StatsSnapshots { workers, scheduler, num_workers, checkpoint, .. } = &snapshots;
+        let stats = snapshots
+            .cache
+            .get(false, || system_stats(workers, scheduler, *num_workers, checkpoint))
+            .await;
+        let event = warp::sse::Event::default().event("stats").json_data(&stats).ok()?;
+        Some((Ok(event), (snapshots, ticks)))
+    })
+}
+
+async fn system_stats(workers: &[Arc<Worker>], scheduler: &Scheduler, num_workers: usize, checkpoint: &StatsCheckpoint) -> SystemStats {
+    let mut worker_stats = Vec::new();
+    for worker in workers {
+        worker_stats.push(worker.get_stats().await);
+    }
+    SystemStats::from_workers(worker_stats, num_workers, checkpoint.process_uptime())
+        .with_latency(merged_latency(workers))
+        .with_processing_latency(latency::summarize(&latency::merge_by_operation(
+            workers.iter().map(|w| w.processing_histograms()),
+        )))
+        .with_scheduled(scheduler.len(), scheduler.dispatched())
+        .with_blocked(scheduler.blocked_len())
+        .with_deleted(scheduler.deleted())
+        .with_cumulative(checkpoint.previous())
+}
+
+/// System-wide latency percentiles from the workers' merged histograms
+fn merged_latency(workers: &[Arc<Worker>]) -> LatencySummary {
+    LatencySummary::from_histogram(&latency::merge(workers.iter().map(|w| w.latency_histogram())))
+}
+
+/// Normalize a request path into its route pattern and task id, if any
+///
+/// `/task/abc/complete` becomes (`/task/{id}/complete`, Some("abc")).
+fn route_pattern(path: &str) -> (String, Option<&str>) {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match segments.as_slice() {
+        ["task", "create"] => ("/task/create".to_string(), None),
+        ["task", "execute"] => ("/task/execute".to_string(), None),
+        ["task", id] => ("/task/{id}".to_string(), Some(*id)),
+        ["task", id, rest @ ..] => (format!("/task/{{id}}/{}", rest.join("/")), Some(*id)),
+        _ => (path.to_string(), None),
+    }
+}
+
+/// Chunked `application/x-ndjson` reply with one stage result per line
+fn result_stream_reply(stream: BoxStream<'static, StageResult>) -> Response {
+    let lines = stream.map(|stage| {
+        let mut line = serde_json::to_string(&stage).unwrap_or_default();
+        line.push('\n');
+        Ok::<_, std::convert::Infallible>(line)
+    });
+    let reply = Response::new(warp::hyper::Body::wrap_stream(lines));
+    let reply = warp::reply::with_header(reply, "content-type", "application/x-ndjson");
+    warp::reply::with_header(reply, "cache-control", "no-store").into_response()
+}
+
+/// 405 reply sent by a read-only replica, pointing at the same path on the primary
+fn read_only_reply(primary: &str, path: &str) -> Response {
+    let location = format!("{}{}", primary.trim_end_matches('/'), path);
+    let message = format!("Read-only replica; send this request to {}", location);
+    let reply = error_reply(StatusCode::METHOD_NOT_ALLOWED, "read_only", &message);
+    let reply = warp::reply::with_header(reply, "location", location);
+    warp::reply::with_header(reply, "allow", "GET").into_response()
+}
+
+/// Who a request counts against: its API key when it presents a configured
+/// one, otherwise its IP address
+fn rate_limit_client(keys: &ApiKeys, headers: &HeaderMap, remote: Option<SocketAddr>) -> String {
+    match keys.authenticate(headers) {
+        Ok(principal) if keys.is_enabled() => format!("key:{}", principal.name),
+        _ => format!("ip:{}", remote.map_or_else(|| "unknown".to_string(), |addr| addr.ip().to_string())),
+    }
+}
+
+/// 429 reply for a client over its rate limit, with the seconds to wait in `Retry-After`
+fn rate_limited_reply(wait: Duration) -> Response {
+    let retry_after = rate_limit::retry_after_secs(wait);
+    let message = format!("Rate limit exceeded, retry in {} seconds", retry_after);
+    let reply = error_reply(StatusCode::TOO_MANY_REQUESTS, "rate_limited", &message);
+    warp::reply::with_header(reply, "retry-after", retry_after.to_string()).into_response()
+}
+
+/// 400 for a bad pagination, sort or filter parameter of a list endpoint
+fn invalid_query_reply(error: QueryError) -> Response {
+    error_reply(StatusCode::BAD_REQUEST, "invalid_query", &error.to_string())
+}
+
+/// Problem reply with an explicit status, for handlers that answer errors directly
+fn error_reply(status: StatusCode, reason: &str, message: &str) -> Response {
+    problem_reply(&ErrorResponse::new(status, message).with_reason(reason), status)
+}
+
+/// `body` as `application/problem+json` (RFC 7807) with `status`
+fn problem_reply<T: serde::Serialize>(body: &T, status: StatusCode) -> Response {
+    let reply = warp::reply::with_header(warp::reply::json(body), "content-type", PROBLEM_JSON);
+    warp::reply::with_status(reply, status).into_response()
+}
+
+/// Outcome of an operator action, with every worker's state afterwards
+async fn admin_reply(message: String, tasks_affected: usize, workers: &[Arc<Worker>]) -> Response {
+    info!("Admin: {}", message);
+    warp::reply::json(&AdminResponse {
+        message,
+        tasks_affected,
+        workers: admin::worker_stats(workers).await,
+    })
+    .into_response()
+}
+
+/// 400 reply for a subscription request that failed validation
+fn invalid_subscription_reply(error: ValidationError) -> Response {
+    error_reply(StatusCode::BAD_REQUEST, "invalid_subscription", &error.to_string())
+}
+
+/// 404 reply for an unknown subscription id
+///
+/// Answered directly: a `not_found` rejection would lose to the method
+/// rejections of the sibling `/subscriptions/{id}` routes and come out as 405.
+fn subscription_not_found_reply(id: &str) -> Response {
+    error_reply(StatusCode::NOT_FOUND, "not_found", &format!("Subscription {} not found", id))
+}
+
+/// A request body that isn't the JSON a route expects
+#[derive(Debug)]
+struct InvalidBody(String);
+
+impl warp::reject::Reject for InvalidBody {}
+
+/// Turn a rejection into a value so the caller can inspect it alongside the request
+async fn capture_rejection(
+    rejection: Rejection,
+) -> Result<(Result<Response, Rejection>,), Rejection> {
+    Ok((Err(rejection),))
+}
+
+/// Map a warp rejection to a status code, a metrics reason and a message
+fn classify_rejection(rejection: &Rejection) -> (StatusCode, &'static str, String) {
+    if rejection.is_not_found() {
+        (StatusCode::NOT_FOUND, "not_found", "Not found".to_string())
+    } else if let Some(e) = rejection.find::<warp::filters::body::BodyDeserializeError>() {
+        (StatusCode::BAD_REQUEST, "invalid_body", e.to_string())
+    } else if let Some(e) = rejection.find::<InvalidBody>() {
+        (StatusCode::BAD_REQUEST, "invalid_body", e.0.clone())
+    } else if let Some(e) = rejection.find::<warp::reject::UnsupportedMediaType>() {
+        (StatusCode::UNSUPPORTED_MEDIA_TYPE, "unsupported_media_type", e.to_string())
+    } else if let Some(e) = rejection.find::<warp::reject::PayloadTooLarge>() {
+        (StatusCode::PAYLOAD_TOO_LARGE, "payload_too_large", e.to_string())
+    } else if let Some(e) = rejection.find::<warp::reject::LengthRequired>() {
+        (StatusCode::LENGTH_REQUIRED, "length_required", e.to_string())
+    } else if let Some(e) = rejection.find::<warp::reject::InvalidQuery>() {
+        (StatusCode::BAD_REQUEST, "invalid_query", e.to_string())
+    } else if let Some(e) = rejection.find::<warp::reject::MissingHeader>() {
+        (StatusCode::BAD_REQUEST, "invalid_header", e.to_string())
+    } else if let Some(e) = rejection.find::<warp::reject::InvalidHeader>() {
+        (StatusCode::BAD_REQUEST, "invalid_header", e.to_string())
+    } else if rejection.find::<auth::Unauthorized>().is_some() {
+        (StatusCode::UNAUTHORIZED, "unauthorized", "Missing or unknown API key".to_string())
+    } else if let Some(e) = rejection.find::<auth::Forbidden>() {
+        (
+            StatusCode::FORBIDDEN,
+            "forbidden",
+            format!("API key may not access namespace {}", e.namespace),
+        )
+    } else if let Some(e) = rejection.find::<warp::reject::MethodNotAllowed>() {
+        (StatusCode::METHOD_NOT_ALLOWED, "method_not_allowed", e.to_string())
+    } else {
+        (StatusCode::INTERNAL_SERVER_ERROR, "internal", format!("Unhandled rejection: {:?}", rejection))
+    }
+}
+
+/// Log and count a rejected request, then build its JSON error reply
+fn rejection_reply(
+    metrics: &HttpMetrics,
+    rejection: &Rejection,
+    method: &Method,
+    path: &str,
+    remote: Option<SocketAddr>,
+) -> Response {
+    let (status, reason, message) = classify_rejection(rejection);
+    let client = remote
+        .map(|addr| addr.to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    warn!(
+        "Rejected {} {} from {}: {} ({})",
+        method, path, client, reason, message
+    );
+    metrics.record_rejection(reason);
+
+    error_reply(status, reason, &message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::*;
+    use std::collections::BTreeMap;
+    use std::time::Instant;
+
+    fn create_test_config() -> OrchestratorConfig {
+        OrchestratorConfig {
+            num_workers: 2,
+            threads_per_worker: 2,
+            orchestrator_port: 9999,
+            ..OrchestratorConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_orchestrator_creation() {
+        let config = create_test_config();
+        let orchestrator = TaskOrchestrator::new(config.clone());
+        
+        assert!(orchestrator.is_ok());
+        let orchestrator = orchestrator.unwrap();
+        assert_eq!(orchestrator.workers.len(), config.num_workers);
+    }
+
+    #[tokio::test]
+    async fn test_port_in_use() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut config = create_test_config();
+        config.orchestrator_port = listener.local_addr().unwrap().port();
+        let orchestrator = TaskOrchestrator::new(config.clone()).unwrap();
+
+        let error = orchestrator.start().await.unwrap_err();
+        let SystemError::Io(io) = &error else { panic!("expected Io error, got {}", error) };
+        assert_eq!(io.kind(), std::io::ErrorKind::AddrInUse);
+        let message = error.to_string();
+        assert!(message.contains(&format!("127.0.0.1:{}", config.orchestrator_port)), "{}", message);
+        assert!(message.contains("--orchestrator-port"), "{}", message);
+        assert!(!orchestrator.running.load(Ordering::Acquire));
+    }
+
+    #[tokio::test]
+    async fn test_ephemeral_port_and_port_file() {
+        let path = std::env::temp_dir().join(format!("task-processor-{}.port", uuid::Uuid::new_v4()));
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        config.port_file = Some(path.clone());
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        assert_eq!(orchestrator.bound_addr(), None);
+
+        orchestrator.start().await.unwrap();
+        assert!(orchestrator.is_ready());
+        assert!(orchestrator.workers.iter().all(|worker| worker.is_running()));
+        let addr = orchestrator.bound_addr().unwrap();
+        assert_ne!(addr.port(), 0);
+        assert_eq!(read_port_file(&path).unwrap(), addr);
+        assert!(std::net::TcpStream::connect(addr).is_ok());
+
+        orchestrator.stop().await;
+        assert_eq!(orchestrator.bound_addr(), None);
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_execute_task() {
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        config.simulated_processing_ms = 200;
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        orchestrator.start().await.unwrap();
+        let request = |id: &str| CreateTaskRequest {
+            id: id.to_string(),
+            title: "Execute".to_string(),
+            priority: TaskPriority::Medium,
+            data: TaskData::new(10, Operation::Factorial),
+            expected_result: None,
+            namespace: None,
+            callback_url: None,
+            run_at: None,
+            delay_seconds: None,
+            idempotency_key: None,
+            depends_on: Vec::new(),
+            labels: BTreeMap::new(),
+            timeout_ms: None,
+        };
+
+        let (id, task) = orchestrator.execute_task(request("fast"), Duration::from_secs(5)).await.unwrap();
+        assert_eq!(id, "fast");
+        assert_eq!(task.unwrap().result.as_deref(), Some("3628800"));
+
+        // Falls back to async; the task still finishes afterwards
+        let (id, task) = orchestrator.execute_task(request("slow"), Duration::from_millis(10)).await.unwrap();
+        assert!(task.is_none());
+        assert!(orchestrator.get_task(&id).await.is_ok());
+
+        orchestrator.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_submission() {
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        orchestrator.start().await.unwrap();
+        let body = |namespace: &str| {
+            serde_json::json!({
+                "id": "twice",
+                "title": "Duplicate",
+                "priority": 2,
+                "namespace": namespace,
+                "data": {"type": "calculation", "input": 5, "operation": "factorial"}
+            })
+        };
+        let url = format!("http://{}/task/create", orchestrator.bound_addr().unwrap());
+        let client = reqwest::Client::new();
+        let submit = |namespace: &'static str| client.post(&url).json(&body(namespace)).send();
+
+        assert_eq!(submit("default").await.unwrap().status(), 201);
+        // Round-robin sends the second attempt to another worker; it's still caught
+        let response = submit("default").await.unwrap();
+        assert_eq!(response.status(), 409);
+        assert_eq!(response.headers()["location"], "/task/twice");
+        let conflict: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(conflict["reason"], "duplicate_task");
+        assert_eq!(conflict["existing"]["url"], "/task/twice");
+        assert!(conflict["existing"]["created_at"].is_string());
+        assert!(conflict["existing"]["status"].is_string());
+
+        // Nothing is revealed about another namespace's task
+        let response = submit("other").await.unwrap();
+        assert_eq!(response.status(), 409);
+        let conflict: serde_json::Value = response.json().await.unwrap();
+        assert!(conflict.get("existing").is_none());
+
+        let request: CreateTaskRequest = serde_json::from_value(body("default")).unwrap();
+        assert!(matches!(
+            orchestrator.create_task(request).await,
+            Err(SystemError::Task(TaskError::TaskAlreadyExists { .. }))
+        ));
+        orchestrator.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_duplicate_submissions() {
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        orchestrator.start().await.unwrap();
+        let url = format!("http://{}/task/create", orchestrator.bound_addr().unwrap());
+        let client = reqwest::Client::new();
+        let body = serde_json::json!({
+            "id": "racing",
+            "title": "Duplicate",
+            "priority": 2,
+            "data": {"type": "calculation", "input": 5, "operation": "factorial"}
+        });
+
+        // Round-robin spreads the attempts over every worker; only one may win
+        let statuses = futures::future::join_all((0..8).map(|_| client.post(&url).json(&body).send())).await;
+        let statuses: Vec<u16> = statuses.into_iter().map(|response| response.unwrap().status().as_u16()).collect();
+        assert_eq!(statuses.iter().filter(|&&status| status == 201).count(), 1, "{:?}", statuses);
+        assert!(statuses.iter().all(|&status| status == 201 || status == 409));
+        let holders = orchestrator.workers.iter().filter(|worker| worker.get_task("racing").is_some()).count();
+        assert_eq!(holders, 1);
+        orchestrator.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_stats_stream() {
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        config.stats_cache_ms = 0;
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        orchestrator.start().await.unwrap();
+        let base = format!("http://{}", orchestrator.bound_addr().unwrap());
+        let mut response = reqwest::get(format!("{}/stats/stream", base)).await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+
+        // Events arrive as they're sent; collect the first two
+        let mut received = String::new();
+        let collected = tokio::time::timeout(Duration::from_secs(5), async {
+            while received.matches("event:stats").count() < 2 {
+                let chunk = response.chunk().await.unwrap().expect("stream open");
+                received.push_str(&String::from_utf8_lossy(&chunk));
+            }
+        })
+        .await;
+        assert!(collected.is_ok(), "expected two events, got {:?}", received);
+        let data = received.lines().find_map(|line| line.strip_prefix("data:")).unwrap();
+        let stats: SystemStats = serde_json::from_str(data).unwrap();
+        assert_eq!(stats.total_workers, 2);
+        orchestrator.stop().await;
+    }
+
+    #[test]
+    fn test_stats_stream_interval_validation() {
+        let mut config = create_test_config();
+        config.stats_stream_interval_seconds = 0;
+        assert!(matches!(validation_error(&config), ValidationError::InvalidStreamInterval(0)));
+        config.stats_stream_interval_seconds = MAX_STATS_STREAM_INTERVAL_SECS + 1;
+        assert!(matches!(validation_error(&config), ValidationError::InvalidStreamInterval(_)));
+    }
+
+    #[tokio::test]
+    async fn test_field_selection() {
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        orchestrator.start().await.unwrap();
+        let base = format!("http://{}", orchestrator.bound_addr().unwrap());
+        let client = reqwest::Client::new();
+        let body = serde_json::json!({
+            "id": "picked",
+            "title": "Fields",
+            "priority": 2,
+            "data": {"type": "calculation", "input": 5, "operation": "factorial"}
+        });
+        assert_eq!(client.post(format!("{}/task/create", base)).json(&body).send().await.unwrap().status(), 201);
+
+        let get = |query: &str| client.get(format!("{}/task/picked{}", base, query)).send();
+        let task: serde_json::Value = get("?fields=id,status").await.unwrap().json().await.unwrap();
+        assert_eq!(task.as_object().unwrap().len(), 2);
+        assert_eq!(task["id"], "picked");
+        assert!(task["status"].is_string());
+
+        let tasks: serde_json::Value = client.get(format!("{}/tasks?fields=id", base)).send().await.unwrap().json().await.unwrap();
+        assert_eq!(tasks, serde_json::json!([{"id": "picked"}]));
+
+        let response = get("?fields=id,colour").await.unwrap();
+        assert_eq!(response.status(), 400);
+        let error: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(error["reason"], "invalid_fields");
+        let response = client.get(format!("{}/tasks?fields=", base)).send().await.unwrap();
+        assert_eq!(response.status(), 400);
+        orchestrator.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_drain_processes_queue_and_refuses_tasks() {
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        config.simulated_processing_ms = 50;
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        orchestrator.start().await.unwrap();
+        let request = |id: String| CreateTaskRequest {
+            id,
+            title: "Drained".to_string(),
+            priority: TaskPriority::Low,
+            data: TaskData::new(5, Operation::Factorial),
+            expected_result: None,
+            namespace: None,
+            callback_url: None,
+            run_at: None,
+            delay_seconds: None,
+            idempotency_key: None,
+            depends_on: Vec::new(),
+            labels: BTreeMap::new(),
+            timeout_ms: None,
+        };
+        for n in 0..8 {
+            orchestrator.create_task(request(format!("drain-{}", n))).await.unwrap();
+        }
+
+        assert!(orchestrator.drain(Duration::from_secs(5)).await.is_empty());
+        for n in 0..8 {
+            let task = orchestrator.get_task(&format!("drain-{}", n)).await.unwrap();
+            assert_eq!(task.status, TaskStatus::Processing);
+        }
+        assert!(!orchestrator.is_ready());
+
+        // Reads still work; new tasks are refused
+        assert!(orchestrator.create_task(request("late".to_string())).await.is_err());
+        let base = format!("http://{}", orchestrator.bound_addr().unwrap());
+        let client = reqwest::Client::new();
+        let body = serde_json::json!({
+            "id": "late",
+            "title": "Too late",
+            "priority": 2,
+            "data": {"type": "calculation", "input": 5, "operation": "factorial"}
+        });
+        let response = client.post(format!("{}/task/create", base)).json(&body).send().await.unwrap();
+        assert_eq!(response.status(), 503);
+        let error: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(error["reason"], "draining");
+        assert_eq!(client.get(format!("{}/task/drain-0", base)).send().await.unwrap().status(), 200);
+        orchestrator.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_liveness_and_readiness_probes() {
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        config.api_keys = vec![ApiKeyConfig {
+            name: "ops".to_string(),
+            key: "secret".to_string(),
+            namespaces: vec!["*".to_string()],
+        }];
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        orchestrator.start().await.unwrap();
+        let base = format!("http://{}", orchestrator.bound_addr().unwrap());
+        let get = |path: &str| reqwest::get(format!("{}{}", base, path));
+
+        // Open without an API key
+        assert_eq!(get("/healthz").await.unwrap().status(), 200);
+        let response = get("/readyz").await.unwrap();
+        assert_eq!(response.status(), 200);
+        let readiness: ReadinessResponse = response.json().await.unwrap();
+        assert_eq!((readiness.status.as_str(), readiness.workers_running), ("ready", 2));
+
+        assert!(orchestrator.drain(Duration::from_secs(1)).await.is_empty());
+        let response = get("/readyz").await.unwrap();
+        assert_eq!(response.status(), 503);
+        let readiness: ReadinessResponse = response.json().await.unwrap();
+        assert!(!readiness.accepting_tasks);
+        assert_eq!(readiness.reasons, vec!["not accepting tasks"]);
+        assert_eq!(get("/healthz").await.unwrap().status(), 200);
+        orchestrator.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_drain_timeout_reports_remaining_tasks() {
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        config.num_workers = 1;
+        config.threads_per_worker = 1;
+        config.simulated_processing_ms = 200;
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        orchestrator.start().await.unwrap();
+        for n in 0..5 {
+            let task = Task::with_id(format!("slow-{}", n), "Slow".to_string(), TaskPriority::Low, TaskData::new(5, Operation::Factorial));
+            orchestrator.workers[0].add_task(task).await.unwrap();
+        }
+
+        let remaining = orchestrator.drain(Duration::from_millis(100)).await;
+        assert!(!remaining.is_empty());
+        assert!(remaining.iter().all(|task| task.status == TaskStatus::Pending));
+        orchestrator.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_keys() {
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        orchestrator.start().await.unwrap();
+        let base = format!("http://{}", orchestrator.bound_addr().unwrap());
+        let client = reqwest::Client::new();
+        let body = serde_json::json!({
+            "title": "Charge once",
+            "data": {"type": "calculation", "input": 5, "operation": "factorial"}
+        });
+        let create = |key: &'static str, body: serde_json::Value| {
+            client.post(format!("{}/task/create", base)).header("Idempotency-Key", key).json(&body).send()
+        };
+
+        let first = create("order-1", body.clone()).await.unwrap();
+        assert_eq!(first.status(), 201);
+        let first: serde_json::Value = first.json().await.unwrap();
+        let repeat = create("order-1", body.clone()).await.unwrap();
+        assert_eq!(repeat.status(), 200);
+        assert_eq!(repeat.headers()["idempotent-replayed"], "true");
+        let repeat: serde_json::Value = repeat.json().await.unwrap();
+        assert_eq!(repeat["id"], first["id"]);
+        assert!(repeat["status"].is_string());
+        assert_eq!(create("order-2", body.clone()).await.unwrap().status(), 201);
+
+        // The body field works the same, but may not contradict the header
+        let mut with_field = body.clone();
+        with_field["idempotency_key"] = "order-1".into();
+        let response = client.post(format!("{}/task/create", base)).json(&with_field).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        let response = create("order-3", with_field).await.unwrap();
+        assert_eq!(response.status(), 400);
+        let error: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(error["reason"], "invalid_idempotency_key");
+
+        // A rejected submission doesn't use up its key
+        let mut invalid = body.clone();
+        invalid["data"]["input"] = 500.into();
+        assert_eq!(create("order-4", invalid).await.unwrap().status(), 400);
+        assert_eq!(create("order-4", body).await.unwrap().status(), 201);
+
+        let request = |id: &str| CreateTaskRequest {
+            id: id.to_string(),
+            title: "Charge once".to_string(),
+            priority: TaskPriority::Low,
+            data: TaskData::new(5, Operation::Factorial),
+            expected_result: None,
+            namespace: None,
+            callback_url: None,
+            run_at: None,
+            delay_seconds: None,
+            idempotency_key: Some("order-5".to_string()),
+            depends_on: Vec::new(),
+            labels: BTreeMap::new(),
+            timeout_ms: None,
+        };
+        assert_eq!(orchestrator.create_task(request("api-1")).await.unwrap(), "api-1");
+        assert_eq!(orchestrator.create_task(request("api-2")).await.unwrap(), "api-1");
+        assert!(orchestrator.get_task("api-2").await.is_err());
+        orchestrator.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiting() {
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        config.rate_limit = RateLimitConfig {
+            requests_per_second: 0.5,
+            burst: 2,
+            routes: vec![RateLimitRule {
+                route: "/task/create".to_string(),
+                requests_per_second: 0.5,
+                burst: 1,
+            }],
+        };
+        config.api_keys = vec![
+            ApiKeyConfig {
+                name: "team-a".to_string(),
+                key: "key-a".to_string(),
+                namespaces: vec!["*".to_string()],
+            },
+            ApiKeyConfig {
+                name: "team-b".to_string(),
+                key: "key-b".to_string(),
+                namespaces: vec!["*".to_string()],
+            },
+        ];
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        orchestrator.start().await.unwrap();
+        let base = format!("http://{}", orchestrator.bound_addr().unwrap());
+        let client = reqwest::Client::new();
+        let get = |path: &str, key: &str| client.get(format!("{}{}", base, path)).header("x-api-key", key).send();
+
+        assert_eq!(get("/stats", "key-a").await.unwrap().status(), 200);
+        assert_eq!(get("/tasks", "key-a").await.unwrap().status(), 200);
+        let limited = get("/stats", "key-a").await.unwrap();
+        assert_eq!(limited.status(), 429);
+        let retry_after: u64 = limited.headers()["retry-after"].to_str().unwrap().parse().unwrap();
+        assert!((1..=2).contains(&retry_after));
+        let error: serde_json::Value = limited.json().await.unwrap();
+        assert_eq!(error["reason"], "rate_limited");
+
+        // Each key has its own buckets, and /health is never limited
+        assert_eq!(get("/stats", "key-b").await.unwrap().status(), 200);
+        assert_eq!(get("/health", "key-a").await.unwrap().status(), 200);
+
+        // /task/create has a bucket of its own
+        let body = serde_json::json!({
+            "title": "Limited",
+            "data": {"type": "calculation", "input": 5, "operation": "factorial"}
+        });
+        let create = || client.post(format!("{}/task/create", base)).header("x-api-key", "key-a").json(&body).send();
+        assert_eq!(create().await.unwrap().status(), 201);
+        assert_eq!(create().await.unwrap().status(), 429);
+
+        assert_eq!(orchestrator.http_metrics().rejection_count("rate_limited"), 2);
+        orchestrator.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_queue_full_backpressure() {
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        config.num_workers = 1;
+        config.threads_per_worker = 1;
+        config.simulated_processing_ms = 2000;
+        config.max_queue_size = 2;
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        orchestrator.start().await.unwrap();
+        let base = format!("http://{}", orchestrator.bound_addr().unwrap());
+        let client = reqwest::Client::new();
+        let body = serde_json::json!({
+            "title": "Backlog",
+            "data": {"type": "calculation", "input": 5, "operation": "factorial"}
+        });
+
+        // One task in flight (if the thread took it yet) plus two queued
+        let mut refused = None;
+        for _ in 0..4 {
+            let response = client.post(format!("{}/task/create", base)).json(&body).send().await.unwrap();
+            if response.status() == 429 {
+                refused = Some(response);
+                break;
+            }
+            assert_eq!(response.status(), 201);
+        }
+        let refused = refused.expect("queue never filled");
+        assert_eq!(refused.headers()["content-type"], PROBLEM_JSON);
+        let error: serde_json::Value = refused.json().await.unwrap();
+        assert_eq!(error["reason"], "queue_full");
+        assert_eq!(error["worker"], 0);
+        assert_eq!(error["queue_depth"], 2);
+        assert_eq!(error["max_queue_size"], 2);
+        assert_eq!(orchestrator.effective_limits().queue.max_queue_size, 2);
+
+        orchestrator.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_effective_limits() {
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        config.max_tasks_per_worker = 50;
+        config.worker_batch_sizes = vec![0, 16];
+        let orchestrator = TaskOrchestrator::new(config)
+            .unwrap()
+            .with_validator(crate::validation::LargeInputPriorityRule::new(10, TaskPriority::High));
+
+        let limits = orchestrator.effective_limits();
+        let factorial = limits.operations.iter().find(|limits| limits.operation == Operation::Factorial).unwrap();
+        assert_eq!((factorial.min_input, factorial.max_input), (0, 20));
+        let generate_prime = limits.operations.iter().find(|limits| limits.operation == Operation::GeneratePrime).unwrap();
+        assert_eq!(generate_prime.min_input, 2);
+        assert_eq!(limits.validators, vec!["large_input_priority"]);
+        assert_eq!(limits.queue.batch_sizes, vec![0, 16]);
+        assert_eq!(limits.retention.max_tasks_per_worker, 50);
+
+        orchestrator.start().await.unwrap();
+        let url = format!("http://{}/admin/limits", orchestrator.bound_addr().unwrap());
+        let served: serde_json::Value = reqwest::get(url).await.unwrap().json().await.unwrap();
+        assert_eq!(served, serde_json::to_value(&limits).unwrap());
+        orchestrator.stop().await;
+    }
+
+    #[test]
+    fn test_operation_limits_are_per_instance() {
+        let mut config = create_test_config();
+        config.operation_limits.max_factorial_input = 10;
+        let narrow = TaskOrchestrator::new(config).unwrap();
+        let default = TaskOrchestrator::new(create_test_config()).unwrap();
+
+        let max_factorial = |orchestrator: &TaskOrchestrator| {
+            let limits = orchestrator.effective_limits();
+            limits.operations.iter().find(|limits| limits.operation == Operation::Factorial).unwrap().max_input
+        };
+        assert_eq!((max_factorial(&narrow), max_factorial(&default)), (10, 20));
+
+        let data = TaskData::new(12, Operation::Factorial);
+        assert!(data.validate(&narrow.calculator).is_err());
+        assert!(data.validate(&default.calculator).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_priority_override_is_audited() {
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        config.num_workers = 1;
+        config.threads_per_worker = 1;
+        config.simulated_processing_ms = 500;
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        orchestrator.start().await.unwrap();
+        let base = format!("http://{}", orchestrator.bound_addr().unwrap());
+        let client = reqwest::Client::new();
+        for id in ["busy", "a", "b"] {
+            let body = serde_json::json!({
+                "id": id,
+                "title": "Queued",
+                "priority": 1,
+                "data": {"type": "calculation", "input": 5, "operation": "factorial"}
+            });
+            assert_eq!(client.post(format!("{}/task/create", base)).json(&body).send().await.unwrap().status(), 201);
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let set_priority = |id: &str| {
+            client
+                .post(format!("{}/task/{}/priority", base, id))
+                .json(&serde_json::json!({"priority": 3, "reason": "customer escalation"}))
+                .send()
+        };
+
+        let response = set_priority("b").await.unwrap();
+        assert_eq!(response.status(), 200);
+        let task: serde_json::Value = response.json().await.unwrap();
+        assert_eq!((task["priority"].as_u64(), task["queue_position"].as_u64()), (Some(3), Some(1)));
+
+        // Already taken off the queue, and unknown
+        let response = set_priority("busy").await.unwrap();
+        assert_eq!(response.status(), 409);
+        assert_eq!(response.json::<serde_json::Value>().await.unwrap()["reason"], "not_queued");
+        assert_eq!(set_priority("missing").await.unwrap().status(), 404);
+
+        let audit: serde_json::Value = client.get(format!("{}/admin/audit?task_id=b", base)).send().await.unwrap().json().await.unwrap();
+        assert_eq!(audit.as_array().unwrap().len(), 1);
+        assert_eq!(audit[0]["action"], "priority_changed");
+        assert_eq!((audit[0]["from"].as_u64(), audit[0]["to"].as_u64()), (Some(1), Some(3)));
+        assert_eq!(audit[0]["actor"], "anonymous");
+        assert_eq!(audit[0]["reason"], "customer escalation");
+        assert_eq!(orchestrator.audit_log().len(), 1);
+        orchestrator.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_analytics_records_finished_tasks() {
+        let dir = std::env::temp_dir().join(format!("analytics-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        config.analytics = Some(AnalyticsConfig { path: dir.join("analytics.db") });
+        config.inject_failures = vec![InjectedFailure {
+            operation: Operation::Factorial,
+            input: 12,
+            message: None,
+            panic: false,
+        }];
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        orchestrator.start().await.unwrap();
+
+        for (id, input) in [("small", 5), ("large", 12)] {
+            let request = CreateTaskRequest {
+                id: id.to_string(),
+                title: "Analytics".to_string(),
+                priority: TaskPriority::Medium,
+                data: TaskData::new(input, Operation::Factorial),
+                expected_result: None,
+                namespace: None,
+                callback_url: None,
+                run_at: None,
+                delay_seconds: None,
+                idempotency_key: None,
+                depends_on: Vec::new(),
+                labels: BTreeMap::new(),
+                timeout_ms: None,
+            };
+            let (_, task) = orchestrator.execute_task(request, Duration::from_secs(2)).await.unwrap();
+            assert!(task.is_some());
+        }
+        // Stopping writes out everything recorded
+        orchestrator.stop().await;
+
+        let report = crate::analytics::analyze(&dir.join("analytics.db")).unwrap();
+        assert_eq!(report.tasks, 2);
+        let rates: Vec<_> = report.failure_rates.iter().map(|rate| (rate.input_range.as_str(), rate.failed)).collect();
+        assert_eq!(rates, vec![("0-9", 0), ("10-99", 1)]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stats_checkpoint_survives_restart() {
+        let path = std::env::temp_dir().join(format!("stats-{}.json", uuid::Uuid::new_v4()));
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        config.num_workers = 1;
+        config.stats_checkpoint = Some(StatsCheckpointConfig { path: path.clone(), interval_seconds: 0 });
+        assert!(matches!(validation_error(&config), ValidationError::InvalidCheckpointInterval(0)));
+        config.stats_checkpoint = Some(StatsCheckpointConfig { path: path.clone(), interval_seconds: 60 });
+
+        let orchestrator = TaskOrchestrator::new(config.clone()).unwrap();
+        orchestrator.start().await.unwrap();
+        let request = CreateTaskRequest::new("Counted".to_string(), TaskPriority::Low, TaskData::new(10, Operation::Fibonacci));
+        let (task_id, _) = orchestrator.execute_task(request, Duration::from_secs(2)).await.unwrap();
+        orchestrator.complete_task(&task_id).await.unwrap();
+        // Saved on stop
+        orchestrator.stop().await;
+
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        let stats = orchestrator.get_system_stats().await;
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(stats.total_tasks_completed, 0);
+        assert_eq!(stats.cumulative.tasks_processed, 1);
+        assert_eq!(stats.cumulative.tasks_completed, 1);
+        assert_eq!(stats.cumulative.workers[&0].tasks_completed, 1);
+    }
+
+    #[tokio::test]
+    async fn test_task_store_restores_tasks() {
+        let dir = std::env::temp_dir().join(format!("task-store-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        config.num_workers = 1;
+        config.task_store = Some(TaskStoreConfig { path: dir.join("tasks.db") });
+        let request = |id: &str| CreateTaskRequest {
+            id: id.to_string(),
+            title: "Stored".to_string(),
+            priority: TaskPriority::Low,
+            data: TaskData::new(10, Operation::Fibonacci),
+            expected_result: None,
+            namespace: None,
+            callback_url: None,
+            run_at: None,
+            delay_seconds: None,
+            idempotency_key: None,
+            depends_on: Vec::new(),
+            labels: BTreeMap::new(),
+            timeout_ms: None,
+        };
+
+        let orchestrator = TaskOrchestrator::new(config.clone()).unwrap();
+        orchestrator.start().await.unwrap();
+        orchestrator.execute_task(request("done"), Duration::from_secs(2)).await.unwrap();
+        orchestrator.complete_task("done").await.unwrap();
+        // Paused, so these are still queued at the restart
+        let worker = &orchestrator.workers[0];
+        worker.pause();
+        for id in ["queued", "urgent"] {
+            let task = Task::with_id(id.to_string(), "Stored".to_string(), TaskPriority::Low, TaskData::new(10, Operation::Fibonacci));
+            worker.add_task(task).await.unwrap();
+        }
+        worker.set_priority("urgent", TaskPriority::High).await.unwrap();
+        orchestrator.stop().await;
+
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        orchestrator.start().await.unwrap();
+        let done = orchestrator.get_task("done").await.unwrap();
+        assert_eq!(done.status, TaskStatus::Completed);
+        assert_eq!(done.result.as_deref(), Some("55"));
+        assert_eq!(orchestrator.get_task("urgent").await.unwrap().priority, TaskPriority::High);
+        // Pending tasks are queued again and calculated
+        for id in ["queued", "urgent"] {
+            let deadline = Instant::now() + Duration::from_secs(2);
+            while orchestrator.get_task(id).await.unwrap().status != TaskStatus::Processing {
+                assert!(Instant::now() < deadline, "{} not calculated after the restart", id);
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }
+        orchestrator.stop().await;
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_changes_feed() {
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        orchestrator.start().await.unwrap();
+        let base = format!("http://{}", orchestrator.bound_addr().unwrap());
+        let client = reqwest::Client::new();
+        let body = serde_json::json!({
+            "id": "fed",
+            "title": "Changes",
+            "data": {"type": "calculation", "input": 5, "operation": "factorial"}
+        });
+        assert_eq!(client.post(format!("{}/task/create", base)).json(&body).send().await.unwrap().status(), 201);
+
+        // Created, then processed once a worker has calculated it
+        let mut page = orchestrator.changes(0, 10);
+        for _ in 0..50 {
+            if page.changes.len() == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            page = orchestrator.changes(0, 10);
+        }
+        let events: Vec<_> = page.changes.iter().map(|change| (change.sequence, change.event, change.status.clone())).collect();
+        assert_eq!(
+            events,
+            vec![(1, TaskEvent::Created, TaskStatus::Pending), (2, TaskEvent::Processed, TaskStatus::Processing)]
+        );
+
+        assert_eq!(client.post(format!("{}/task/fed/complete", base)).send().await.unwrap().status(), 200);
+        let url = format!("{}/changes?since={}", base, page.next);
+        let mut changes = serde_json::Value::Null;
+        for _ in 0..50 {
+            changes = client.get(&url).send().await.unwrap().json().await.unwrap();
+            if changes["next"] == 3 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(changes["changes"][0]["event"], "completed");
+        assert_eq!(changes["changes"][0]["task_id"], "fed");
+        assert_eq!(changes["truncated"], false);
+
+        let response = client.get(format!("{}/changes?limit=0", base)).send().await.unwrap();
+        assert_eq!(response.status(), 400);
+        orchestrator.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_problem_replies() {
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        config.num_workers = 1;
+        config.threads_per_worker = 1;
+        config.simulated_processing_ms = 5_000;
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        orchestrator.start().await.unwrap();
+        let base = format!("http://{}", orchestrator.bound_addr().unwrap());
+        let client = reqwest::Client::new();
+        let body = serde_json::json!({
+            "id": "slow",
+            "title": "Problems",
+            "data": {"type": "calculation", "input": 5, "operation": "factorial"}
+        });
+        assert_eq!(client.post(format!("{}/task/create", base)).json(&body).send().await.unwrap().status(), 201);
+
+        // Still pending, so it can't be completed yet
+        let response = client.post(format!("{}/task/slow/complete", base)).send().await.unwrap();
+        assert_eq!(response.status(), 409);
+        assert_eq!(response.headers()["content-type"], PROBLEM_JSON);
+        let problem: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(problem["reason"], "not_completable");
+        assert_eq!((problem["status"].as_u64(), problem["title"].as_str()), (Some(409), Some("Conflict")));
+        assert!(matches!(orchestrator.complete_task("slow").await, Err(TaskError::InvalidStatusTransition { .. })));
+
+        let response = client.post(format!("{}/task/create", base)).json(&body).send().await.unwrap();
+        assert_eq!(response.status(), 409);
+        let problem: serde_json::Value = response.json().await.unwrap();
+        assert_eq!((problem["reason"].as_str(), problem["existing"]["id"].as_str()), (Some("duplicate_task"), Some("slow")));
+
+        // Stopping: requests still arriving are refused
+        orchestrator.running.store(false, Ordering::Release);
+        let response = client.get(format!("{}/task/slow", base)).send().await.unwrap();
+        assert_eq!(response.status(), 503);
+        assert_eq!(response.json::<serde_json::Value>().await.unwrap()["reason"], "not_running");
+        orchestrator.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_cancel_task() {
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        config.num_workers = 1;
+        config.threads_per_worker = 1;
+        config.simulated_processing_ms = 5_000;
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        orchestrator.start().await.unwrap();
+        let base = format!("http://{}", orchestrator.bound_addr().unwrap());
+        let client = reqwest::Client::new();
+        for id in ["busy", "queued"] {
+            let body = serde_json::json!({
+                "id": id,
+                "title": "Cancelled",
+                "data": {"type": "calculation", "input": 5, "operation": "factorial"}
+            });
+            assert_eq!(client.post(format!("{}/task/create", base)).json(&body).send().await.unwrap().status(), 201);
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let cancel = |id: &str| client.post(format!("{}/task/{}/cancel", base, id)).send();
+
+        // One queued, one in its (simulated) calculation, which ends early
+        for id in ["queued", "busy"] {
+            let response = cancel(id).await.unwrap();
+            assert_eq!(response.status(), 200);
+            assert_eq!(response.json::<serde_json::Value>().await.unwrap()["status"], "cancelled");
+        }
+        let idle = tokio::time::timeout(Duration::from_secs(2), async {
+            while orchestrator.workers[0].busy_threads() > 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+        assert!(idle.is_ok());
+        let task: serde_json::Value = client.get(format!("{}/task/busy", base)).send().await.unwrap().json().await.unwrap();
+        assert_eq!(task["status"], "cancelled");
+        assert!(task.get("result").is_none());
+        let stats = orchestrator.worker_stats().await;
+        assert_eq!((stats[0].tasks_processed, stats[0].tasks_cancelled), (0, 2));
+
+        let response = cancel("busy").await.unwrap();
+        assert_eq!(response.status(), 409);
+        assert_eq!(response.json::<serde_json::Value>().await.unwrap()["reason"], "not_cancellable");
+        assert_eq!(cancel("missing").await.unwrap().status(), 404);
+        assert!(matches!(orchestrator.cancel_task("missing").await, Err(TaskError::TaskNotFound { .. })));
+        orchestrator.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_task_index_follows_tasks() {
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        config.threads_per_worker = 1;
+        config.simulated_processing_ms = 5_000;
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        orchestrator.start().await.unwrap();
+        let ids = ["a", "b", "c", "d", "e", "f"];
+        for id in ids {
+            let request = CreateTaskRequest {
+                id: id.to_string(),
+                title: "Indexed".to_string(),
+                priority: TaskPriority::Medium,
+                data: TaskData::new(5, Operation::Factorial),
+                expected_result: None,
+                namespace: None,
+                callback_url: None,
+                run_at: None,
+                delay_seconds: None,
+                idempotency_key: None,
+                depends_on: Vec::new(),
+                labels: BTreeMap::new(),
+                timeout_ms: None,
+            };
+            orchestrator.create_task(request).await.unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let index = Arc::clone(orchestrator.workers[0].task_index().unwrap());
+        assert_eq!(index.len(), 6);
+        let on_first: Vec<&str> = ids.into_iter().filter(|id| index.owner(id) == Some(0)).collect();
+        assert_eq!(on_first.len(), 3);
+
+        // Paused worker 0's queue moves to worker 1, and lookups follow it
+        orchestrator.workers[0].pause();
+        assert_eq!(orchestrator.rebalance_queues().await, 2);
+        let moved: Vec<&str> = on_first.into_iter().filter(|id| index.owner(id) == Some(1)).collect();
+        assert_eq!(moved.len(), 2);
+        for &id in &moved {
+            assert_eq!(orchestrator.get_task(id).await.unwrap().id, id);
+            orchestrator.cancel_task(id).await.unwrap();
+        }
+        assert_eq!(orchestrator.purge_tasks(&TaskStatus::Cancelled).unwrap(), 2);
+        assert_eq!(index.len(), 4);
+        assert!(matches!(orchestrator.get_task(moved[0]).await, Err(TaskError::TaskNotFound { .. })));
+        orchestrator.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_tasks() {
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        orchestrator.start().await.unwrap();
+        let base = format!("http://{}", orchestrator.bound_addr().unwrap());
+        let client = reqwest::Client::new();
+        let create = |body: Value| client.post(format!("{}/task/create", base)).json(&body).send();
+        let task = |id: &str, schedule: Value| {
+            let mut body = serde_json::json!({
+                "id": id,
+                "title": "Scheduled",
+                "data": {"type": "calculation", "input": 5, "operation": "factorial"}
+            });
+            body.as_object_mut().unwrap().extend(schedule.as_object().unwrap().clone());
+            body
+        };
+        let get = |id: &str| client.get(format!("{}/task/{}", base, id)).send();
+
+        let run_at = Utc::now() + chrono::Duration::milliseconds(300);
+        let response = create(task("soon", serde_json::json!({"run_at": run_at.to_rfc3339()}))).await.unwrap();
+        assert_eq!(response.status(), 201);
+        assert_eq!(response.json::<Value>().await.unwrap()["status"], "scheduled");
+        assert_eq!(create(task("later", serde_json::json!({"delay_seconds": 60}))).await.unwrap().status(), 201);
+        assert_eq!(create(task("later", serde_json::json!({}))).await.unwrap().status(), 409);
+
+        let stats = orchestrator.get_system_stats().await;
+        assert_eq!((stats.scheduled_tasks, stats.total_scheduled_dispatched), (2, 0));
+        assert_eq!(get("later").await.unwrap().json::<Value>().await.unwrap()["status"], "scheduled");
+        let complete = client.post(format!("{}/task/later/complete", base)).send().await.unwrap();
+        assert_eq!(complete.status(), 409);
+
+        // Cancelled before it was due, and kept like any finished task
+        let cancel = client.post(format!("{}/task/later/cancel", base)).send().await.unwrap();
+        assert_eq!(cancel.status(), 200);
+        assert_eq!(get("later").await.unwrap().json::<Value>().await.unwrap()["status"], "cancelled");
+
+        // Conflicting or too distant schedules, and synchronous execution, are refused
+        let both = serde_json::json!({"run_at": run_at.to_rfc3339(), "delay_seconds": 5});
+        assert_eq!(create(task("both", both)).await.unwrap().status(), 400);
+        let distant = serde_json::json!({"delay_seconds": MAX_SCHEDULE_DELAY_SECS + 1});
+        assert_eq!(create(task("distant", distant)).await.unwrap().status(), 400);
+        let execute = client
+            .post(format!("{}/task/execute", base))
+            .json(&task("now", serde_json::json!({"delay_seconds": 5})))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(execute.status(), 400);
+
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        let task = orchestrator.get_task("soon").await.unwrap();
+        assert_ne!(task.status, TaskStatus::Scheduled);
+        assert_eq!(task.run_at, Some(run_at));
+        let stats = orchestrator.get_system_stats().await;
+        assert_eq!((stats.scheduled_tasks, stats.total_scheduled_dispatched), (0, 1));
+        orchestrator.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_task_dependencies() {
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        orchestrator.start().await.unwrap();
+        let base = format!("http://{}", orchestrator.bound_addr().unwrap());
+        let client = reqwest::Client::new();
+        let create = |id: &str, depends_on: &[&str]| {
+            let body = serde_json::json!({
+                "id": id,
+                "title": "Dependent",
+                "data": {"type": "calculation", "input": 5, "operation": "factorial"},
+                "depends_on": depends_on
+            });
+            client.post(format!("{}/task/create", base)).json(&body).send()
+        };
+        let status = |id: &'static str| {
+            let orchestrator = &orchestrator;
+            async move { orchestrator.get_task(id).await.unwrap().status }
+        };
+        let settle = || tokio::time::sleep(Duration::from_millis(200));
+
+        // Dependencies must be submitted first
+        let unknown = create("b", &["a"]).await.unwrap();
+        assert_eq!(unknown.status(), 400);
+        assert_eq!(unknown.json::<Value>().await.unwrap()["reason"], "unknown_dependency");
+        assert_eq!(create("self", &["self"]).await.unwrap().status(), 400);
+
+        // Calculated but not completed yet
+        assert_eq!(create("a", &[]).await.unwrap().status(), 201);
+        settle().await;
+        let response = create("b", &["a"]).await.unwrap();
+        assert_eq!(response.status(), 201);
+        assert_eq!(response.json::<Value>().await.unwrap()["status"], "blocked");
+        assert_eq!(create("c", &["b"]).await.unwrap().status(), 201);
+        assert_eq!(orchestrator.get_system_stats().await.blocked_tasks, 2);
+        orchestrator.complete_task("a").await.unwrap();
+        settle().await;
+        assert_eq!(status("b").await, TaskStatus::Processing);
+        assert_eq!(status("c").await, TaskStatus::Blocked);
+
+        // Cancelling "c"'s dependency fails "c"
+        orchestrator.cancel_task("b").await.unwrap();
+        settle().await;
+        let task = orchestrator.get_task("c").await.unwrap();
+        assert_eq!(task.status, TaskStatus::Failed);
+        assert_eq!(task.error_message.as_deref(), Some("Dependency b cancelled"));
+        assert_eq!(orchestrator.get_system_stats().await.blocked_tasks, 0);
+
+        // Deleting a dependency before it finishes fails its dependents
+        let later = serde_json::json!({
+            "id": "later",
+            "title": "Later",
+            "data": {"type": "calculation", "input": 5, "operation": "factorial"},
+            "delay_seconds": 60
         });
-    
-        Ok(handle)
+        assert_eq!(client.post(format!("{}/task/create", base)).json(&later).send().await.unwrap().status(), 201);
+        assert_eq!(create("d", &["later"]).await.unwrap().status(), 201);
+        orchestrator.delete_task("later", false).await.unwrap();
+        settle().await;
+        let task = orchestrator.get_task("d").await.unwrap();
+        assert_eq!(task.status, TaskStatus::Failed);
+        assert_eq!(task.error_message.as_deref(), Some("Dependency later removed"));
+        orchestrator.stop().await;
     }
 
-}
+    #[tokio::test]
+    async fn test_delete_task() {
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        orchestrator.start().await.unwrap();
+        let base = format!("http://{}", orchestrator.bound_addr().unwrap());
+        let client = reqwest::Client::new();
+        let create = |id: &str, extra: Value| {
+            let mut body = serde_json::json!({
+                "id": id,
+                "title": "Deleted",
+                "data": {"type": "calculation", "input": 5, "operation": "factorial"}
+            });
+            body.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+            client.post(format!("{}/task/create", base)).json(&body).send()
+        };
+        let delete = |path: &str| client.delete(format!("{}/task/{}", base, path)).send();
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::types::*;
+        assert_eq!(create("now", serde_json::json!({})).await.unwrap().status(), 201);
+        assert_eq!(create("later", serde_json::json!({"delay_seconds": 60})).await.unwrap().status(), 201);
+        tokio::time::sleep(Duration::from_millis(200)).await;
 
-    fn create_test_config() -> OrchestratorConfig {
-        OrchestratorConfig {
-            num_workers: 2,
-            threads_per_worker: 2,
-            orchestrator_port: 9999,
+        // Calculated, awaiting completion
+        let refused = delete("now").await.unwrap();
+        assert_eq!(refused.status(), 409);
+        assert_eq!(refused.json::<Value>().await.unwrap()["reason"], "not_deletable");
+        let forced = delete("now?force=true").await.unwrap();
+        assert_eq!(forced.status(), 200);
+        assert_eq!(forced.json::<Value>().await.unwrap()["status"], "processing");
+        assert!(matches!(orchestrator.get_task("now").await, Err(TaskError::TaskNotFound { .. })));
+
+        // Still waiting in the scheduler
+        assert_eq!(delete("later").await.unwrap().status(), 200);
+        assert_eq!(delete("later").await.unwrap().status(), 404);
+        assert_eq!(orchestrator.get_system_stats().await.total_tasks_deleted, 2);
+        orchestrator.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_update_task() {
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        // Paused, so tasks stay queued
+        for worker in &orchestrator.workers {
+            worker.pause();
         }
+        orchestrator.start().await.unwrap();
+        let base = format!("http://{}", orchestrator.bound_addr().unwrap());
+        let client = reqwest::Client::new();
+        let patch = |id: &str, body: Value| client.patch(format!("{}/task/{}", base, id)).json(&body).send();
+
+        let task = Task::with_id("queued".to_string(), "Queued".to_string(), TaskPriority::Low, TaskData::new(5, Operation::Factorial));
+        orchestrator.workers[0].add_task(task).await.unwrap();
+        let response = patch("queued", serde_json::json!({"priority": 3, "title": "Urgent"})).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let task: Value = response.json().await.unwrap();
+        assert_eq!((task["priority"].as_u64(), task["title"].as_str()), (Some(3), Some("Urgent")));
+        assert_eq!(orchestrator.audit_log().len(), 1);
+
+        assert_eq!(patch("queued", serde_json::json!({})).await.unwrap().status(), 400);
+        assert_eq!(patch("queued", serde_json::json!({"title": ""})).await.unwrap().status(), 400);
+        assert_eq!(patch("missing", serde_json::json!({"priority": 1})).await.unwrap().status(), 404);
+
+        orchestrator.cancel_task("queued").await.unwrap();
+        let refused = patch("queued", serde_json::json!({"priority": 1})).await.unwrap();
+        assert_eq!(refused.status(), 409);
+        assert_eq!(refused.json::<Value>().await.unwrap()["reason"], "not_queued");
+        orchestrator.stop().await;
     }
 
     #[tokio::test]
-    async fn test_orchestrator_creation() {
-        let config = create_test_config();
-        let orchestrator = TaskOrchestrator::new(config.clone());
-        
-        assert!(orchestrator.is_ok());
-        let orchestrator = orchestrator.unwrap();
-        assert_eq!(orchestrator.workers.len(), config.num_workers);
+    async fn test_pause_processing() {
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        orchestrator.start().await.unwrap();
+        let base = format!("http://{}", orchestrator.bound_addr().unwrap());
+        let client = reqwest::Client::new();
+        let post = |path: &str| client.post(format!("{}{}", base, path)).send();
+
+        let response = post("/admin/pause").await.unwrap();
+        assert_eq!(response.status(), 200);
+        let reply: Value = response.json().await.unwrap();
+        assert!(reply["workers"].as_array().unwrap().iter().all(|worker| worker["processing_paused"] == true));
+        assert!(orchestrator.get_system_stats().await.processing_paused);
+
+        // Submissions are still accepted, but stay queued
+        let body = serde_json::json!({
+            "id": "held",
+            "title": "Held",
+            "data": {"type": "calculation", "input": 5, "operation": "factorial"}
+        });
+        assert_eq!(client.post(format!("{}/task/create", base)).json(&body).send().await.unwrap().status(), 201);
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(orchestrator.get_task("held").await.unwrap().status, TaskStatus::Pending);
+
+        // One worker resumed makes the system no longer fully paused
+        assert_eq!(post("/workers/0/resume").await.unwrap().status(), 200);
+        assert!(!orchestrator.workers[0].is_processing_paused());
+        assert!(!orchestrator.get_system_stats().await.processing_paused);
+        assert_eq!(post("/workers/99/pause").await.unwrap().status(), 404);
+
+        assert_eq!(post("/admin/resume").await.unwrap().status(), 200);
+        let task = orchestrator.wait_for_task("held", Duration::from_secs(5)).await.unwrap();
+        assert_eq!(task.status, TaskStatus::Processing);
+        orchestrator.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_task() {
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        config.simulated_processing_ms = 300;
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        orchestrator.start().await.unwrap();
+        let base = format!("http://{}", orchestrator.bound_addr().unwrap());
+        let client = reqwest::Client::new();
+        let run_at = Utc::now() + chrono::Duration::milliseconds(100);
+        for (id, schedule) in [("now", Value::Null), ("soon", Value::String(run_at.to_rfc3339()))] {
+            let body = serde_json::json!({
+                "id": id,
+                "title": "Awaited",
+                "run_at": schedule,
+                "data": {"type": "calculation", "input": 5, "operation": "factorial"}
+            });
+            assert_eq!(client.post(format!("{}/task/create", base)).json(&body).send().await.unwrap().status(), 201);
+        }
+        let wait = |id: &str, timeout_ms: u64| client.get(format!("{}/task/{}/wait?timeout_ms={}", base, id, timeout_ms)).send();
+
+        // Not calculated yet: 202 with the task as it stands
+        let response = wait("now", 50).await.unwrap();
+        assert_eq!(response.status(), 202);
+        assert_eq!(response.json::<Value>().await.unwrap()["status"], "pending");
+
+        for id in ["now", "soon"] {
+            let response = wait(id, 5_000).await.unwrap();
+            assert_eq!(response.status(), 200);
+            let task: Value = response.json().await.unwrap();
+            assert_eq!(task["status"], "processing");
+            assert_eq!(task["result"], "120");
+        }
+        let task = orchestrator.wait_for_task("now", Duration::from_millis(10)).await.unwrap();
+        assert_eq!(task.status, TaskStatus::Processing);
+
+        assert_eq!(wait("now", 0).await.unwrap().status(), 400);
+        assert_eq!(wait("now", MAX_WAIT_TIMEOUT_MS + 1).await.unwrap().status(), 400);
+        assert_eq!(wait("missing", 10).await.unwrap().status(), 404);
+        assert!(matches!(
+            orchestrator.wait_for_task("missing", Duration::from_millis(10)).await,
+            Err(TaskError::TaskNotFound { .. })
+        ));
+        orchestrator.stop().await;
+    }
+
+    fn peer(name: &str, url: String) -> PeerConfig {
+        PeerConfig {
+            name: name.to_string(),
+            url,
+            api_key: None,
+        }
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_federation_forwards_unknown_operations() {
+        // A peer answering like the C++ server, which knows one more operation
+        let create = warp::path!("task" / "create")
+            .and(warp::body::json())
+            .map(|body: serde_json::Value| warp::reply::json(&serde_json::json!({"task_id": body["id"], "status": "pending"})));
+        let get = warp::path!("task" / String)
+            .map(|id: String| warp::reply::json(&serde_json::json!({"id": id, "status": "processing", "result": "2"})));
+        let (peer_addr, peer_server) = warp::serve(create.or(get)).bind_ephemeral(([127, 0, 0, 1], 0));
+        let peer_handle = tokio::spawn(peer_server);
+
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        config.federation.peers = vec![peer("cpp", format!("http://{}", peer_addr))];
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        orchestrator.start().await.unwrap();
+        let base = format!("http://{}", orchestrator.bound_addr().unwrap());
+        let client = reqwest::Client::new();
+        let body = serde_json::json!({
+            "id": "sqrt-4",
+            "title": "Unknown here",
+            "data": {"type": "calculation", "input": 4, "operation": "sqrt"}
+        });
+
+        let response = client.post(format!("{}/task/create", base)).json(&body).send().await.unwrap();
+        assert_eq!(response.status(), 202);
+        assert_eq!(response.headers()["location"], "/task/sqrt-4");
+        let remote: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(remote["peer"], "cpp");
+        assert_eq!(remote["reason"], "unknown_operation");
+        assert_eq!(remote["namespace"], "default");
+
+        // Status queries are answered by the peer
+        let response = client.get(format!("{}/task/sqrt-4?fields=id,result", base)).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["x-forwarded-to"], "cpp");
+        let task: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(task, serde_json::json!({"id": "sqrt-4", "result": "2"}));
+
+        let status: serde_json::Value = client.get(format!("{}/admin/federation", base)).send().await.unwrap().json().await.unwrap();
+        assert_eq!(status["peers"][0]["name"], "cpp");
+        assert_eq!(status["remote_tasks"][0]["id"], "sqrt-4");
+        assert_eq!(orchestrator.remote_tasks().len(), 1);
+
+        // A task another orchestrator forwarded is never passed on again
+        let response = client
+            .post(format!("{}/task/create", base))
+            .header(FORWARDED_HEADER, "task-processor")
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 400);
+        let error: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(error["reason"], "invalid_body");
+
+        orchestrator.stop().await;
+        peer_handle.abort();
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_federation_forwards_without_capacity() {
+        let mut peer_config = create_test_config();
+        peer_config.orchestrator_port = 0;
+        let peer_orchestrator = TaskOrchestrator::new(peer_config).unwrap();
+        peer_orchestrator.start().await.unwrap();
+
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        config.federation.peers = vec![
+            peer("gone", "http://127.0.0.1:9".to_string()),
+            peer("rust", format!("http://{}", peer_orchestrator.bound_addr().unwrap())),
+        ];
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        orchestrator.start().await.unwrap();
+        let base = format!("http://{}", orchestrator.bound_addr().unwrap());
+        let client = reqwest::Client::new();
+        let body = serde_json::json!({
+            "id": "overflow",
+            "title": "No room here",
+            "data": {"type": "calculation", "input": 5, "operation": "factorial"}
+        });
+
+        // Taken locally while a worker is active
+        orchestrator.pause_worker(0).unwrap();
+        let response = client.post(format!("{}/task/create", base)).json(&body).send().await.unwrap();
+        assert_eq!(response.status(), 201);
+
+        // Once every worker is paused it goes to the first reachable peer
+        orchestrator.pause_worker(1).unwrap();
+        let mut body = body;
+        body["id"] = "forwarded".into();
+        let response = client.post(format!("{}/task/create", base)).json(&body).send().await.unwrap();
+        assert_eq!(response.status(), 202);
+        let remote: serde_json::Value = response.json().await.unwrap();
+        assert_eq!((remote["peer"].as_str(), remote["reason"].as_str()), (Some("rust"), Some("no_capacity")));
+        assert!(peer_orchestrator.get_task("forwarded").await.is_ok());
+
+        // Proxied until the peer has calculated it, then completed through this server
+        let mut task = serde_json::Value::Null;
+        for _ in 0..50 {
+            task = client.get(format!("{}/task/forwarded", base)).send().await.unwrap().json().await.unwrap();
+            if task["status"] == "processing" {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(task["result"], "120");
+        let response = client.post(format!("{}/task/forwarded/complete", base)).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(peer_orchestrator.get_task("forwarded").await.unwrap().status, TaskStatus::Completed);
+        // Finished on the peer, so no longer tracked here
+        assert!(orchestrator.remote_tasks().is_empty());
+
+        // The peer doesn't pass it on either, so without room anywhere the local rejection stands
+        peer_orchestrator.pause_worker(0).unwrap();
+        peer_orchestrator.pause_worker(1).unwrap();
+        body["id"] = "stranded".into();
+        let response = client.post(format!("{}/task/execute", base)).json(&body).send().await.unwrap();
+        assert_eq!(response.status(), 503);
+        assert!(orchestrator.remote_tasks().is_empty());
+
+        orchestrator.stop().await;
+        peer_orchestrator.stop().await;
+    }
+
+    #[test]
+    fn test_invalid_federation_peers() {
+        let mut config = create_test_config();
+        config.federation.peers = vec![peer("cpp", "cpp:7000".to_string())];
+        assert!(matches!(validation_error(&config), ValidationError::InvalidPeerUrl(_)));
+        config.federation.peers = vec![peer("cpp", "http://a:7000".to_string()), peer("cpp", "http://b:7000".to_string())];
+        assert!(matches!(validation_error(&config), ValidationError::InvalidPeerName(_)));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_sink() {
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        let sink = Arc::new(crate::metrics::PrometheusSink::new());
+        let orchestrator = TaskOrchestrator::new(config).unwrap().with_metrics_sink(sink.clone());
+        orchestrator.start().await.unwrap();
+
+        let request = CreateTaskRequest {
+            id: "metrics".to_string(),
+            title: "Metrics".to_string(),
+            priority: TaskPriority::Medium,
+            data: TaskData::new(5, Operation::Factorial),
+            expected_result: None,
+            namespace: None,
+            callback_url: None,
+            run_at: None,
+            delay_seconds: None,
+            idempotency_key: None,
+            depends_on: Vec::new(),
+            labels: BTreeMap::new(),
+            timeout_ms: None,
+        };
+        orchestrator.create_task(request).await.unwrap();
+        let url = format!("http://{}/task/missing", orchestrator.bound_addr().unwrap());
+        assert_eq!(reqwest::get(url).await.unwrap().status(), 404);
+        orchestrator.stop().await;
+
+        let text = sink.render();
+        assert!(text.contains("tasks_submitted_total{operation=\"factorial\"} 1"), "{}", text);
+        assert!(text.contains("# TYPE worker_queue_depth gauge"), "{}", text);
+        assert!(
+            text.contains("http_requests_total{method=\"GET\",route=\"/task/{id}\",status=\"404\"} 1"),
+            "{}",
+            text
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_hooks() {
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        config.inject_failures = vec![InjectedFailure {
+            operation: Operation::Factorial,
+            input: 7,
+            message: None,
+            panic: false,
+        }];
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let hook = |name: &'static str| {
+            let sender = sender.clone();
+            move |task: Task| {
+                let sender = sender.clone();
+                async move {
+                    let _ = sender.send((name, task.id, task.status));
+                }
+            }
+        };
+        let orchestrator = TaskOrchestrator::new(config)
+            .unwrap()
+            .on_task_created(hook("created"))
+            .on_task_processed(hook("processed"))
+            .on_task_completed(hook("completed"))
+            .on_task_failed(hook("failed"));
+        orchestrator.start().await.unwrap();
+
+        let request = |id: &str, input: u64| CreateTaskRequest {
+            id: id.to_string(),
+            title: "Hooked".to_string(),
+            priority: TaskPriority::Medium,
+            data: TaskData::new(input, Operation::Factorial),
+            expected_result: None,
+            namespace: None,
+            callback_url: None,
+            run_at: None,
+            delay_seconds: None,
+            idempotency_key: None,
+            depends_on: Vec::new(),
+            labels: BTreeMap::new(),
+            timeout_ms: None,
+        };
+        async fn next<T>(receiver: &mut tokio::sync::mpsc::UnboundedReceiver<T>) -> T {
+            tokio::time::timeout(Duration::from_secs(2), receiver.recv()).await.unwrap().unwrap()
+        }
+
+        orchestrator.create_task(request("ok", 5)).await.unwrap();
+        assert_eq!(next(&mut receiver).await, ("created", "ok".to_string(), TaskStatus::Pending));
+        assert_eq!(next(&mut receiver).await, ("processed", "ok".to_string(), TaskStatus::Processing));
+        orchestrator.complete_task("ok").await.unwrap();
+        assert_eq!(next(&mut receiver).await, ("completed", "ok".to_string(), TaskStatus::Completed));
+
+        orchestrator.create_task(request("broken", 7)).await.unwrap();
+        assert_eq!(next(&mut receiver).await.0, "created");
+        assert_eq!(next(&mut receiver).await, ("failed", "broken".to_string(), TaskStatus::Failed));
+        orchestrator.stop().await;
+    }
+
+    #[test]
+    fn test_invalid_statsd_address() {
+        let mut config = create_test_config();
+        config.statsd = Some(StatsdConfig {
+            address: "no-port".to_string(),
+            prefix: String::new(),
+        });
+        let error = TaskOrchestrator::new(config).err().expect("statsd address is rejected").to_string();
+        assert!(error.contains("Cannot use statsd address no-port"), "{}", error);
+    }
+
+    #[test]
+    fn test_execute_max_wait() {
+        let parse = |body: serde_json::Value| serde_json::from_value::<ExecuteTaskRequest>(body).unwrap();
+        let body = |max_wait_ms: serde_json::Value| {
+            serde_json::json!({
+                "title": "t",
+                "data": {"type": "calculation", "input": 5, "operation": "factorial"},
+                "max_wait_ms": max_wait_ms,
+            })
+        };
+
+        assert_eq!(parse(body(serde_json::Value::Null)).max_wait_ms().unwrap(), DEFAULT_EXECUTE_WAIT_MS);
+        assert_eq!(parse(body(250.into())).max_wait_ms().unwrap(), 250);
+        assert_eq!(parse(body(MAX_EXECUTE_WAIT_MS.into())).max_wait_ms().unwrap(), MAX_EXECUTE_WAIT_MS);
+        assert!(parse(body(0.into())).max_wait_ms().is_err());
+        assert!(parse(body((MAX_EXECUTE_WAIT_MS + 1).into())).max_wait_ms().is_err());
+        assert_eq!(parse(body(250.into())).task.data.input, 5);
     }
 
     #[tokio::test]
@@ -389,9 +4044,9 @@ mod tests {
         let orchestrator = TaskOrchestrator::new(config).unwrap();
         
         // Test round-robin selection
-        assert_eq!(orchestrator.select_worker(), 0);
-        assert_eq!(orchestrator.select_worker(), 1);
-        assert_eq!(orchestrator.select_worker(), 0); // Wraps around
+        assert_eq!(orchestrator.select_worker().await.unwrap().id, 0);
+        assert_eq!(orchestrator.select_worker().await.unwrap().id, 1);
+        assert_eq!(orchestrator.select_worker().await.unwrap().id, 0); // Wraps around
     }
 
     #[tokio::test]
@@ -401,9 +4056,18 @@ mod tests {
             title: "Test Task".to_string(),
             priority: TaskPriority::High,
             data: TaskData::new(10, Operation::Factorial),
+            expected_result: None,
+            namespace: None,
+            callback_url: None,
+            run_at: None,
+            delay_seconds: None,
+            idempotency_key: None,
+            depends_on: Vec::new(),
+            labels: BTreeMap::new(),
+            timeout_ms: None,
         };
 
-        let task = request.into_task();
+        let task = request.into_task(&Calculator::default());
         assert!(task.is_ok());
         
         let task = task.unwrap();
@@ -412,6 +4076,356 @@ mod tests {
         assert_eq!(task.status, TaskStatus::Pending);
     }
 
+    #[test]
+    fn test_route_pattern() {
+        assert_eq!(route_pattern("/task/create"), ("/task/create".to_string(), None));
+        assert_eq!(route_pattern("/task/abc"), ("/task/{id}".to_string(), Some("abc")));
+        assert_eq!(
+            route_pattern("/task/abc/complete"),
+            ("/task/{id}/complete".to_string(), Some("abc"))
+        );
+        assert_eq!(route_pattern("/stats"), ("/stats".to_string(), None));
+        assert_eq!(route_pattern("/task/execute"), ("/task/execute".to_string(), None));
+    }
+
+    #[test]
+    fn test_read_only_reply() {
+        let reply = read_only_reply("http://primary:7000/", "/task/create");
+        assert_eq!(reply.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(reply.headers()["location"], "http://primary:7000/task/create");
+        assert_eq!(reply.headers()["allow"], "GET");
+    }
+
+    #[tokio::test]
+    async fn test_worker_speed_factors() {
+        let mut config = create_test_config();
+        config.worker_speed_factors = vec![1.0, 4.0];
+        assert_eq!(config.speed_factor(1), 4.0);
+        let orchestrator = TaskOrchestrator::new(config.clone()).unwrap();
+        assert_eq!(orchestrator.get_system_stats().await.workers[1].speed_factor, 4.0);
+
+        config.worker_speed_factors = vec![1.0, 0.0];
+        assert!(TaskOrchestrator::new(config.clone()).is_err());
+
+        config.worker_speed_factors = vec![1.0, 1.0, 1.0];
+        assert!(TaskOrchestrator::new(config).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_primary_url() {
+        let mut config = create_test_config();
+        config.primary_url = Some("primary:7000".to_string());
+        assert!(TaskOrchestrator::new(config.clone()).is_err());
+
+        config.primary_url = Some("http://primary:7000".to_string());
+        assert!(config.is_read_only());
+        // Nothing to serve without the primary's task store
+        assert!(matches!(validation_error(&config), ValidationError::ReplicaWithoutTaskStore));
+        let dir = std::env::temp_dir().join(format!("replica-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        config.task_store = Some(TaskStoreConfig { path: dir.join("tasks.db") });
+        assert!(TaskOrchestrator::new(config).is_ok());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// The single error `validate` reports
+    fn validation_error(config: &OrchestratorConfig) -> ValidationError {
+        let mut errors = config.validate().unwrap_err().errors;
+        assert_eq!(errors.len(), 1);
+        errors.remove(0).error
+    }
+
+    #[tokio::test]
+    async fn test_workers_snapshot() {
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        config.worker_labels = vec![BTreeMap::from([("zone".to_string(), "eu-1".to_string())])];
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+
+        let snapshots = orchestrator.workers_snapshot().await;
+        assert_eq!(snapshots.len(), 2);
+        assert!(snapshots.iter().all(|worker| worker.health == WorkerHealth::Stopped));
+
+        orchestrator.start().await.unwrap();
+        orchestrator.pause_worker(1).unwrap();
+        let snapshots = orchestrator.workers_snapshot().await;
+        assert_eq!(snapshots[0].id, 0);
+        assert_eq!(snapshots[0].health, WorkerHealth::Healthy);
+        assert_eq!(snapshots[0].labels["zone"], "eu-1");
+        assert_eq!(snapshots[0].capabilities, Operation::ALL.to_vec());
+        assert_eq!(snapshots[0].threads, 2);
+        assert_eq!(snapshots[0].queue_depth, 0);
+        assert_eq!(snapshots[1].health, WorkerHealth::Paused);
+        assert!(snapshots[1].labels.is_empty());
+        orchestrator.stop().await;
+    }
+
+    #[test]
+    fn test_worker_labels_validation() {
+        let mut config = create_test_config();
+        config.worker_labels = vec![BTreeMap::new(); 3];
+        assert!(matches!(
+            validation_error(&config),
+            ValidationError::WorkerLabelCount { given: 3, workers: 2 }
+        ));
+
+        config.worker_labels = vec![BTreeMap::new(), BTreeMap::from([(" ".to_string(), "x".to_string())])];
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors.errors[0].field, "worker_labels[1]");
+    }
+
+    #[test]
+    fn test_operation_limits_validation() {
+        let mut config = create_test_config();
+        config.operation_limits.max_factorial_input = 34;
+        config.operation_limits.max_fibonacci_input = 50;
+        config.operation_limits.max_prime_check_input = u64::MAX;
+        assert!(config.validate().is_ok());
+        assert_eq!(config.operation_limits.max_input(&Operation::Fibonacci), Some(50));
+        assert_eq!(config.operation_limits.max_input(&Operation::Gcd), None);
+
+        config.operation_limits.max_factorial_input = 35;
+        assert!(matches!(
+            validation_error(&config),
+            ValidationError::OperationLimitTooLarge { operation: Operation::Factorial, max_input: 35, ceiling: 34 }
+        ));
+        config.operation_limits.max_factorial_input = 20;
+        config.operation_limits.max_fibonacci_input = 94;
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors.errors[0].field, "operation_limits");
+    }
+
+    #[test]
+    fn test_worker_weights_validation() {
+        let mut config = create_test_config();
+        config.load_balancing = LoadBalancingStrategy::WeightedRoundRobin;
+        config.worker_weights = vec![3, 1];
+        assert!(config.validate().is_ok());
+
+        config.worker_weights = vec![1, 1, 1];
+        assert!(matches!(
+            validation_error(&config),
+            ValidationError::WorkerWeightCount { given: 3, workers: 2 }
+        ));
+
+        config.worker_weights = vec![1, 0];
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors.errors[0].field, "worker_weights[1]");
+        assert!(matches!(errors.errors[0].error, ValidationError::InvalidWorkerWeight { worker: 1 }));
+    }
+
+    #[tokio::test]
+    async fn test_least_loaded_selection() {
+        let mut config = create_test_config();
+        config.load_balancing = LoadBalancingStrategy::LeastLoaded;
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        // Not started, so the task stays queued on the worker it went to
+        let task = Task::new("Queued".to_string(), TaskPriority::Low, TaskData::new(5, Operation::Factorial));
+        orchestrator.workers[0].add_task(task).await.unwrap();
+        assert_eq!(orchestrator.select_worker().await.unwrap().id, 1);
+        assert_eq!(orchestrator.select_worker().await.unwrap().id, 1);
+    }
+
+    #[test]
+    fn test_queue_polling_validation() {
+        let mut config = create_test_config();
+        config.queue_polling.fallback_interval_ms = 0;
+        assert!(matches!(validation_error(&config), ValidationError::InvalidTimeLimit(0)));
+
+        // The interval only matters when falling back
+        config.queue_polling.mode = QueuePollingMode::Notify;
+        assert!(config.validate().is_ok());
+
+        config.queue_polling.batch_size = 8;
+        config.worker_batch_sizes = vec![64];
+        assert!(config.validate().is_ok());
+        assert_eq!(config.queue_polling(0).batch_size, 64);
+        assert_eq!(config.queue_polling(1).batch_size, 8);
+
+        config.worker_batch_sizes = vec![1, 2, 3];
+        assert!(matches!(
+            validation_error(&config),
+            ValidationError::BatchSizeCount { given: 3, workers: 2 }
+        ));
+    }
+
+    #[test]
+    fn test_callback_validation() {
+        let mut config = create_test_config();
+        config.callbacks.max_attempts = 0;
+        assert!(matches!(validation_error(&config), ValidationError::InvalidMaxAttempts(0)));
+        config.callbacks.max_attempts = 1;
+        config.callbacks.max_backoff_ms = 0;
+        assert!(matches!(validation_error(&config), ValidationError::InvalidTimeLimit(0)));
+
+        let mut task = Task::new("Callback".to_string(), TaskPriority::Low, TaskData::new(5, Operation::Factorial));
+        task.callback_url = Some("ftp://client/hook".to_string());
+        assert!(matches!(task.validate(&Calculator::default()), Err(ValidationError::InvalidCallbackUrl(_))));
+        task.callback_url = Some("https://client/hook".to_string());
+        assert!(task.validate(&Calculator::default()).is_ok());
+    }
+
+    #[test]
+    fn test_config_errors_aggregated() {
+        let mut config = create_test_config();
+        config.num_workers = 0;
+        config.orchestrator_port = 80;
+        config.worker_speed_factors = vec![-1.0];
+        config.primary_url = Some("primary:7000".to_string());
+
+        let errors = config.validate().unwrap_err();
+        let fields: Vec<&str> = errors.errors.iter().map(|error| error.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec!["num_workers", "orchestrator_port", "worker_speed_factors", "worker_speed_factors[0]", "primary_url"]
+        );
+
+        let errors = errors.with_sources(|key| (key == "num_workers").then(|| "--workers".to_string()));
+        let message = errors.to_string();
+        assert!(message.starts_with("5 problems\n  - num_workers (from --workers): Invalid worker count: 0"));
+        assert!(message.contains("\n  - worker_speed_factors[0]: Invalid speed factor -1"));
+    }
+
+    #[test]
+    fn test_api_key_validation() {
+        let mut config = create_test_config();
+        config.api_keys = vec![ApiKeyConfig {
+            name: "team-a".to_string(),
+            key: "key-a".to_string(),
+            namespaces: vec!["team-a".to_string(), "*".to_string()],
+        }];
+        assert!(config.validate().is_ok());
+
+        config.api_keys[0].namespaces = vec!["team a".to_string()];
+        assert!(matches!(validation_error(&config), ValidationError::InvalidNamespace(_)));
+
+        config.api_keys[0].namespaces.clear();
+        assert!(matches!(validation_error(&config), ValidationError::InvalidApiKey(_)));
+    }
+
+    #[test]
+    fn test_rate_limit_validation() {
+        let mut config = create_test_config();
+        config.rate_limit.requests_per_second = 10.0;
+        config.rate_limit.routes = vec![RateLimitRule {
+            route: "/task/create".to_string(),
+            requests_per_second: 0.0,
+            burst: 1,
+        }];
+        assert!(config.validate().is_ok());
+
+        config.rate_limit.burst = 0;
+        assert!(matches!(validation_error(&config), ValidationError::InvalidRateLimit { burst: 0, .. }));
+        config.rate_limit.burst = 20;
+
+        config.rate_limit.routes[0].route = "task/create".to_string();
+        assert!(matches!(validation_error(&config), ValidationError::InvalidRateLimitRoute(_)));
+        config.rate_limit.routes[0].route = "/task/create".to_string();
+
+        config.rate_limit.routes[0].requests_per_second = -1.0;
+        assert!(matches!(validation_error(&config), ValidationError::InvalidRateLimit { .. }));
+    }
+
+    #[test]
+    fn test_listener_validation() {
+        let listener = |address: &str| ListenerConfig {
+            address: address.parse().unwrap(),
+            tls: None,
+        };
+        let mut config = create_test_config();
+        config.listeners = vec![listener("0.0.0.0:7443"), listener("[::1]:7001"), listener("127.0.0.1:0")];
+        assert!(config.validate().is_ok());
+
+        config.listeners = vec![listener("0.0.0.0:9999")];
+        assert!(matches!(validation_error(&config), ValidationError::ListenerConflict(_)));
+
+        config.listeners = vec![listener("0.0.0.0:7443"), listener("[::1]:7443")];
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors.errors[0].field, "listeners[1]");
+
+        config.listeners = vec![listener("0.0.0.0:443")];
+        assert!(matches!(validation_error(&config), ValidationError::InvalidPort(443)));
+
+        // Dual-stack main listener overlaps any address on its port
+        config.bind_address = "::".parse().unwrap();
+        config.listeners = vec![listener("127.0.0.1:9999")];
+        assert!(matches!(validation_error(&config), ValidationError::ListenerConflict(_)));
+
+        config.listeners.clear();
+        config.bind_address = "ff02::1".parse().unwrap();
+        assert!(matches!(validation_error(&config), ValidationError::InvalidBindAddress(_)));
+    }
+
+    #[test]
+    fn test_tls_validation() {
+        let mut config = create_test_config();
+        config.tls_cert_path = Some("certs/server.crt".into());
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors.errors[0].field, "tls_key_path");
+        assert!(matches!(errors.errors[0].error, ValidationError::IncompleteTls { missing: "tls_key_path" }));
+        assert!(config.tls().is_none());
+
+        config.tls_cert_path = None;
+        config.tls_key_path = Some("certs/server.key".into());
+        assert!(matches!(validation_error(&config), ValidationError::IncompleteTls { missing: "tls_cert_path" }));
+
+        config.tls_cert_path = Some("certs/server.crt".into());
+        assert!(config.validate().is_ok());
+        assert_eq!(config.url_scheme(), "https");
+    }
+
+    #[tokio::test]
+    async fn test_ipv6_bind() {
+        if std::net::TcpListener::bind("[::1]:0").is_err() {
+            eprintln!("IPv6 unavailable, skipping");
+            return;
+        }
+        let mut config = create_test_config();
+        config.orchestrator_port = 0;
+        config.bind_address = "::1".parse().unwrap();
+        let orchestrator = TaskOrchestrator::new(config.clone()).unwrap();
+        orchestrator.start().await.unwrap();
+        let addr = orchestrator.bound_addr().unwrap();
+        assert!(addr.is_ipv6() && addr.ip().is_loopback());
+        assert!(std::net::TcpStream::connect(addr).is_ok());
+        orchestrator.stop().await;
+
+        // "::" takes IPv4 clients too (dual-stack)
+        config.bind_address = "::".parse().unwrap();
+        let orchestrator = TaskOrchestrator::new(config).unwrap();
+        orchestrator.start().await.unwrap();
+        let port = orchestrator.bound_addr().unwrap().port();
+        assert!(std::net::TcpStream::connect(("::1", port)).is_ok());
+        assert!(std::net::TcpStream::connect(("127.0.0.1", port)).is_ok());
+        orchestrator.stop().await;
+    }
+
+    #[test]
+    fn test_worker_port_range_validation() {
+        let mut config = create_test_config();
+        config.base_port = Some(9000);
+        assert_eq!(config.worker_port_range(), Some((9000, 9001)));
+        assert!(config.validate().is_ok());
+
+        config.orchestrator_port = 9001;
+        assert!(matches!(
+            validation_error(&config),
+            ValidationError::PortConflict { orchestrator_port: 9001, worker_port_range: (9000, 9001) }
+        ));
+
+        config.base_port = Some(65535);
+        assert!(matches!(validation_error(&config), ValidationError::InvalidPort(65535)));
+        config.base_port = Some(80);
+        assert!(matches!(validation_error(&config), ValidationError::InvalidPort(80)));
+    }
+
+    #[test]
+    fn test_classify_rejection() {
+        let (status, reason, _) = classify_rejection(&warp::reject::not_found());
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(reason, "not_found");
+    }
+
     #[tokio::test]
     async fn test_system_stats_calculation() {
         let config = create_test_config();
@@ -0,0 +1,372 @@
+//! Rendering of CLI query results (`--output table|json|yaml`)
+
+use crate::analytics::AnalyticsReport;
+use crate::latency::LatencySummary;
+use crate::table::Table;
+use crate::types::*;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+    Yaml,
+}
+
+/// Parse `--output`
+pub fn parse_format(value: &str) -> Result<OutputFormat, String> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "table" => Ok(OutputFormat::Table),
+        "json" => Ok(OutputFormat::Json),
+        "yaml" | "yml" => Ok(OutputFormat::Yaml),
+        other => Err(format!("invalid output format '{}', expected table, json or yaml", other)),
+    }
+}
+
+/// Render `value` as JSON or YAML, or with `table` for the table format
+pub fn render<T: Serialize>(value: &T, format: OutputFormat, table: impl FnOnce() -> String) -> String {
+    match format {
+        OutputFormat::Table => table(),
+        OutputFormat::Json => {
+            let mut json = serde_json::to_string_pretty(value).unwrap_or_default();
+            json.push('\n');
+            json
+        }
+        OutputFormat::Yaml => to_yaml(&serde_json::to_value(value).unwrap_or(Value::Null)),
+    }
+}
+
+/// Block-style YAML for a JSON value, keys in alphabetical order
+pub fn to_yaml(value: &Value) -> String {
+    let mut out = String::new();
+    match value {
+        Value::Object(map) if !map.is_empty() => write_map(&mut out, map, 0),
+        Value::Array(items) if !items.is_empty() => write_list(&mut out, items, 0),
+        scalar => {
+            out.push_str(&yaml_scalar(scalar));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn write_map(out: &mut String, map: &serde_json::Map<String, Value>, indent: usize) {
+    for (key, value) in map {
+        out.push_str(&" ".repeat(indent));
+        out.push_str(&yaml_string(key));
+        out.push(':');
+        write_nested(out, value, indent);
+    }
+}
+
+fn write_list(out: &mut String, items: &[Value], indent: usize) {
+    for item in items {
+        out.push_str(&" ".repeat(indent));
+        out.push('-');
+        match item {
+            // The first key goes on the dash line, the rest line up under it
+            Value::Object(map) if !map.is_empty() => {
+                let mut nested = String::new();
+                write_map(&mut nested, map, indent + 2);
+                out.push(' ');
+                out.push_str(&nested[indent + 2..]);
+            }
+            _ => write_nested(out, item, indent),
+        }
+    }
+}
+
+/// Value after a `key:` or `-`: scalars stay on the line, collections go below
+fn write_nested(out: &mut String, value: &Value, indent: usize) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            out.push('\n');
+            write_map(out, map, indent + 2);
+        }
+        Value::Array(items) if !items.is_empty() => {
+            out.push('\n');
+            write_list(out, items, indent + 2);
+        }
+        scalar => {
+            out.push(' ');
+            out.push_str(&yaml_scalar(scalar));
+            out.push('\n');
+        }
+    }
+}
+
+fn yaml_scalar(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::String(s) => yaml_string(s),
+        Value::Object(_) => "{}".to_string(),
+        Value::Array(_) => "[]".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Plain when unambiguous, otherwise double-quoted (JSON escapes are valid YAML)
+fn yaml_string(s: &str) -> String {
+    let reserved = matches!(
+        s.to_ascii_lowercase().as_str(),
+        "null" | "~" | "true" | "false" | "yes" | "no" | "on" | "off"
+    );
+    let plain = !s.is_empty()
+        && !reserved
+        && s.parse::<f64>().is_err()
+        && s.trim() == s
+        && !s.starts_with(|c: char| "-?:,[]{}#&*!|>'\"%@`".contains(c))
+        && !s.contains(": ")
+        && !s.contains(" #")
+        && !s.ends_with(':')
+        && !s.chars().any(char::is_control);
+    if plain {
+        s.to_string()
+    } else {
+        Value::String(s.to_string()).to_string()
+    }
+}
+
+fn timestamp(time: &chrono::DateTime<chrono::Utc>) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// One task as FIELD/VALUE rows
+pub fn task_table(task: &Task) -> Table {
+    let mut table = Table::new(["FIELD", "VALUE"]);
+    table
+        .add_row(["id", task.id.as_str()])
+        .add_row(["title", task.title.as_str()])
+        .add_row(["namespace", task.namespace.as_str()])
+        .add_row(["status".to_string(), task.status.to_string()])
+        .add_row(["priority".to_string(), task.priority.to_string()])
+        .add_row(["operation".to_string(), task.data.operation.to_string()])
+        .add_row(["input".to_string(), input_cell(&task.data)])
+        .add_row(["created_at".to_string(), timestamp(&task.created_at)]);
+    if let Some(position) = task.queue_position {
+        table.add_row(["queue_position".to_string(), position.to_string()]);
+    }
+    if let Some(result) = &task.result {
+        table.add_row(["result", result.as_str()]);
+    }
+    if let Some(error) = &task.error_message {
+        table.add_row(["error", error.as_str()]);
+    }
+    if let Some(worker_id) = task.worker_id {
+        table.add_row(["worker_id".to_string(), worker_id.to_string()]);
+    }
+    if let Some(duration) = task.processing_duration_ms {
+        table.add_row(["processing_duration_ms".to_string(), duration.to_string()]);
+    }
+    if let Some(completed_at) = &task.completed_at {
+        table.add_row(["completed_at".to_string(), timestamp(completed_at)]);
+    }
+    table
+}
+
+/// `input`, or the list of `inputs` when the task has several
+fn input_cell(data: &TaskData) -> String {
+    if data.inputs.is_empty() {
+        data.input.to_string()
+    } else {
+        serde_json::to_string(&data.inputs).unwrap_or_default()
+    }
+}
+
+/// Tasks one per row
+pub fn task_list_table(tasks: &[Task]) -> Table {
+    let mut table = Table::new(["ID", "NAMESPACE", "STATUS", "PRIORITY", "OPERATION", "INPUT", "RESULT", "CREATED"]);
+    for task in tasks {
+        let result = task.result.as_deref().or(task.error_message.as_deref()).unwrap_or("-");
+        table.add_row([
+            task.id.clone(),
+            task.namespace.clone(),
+            task.status.to_string(),
+            task.priority.to_string(),
+            task.data.operation.to_string(),
+            input_cell(&task.data),
+            result.to_string(),
+            timestamp(&task.created_at),
+        ]);
+    }
+    table
+}
+
+/// Worker states, one row per worker
+pub fn workers_table(workers: &[WorkerStats]) -> Table {
+    let mut table = Table::new(["ID", "STATE", "QUEUED", "PROCESSED", "COMPLETED", "FAILED", "P99 MS"]);
+    for worker in workers {
+        let state = match (worker.is_healthy, worker.paused, worker.processing_paused) {
+            (false, _, _) => "down",
+            (true, true, _) => "paused",
+            (true, false, true) => "held",
+            (true, false, false) => "active",
+        };
+        table.add_row([
+            worker.id.to_string(),
+            state.to_string(),
+            worker.current_load.to_string(),
+            worker.tasks_processed.to_string(),
+            worker.tasks_completed.to_string(),
+            worker.tasks_failed.to_string(),
+            format!("{:.1}", worker.latency.p99_ms),
+        ]);
+    }
+    table
+}
+
+/// System totals followed by the workers table
+pub fn stats_tables(stats: &SystemStats) -> String {
+    let mut totals = Table::new(["METRIC", "VALUE"]);
+    totals
+        .add_row(["workers".to_string(), stats.total_workers.to_string()])
+        .add_row(["uptime_seconds".to_string(), stats.uptime_seconds.to_string()])
+        .add_row(["tasks_processed".to_string(), stats.total_tasks_processed.to_string()])
+        .add_row(["tasks_completed".to_string(), stats.total_tasks_completed.to_string()])
+        .add_row(["tasks_failed".to_string(), stats.total_tasks_failed.to_string()])
+        .add_row(["tasks_cancelled".to_string(), stats.total_tasks_cancelled.to_string()])
+        .add_row(["tasks_deleted".to_string(), stats.total_tasks_deleted.to_string()])
+        .add_row(["result_mismatches".to_string(), stats.total_result_mismatches.to_string()])
+        .add_row(["storage_evictions".to_string(), stats.total_storage_evictions.to_string()])
+        .add_row(["tasks_expired".to_string(), stats.total_tasks_expired.to_string()])
+        .add_row(["wedged_threads".to_string(), stats.total_wedged_threads.to_string()])
+        .add_row(["threads_respawned".to_string(), stats.total_threads_respawned.to_string()])
+        .add_row(["worker_restarts".to_string(), stats.worker_restarts.to_string()])
+        .add_row(["scheduled_tasks".to_string(), stats.scheduled_tasks.to_string()])
+        .add_row(["scheduled_dispatched".to_string(), stats.total_scheduled_dispatched.to_string()])
+        .add_row(["blocked_tasks".to_string(), stats.blocked_tasks.to_string()])
+        .add_row(["latency_p50_ms".to_string(), format!("{:.1}", stats.latency.p50_ms)])
+        .add_row(["latency_p99_ms".to_string(), format!("{:.1}", stats.latency.p99_ms)])
+        .add_row(["cumulative_uptime_seconds".to_string(), stats.cumulative.uptime_seconds.to_string()])
+        .add_row(["cumulative_tasks_processed".to_string(), stats.cumulative.tasks_processed.to_string()])
+        .add_row(["cumulative_tasks_completed".to_string(), stats.cumulative.tasks_completed.to_string()])
+        .add_row(["cumulative_tasks_failed".to_string(), stats.cumulative.tasks_failed.to_string()]);
+    if stats.processing_by_operation.is_empty() {
+        return format!("{}\n{}", totals, workers_table(&stats.workers));
+    }
+    format!("{}\n{}\n{}", totals, operations_table(&stats.processing_by_operation), workers_table(&stats.workers))
+}
+
+/// Calculation-time percentiles, one row per operation
+pub fn operations_table(operations: &BTreeMap<String, LatencySummary>) -> Table {
+    let mut table = Table::new(["OPERATION", "TASKS", "MIN MS", "P50 MS", "P95 MS", "P99 MS", "MAX MS"]);
+    for (operation, latency) in operations {
+        table.add_row([
+            operation.clone(),
+            latency.count.to_string(),
+            format!("{:.3}", latency.min_ms),
+            format!("{:.3}", latency.p50_ms),
+            format!("{:.3}", latency.p95_ms),
+            format!("{:.3}", latency.p99_ms),
+            format!("{:.3}", latency.max_ms),
+        ]);
+    }
+    table
+}
+
+/// `analyze` reports: slowest operations, then failure rates by input range
+pub fn analytics_tables(report: &AnalyticsReport) -> String {
+    let mut slowest = Table::new(["OPERATION", "TASKS", "MEAN MS", "MAX MS", "MEAN QUEUE MS"]);
+    for timing in &report.slowest_operations {
+        slowest.add_row([
+            timing.operation.clone(),
+            timing.tasks.to_string(),
+            format!("{:.1}", timing.mean_processing_ms),
+            format!("{:.1}", timing.max_processing_ms),
+            format!("{:.1}", timing.mean_queue_ms),
+        ]);
+    }
+    let mut failures = Table::new(["OPERATION", "INPUT RANGE", "TASKS", "FAILED", "FAILURE RATE"]);
+    for rate in &report.failure_rates {
+        failures.add_row([
+            rate.operation.clone(),
+            rate.input_range.clone(),
+            rate.tasks.to_string(),
+            rate.failed.to_string(),
+            format!("{:.1}%", rate.failure_rate * 100.0),
+        ]);
+    }
+    format!("{}\n{}", slowest, failures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(parse_format("YAML"), Ok(OutputFormat::Yaml));
+        assert_eq!(parse_format("json"), Ok(OutputFormat::Json));
+        assert!(parse_format("csv").is_err());
+    }
+
+    #[test]
+    fn test_yaml() {
+        let value = json!({
+            "id": "task-1",
+            "status": "processing",
+            "result": "3628800",
+            "matched": true,
+            "note": "a: b",
+            "stages": [{"stage": 0, "operation": "factorial"}, {"stage": 1, "operation": "fibonacci"}],
+            "tags": [],
+            "data": {"input": 10, "pipeline": ["fibonacci"]}
+        });
+        // serde_json maps are sorted, so keys come out alphabetically
+        let expected = "\
+data:
+  input: 10
+  pipeline:
+    - fibonacci
+id: task-1
+matched: true
+note: \"a: b\"
+result: \"3628800\"
+stages:
+  - operation: factorial
+    stage: 0
+  - operation: fibonacci
+    stage: 1
+status: processing
+tags: []
+";
+        assert_eq!(to_yaml(&value), expected);
+    }
+
+    #[test]
+    fn test_task_views() {
+        let mut task = Task::with_id(
+            "task-1".to_string(),
+            "Factorial".to_string(),
+            TaskPriority::High,
+            TaskData::new(10, Operation::Factorial),
+        );
+        task.set_processing("3628800".to_string());
+
+        let detail = task_table(&task).to_string();
+        assert!(detail.contains("status      processing"));
+        assert!(detail.contains("result      3628800"));
+
+        let list = task_list_table(&[task.clone()]).to_string();
+        assert!(list.lines().nth(1).unwrap().starts_with("task-1  default    processing"));
+
+        let json = render(&task, OutputFormat::Json, || unreachable!());
+        assert_eq!(serde_json::from_str::<Value>(&json).unwrap()["id"], "task-1");
+        assert!(render(&task, OutputFormat::Yaml, || unreachable!()).contains("\nid: task-1\n"));
+    }
+
+    #[tokio::test]
+    async fn test_workers_table() {
+        let worker = crate::worker::Worker::new(0, 1);
+        worker.pause();
+        let table = workers_table(&[worker.get_stats().await]).to_string();
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[0].starts_with("ID  STATE"));
+        // Not started, so reported as down even though paused
+        assert!(lines[1].contains("down"));
+    }
+}
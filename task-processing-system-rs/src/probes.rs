@@ -0,0 +1,83 @@
+//! Liveness and readiness probes
+//!
+//! `GET /health` always answers `healthy`. For orchestrators such as
+//! Kubernetes, `GET /healthz` answers 200 whenever the process can serve a
+//! request at all, and `GET /readyz` answers 200 only while the instance can
+//! take work: it is accepting tasks (not stopping or draining), every worker
+//! is running and no worker's queue is full. Otherwise it answers 503 with the
+//! reasons.
+
+use crate::types::ReadinessResponse;
+use crate::worker::Worker;
+use chrono::Utc;
+use std::sync::Arc;
+
+/// Check whether the instance is ready for traffic
+pub async fn readiness(workers: &[Arc<Worker>], accepting_tasks: bool) -> ReadinessResponse {
+    let workers_running = workers
+        .iter()
+        .filter(|worker| worker.is_running() && !worker.is_crashed())
+        .count();
+    let mut full_queues = 0;
+    for worker in workers {
+        if worker.is_queue_full().await {
+            full_queues += 1;
+        }
+    }
+
+    let mut reasons = Vec::new();
+    if !accepting_tasks {
+        reasons.push("not accepting tasks".to_string());
+    }
+    if workers_running < workers.len() {
+        reasons.push(format!("{} of {} workers running", workers_running, workers.len()));
+    }
+    if full_queues > 0 {
+        reasons.push(format!("{} worker queues full", full_queues));
+    }
+
+    ReadinessResponse {
+        status: if reasons.is_empty() { "ready" } else { "not_ready" }.to_string(),
+        accepting_tasks,
+        workers_running,
+        workers_total: workers.len(),
+        full_queues,
+        reasons,
+        timestamp: Utc::now(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Operation, Task, TaskData, TaskPriority};
+
+    #[tokio::test]
+    async fn test_readiness() {
+        let workers = vec![Arc::new(Worker::new(0, 1).with_max_queue_size(1)), Arc::new(Worker::new(1, 1))];
+        let not_started = readiness(&workers, true).await;
+        assert!(!not_started.is_ready());
+        assert_eq!(not_started.reasons, vec!["0 of 2 workers running"]);
+
+        for worker in &workers {
+            let runner = Arc::clone(worker);
+            tokio::spawn(async move { runner.start().await });
+            worker.wait_running().await;
+        }
+        workers[0].pause_processing();
+        let ready = readiness(&workers, true).await;
+        assert!(ready.is_ready());
+        assert_eq!((ready.status.as_str(), ready.workers_running), ("ready", 2));
+
+        let task = Task::new("Queued".to_string(), TaskPriority::Low, TaskData::new(5, Operation::Factorial));
+        workers[0].add_task(task).await.unwrap();
+        let full = readiness(&workers, false).await;
+        assert_eq!(full.status, "not_ready");
+        assert_eq!(full.full_queues, 1);
+        assert_eq!(full.reasons, vec!["not accepting tasks", "1 worker queues full"]);
+
+        for worker in &workers {
+            worker.stop().await;
+        }
+    }
+}
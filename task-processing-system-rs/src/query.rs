@@ -0,0 +1,217 @@
+//! Pagination, sorting and filter parameters of list endpoints
+//!
+//! `GET /tasks` and `GET /subscriptions` describe their parameters with a
+//! [`ListSpec`] and parse the raw query string through it, so every list
+//! validates `limit`/`offset`/`sort` the same way and rejects bad values with
+//! a [`QueryError`] (`400`, reason `invalid_query`). Parameters a spec doesn't
+//! know, like `fields`, are left to other filters.
+
+use serde::de::DeserializeOwned;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum QueryError {
+    #[error("Invalid {param}: {value:?}, expected a whole number")]
+    NotANumber { param: &'static str, value: String },
+
+    #[error("Invalid limit: {limit}, must be between 1 and {max}")]
+    LimitOutOfRange { limit: usize, max: usize },
+
+    #[error("Cannot sort by {field:?}, expected one of: {allowed}")]
+    UnknownSortField { field: String, allowed: String },
+
+    #[error("Invalid {param} filter: {value:?}")]
+    InvalidFilter { param: String, value: String },
+}
+
+/// Sort order of a list: `sort=created_at` ascending, `sort=-created_at` descending
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sort {
+    pub field: &'static str,
+    pub descending: bool,
+}
+
+impl fmt::Display for Sort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", if self.descending { "-" } else { "" }, self.field)
+    }
+}
+
+/// Parameters a list endpoint accepts
+#[derive(Debug, Clone, Copy)]
+pub struct ListSpec {
+    pub default_limit: usize,
+    pub max_limit: usize,
+    pub sort_fields: &'static [&'static str],
+    /// Order when `sort` isn't given
+    pub default_sort: Sort,
+    /// Filter parameters, passed through for the endpoint to parse
+    pub filters: &'static [&'static str],
+}
+
+/// Validated parameters of one list request
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListParams {
+    pub limit: usize,
+    pub offset: usize,
+    pub sort: Sort,
+    filters: BTreeMap<&'static str, String>,
+}
+
+impl ListSpec {
+    /// Validate the raw query parameters of a request
+    pub fn parse(&self, query: &HashMap<String, String>) -> Result<ListParams, QueryError> {
+        let limit = number(query, "limit")?.unwrap_or(self.default_limit);
+        if limit == 0 || limit > self.max_limit {
+            return Err(QueryError::LimitOutOfRange {
+                limit,
+                max: self.max_limit,
+            });
+        }
+        let sort = match query.get("sort") {
+            Some(sort) => self.sort(sort)?,
+            None => self.default_sort,
+        };
+        let filters = self
+            .filters
+            .iter()
+            .filter_map(|&name| Some((name, query.get(name)?.clone())))
+            .collect();
+        Ok(ListParams {
+            limit,
+            offset: number(query, "offset")?.unwrap_or(0),
+            sort,
+            filters,
+        })
+    }
+
+    fn sort(&self, sort: &str) -> Result<Sort, QueryError> {
+        let (descending, name) = match sort.strip_prefix('-') {
+            Some(name) => (true, name),
+            None => (false, sort),
+        };
+        let field = self
+            .sort_fields
+            .iter()
+            .find(|&&field| field == name)
+            .ok_or_else(|| QueryError::UnknownSortField {
+                field: name.to_string(),
+                allowed: self.sort_fields.join(", "),
+            })?;
+        Ok(Sort { field, descending })
+    }
+}
+
+fn number(query: &HashMap<String, String>, param: &'static str) -> Result<Option<usize>, QueryError> {
+    query
+        .get(param)
+        .map(|value| {
+            value.trim().parse().map_err(|_| QueryError::NotANumber {
+                param,
+                value: value.clone(),
+            })
+        })
+        .transpose()
+}
+
+impl ListParams {
+    /// A filter's value, parsed the way the field is serialized (e.g. `status=failed`)
+    pub fn filter<T: DeserializeOwned>(&self, name: &str) -> Result<Option<T>, QueryError> {
+        self.filters
+            .get(name)
+            .map(|value| {
+                serde_json::from_value(serde_json::Value::String(value.clone())).map_err(|_| QueryError::InvalidFilter {
+                    param: name.to_string(),
+                    value: value.clone(),
+                })
+            })
+            .transpose()
+    }
+
+    /// Sort `items` by the requested field with `compare(field, a, b)`, then take the requested page
+    pub fn apply<T>(&self, mut items: Vec<T>, compare: impl Fn(&str, &T, &T) -> Ordering) -> Vec<T> {
+        items.sort_by(|a, b| {
+            let ordering = compare(self.sort.field, a, b);
+            if self.sort.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        items.into_iter().skip(self.offset).take(self.limit).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TaskStatus;
+
+    const SPEC: ListSpec = ListSpec {
+        default_limit: 2,
+        max_limit: 10,
+        sort_fields: &["n", "name"],
+        default_sort: Sort {
+            field: "n",
+            descending: false,
+        },
+        filters: &["status"],
+    };
+
+    fn query(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn test_defaults_and_paging() {
+        let params = SPEC.parse(&query(&[("fields", "id")])).unwrap();
+        assert_eq!((params.limit, params.offset, params.sort), (2, 0, SPEC.default_sort));
+
+        let items = vec![(3, "c"), (1, "a"), (2, "b"), (4, "d")];
+        let compare = |field: &str, a: &(u32, &str), b: &(u32, &str)| match field {
+            "n" => a.0.cmp(&b.0),
+            _ => a.1.cmp(b.1),
+        };
+        assert_eq!(params.apply(items.clone(), compare), vec![(1, "a"), (2, "b")]);
+
+        let params = SPEC.parse(&query(&[("sort", "-name"), ("offset", "1"), ("limit", "10")])).unwrap();
+        assert_eq!(params.sort.to_string(), "-name");
+        assert_eq!(params.apply(items, compare), vec![(3, "c"), (2, "b"), (1, "a")]);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            SPEC.parse(&query(&[("limit", "ten")])),
+            Err(QueryError::NotANumber {
+                param: "limit",
+                value: "ten".to_string()
+            })
+        );
+        assert_eq!(
+            SPEC.parse(&query(&[("limit", "11")])),
+            Err(QueryError::LimitOutOfRange { limit: 11, max: 10 })
+        );
+        assert!(SPEC.parse(&query(&[("limit", "0")])).is_err());
+        assert!(SPEC.parse(&query(&[("offset", "-1")])).is_err());
+        assert_eq!(
+            SPEC.parse(&query(&[("sort", "-size")])).unwrap_err().to_string(),
+            "Cannot sort by \"size\", expected one of: n, name"
+        );
+    }
+
+    #[test]
+    fn test_filters() {
+        let params = SPEC.parse(&query(&[("status", "failed")])).unwrap();
+        assert_eq!(params.filter::<TaskStatus>("status"), Ok(Some(TaskStatus::Failed)));
+        assert_eq!(params.filter::<TaskStatus>("other"), Ok(None));
+
+        let params = SPEC.parse(&query(&[("status", "bogus")])).unwrap();
+        assert!(matches!(
+            params.filter::<TaskStatus>("status"),
+            Err(QueryError::InvalidFilter { param, .. }) if param == "status"
+        ));
+    }
+}
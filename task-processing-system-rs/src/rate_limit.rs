@@ -0,0 +1,217 @@
+//! Token-bucket rate limiting per client (`[rate_limit]` in the config)
+//!
+//! Callers presenting a configured API key are counted by key, everyone else
+//! by IP address. Each client has a bucket of `burst` tokens refilled at
+//! `requests_per_second`; a request takes a token, and a client without one
+//! gets 429 with `Retry-After`. A `[[rate_limit.routes]]` entry replaces the
+//! default limit on its route, with a bucket of its own.
+
+use crate::types::RateLimitConfig;
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Bucket key for requests under the default limit
+const DEFAULT_ROUTE: &str = "*";
+
+/// Full buckets are swept at most this often
+const PRUNE_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy)]
+struct Limit {
+    rate: f64,
+    burst: f64,
+}
+
+impl Limit {
+    /// `None` for a rule that doesn't limit (a rate of 0)
+    fn from_rule(requests_per_second: f64, burst: u32) -> Option<Self> {
+        (requests_per_second > 0.0).then(|| Self {
+            rate: requests_per_second,
+            burst: burst.max(1) as f64,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, limit: Limit, now: Instant) {
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.rate).min(limit.burst);
+        self.updated = now;
+    }
+}
+
+/// Token buckets per client and route
+pub struct RateLimiter {
+    default: Option<Limit>,
+    /// Route pattern to its own limit (`None` exempts the route)
+    routes: HashMap<String, Option<Limit>>,
+    /// `(client, route)` to the client's bucket for that limit
+    buckets: DashMap<(String, String), Bucket>,
+    last_prune: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        Self {
+            default: Limit::from_rule(config.requests_per_second, config.burst),
+            routes: config
+                .routes
+                .iter()
+                .map(|rule| (rule.route.clone(), Limit::from_rule(rule.requests_per_second, rule.burst)))
+                .collect(),
+            buckets: DashMap::new(),
+            last_prune: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.default.is_some() || self.routes.values().any(Option::is_some)
+    }
+
+    /// Take a token from `client`'s bucket for `route` (a route pattern such
+    /// as `/task/{id}`), or say how long until one is available
+    pub fn check(&self, client: &str, route: &str) -> Result<(), Duration> {
+        self.check_at(client, route, Instant::now())
+    }
+
+    fn check_at(&self, client: &str, route: &str, now: Instant) -> Result<(), Duration> {
+        let (bucket_route, limit) = match self.routes.get(route) {
+            Some(limit) => (route, *limit),
+            None => (DEFAULT_ROUTE, self.default),
+        };
+        let Some(limit) = limit else {
+            return Ok(());
+        };
+        self.prune(now);
+
+        let mut bucket = self
+            .buckets
+            .entry((client.to_string(), bucket_route.to_string()))
+            .or_insert_with(|| Bucket {
+                tokens: limit.burst,
+                updated: now,
+            });
+        bucket.refill(limit, now);
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / limit.rate))
+        }
+    }
+
+    /// Drop buckets that have refilled completely; a new one starts full anyway
+    fn prune(&self, now: Instant) {
+        {
+            let mut last_prune = self.last_prune.lock();
+            if now.duration_since(*last_prune) < PRUNE_INTERVAL {
+                return;
+            }
+            *last_prune = now;
+        }
+        self.buckets.retain(|(_, route), bucket| {
+            let limit = match self.routes.get(route.as_str()) {
+                Some(limit) => *limit,
+                None => self.default,
+            };
+            limit.is_some_and(|limit| {
+                bucket.refill(limit, now);
+                bucket.tokens < limit.burst
+            })
+        });
+    }
+}
+
+/// Whole seconds for a `Retry-After` header, rounded up (at least 1)
+pub fn retry_after_secs(wait: Duration) -> u64 {
+    (wait.as_secs_f64().ceil() as u64).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::RateLimitRule;
+
+    fn config(requests_per_second: f64, burst: u32, routes: Vec<RateLimitRule>) -> RateLimitConfig {
+        RateLimitConfig {
+            requests_per_second,
+            burst,
+            routes,
+        }
+    }
+
+    fn rule(route: &str, requests_per_second: f64, burst: u32) -> RateLimitRule {
+        RateLimitRule {
+            route: route.to_string(),
+            requests_per_second,
+            burst,
+        }
+    }
+
+    #[test]
+    fn test_burst_then_refill() {
+        let limiter = RateLimiter::new(&config(2.0, 3, Vec::new()));
+        let start = Instant::now();
+        for _ in 0..3 {
+            assert!(limiter.check_at("ip:10.0.0.1", "/stats", start).is_ok());
+        }
+        let wait = limiter.check_at("ip:10.0.0.1", "/stats", start).unwrap_err();
+        assert_eq!(wait, Duration::from_millis(500));
+        assert_eq!(retry_after_secs(wait), 1);
+
+        // Other clients have their own bucket
+        assert!(limiter.check_at("ip:10.0.0.2", "/stats", start).is_ok());
+
+        // Half a second buys one more request
+        let later = start + Duration::from_millis(500);
+        assert!(limiter.check_at("ip:10.0.0.1", "/stats", later).is_ok());
+        assert!(limiter.check_at("ip:10.0.0.1", "/stats", later).is_err());
+    }
+
+    #[test]
+    fn test_route_rules() {
+        let limiter = RateLimiter::new(&config(
+            1.0,
+            1,
+            vec![rule("/task/create", 1.0, 2), rule("/task/{id}", 0.0, 1)],
+        ));
+        assert!(limiter.is_enabled());
+        let now = Instant::now();
+
+        // /task/create has its own bucket of two
+        assert!(limiter.check_at("key:team-a", "/task/create", now).is_ok());
+        assert!(limiter.check_at("key:team-a", "/task/create", now).is_ok());
+        assert!(limiter.check_at("key:team-a", "/task/create", now).is_err());
+        // ...separate from the default bucket shared by other routes
+        assert!(limiter.check_at("key:team-a", "/stats", now).is_ok());
+        assert!(limiter.check_at("key:team-a", "/tasks", now).is_err());
+        // A rate of 0 exempts the route
+        for _ in 0..10 {
+            assert!(limiter.check_at("key:team-a", "/task/{id}", now).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_disabled_by_default() {
+        let limiter = RateLimiter::new(&RateLimitConfig::default());
+        assert!(!limiter.is_enabled());
+        let now = Instant::now();
+        for _ in 0..1000 {
+            assert!(limiter.check_at("ip:10.0.0.1", "/task/create", now).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_retry_after_rounds_up() {
+        assert_eq!(retry_after_secs(Duration::from_millis(10)), 1);
+        assert_eq!(retry_after_secs(Duration::from_millis(2100)), 3);
+    }
+}
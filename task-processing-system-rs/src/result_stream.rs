@@ -0,0 +1,154 @@
+//! Incremental task results for `GET /task/{id}/result/stream`
+//!
+//! Workers append every stage result to the task's [`ResultBuffer`] as soon
+//! as it is computed, so a client streaming a pipeline sees the early stages
+//! while later ones are still running. The buffer is dropped once the task
+//! finishes; late readers are served from the stored task instead.
+
+use crate::types::{StageResult, Task};
+use futures::stream::{self, BoxStream, StreamExt};
+use tokio::sync::watch;
+
+#[derive(Debug, Default)]
+struct Progress {
+    stages: Vec<StageResult>,
+    finished: bool,
+}
+
+/// Stage results of one task, shared between its worker and streaming readers
+#[derive(Debug)]
+pub struct ResultBuffer {
+    sender: watch::Sender<Progress>,
+}
+
+impl Default for ResultBuffer {
+    fn default() -> Self {
+        Self {
+            sender: watch::Sender::new(Progress::default()),
+        }
+    }
+}
+
+impl ResultBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Buffer holding the final stage results of an already finished task
+    pub fn finished(task: &Task) -> Self {
+        let buffer = Self::new();
+        for stage in final_stages(task) {
+            buffer.push(stage);
+        }
+        buffer.finish();
+        buffer
+    }
+
+    /// Append a stage result and wake the readers
+    pub fn push(&self, stage: StageResult) {
+        self.sender.send_modify(|progress| progress.stages.push(stage));
+    }
+
+    /// Mark the task as finished; readers end after the last stage
+    pub fn finish(&self) {
+        self.sender.send_modify(|progress| progress.finished = true);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.sender.borrow().finished
+    }
+
+    /// Every stage from the first, waiting for new ones until the task finishes
+    pub fn stream(&self) -> BoxStream<'static, StageResult> {
+        let receiver = self.sender.subscribe();
+        stream::unfold((receiver, 0), |(mut receiver, next)| async move {
+            loop {
+                {
+                    let progress = receiver.borrow_and_update();
+                    if let Some(stage) = progress.stages.get(next) {
+                        let stage = stage.clone();
+                        drop(progress);
+                        return Some((stage, (receiver, next + 1)));
+                    }
+                    if progress.finished {
+                        return None;
+                    }
+                }
+                // The worker dropped the buffer without finishing it
+                if receiver.changed().await.is_err() {
+                    return None;
+                }
+            }
+        })
+        .boxed()
+    }
+}
+
+/// Stage results of a finished task: its pipeline stages, or one stage for a single operation
+pub fn final_stages(task: &Task) -> Vec<StageResult> {
+    if !task.stages.is_empty() {
+        return task.stages.clone();
+    }
+    if task.result.is_none() && task.error_message.is_none() {
+        return Vec::new();
+    }
+    vec![StageResult {
+        stage: 0,
+        operation: task.data.operation.clone(),
+        input: task.data.input,
+        result: task.result.clone(),
+        error: task.error_message.clone(),
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Operation, TaskData, TaskPriority};
+    use std::time::Duration;
+
+    fn stage(stage: usize, result: &str) -> StageResult {
+        StageResult {
+            stage,
+            operation: Operation::Factorial,
+            input: 3,
+            result: Some(result.to_string()),
+            error: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reader_sees_stages_as_they_arrive() {
+        let buffer = ResultBuffer::new();
+        buffer.push(stage(0, "6"));
+        let mut reader = buffer.stream();
+
+        assert_eq!(reader.next().await.unwrap().result.as_deref(), Some("6"));
+        // Nothing new yet and not finished: the reader waits
+        assert!(tokio::time::timeout(Duration::from_millis(50), reader.next()).await.is_err());
+
+        buffer.push(stage(1, "720"));
+        buffer.finish();
+        assert_eq!(reader.next().await.unwrap().result.as_deref(), Some("720"));
+        assert!(reader.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_dropped_buffer_ends_stream() {
+        let buffer = ResultBuffer::new();
+        let mut reader = buffer.stream();
+        drop(buffer);
+        assert!(reader.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_finished_task() {
+        let mut task = Task::new("Done".to_string(), TaskPriority::Low, TaskData::new(5, Operation::Factorial));
+        assert!(final_stages(&task).is_empty());
+
+        task.set_processing("120".to_string());
+        let stages: Vec<StageResult> = ResultBuffer::finished(&task).stream().collect().await;
+        assert_eq!(stages.len(), 1);
+        assert_eq!(stages[0].result.as_deref(), Some("120"));
+    }
+}
@@ -0,0 +1,155 @@
+//! Typed serialization of task results
+//!
+//! Results are stored as the strings the calculations produce. With
+//! `result_format = "typed"` in the config, `result` fields are written as a
+//! tagged [`ResultValue`] (`{"type": "integer", "value": "120"}`) so clients
+//! don't have to guess whether `"true"` or `"120"` is a boolean or a number;
+//! the default `plain` keeps the bare string. The orchestrator serializes
+//! inside [`with_format`] (through its
+//! [`WireFormat`](crate::timestamps::WireFormat)) to apply its setting.
+//! Deserialization accepts either.
+
+use crate::types::ResultFormat;
+use serde::de::Deserializer;
+use serde::{Deserialize, Serialize, Serializer};
+use std::cell::Cell;
+use std::fmt;
+
+/// A task or stage result with its type made explicit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum ResultValue {
+    /// Decimal digits, kept as a string because factorials and fibonacci
+    /// numbers outgrow JSON's safe integer range
+    Integer(String),
+    Boolean(bool),
+    /// Anything else, as JSON (a plain string when it isn't valid JSON)
+    Json(serde_json::Value),
+}
+
+impl ResultValue {
+    /// Classify a stored result string
+    pub fn parse(raw: &str) -> Self {
+        if !raw.is_empty() && raw.bytes().all(|byte| byte.is_ascii_digit()) {
+            return ResultValue::Integer(raw.to_string());
+        }
+        match raw {
+            "true" => ResultValue::Boolean(true),
+            "false" => ResultValue::Boolean(false),
+            _ => ResultValue::Json(
+                serde_json::from_str(raw).unwrap_or_else(|_| serde_json::Value::String(raw.to_string())),
+            ),
+        }
+    }
+}
+
+/// The stored string form, as [`ResultValue::parse`] accepts it
+impl fmt::Display for ResultValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResultValue::Integer(digits) => f.write_str(digits),
+            ResultValue::Boolean(value) => write!(f, "{}", value),
+            ResultValue::Json(serde_json::Value::String(text)) => f.write_str(text),
+            ResultValue::Json(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+thread_local! {
+    static FORMAT_OVERRIDE: Cell<Option<ResultFormat>> = const { Cell::new(None) };
+}
+
+/// Format that serialization on this thread will use right now (`plain`
+/// outside of [`with_format`])
+pub fn current_format() -> ResultFormat {
+    FORMAT_OVERRIDE.with(|cell| cell.get()).unwrap_or_default()
+}
+
+/// Run `f` (typically a synchronous serialization) with `format` in effect
+pub fn with_format<R>(format: ResultFormat, f: impl FnOnce() -> R) -> R {
+    let previous = FORMAT_OVERRIDE.with(|cell| cell.replace(Some(format)));
+    let result = f();
+    FORMAT_OVERRIDE.with(|cell| cell.set(previous));
+    result
+}
+
+/// Plain string or tagged value on the wire
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WireResult {
+    Plain(String),
+    Typed(ResultValue),
+}
+
+/// `#[serde(with = "...")]` helpers for `Option<String>` results
+pub mod optional_result {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &Option<String>, serializer: S) -> Result<S::Ok, S::Error> {
+        match (value, current_format()) {
+            (None, _) => serializer.serialize_none(),
+            (Some(raw), ResultFormat::Plain) => serializer.serialize_str(raw),
+            (Some(raw), ResultFormat::Typed) => ResultValue::parse(raw).serialize(serializer),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+        Ok(Option::<WireResult>::deserialize(deserializer)?.map(|wire| match wire {
+            WireResult::Plain(raw) => raw,
+            WireResult::Typed(value) => value.to_string(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Operation, Task, TaskData, TaskPriority};
+    use serde_json::json;
+
+    fn task_with_result(result: &str) -> Task {
+        let mut task = Task::new("Results".to_string(), TaskPriority::Low, TaskData::new(5, Operation::Factorial));
+        task.result = Some(result.to_string());
+        task
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(ResultValue::parse("120"), ResultValue::Integer("120".to_string()));
+        assert_eq!(ResultValue::parse("false"), ResultValue::Boolean(false));
+        assert_eq!(ResultValue::parse("[1,2]"), ResultValue::Json(json!([1, 2])));
+        assert_eq!(ResultValue::parse("n/a"), ResultValue::Json(json!("n/a")));
+        assert_eq!(ResultValue::parse(""), ResultValue::Json(json!("")));
+        for raw in ["2432902008176640000", "true", "[1,2]", "n/a"] {
+            assert_eq!(ResultValue::parse(raw).to_string(), raw);
+        }
+    }
+
+    #[test]
+    fn test_typed_round_trip() {
+        let task = task_with_result("120");
+        let json = with_format(ResultFormat::Typed, || serde_json::to_value(&task).unwrap());
+        assert_eq!(json["result"], json!({"type": "integer", "value": "120"}));
+
+        let parsed: Task = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.result.as_deref(), Some("120"));
+
+        let json = with_format(ResultFormat::Typed, || serde_json::to_value(task_with_result("true")).unwrap());
+        assert_eq!(json["result"], json!({"type": "boolean", "value": true}));
+    }
+
+    #[test]
+    fn test_plain_mode() {
+        let json = with_format(ResultFormat::Plain, || serde_json::to_value(task_with_result("true")).unwrap());
+        assert_eq!(json["result"], "true");
+
+        let parsed: Task = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.result.as_deref(), Some("true"));
+
+        // Unset results are still left out
+        let mut task = task_with_result("1");
+        task.result = None;
+        let json = with_format(ResultFormat::Typed, || serde_json::to_value(&task).unwrap());
+        assert!(json.get("result").is_none());
+    }
+}
@@ -0,0 +1,135 @@
+//! Tokio runtime health, exposed on `GET /stats/runtime`
+//!
+//! Calculations run synchronously inside async tasks, so a long one holds a
+//! runtime worker thread. A probe task periodically measures how long a freshly
+//! spawned task waits before it is first polled; large delays mean the
+//! executor is starved.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+/// Probe delays above this count as a scheduler stall
+pub const STALL_THRESHOLD: Duration = Duration::from_millis(10);
+
+/// How often the scheduler probe runs
+pub const PROBE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Snapshot returned by `GET /stats/runtime`
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimeStats {
+    /// Runtime worker threads
+    pub runtime_workers: usize,
+    /// Tasks currently alive on the runtime
+    pub alive_tasks: usize,
+    /// Tasks waiting in the runtime's global queue
+    pub global_queue_depth: usize,
+    /// Spawn-to-poll delay of the most recent probe
+    pub scheduler_delay_ms: f64,
+    /// Largest spawn-to-poll delay seen so far
+    pub max_scheduler_delay_ms: f64,
+    /// Probes delayed by more than the stall threshold
+    pub scheduler_stalls: u64,
+    pub probes: u64,
+    /// Processing threads currently inside a calculation (each blocks a runtime worker)
+    pub blocked_processing_threads: usize,
+}
+
+/// Collects scheduler probe results
+#[derive(Debug, Default)]
+pub struct RuntimeMonitor {
+    last_delay_us: AtomicU64,
+    max_delay_us: AtomicU64,
+    stalls: AtomicU64,
+    probes: AtomicU64,
+}
+
+impl RuntimeMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one spawn-to-poll measurement
+    pub fn record_delay(&self, delay: Duration) {
+        let micros = delay.as_micros() as u64;
+        self.last_delay_us.store(micros, Ordering::Relaxed);
+        self.max_delay_us.fetch_max(micros, Ordering::Relaxed);
+        self.probes.fetch_add(1, Ordering::Relaxed);
+        if delay > STALL_THRESHOLD {
+            self.stalls.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Start the background probe on the current runtime
+    pub fn spawn_probe(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        let monitor = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let spawned_at = Instant::now();
+                let delay = tokio::spawn(async move { spawned_at.elapsed() }).await;
+                if let Ok(delay) = delay {
+                    monitor.record_delay(delay);
+                }
+            }
+        })
+    }
+
+    /// Combine probe results with the current runtime metrics
+    pub fn snapshot(&self, blocked_processing_threads: usize) -> RuntimeStats {
+        let (runtime_workers, alive_tasks, global_queue_depth) =
+            match tokio::runtime::Handle::try_current() {
+                Ok(handle) => {
+                    let metrics = handle.metrics();
+                    (metrics.num_workers(), metrics.num_alive_tasks(), metrics.global_queue_depth())
+                }
+                Err(_) => (0, 0, 0),
+            };
+
+        RuntimeStats {
+            runtime_workers,
+            alive_tasks,
+            global_queue_depth,
+            scheduler_delay_ms: self.last_delay_us.load(Ordering::Relaxed) as f64 / 1000.0,
+            max_scheduler_delay_ms: self.max_delay_us.load(Ordering::Relaxed) as f64 / 1000.0,
+            scheduler_stalls: self.stalls.load(Ordering::Relaxed),
+            probes: self.probes.load(Ordering::Relaxed),
+            blocked_processing_threads,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_delay() {
+        let monitor = RuntimeMonitor::new();
+        monitor.record_delay(Duration::from_millis(2));
+        monitor.record_delay(Duration::from_millis(30));
+        monitor.record_delay(Duration::from_millis(1));
+
+        let stats = monitor.snapshot(0);
+        assert_eq!(stats.probes, 3);
+        assert_eq!(stats.scheduler_stalls, 1);
+        assert_eq!(stats.scheduler_delay_ms, 1.0);
+        assert_eq!(stats.max_scheduler_delay_ms, 30.0);
+    }
+
+    #[tokio::test]
+    async fn test_probe_and_runtime_metrics() {
+        let monitor = Arc::new(RuntimeMonitor::new());
+        let probe = monitor.spawn_probe(Duration::from_millis(5));
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        probe.abort();
+
+        let stats = monitor.snapshot(2);
+        assert!(stats.probes >= 1);
+        assert_eq!(stats.runtime_workers, 1);
+        assert_eq!(stats.blocked_processing_threads, 2);
+    }
+}
@@ -0,0 +1,623 @@
+//! Scheduled tasks (`run_at` / `delay_seconds`)
+//!
+//! A task submitted with a future `run_at`, or a `delay_seconds`, is held by
+//! the orchestrator in status `scheduled` instead of going to a worker. The
+//! [`Scheduler`] keeps them ordered by due time and, from a background task
+//! that sleeps until the earliest one is due, hands each to the worker the
+//! orchestrator's [`LoadBalancer`] picks (skipping paused workers) as an
+//! ordinary pending task.
+//! When no worker can take it (all paused, or storage full) it tries again
+//! after [`RETRY_DELAY`]. A cancelled scheduled task is kept on a worker like
+//! any other finished task. Waiting tasks live in memory only: they are not
+//! written to the task store and are lost on restart.
+//!
+//! Tasks with `depends_on` wait here too, in status `blocked`, until their
+//! dependencies are completed (see [`crate::dependencies`]); then they are
+//! due right away and dispatched like a scheduled task. A dependency must be
+//! known when the task is submitted, and one removed before it finishes
+//! fails the tasks waiting for it.
+
+use crate::dependencies::DependencyGraph;
+use crate::events::{EventBus, TaskEvent};
+use crate::load_balancer::LoadBalancer;
+use crate::task_index;
+use crate::types::{LoadBalancingStrategy, Task, TaskError, TaskStatus};
+use crate::worker::Worker;
+use chrono::{DateTime, Utc};
+use dashmap::{DashMap, DashSet};
+use parking_lot::Mutex;
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+/// How long a due task waits before another try when no worker can take it
+pub const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// A task waiting for its time, and when it's next due
+struct Entry {
+    due: DateTime<Utc>,
+    task: Task,
+}
+
+/// Holds scheduled tasks and dispatches them to workers when due
+pub struct Scheduler {
+    workers: Vec<Arc<Worker>>,
+    /// Picks the worker each due task goes to
+    balancer: Arc<LoadBalancer>,
+    events: Arc<EventBus>,
+    tasks: DashMap<String, Entry>,
+    /// Due time and ID of every waiting task, earliest first
+    timeline: Mutex<BTreeSet<(DateTime<Utc>, String)>>,
+    notify: Notify,
+    /// Woken whenever a task leaves the scheduler, dispatched or cancelled
+    released: Notify,
+    next_worker: AtomicUsize,
+    dispatched: AtomicU64,
+    deleted: AtomicU64,
+    /// Tasks waiting for their dependencies
+    blocked: DashMap<String, Task>,
+    /// Dependencies still open; locked while a task is blocked or released,
+    /// and while a waiting task is taken off `tasks` or `blocked`
+    graph: Mutex<DependencyGraph>,
+    /// Waiting tasks taken off `tasks` or `blocked` and not yet stored on a worker
+    in_transit: DashSet<String>,
+}
+
+impl Scheduler {
+    pub fn new(workers: Vec<Arc<Worker>>, events: Arc<EventBus>) -> Self {
+        let balancer = Arc::new(LoadBalancer::new(LoadBalancingStrategy::RoundRobin, workers.len(), &[]));
+        Self {
+            workers,
+            balancer,
+            events,
+            tasks: DashMap::new(),
+            timeline: Mutex::new(BTreeSet::new()),
+            notify: Notify::new(),
+            released: Notify::new(),
+            next_worker: AtomicUsize::new(0),
+            dispatched: AtomicU64::new(0),
+            deleted: AtomicU64::new(0),
+            blocked: DashMap::new(),
+            graph: Mutex::new(DependencyGraph::new()),
+            in_transit: DashSet::new(),
+        }
+    }
+
+    /// Dispatch due tasks to the workers `balancer` picks, instead of round-robin
+    pub fn with_balancer(mut self, balancer: Arc<LoadBalancer>) -> Self {
+        self.balancer = balancer;
+        self
+    }
+
+    /// Release or fail blocked tasks as their dependencies finish
+    pub fn subscribe(self: &Arc<Self>, events: &EventBus) {
+        for event in [TaskEvent::Completed, TaskEvent::Failed, TaskEvent::Cancelled] {
+            // Weak: the scheduler holds the bus, whose hooks would keep it alive
+            let scheduler = Arc::downgrade(self);
+            events.register(event, move |task: Task| {
+                let scheduler = scheduler.clone();
+                async move {
+                    if let Some(scheduler) = scheduler.upgrade() {
+                        scheduler.dependency_finished(&task).await;
+                    }
+                }
+            });
+        }
+    }
+
+    /// Hold `task` until its `run_at` time
+    pub fn schedule(&self, task: Task) -> Result<(), TaskError> {
+        let due = task.run_at.unwrap_or_else(Utc::now);
+        match self.tasks.entry(task.id.clone()) {
+            dashmap::mapref::entry::Entry::Occupied(_) => return Err(TaskError::TaskAlreadyExists { id: task.id }),
+            dashmap::mapref::entry::Entry::Vacant(slot) => {
+                self.timeline.lock().insert((due, task.id.clone()));
+                slot.insert(Entry { due, task });
+            }
+        }
+        // Wakes the dispatcher, which may now have an earlier task to wait for
+        self.notify.notify_one();
+        Ok(())
+    }
+
+    /// Hold `task` until every task in its `depends_on` is completed
+    ///
+    /// Returns the task as it now stands: `blocked`, `scheduled` to be
+    /// dispatched right away when its dependencies are already completed,
+    /// or `failed` when one of them failed or was cancelled. A dependency
+    /// neither waiting here nor stored on a worker is refused with
+    /// [`TaskError::UnknownDependency`], so every dependency can still finish.
+    pub async fn block(&self, mut task: Task) -> Result<Task, TaskError> {
+        let failed_dependency = {
+            let mut graph = self.graph.lock();
+            if self.tasks.contains_key(&task.id) || self.blocked.contains_key(&task.id) {
+                return Err(TaskError::TaskAlreadyExists { id: task.id });
+            }
+            // Under the lock, so a dependency finishing after its check finds the task waiting
+            let mut waiting = Vec::new();
+            let mut failed = None;
+            for dependency in &task.depends_on {
+                // Here first: a waiting task leaves only once it's stored on a worker
+                if self.is_waiting(dependency) {
+                    waiting.push(dependency.clone());
+                    continue;
+                }
+                match self.stored_status(dependency) {
+                    Some(TaskStatus::Completed) => {}
+                    Some(status @ (TaskStatus::Failed | TaskStatus::Cancelled)) => {
+                        failed = Some((dependency.clone(), status));
+                        break;
+                    }
+                    Some(_) => waiting.push(dependency.clone()),
+                    None => return Err(TaskError::UnknownDependency { id: dependency.clone() }),
+                }
+            }
+            if failed.is_none() && !waiting.is_empty() {
+                graph.add(&task.id, waiting);
+                self.blocked.insert(task.id.clone(), task.clone());
+                return Ok(task);
+            }
+            failed
+        };
+        match failed_dependency {
+            Some((dependency, status)) => {
+                task.set_failed(dependency_failure(&dependency, &status));
+                self.keep_finished(task.clone(), TaskEvent::Failed).await;
+            }
+            None => {
+                task.status = TaskStatus::Scheduled;
+                self.schedule(task.clone())?;
+            }
+        }
+        Ok(task)
+    }
+
+    /// Release the tasks blocked on `dependency` once it's completed, or
+    /// fail them if it failed or was cancelled
+    pub async fn dependency_finished(&self, dependency: &Task) {
+        let (ready, failed) = {
+            let mut graph = self.graph.lock();
+            match dependency.status {
+                TaskStatus::Completed => (graph.resolve(&dependency.id), Vec::new()),
+                TaskStatus::Failed | TaskStatus::Cancelled => (Vec::new(), self.take_blocked(graph.fail(&dependency.id))),
+                _ => return,
+            }
+        };
+        for task_id in ready {
+            let Some(mut task) = self.blocked.get(&task_id).map(|task| task.clone()) else {
+                continue;
+            };
+            task.status = TaskStatus::Scheduled;
+            // Scheduled before it leaves `blocked`, so readers always find it
+            if let Err(e) = self.schedule(task) {
+                warn!("Cannot release blocked task {}: {}", task_id, e);
+            }
+            self.blocked.remove(&task_id);
+            debug!("Dependencies of task {} completed", task_id);
+        }
+        self.fail_blocked(failed, dependency_failure(&dependency.id, &dependency.status)).await;
+    }
+
+    /// Fail the tasks blocked on `dependency`, removed before it finished
+    pub async fn dependency_removed(&self, dependency: &str) {
+        let failed = {
+            let mut graph = self.graph.lock();
+            self.take_blocked(graph.fail(dependency))
+        };
+        if !failed.is_empty() {
+            debug!("Dependency {} removed; failing {} blocked tasks", dependency, failed.len());
+        }
+        self.fail_blocked(failed, format!("Dependency {} removed", dependency)).await;
+    }
+
+    /// Take the blocked tasks with `task_ids` on their way to a worker;
+    /// called with the graph locked
+    fn take_blocked(&self, task_ids: Vec<String>) -> Vec<Task> {
+        task_ids
+            .into_iter()
+            .filter_map(|task_id| {
+                let (task_id, task) = self.blocked.remove(&task_id)?;
+                self.in_transit.insert(task_id);
+                Some(task)
+            })
+            .collect()
+    }
+
+    /// Fail tasks taken with [`take_blocked`](Self::take_blocked) with `reason`
+    async fn fail_blocked(&self, tasks: Vec<Task>, reason: String) {
+        for mut task in tasks {
+            task.set_failed(reason.clone());
+            // Emits Failed, so whatever depends on this task fails in turn
+            self.keep_finished(task, TaskEvent::Failed).await;
+        }
+    }
+
+    /// Whether `task_id` is waiting here, or on its way from here to a worker
+    fn is_waiting(&self, task_id: &str) -> bool {
+        self.blocked.contains_key(task_id) || self.tasks.contains_key(task_id) || self.in_transit.contains(task_id)
+    }
+
+    /// Status of `task_id` on whichever worker stores it
+    fn stored_status(&self, task_id: &str) -> Option<TaskStatus> {
+        task_index::owner(&self.workers, task_id)
+            .and_then(|worker| worker.get_task(task_id))
+            .map(|task| task.status)
+    }
+
+    /// The waiting task with `task_id`
+    pub fn get(&self, task_id: &str) -> Option<Task> {
+        self.tasks
+            .get(task_id)
+            .map(|entry| entry.task.clone())
+            .or_else(|| self.blocked.get(task_id).map(|task| task.clone()))
+    }
+
+    /// Every waiting task
+    pub fn tasks(&self) -> Vec<Task> {
+        self.tasks
+            .iter()
+            .map(|entry| entry.task.clone())
+            .chain(self.blocked.iter().map(|task| task.clone()))
+            .collect()
+    }
+
+    /// Stop waiting for `task_id`, returning it cancelled
+    pub async fn cancel(&self, task_id: &str) -> Option<Task> {
+        let mut task = {
+            let mut graph = self.graph.lock();
+            let task = self.take(&mut graph, task_id)?;
+            self.in_transit.insert(task_id.to_string());
+            task
+        };
+        if task.set_cancelled().is_err() {
+            self.in_transit.remove(task_id);
+            return None;
+        }
+        self.keep_finished(task.clone(), TaskEvent::Cancelled).await;
+        Some(task)
+    }
+
+    /// Forget the waiting task with `task_id`, returning it
+    ///
+    /// Tasks blocked on it are left for the caller to fail with
+    /// [`dependency_removed`](Self::dependency_removed), as for a task
+    /// deleted from a worker.
+    pub fn delete(&self, task_id: &str) -> Option<Task> {
+        let task = self.take(&mut self.graph.lock(), task_id)?;
+        self.deleted.fetch_add(1, Ordering::Relaxed);
+        self.released.notify_waiters();
+        Some(task)
+    }
+
+    /// Stop waiting for `task_id`, whether scheduled or blocked
+    fn take(&self, graph: &mut DependencyGraph, task_id: &str) -> Option<Task> {
+        match self.tasks.remove(task_id) {
+            Some((_, Entry { due, task })) => {
+                self.timeline.lock().remove(&(due, task.id.clone()));
+                Some(task)
+            }
+            None => {
+                let (_, task) = self.blocked.remove(task_id)?;
+                graph.remove(task_id);
+                Some(task)
+            }
+        }
+    }
+
+    /// Keep a task that finished while waiting here on a worker, like any
+    /// other finished task
+    async fn keep_finished(&self, task: Task, event: TaskEvent) {
+        // Paused workers too: the task is only stored, never calculated
+        let start = self.next_worker.fetch_add(1, Ordering::Relaxed);
+        if let Some(worker) = self.workers.get(start % self.workers.len().max(1)) {
+            if let Err(e) = worker.restore(task.clone()).await {
+                warn!("Cannot keep {} task {} on worker {}: {}", task.status, task.id, worker.id, e);
+            }
+        }
+        // Emitted once stored, so tasks blocked on it meanwhile still hear of it
+        self.in_transit.remove(&task.id);
+        self.events.emit(event, &task);
+        self.released.notify_waiters();
+    }
+
+    /// Wait until `task_id` is no longer scheduled, or `deadline` passes;
+    /// returns whether it left in time
+    pub async fn wait_released(&self, task_id: &str, deadline: tokio::time::Instant) -> bool {
+        loop {
+            // Registered before checking, so a release in between isn't missed
+            let released = self.released.notified();
+            tokio::pin!(released);
+            released.as_mut().enable();
+            if !self.is_waiting(task_id) {
+                return true;
+            }
+            if tokio::time::timeout_at(deadline, released).await.is_err() {
+                return false;
+            }
+        }
+    }
+
+    /// Tasks waiting for their time
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Tasks waiting for their dependencies
+    pub fn blocked_len(&self) -> usize {
+        self.blocked.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty() && self.blocked.is_empty()
+    }
+
+    /// Waiting tasks deleted so far
+    pub fn deleted(&self) -> u64 {
+        self.deleted.load(Ordering::Relaxed)
+    }
+
+    /// Tasks handed to a worker so far
+    pub fn dispatched(&self) -> u64 {
+        self.dispatched.load(Ordering::Relaxed)
+    }
+
+    /// Dispatch tasks as they fall due until the handle is aborted
+    pub fn spawn(self: &Arc<Self>) -> JoinHandle<()> {
+        let scheduler = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                let wait = scheduler.dispatch_due(Utc::now()).await;
+                match wait {
+                    Some(wait) => {
+                        tokio::select! {
+                            _ = tokio::time::sleep(wait) => {}
+                            _ = scheduler.notify.notified() => {}
+                        }
+                    }
+                    None => scheduler.notify.notified().await,
+                }
+            }
+        })
+    }
+
+    /// Hand every task due by `now` to a worker, returning how long until the next is due
+    async fn dispatch_due(&self, now: DateTime<Utc>) -> Option<Duration> {
+        loop {
+            let (due, task_id) = {
+                let mut timeline = self.timeline.lock();
+                let first = timeline.first()?.clone();
+                if first.0 > now {
+                    return Some((first.0 - now).to_std().unwrap_or_default());
+                }
+                timeline.pop_first()?
+            };
+            let task = {
+                let _graph = self.graph.lock();
+                // Cancelled since, or rescheduled for a retry under a later time
+                let Some((_, Entry { task, .. })) = self.tasks.remove_if(&task_id, |_, entry| entry.due == due) else {
+                    continue;
+                };
+                self.in_transit.insert(task_id.clone());
+                task
+            };
+            if let Err(task) = self.dispatch(task).await {
+                let retry = now + chrono::Duration::from_std(RETRY_DELAY).unwrap_or_default();
+                self.timeline.lock().insert((retry, task.id.clone()));
+                self.tasks.insert(task.id.clone(), Entry { due: retry, task });
+            }
+            self.in_transit.remove(&task_id);
+        }
+    }
+
+    /// Queue `task` on the worker the balancer picks, giving it back if none can take it now
+    async fn dispatch(&self, mut task: Task) -> Result<(), Task> {
+        let Some(worker) = self.balancer.pick(&self.workers).await else {
+            debug!("All workers paused; scheduled task {} waits", task.id);
+            return Err(task);
+        };
+        task.status = TaskStatus::Pending;
+        let task_id = task.id.clone();
+        match worker.add_task(task.clone()).await {
+            Ok(()) => {
+                self.dispatched.fetch_add(1, Ordering::Relaxed);
+                self.released.notify_waiters();
+                debug!("Scheduled task {} dispatched to worker {}", task_id, worker.id);
+                Ok(())
+            }
+            Err(TaskError::StorageFull { .. } | TaskError::QueueFull { .. }) => {
+                task.status = TaskStatus::Scheduled;
+                Err(task)
+            }
+            Err(e) => {
+                warn!("Dropping scheduled task {}: {}", task_id, e);
+                self.in_transit.remove(&task_id);
+                self.dependency_removed(&task_id).await;
+                self.released.notify_waiters();
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Error message of a task failed because `dependency` ended `status`
+fn dependency_failure(dependency: &str, status: &TaskStatus) -> String {
+    format!("Dependency {} {}", dependency, status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Operation, TaskData, TaskPriority};
+
+    fn scheduled(id: &str, in_ms: i64) -> Task {
+        let mut task = Task::new("Later".to_string(), TaskPriority::Medium, TaskData::new(5, Operation::Factorial));
+        task.id = id.to_string();
+        task.status = TaskStatus::Scheduled;
+        task.run_at = Some(Utc::now() + chrono::Duration::milliseconds(in_ms));
+        task
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_when_due() {
+        let workers: Vec<Arc<Worker>> = (0..2).map(|id| Arc::new(Worker::new(id, 1))).collect();
+        let scheduler = Scheduler::new(workers.clone(), Arc::new(EventBus::new()));
+        scheduler.schedule(scheduled("soon", 0)).unwrap();
+        scheduler.schedule(scheduled("later", 60_000)).unwrap();
+        assert!(matches!(scheduler.schedule(scheduled("soon", 0)), Err(TaskError::TaskAlreadyExists { .. })));
+
+        let wait = scheduler.dispatch_due(Utc::now()).await.unwrap();
+        assert!(wait > Duration::from_secs(50));
+        assert_eq!(scheduler.len(), 1);
+        assert_eq!(scheduler.dispatched(), 1);
+        let task = workers[0].get_task("soon").unwrap();
+        assert_eq!(task.status, TaskStatus::Pending);
+        assert!(task.run_at.is_some());
+        assert_eq!(scheduler.get("later").unwrap().status, TaskStatus::Scheduled);
+
+        let cancelled = scheduler.cancel("later").await.unwrap();
+        assert_eq!(cancelled.status, TaskStatus::Cancelled);
+        let kept = workers.iter().find_map(|worker| worker.get_task("later")).unwrap();
+        assert_eq!(kept.status, TaskStatus::Cancelled);
+        assert_eq!(scheduler.dispatch_due(Utc::now()).await, None);
+        assert!(scheduler.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_through_balancer() {
+        let workers: Vec<Arc<Worker>> = (0..2).map(|id| Arc::new(Worker::new(id, 1))).collect();
+        let balancer = Arc::new(LoadBalancer::new(LoadBalancingStrategy::LeastLoaded, workers.len(), &[]));
+        let scheduler = Scheduler::new(workers.clone(), Arc::new(EventBus::new())).with_balancer(balancer);
+        for id in ["queued-1", "queued-2"] {
+            workers[0].add_task(scheduled(id, 0)).await.unwrap();
+        }
+
+        scheduler.schedule(scheduled("due", 0)).unwrap();
+        assert_eq!(scheduler.dispatch_due(Utc::now()).await, None);
+        assert!(workers[1].get_task("due").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_retry_while_workers_paused() {
+        let worker = Arc::new(Worker::new(0, 1));
+        let scheduler = Scheduler::new(vec![Arc::clone(&worker)], Arc::new(EventBus::new()));
+        worker.pause();
+        scheduler.schedule(scheduled("waiting", 0)).unwrap();
+
+        let wait = scheduler.dispatch_due(Utc::now()).await.unwrap();
+        assert!(wait <= RETRY_DELAY);
+        assert_eq!(scheduler.get("waiting").unwrap().status, TaskStatus::Scheduled);
+
+        worker.resume();
+        let later = Utc::now() + chrono::Duration::from_std(RETRY_DELAY).unwrap();
+        assert_eq!(scheduler.dispatch_due(later).await, None);
+        assert!(worker.get_task("waiting").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_background_dispatch() {
+        let worker = Arc::new(Worker::new(0, 1));
+        let scheduler = Arc::new(Scheduler::new(vec![Arc::clone(&worker)], Arc::new(EventBus::new())));
+        let handle = scheduler.spawn();
+        scheduler.schedule(scheduled("delayed", 50)).unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(worker.get_task("delayed").is_none());
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert!(worker.get_task("delayed").is_some());
+        handle.abort();
+    }
+
+    fn dependent(id: &str, depends_on: &[&str]) -> Task {
+        let mut task = Task::new("After".to_string(), TaskPriority::Medium, TaskData::new(5, Operation::Factorial));
+        task.id = id.to_string();
+        task.status = TaskStatus::Blocked;
+        task.depends_on = depends_on.iter().map(|id| id.to_string()).collect();
+        task
+    }
+
+    async fn stored(worker: &Worker, id: &str, status: TaskStatus) -> Task {
+        let mut task = dependent(id, &[]);
+        task.status = status;
+        worker.restore(task.clone()).await.unwrap();
+        task
+    }
+
+    #[tokio::test]
+    async fn test_release_when_dependencies_complete() {
+        let worker = Arc::new(Worker::new(0, 1));
+        let scheduler = Scheduler::new(vec![Arc::clone(&worker)], Arc::new(EventBus::new()));
+        let done = stored(&worker, "a", TaskStatus::Completed).await;
+        stored(&worker, "b", TaskStatus::Processing).await;
+
+        let task = scheduler.block(dependent("c", &["a", "b"])).await.unwrap();
+        assert_eq!(task.status, TaskStatus::Blocked);
+        assert_eq!(scheduler.blocked_len(), 1);
+        assert!(matches!(scheduler.block(dependent("c", &["a"])).await, Err(TaskError::TaskAlreadyExists { .. })));
+        assert!(matches!(
+            scheduler.block(dependent("d", &["a", "missing"])).await,
+            Err(TaskError::UnknownDependency { id }) if id == "missing"
+        ));
+
+        // "a" was already completed; "c" still waits for "b"
+        scheduler.dependency_finished(&done).await;
+        assert_eq!(scheduler.get("c").unwrap().status, TaskStatus::Blocked);
+        worker.complete_task("b").unwrap();
+        scheduler.dependency_finished(&worker.get_task("b").unwrap()).await;
+        assert_eq!(scheduler.blocked_len(), 0);
+        assert_eq!(scheduler.get("c").unwrap().status, TaskStatus::Scheduled);
+
+        assert_eq!(scheduler.dispatch_due(Utc::now()).await, None);
+        assert_eq!(worker.get_task("c").unwrap().status, TaskStatus::Pending);
+        assert!(scheduler.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fail_when_dependency_fails() {
+        let worker = Arc::new(Worker::new(0, 1));
+        let scheduler = Scheduler::new(vec![Arc::clone(&worker)], Arc::new(EventBus::new()));
+        stored(&worker, "a", TaskStatus::Processing).await;
+        scheduler.block(dependent("b", &["a"])).await.unwrap();
+        // "b" is only blocked, but known
+        scheduler.block(dependent("c", &["b"])).await.unwrap();
+
+        worker.cancel_task("a").await.unwrap();
+        scheduler.dependency_finished(&worker.get_task("a").unwrap()).await;
+        let b = worker.get_task("b").unwrap();
+        assert_eq!(b.status, TaskStatus::Failed);
+        assert_eq!(b.error_message.as_deref(), Some("Dependency a cancelled"));
+        // No event bus running here, so "c" is failed by hand, as its hook would
+        scheduler.dependency_finished(&b).await;
+        assert_eq!(worker.get_task("c").unwrap().status, TaskStatus::Failed);
+        assert!(scheduler.is_empty());
+
+        // Already cancelled: the new task fails right away
+        let task = scheduler.block(dependent("d", &["a"])).await.unwrap();
+        assert_eq!(task.status, TaskStatus::Failed);
+    }
+
+    #[tokio::test]
+    async fn test_fail_when_dependency_removed() {
+        let worker = Arc::new(Worker::new(0, 1));
+        let scheduler = Scheduler::new(vec![Arc::clone(&worker)], Arc::new(EventBus::new()));
+        stored(&worker, "a", TaskStatus::Pending).await;
+        scheduler.schedule(scheduled("b", 60_000)).unwrap();
+        scheduler.block(dependent("c", &["a"])).await.unwrap();
+        scheduler.block(dependent("d", &["b"])).await.unwrap();
+
+        worker.delete_task("a", false).await.unwrap().unwrap();
+        scheduler.dependency_removed("a").await;
+        let c = worker.get_task("c").unwrap();
+        assert_eq!(c.status, TaskStatus::Failed);
+        assert_eq!(c.error_message.as_deref(), Some("Dependency a removed"));
+        assert!(matches!(scheduler.block(dependent("e", &["a"])).await, Err(TaskError::UnknownDependency { .. })));
+
+        scheduler.delete("b").unwrap();
+        scheduler.dependency_removed("b").await;
+        assert_eq!(worker.get_task("d").unwrap().error_message.as_deref(), Some("Dependency b removed"));
+        assert!(scheduler.is_empty());
+    }
+}
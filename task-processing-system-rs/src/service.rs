@@ -0,0 +1,283 @@
+//! Integration with OS service managers
+//!
+//! On Linux this implements the systemd `sd_notify` protocol so the binary can
+//! run as a `Type=notify` unit. On Windows it runs the Service Control Manager
+//! dispatcher: readiness and shutdown become service states, and a stop or
+//! system shutdown request resolves [`stop_requested`] so it triggers the same
+//! graceful drain as SIGTERM. Everywhere else the notifications are no-ops and
+//! `--service` is refused.
+
+use std::io;
+use std::time::Duration;
+
+/// Whether `--service` can run on this platform
+pub const SUPPORTED: bool = cfg!(any(target_os = "linux", windows));
+
+/// Connect to the service manager, before the orchestrator starts
+///
+/// `stop_timeout` is how long a shutdown may take; the Windows SCM is told so
+/// it doesn't give up on the service while queues drain. A no-op on Linux,
+/// where systemd finds `NOTIFY_SOCKET` on its own.
+pub fn start(stop_timeout: Duration) -> io::Result<()> {
+    imp::start(stop_timeout)
+}
+
+/// Resolves when the service manager asks the service to stop
+///
+/// Only the Windows SCM sends stop requests this way; systemd sends SIGTERM,
+/// so elsewhere this never resolves.
+pub async fn stop_requested() {
+    imp::stop_requested().await
+}
+
+/// Tell the service manager the process is about to exit
+pub fn notify_stopped() -> io::Result<()> {
+    imp::stopped()
+}
+
+/// Send a raw `sd_notify` state string (e.g. `READY=1`)
+///
+/// Returns `Ok(false)` when no service manager is listening
+/// (`NOTIFY_SOCKET` unset, [`start`] not called or unsupported platform).
+/// The Windows SCM only knows `READY=1` and `STOPPING=1`; other states are
+/// accepted and dropped.
+pub fn notify(state: &str) -> io::Result<bool> {
+    imp::notify(state)
+}
+
+/// Tell the service manager that startup finished
+pub fn notify_ready() -> io::Result<bool> {
+    notify("READY=1")
+}
+
+/// Tell the service manager that shutdown has begun
+pub fn notify_stopping() -> io::Result<bool> {
+    notify("STOPPING=1")
+}
+
+/// Publish a free-form status line (shown by `systemctl status`)
+pub fn notify_status(status: &str) -> io::Result<bool> {
+    // Newlines would start a new assignment in the protocol
+    notify(&format!("STATUS={}", status.replace('\n', " ")))
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use std::io;
+    use std::os::linux::net::SocketAddrExt;
+    use std::os::unix::net::{SocketAddr, UnixDatagram};
+    use std::time::Duration;
+
+    pub fn start(_stop_timeout: Duration) -> io::Result<()> {
+        Ok(())
+    }
+
+    pub async fn stop_requested() {
+        std::future::pending().await
+    }
+
+    // systemd notices the exit itself
+    pub fn stopped() -> io::Result<()> {
+        Ok(())
+    }
+
+    pub fn notify(state: &str) -> io::Result<bool> {
+        let Some(path) = std::env::var_os("NOTIFY_SOCKET") else {
+            return Ok(false);
+        };
+        let path = path.to_string_lossy().into_owned();
+
+        // A leading '@' denotes a socket in the abstract namespace
+        let addr = match path.strip_prefix('@') {
+            Some(name) => SocketAddr::from_abstract_name(name.as_bytes())?,
+            None => SocketAddr::from_pathname(&path)?,
+        };
+
+        let socket = UnixDatagram::unbound()?;
+        socket.send_to_addr(state.as_bytes(), &addr)?;
+        Ok(true)
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::ffi::OsString;
+    use std::io;
+    use std::sync::{mpsc, Mutex, OnceLock};
+    use std::time::Duration;
+    use tokio::sync::Notify;
+    use windows_service::service::{
+        ServiceControl, ServiceControlAccept, ServiceExitCode, ServiceState, ServiceStatus, ServiceType,
+    };
+    use windows_service::service_control_handler::{self, ServiceControlHandlerResult, ServiceStatusHandle};
+    use windows_service::{define_windows_service, service_dispatcher};
+
+    // Ignored by the SCM for services in their own process, but must be non-empty
+    const SERVICE_NAME: &str = "task-processor";
+
+    /// How long startup may take before the SCM reports the service as hung
+    const START_WAIT_HINT: Duration = Duration::from_secs(30);
+
+    /// Set once the control handler is registered
+    static STATUS: OnceLock<ServiceStatusHandle> = OnceLock::new();
+    static STOP_WAIT_HINT: OnceLock<Duration> = OnceLock::new();
+    /// Hands the registration result from `service_main` back to `start`
+    static REGISTERED: Mutex<Option<mpsc::Sender<io::Result<()>>>> = Mutex::new(None);
+    /// Lets `service_main` (and with it the dispatcher) return once stopped
+    static EXIT: OnceLock<mpsc::Sender<()>> = OnceLock::new();
+
+    define_windows_service!(ffi_service_main, service_main);
+
+    fn stop_signal() -> &'static Notify {
+        static STOP: OnceLock<Notify> = OnceLock::new();
+        STOP.get_or_init(Notify::new)
+    }
+
+    pub fn start(stop_timeout: Duration) -> io::Result<()> {
+        // The SCM takes the hint in milliseconds as a u32
+        let _ = STOP_WAIT_HINT.set(stop_timeout.min(Duration::from_millis(u32::MAX.into())));
+        let (registered_tx, registered_rx) = mpsc::channel();
+        *REGISTERED.lock().unwrap() = Some(registered_tx.clone());
+
+        // The dispatcher blocks its thread until the service stops, and fails
+        // straight away when the process wasn't started by the SCM
+        std::thread::Builder::new()
+            .name("service-dispatcher".to_string())
+            .spawn(move || {
+                if let Err(e) = service_dispatcher::start(SERVICE_NAME, ffi_service_main) {
+                    let _ = registered_tx.send(Err(io::Error::other(e)));
+                }
+            })?;
+
+        registered_rx
+            .recv()
+            .unwrap_or_else(|_| Err(io::Error::other("service dispatcher exited")))
+    }
+
+    fn service_main(_arguments: Vec<OsString>) {
+        let handler = |control: ServiceControl| match control {
+            // Same graceful drain as Ctrl+C or SIGTERM
+            ServiceControl::Stop | ServiceControl::Shutdown | ServiceControl::Preshutdown => {
+                stop_signal().notify_one();
+                ServiceControlHandlerResult::NoError
+            }
+            ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+            _ => ServiceControlHandlerResult::NotImplemented,
+        };
+
+        let (exit_tx, exit_rx) = mpsc::channel();
+        let registered = service_control_handler::register(SERVICE_NAME, handler)
+            .map_err(io::Error::other)
+            .and_then(|handle| {
+                let _ = STATUS.set(handle);
+                let _ = EXIT.set(exit_tx);
+                set_status(ServiceState::StartPending, START_WAIT_HINT)
+            });
+        let ok = registered.is_ok();
+        if let Some(registered_tx) = REGISTERED.lock().unwrap().take() {
+            let _ = registered_tx.send(registered);
+        }
+        if ok {
+            let _ = exit_rx.recv();
+        }
+    }
+
+    fn set_status(state: ServiceState, wait_hint: Duration) -> io::Result<()> {
+        let Some(handle) = STATUS.get() else {
+            return Ok(());
+        };
+        let controls_accepted = match state {
+            ServiceState::Running => {
+                ServiceControlAccept::STOP | ServiceControlAccept::PRESHUTDOWN | ServiceControlAccept::SHUTDOWN
+            }
+            _ => ServiceControlAccept::empty(),
+        };
+        // Pending states need a non-zero checkpoint for the wait hint to count
+        let checkpoint = u32::from(matches!(state, ServiceState::StartPending | ServiceState::StopPending));
+        handle
+            .set_service_status(ServiceStatus {
+                service_type: ServiceType::OWN_PROCESS,
+                current_state: state,
+                controls_accepted,
+                exit_code: ServiceExitCode::Win32(0),
+                checkpoint,
+                wait_hint,
+                process_id: None,
+            })
+            .map_err(io::Error::other)
+    }
+
+    pub fn notify(state: &str) -> io::Result<bool> {
+        if STATUS.get().is_none() {
+            return Ok(false);
+        }
+        match state {
+            "READY=1" => set_status(ServiceState::Running, Duration::ZERO)?,
+            "STOPPING=1" => {
+                let wait_hint = STOP_WAIT_HINT.get().copied().unwrap_or(START_WAIT_HINT);
+                set_status(ServiceState::StopPending, wait_hint)?
+            }
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    pub async fn stop_requested() {
+        stop_signal().notified().await
+    }
+
+    pub fn stopped() -> io::Result<()> {
+        set_status(ServiceState::Stopped, Duration::ZERO)?;
+        if let Some(exit) = EXIT.get() {
+            let _ = exit.send(());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+mod imp {
+    use std::io;
+    use std::time::Duration;
+
+    pub fn start(_stop_timeout: Duration) -> io::Result<()> {
+        Ok(())
+    }
+
+    pub fn notify(_state: &str) -> io::Result<bool> {
+        Ok(false)
+    }
+
+    pub async fn stop_requested() {
+        std::future::pending().await
+    }
+
+    pub fn stopped() -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixDatagram;
+
+    #[test]
+    fn test_notify_sends_state() {
+        let dir = std::env::temp_dir().join(format!("sd-notify-test-{}", std::process::id()));
+        let _ = std::fs::remove_file(&dir);
+        let listener = UnixDatagram::bind(&dir).unwrap();
+
+        std::env::set_var("NOTIFY_SOCKET", &dir);
+        let sent = notify_ready().unwrap();
+        std::env::remove_var("NOTIFY_SOCKET");
+
+        let mut buf = [0u8; 64];
+        let len = listener.recv(&mut buf).unwrap();
+        let _ = std::fs::remove_file(&dir);
+
+        assert!(sent);
+        assert_eq!(&buf[..len], b"READY=1");
+        assert!(!notify_stopping().unwrap());
+    }
+}
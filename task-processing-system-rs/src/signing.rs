@@ -0,0 +1,164 @@
+use crate::types::Task;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Hex-encoded HMAC-SHA256 of `message` under `key`
+pub fn hmac_sha256_hex(key: impl AsRef<[u8]>, message: impl AsRef<[u8]>) -> String {
+    let mut mac = HmacSha256::new_from_slice(key.as_ref()).expect("HMAC accepts any key length");
+    mac.update(message.as_ref());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Signs task results with HMAC-SHA256 using a per-deployment key
+///
+/// The signed message binds the result to its task:
+/// `{id}:{operation}:{input}:{result}`, so a signature can't be moved to
+/// another task or input.
+#[derive(Clone)]
+pub struct ResultSigner {
+    key: Vec<u8>,
+}
+
+impl ResultSigner {
+    pub fn new(key: impl AsRef<[u8]>) -> Self {
+        Self {
+            key: key.as_ref().to_vec(),
+        }
+    }
+
+    /// Message that gets signed for a task, or `None` if it has no result
+    ///
+    /// A task with `input2` (gcd, lcm) signs its inputs as `input,input2`;
+    /// one with `inputs` signs them as `[a,b,...]` in place of `input`.
+    pub fn signing_payload(task: &Task) -> Option<String> {
+        let result = task.result.as_ref()?;
+        let first = if task.data.inputs.is_empty() {
+            task.data.input.to_string()
+        } else {
+            serde_json::to_string(&task.data.inputs).ok()?
+        };
+        let input = match task.data.input2 {
+            Some(input2) => format!("{},{}", first, input2),
+            None => first,
+        };
+        Some(format!("{}:{}:{}:{}", task.id, task.data.operation, input, result))
+    }
+
+    /// Hex-encoded signature of the task's result
+    pub fn sign(&self, task: &Task) -> Option<String> {
+        let payload = Self::signing_payload(task)?;
+        let mut mac = self.mac();
+        mac.update(payload.as_bytes());
+        Some(hex::encode(mac.finalize().into_bytes()))
+    }
+
+    /// Check `task.result_signature` against the task's current result
+    pub fn verify(&self, task: &Task) -> bool {
+        let (Some(payload), Some(signature)) = (Self::signing_payload(task), task.result_signature.as_ref()) else {
+            return false;
+        };
+        let Ok(signature) = hex::decode(signature) else {
+            return false;
+        };
+
+        let mut mac = self.mac();
+        mac.update(payload.as_bytes());
+        mac.verify_slice(&signature).is_ok()
+    }
+
+    fn mac(&self) -> HmacSha256 {
+        // HMAC accepts keys of any length
+        HmacSha256::new_from_slice(&self.key).expect("HMAC accepts any key length")
+    }
+}
+
+impl std::fmt::Debug for ResultSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResultSigner").field("key", &"<redacted>").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Operation, TaskData, TaskPriority};
+
+    fn processed_task() -> Task {
+        let mut task = Task::with_id(
+            "sign-001".to_string(),
+            "Signed".to_string(),
+            TaskPriority::Medium,
+            TaskData::new(5, Operation::Factorial),
+        );
+        task.set_processing("120".to_string());
+        task
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let signer = ResultSigner::new("deployment-key");
+        let mut task = processed_task();
+        task.result_signature = signer.sign(&task);
+
+        assert!(task.result_signature.is_some());
+        assert!(signer.verify(&task));
+        assert!(!ResultSigner::new("other-key").verify(&task));
+    }
+
+    #[test]
+    fn test_tampered_result_fails_verification() {
+        let signer = ResultSigner::new("deployment-key");
+        let mut task = processed_task();
+        task.result_signature = signer.sign(&task);
+
+        task.result = Some("121".to_string());
+        assert!(!signer.verify(&task));
+    }
+
+    #[test]
+    fn test_second_input_is_signed() {
+        let signer = ResultSigner::new("deployment-key");
+        let mut task = Task::new("GCD".to_string(), TaskPriority::Low, TaskData::new(48, Operation::Gcd).with_input2(36));
+        task.result = Some("12".to_string());
+        assert!(ResultSigner::signing_payload(&task).unwrap().ends_with(":gcd:48,36:12"));
+        task.result_signature = signer.sign(&task);
+
+        task.data.input2 = Some(24);
+        assert!(!signer.verify(&task));
+    }
+
+    #[test]
+    fn test_inputs_are_signed() {
+        let signer = ResultSigner::new("deployment-key");
+        let mut task = Task::new("Batch".to_string(), TaskPriority::Low, TaskData::batch(vec![2, 4], Operation::PrimeCheck));
+        task.result = Some(r#"["true","false"]"#.to_string());
+        assert!(ResultSigner::signing_payload(&task).unwrap().ends_with(r#":prime_check:[2,4]:["true","false"]"#));
+        task.result_signature = signer.sign(&task);
+
+        task.data.inputs = vec![2, 5];
+        assert!(!signer.verify(&task));
+    }
+
+    #[test]
+    fn test_unprocessed_task_has_no_signature() {
+        let signer = ResultSigner::new("deployment-key");
+        let task = Task::new(
+            "Pending".to_string(),
+            TaskPriority::Low,
+            TaskData::new(5, Operation::Factorial),
+        );
+        assert!(signer.sign(&task).is_none());
+        assert!(!signer.verify(&task));
+    }
+
+    #[test]
+    fn test_hmac_sha256_hex() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            hmac_sha256_hex("Jefe", "what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}
@@ -0,0 +1,88 @@
+//! Automatic worker/thread sizing from the CPUs actually available
+//!
+//! `std::thread::available_parallelism` honours cgroup CPU quotas and
+//! affinity masks on Linux, so a 2-CPU container reports 2 rather than the
+//! host's core count.
+
+/// Keyword accepted in place of a number for `num_workers`/`threads_per_worker`
+pub const AUTO: &str = "auto";
+
+/// Default threads per worker when both values are automatic
+const AUTO_THREADS_PER_WORKER: usize = 4;
+
+const MAX_WORKERS: usize = 50;
+const MAX_THREADS: usize = 32;
+
+/// Number of CPUs this process may use (at least 1)
+pub fn available_cpus() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
+
+/// Resolve automatic values so the total thread count matches `cpus`
+///
+/// `None` means "auto". Explicit values are returned unchanged.
+pub fn resolve_layout(workers: Option<usize>, threads: Option<usize>, cpus: usize) -> (usize, usize) {
+    let cpus = cpus.max(1);
+    match (workers, threads) {
+        (Some(workers), Some(threads)) => (workers, threads),
+        (None, Some(threads)) => ((cpus / threads.max(1)).clamp(1, MAX_WORKERS), threads),
+        (Some(workers), None) => (workers, (cpus / workers.max(1)).clamp(1, MAX_THREADS)),
+        (None, None) => {
+            let threads = cpus.min(AUTO_THREADS_PER_WORKER);
+            ((cpus / threads).clamp(1, MAX_WORKERS), threads)
+        }
+    }
+}
+
+/// Parse a count that may also be the keyword `auto` (`None`)
+pub fn parse_count_or_auto(value: &str) -> Result<Option<usize>, String> {
+    let value = value.trim();
+    if value.eq_ignore_ascii_case(AUTO) {
+        return Ok(None);
+    }
+    value
+        .parse()
+        .map(Some)
+        .map_err(|_| format!("invalid count '{}', expected a number or \"auto\"", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explicit_values_unchanged() {
+        assert_eq!(resolve_layout(Some(3), Some(4), 2), (3, 4));
+    }
+
+    #[test]
+    fn test_auto_workers() {
+        assert_eq!(resolve_layout(None, Some(4), 2), (1, 4));
+        assert_eq!(resolve_layout(None, Some(2), 16), (8, 2));
+        assert_eq!(resolve_layout(None, Some(1), 1000), (50, 1));
+    }
+
+    #[test]
+    fn test_auto_threads() {
+        assert_eq!(resolve_layout(Some(3), None, 2), (3, 1));
+        assert_eq!(resolve_layout(Some(2), None, 16), (2, 8));
+    }
+
+    #[test]
+    fn test_fully_automatic() {
+        assert_eq!(resolve_layout(None, None, 1), (1, 1));
+        assert_eq!(resolve_layout(None, None, 2), (1, 2));
+        assert_eq!(resolve_layout(None, None, 16), (4, 4));
+        assert!(available_cpus() >= 1);
+    }
+
+    #[test]
+    fn test_parse_count_or_auto() {
+        assert_eq!(parse_count_or_auto("auto"), Ok(None));
+        assert_eq!(parse_count_or_auto("AUTO"), Ok(None));
+        assert_eq!(parse_count_or_auto("8"), Ok(Some(8)));
+        assert!(parse_count_or_auto("lots").is_err());
+    }
+}
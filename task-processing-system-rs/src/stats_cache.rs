@@ -0,0 +1,99 @@
+//! Short-lived cache of the aggregated `GET /stats` response
+//!
+//! Aggregating stats visits every worker, so frequent monitoring polls reuse
+//! the last result for `stats_cache_ms`. `?fresh=true` bypasses the cache (and
+//! refreshes it). Concurrent requests on an expired cache wait for a single
+//! recomputation instead of each visiting the workers.
+
+use crate::types::SystemStats;
+use serde::Deserialize;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct StatsQuery {
+    /// Recompute instead of returning the cached result
+    #[serde(default)]
+    pub fresh: bool,
+}
+
+pub struct StatsCache {
+    ttl: Duration,
+    cached: Mutex<Option<(Instant, SystemStats)>>,
+}
+
+impl StatsCache {
+    /// Cache results for `ttl` (zero disables caching)
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// The cached stats if younger than the TTL, otherwise (or when `fresh`) the result of `compute`
+    pub async fn get<F, Fut>(&self, fresh: bool, compute: F) -> SystemStats
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = SystemStats>,
+    {
+        if self.ttl.is_zero() {
+            return compute().await;
+        }
+
+        // Held while computing, so concurrent callers wait and reuse the result
+        let mut cached = self.cached.lock().await;
+        if !fresh {
+            if let Some((at, stats)) = cached.as_ref() {
+                if at.elapsed() < self.ttl {
+                    return stats.clone();
+                }
+            }
+        }
+        let stats = compute().await;
+        *cached = Some((Instant::now(), stats.clone()));
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    fn stats(processed: u64) -> SystemStats {
+        let mut stats = SystemStats::from_workers(Vec::new(), 0, 0);
+        stats.total_tasks_processed = processed;
+        stats
+    }
+
+    #[tokio::test]
+    async fn test_cache_and_fresh() {
+        let cache = StatsCache::new(Duration::from_secs(60));
+        let computed = AtomicU64::new(0);
+        let compute = || async { stats(computed.fetch_add(1, Ordering::SeqCst) + 1) };
+
+        assert_eq!(cache.get(false, compute).await.total_tasks_processed, 1);
+        assert_eq!(cache.get(false, compute).await.total_tasks_processed, 1);
+        assert_eq!(cache.get(true, compute).await.total_tasks_processed, 2);
+        // The fresh result replaced the cached one
+        assert_eq!(cache.get(false, compute).await.total_tasks_processed, 2);
+        assert_eq!(computed.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_expiry_and_disabled() {
+        let computed = AtomicU64::new(0);
+        let compute = || async { stats(computed.fetch_add(1, Ordering::SeqCst) + 1) };
+
+        let cache = StatsCache::new(Duration::from_millis(20));
+        cache.get(false, compute).await;
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(cache.get(false, compute).await.total_tasks_processed, 2);
+
+        let uncached = StatsCache::new(Duration::ZERO);
+        assert_eq!(uncached.get(false, compute).await.total_tasks_processed, 3);
+        assert_eq!(uncached.get(false, compute).await.total_tasks_processed, 4);
+    }
+}
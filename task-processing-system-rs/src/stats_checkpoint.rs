@@ -0,0 +1,156 @@
+//! Statistics carried across restarts
+//!
+//! Counters in `/stats` start from zero with every process. With
+//! `[stats_checkpoint]` configured the orchestrator also saves the cumulative
+//! counters (this run's added to the ones the file held when it started) to a
+//! JSON file every `interval_seconds` and on stop, and loads the file again on
+//! the next start. `/stats` reports them as `cumulative`, next to this run's
+//! `process_uptime`.
+
+use crate::types::{CumulativeStats, SystemError, SystemStats};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Earlier runs' counters, and the file they're saved to
+pub struct StatsCheckpoint {
+    path: Option<PathBuf>,
+    previous: CumulativeStats,
+    start_time: Instant,
+}
+
+impl StatsCheckpoint {
+    /// No file: the cumulative counters are this run's
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            previous: CumulativeStats::default(),
+            start_time: Instant::now(),
+        }
+    }
+
+    /// Load the counters saved in `path`, starting from zero if it doesn't exist yet
+    pub fn open(path: &Path) -> Result<Self, SystemError> {
+        let previous = match std::fs::read(path) {
+            Ok(json) => serde_json::from_slice(&json)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => CumulativeStats::default(),
+            Err(e) => return Err(e.into()),
+        };
+        if previous.uptime_seconds > 0 {
+            info!(
+                "Loaded statistics of earlier runs from {}: {} tasks processed over {} s",
+                path.display(),
+                previous.tasks_processed,
+                previous.uptime_seconds
+            );
+        }
+        Ok(Self {
+            path: Some(path.to_path_buf()),
+            previous,
+            start_time: Instant::now(),
+        })
+    }
+
+    /// Whether counters are saved to a file
+    pub fn is_persistent(&self) -> bool {
+        self.path.is_some()
+    }
+
+    /// Seconds since this process created the checkpoint
+    pub fn process_uptime(&self) -> u64 {
+        self.start_time.elapsed().as_secs()
+    }
+
+    /// Counters of the runs before this one
+    pub fn previous(&self) -> &CumulativeStats {
+        &self.previous
+    }
+
+    /// Write the cumulative counters of `stats` to the file, replacing it whole
+    pub fn save(&self, stats: &SystemStats) -> Result<(), SystemError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        // Written aside and renamed, so a crash mid-write leaves the last checkpoint
+        let partial = path.with_extension("partial");
+        std::fs::write(&partial, serde_json::to_vec_pretty(&stats.cumulative)?)?;
+        std::fs::rename(&partial, path)?;
+        Ok(())
+    }
+}
+
+/// Save the stats returned by `snapshot` every `interval` until `running` is cleared
+pub fn spawn<F, Fut>(checkpoint: Arc<StatsCheckpoint>, interval: Duration, running: Arc<AtomicBool>, snapshot: F) -> JoinHandle<()>
+where
+    F: Fn() -> Fut + Send + 'static,
+    Fut: Future<Output = SystemStats> + Send,
+{
+    tokio::spawn(async move {
+        let mut saves = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+        saves.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            saves.tick().await;
+            if !running.load(Ordering::Acquire) {
+                break;
+            }
+            let stats = snapshot().await;
+            let checkpoint = Arc::clone(&checkpoint);
+            match tokio::task::spawn_blocking(move || checkpoint.save(&stats)).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => warn!("Cannot save statistics checkpoint: {}", e),
+                Err(e) => warn!("Statistics checkpoint task failed: {}", e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::worker::Worker;
+
+    async fn stats(processed: u64, restarts: u64) -> SystemStats {
+        let mut worker = Worker::new(0, 1).get_stats().await;
+        worker.tasks_processed = processed;
+        worker.tasks_completed = processed;
+        worker.restarts = restarts;
+        SystemStats::from_workers(vec![worker], 1, 30)
+    }
+
+    #[tokio::test]
+    async fn test_counters_carried_across_runs() {
+        let path = std::env::temp_dir().join(format!("stats-checkpoint-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let first = StatsCheckpoint::open(&path).unwrap();
+        assert_eq!(first.previous(), &CumulativeStats::default());
+        let first_run = stats(5, 1).await.with_cumulative(first.previous());
+        first.save(&first_run).unwrap();
+
+        let second = StatsCheckpoint::open(&path).unwrap();
+        let second_run = stats(3, 0).await.with_cumulative(second.previous());
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(second_run.total_tasks_processed, 3);
+        assert_eq!(second_run.process_uptime, 30);
+        let cumulative = &second_run.cumulative;
+        assert_eq!(cumulative.tasks_processed, 8);
+        assert_eq!(cumulative.tasks_completed, 8);
+        assert_eq!(cumulative.worker_restarts, 1);
+        assert_eq!(cumulative.uptime_seconds, 60);
+        assert_eq!(cumulative.workers[&0].tasks_processed, 8);
+    }
+
+    #[test]
+    fn test_unreadable_checkpoint() {
+        let path = std::env::temp_dir().join(format!("stats-checkpoint-bad-{}.json", std::process::id()));
+        std::fs::write(&path, "not json").unwrap();
+        let opened = StatsCheckpoint::open(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(opened, Err(SystemError::Json(_))));
+    }
+}
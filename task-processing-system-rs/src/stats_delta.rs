@@ -0,0 +1,245 @@
+//! Changed counters for `GET /stats/delta?since=<token>`
+//!
+//! Every response carries a token naming the counters it was computed from;
+//! passing it back as `since` returns only the counters that changed since
+//! then. The last [`SNAPSHOT_HISTORY`] snapshots are kept in memory, so an
+//! unknown or expired token (or a token from before a restart) gets every
+//! counter, marked `full`.
+
+use crate::types::{SystemStats, WorkerStats};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+
+/// Snapshots kept for `since` lookups
+pub const SNAPSHOT_HISTORY: usize = 64;
+
+type Counters = BTreeMap<&'static str, u64>;
+
+#[derive(Debug, Deserialize)]
+pub struct DeltaQuery {
+    pub since: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatsDelta {
+    /// Pass as `since` on the next request
+    pub token: String,
+    /// Every counter is included (no `since`, or it was unknown or expired)
+    pub full: bool,
+    /// Changed system totals
+    pub totals: BTreeMap<String, u64>,
+    /// Workers with at least one changed counter, with only those counters
+    pub workers: Vec<WorkerDelta>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkerDelta {
+    pub id: usize,
+    #[serde(flatten)]
+    pub counters: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Snapshot {
+    sequence: u64,
+    totals: Counters,
+    workers: Vec<Counters>,
+}
+
+impl Snapshot {
+    fn same_counters(&self, other: &Snapshot) -> bool {
+        self.totals == other.totals && self.workers == other.workers
+    }
+}
+
+fn totals(stats: &SystemStats) -> Counters {
+    Counters::from([
+        ("total_tasks_processed", stats.total_tasks_processed),
+        ("total_tasks_completed", stats.total_tasks_completed),
+        ("total_tasks_failed", stats.total_tasks_failed),
+        ("total_tasks_cancelled", stats.total_tasks_cancelled),
+        ("total_tasks_deleted", stats.total_tasks_deleted),
+        ("total_result_mismatches", stats.total_result_mismatches),
+        ("total_storage_evictions", stats.total_storage_evictions),
+        ("total_tasks_expired", stats.total_tasks_expired),
+        ("total_wedged_threads", stats.total_wedged_threads),
+        ("total_threads_respawned", stats.total_threads_respawned),
+        ("worker_restarts", stats.worker_restarts),
+        ("scheduled_tasks", stats.scheduled_tasks as u64),
+        ("total_scheduled_dispatched", stats.total_scheduled_dispatched),
+        ("blocked_tasks", stats.blocked_tasks as u64),
+    ])
+}
+
+fn worker_counters(worker: &WorkerStats) -> Counters {
+    Counters::from([
+        ("tasks_processed", worker.tasks_processed),
+        ("tasks_completed", worker.tasks_completed),
+        ("tasks_failed", worker.tasks_failed),
+        ("tasks_cancelled", worker.tasks_cancelled),
+        ("tasks_deleted", worker.tasks_deleted),
+        ("result_mismatches", worker.result_mismatches),
+        ("storage_evictions", worker.storage_evictions),
+        ("tasks_expired", worker.tasks_expired),
+        ("wedged_threads", worker.wedged_threads),
+        ("threads_respawned", worker.threads_respawned),
+        ("restarts", worker.restarts),
+        ("current_load", worker.current_load as u64),
+    ])
+}
+
+/// Entries of `current` that differ from `base` (all of them without a base)
+fn changed(current: &Counters, base: Option<&Counters>) -> BTreeMap<String, u64> {
+    current
+        .iter()
+        .filter(|(key, value)| base.and_then(|base| base.get(*key)) != Some(*value))
+        .map(|(key, value)| (key.to_string(), *value))
+        .collect()
+}
+
+/// Recent counter snapshots, keyed by the tokens handed out
+pub struct StatsHistory {
+    /// Random per-process prefix so tokens from before a restart never match
+    epoch: String,
+    snapshots: Mutex<VecDeque<Snapshot>>,
+}
+
+impl StatsHistory {
+    pub fn new() -> Self {
+        Self {
+            epoch: uuid::Uuid::new_v4().simple().to_string()[..8].to_string(),
+            snapshots: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    fn token(&self, sequence: u64) -> String {
+        format!("{}-{}", self.epoch, sequence)
+    }
+
+    fn sequence(&self, token: &str) -> Option<u64> {
+        let (epoch, sequence) = token.split_once('-')?;
+        if epoch != self.epoch {
+            return None;
+        }
+        sequence.parse().ok()
+    }
+
+    /// Record `stats` and return the counters changed since the `since` token
+    pub fn delta(&self, stats: &SystemStats, since: Option<&str>) -> StatsDelta {
+        let mut snapshots = self.snapshots.lock();
+        let base = since
+            .and_then(|token| self.sequence(token))
+            .and_then(|sequence| snapshots.iter().find(|snapshot| snapshot.sequence == sequence))
+            .cloned();
+
+        let mut current = Snapshot {
+            sequence: snapshots.back().map_or(1, |last| last.sequence + 1),
+            totals: totals(stats),
+            workers: stats.workers.iter().map(worker_counters).collect(),
+        };
+        // Unchanged counters keep the latest token, so idle polling doesn't fill the history
+        match snapshots.back() {
+            Some(last) if last.same_counters(&current) => current.sequence = last.sequence,
+            _ => {
+                snapshots.push_back(current.clone());
+                if snapshots.len() > SNAPSHOT_HISTORY {
+                    snapshots.pop_front();
+                }
+            }
+        }
+        drop(snapshots);
+
+        let workers = stats
+            .workers
+            .iter()
+            .zip(&current.workers)
+            .enumerate()
+            .filter_map(|(index, (worker, counters))| {
+                let base = base.as_ref().and_then(|base| base.workers.get(index));
+                let counters = changed(counters, base);
+                (!counters.is_empty()).then_some(WorkerDelta { id: worker.id, counters })
+            })
+            .collect();
+
+        StatsDelta {
+            token: self.token(current.sequence),
+            full: base.is_none(),
+            totals: changed(&current.totals, base.as_ref().map(|base| &base.totals)),
+            workers,
+        }
+    }
+}
+
+impl Default for StatsHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::worker::Worker;
+
+    async fn stats(workers: &[Worker]) -> SystemStats {
+        let mut worker_stats = Vec::new();
+        for worker in workers {
+            worker_stats.push(worker.get_stats().await);
+        }
+        SystemStats::from_workers(worker_stats, workers.len(), 0)
+    }
+
+    fn queued_task(input: u64) -> crate::types::Task {
+        use crate::types::*;
+        Task::new("Queued".to_string(), TaskPriority::Low, TaskData::new(input, Operation::Factorial))
+    }
+
+    #[tokio::test]
+    async fn test_only_changed_counters() {
+        let workers = vec![Worker::new(0, 1), Worker::new(1, 1)];
+        let history = StatsHistory::new();
+
+        let first = history.delta(&stats(&workers).await, None);
+        assert!(first.full);
+        assert_eq!(first.totals.len(), 14);
+        assert_eq!(first.workers.len(), 2);
+
+        // Nothing changed: same token, empty delta
+        let idle = history.delta(&stats(&workers).await, Some(&first.token));
+        assert_eq!(idle.token, first.token);
+        assert!(!idle.full && idle.totals.is_empty() && idle.workers.is_empty());
+
+        workers[1].add_task(queued_task(5)).await.unwrap();
+        let delta = history.delta(&stats(&workers).await, Some(&first.token));
+        assert_ne!(delta.token, first.token);
+        assert!(delta.totals.is_empty());
+        assert_eq!(delta.workers.len(), 1);
+        assert_eq!(delta.workers[0].id, 1);
+        assert_eq!(delta.workers[0].counters, BTreeMap::from([("current_load".to_string(), 1)]));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_token_is_full() {
+        let workers = vec![Worker::new(0, 1)];
+        let history = StatsHistory::new();
+        let other = StatsHistory::new();
+        let token = other.delta(&stats(&workers).await, None).token;
+
+        assert!(history.delta(&stats(&workers).await, Some(&token)).full);
+        assert!(history.delta(&stats(&workers).await, Some("garbage")).full);
+    }
+
+    #[tokio::test]
+    async fn test_history_is_bounded() {
+        let workers = vec![Worker::new(0, 1)];
+        let history = StatsHistory::new();
+        let first = history.delta(&stats(&workers).await, None).token;
+        for input in 0..SNAPSHOT_HISTORY as u64 {
+            workers[0].add_task(queued_task(input % 20 + 1)).await.unwrap();
+            history.delta(&stats(&workers).await, None);
+        }
+        assert_eq!(history.snapshots.lock().len(), SNAPSHOT_HISTORY);
+        assert!(history.delta(&stats(&workers).await, Some(&first)).full);
+    }
+}
@@ -0,0 +1,197 @@
+//! Webhook subscriptions managed through `/subscriptions`
+//!
+//! Subscriptions live next to the tasks: in memory, for the lifetime of the
+//! process.
+
+use crate::query::{ListParams, ListSpec, QueryError, Sort};
+use crate::types::*;
+use dashmap::DashMap;
+
+/// Parameters of `GET /subscriptions`
+pub const SUBSCRIPTION_LIST: ListSpec = ListSpec {
+    default_limit: 100,
+    max_limit: MAX_LIST_LIMIT,
+    sort_fields: &["created_at", "id"],
+    default_sort: Sort {
+        field: "created_at",
+        descending: false,
+    },
+    filters: &["event"],
+};
+
+#[derive(Debug, Default)]
+pub struct SubscriptionStore {
+    subscriptions: DashMap<String, Subscription>,
+}
+
+impl SubscriptionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.subscriptions.is_empty()
+    }
+
+    /// Register a new subscription
+    pub fn create(&self, request: SubscriptionRequest) -> Result<Subscription, ValidationError> {
+        let subscription = Subscription::new(request)?;
+        self.subscriptions.insert(subscription.id.clone(), subscription.clone());
+        Ok(subscription)
+    }
+
+    pub fn get(&self, id: &str) -> Option<Subscription> {
+        self.subscriptions.get(id).map(|entry| entry.clone())
+    }
+
+    /// All subscriptions, oldest first
+    pub fn list(&self) -> Vec<Subscription> {
+        let mut subscriptions: Vec<Subscription> =
+            self.subscriptions.iter().map(|entry| entry.value().clone()).collect();
+        subscriptions.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)));
+        subscriptions
+    }
+
+    /// One page of subscriptions for `GET /subscriptions`, optionally only those with an `event`
+    pub fn page(&self, params: &ListParams) -> Result<Vec<Subscription>, QueryError> {
+        let event = params.filter::<WebhookEventType>("event")?;
+        let subscriptions = self
+            .list()
+            .into_iter()
+            .filter(|subscription| event.as_ref().is_none_or(|event| subscription.events.contains(event)))
+            .collect();
+        Ok(params.apply(subscriptions, |field, a, b| match field {
+            "id" => a.id.cmp(&b.id),
+            _ => a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)),
+        }))
+    }
+
+    /// Replace a subscription's settings, keeping its id and creation time
+    ///
+    /// Returns `Ok(None)` when the id is unknown.
+    pub fn update(&self, id: &str, request: SubscriptionRequest) -> Result<Option<Subscription>, ValidationError> {
+        let replacement = Subscription::new(request)?;
+        let Some(mut entry) = self.subscriptions.get_mut(id) else {
+            return Ok(None);
+        };
+        let updated = Subscription {
+            id: entry.id.clone(),
+            created_at: entry.created_at,
+            ..replacement
+        };
+        *entry = updated.clone();
+        Ok(Some(updated))
+    }
+
+    pub fn delete(&self, id: &str) -> bool {
+        self.subscriptions.remove(id).is_some()
+    }
+
+    /// Subscriptions that want `event` for `task`
+    pub fn matching(&self, event: WebhookEventType, task: &Task) -> Vec<Subscription> {
+        self.subscriptions
+            .iter()
+            .filter(|entry| entry.accepts(event, task))
+            .map(|entry| entry.value().clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(url: &str) -> SubscriptionRequest {
+        SubscriptionRequest {
+            url: url.to_string(),
+            events: WebhookEventType::ALL.to_vec(),
+            filter: SubscriptionFilter::default(),
+            secret: None,
+        }
+    }
+
+    fn task(operation: Operation, priority: TaskPriority) -> Task {
+        Task::new("Sub".to_string(), priority, TaskData::new(5, operation))
+    }
+
+    #[test]
+    fn test_crud() {
+        let store = SubscriptionStore::new();
+        let created = store.create(request("http://a/hook")).unwrap();
+        assert_eq!(store.get(&created.id).unwrap().url, "http://a/hook");
+        assert_eq!(store.list().len(), 1);
+
+        let mut changed = request("http://b/hook");
+        changed.secret = Some("s3cret".to_string());
+        let updated = store.update(&created.id, changed).unwrap().unwrap();
+        assert_eq!(updated.id, created.id);
+        assert_eq!(updated.created_at, created.created_at);
+        assert!(updated.has_secret);
+        assert!(store.update("missing", request("http://c")).unwrap().is_none());
+
+        assert!(store.delete(&created.id));
+        assert!(!store.delete(&created.id));
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_requests_rejected() {
+        let store = SubscriptionStore::new();
+        assert!(store.create(request("ftp://a")).is_err());
+
+        let mut no_events = request("http://a");
+        no_events.events.clear();
+        assert!(store.create(no_events).is_err());
+    }
+
+    #[test]
+    fn test_matching_applies_events_and_filters() {
+        let store = SubscriptionStore::new();
+        let mut completed_factorials = request("http://a");
+        completed_factorials.events = vec![WebhookEventType::TaskCompleted];
+        completed_factorials.filter.operations = vec![Operation::Factorial];
+        store.create(completed_factorials).unwrap();
+
+        let mut high_priority = request("http://b");
+        high_priority.filter.priorities = vec![TaskPriority::High];
+        store.create(high_priority).unwrap();
+
+        let factorial = task(Operation::Factorial, TaskPriority::Low);
+        assert_eq!(store.matching(WebhookEventType::TaskCompleted, &factorial).len(), 1);
+        assert!(store.matching(WebhookEventType::TaskCreated, &factorial).is_empty());
+
+        let urgent = task(Operation::Fibonacci, TaskPriority::High);
+        let matched = store.matching(WebhookEventType::TaskCreated, &urgent);
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].url, "http://b");
+    }
+
+    #[test]
+    fn test_page() {
+        let store = SubscriptionStore::new();
+        let mut created_only = request("http://a");
+        created_only.events = vec![WebhookEventType::TaskCreated];
+        store.create(created_only).unwrap();
+        store.create(request("http://b")).unwrap();
+        let query = |pairs: &[(&str, &str)]| {
+            let query = pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect();
+            SUBSCRIPTION_LIST.parse(&query).unwrap()
+        };
+
+        let completed = store.page(&query(&[("event", "task.completed")])).unwrap();
+        assert_eq!(completed.len(), 1);
+        assert_eq!(completed[0].url, "http://b");
+        assert_eq!(store.page(&query(&[("limit", "1"), ("offset", "1")])).unwrap().len(), 1);
+        assert!(store.page(&query(&[("event", "task.failed")])).is_err());
+    }
+
+    #[test]
+    fn test_secret_not_serialized() {
+        let mut with_secret = request("http://a");
+        with_secret.secret = Some("s3cret".to_string());
+        let json = serde_json::to_value(Subscription::new(with_secret).unwrap()).unwrap();
+        assert!(json.get("secret").is_none());
+        assert_eq!(json["has_secret"], true);
+        assert_eq!(json["events"][0], "task.created");
+    }
+}
@@ -0,0 +1,143 @@
+//! Worker crash recovery
+//!
+//! A processing thread that panics takes its worker down: the worker stops
+//! its other threads and marks itself crashed. The supervisor checks the
+//! workers every [`CHECK_INTERVAL`], fails the task whose calculation
+//! panicked, starts the worker again and moves its pending tasks to healthy
+//! workers (back onto the restarted worker when there are none).
+
+use crate::worker::Worker;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+/// How often the supervisor looks for crashed workers
+pub const CHECK_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Watch `workers` until `running` is cleared, recovering any that crash
+pub fn spawn(workers: Vec<Arc<Worker>>, running: Arc<AtomicBool>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut checks = tokio::time::interval(CHECK_INTERVAL);
+        checks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            checks.tick().await;
+            if !running.load(Ordering::Acquire) {
+                break;
+            }
+            for index in 0..workers.len() {
+                // Crashed and done shutting down its threads
+                if workers[index].is_crashed() && !workers[index].is_running() {
+                    recover(&workers, index).await;
+                }
+            }
+        }
+    })
+}
+
+/// Restart the crashed worker at `index` and move its pending tasks to
+/// healthy peers, returning how many moved
+pub async fn recover(workers: &[Arc<Worker>], index: usize) -> usize {
+    let crashed = &workers[index];
+    // Taken before the restart, so the restarted worker doesn't pick them up first
+    let stranded = crashed.take_stranded().await;
+
+    crashed.record_restart();
+    let runner = Arc::clone(crashed);
+    tokio::spawn(async move {
+        if let Err(e) = runner.start().await {
+            error!("Worker {} failed to restart: {}", runner.id, e);
+        }
+    });
+    crashed.wait_running().await;
+    info!("Restarted worker {} after a crash", crashed.id);
+
+    let healthy: Vec<&Arc<Worker>> = workers
+        .iter()
+        .filter(|worker| worker.id != crashed.id && worker.is_running() && !worker.is_crashed() && !worker.is_paused())
+        .collect();
+    let mut moved = 0;
+    for (position, (task, buffer)) in stranded.into_iter().enumerate() {
+        let task_id = task.id.clone();
+        if let Some(target) = healthy.get(position % healthy.len().max(1)) {
+            match target.requeue(task.clone(), buffer).await {
+                Ok(()) => {
+                    crashed.release(&task_id);
+                    moved += 1;
+                    continue;
+                }
+                Err(e) => warn!("Cannot move task {} to worker {}: {}", task_id, target.id, e),
+            }
+        }
+        // No healthy peer could take it (or all were full): queue it on the restarted worker again
+        crashed.put_back(task).await;
+    }
+
+    if moved > 0 {
+        info!("Moved {} pending tasks off crashed worker {}", moved, crashed.id);
+    }
+    moved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{InjectedFailure, Operation, Task, TaskData, TaskPriority, TaskStatus};
+    use tokio::time::timeout;
+
+    fn task(id: &str, input: u64) -> Task {
+        Task::with_id(id.to_string(), "Supervised".to_string(), TaskPriority::Low, TaskData::new(input, Operation::Factorial))
+    }
+
+    #[tokio::test]
+    async fn test_crashed_worker_restarted_and_tasks_moved() {
+        let panics = vec![InjectedFailure {
+            operation: Operation::Factorial,
+            input: 13,
+            message: None,
+            panic: true,
+        }];
+        let workers = vec![
+            Arc::new(Worker::new(0, 1).with_injected_failures(panics)),
+            Arc::new(Worker::new(1, 1)),
+        ];
+        // Queued before the start, so the panicking task goes first and the rest wait behind it
+        for (id, input) in [("poison", 13), ("first", 5), ("second", 6)] {
+            workers[0].add_task(task(id, input)).await.unwrap();
+        }
+        for worker in &workers {
+            let runner = Arc::clone(worker);
+            tokio::spawn(async move { runner.start().await });
+        }
+
+        let crashed = timeout(Duration::from_secs(2), async {
+            while !workers[0].is_crashed() || workers[0].is_running() {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await;
+        assert!(crashed.is_ok());
+        assert_eq!(recover(&workers, 0).await, 2);
+
+        assert!(workers[0].is_running() && !workers[0].is_crashed());
+        assert_eq!(workers[0].get_stats().await.restarts, 1);
+        let poison = workers[0].get_task("poison").unwrap();
+        assert_eq!(poison.status, TaskStatus::Failed);
+        assert!(poison.error_message.unwrap().contains("crashed"));
+
+        for id in ["first", "second"] {
+            assert!(workers[0].get_task(id).is_none());
+            let processed = timeout(Duration::from_secs(2), async {
+                while workers[1].get_task(id).unwrap().status == TaskStatus::Pending {
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                }
+            })
+            .await;
+            assert!(processed.is_ok(), "task {} still pending", id);
+        }
+        for worker in &workers {
+            worker.stop().await;
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Plain-text tables for CLI output
+
+use std::fmt;
+
+/// Column-aligned text table
+///
+/// Columns whose cells are all numbers (or `-`) are right-aligned.
+#[derive(Debug, Clone, Default)]
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new<I, S>(headers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            headers: headers.into_iter().map(Into::into).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn add_row<I, S>(&mut self, cells: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.rows.push(cells.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    fn cells(&self, column: usize) -> impl Iterator<Item = &str> {
+        self.rows.iter().map(move |row| row.get(column).map_or("", String::as_str))
+    }
+
+    fn is_numeric(&self, column: usize) -> bool {
+        !self.rows.is_empty() && self.cells(column).all(|cell| cell == "-" || cell.parse::<f64>().is_ok())
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let columns: Vec<(usize, bool)> = (0..self.headers.len())
+            .map(|column| {
+                let width = self
+                    .cells(column)
+                    .chain(std::iter::once(self.headers[column].as_str()))
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or(0);
+                (width, self.is_numeric(column))
+            })
+            .collect();
+
+        for row in std::iter::once(&self.headers).chain(&self.rows) {
+            let mut line = String::new();
+            for (column, &(width, numeric)) in columns.iter().enumerate() {
+                let cell = row.get(column).map_or("", String::as_str);
+                if column > 0 {
+                    line.push_str("  ");
+                }
+                if numeric {
+                    line.push_str(&format!("{:>width$}", cell, width = width));
+                } else {
+                    line.push_str(&format!("{:<width$}", cell, width = width));
+                }
+            }
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alignment() {
+        let mut table = Table::new(["ID", "STATE", "QUEUED"]);
+        table.add_row(["0", "active", "12"]).add_row(["1", "paused", "-"]);
+
+        assert_eq!(
+            table.to_string(),
+            "ID  STATE   QUEUED\n 0  active      12\n 1  paused       -\n"
+        );
+    }
+
+    #[test]
+    fn test_headers_only() {
+        let table = Table::new(["ID", "STATE"]);
+        assert!(table.is_empty());
+        assert_eq!(table.to_string(), "ID  STATE\n");
+    }
+}
@@ -0,0 +1,155 @@
+//! Which worker holds each task
+//!
+//! Every worker the orchestrator creates shares one [`TaskIndex`] and keeps
+//! it up to date as tasks are stored, evicted, purged or moved to a peer, so
+//! looking a task up (`GET /task/{id}`, completing, cancelling, ...) goes
+//! straight to the owning worker instead of asking each worker in turn.
+
+use crate::worker::Worker;
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use std::sync::Arc;
+
+/// Task ID → index of the worker holding it
+#[derive(Debug, Default)]
+pub struct TaskIndex {
+    owners: DashMap<String, usize>,
+}
+
+impl TaskIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that worker `worker` now holds `task_id`
+    pub fn insert(&self, task_id: &str, worker: usize) {
+        self.owners.insert(task_id.to_string(), worker);
+    }
+
+    /// Record `worker` as the holder of a new `task_id`, unless some worker already holds it
+    ///
+    /// Atomic, so of concurrent submissions with one ID only the first is
+    /// claimed, whichever workers they went to.
+    pub fn claim(&self, task_id: &str, worker: usize) -> bool {
+        match self.owners.entry(task_id.to_string()) {
+            Entry::Vacant(entry) => {
+                entry.insert(worker);
+                true
+            }
+            Entry::Occupied(_) => false,
+        }
+    }
+
+    /// Forget `task_id` unless it has since moved to another worker
+    pub fn remove(&self, task_id: &str, worker: usize) {
+        self.owners.remove_if(task_id, |_, owner| *owner == worker);
+    }
+
+    /// Index of the worker holding `task_id`
+    pub fn owner(&self, task_id: &str) -> Option<usize> {
+        self.owners.get(task_id).map(|owner| *owner)
+    }
+
+    /// Tasks indexed
+    pub fn len(&self) -> usize {
+        self.owners.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.owners.is_empty()
+    }
+}
+
+/// The worker in `workers` holding `task_id`
+///
+/// Uses the index the workers share; workers built without one are searched in turn.
+pub fn owner<'a>(workers: &'a [Arc<Worker>], task_id: &str) -> Option<&'a Arc<Worker>> {
+    match workers.first().and_then(|worker| worker.task_index()) {
+        Some(index) => index
+            .owner(task_id)
+            .and_then(|id| workers.get(id))
+            .filter(|worker| worker.get_task(task_id).is_some()),
+        None => workers.iter().find(|worker| worker.get_task(task_id).is_some()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Operation, Task, TaskData, TaskError, TaskPriority, TaskStatus};
+
+    fn task(id: &str) -> Task {
+        let mut task = Task::new("Indexed".to_string(), TaskPriority::Medium, TaskData::new(5, Operation::Factorial));
+        task.id = id.to_string();
+        task
+    }
+
+    fn workers(index: &Arc<TaskIndex>) -> Vec<Arc<Worker>> {
+        (0..3)
+            .map(|id| Arc::new(Worker::new(id, 1).with_task_index(Arc::clone(index))))
+            .collect()
+    }
+
+    #[test]
+    fn test_remove_only_by_owner() {
+        let index = TaskIndex::new();
+        index.insert("a", 0);
+        // Moved to worker 1 before worker 0 let go of it
+        index.insert("a", 1);
+        index.remove("a", 0);
+        assert_eq!(index.owner("a"), Some(1));
+        index.remove("a", 1);
+        assert!(index.is_empty());
+    }
+
+    #[test]
+    fn test_claim_only_once() {
+        let index = TaskIndex::new();
+        assert!(index.claim("a", 0));
+        assert!(!index.claim("a", 1));
+        assert_eq!(index.owner("a"), Some(0));
+        index.remove("a", 0);
+        assert!(index.claim("a", 1));
+    }
+
+    #[tokio::test]
+    async fn test_same_id_on_two_workers() {
+        let index = Arc::new(TaskIndex::new());
+        let workers = workers(&index);
+        let (first, second) = tokio::join!(workers[0].add_task(task("twice")), workers[1].add_task(task("twice")));
+        let results = [first, second];
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+        assert!(results.iter().any(|result| matches!(result, Err(TaskError::TaskAlreadyExists { .. }))));
+        assert_eq!(workers.iter().filter(|worker| worker.get_task("twice").is_some()).count(), 1);
+
+        // A submission the worker can't store gives the ID back
+        let full = Worker::new(3, 1).with_max_tasks(1).with_task_index(Arc::clone(&index));
+        full.add_task(task("stored")).await.unwrap();
+        assert!(matches!(full.add_task(task("refused")).await, Err(TaskError::StorageFull { .. })));
+        assert!(index.owner("refused").is_none());
+        workers[0].add_task(task("refused")).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_workers_keep_index_current() {
+        let index = Arc::new(TaskIndex::new());
+        let workers = workers(&index);
+        workers[2].add_task(task("queued")).await.unwrap();
+        workers[1].add_task(task("done")).await.unwrap();
+        assert_eq!(index.owner("queued"), Some(2));
+        assert_eq!(owner(&workers, "done").map(|worker| worker.id), Some(1));
+        assert!(owner(&workers, "missing").is_none());
+
+        // Moved like POST /admin/rebalance does
+        for (task, buffer) in workers[2].take_queued(1).await {
+            workers[0].requeue(task, buffer).await.unwrap();
+            workers[2].release("queued");
+        }
+        assert_eq!(owner(&workers, "queued").map(|worker| worker.id), Some(0));
+
+        workers[1].cancel_task("done").await.unwrap();
+        assert_eq!(workers[1].purge(&TaskStatus::Cancelled), 1);
+        assert_eq!(index.owner("done"), None);
+        assert_eq!(index.len(), 1);
+    }
+}
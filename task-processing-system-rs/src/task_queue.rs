@@ -0,0 +1,194 @@
+//! A worker's queue of pending tasks, ordered by priority
+//!
+//! Threads take the highest-priority task first and, within a priority, the
+//! oldest by `created_at`, so a task moved between workers keeps its place by
+//! age. Entries are kept in a `BTreeMap` rather than a `BinaryHeap` because the
+//! queue is also listed in order, asked for positions, and has single tasks
+//! moved when an operator changes their priority.
+
+use crate::types::{QueueDepth, Task, TaskPriority};
+use chrono::{DateTime, Utc};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct QueueKey {
+    priority: Reverse<TaskPriority>,
+    created_at: DateTime<Utc>,
+    /// Orders tasks created at the same instant by when they were queued
+    sequence: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct TaskQueue {
+    entries: BTreeMap<QueueKey, Task>,
+    next_sequence: u64,
+}
+
+impl TaskQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, task: Task) {
+        let key = QueueKey {
+            priority: Reverse(task.priority),
+            created_at: task.created_at,
+            sequence: self.next_sequence,
+        };
+        self.next_sequence += 1;
+        self.entries.insert(key, task);
+    }
+
+    /// The task threads should take next
+    pub fn pop(&mut self) -> Option<Task> {
+        self.entries.pop_first().map(|(_, task)| task)
+    }
+
+    /// Up to `count` tasks from the front, in order
+    pub fn take_front(&mut self, count: usize) -> Vec<Task> {
+        std::iter::from_fn(|| self.pop()).take(count).collect()
+    }
+
+    /// Up to `count` tasks from the back (lowest priority, newest), in order
+    pub fn take_back(&mut self, count: usize) -> Vec<Task> {
+        let mut taken: Vec<Task> = std::iter::from_fn(|| self.entries.pop_last().map(|(_, task)| task))
+            .take(count)
+            .collect();
+        taken.reverse();
+        taken
+    }
+
+    /// Take a task off the queue wherever it is
+    pub fn remove(&mut self, task_id: &str) -> Option<Task> {
+        let key = self.key_of(task_id)?;
+        self.entries.remove(&key)
+    }
+
+    /// The queued task with `task_id`
+    pub fn get(&self, task_id: &str) -> Option<&Task> {
+        self.iter().find(|task| task.id == task_id)
+    }
+
+    /// Rename a queued task, returning whether it was queued
+    pub fn set_title(&mut self, task_id: &str, title: &str) -> bool {
+        match self.entries.values_mut().find(|task| task.id == task_id) {
+            Some(task) => {
+                task.title = title.to_string();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Queued tasks in the order threads will take them
+    pub fn iter(&self) -> impl Iterator<Item = &Task> {
+        self.entries.values()
+    }
+
+    /// 1-based position of a queued task
+    pub fn position(&self, task_id: &str) -> Option<usize> {
+        self.iter().position(|task| task.id == task_id).map(|index| index + 1)
+    }
+
+    /// Change a queued task's priority, returning the old one and its new position
+    pub fn set_priority(&mut self, task_id: &str, priority: TaskPriority) -> Option<(TaskPriority, usize)> {
+        let key = self.key_of(task_id)?;
+        let mut task = self.entries.remove(&key).expect("key found above");
+        let previous = std::mem::replace(&mut task.priority, priority);
+        self.entries.insert(
+            QueueKey {
+                priority: Reverse(priority),
+                ..key
+            },
+            task,
+        );
+        Some((previous, self.position(task_id)?))
+    }
+
+    fn key_of(&self, task_id: &str) -> Option<QueueKey> {
+        self.entries.iter().find(|(_, task)| task.id == task_id).map(|(key, _)| *key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Queued tasks of each priority
+    pub fn depth(&self) -> QueueDepth {
+        let mut depth = QueueDepth::default();
+        for key in self.entries.keys() {
+            match key.priority.0 {
+                TaskPriority::High => depth.high += 1,
+                TaskPriority::Medium => depth.medium += 1,
+                TaskPriority::Low => depth.low += 1,
+            }
+        }
+        depth
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Operation, TaskData};
+
+    fn task(id: &str, priority: TaskPriority) -> Task {
+        Task::with_id(id.to_string(), "Queued".to_string(), priority, TaskData::new(5, Operation::Factorial))
+    }
+
+    fn ids(queue: &TaskQueue) -> Vec<&str> {
+        queue.iter().map(|task| task.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_priority_order() {
+        let mut queue = TaskQueue::new();
+        for (id, priority) in [("low", TaskPriority::Low), ("medium", TaskPriority::Medium), ("high-1", TaskPriority::High), ("high-2", TaskPriority::High)] {
+            queue.push(task(id, priority));
+        }
+        assert_eq!(ids(&queue), vec!["high-1", "high-2", "medium", "low"]);
+        assert_eq!(queue.depth(), QueueDepth { high: 2, medium: 1, low: 1 });
+        assert_eq!(queue.position("medium"), Some(3));
+
+        assert_eq!(queue.pop().unwrap().id, "high-1");
+        let back: Vec<String> = queue.take_back(2).into_iter().map(|task| task.id).collect();
+        assert_eq!(back, vec!["medium", "low"]);
+        assert_eq!(queue.take_front(5).len(), 1);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_older_tasks_first() {
+        let mut queue = TaskQueue::new();
+        let older = task("older", TaskPriority::Medium);
+        let mut newer = task("newer", TaskPriority::Medium);
+        newer.created_at = older.created_at + chrono::Duration::seconds(1);
+        // Queued out of order, e.g. moved here from another worker
+        queue.push(newer);
+        queue.push(older);
+        assert_eq!(ids(&queue), vec!["older", "newer"]);
+    }
+
+    #[test]
+    fn test_set_priority() {
+        let mut queue = TaskQueue::new();
+        for id in ["a", "b", "c"] {
+            queue.push(task(id, TaskPriority::Low));
+        }
+        assert_eq!(queue.set_priority("c", TaskPriority::High), Some((TaskPriority::Low, 1)));
+        assert_eq!(queue.iter().next().unwrap().priority, TaskPriority::High);
+        // Back among its old priority, it keeps its place by age
+        assert_eq!(queue.set_priority("c", TaskPriority::Low), Some((TaskPriority::High, 3)));
+        assert_eq!(ids(&queue), vec!["a", "b", "c"]);
+        assert_eq!(queue.set_priority("missing", TaskPriority::High), None);
+
+        assert_eq!(queue.remove("b").unwrap().id, "b");
+        assert!(queue.remove("b").is_none());
+        assert_eq!(ids(&queue), vec!["a", "c"]);
+    }
+}
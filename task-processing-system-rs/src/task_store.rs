@@ -0,0 +1,260 @@
+//! Persistent storage of tasks across restarts
+//!
+//! Workers keep their tasks in memory; with a [`TaskStore`] they also save
+//! every task whenever it changes (created, reprioritized, calculated,
+//! completed, failed or cancelled) and remove it when it's purged or evicted.
+//! On start the orchestrator loads the stored tasks back onto its workers,
+//! re-queueing the ones still pending. [`SqliteTaskStore`] is the built-in
+//! backend, enabled with `[task_store]` in the config.
+//!
+//! A read-only replica runs no workers: it answers task lookups and lists
+//! from the store it shares with its primary, reading it on every request.
+
+use crate::types::{SystemError, Task};
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::warn;
+
+/// Where workers save their tasks
+///
+/// `save` and `remove` are called from processing threads and request
+/// handlers, so they shouldn't block on slow I/O; `flush` is where to wait.
+pub trait TaskStore: Send + Sync {
+    /// Record the current state of `task`, replacing any earlier one
+    fn save(&self, task: &Task);
+
+    /// Forget a task
+    fn remove(&self, task_id: &str);
+
+    /// Every stored task, in no particular order
+    fn load(&self) -> Result<Vec<Task>, SystemError>;
+
+    /// The stored task with `task_id`, if any
+    fn get(&self, task_id: &str) -> Result<Option<Task>, SystemError> {
+        Ok(self.load()?.into_iter().find(|task| task.id == task_id))
+    }
+
+    /// Wait until everything saved or removed so far is stored
+    fn flush(&self) {}
+}
+
+/// How long a read or write waits for another process's transaction, such
+/// as a primary's writer seen from a replica
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS tasks (
+    id TEXT PRIMARY KEY,
+    status TEXT NOT NULL,
+    task TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+)";
+
+enum Message {
+    Save(Box<Task>),
+    Remove(String),
+    /// Answered once everything queued before it is written
+    Flush(mpsc::Sender<()>),
+}
+
+/// Keeps tasks in a SQLite file, written on a background thread
+///
+/// Each task is one row holding its JSON, so results and stage details come
+/// back exactly as the API last served them.
+pub struct SqliteTaskStore {
+    connection: Mutex<Connection>,
+    sender: Mutex<Option<mpsc::Sender<Message>>>,
+    writer: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl SqliteTaskStore {
+    /// Open (creating if needed) the database at `path`
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.busy_timeout(BUSY_TIMEOUT)?;
+        connection.execute(CREATE_TABLE, [])?;
+        let writer_connection = Connection::open(path)?;
+        writer_connection.busy_timeout(BUSY_TIMEOUT)?;
+        let (sender, receiver) = mpsc::channel();
+        let writer = std::thread::spawn(move || write_changes(writer_connection, receiver));
+        Ok(Self {
+            connection: Mutex::new(connection),
+            sender: Mutex::new(Some(sender)),
+            writer: Mutex::new(Some(writer)),
+        })
+    }
+
+    fn send(&self, message: Message) {
+        if let Some(sender) = self.sender.lock().as_ref() {
+            let _ = sender.send(message);
+        }
+    }
+
+    /// Write everything queued so far and stop the writer
+    pub fn close(&self) {
+        self.sender.lock().take();
+        if let Some(writer) = self.writer.lock().take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+impl TaskStore for SqliteTaskStore {
+    /// Queue `task` for writing (dropped once the store is closed)
+    fn save(&self, task: &Task) {
+        self.send(Message::Save(Box::new(task.clone())));
+    }
+
+    fn remove(&self, task_id: &str) {
+        self.send(Message::Remove(task_id.to_string()));
+    }
+
+    /// Rows that no longer parse as tasks are skipped with a warning
+    fn load(&self) -> Result<Vec<Task>, SystemError> {
+        self.flush();
+        let connection = self.connection.lock();
+        let read = || -> rusqlite::Result<Vec<(String, String)>> {
+            let mut statement = connection.prepare("SELECT id, task FROM tasks")?;
+            let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect()
+        };
+        let rows = read().map_err(|e| SystemError::Store {
+            message: format!("Cannot read stored tasks: {}", e),
+        })?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|(id, json)| match serde_json::from_str(&json) {
+                Ok(task) => Some(task),
+                Err(e) => {
+                    warn!("Skipping stored task {}: {}", id, e);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    fn get(&self, task_id: &str) -> Result<Option<Task>, SystemError> {
+        self.flush();
+        let connection = self.connection.lock();
+        let json: Option<String> = connection
+            .query_row("SELECT task FROM tasks WHERE id = ?1", params![task_id], |row| row.get(0))
+            .optional()
+            .map_err(|e| SystemError::Store {
+                message: format!("Cannot read stored task {}: {}", task_id, e),
+            })?;
+        Ok(json.and_then(|json| match serde_json::from_str(&json) {
+            Ok(task) => Some(task),
+            Err(e) => {
+                warn!("Skipping stored task {}: {}", task_id, e);
+                None
+            }
+        }))
+    }
+
+    fn flush(&self) {
+        let (done, written) = mpsc::channel();
+        if let Some(sender) = self.sender.lock().as_ref() {
+            if sender.send(Message::Flush(done)).is_ok() {
+                let _ = written.recv();
+            }
+        }
+    }
+}
+
+/// Apply changes as they arrive, everything already queued in one transaction
+fn write_changes(mut connection: Connection, receiver: mpsc::Receiver<Message>) {
+    while let Ok(first) = receiver.recv() {
+        let mut changes = Vec::new();
+        let mut flushes = Vec::new();
+        for message in std::iter::once(first).chain(receiver.try_iter()) {
+            match message {
+                Message::Flush(done) => flushes.push(done),
+                change => changes.push(change),
+            }
+        }
+        if !changes.is_empty() {
+            if let Err(e) = apply(&mut connection, &changes) {
+                warn!("Failed to store {} task changes: {}", changes.len(), e);
+            }
+        }
+        for done in flushes {
+            let _ = done.send(());
+        }
+    }
+}
+
+fn apply(connection: &mut Connection, changes: &[Message]) -> rusqlite::Result<()> {
+    let transaction = connection.transaction()?;
+    {
+        let mut save = transaction.prepare_cached(
+            "INSERT OR REPLACE INTO tasks (id, status, task, updated_at) VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        let mut remove = transaction.prepare_cached("DELETE FROM tasks WHERE id = ?1")?;
+        for change in changes {
+            match change {
+                Message::Save(task) => {
+                    let json = match serde_json::to_string(task) {
+                        Ok(json) => json,
+                        Err(e) => {
+                            warn!("Cannot store task {}: {}", task.id, e);
+                            continue;
+                        }
+                    };
+                    save.execute(params![task.id, task.status.to_string(), json, chrono::Utc::now().to_rfc3339()])?;
+                }
+                Message::Remove(id) => {
+                    remove.execute(params![id])?;
+                }
+                Message::Flush(_) => {}
+            }
+        }
+    }
+    transaction.commit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Operation, TaskData, TaskPriority, TaskStatus};
+
+    #[test]
+    fn test_save_load_remove() {
+        let dir = std::env::temp_dir().join(format!("task-store-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("tasks.db");
+
+        let store = SqliteTaskStore::open(&path).unwrap();
+        let pending = Task::new("Pending".to_string(), TaskPriority::High, TaskData::new(5, Operation::Factorial));
+        let mut processed = Task::new("Processed".to_string(), TaskPriority::Low, TaskData::new(10, Operation::Fibonacci));
+        store.save(&pending);
+        store.save(&processed);
+        processed.set_processing("55".to_string());
+        store.save(&processed);
+
+        let mut loaded = store.load().unwrap();
+        loaded.sort_by_key(|task| task.title.clone());
+        assert_eq!(loaded.len(), 2);
+        assert_eq!((loaded[0].id.as_str(), loaded[0].status.clone()), (pending.id.as_str(), TaskStatus::Pending));
+        assert_eq!(loaded[1].status, TaskStatus::Processing);
+        assert_eq!(loaded[1].result.as_deref(), Some("55"));
+
+        assert_eq!(store.get(&processed.id).unwrap().map(|task| task.status), Some(TaskStatus::Processing));
+        assert!(store.get("missing").unwrap().is_none());
+
+        store.remove(&pending.id);
+        store.close();
+        // Closed stores drop changes
+        store.remove(&processed.id);
+
+        // Reopening finds what was written before the close
+        let store = SqliteTaskStore::open(&path).unwrap();
+        let loaded = store.load().unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].id, processed.id);
+        store.close();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
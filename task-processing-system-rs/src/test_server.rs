@@ -0,0 +1,113 @@
+//! Ephemeral in-process server for tests and examples
+//!
+//! [`TestServer`] runs a whole orchestrator on a free local port, so a test
+//! (or an example) needs no `cargo run` server up:
+//!
+//! ```rust,no_run
+//! use task_processing_system_rs::TestServer;
+//!
+//! # async fn run() -> Result<(), task_processing_system_rs::SystemError> {
+//! let server = TestServer::start().await?;
+//! let health = reqwest::get(server.url("/health")).await;
+//! server.stop().await;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::orchestrator::TaskOrchestrator;
+use crate::types::{OrchestratorConfig, SystemError};
+use std::net::SocketAddr;
+
+/// An orchestrator listening on a free local port until [`stop`](Self::stop)ped
+pub struct TestServer {
+    orchestrator: TaskOrchestrator,
+    addr: SocketAddr,
+    base_url: String,
+}
+
+impl TestServer {
+    /// Start a server with the default configuration
+    pub async fn start() -> Result<Self, SystemError> {
+        Self::start_with(OrchestratorConfig::default()).await
+    }
+
+    /// Start a server with `config`; it binds any free port instead of the configured one
+    ///
+    /// Resolves once the listener accepts connections.
+    pub async fn start_with(mut config: OrchestratorConfig) -> Result<Self, SystemError> {
+        config.orchestrator_port = 0;
+        let scheme = config.url_scheme();
+        let orchestrator = TaskOrchestrator::new(config)?;
+        orchestrator.start().await?;
+        let addr = orchestrator.bound_addr().ok_or_else(|| SystemError::Orchestrator {
+            message: "Test server started without a bound address".to_string(),
+        })?;
+        Ok(Self {
+            orchestrator,
+            addr,
+            base_url: format!("{}://{}", scheme, addr),
+        })
+    }
+
+    /// Address of the main listener, with the port chosen
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// `http://` or `https://` and the address, without a trailing '/'
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Absolute URL for `path` (which starts with '/')
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// Bound addresses of the main listener and each configured `listeners` entry
+    pub fn addrs(&self) -> Vec<SocketAddr> {
+        self.orchestrator.bound_addrs()
+    }
+
+    /// The running orchestrator, for calls that bypass HTTP
+    pub fn orchestrator(&self) -> &TaskOrchestrator {
+        &self.orchestrator
+    }
+
+    /// Stop the orchestrator, closing every listener
+    pub async fn stop(self) {
+        self.orchestrator.stop().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_servers_get_their_own_ports() {
+        let first = TestServer::start().await.unwrap();
+        let second = TestServer::start().await.unwrap();
+        assert_ne!(first.addr().port(), 0);
+        assert_ne!(first.addr(), second.addr());
+        assert_eq!(first.url("/health"), format!("http://{}/health", first.addr()));
+
+        let health = reqwest::get(first.url("/health")).await.unwrap();
+        assert!(health.status().is_success());
+        assert!(first.orchestrator().is_ready());
+
+        let addr = first.addr();
+        first.stop().await;
+        second.stop().await;
+        assert!(reqwest::get(format!("http://{}/health", addr)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_config_fails_start() {
+        let config = OrchestratorConfig {
+            num_workers: 0,
+            ..OrchestratorConfig::default()
+        };
+        assert!(TestServer::start_with(config).await.is_err());
+    }
+}
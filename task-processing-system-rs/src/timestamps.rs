@@ -0,0 +1,227 @@
+//! Configurable serialization of task timestamps
+//!
+//! `created_at`/`completed_at` are written as RFC 3339 unless serialized
+//! inside [`with_format`]. Each orchestrator keeps its configured
+//! `timestamp_format` (and `result_format`) in a [`WireFormat`], which its
+//! replies, webhooks, callbacks and live events are serialized with; a
+//! request's `x-timestamp-format` header overrides the timestamp part for that
+//! reply. Deserialization accepts either format.
+
+use crate::results;
+use crate::types::{ResultFormat, TimestampFormat};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::de::{self, Deserializer, Visitor};
+use serde::Serializer;
+use std::cell::Cell;
+use std::fmt;
+
+thread_local! {
+    static FORMAT_OVERRIDE: Cell<Option<TimestampFormat>> = const { Cell::new(None) };
+}
+
+/// How an orchestrator writes task timestamps and results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WireFormat {
+    pub timestamps: TimestampFormat,
+    pub results: ResultFormat,
+}
+
+impl WireFormat {
+    /// The formats `timestamp_format` and `result_format` configure
+    pub fn new(timestamps: TimestampFormat, results: ResultFormat) -> Self {
+        Self { timestamps, results }
+    }
+
+    /// The same, with timestamps in `requested` when given
+    pub fn with_timestamps(self, requested: Option<TimestampFormat>) -> Self {
+        Self {
+            timestamps: requested.unwrap_or(self.timestamps),
+            ..self
+        }
+    }
+
+    /// Run `f` (typically a synchronous serialization) with both formats in effect
+    pub fn apply<R>(&self, f: impl FnOnce() -> R) -> R {
+        results::with_format(self.results, || with_format(Some(self.timestamps), f))
+    }
+}
+
+/// Format that serialization on this thread will use right now
+pub fn current_format() -> TimestampFormat {
+    FORMAT_OVERRIDE.with(|cell| cell.get()).unwrap_or_default()
+}
+
+/// Run `f` (typically a synchronous serialization) with `format` in effect
+///
+/// `None` keeps whatever format is in effect already.
+pub fn with_format<R>(format: Option<TimestampFormat>, f: impl FnOnce() -> R) -> R {
+    let previous = FORMAT_OVERRIDE.with(|cell| cell.replace(format.or(cell.get())));
+    let result = f();
+    FORMAT_OVERRIDE.with(|cell| cell.set(previous));
+    result
+}
+
+fn serialize_in_current_format<S: Serializer>(
+    value: &DateTime<Utc>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match current_format() {
+        TimestampFormat::Rfc3339 => serializer.collect_str(&value.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true)),
+        TimestampFormat::EpochMillis => serializer.serialize_i64(value.timestamp_millis()),
+    }
+}
+
+/// Accepts an RFC 3339 string or integer epoch milliseconds
+struct TimestampVisitor;
+
+impl<'de> Visitor<'de> for TimestampVisitor {
+    type Value = DateTime<Utc>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an RFC 3339 timestamp or epoch milliseconds")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        DateTime::parse_from_rfc3339(value)
+            .map(|dt| dt.with_timezone(&Utc))
+            .map_err(E::custom)
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        Utc.timestamp_millis_opt(value)
+            .single()
+            .ok_or_else(|| E::custom(format!("timestamp {} out of range", value)))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        let value = i64::try_from(value).map_err(E::custom)?;
+        self.visit_i64(value)
+    }
+}
+
+/// `#[serde(with = "...")]` helpers for `DateTime<Utc>`
+pub mod timestamp {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_in_current_format(value, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+        deserializer.deserialize_any(TimestampVisitor)
+    }
+}
+
+/// `#[serde(with = "...")]` helpers for `Option<DateTime<Utc>>`
+pub mod optional_timestamp {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serialize_in_current_format(value, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error> {
+        struct OptionalVisitor;
+
+        impl<'de> Visitor<'de> for OptionalVisitor {
+            type Value = Option<DateTime<Utc>>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an optional timestamp")
+            }
+
+            fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+                Ok(None)
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+                Ok(None)
+            }
+
+            fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+                timestamp::deserialize(deserializer).map(Some)
+            }
+        }
+
+        deserializer.deserialize_option(OptionalVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Operation, Task, TaskData, TaskPriority};
+
+    fn sample_task() -> Task {
+        let mut task = Task::new(
+            "Timestamps".to_string(),
+            TaskPriority::Low,
+            TaskData::new(5, Operation::Factorial),
+        );
+        task.created_at = Utc.timestamp_millis_opt(1_705_314_600_123).unwrap();
+        task.completed_at = Some(Utc.timestamp_millis_opt(1_705_314_601_000).unwrap());
+        task
+    }
+
+    #[test]
+    fn test_rfc3339_round_trip() {
+        let task = sample_task();
+        let json = with_format(Some(TimestampFormat::Rfc3339), || serde_json::to_value(&task).unwrap());
+        assert_eq!(json["created_at"], "2024-01-15T10:30:00.123Z");
+
+        let parsed: Task = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.created_at, task.created_at);
+        assert_eq!(parsed.completed_at, task.completed_at);
+    }
+
+    #[test]
+    fn test_epoch_millis_round_trip() {
+        let task = sample_task();
+        let json = with_format(Some(TimestampFormat::EpochMillis), || serde_json::to_value(&task).unwrap());
+        assert_eq!(json["created_at"], 1_705_314_600_123i64);
+        assert_eq!(json["completed_at"], 1_705_314_601_000i64);
+
+        let parsed: Task = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.created_at, task.created_at);
+        assert_eq!(parsed.completed_at, task.completed_at);
+    }
+
+    #[test]
+    fn test_override_is_scoped() {
+        with_format(Some(TimestampFormat::EpochMillis), || {
+            assert_eq!(current_format(), TimestampFormat::EpochMillis);
+            with_format(None, || assert_eq!(current_format(), TimestampFormat::EpochMillis));
+        });
+        assert_eq!(FORMAT_OVERRIDE.with(|cell| cell.get()), None);
+    }
+
+    #[test]
+    fn test_wire_format() {
+        let task = sample_task();
+        let format = WireFormat::new(TimestampFormat::EpochMillis, ResultFormat::Typed);
+        let mut processed = task.clone();
+        processed.result = Some("120".to_string());
+        let json = format.apply(|| serde_json::to_value(&processed).unwrap());
+        assert_eq!(json["created_at"], 1_705_314_600_123i64);
+        assert_eq!(json["result"]["type"], "integer");
+
+        // A requested format only replaces the timestamps
+        let json = format.with_timestamps(Some(TimestampFormat::Rfc3339)).apply(|| serde_json::to_value(&processed).unwrap());
+        assert_eq!(json["created_at"], "2024-01-15T10:30:00.123Z");
+        assert_eq!(json["result"]["type"], "integer");
+        assert_eq!(format.with_timestamps(None), format);
+
+        // Outside of one, the defaults
+        assert_eq!(serde_json::to_value(&task).unwrap()["created_at"], "2024-01-15T10:30:00.123Z");
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!("epoch-millis".parse::<TimestampFormat>(), Ok(TimestampFormat::EpochMillis));
+        assert_eq!("RFC3339".parse::<TimestampFormat>(), Ok(TimestampFormat::Rfc3339));
+        assert!("unix".parse::<TimestampFormat>().is_err());
+    }
+}
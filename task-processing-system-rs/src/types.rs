@@ -1,7 +1,15 @@
 #![allow(warnings)]
+use crate::calculations::Calculator;
+use crate::dependencies::MAX_DEPENDENCIES;
+use crate::latency::LatencySummary;
+use crate::query::{ListParams, ListSpec, QueryError, Sort};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::PathBuf;
+use std::time::Duration;
 use uuid::Uuid;
 
 /// Task priority levels
@@ -47,10 +55,13 @@ impl From<TaskPriority> for u8 {
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum TaskStatus {
+    Scheduled,  // Task held by the orchestrator until its run_at time
+    Blocked,    // Task held by the orchestrator until its dependencies complete
     Pending,    // Task created, waiting to be processed
     Processing, // Task calculation completed, awaiting API completion
     Completed,  // Task marked complete via API call
     Failed,     // Task processing failed
+    Cancelled,  // Task cancelled via API call before it was completed
 }
 
 impl Default for TaskStatus {
@@ -59,6 +70,62 @@ impl Default for TaskStatus {
     }
 }
 
+impl fmt::Display for TaskStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            TaskStatus::Scheduled => "scheduled",
+            TaskStatus::Blocked => "blocked",
+            TaskStatus::Pending => "pending",
+            TaskStatus::Processing => "processing",
+            TaskStatus::Completed => "completed",
+            TaskStatus::Failed => "failed",
+            TaskStatus::Cancelled => "cancelled",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl TaskStatus {
+    /// Cache-Control header value for a task in this state
+    ///
+    /// Completed and cancelled tasks don't change, but may still be deleted,
+    /// expire or be evicted, so the caller's own cache may keep them only
+    /// briefly. Shared caches may not keep them, as replies depend on the
+    /// caller's API key.
+    pub fn cache_control(&self) -> &'static str {
+        match self {
+            TaskStatus::Scheduled | TaskStatus::Blocked | TaskStatus::Pending | TaskStatus::Processing => "no-store",
+            TaskStatus::Completed | TaskStatus::Cancelled => "private, max-age=60",
+            TaskStatus::Failed => "no-cache",
+        }
+    }
+
+    /// Completed, failed or cancelled: the task will not change again on its own
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, TaskStatus::Completed | TaskStatus::Failed | TaskStatus::Cancelled)
+    }
+}
+
+impl std::str::FromStr for TaskStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "scheduled" => Ok(TaskStatus::Scheduled),
+            "blocked" => Ok(TaskStatus::Blocked),
+            "pending" => Ok(TaskStatus::Pending),
+            "processing" => Ok(TaskStatus::Processing),
+            "completed" => Ok(TaskStatus::Completed),
+            "failed" => Ok(TaskStatus::Failed),
+            "cancelled" => Ok(TaskStatus::Cancelled),
+            other => Err(format!(
+                "unknown status '{}', expected scheduled, blocked, pending, processing, completed, failed or cancelled",
+                other
+            )),
+        }
+    }
+}
+
 /// Mathematical operations supported by the system
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Operation {
@@ -68,6 +135,54 @@ pub enum Operation {
     Fibonacci,
     #[serde(rename = "prime_check")]
     PrimeCheck,
+    #[serde(rename = "generate_prime")]
+    GeneratePrime,
+    /// Greatest common divisor of `input` and `input2`
+    #[serde(rename = "gcd")]
+    Gcd,
+    /// Least common multiple of `input` and `input2`
+    #[serde(rename = "lcm")]
+    Lcm,
+}
+
+impl Operation {
+    /// Every operation, in declaration order
+    pub const ALL: [Operation; 6] = [
+        Operation::Factorial,
+        Operation::Fibonacci,
+        Operation::PrimeCheck,
+        Operation::GeneratePrime,
+        Operation::Gcd,
+        Operation::Lcm,
+    ];
+
+    /// Smallest and largest input accepted for this operation
+    ///
+    /// Factorial, fibonacci and prime_check are capped by the
+    /// `operation_limits` of `calculator`. For `generate_prime` the input is a
+    /// bit length, capped by its `prime_generation.max_bits`.
+    pub fn input_range(&self, calculator: &Calculator) -> (u64, u64) {
+        let limits = calculator.operation_limits();
+        match self {
+            Operation::Factorial => (0, limits.max_factorial_input),
+            Operation::Fibonacci => (0, limits.max_fibonacci_input),
+            Operation::PrimeCheck => (0, limits.max_prime_check_input),
+            Operation::GeneratePrime => (2, calculator.prime_generation().max_bits as u64),
+            Operation::Gcd | Operation::Lcm => (0, u64::MAX),
+        }
+    }
+
+    /// Whether the operation also takes `input2`
+    pub fn takes_second_input(&self) -> bool {
+        matches!(self, Operation::Gcd | Operation::Lcm)
+    }
+}
+
+impl Operation {
+    /// Whether `precision: "big"` changes how this operation is calculated
+    pub fn supports_big_precision(&self) -> bool {
+        matches!(self, Operation::Factorial | Operation::Fibonacci)
+    }
 }
 
 impl fmt::Display for Operation {
@@ -76,18 +191,218 @@ impl fmt::Display for Operation {
             Operation::Factorial => "factorial",
             Operation::Fibonacci => "fibonacci",
             Operation::PrimeCheck => "prime_check",
+            Operation::GeneratePrime => "generate_prime",
+            Operation::Gcd => "gcd",
+            Operation::Lcm => "lcm",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Integer arithmetic for factorial and fibonacci
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Precision {
+    /// Fixed-width integers: factorial up to 20, fibonacci up to 93
+    #[default]
+    Native,
+    /// Arbitrary precision, for results of up to `big_precision.max_digits` digits
+    Big,
+}
+
+impl Precision {
+    pub fn is_native(&self) -> bool {
+        *self == Precision::Native
+    }
+}
+
+/// Algorithms available for the fibonacci operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FibonacciAlgorithm {
+    Iterative,
+    FastDoubling,
+    Matrix,
+}
+
+impl Default for FibonacciAlgorithm {
+    fn default() -> Self {
+        FibonacciAlgorithm::Iterative
+    }
+}
+
+impl fmt::Display for FibonacciAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            FibonacciAlgorithm::Iterative => "iterative",
+            FibonacciAlgorithm::FastDoubling => "fast_doubling",
+            FibonacciAlgorithm::Matrix => "matrix",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Algorithms available for the prime_check operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrimeCheckAlgorithm {
+    /// Deterministic for every u64, microseconds even near `u64::MAX`
+    #[default]
+    MillerRabin,
+    /// Odd divisors up to the square root, kept for comparison benchmarks
+    TrialDivision,
+}
+
+impl fmt::Display for PrimeCheckAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            PrimeCheckAlgorithm::MillerRabin => "miller_rabin",
+            PrimeCheckAlgorithm::TrialDivision => "trial_division",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// How `created_at`/`completed_at` are written in responses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampFormat {
+    /// `"2024-01-15T10:30:00Z"`
+    Rfc3339,
+    /// Milliseconds since the Unix epoch, as a number
+    EpochMillis,
+}
+
+impl Default for TimestampFormat {
+    fn default() -> Self {
+        TimestampFormat::Rfc3339
+    }
+}
+
+impl fmt::Display for TimestampFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            TimestampFormat::Rfc3339 => "rfc3339",
+            TimestampFormat::EpochMillis => "epoch_millis",
         };
         write!(f, "{}", s)
     }
 }
 
+impl std::str::FromStr for TimestampFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "rfc3339" => Ok(TimestampFormat::Rfc3339),
+            "epoch_millis" => Ok(TimestampFormat::EpochMillis),
+            other => Err(format!("unknown timestamp format '{}', expected rfc3339 or epoch_millis", other)),
+        }
+    }
+}
+
+/// How `result` fields are written in responses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultFormat {
+    /// The bare result string, e.g. `"120"` or `"true"`
+    #[default]
+    Plain,
+    /// A tagged [`ResultValue`](crate::results::ResultValue), e.g. `{"type": "integer", "value": "120"}`
+    Typed,
+}
+
+/// Where workers run calculations, keeping them off the async runtime's threads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CalculationExecutor {
+    /// On the runtime thread of the processing task (a long calculation stalls HTTP handling)
+    Inline,
+    /// On tokio's blocking thread pool
+    #[default]
+    Blocking,
+    /// On `threads_per_worker` OS threads owned by each worker
+    Dedicated,
+}
+
+/// How the orchestrator picks the worker for each new task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadBalancingStrategy {
+    /// Each worker in turn
+    #[default]
+    RoundRobin,
+    /// The worker with the fewest queued and running tasks
+    LeastLoaded,
+    /// In turn, in proportion to `worker_weights`
+    WeightedRoundRobin,
+    /// A worker chosen at random
+    Random,
+}
+
 /// Task data payload containing calculation parameters
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskData {
     #[serde(rename = "type")]
     pub task_type: String, // Always "calculation" for our use case
+    /// Ignored when `inputs` is given
+    #[serde(default)]
     pub input: u64,
+    /// Calculate the operation for each of these instead of `input`; the
+    /// result is a JSON array with one result per input
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inputs: Vec<u64>,
+    /// Spread `inputs` over several threads
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub parallel: bool,
+    /// Second operand of `gcd` and `lcm`, wherever they appear in the pipeline
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input2: Option<u64>,
     pub operation: Operation,
+    /// Fibonacci algorithm override (falls back to the configured default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub algorithm: Option<FibonacciAlgorithm>,
+    /// Prime check algorithm override (falls back to the configured default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prime_check_algorithm: Option<PrimeCheckAlgorithm>,
+    /// Further operations applied in order, each taking the previous result as input
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pipeline: Vec<Operation>,
+    /// Arithmetic used for factorial and fibonacci stages
+    #[serde(default, skip_serializing_if = "Precision::is_native")]
+    pub precision: Precision,
+    /// Times a failed calculation is queued again before the task fails
+    #[serde(default, skip_serializing_if = "is_zero_u32")]
+    pub max_retries: u32,
+    /// Wait before the first retry; doubled for every further one
+    #[serde(default = "default_retry_backoff_ms", skip_serializing_if = "is_default_retry_backoff")]
+    pub retry_backoff_ms: u64,
+}
+
+/// Maximum number of extra pipeline stages per task
+pub const MAX_PIPELINE_STAGES: usize = 10;
+
+/// Most `inputs` one task may have
+pub const MAX_TASK_INPUTS: usize = 1000;
+
+/// Most retries a task may ask for
+pub const MAX_TASK_RETRIES: u32 = 10;
+
+/// Longest wait before a retry, however many attempts failed
+pub const MAX_RETRY_BACKOFF_MS: u64 = 60_000;
+
+const DEFAULT_RETRY_BACKOFF_MS: u64 = 500;
+
+fn default_retry_backoff_ms() -> u64 {
+    DEFAULT_RETRY_BACKOFF_MS
+}
+
+fn is_default_retry_backoff(backoff_ms: &u64) -> bool {
+    *backoff_ms == DEFAULT_RETRY_BACKOFF_MS
+}
+
+fn is_zero_u32(value: &u32) -> bool {
+    *value == 0
 }
 
 impl TaskData {
@@ -95,65 +410,287 @@ impl TaskData {
         Self {
             task_type: "calculation".to_string(),
             input,
+            inputs: Vec::new(),
+            parallel: false,
+            input2: None,
             operation,
+            algorithm: None,
+            prime_check_algorithm: None,
+            pipeline: Vec::new(),
+            precision: Precision::Native,
+            max_retries: 0,
+            retry_backoff_ms: DEFAULT_RETRY_BACKOFF_MS,
         }
     }
 
-    /// Validate task data input constraints
-    pub fn validate(&self) -> Result<(), ValidationError> {
+    /// Calculate `operation` for each of `inputs`, one result per input
+    pub fn batch(inputs: Vec<u64>, operation: Operation) -> Self {
+        Self {
+            inputs,
+            ..Self::new(0, operation)
+        }
+    }
+
+    /// Calculate `inputs` on several threads at once
+    pub fn in_parallel(mut self) -> Self {
+        self.parallel = true;
+        self
+    }
+
+    /// Inputs the operation runs on: `inputs`, or just `input` without them
+    pub fn all_inputs(&self) -> &[u64] {
+        if self.inputs.is_empty() {
+            std::slice::from_ref(&self.input)
+        } else {
+            &self.inputs
+        }
+    }
+
+    /// Second operand for `gcd` and `lcm` stages
+    pub fn with_input2(mut self, input2: u64) -> Self {
+        self.input2 = Some(input2);
+        self
+    }
+
+    /// Queue the calculation again up to `max_retries` times if it fails,
+    /// waiting `backoff_ms` before the first retry
+    pub fn with_retries(mut self, max_retries: u32, backoff_ms: u64) -> Self {
+        self.max_retries = max_retries;
+        self.retry_backoff_ms = backoff_ms;
+        self
+    }
+
+    /// Wait before retrying after the `attempt`th (1-based) failed attempt
+    pub fn retry_backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+        Duration::from_millis(self.retry_backoff_ms.saturating_mul(factor).min(MAX_RETRY_BACKOFF_MS))
+    }
+
+    /// Calculate factorial and fibonacci stages with `precision`
+    pub fn with_precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Append further operations to run on the result of this one
+    pub fn with_pipeline(mut self, pipeline: Vec<Operation>) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    /// All operations in execution order (the primary operation first)
+    pub fn stages(&self) -> impl Iterator<Item = &Operation> {
+        std::iter::once(&self.operation).chain(self.pipeline.iter())
+    }
+
+    /// Select a specific fibonacci algorithm for this task
+    pub fn with_algorithm(mut self, algorithm: FibonacciAlgorithm) -> Self {
+        self.algorithm = Some(algorithm);
+        self
+    }
+
+    /// Select a specific primality test for this task
+    pub fn with_prime_check_algorithm(mut self, algorithm: PrimeCheckAlgorithm) -> Self {
+        self.prime_check_algorithm = Some(algorithm);
+        self
+    }
+
+    /// Validate task data input constraints, inputs against the limits of `calculator`
+    pub fn validate(&self, calculator: &Calculator) -> Result<(), ValidationError> {
         if self.task_type != "calculation" {
             return Err(ValidationError::InvalidTaskType(self.task_type.clone()));
         }
 
-        match self.operation {
-            Operation::Factorial => {
-                if self.input > 20 {
-                    return Err(ValidationError::InputTooLarge {
-                        operation: self.operation.clone(),
-                        input: self.input,
-                        max_allowed: 20,
-                    });
-                }
+        if self.algorithm.is_some() && !self.stages().any(|op| *op == Operation::Fibonacci) {
+            return Err(ValidationError::AlgorithmNotSupported(self.operation.clone()));
+        }
+
+        if self.prime_check_algorithm.is_some() && !self.stages().any(|op| *op == Operation::PrimeCheck) {
+            return Err(ValidationError::AlgorithmNotSupported(self.operation.clone()));
+        }
+
+        match self.stages().find(|op| op.takes_second_input()) {
+            Some(operation) if self.input2.is_none() => {
+                return Err(ValidationError::MissingSecondInput(operation.clone()));
             }
-            Operation::Fibonacci => {
-                if self.input > 93 {
-                    return Err(ValidationError::InputTooLarge {
-                        operation: self.operation.clone(),
-                        input: self.input,
-                        max_allowed: 93,
-                    });
-                }
+            None if self.input2.is_some() => {
+                return Err(ValidationError::UnexpectedSecondInput(self.operation.clone()));
             }
-            Operation::PrimeCheck => {
-                if self.input > u64::MAX / 2 {
-                    return Err(ValidationError::InputTooLarge {
-                        operation: self.operation.clone(),
-                        input: self.input,
-                        max_allowed: u64::MAX / 2,
-                    });
-                }
+            _ => {}
+        }
+
+        if self.precision == Precision::Big && !self.stages().any(Operation::supports_big_precision) {
+            return Err(ValidationError::PrecisionNotSupported(self.operation.clone()));
+        }
+
+        if self.pipeline.len() > MAX_PIPELINE_STAGES {
+            return Err(ValidationError::PipelineTooLong {
+                stages: self.pipeline.len(),
+                max_allowed: MAX_PIPELINE_STAGES,
+            });
+        }
+
+        if self.inputs.len() > MAX_TASK_INPUTS {
+            return Err(ValidationError::TooManyInputs {
+                inputs: self.inputs.len(),
+                max_allowed: MAX_TASK_INPUTS,
+            });
+        }
+        if !self.inputs.is_empty() && !self.pipeline.is_empty() {
+            return Err(ValidationError::InputsWithPipeline);
+        }
+        if self.parallel && self.inputs.is_empty() {
+            return Err(ValidationError::ParallelWithoutInputs);
+        }
+
+        if self.max_retries > MAX_TASK_RETRIES {
+            return Err(ValidationError::TooManyRetries {
+                retries: self.max_retries,
+                max_allowed: MAX_TASK_RETRIES,
+            });
+        }
+        if self.retry_backoff_ms > MAX_RETRY_BACKOFF_MS {
+            return Err(ValidationError::RetryBackoffTooLong {
+                backoff_ms: self.retry_backoff_ms,
+                max_ms: MAX_RETRY_BACKOFF_MS,
+            });
+        }
+
+        self.all_inputs().iter().try_for_each(|input| self.validate_input(*input, calculator))
+    }
+
+    /// Check one input against the operation's range
+    fn validate_input(&self, input: u64, calculator: &Calculator) -> Result<(), ValidationError> {
+        if self.precision == Precision::Big && self.operation.supports_big_precision() {
+            let limits = calculator.big_precision_limits();
+            if input > limits.max_input(&self.operation) {
+                return Err(ValidationError::TooManyDigits {
+                    operation: self.operation.clone(),
+                    input,
+                    max_digits: limits.max_digits,
+                });
             }
+            return Ok(());
+        }
+
+        let (min_allowed, max_allowed) = self.operation.input_range(calculator);
+        if input < min_allowed {
+            return Err(ValidationError::InputTooSmall {
+                operation: self.operation.clone(),
+                input,
+                min_allowed,
+            });
+        }
+        if input > max_allowed {
+            return Err(ValidationError::InputTooLarge {
+                operation: self.operation.clone(),
+                input,
+                max_allowed,
+            });
         }
 
         Ok(())
     }
 }
 
+/// Namespace of tasks submitted without one
+pub const DEFAULT_NAMESPACE: &str = "default";
+
+fn default_namespace() -> String {
+    DEFAULT_NAMESPACE.to_string()
+}
+
+/// Namespaces are 1-64 characters of lowercase letters, digits, `-` and `_`
+pub fn validate_namespace(namespace: &str) -> Result<(), ValidationError> {
+    let valid = !namespace.is_empty()
+        && namespace.len() <= 64
+        && namespace
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(ValidationError::InvalidNamespace(namespace.to_string()))
+    }
+}
+
 /// Main Task structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
     pub id: String,
     pub title: String,
     pub priority: TaskPriority,
+    /// Tenant the task belongs to; API keys only reach their own namespaces
+    #[serde(default = "default_namespace")]
+    pub namespace: String,
+    #[serde(with = "crate::timestamps::timestamp")]
     pub created_at: DateTime<Utc>,
     pub data: TaskData,
     pub status: TaskStatus,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, with = "crate::results::optional_result", skip_serializing_if = "Option::is_none")]
     pub result: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error_message: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, with = "crate::timestamps::optional_timestamp", skip_serializing_if = "Option::is_none")]
     pub completed_at: Option<DateTime<Utc>>,
+    /// Algorithm that produced the result (fibonacci only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub algorithm: Option<FibonacciAlgorithm>,
+    /// Per-stage results for pipeline tasks
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stages: Vec<StageResult>,
+    /// Result the submitter expects, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_result: Option<String>,
+    /// URL the task is POSTed to when it's calculated, completed or fails
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<String>,
+    /// When a scheduled task is (or was) due to be handed to a worker
+    #[serde(default, with = "crate::timestamps::optional_timestamp", skip_serializing_if = "Option::is_none")]
+    pub run_at: Option<DateTime<Utc>>,
+    /// Tasks that must be completed before this one is queued
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// Submitter's key/value tags, returned as given (`GET /tasks?label=key:value` filters on them)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    /// Milliseconds one calculation attempt may take before the task fails
+    /// with [`TaskError::TimedOut`] (`default_task_timeout_ms` when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// Calculation attempts so far, retries included
+    #[serde(default, skip_serializing_if = "is_zero_u32")]
+    pub attempts: u32,
+    /// Whether the result matched `expected_result` (set after processing)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matched: Option<bool>,
+    /// Hex HMAC-SHA256 of the result, when result signing is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_signature: Option<String>,
+    /// 1-based place in the owning worker's queue (pending tasks, filled on read)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue_position: Option<usize>,
+    /// Worker that took the task off its queue
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worker_id: Option<usize>,
+    /// When the latest calculation attempt started
+    #[serde(default, with = "crate::timestamps::optional_timestamp", skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime<Utc>>,
+    /// How long the latest calculation attempt took, simulated processing included
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub processing_duration_ms: Option<u64>,
+}
+
+/// Outcome of one stage of a pipeline task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageResult {
+    pub stage: usize,
+    pub operation: Operation,
+    pub input: u64,
+    #[serde(default, with = "crate::results::optional_result", skip_serializing_if = "Option::is_none")]
+    pub result: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
 impl Task {
@@ -163,12 +700,28 @@ impl Task {
             id: Uuid::new_v4().to_string(),
             title,
             priority,
+            namespace: default_namespace(),
             created_at: Utc::now(),
             data,
             status: TaskStatus::Pending,
             result: None,
             error_message: None,
             completed_at: None,
+            algorithm: None,
+            stages: Vec::new(),
+            expected_result: None,
+            callback_url: None,
+            run_at: None,
+            depends_on: Vec::new(),
+            labels: BTreeMap::new(),
+            timeout_ms: None,
+            attempts: 0,
+            matched: None,
+            result_signature: None,
+            queue_position: None,
+            worker_id: None,
+            started_at: None,
+            processing_duration_ms: None,
         }
     }
 
@@ -183,15 +736,36 @@ impl Task {
             id,
             title,
             priority,
+            namespace: default_namespace(),
             created_at: Utc::now(),
             data,
             status: TaskStatus::Pending,
             result: None,
             error_message: None,
             completed_at: None,
+            algorithm: None,
+            stages: Vec::new(),
+            expected_result: None,
+            callback_url: None,
+            run_at: None,
+            depends_on: Vec::new(),
+            labels: BTreeMap::new(),
+            timeout_ms: None,
+            attempts: 0,
+            matched: None,
+            result_signature: None,
+            queue_position: None,
+            worker_id: None,
+            started_at: None,
+            processing_duration_ms: None,
         }
     }
 
+    /// Canonical API path of this task, `/task/{id}`
+    pub fn url(&self) -> String {
+        format!("/task/{}", self.id)
+    }
+
     /// Mark task as processing with result
     pub fn set_processing(&mut self, result: String) {
         self.status = TaskStatus::Processing;
@@ -211,31 +785,107 @@ impl Task {
         Ok(())
     }
 
+    /// Mark a pending or calculated task as cancelled (via API call)
+    pub fn set_cancelled(&mut self) -> Result<(), TaskError> {
+        if self.status.is_terminal() {
+            return Err(TaskError::InvalidStatusTransition {
+                current: self.status.clone(),
+                requested: TaskStatus::Cancelled,
+            });
+        }
+        self.status = TaskStatus::Cancelled;
+        Ok(())
+    }
+
     /// Mark task as failed
     pub fn set_failed(&mut self, error_message: String) {
         self.status = TaskStatus::Failed;
         self.error_message = Some(error_message);
     }
 
+    /// Compare the result against `expected_result` and record the outcome
+    ///
+    /// A failed task never matches. Returns `None` when nothing was expected.
+    pub fn check_expected_result(&mut self) -> Option<bool> {
+        let expected = self.expected_result.as_ref()?;
+        let matched = self.status != TaskStatus::Failed && self.result.as_ref() == Some(expected);
+        self.matched = Some(matched);
+        Some(matched)
+    }
+
     /// Get task age in seconds
     pub fn age_seconds(&self) -> i64 {
         (Utc::now() - self.created_at).num_seconds()
     }
 
-    /// Validate task data
-    pub fn validate(&self) -> Result<(), ValidationError> {
+    /// Validate task data, inputs against the limits of `calculator`
+    pub fn validate(&self, calculator: &Calculator) -> Result<(), ValidationError> {
         if self.id.is_empty() {
             return Err(ValidationError::EmptyTaskId);
         }
         if self.title.is_empty() {
             return Err(ValidationError::EmptyTitle);
         }
-        self.data.validate()
+        validate_namespace(&self.namespace)?;
+        if self.depends_on.len() > MAX_DEPENDENCIES {
+            return Err(ValidationError::TooManyDependencies { max: MAX_DEPENDENCIES });
+        }
+        for (index, dependency) in self.depends_on.iter().enumerate() {
+            if dependency.is_empty() || *dependency == self.id || self.depends_on[..index].contains(dependency) {
+                return Err(ValidationError::InvalidDependency(dependency.clone()));
+            }
+        }
+        if let Some(url) = &self.callback_url {
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                return Err(ValidationError::InvalidCallbackUrl(url.clone()));
+            }
+        }
+        validate_labels(&self.labels)?;
+        if self.timeout_ms == Some(0) {
+            return Err(ValidationError::ZeroTaskTimeout);
+        }
+        self.data.validate(calculator)
+    }
+
+    /// Whether the task carries every `key: value` pair of `selector`
+    pub fn has_labels(&self, selector: &[(String, String)]) -> bool {
+        selector.iter().all(|(key, value)| self.labels.get(key) == Some(value))
     }
 }
 
+/// Most labels one task may carry
+pub const MAX_TASK_LABELS: usize = 20;
+
+/// Label keys are 1-63 characters of letters, digits, `-`, `_`, `.` and `/`;
+/// values at most 255 characters without `,` (which separates `label` filters)
+pub fn validate_labels(labels: &BTreeMap<String, String>) -> Result<(), ValidationError> {
+    if labels.len() > MAX_TASK_LABELS {
+        return Err(ValidationError::TooManyLabels { max: MAX_TASK_LABELS });
+    }
+    for (key, value) in labels {
+        let valid_key = !key.is_empty()
+            && key.len() <= 63
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'));
+        if !valid_key || value.len() > 255 || value.contains(',') {
+            return Err(ValidationError::InvalidLabel(key.clone()));
+        }
+    }
+    Ok(())
+}
+
+/// Parse a `label` filter: comma-separated `key:value` pairs, all of which must match
+pub fn parse_label_selector(selector: &str) -> Option<Vec<(String, String)>> {
+    selector
+        .split(',')
+        .map(|pair| {
+            let (key, value) = pair.split_once(':')?;
+            (!key.trim().is_empty()).then(|| (key.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
 /// Task creation request from API
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateTaskRequest {
     #[serde(default = "generate_task_id")]
     pub id: String,
@@ -243,6 +893,34 @@ pub struct CreateTaskRequest {
     #[serde(default)]
     pub priority: TaskPriority,
     pub data: TaskData,
+    /// Result the caller expects; compared after processing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_result: Option<String>,
+    /// Target namespace (defaults to the API key's first namespace, or "default")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// POSTed the task when it's calculated, completed or fails
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<String>,
+    /// Hold the task until this time (RFC 3339) before queueing it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_at: Option<DateTime<Utc>>,
+    /// Hold the task this many seconds before queueing it (instead of `run_at`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay_seconds: Option<u64>,
+    /// Repeats with the same key return the task created first (the
+    /// `Idempotency-Key` header does the same)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+    /// Hold the task until each of these tasks is completed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// Key/value tags stored on the task as given
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    /// Fail the task if a calculation attempt takes longer (server default when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
 }
 
 fn generate_task_id() -> String {
@@ -250,77 +928,1954 @@ fn generate_task_id() -> String {
 }
 
 impl CreateTaskRequest {
-    pub fn into_task(self) -> Result<Task, ValidationError> {
-        let task = Task::with_id(self.id, self.title, self.priority, self.data);
-        task.validate()?;
+    /// Request for a task queued right away under a generated ID
+    pub fn new(title: String, priority: TaskPriority, data: TaskData) -> Self {
+        Self {
+            id: generate_task_id(),
+            title,
+            priority,
+            data,
+            expected_result: None,
+            namespace: None,
+            callback_url: None,
+            run_at: None,
+            delay_seconds: None,
+            idempotency_key: None,
+            depends_on: Vec::new(),
+            labels: BTreeMap::new(),
+            timeout_ms: None,
+        }
+    }
+
+    /// The task to queue, validated against the limits of `calculator`
+    pub fn into_task(self, calculator: &Calculator) -> Result<Task, ValidationError> {
+        let mut task = Task::with_id(self.id, self.title, self.priority, self.data);
+        task.expected_result = self.expected_result;
+        task.callback_url = self.callback_url;
+        task.depends_on = self.depends_on;
+        task.labels = self.labels;
+        task.timeout_ms = self.timeout_ms;
+        if let Some(namespace) = self.namespace {
+            task.namespace = namespace;
+        }
+        task.validate(calculator)?;
+        if !task.depends_on.is_empty() {
+            if self.run_at.is_some() || self.delay_seconds.is_some() {
+                return Err(ValidationError::ScheduledWithDependencies);
+            }
+            task.status = TaskStatus::Blocked;
+            return Ok(task);
+        }
+        // A time already past just queues the task right away
+        let run_at = schedule_time(self.run_at, self.delay_seconds, task.created_at)?;
+        if let Some(run_at) = run_at.filter(|run_at| *run_at > task.created_at) {
+            task.status = TaskStatus::Scheduled;
+            task.run_at = Some(run_at);
+        }
         Ok(task)
     }
+
+    /// Whether the request asks for the task to be held before it's queued
+    pub fn is_scheduled(&self) -> bool {
+        self.run_at.is_some() || self.delay_seconds.is_some()
+    }
 }
 
-/// Task completion response
+/// Furthest ahead a task may be scheduled (7 days)
+pub const MAX_SCHEDULE_DELAY_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// When a task submitted at `now` asking for `run_at` or `delay_seconds` is due
+fn schedule_time(run_at: Option<DateTime<Utc>>, delay_seconds: Option<u64>, now: DateTime<Utc>) -> Result<Option<DateTime<Utc>>, ValidationError> {
+    let run_at = match (run_at, delay_seconds) {
+        (Some(_), Some(_)) => return Err(ValidationError::ConflictingSchedule),
+        (Some(run_at), None) => run_at,
+        (None, Some(delay)) if delay <= MAX_SCHEDULE_DELAY_SECS => now + chrono::Duration::seconds(delay as i64),
+        (None, Some(_)) => return Err(ValidationError::ScheduleTooFar { max_seconds: MAX_SCHEDULE_DELAY_SECS }),
+        (None, None) => return Ok(None),
+    };
+    if run_at > now + chrono::Duration::seconds(MAX_SCHEDULE_DELAY_SECS as i64) {
+        return Err(ValidationError::ScheduleTooFar { max_seconds: MAX_SCHEDULE_DELAY_SECS });
+    }
+    Ok(Some(run_at))
+}
+
+/// Task creation response
 #[derive(Debug, Serialize)]
-pub struct TaskCompletionResponse {
+pub struct TaskCreatedResponse {
     pub id: String,
     pub status: TaskStatus,
     pub message: String,
 }
 
-/// Worker statistics
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct WorkerStats {
-    pub id: usize,
-    pub tasks_processed: u64,
-    pub tasks_completed: u64,
-    pub tasks_failed: u64,
-    pub current_load: usize,
-    pub uptime_seconds: u64,
-    pub is_healthy: bool,
+impl TaskCreatedResponse {
+    pub fn new(id: String) -> Self {
+        Self {
+            id,
+            status: TaskStatus::Pending,
+            message: "Task created successfully".to_string(),
+        }
+    }
+
+    /// 202 body of `POST /task/execute` when the result took longer than `max_wait_ms`
+    pub fn still_processing(id: String, max_wait_ms: u64) -> Self {
+        let message = format!("Not finished within {} ms; poll GET /task/{}", max_wait_ms, id);
+        Self {
+            id,
+            status: TaskStatus::Pending,
+            message,
+        }
+    }
 }
 
-/// System-wide statistics
+/// 409 body when a submitted task's ID is already taken
 #[derive(Debug, Serialize)]
-pub struct SystemStats {
-    pub total_tasks_processed: u64,
-    pub total_tasks_completed: u64,
-    pub total_tasks_failed: u64,
-    pub total_workers: usize,
-    pub uptime_seconds: u64,
-    pub workers: Vec<WorkerStats>,
+pub struct DuplicateTaskResponse {
+    #[serde(flatten)]
+    pub problem: ErrorResponse,
+    /// The task holding the ID, left out when it's in another namespace
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub existing: Option<ExistingTask>,
 }
 
-/// Configuration structures
-#[derive(Debug, Clone, Deserialize)]
-pub struct OrchestratorConfig {
-    pub num_workers: usize,
-    pub threads_per_worker: usize,
-    pub orchestrator_port: u16,
+/// Where a conflicting task stands, so the client needn't fetch it
+#[derive(Debug, Serialize)]
+pub struct ExistingTask {
+    pub id: String,
+    pub status: TaskStatus,
+    #[serde(with = "crate::timestamps::timestamp")]
+    pub created_at: DateTime<Utc>,
+    /// Canonical `GET` path of the task
+    pub url: String,
 }
 
-impl Default for OrchestratorConfig {
-    fn default() -> Self {
+impl DuplicateTaskResponse {
+    /// Describe `existing` when the submitter may see it (`visible`)
+    pub fn new(existing: &Task, visible: bool) -> Self {
         Self {
-            num_workers: 3,
-            threads_per_worker: 4,
+            problem: ErrorResponse::new(
+                warp::http::StatusCode::CONFLICT,
+                TaskError::TaskAlreadyExists { id: existing.id.clone() }.to_string(),
+            )
+            .with_reason("duplicate_task"),
+            existing: visible.then(|| ExistingTask {
+                id: existing.id.clone(),
+                status: existing.status.clone(),
+                created_at: existing.created_at,
+                url: existing.url(),
+            }),
+        }
+    }
+}
+
+/// 429 body when the picked worker's queue is at `max_queue_size`
+#[derive(Debug, Serialize)]
+pub struct QueueFullResponse {
+    #[serde(flatten)]
+    pub problem: ErrorResponse,
+    pub worker: usize,
+    /// Tasks in the worker's queue when the submission was refused
+    pub queue_depth: usize,
+    pub max_queue_size: usize,
+}
+
+impl QueueFullResponse {
+    pub fn new(worker: usize, queue_depth: usize, max_queue_size: usize) -> Self {
+        let error = TaskError::QueueFull {
+            worker,
+            depth: queue_depth,
+            capacity: max_queue_size,
+        };
+        Self {
+            problem: ErrorResponse::new(warp::http::StatusCode::TOO_MANY_REQUESTS, error.to_string())
+                .with_reason("queue_full"),
+            worker,
+            queue_depth,
+            max_queue_size,
+        }
+    }
+}
+
+/// Wait used by `POST /task/execute` when the request doesn't set `max_wait_ms`
+pub const DEFAULT_EXECUTE_WAIT_MS: u64 = 5_000;
+
+/// Longest `max_wait_ms` a request may ask for
+pub const MAX_EXECUTE_WAIT_MS: u64 = 60_000;
+
+/// Synchronous execution request (`POST /task/execute`): a task plus how long
+/// to wait for its result before falling back to asynchronous mode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecuteTaskRequest {
+    #[serde(flatten)]
+    pub task: CreateTaskRequest,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_wait_ms: Option<u64>,
+}
+
+impl ExecuteTaskRequest {
+    /// Requested wait in milliseconds, defaulted and checked against [`MAX_EXECUTE_WAIT_MS`]
+    pub fn max_wait_ms(&self) -> Result<u64, ValidationError> {
+        match self.max_wait_ms.unwrap_or(DEFAULT_EXECUTE_WAIT_MS) {
+            wait @ 1..=MAX_EXECUTE_WAIT_MS => Ok(wait),
+            wait => Err(ValidationError::InvalidMaxWait(wait)),
+        }
+    }
+}
+
+/// Wait used by `GET /task/{id}/wait` when the request doesn't set `timeout_ms`
+pub const DEFAULT_WAIT_TIMEOUT_MS: u64 = 5_000;
+
+/// Longest `timeout_ms` a `GET /task/{id}/wait` may ask for
+pub const MAX_WAIT_TIMEOUT_MS: u64 = 60_000;
+
+/// Query of `DELETE /task/{id}`
+#[derive(Debug, Default, Deserialize)]
+pub struct DeleteQuery {
+    /// Also delete tasks that are calculated and awaiting completion
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Query of `GET /task/{id}/wait`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WaitQuery {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+}
+
+impl WaitQuery {
+    /// Requested wait in milliseconds, defaulted and checked against [`MAX_WAIT_TIMEOUT_MS`]
+    pub fn timeout_ms(&self) -> Result<u64, ValidationError> {
+        match self.timeout_ms.unwrap_or(DEFAULT_WAIT_TIMEOUT_MS) {
+            wait @ 1..=MAX_WAIT_TIMEOUT_MS => Ok(wait),
+            wait => Err(ValidationError::InvalidWaitTimeout(wait)),
+        }
+    }
+}
+
+/// Task completion response
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskCompletionResponse {
+    pub id: String,
+    pub status: TaskStatus,
+    pub message: String,
+}
+
+/// Error body returned by the API, an RFC 7807 problem (`application/problem+json`)
+///
+/// `error` repeats `detail` for clients written against the original
+/// `{"error", "reason"}` body, which the C++ server still sends.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    #[serde(rename = "type", default = "default_problem_type")]
+    pub problem_type: String,
+    /// The status code's reason phrase
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub status: u16,
+    #[serde(default)]
+    pub detail: String,
+    #[serde(default)]
+    pub error: String,
+    /// Stable machine-readable error code, e.g. `not_found` or `duplicate_task`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+fn default_problem_type() -> String {
+    "about:blank".to_string()
+}
+
+impl ErrorResponse {
+    pub fn new(status: warp::http::StatusCode, error: impl Into<String>) -> Self {
+        let error = error.into();
+        Self {
+            problem_type: default_problem_type(),
+            title: status.canonical_reason().unwrap_or_default().to_string(),
+            status: status.as_u16(),
+            detail: error.clone(),
+            error,
+            reason: None,
+        }
+    }
+
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+}
+
+/// Health check response
+#[derive(Debug, Serialize)]
+pub struct HealthResponse {
+    pub status: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// `GET /readyz` response: `status` is `"ready"` (200) or `"not_ready"` (503)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadinessResponse {
+    pub status: String,
+    /// Whether new tasks are accepted (not stopping or draining)
+    pub accepting_tasks: bool,
+    pub workers_running: usize,
+    pub workers_total: usize,
+    /// Workers whose queue is at `max_queue_size`
+    pub full_queues: usize,
+    /// Why the instance isn't ready, empty when it is
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reasons: Vec<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl ReadinessResponse {
+    pub fn is_ready(&self) -> bool {
+        self.reasons.is_empty()
+    }
+}
+
+/// Events a webhook subscription can receive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WebhookEventType {
+    #[serde(rename = "task.created")]
+    TaskCreated,
+    #[serde(rename = "task.completed")]
+    TaskCompleted,
+}
+
+impl WebhookEventType {
+    pub const ALL: [WebhookEventType; 2] = [WebhookEventType::TaskCreated, WebhookEventType::TaskCompleted];
+}
+
+/// Restricts which tasks a subscription hears about (empty lists match everything)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubscriptionFilter {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub operations: Vec<Operation>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub priorities: Vec<TaskPriority>,
+}
+
+impl SubscriptionFilter {
+    pub fn matches(&self, task: &Task) -> bool {
+        (self.operations.is_empty() || self.operations.contains(&task.data.operation))
+            && (self.priorities.is_empty() || self.priorities.contains(&task.priority))
+    }
+}
+
+/// Body of `POST /subscriptions` and `PUT /subscriptions/{id}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubscriptionRequest {
+    pub url: String,
+    /// Event types to deliver (default: all)
+    #[serde(default = "all_webhook_events")]
+    pub events: Vec<WebhookEventType>,
+    #[serde(default)]
+    pub filter: SubscriptionFilter,
+    /// HMAC-SHA256 key for the `X-Webhook-Signature` header
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+}
+
+fn all_webhook_events() -> Vec<WebhookEventType> {
+    WebhookEventType::ALL.to_vec()
+}
+
+impl SubscriptionRequest {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if !(self.url.starts_with("http://") || self.url.starts_with("https://")) {
+            return Err(ValidationError::InvalidWebhookEndpoint(self.url.clone()));
+        }
+        if self.events.is_empty() {
+            return Err(ValidationError::NoSubscribedEvents);
+        }
+        Ok(())
+    }
+}
+
+/// A persistent webhook subscription
+///
+/// The secret is never serialized; `has_secret` tells clients whether one is set.
+#[derive(Debug, Clone, Serialize)]
+pub struct Subscription {
+    pub id: String,
+    pub url: String,
+    pub events: Vec<WebhookEventType>,
+    pub filter: SubscriptionFilter,
+    #[serde(skip)]
+    pub secret: Option<String>,
+    pub has_secret: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Subscription {
+    pub fn new(request: SubscriptionRequest) -> Result<Self, ValidationError> {
+        request.validate()?;
+        Ok(Self {
+            id: Uuid::new_v4().to_string(),
+            url: request.url,
+            events: request.events,
+            filter: request.filter,
+            has_secret: request.secret.is_some(),
+            secret: request.secret,
+            created_at: Utc::now(),
+        })
+    }
+
+    /// Whether this subscription wants `event` for `task`
+    pub fn accepts(&self, event: WebhookEventType, task: &Task) -> bool {
+        self.events.contains(&event) && self.filter.matches(task)
+    }
+}
+
+/// Worker statistics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerStats {
+    pub id: usize,
+    pub tasks_processed: u64,
+    pub tasks_completed: u64,
+    pub tasks_failed: u64,
+    /// Tasks cancelled via `POST /task/{id}/cancel`
+    #[serde(default)]
+    pub tasks_cancelled: u64,
+    /// Tasks removed via `DELETE /task/{id}`
+    #[serde(default)]
+    pub tasks_deleted: u64,
+    /// Failed calculations queued again under the task's `max_retries`
+    #[serde(default)]
+    pub task_retries: u64,
+    /// Tasks whose result differed from their `expected_result`
+    #[serde(default)]
+    pub result_mismatches: u64,
+    /// Completed or failed tasks dropped to stay within `max_tasks_per_worker`
+    #[serde(default)]
+    pub storage_evictions: u64,
+    /// Completed, failed or cancelled tasks dropped `completed_task_ttl_seconds` after finishing
+    #[serde(default)]
+    pub tasks_expired: u64,
+    /// Times the watchdog found a processing thread stuck on one task
+    #[serde(default)]
+    pub wedged_threads: u64,
+    /// Replacement threads started for wedged ones
+    #[serde(default)]
+    pub threads_respawned: u64,
+    pub current_load: usize,
+    /// `current_load` split by priority; high-priority tasks are taken first
+    #[serde(default)]
+    pub queued_by_priority: QueueDepth,
+    pub uptime_seconds: u64,
+    pub is_healthy: bool,
+    /// Paused by an operator: takes no new tasks and leaves its queue untouched
+    #[serde(default)]
+    pub paused: bool,
+    /// Processing paused for maintenance: still takes new tasks but leaves them queued
+    #[serde(default)]
+    pub processing_paused: bool,
+    /// Multiplier on simulated processing time (1.0 = normal speed)
+    #[serde(default = "default_speed_factor")]
+    pub speed_factor: f64,
+    /// Time from task creation until its result was ready
+    #[serde(default)]
+    pub latency: LatencySummary,
+    /// Calculation time alone, by operation
+    #[serde(default)]
+    pub processing_by_operation: BTreeMap<String, LatencySummary>,
+    /// Times the supervisor restarted this worker after a processing thread panicked
+    #[serde(default)]
+    pub restarts: u64,
+    /// Queued tasks this worker took from busier peers
+    #[serde(default)]
+    pub tasks_stolen: u64,
+    /// Queued tasks idle peers took from this worker
+    #[serde(default)]
+    pub tasks_stolen_from: u64,
+}
+
+/// Queued tasks of each priority
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueDepth {
+    pub high: usize,
+    pub medium: usize,
+    pub low: usize,
+}
+
+pub(crate) fn default_speed_factor() -> f64 {
+    1.0
+}
+
+/// Whether a worker is taking tasks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum WorkerHealth {
+    /// Running and taking new tasks
+    Healthy,
+    /// Running, but paused by an operator
+    Paused,
+    /// Not started yet, or stopped
+    Stopped,
+}
+
+/// Point-in-time view of a worker for library users (`TaskOrchestrator::workers_snapshot`)
+///
+/// Part of the stable API: fields may be added, but existing ones keep their
+/// meaning, so construct it only through the orchestrator.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct WorkerSnapshot {
+    pub id: usize,
+    pub health: WorkerHealth,
+    /// Tasks waiting in the worker's queue
+    pub queue_depth: usize,
+    /// Processing threads, and how many are running a calculation right now
+    pub threads: usize,
+    pub busy_threads: usize,
+    /// Operations the worker can run
+    pub capabilities: Vec<Operation>,
+    /// Labels from `worker_labels` in the config
+    pub labels: BTreeMap<String, String>,
+}
+
+/// Entry of a worker's queue snapshot, in the order threads will take tasks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedTask {
+    pub position: usize,
+    pub id: String,
+    pub priority: TaskPriority,
+    pub operation: Operation,
+    pub input: u64,
+    #[serde(with = "crate::timestamps::timestamp")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Body of `POST /admin/scale`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScaleRequest {
+    /// Workers that keep taking tasks; the rest are paused
+    pub workers: usize,
+}
+
+/// Query of `POST /admin/purge`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurgeQuery {
+    pub status: TaskStatus,
+}
+
+/// Body of `POST /task/{id}/priority`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriorityChangeRequest {
+    pub priority: TaskPriority,
+    /// Why the change was made, kept in the audit history
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Body of `PATCH /task/{id}`: the fields to change, at least one
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskUpdateRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<TaskPriority>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+impl TaskUpdateRequest {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.priority.is_none() && self.title.is_none() {
+            return Err(ValidationError::EmptyUpdate);
+        }
+        if self.title.as_ref().is_some_and(|title| title.is_empty()) {
+            return Err(ValidationError::EmptyTitle);
+        }
+        Ok(())
+    }
+}
+
+/// One recorded operator change, as listed by `GET /admin/audit`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    #[serde(with = "crate::timestamps::timestamp")]
+    pub at: DateTime<Utc>,
+    /// Name of the API key that made the change (`anonymous` without auth)
+    pub actor: String,
+    pub task_id: String,
+    #[serde(flatten)]
+    pub change: AuditChange,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum AuditChange {
+    PriorityChanged { from: TaskPriority, to: TaskPriority },
+}
+
+/// Runtime limits in effect, as returned by `GET /admin/limits`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EffectiveLimits {
+    /// Accepted input range of each operation
+    pub operations: Vec<OperationLimits>,
+    pub max_pipeline_stages: usize,
+    /// Most `max_retries` a task may ask for
+    pub max_task_retries: u32,
+    /// Cap on the wait before any retry
+    pub max_retry_backoff_ms: u64,
+    /// Custom validators run after the built-in checks, in order
+    pub validators: Vec<String>,
+    pub queue: QueueLimits,
+    pub timeouts: TimeoutLimits,
+    pub retention: RetentionLimits,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperationLimits {
+    pub operation: Operation,
+    pub min_input: u64,
+    pub max_input: u64,
+    /// Largest input with `precision: "big"`, for operations that have it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_big_input: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueLimits {
+    pub polling: QueuePollingMode,
+    /// Queue check interval, when polling falls back to it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_interval_ms: Option<u64>,
+    /// Tasks taken per wakeup, indexed by worker id (0 = one at a time)
+    pub batch_sizes: Vec<usize>,
+    /// Tasks a worker may have queued before submissions get 429 (0 = unlimited)
+    #[serde(default)]
+    pub max_queue_size: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeoutLimits {
+    /// `POST /task/execute` wait when `max_wait_ms` isn't given
+    pub default_execute_wait_ms: u64,
+    pub max_execute_wait_ms: u64,
+    /// Time allowed for one `generate_prime` calculation
+    pub prime_generation_ms: u64,
+    pub webhook_delivery_ms: u64,
+    /// Requests slower than this are logged (0 = disabled)
+    pub slow_request_threshold_ms: u64,
+    /// Time allowed for one calculation attempt of a task without `timeout_ms` (0 = unlimited)
+    #[serde(default)]
+    pub default_task_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionLimits {
+    /// Tasks stored per worker before the oldest finished ones are evicted (0 = unlimited)
+    pub max_tasks_per_worker: usize,
+    /// Seconds finished tasks are kept before being dropped (0 = forever)
+    #[serde(default)]
+    pub completed_task_ttl_seconds: u64,
+    /// Most tasks one `GET /tasks` page returns
+    pub max_list_limit: usize,
+}
+
+/// Outcome of an operator action under `/admin`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminResponse {
+    pub message: String,
+    /// Tasks moved by a rebalance or removed by a purge
+    #[serde(default)]
+    pub tasks_affected: usize,
+    /// Every worker's state after the action
+    pub workers: Vec<WorkerStats>,
+}
+
+/// Query of `GET /tasks`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskListQuery {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<TaskStatus>,
+    /// Tasks returned (default 100, at most 1000)
+    #[serde(default = "default_list_limit")]
+    pub limit: usize,
+    /// Tasks skipped before the first one returned
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub offset: usize,
+    /// `created_at`, `priority` or `id`, with a leading `-` for descending (default `-created_at`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+    /// `key:value` pairs, comma-separated, that every returned task carries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+pub const MAX_LIST_LIMIT: usize = 1000;
+
+fn default_list_limit() -> usize {
+    100
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
+/// Parameters of `GET /tasks`
+pub const TASK_LIST: ListSpec = ListSpec {
+    default_limit: 100,
+    max_limit: MAX_LIST_LIMIT,
+    sort_fields: &["created_at", "priority", "id"],
+    default_sort: Sort {
+        field: "created_at",
+        descending: true,
+    },
+    filters: &["status", "label"],
+};
+
+impl Default for TaskListQuery {
+    fn default() -> Self {
+        Self {
+            status: None,
+            limit: default_list_limit(),
+            offset: 0,
+            sort: None,
+            label: None,
+        }
+    }
+}
+
+impl TaskListQuery {
+    /// Validated through [`TASK_LIST`], as the endpoint would
+    pub fn params(&self) -> Result<ListParams, QueryError> {
+        let mut query = HashMap::from([
+            ("limit".to_string(), self.limit.to_string()),
+            ("offset".to_string(), self.offset.to_string()),
+        ]);
+        if let Some(status) = &self.status {
+            query.insert("status".to_string(), status.to_string());
+        }
+        if let Some(sort) = &self.sort {
+            query.insert("sort".to_string(), sort.clone());
+        }
+        if let Some(label) = &self.label {
+            query.insert("label".to_string(), label.clone());
+        }
+        TASK_LIST.parse(&query)
+    }
+}
+
+/// System-wide statistics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemStats {
+    pub total_tasks_processed: u64,
+    pub total_tasks_completed: u64,
+    pub total_tasks_failed: u64,
+    #[serde(default)]
+    pub total_tasks_cancelled: u64,
+    /// Tasks removed via `DELETE /task/{id}`, from workers and the scheduler
+    #[serde(default)]
+    pub total_tasks_deleted: u64,
+    pub total_result_mismatches: u64,
+    #[serde(default)]
+    pub total_storage_evictions: u64,
+    #[serde(default)]
+    pub total_tasks_expired: u64,
+    #[serde(default)]
+    pub total_wedged_threads: u64,
+    #[serde(default)]
+    pub total_threads_respawned: u64,
+    /// Tasks held by the orchestrator until their `run_at` time
+    #[serde(default)]
+    pub scheduled_tasks: usize,
+    /// Scheduled tasks handed to a worker once due
+    #[serde(default)]
+    pub total_scheduled_dispatched: u64,
+    /// Tasks held by the orchestrator until their dependencies complete
+    #[serde(default)]
+    pub blocked_tasks: usize,
+    /// Workers restarted by the supervisor after crashing
+    #[serde(default)]
+    pub worker_restarts: u64,
+    /// Every worker's processing is paused (`POST /admin/pause`); submissions still queue
+    #[serde(default)]
+    pub processing_paused: bool,
+    pub total_workers: usize,
+    pub uptime_seconds: u64,
+    /// Seconds since this process started (the same as `uptime_seconds`)
+    #[serde(default)]
+    pub process_uptime: u64,
+    /// Counters over this run and every earlier one recorded by
+    /// `[stats_checkpoint]` (this run's alone when it isn't configured)
+    #[serde(default)]
+    pub cumulative: CumulativeStats,
+    /// Percentiles over every worker's tasks, merged from the workers' histograms
+    pub latency: LatencySummary,
+    /// Calculation time by operation, merged the same way
+    #[serde(default)]
+    pub processing_by_operation: BTreeMap<String, LatencySummary>,
+    pub workers: Vec<WorkerStats>,
+}
+
+impl SystemStats {
+    /// Aggregate per-worker statistics into system totals
+    pub fn from_workers(workers: Vec<WorkerStats>, total_workers: usize, uptime_seconds: u64) -> Self {
+        Self {
+            total_tasks_processed: workers.iter().map(|w| w.tasks_processed).sum(),
+            total_tasks_completed: workers.iter().map(|w| w.tasks_completed).sum(),
+            total_tasks_failed: workers.iter().map(|w| w.tasks_failed).sum(),
+            total_tasks_cancelled: workers.iter().map(|w| w.tasks_cancelled).sum(),
+            total_tasks_deleted: workers.iter().map(|w| w.tasks_deleted).sum(),
+            total_result_mismatches: workers.iter().map(|w| w.result_mismatches).sum(),
+            total_storage_evictions: workers.iter().map(|w| w.storage_evictions).sum(),
+            total_tasks_expired: workers.iter().map(|w| w.tasks_expired).sum(),
+            total_wedged_threads: workers.iter().map(|w| w.wedged_threads).sum(),
+            total_threads_respawned: workers.iter().map(|w| w.threads_respawned).sum(),
+            scheduled_tasks: 0,
+            total_scheduled_dispatched: 0,
+            blocked_tasks: 0,
+            worker_restarts: workers.iter().map(|w| w.restarts).sum(),
+            processing_paused: !workers.is_empty() && workers.iter().all(|w| w.processing_paused),
+            total_workers,
+            uptime_seconds,
+            process_uptime: uptime_seconds,
+            cumulative: CumulativeStats::default(),
+            latency: LatencySummary::default(),
+            processing_by_operation: BTreeMap::new(),
+            workers,
+        }
+    }
+
+    /// Attach system-wide latency percentiles
+    pub fn with_latency(mut self, latency: LatencySummary) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Attach per-operation calculation times merged from the workers' histograms
+    pub fn with_processing_latency(mut self, processing_by_operation: BTreeMap<String, LatencySummary>) -> Self {
+        self.processing_by_operation = processing_by_operation;
+        self
+    }
+
+    /// Attach the orchestrator's count of waiting and dispatched scheduled tasks
+    pub fn with_scheduled(mut self, waiting: usize, dispatched: u64) -> Self {
+        self.scheduled_tasks = waiting;
+        self.total_scheduled_dispatched = dispatched;
+        self
+    }
+
+    /// Attach the orchestrator's count of tasks waiting for dependencies
+    pub fn with_blocked(mut self, blocked: usize) -> Self {
+        self.blocked_tasks = blocked;
+        self
+    }
+
+    /// Add the waiting tasks the orchestrator deleted before any worker held them
+    pub fn with_deleted(mut self, deleted: u64) -> Self {
+        self.total_tasks_deleted += deleted;
+        self
+    }
+
+    /// Attach cumulative counters: `previous` runs' plus this one's
+    ///
+    /// Call last, once the other counters are attached.
+    pub fn with_cumulative(mut self, previous: &CumulativeStats) -> Self {
+        let mut workers = previous.workers.clone();
+        for worker in &self.workers {
+            let counters = workers.entry(worker.id).or_default();
+            counters.tasks_processed += worker.tasks_processed;
+            counters.tasks_completed += worker.tasks_completed;
+            counters.tasks_failed += worker.tasks_failed;
+            counters.tasks_cancelled += worker.tasks_cancelled;
+        }
+        self.cumulative = CumulativeStats {
+            uptime_seconds: previous.uptime_seconds + self.process_uptime,
+            tasks_processed: previous.tasks_processed + self.total_tasks_processed,
+            tasks_completed: previous.tasks_completed + self.total_tasks_completed,
+            tasks_failed: previous.tasks_failed + self.total_tasks_failed,
+            tasks_cancelled: previous.tasks_cancelled + self.total_tasks_cancelled,
+            tasks_deleted: previous.tasks_deleted + self.total_tasks_deleted,
+            worker_restarts: previous.worker_restarts + self.worker_restarts,
+            workers,
+        };
+        self
+    }
+}
+
+/// Counters summed over server runs, as saved by `[stats_checkpoint]`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CumulativeStats {
+    /// Seconds the server has been running, over every run
+    pub uptime_seconds: u64,
+    pub tasks_processed: u64,
+    pub tasks_completed: u64,
+    pub tasks_failed: u64,
+    pub tasks_cancelled: u64,
+    pub tasks_deleted: u64,
+    pub worker_restarts: u64,
+    /// Task counters by worker ID
+    pub workers: BTreeMap<usize, WorkerCounters>,
+}
+
+/// One worker's task counters in [`CumulativeStats`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkerCounters {
+    pub tasks_processed: u64,
+    pub tasks_completed: u64,
+    pub tasks_failed: u64,
+    pub tasks_cancelled: u64,
+}
+
+/// Configuration structures
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrchestratorConfig {
+    pub num_workers: usize,
+    pub threads_per_worker: usize,
+    /// API port; 0 binds any free port (see `TaskOrchestrator::bound_addr` and `port_file`)
+    pub orchestrator_port: u16,
+    /// Address the main listener binds: `127.0.0.1` by default, `::1` for IPv6
+    /// loopback, `0.0.0.0` or `::` (dual-stack) for every interface
+    #[serde(default = "default_bind_address")]
+    pub bind_address: IpAddr,
+    /// File the bound address is written to once the server is listening
+    /// (removed again on stop), for finding a server started on port 0
+    #[serde(default)]
+    pub port_file: Option<PathBuf>,
+    /// Further addresses serving the same API, each optionally over TLS
+    /// (`[[listeners]]`); started and stopped with the main listener
+    #[serde(default)]
+    pub listeners: Vec<ListenerConfig>,
+    /// PEM certificate chain the main listener serves HTTPS with (plain HTTP
+    /// unless given together with `tls_key_path`)
+    #[serde(default)]
+    pub tls_cert_path: Option<PathBuf>,
+    /// PEM private key for `tls_cert_path`
+    #[serde(default)]
+    pub tls_key_path: Option<PathBuf>,
+    /// First per-worker diagnostics port; worker `i` serves on `base_port + i`
+    /// (disabled when unset)
+    #[serde(default)]
+    pub base_port: Option<u16>,
+    #[serde(default)]
+    pub prime_generation: PrimeGenerationConfig,
+    /// Result size limit for tasks with `precision: "big"`
+    #[serde(default)]
+    pub big_precision: BigPrecisionConfig,
+    /// Largest inputs of factorial, fibonacci and prime_check
+    #[serde(default)]
+    pub operation_limits: OperationLimitsConfig,
+    /// Fibonacci algorithm used when a task doesn't specify one
+    #[serde(default)]
+    pub fibonacci_algorithm: FibonacciAlgorithm,
+    /// Primality test used by prime_check
+    #[serde(default)]
+    pub prime_check_algorithm: PrimeCheckAlgorithm,
+    /// Requests slower than this are logged and counted (0 disables)
+    #[serde(default = "default_slow_request_threshold_ms")]
+    pub slow_request_threshold_ms: u64,
+    /// How long `GET /stats` reuses its aggregated result (0 disables caching)
+    #[serde(default = "default_stats_cache_ms")]
+    pub stats_cache_ms: u64,
+    /// Seconds between the snapshots `GET /stats/stream` pushes
+    #[serde(default = "default_stats_stream_interval_seconds")]
+    pub stats_stream_interval_seconds: u64,
+    /// Seconds an idempotency key keeps returning the task it created
+    /// (0 ignores idempotency keys)
+    #[serde(default = "default_idempotency_window_seconds")]
+    pub idempotency_window_seconds: u64,
+    /// HMAC key used by workers to sign results (signing disabled when unset)
+    #[serde(default, skip_serializing)]
+    pub result_signing_key: Option<String>,
+    /// Base URL of the primary; when set this instance is a read-only replica,
+    /// serving the tasks in the `task_store` it shares with the primary
+    #[serde(default)]
+    pub primary_url: Option<String>,
+    /// Default format for `created_at`/`completed_at` in responses
+    #[serde(default)]
+    pub timestamp_format: TimestampFormat,
+    /// Whether results are written as bare strings or tagged typed values
+    #[serde(default)]
+    pub result_format: ResultFormat,
+    /// Where workers run calculations: `blocking`, `dedicated` or `inline`
+    #[serde(default)]
+    pub calculation_executor: CalculationExecutor,
+    /// Simulated processing time added to every task before `speed_factor` scaling
+    #[serde(default)]
+    pub simulated_processing_ms: u64,
+    /// Per-worker multipliers on simulated processing time, indexed by worker id
+    /// (missing entries mean 1.0); used to emulate deliberately slow workers
+    #[serde(default)]
+    pub worker_speed_factors: Vec<f64>,
+    /// How new tasks are spread over the workers
+    #[serde(default)]
+    pub load_balancing: LoadBalancingStrategy,
+    /// Per-worker shares under `weighted_round_robin`, indexed by worker id
+    /// (missing entries mean 1)
+    #[serde(default)]
+    pub worker_weights: Vec<u32>,
+    /// Per-worker labels reported in worker snapshots, indexed by worker id
+    /// (missing entries mean no labels)
+    #[serde(default)]
+    pub worker_labels: Vec<BTreeMap<String, String>>,
+    /// Most tasks a worker stores, oldest completed or failed evicted first
+    /// (0 means unlimited)
+    #[serde(default)]
+    pub max_tasks_per_worker: usize,
+    /// Most tasks waiting in a worker's queue; submissions past it get 429
+    /// (0 means unlimited)
+    #[serde(default)]
+    pub max_queue_size: usize,
+    /// Seconds completed, failed and cancelled tasks are kept after finishing
+    /// before a worker drops them (0 means forever)
+    #[serde(default)]
+    pub completed_task_ttl_seconds: u64,
+    /// Seconds a shutdown waits for workers to process queued tasks before
+    /// stopping (0 stops right away)
+    #[serde(default = "default_shutdown_drain_timeout_seconds")]
+    pub shutdown_drain_timeout_seconds: u64,
+    /// Milliseconds a calculation attempt may take when the task doesn't set
+    /// `timeout_ms` (0 means no limit)
+    #[serde(default)]
+    pub default_task_timeout_ms: u64,
+    /// How processing threads wait for and take queued tasks
+    #[serde(default)]
+    pub queue_polling: QueuePollingConfig,
+    /// Per-worker `queue_polling.batch_size` overrides, indexed by worker id
+    /// (missing entries use `queue_polling.batch_size`)
+    #[serde(default)]
+    pub worker_batch_sizes: Vec<usize>,
+    /// Flags (and optionally replaces) processing threads stuck on one task
+    #[serde(default)]
+    pub watchdog: WatchdogConfig,
+    /// Idle workers take queued tasks from the most loaded peer (off by default)
+    #[serde(default)]
+    pub work_stealing: WorkStealingConfig,
+    /// Endpoints notified when tasks complete
+    #[serde(default)]
+    pub webhooks: WebhookConfig,
+    /// Delivery of tasks to their `callback_url`
+    #[serde(default)]
+    pub callbacks: CallbackConfig,
+    /// Push metrics to a statsd daemon (disabled when unset)
+    #[serde(default)]
+    pub statsd: Option<StatsdConfig>,
+    /// Record finished calculations in a local SQLite file (disabled when unset)
+    #[serde(default)]
+    pub analytics: Option<AnalyticsConfig>,
+    /// Keep tasks in a local SQLite file so they survive restarts (in memory only when unset)
+    #[serde(default)]
+    pub task_store: Option<TaskStoreConfig>,
+    /// Save cumulative statistics to a JSON file so they survive restarts
+    /// (counters start from zero with every process when unset)
+    #[serde(default)]
+    pub stats_checkpoint: Option<StatsCheckpointConfig>,
+    /// Peer orchestrators receiving tasks this one can't take (disabled without peers)
+    #[serde(default)]
+    pub federation: FederationConfig,
+    /// API keys and the namespaces they may use (auth disabled when empty)
+    #[serde(default)]
+    pub api_keys: Vec<ApiKeyConfig>,
+    /// Requests per second each client may make, by API key or IP (off by default)
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    /// Calculations forced to fail, for exercising failure paths (debug builds only)
+    #[serde(default)]
+    pub inject_failures: Vec<InjectedFailure>,
+}
+
+fn default_bind_address() -> IpAddr {
+    IpAddr::V4(Ipv4Addr::LOCALHOST)
+}
+
+fn default_slow_request_threshold_ms() -> u64 {
+    500
+}
+
+fn default_stats_cache_ms() -> u64 {
+    250
+}
+
+fn default_shutdown_drain_timeout_seconds() -> u64 {
+    30
+}
+
+fn default_idempotency_window_seconds() -> u64 {
+    24 * 60 * 60
+}
+
+fn default_stats_stream_interval_seconds() -> u64 {
+    1
+}
+
+/// Longest `stats_stream_interval_seconds` allowed
+pub const MAX_STATS_STREAM_INTERVAL_SECS: u64 = 3600;
+
+impl Default for OrchestratorConfig {
+    fn default() -> Self {
+        Self {
+            num_workers: 3,
+            threads_per_worker: 4,
             orchestrator_port: 7000,
+            bind_address: default_bind_address(),
+            port_file: None,
+            listeners: Vec::new(),
+            tls_cert_path: None,
+            tls_key_path: None,
+            base_port: None,
+            prime_generation: PrimeGenerationConfig::default(),
+            big_precision: BigPrecisionConfig::default(),
+            operation_limits: OperationLimitsConfig::default(),
+            fibonacci_algorithm: FibonacciAlgorithm::default(),
+            prime_check_algorithm: PrimeCheckAlgorithm::default(),
+            slow_request_threshold_ms: default_slow_request_threshold_ms(),
+            stats_cache_ms: default_stats_cache_ms(),
+            stats_stream_interval_seconds: default_stats_stream_interval_seconds(),
+            idempotency_window_seconds: default_idempotency_window_seconds(),
+            result_signing_key: None,
+            primary_url: None,
+            timestamp_format: TimestampFormat::default(),
+            result_format: ResultFormat::default(),
+            calculation_executor: CalculationExecutor::default(),
+            simulated_processing_ms: 0,
+            worker_speed_factors: Vec::new(),
+            load_balancing: LoadBalancingStrategy::default(),
+            worker_weights: Vec::new(),
+            worker_labels: Vec::new(),
+            max_tasks_per_worker: 0,
+            max_queue_size: 0,
+            completed_task_ttl_seconds: 0,
+            shutdown_drain_timeout_seconds: default_shutdown_drain_timeout_seconds(),
+            default_task_timeout_ms: 0,
+            queue_polling: QueuePollingConfig::default(),
+            worker_batch_sizes: Vec::new(),
+            watchdog: WatchdogConfig::default(),
+            work_stealing: WorkStealingConfig::default(),
+            webhooks: WebhookConfig::default(),
+            callbacks: CallbackConfig::default(),
+            statsd: None,
+            analytics: None,
+            task_store: None,
+            stats_checkpoint: None,
+            federation: FederationConfig::default(),
+            api_keys: Vec::new(),
+            rate_limit: RateLimitConfig::default(),
+            inject_failures: Vec::new(),
+        }
+    }
+}
+
+/// Settings for the `generate_prime` operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrimeGenerationConfig {
+    /// Largest bit length a task may request (2-64)
+    pub max_bits: u32,
+    /// Fixed RNG seed for reproducible fixtures; `None` uses OS entropy
+    pub seed: Option<u64>,
+    /// Give up on a single generation after this many milliseconds
+    pub time_limit_ms: u64,
+}
+
+impl Default for PrimeGenerationConfig {
+    fn default() -> Self {
+        Self {
+            max_bits: 64,
+            seed: None,
+            time_limit_ms: 1000,
+        }
+    }
+}
+
+impl PrimeGenerationConfig {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.max_bits < 2 || self.max_bits > 64 {
+            return Err(ValidationError::InvalidPrimeBits(self.max_bits));
+        }
+        if self.time_limit_ms == 0 {
+            return Err(ValidationError::InvalidTimeLimit(self.time_limit_ms));
+        }
+        Ok(())
+    }
+}
+
+/// Most digits `big_precision.max_digits` may allow
+pub const MAX_BIG_DIGITS: usize = 100_000;
+
+/// Settings for tasks with `precision: "big"`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BigPrecisionConfig {
+    /// Most decimal digits a result may have (1-100000); each operation's
+    /// largest input follows from it
+    pub max_digits: usize,
+}
+
+impl Default for BigPrecisionConfig {
+    fn default() -> Self {
+        Self { max_digits: 1000 }
+    }
+}
+
+impl BigPrecisionConfig {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.max_digits == 0 || self.max_digits > MAX_BIG_DIGITS {
+            return Err(ValidationError::InvalidMaxDigits(self.max_digits));
+        }
+        Ok(())
+    }
+}
+
+/// Largest factorial input whose result fits in the u128 the calculation uses
+pub const MAX_FACTORIAL_INPUT: u64 = 34;
+
+/// Largest fibonacci input whose result fits in a u64
+pub const MAX_FIBONACCI_INPUT: u64 = 93;
+
+/// Largest inputs accepted by the operations with fixed precision
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OperationLimitsConfig {
+    /// At most 34
+    pub max_factorial_input: u64,
+    /// At most 93
+    pub max_fibonacci_input: u64,
+    pub max_prime_check_input: u64,
+}
+
+impl Default for OperationLimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_factorial_input: 20,
+            max_fibonacci_input: MAX_FIBONACCI_INPUT,
+            max_prime_check_input: u64::MAX,
+        }
+    }
+}
+
+impl OperationLimitsConfig {
+    /// Configured largest input of `operation`, `None` for operations it doesn't cover
+    pub fn max_input(&self, operation: &Operation) -> Option<u64> {
+        match operation {
+            Operation::Factorial => Some(self.max_factorial_input),
+            Operation::Fibonacci => Some(self.max_fibonacci_input),
+            Operation::PrimeCheck => Some(self.max_prime_check_input),
+            _ => None,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.max_factorial_input > MAX_FACTORIAL_INPUT {
+            return Err(ValidationError::OperationLimitTooLarge {
+                operation: Operation::Factorial,
+                max_input: self.max_factorial_input,
+                ceiling: MAX_FACTORIAL_INPUT,
+            });
+        }
+        if self.max_fibonacci_input > MAX_FIBONACCI_INPUT {
+            return Err(ValidationError::OperationLimitTooLarge {
+                operation: Operation::Fibonacci,
+                max_input: self.max_fibonacci_input,
+                ceiling: MAX_FIBONACCI_INPUT,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Completion webhook delivery settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    /// URLs that receive `task.completed` events
+    pub endpoints: Vec<String>,
+    /// Send a batch as soon as it holds this many events (1 disables batching)
+    pub batch_size: usize,
+    /// Send a partial batch after at most this long
+    pub flush_interval_ms: u64,
+    /// Timeout for each delivery request
+    pub timeout_ms: u64,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            endpoints: Vec::new(),
+            batch_size: 1,
+            flush_interval_ms: 1000,
+            timeout_ms: 5000,
+        }
+    }
+}
+
+impl WebhookConfig {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        for url in &self.endpoints {
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                return Err(ValidationError::InvalidWebhookEndpoint(url.clone()));
+            }
+        }
+        if self.batch_size == 0 {
+            return Err(ValidationError::InvalidBatchSize(self.batch_size));
+        }
+        if self.flush_interval_ms == 0 {
+            return Err(ValidationError::InvalidTimeLimit(self.flush_interval_ms));
+        }
+        if self.timeout_ms == 0 {
+            return Err(ValidationError::InvalidTimeLimit(self.timeout_ms));
+        }
+        Ok(())
+    }
+}
+
+/// Delivery of tasks to the `callback_url` they were submitted with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CallbackConfig {
+    /// Timeout for each delivery attempt
+    pub timeout_ms: u64,
+    /// Attempts per callback, including the first
+    pub max_attempts: u32,
+    /// Wait before the first retry; doubled after every further failure
+    pub initial_backoff_ms: u64,
+    /// Longest wait between two attempts
+    pub max_backoff_ms: u64,
+}
+
+impl Default for CallbackConfig {
+    fn default() -> Self {
+        Self {
+            timeout_ms: 5000,
+            max_attempts: 5,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 30_000,
+        }
+    }
+}
+
+impl CallbackConfig {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.max_attempts == 0 {
+            return Err(ValidationError::InvalidMaxAttempts(self.max_attempts));
+        }
+        for ms in [self.timeout_ms, self.initial_backoff_ms, self.max_backoff_ms] {
+            if ms == 0 {
+                return Err(ValidationError::InvalidTimeLimit(ms));
+            }
+        }
+        Ok(())
+    }
+
+    /// Wait after the `attempt`th (1-based) failed attempt
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+        Duration::from_millis(self.initial_backoff_ms.saturating_mul(factor).min(self.max_backoff_ms))
+    }
+}
+
+/// How an idle processing thread notices new tasks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueuePollingMode {
+    /// Sleep until a task is queued; no CPU used while idle
+    Notify,
+    /// Also check the queue every `fallback_interval_ms`, in case a wakeup was missed
+    NotifyWithFallback,
+}
+
+/// Processing loop wakeup settings (`[queue_polling]` in the config)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueuePollingConfig {
+    pub mode: QueuePollingMode,
+    /// Queue check interval for `notify_with_fallback`
+    pub fallback_interval_ms: u64,
+    /// Tasks a thread takes off the queue at once, per wakeup, and processes back to
+    /// back (0 means one at a time until the queue is empty); see `worker_batch_sizes`
+    pub batch_size: usize,
+}
+
+impl Default for QueuePollingConfig {
+    fn default() -> Self {
+        Self {
+            mode: QueuePollingMode::NotifyWithFallback,
+            fallback_interval_ms: 100,
+            batch_size: 0,
+        }
+    }
+}
+
+impl QueuePollingConfig {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.mode == QueuePollingMode::NotifyWithFallback && self.fallback_interval_ms == 0 {
+            return Err(ValidationError::InvalidTimeLimit(self.fallback_interval_ms));
+        }
+        Ok(())
+    }
+
+    /// How long a thread waits for a notification before checking anyway (`None` waits indefinitely)
+    pub fn fallback_interval(&self) -> Option<Duration> {
+        match self.mode {
+            QueuePollingMode::Notify => None,
+            QueuePollingMode::NotifyWithFallback => Some(Duration::from_millis(self.fallback_interval_ms)),
+        }
+    }
+}
+
+/// Idle processing threads taking queued tasks from busier workers (`[work_stealing]` in the config)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkStealingConfig {
+    pub enabled: bool,
+    /// How often an idle thread looks for a peer to steal from
+    pub interval_ms: u64,
+    /// Only peers with at least this many queued tasks are stolen from
+    pub min_queue: usize,
+    /// Most tasks taken in one steal (half the peer's queue otherwise)
+    pub max_batch: usize,
+}
+
+impl Default for WorkStealingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_ms: 100,
+            min_queue: 2,
+            max_batch: 8,
+        }
+    }
+}
+
+impl WorkStealingConfig {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.interval_ms == 0 {
+            return Err(ValidationError::InvalidTimeLimit(self.interval_ms));
+        }
+        if self.max_batch == 0 {
+            return Err(ValidationError::InvalidBatchSize(self.max_batch));
+        }
+        Ok(())
+    }
+}
+
+/// Detection of processing threads stuck on one task (`[watchdog]` in the config)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchdogConfig {
+    /// A thread busy with the same task for longer than this is flagged (0 disables the watchdog)
+    pub threshold_ms: u64,
+    /// How often each worker checks its threads
+    pub check_interval_ms: u64,
+    /// Start a replacement for a flagged thread; the stuck one exits once its task finishes
+    pub respawn: bool,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            threshold_ms: 30_000,
+            check_interval_ms: 1000,
+            respawn: false,
+        }
+    }
+}
+
+impl WatchdogConfig {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.threshold_ms > 0 && self.check_interval_ms == 0 {
+            return Err(ValidationError::InvalidTimeLimit(self.check_interval_ms));
+        }
+        Ok(())
+    }
+
+    /// Threshold after which a busy thread is flagged (`None` when disabled)
+    pub fn threshold(&self) -> Option<Duration> {
+        (self.threshold_ms > 0).then(|| Duration::from_millis(self.threshold_ms))
+    }
+}
+
+/// Token-bucket limits on each client's requests (`[rate_limit]` in the config)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    /// Sustained requests per second per client on routes without their own rule (0 disables)
+    pub requests_per_second: f64,
+    /// Requests a client may make in a burst before the rate applies
+    pub burst: u32,
+    /// Routes with a limit of their own, replacing the default one
+    pub routes: Vec<RateLimitRule>,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_second: 0.0,
+            burst: 20,
+            routes: Vec::new(),
+        }
+    }
+}
+
+/// Limit for one route (`[[rate_limit.routes]]` in the config)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitRule {
+    /// Route pattern as reported in metrics, e.g. `/task/create` or `/task/{id}`
+    pub route: String,
+    /// Sustained requests per second per client (0 exempts the route)
+    pub requests_per_second: f64,
+    /// Requests a client may make in a burst
+    #[serde(default = "default_rate_limit_burst")]
+    pub burst: u32,
+}
+
+fn default_rate_limit_burst() -> u32 {
+    20
+}
+
+impl RateLimitRule {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if !self.route.starts_with('/') {
+            return Err(ValidationError::InvalidRateLimitRoute(self.route.clone()));
         }
+        validate_rate_limit(self.requests_per_second, self.burst)
     }
 }
 
-impl OrchestratorConfig {
+fn validate_rate_limit(requests_per_second: f64, burst: u32) -> Result<(), ValidationError> {
+    if !requests_per_second.is_finite() || requests_per_second < 0.0 || burst == 0 {
+        return Err(ValidationError::InvalidRateLimit { requests_per_second, burst });
+    }
+    Ok(())
+}
+
+/// Statsd daemon receiving metrics (`[statsd]` in the config)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsdConfig {
+    /// `host:port` of the daemon, e.g. `127.0.0.1:8125`
+    pub address: String,
+    /// Prepended to every metric name as `prefix.` (empty for none)
+    #[serde(default = "default_statsd_prefix")]
+    pub prefix: String,
+}
+
+fn default_statsd_prefix() -> String {
+    "task_processor".to_string()
+}
+
+/// SQLite file receiving one row per finished calculation (`[analytics]` in the config)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyticsConfig {
+    /// Created if missing; rows are appended across restarts
+    pub path: PathBuf,
+}
+
+/// SQLite file holding every stored task (`[task_store]` in the config)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskStoreConfig {
+    /// Created if missing; tasks found in it are restored on start
+    pub path: PathBuf,
+}
+
+/// JSON file holding cumulative statistics (`[stats_checkpoint]` in the config)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsCheckpointConfig {
+    /// Created if missing; its counters are added to this run's
+    pub path: PathBuf,
+    /// Seconds between saves; the counters are also saved on stop
+    #[serde(default = "default_checkpoint_interval_seconds")]
+    pub interval_seconds: u64,
+}
+
+fn default_checkpoint_interval_seconds() -> u64 {
+    60
+}
+
+/// Peer orchestrators that tasks are forwarded to (`[federation]` in the config)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FederationConfig {
+    /// Tried round-robin for each forwarded task
+    pub peers: Vec<PeerConfig>,
+    /// Timeout for each request to a peer
+    pub timeout_ms: u64,
+    /// Most forwarded tasks remembered; the oldest are forgotten first (0 = unlimited)
+    pub max_remote_tasks: usize,
+}
+
+impl Default for FederationConfig {
+    fn default() -> Self {
+        Self {
+            peers: Vec::new(),
+            timeout_ms: 5000,
+            max_remote_tasks: 10_000,
+        }
+    }
+}
+
+impl FederationConfig {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        for (index, peer) in self.peers.iter().enumerate() {
+            if peer.name.trim().is_empty() || self.peers[..index].iter().any(|other| other.name == peer.name) {
+                return Err(ValidationError::InvalidPeerName(peer.name.clone()));
+            }
+            if !(peer.url.starts_with("http://") || peer.url.starts_with("https://")) {
+                return Err(ValidationError::InvalidPeerUrl(peer.url.clone()));
+            }
+        }
+        if self.timeout_ms == 0 {
+            return Err(ValidationError::InvalidTimeLimit(self.timeout_ms));
+        }
+        Ok(())
+    }
+}
+
+/// One peer orchestrator (`[[federation.peers]]`), Rust or C++
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeerConfig {
+    /// Unique name shown for the tasks it holds
+    pub name: String,
+    /// Base URL of its REST API, e.g. `http://cpp-host:7000`
+    pub url: String,
+    /// Sent as a bearer token when the peer requires API keys
+    #[serde(default, skip_serializing)]
+    pub api_key: Option<String>,
+}
+
+/// An extra address serving the API (`[[listeners]]` in the config)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListenerConfig {
+    /// Address to bind, e.g. `0.0.0.0:7443` or `[::1]:7001` (port 0 picks a free one)
+    pub address: SocketAddr,
+    /// Serve HTTPS with this certificate instead of plain HTTP
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+}
+
+/// PEM certificate chain and private key for a TLS listener
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+/// Whether two listen addresses would compete for the same socket
+fn addresses_overlap(a: SocketAddr, b: SocketAddr) -> bool {
+    a.port() != 0
+        && a.port() == b.port()
+        && (a.ip() == b.ip() || a.ip().is_unspecified() || b.ip().is_unspecified())
+}
+
+/// One API key (`[[api_keys]]` in the config)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyConfig {
+    /// Label used in logs; the key itself is never logged
+    pub name: String,
+    /// Never serialized, so printed configs don't leak it
+    #[serde(skip_serializing)]
+    pub key: String,
+    /// Namespaces the key may create and read tasks in; `"*"` allows all
+    pub namespaces: Vec<String>,
+}
+
+impl ApiKeyConfig {
     pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.key.is_empty() || self.namespaces.is_empty() {
+            return Err(ValidationError::InvalidApiKey(self.name.clone()));
+        }
+        for namespace in &self.namespaces {
+            if namespace != "*" {
+                validate_namespace(namespace)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A calculation forced to fail (`[[inject_failures]]` in the config)
+///
+/// Only honoured in debug builds; release builds reject the setting.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InjectedFailure {
+    pub operation: Operation,
+    pub input: u64,
+    /// Error recorded on the task (default: "Injected failure for <operation>(<input>)")
+    #[serde(default)]
+    pub message: Option<String>,
+    /// Panic instead of failing, crashing the worker so recovery can be tested
+    #[serde(default)]
+    pub panic: bool,
+}
+
+impl InjectedFailure {
+    /// The injected error if `failures` lists this operation and input
+    pub fn check(failures: &[InjectedFailure], operation: &Operation, input: u64) -> Result<(), TaskError> {
+        match failures.iter().find(|failure| failure.operation == *operation && failure.input == input) {
+            Some(failure) if failure.panic => panic!("Injected panic for {}({})", operation, input),
+            Some(failure) => Err(TaskError::CalculationError {
+                message: failure
+                    .message
+                    .clone()
+                    .unwrap_or_else(|| format!("Injected failure for {}({})", operation, input)),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl OrchestratorConfig {
+    /// Check every field, reporting all problems rather than stopping at the first
+    pub fn validate(&self) -> Result<(), ConfigErrors> {
+        let mut errors = ConfigErrors::default();
+
         if self.num_workers == 0 || self.num_workers > 50 {
-            return Err(ValidationError::InvalidWorkerCount(self.num_workers));
+            errors.push("num_workers", ValidationError::InvalidWorkerCount(self.num_workers));
         }
         
         if self.threads_per_worker == 0 || self.threads_per_worker > 32 {
-            return Err(ValidationError::InvalidThreadCount(self.threads_per_worker));
+            errors.push("threads_per_worker", ValidationError::InvalidThreadCount(self.threads_per_worker));
         }
 
-        if self.orchestrator_port <= 1024 {
-            return Err(ValidationError::InvalidPort(self.orchestrator_port));
+        if self.orchestrator_port != 0 && self.orchestrator_port <= 1024 {
+            errors.push("orchestrator_port", ValidationError::InvalidPort(self.orchestrator_port));
         }
 
-        Ok(())
+        if self.bind_address.is_multicast() {
+            errors.push("bind_address", ValidationError::InvalidBindAddress(self.bind_address));
+        }
+
+        if let Some(base_port) = self.base_port {
+            match self.worker_port_range() {
+                None => errors.push("base_port", ValidationError::InvalidPort(base_port)),
+                Some((first, _)) if first <= 1024 => errors.push("base_port", ValidationError::InvalidPort(first)),
+                Some((first, last)) if (first..=last).contains(&self.orchestrator_port) => errors.push(
+                    "base_port",
+                    ValidationError::PortConflict {
+                        orchestrator_port: self.orchestrator_port,
+                        worker_port_range: (first, last),
+                    },
+                ),
+                Some(_) => {}
+            }
+        }
+
+        match (&self.tls_cert_path, &self.tls_key_path) {
+            (Some(_), None) => errors.push("tls_key_path", ValidationError::IncompleteTls { missing: "tls_key_path" }),
+            (None, Some(_)) => errors.push("tls_cert_path", ValidationError::IncompleteTls { missing: "tls_cert_path" }),
+            _ => {}
+        }
+
+        for (index, listener) in self.listeners.iter().enumerate() {
+            let field = format!("listeners[{}]", index);
+            let address = listener.address;
+            if address.port() != 0 && address.port() <= 1024 {
+                errors.push(field, ValidationError::InvalidPort(address.port()));
+            } else if addresses_overlap(address, self.listen_addr())
+                || self.listeners[..index].iter().any(|other| addresses_overlap(address, other.address))
+            {
+                errors.push(field, ValidationError::ListenerConflict(address));
+            }
+        }
+
+        if let Err(e) = self.prime_generation.validate() {
+            errors.push("prime_generation", e);
+        }
+        if let Err(e) = self.big_precision.validate() {
+            errors.push("big_precision", e);
+        }
+        if let Err(e) = self.operation_limits.validate() {
+            errors.push("operation_limits", e);
+        }
+        if let Err(e) = self.webhooks.validate() {
+            errors.push("webhooks", e);
+        }
+        if let Err(e) = self.callbacks.validate() {
+            errors.push("callbacks", e);
+        }
+        if let Err(e) = self.queue_polling.validate() {
+            errors.push("queue_polling", e);
+        }
+        if let Err(e) = self.watchdog.validate() {
+            errors.push("watchdog", e);
+        }
+        if let Err(e) = self.work_stealing.validate() {
+            errors.push("work_stealing", e);
+        }
+        if let Err(e) = self.federation.validate() {
+            errors.push("federation", e);
+        }
+
+        for (index, api_key) in self.api_keys.iter().enumerate() {
+            if let Err(e) = api_key.validate() {
+                errors.push(format!("api_keys[{}]", index), e);
+            }
+        }
+        if let Err(e) = validate_rate_limit(self.rate_limit.requests_per_second, self.rate_limit.burst) {
+            errors.push("rate_limit", e);
+        }
+        for (index, rule) in self.rate_limit.routes.iter().enumerate() {
+            if let Err(e) = rule.validate() {
+                errors.push(format!("rate_limit.routes[{}]", index), e);
+            }
+        }
+
+        if self.worker_speed_factors.len() > self.num_workers {
+            errors.push(
+                "worker_speed_factors",
+                ValidationError::SpeedFactorCount {
+                    given: self.worker_speed_factors.len(),
+                    workers: self.num_workers,
+                },
+            );
+        }
+
+        for (worker, &factor) in self.worker_speed_factors.iter().enumerate() {
+            if !factor.is_finite() || factor <= 0.0 {
+                errors.push(
+                    format!("worker_speed_factors[{}]", worker),
+                    ValidationError::InvalidSpeedFactor { worker, factor },
+                );
+            }
+        }
+
+        if self.stats_stream_interval_seconds == 0 || self.stats_stream_interval_seconds > MAX_STATS_STREAM_INTERVAL_SECS {
+            errors.push(
+                "stats_stream_interval_seconds",
+                ValidationError::InvalidStreamInterval(self.stats_stream_interval_seconds),
+            );
+        }
+
+        if let Some(checkpoint) = &self.stats_checkpoint {
+            if checkpoint.interval_seconds == 0 {
+                errors.push(
+                    "stats_checkpoint.interval_seconds",
+                    ValidationError::InvalidCheckpointInterval(checkpoint.interval_seconds),
+                );
+            }
+        }
+
+        if self.worker_weights.len() > self.num_workers {
+            errors.push(
+                "worker_weights",
+                ValidationError::WorkerWeightCount {
+                    given: self.worker_weights.len(),
+                    workers: self.num_workers,
+                },
+            );
+        }
+        for (worker, &weight) in self.worker_weights.iter().enumerate() {
+            if weight == 0 {
+                errors.push(format!("worker_weights[{}]", worker), ValidationError::InvalidWorkerWeight { worker });
+            }
+        }
+
+        if self.worker_labels.len() > self.num_workers {
+            errors.push(
+                "worker_labels",
+                ValidationError::WorkerLabelCount {
+                    given: self.worker_labels.len(),
+                    workers: self.num_workers,
+                },
+            );
+        }
+
+        for (worker, labels) in self.worker_labels.iter().enumerate() {
+            if let Some(key) = labels.keys().find(|key| key.trim().is_empty()) {
+                errors.push(
+                    format!("worker_labels[{}]", worker),
+                    ValidationError::InvalidWorkerLabel { worker, key: key.clone() },
+                );
+            }
+        }
+
+        if self.worker_batch_sizes.len() > self.num_workers {
+            errors.push(
+                "worker_batch_sizes",
+                ValidationError::BatchSizeCount {
+                    given: self.worker_batch_sizes.len(),
+                    workers: self.num_workers,
+                },
+            );
+        }
+
+        if let Some(url) = &self.primary_url {
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                errors.push("primary_url", ValidationError::InvalidPrimaryUrl(url.clone()));
+            } else if self.task_store.is_none() {
+                errors.push("primary_url", ValidationError::ReplicaWithoutTaskStore);
+            }
+        }
+
+        if !self.inject_failures.is_empty() && !cfg!(debug_assertions) {
+            errors.push("inject_failures", ValidationError::FailureInjectionUnavailable);
+        }
+
+        errors.into_result()
+    }
+
+    /// Address of the main API listener
+    pub fn listen_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.bind_address, self.orchestrator_port)
+    }
+
+    /// Certificate and key of the main listener, when it serves HTTPS
+    pub fn tls(&self) -> Option<TlsConfig> {
+        Some(TlsConfig {
+            cert_path: self.tls_cert_path.clone()?,
+            key_path: self.tls_key_path.clone()?,
+        })
+    }
+
+    /// `https` when the main listener serves TLS, `http` otherwise
+    pub fn url_scheme(&self) -> &'static str {
+        if self.tls().is_some() {
+            "https"
+        } else {
+            "http"
+        }
+    }
+
+    /// Address a client on this host uses to reach the main listener
+    /// (loopback of the same family when bound to every interface)
+    pub fn connect_addr(&self) -> SocketAddr {
+        let ip = match self.bind_address {
+            IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+            IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            ip => ip,
+        };
+        SocketAddr::new(ip, self.orchestrator_port)
+    }
+
+    /// First and last worker diagnostics port, or `None` when disabled or past 65535
+    pub fn worker_port_range(&self) -> Option<(u16, u16)> {
+        let first = self.base_port?;
+        let last = u16::try_from(first as usize + self.num_workers.checked_sub(1)?).ok()?;
+        Some((first, last))
+    }
+
+    /// Speed factor configured for a worker (1.0 when not listed)
+    pub fn speed_factor(&self, worker_id: usize) -> f64 {
+        self.worker_speed_factors
+            .get(worker_id)
+            .copied()
+            .unwrap_or_else(default_speed_factor)
+    }
+
+    /// Labels configured for a worker (empty when not listed)
+    pub fn worker_labels(&self, worker_id: usize) -> BTreeMap<String, String> {
+        self.worker_labels.get(worker_id).cloned().unwrap_or_default()
+    }
+
+    /// Queue polling settings for a worker, with its `worker_batch_sizes` override applied
+    pub fn queue_polling(&self, worker_id: usize) -> QueuePollingConfig {
+        let mut polling = self.queue_polling.clone();
+        if let Some(&batch_size) = self.worker_batch_sizes.get(worker_id) {
+            polling.batch_size = batch_size;
+        }
+        polling
+    }
+
+    /// Whether this instance only serves reads and redirects mutations to a primary
+    pub fn is_read_only(&self) -> bool {
+        self.primary_url.is_some()
     }
 }
 
@@ -338,9 +2893,30 @@ pub enum TaskError {
     
     #[error("Task already exists: {id}")]
     TaskAlreadyExists { id: String },
+
+    #[error("Task {id} is not queued (status {status}), only queued tasks can be reprioritized or updated")]
+    NotQueued { id: String, status: TaskStatus },
+
+    #[error("Task {id} is {status}; only waiting, pending or finished tasks can be deleted without force=true")]
+    NotDeletable { id: String, status: TaskStatus },
+
+    #[error("Worker {worker} is full: all {capacity} stored tasks are unfinished")]
+    StorageFull { worker: usize, capacity: usize },
+
+    #[error("Unknown dependency: {id}")]
+    UnknownDependency { id: String },
+
+    #[error("Worker {worker} queue is full: {depth} tasks queued, max {capacity}")]
+    QueueFull { worker: usize, depth: usize, capacity: usize },
     
     #[error("Calculation error: {message}")]
     CalculationError { message: String },
+
+    #[error("Calculation cancelled")]
+    Cancelled,
+
+    #[error("Calculation timed out after {timeout_ms} ms")]
+    TimedOut { timeout_ms: u64 },
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -350,6 +2926,18 @@ pub enum ValidationError {
     
     #[error("Empty title")]
     EmptyTitle,
+
+    #[error("Nothing to update: give priority and/or title")]
+    EmptyUpdate,
+
+    #[error("Invalid label {0:?}: keys are 1-63 letters, digits, '-', '_', '.' or '/', values up to 255 characters without ','")]
+    InvalidLabel(String),
+
+    #[error("Too many labels, max allowed: {max}")]
+    TooManyLabels { max: usize },
+
+    #[error("timeout_ms must be at least 1")]
+    ZeroTaskTimeout,
     
     #[error("Invalid task type: {0}, expected 'calculation'")]
     InvalidTaskType(String),
@@ -361,6 +2949,50 @@ pub enum ValidationError {
         max_allowed: u64,
     },
     
+    #[error("Input {input} too small for operation {operation}, min allowed: {min_allowed}")]
+    InputTooSmall {
+        operation: Operation,
+        input: u64,
+        min_allowed: u64,
+    },
+    
+    #[error("Pipeline has {stages} stages, max allowed: {max_allowed}")]
+    PipelineTooLong { stages: usize, max_allowed: usize },
+
+    #[error("Task has {inputs} inputs, max allowed: {max_allowed}")]
+    TooManyInputs { inputs: usize, max_allowed: usize },
+
+    #[error("Tasks with inputs can't have a pipeline")]
+    InputsWithPipeline,
+
+    #[error("parallel only applies to tasks with inputs")]
+    ParallelWithoutInputs,
+
+    #[error("Task asks for {retries} retries, max allowed: {max_allowed}")]
+    TooManyRetries { retries: u32, max_allowed: u32 },
+
+    #[error("Retry backoff {backoff_ms}ms is too long, max allowed: {max_ms}ms")]
+    RetryBackoffTooLong { backoff_ms: u64, max_ms: u64 },
+    
+    #[error("Operation {0} does not support algorithm selection")]
+    AlgorithmNotSupported(Operation),
+
+    #[error("Operation {0} needs a second input (input2)")]
+    MissingSecondInput(Operation),
+
+    #[error("Operation {0} takes a single input, input2 is only used by gcd and lcm")]
+    UnexpectedSecondInput(Operation),
+
+    #[error("Operation {0} has no big precision mode, only factorial and fibonacci do")]
+    PrecisionNotSupported(Operation),
+
+    #[error("Input {input} too large for operation {operation}: the result would exceed {max_digits} digits")]
+    TooManyDigits {
+        operation: Operation,
+        input: u64,
+        max_digits: usize,
+    },
+    
     #[error("Invalid worker count: {0}, must be between 1 and 50")]
     InvalidWorkerCount(usize),
     
@@ -370,13 +3002,210 @@ pub enum ValidationError {
     #[error("Invalid port: {0}, must be between 1024 and 65535")]
     InvalidPort(u16),
     
+    #[error("Invalid prime generation max_bits: {0}, must be between 2 and 64")]
+    InvalidPrimeBits(u32),
+    
+    #[error("Invalid big precision max_digits: {0}, must be between 1 and 100000")]
+    InvalidMaxDigits(usize),
+
+    #[error("Invalid {operation} input limit: {max_input}, results only fit up to {ceiling}")]
+    OperationLimitTooLarge { operation: Operation, max_input: u64, ceiling: u64 },
+
+    #[error("Invalid time limit: {0}ms, must be greater than 0")]
+    InvalidTimeLimit(u64),
+    
+    #[error("Invalid speed factor {factor} for worker {worker}, must be a positive number")]
+    InvalidSpeedFactor { worker: usize, factor: f64 },
+    
+    #[error("{given} worker speed factors given for {workers} workers")]
+    SpeedFactorCount { given: usize, workers: usize },
+
+    #[error("Invalid weight 0 for worker {worker}, weights must be at least 1")]
+    InvalidWorkerWeight { worker: usize },
+
+    #[error("{given} worker weights given for {workers} workers")]
+    WorkerWeightCount { given: usize, workers: usize },
+
+    #[error("{given} worker label sets given for {workers} workers")]
+    WorkerLabelCount { given: usize, workers: usize },
+
+    #[error("Invalid label {key:?} for worker {worker}, label names may not be empty")]
+    InvalidWorkerLabel { worker: usize, key: String },
+
+    #[error("{given} worker batch sizes given for {workers} workers")]
+    BatchSizeCount { given: usize, workers: usize },
+    
+    #[error("Invalid webhook endpoint: {0}, must start with http:// or https://")]
+    InvalidWebhookEndpoint(String),
+
+    #[error("Invalid callback URL: {0}, must start with http:// or https://")]
+    InvalidCallbackUrl(String),
+
+    #[error("Give either run_at or delay_seconds, not both")]
+    ConflictingSchedule,
+
+    #[error("Give either depends_on or run_at/delay_seconds, not both")]
+    ScheduledWithDependencies,
+
+    #[error("A task can depend on at most {max} tasks")]
+    TooManyDependencies { max: usize },
+
+    #[error("Invalid dependency {0:?}: must be another task's ID, listed once")]
+    InvalidDependency(String),
+
+    #[error("Tasks can be scheduled at most {max_seconds} seconds ahead")]
+    ScheduleTooFar { max_seconds: u64 },
+
+    #[error("Invalid callback max_attempts: {0}, must be at least 1")]
+    InvalidMaxAttempts(u32),
+    
+    #[error("Invalid namespace: {0:?}, use 1-64 lowercase letters, digits, '-' or '_'")]
+    InvalidNamespace(String),
+    
+    #[error("Invalid API key {0}: key and namespaces must not be empty")]
+    InvalidApiKey(String),
+    
+    #[error("Subscription must include at least one event type")]
+    NoSubscribedEvents,
+    
+    #[error("Invalid webhook batch size: {0}, must be at least 1")]
+    InvalidBatchSize(usize),
+    
+    #[error("Invalid primary URL: {0}, must start with http:// or https://")]
+    InvalidPrimaryUrl(String),
+
+    #[error("A read-only replica needs the task_store its primary writes to")]
+    ReplicaWithoutTaskStore,
+
+    #[error("Invalid federation peer URL: {0}, must start with http:// or https://")]
+    InvalidPeerUrl(String),
+
+    #[error("Invalid federation peer name {0:?}, names must be non-empty and unique")]
+    InvalidPeerName(String),
+    
     #[error("Port conflict: orchestrator port {orchestrator_port} conflicts with worker port range {worker_port_range:?}")]
     PortConflict {
         orchestrator_port: u16,
         worker_port_range: (u16, u16),
     },
+
+    #[error("Invalid bind address: {0}, must be a unicast or unspecified address")]
+    InvalidBindAddress(IpAddr),
+
+    #[error("Invalid max_wait_ms: {0}, must be between 1 and 60000")]
+    InvalidMaxWait(u64),
+
+    #[error("Invalid timeout_ms: {0}, must be between 1 and 60000")]
+    InvalidWaitTimeout(u64),
+
+    #[error("Invalid idempotency key, must be 1 to {max_len} characters")]
+    InvalidIdempotencyKey { max_len: usize },
+
+    #[error("Invalid rate limit: {requests_per_second} requests/s with burst {burst}, the rate must be at least 0 and the burst at least 1")]
+    InvalidRateLimit { requests_per_second: f64, burst: u32 },
+
+    #[error("Invalid rate limit route: {0:?}, must be a route pattern starting with '/'")]
+    InvalidRateLimitRoute(String),
+
+    #[error("Invalid stats_stream_interval_seconds: {0}, must be between 1 and 3600")]
+    InvalidStreamInterval(u64),
+
+    #[error("Invalid stats_checkpoint.interval_seconds: {0}, must be at least 1")]
+    InvalidCheckpointInterval(u64),
+
+    #[error("Unknown field {0:?} in fields selection")]
+    UnknownField(String),
+
+    #[error("Listener {0} overlaps the main listener or an earlier listener")]
+    ListenerConflict(SocketAddr),
+
+    #[error("{missing} is missing: HTTPS needs both tls_cert_path and tls_key_path")]
+    IncompleteTls { missing: &'static str },
+
+    #[error("Invalid worker scale: {requested}, must be between 1 and {max}")]
+    InvalidScale { requested: usize, max: usize },
+    
+    #[error("Cannot purge {0} tasks, only completed, failed or cancelled ones")]
+    UnpurgeableStatus(TaskStatus),
+
+    #[error("Rejected by validator {validator}: {reason}")]
+    Rejected { validator: String, reason: String },
+
+    #[error("inject_failures is only available in debug builds")]
+    FailureInjectionUnavailable,
 }
 
+/// One invalid configuration field
+#[derive(Debug)]
+pub struct FieldError {
+    /// Field path, e.g. `num_workers` or `api_keys[1]`
+    pub field: String,
+    /// Where the value came from (config file, CLI flag, ...), when known
+    pub source: Option<String>,
+    pub error: ValidationError,
+}
+
+impl FieldError {
+    /// Top-level config key the field belongs to (`api_keys` for `api_keys[1]`)
+    pub fn key(&self) -> &str {
+        self.field.split(['.', '[']).next().unwrap_or(&self.field)
+    }
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.source {
+            Some(source) => write!(f, "{} (from {}): {}", self.field, source, self.error),
+            None => write!(f, "{}: {}", self.field, self.error),
+        }
+    }
+}
+
+/// Every problem found by [`OrchestratorConfig::validate`]
+#[derive(Debug, Default)]
+pub struct ConfigErrors {
+    pub errors: Vec<FieldError>,
+}
+
+impl ConfigErrors {
+    pub fn push(&mut self, field: impl Into<String>, error: ValidationError) {
+        self.errors.push(FieldError {
+            field: field.into(),
+            source: None,
+            error,
+        });
+    }
+
+    pub fn into_result(self) -> Result<(), Self> {
+        if self.errors.is_empty() { Ok(()) } else { Err(self) }
+    }
+
+    /// Attach the source of each field, looked up by its top-level key
+    pub fn with_sources(mut self, source: impl Fn(&str) -> Option<String>) -> Self {
+        for error in &mut self.errors {
+            error.source = source(error.key());
+        }
+        self
+    }
+}
+
+impl fmt::Display for ConfigErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.errors.as_slice() {
+            [error] => write!(f, "{}", error),
+            errors => {
+                write!(f, "{} problems", errors.len())?;
+                for error in errors {
+                    write!(f, "\n  - {}", error)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigErrors {}
+
 #[derive(Debug, thiserror::Error)]
 pub enum SystemError {
     #[error("Worker error: {message}")]
@@ -385,8 +3214,18 @@ pub enum SystemError {
     #[error("Orchestrator error: {message}")]
     Orchestrator { message: String },
     
+    #[error("Migration error: {message}")]
+    Migration { message: String },
+
+    #[error("Task store error: {message}")]
+    Store { message: String },
+
+    
     #[error("Configuration error: {0}")]
     Config(#[from] ValidationError),
+
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(#[from] ConfigErrors),
     
     #[error("Task error: {0}")]
     Task(#[from] TaskError),
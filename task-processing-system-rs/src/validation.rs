@@ -0,0 +1,180 @@
+use crate::types::{Task, TaskPriority, ValidationError};
+use std::sync::Arc;
+use tracing::debug;
+
+/// Custom validation rule applied to tasks at intake
+///
+/// Validators run after the built-in `Task::validate` checks, in the order
+/// they were registered. The first validator that returns an error rejects
+/// the task.
+pub trait TaskValidator: Send + Sync {
+    /// Short name used in error messages and logs
+    fn name(&self) -> &str;
+
+    /// Check a task, returning an error to reject it
+    fn validate(&self, task: &Task) -> Result<(), ValidationError>;
+}
+
+/// Ordered chain of task validators
+#[derive(Clone, Default)]
+pub struct ValidatorChain {
+    validators: Vec<Arc<dyn TaskValidator>>,
+}
+
+impl ValidatorChain {
+    /// Create an empty chain (accepts every task)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a validator to the end of the chain
+    pub fn push<V: TaskValidator + 'static>(&mut self, validator: V) {
+        self.validators.push(Arc::new(validator));
+    }
+
+    /// Number of registered validators
+    pub fn len(&self) -> usize {
+        self.validators.len()
+    }
+
+    /// Names of the registered validators, in the order they run
+    pub fn names(&self) -> Vec<String> {
+        self.validators.iter().map(|v| v.name().to_string()).collect()
+    }
+
+    /// Whether the chain has no validators
+    pub fn is_empty(&self) -> bool {
+        self.validators.is_empty()
+    }
+
+    /// Run every validator against the task, stopping at the first failure
+    pub fn validate(&self, task: &Task) -> Result<(), ValidationError> {
+        for validator in &self.validators {
+            if let Err(e) = validator.validate(task) {
+                debug!("Task {} rejected by validator {}: {}", task.id, validator.name(), e);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for ValidatorChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ValidatorChain").field("validators", &self.names()).finish()
+    }
+}
+
+/// Require a minimum priority for tasks with any input over a threshold
+///
+/// Example: inputs over 10^12 must be submitted with High priority.
+#[derive(Debug, Clone)]
+pub struct LargeInputPriorityRule {
+    pub threshold: u64,
+    pub min_priority: TaskPriority,
+}
+
+impl LargeInputPriorityRule {
+    pub fn new(threshold: u64, min_priority: TaskPriority) -> Self {
+        Self {
+            threshold,
+            min_priority,
+        }
+    }
+}
+
+impl TaskValidator for LargeInputPriorityRule {
+    fn name(&self) -> &str {
+        "large_input_priority"
+    }
+
+    fn validate(&self, task: &Task) -> Result<(), ValidationError> {
+        let large = task.data.all_inputs().iter().any(|&n| n > self.threshold);
+        if large && task.priority < self.min_priority {
+            return Err(ValidationError::Rejected {
+                validator: self.name().to_string(),
+                reason: format!(
+                    "inputs over {} require priority {} or higher",
+                    self.threshold, self.min_priority
+                ),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Operation, TaskData};
+
+    struct RejectAll;
+
+    impl TaskValidator for RejectAll {
+        fn name(&self) -> &str {
+            "reject_all"
+        }
+
+        fn validate(&self, _task: &Task) -> Result<(), ValidationError> {
+            Err(ValidationError::Rejected {
+                validator: self.name().to_string(),
+                reason: "nothing is allowed".to_string(),
+            })
+        }
+    }
+
+    fn task(input: u64, priority: TaskPriority) -> Task {
+        Task::new(
+            "Validator test".to_string(),
+            priority,
+            TaskData::new(input, Operation::PrimeCheck),
+        )
+    }
+
+    #[test]
+    fn test_empty_chain_accepts() {
+        let chain = ValidatorChain::new();
+        assert!(chain.is_empty());
+        assert!(chain.validate(&task(5, TaskPriority::Low)).is_ok());
+    }
+
+    #[test]
+    fn test_large_input_priority_rule() {
+        let mut chain = ValidatorChain::new();
+        chain.push(LargeInputPriorityRule::new(1_000_000_000_000, TaskPriority::High));
+
+        assert!(chain.validate(&task(1_000_000_000_000, TaskPriority::Low)).is_ok());
+        assert!(chain.validate(&task(1_000_000_000_001, TaskPriority::High)).is_ok());
+
+        let err = chain
+            .validate(&task(1_000_000_000_001, TaskPriority::Medium))
+            .unwrap_err();
+        assert!(err.to_string().contains("large_input_priority"));
+    }
+
+    #[test]
+    fn test_large_input_priority_rule_checks_batch_inputs() {
+        let rule = LargeInputPriorityRule::new(1_000, TaskPriority::High);
+        let batch = |priority| {
+            Task::new(
+                "Batch validator test".to_string(),
+                priority,
+                TaskData::batch(vec![5, 1_001, 7], Operation::PrimeCheck),
+            )
+        };
+
+        assert!(rule.validate(&batch(TaskPriority::Low)).is_err());
+        assert!(rule.validate(&batch(TaskPriority::High)).is_ok());
+    }
+
+    #[test]
+    fn test_chain_stops_at_first_failure() {
+        let mut chain = ValidatorChain::new();
+        chain.push(RejectAll);
+        chain.push(LargeInputPriorityRule::new(0, TaskPriority::High));
+        assert_eq!(chain.len(), 2);
+
+        let err = chain.validate(&task(10, TaskPriority::Low)).unwrap_err();
+        assert!(err.to_string().contains("reject_all"));
+    }
+}
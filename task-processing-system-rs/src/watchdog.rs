@@ -0,0 +1,102 @@
+//! Heartbeats of processing threads, for spotting threads wedged on one task
+//!
+//! Each processing thread owns a [`ThreadHeartbeat`] and beats it whenever it
+//! starts or finishes a task. A worker's watchdog (see `[watchdog]` in the
+//! config) periodically looks for threads that have been busy with the same
+//! task for longer than `threshold_ms`, e.g. stuck in a pathological
+//! calculation. Calculations can't be interrupted, so a respawned thread's
+//! predecessor is only retired: it exits once its task finally returns.
+
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+pub struct ThreadHeartbeat {
+    origin: Instant,
+    /// Milliseconds after `origin` of the last beat
+    last_beat_ms: AtomicU64,
+    task: Mutex<Option<String>>,
+    /// Already reported for the current task
+    flagged: AtomicBool,
+    retired: AtomicBool,
+}
+
+impl Default for ThreadHeartbeat {
+    fn default() -> Self {
+        Self {
+            origin: Instant::now(),
+            last_beat_ms: AtomicU64::new(0),
+            task: Mutex::new(None),
+            flagged: AtomicBool::new(false),
+            retired: AtomicBool::new(false),
+        }
+    }
+}
+
+impl ThreadHeartbeat {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that the thread is alive and now working on `task_id` (idle for `None`)
+    pub fn beat(&self, task_id: Option<&str>) {
+        *self.task.lock() = task_id.map(str::to_string);
+        self.last_beat_ms.store(self.origin.elapsed().as_millis() as u64, Ordering::Release);
+        self.flagged.store(false, Ordering::Release);
+    }
+
+    /// Task the thread has been busy with for longer than `threshold`, and for how long
+    ///
+    /// Reported once per task; idle threads are never stalled.
+    pub fn flag_if_stalled(&self, threshold: Duration) -> Option<(String, Duration)> {
+        let task = self.task.lock();
+        let task_id = task.as_ref()?;
+        let last_beat = Duration::from_millis(self.last_beat_ms.load(Ordering::Acquire));
+        let busy_for = self.origin.elapsed().saturating_sub(last_beat);
+        if busy_for <= threshold || self.flagged.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        Some((task_id.clone(), busy_for))
+    }
+
+    /// Ask the thread to exit after its current task (a replacement has taken over)
+    pub fn retire(&self) {
+        self.retired.store(true, Ordering::Release);
+    }
+
+    pub fn is_retired(&self) -> bool {
+        self.retired.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flags_busy_threads_once() {
+        let heartbeat = ThreadHeartbeat::new();
+        heartbeat.beat(None);
+        std::thread::sleep(Duration::from_millis(20));
+        // Idle for a while is fine
+        assert!(heartbeat.flag_if_stalled(Duration::from_millis(5)).is_none());
+
+        heartbeat.beat(Some("slow"));
+        assert!(heartbeat.flag_if_stalled(Duration::from_millis(5)).is_none());
+        std::thread::sleep(Duration::from_millis(20));
+        let (task_id, busy_for) = heartbeat.flag_if_stalled(Duration::from_millis(5)).unwrap();
+        assert_eq!(task_id, "slow");
+        assert!(busy_for >= Duration::from_millis(20));
+        assert!(heartbeat.flag_if_stalled(Duration::from_millis(5)).is_none());
+
+        // The next task starts a fresh stall
+        heartbeat.beat(Some("next"));
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(heartbeat.flag_if_stalled(Duration::from_millis(5)).unwrap().0, "next");
+
+        assert!(!heartbeat.is_retired());
+        heartbeat.retire();
+        assert!(heartbeat.is_retired());
+    }
+}
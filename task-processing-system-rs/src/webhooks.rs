@@ -0,0 +1,513 @@
+//! Task webhooks with batching and per-endpoint delivery stats
+//!
+//! Task events are queued and POSTed as `{"events": [...]}`. Endpoints from
+//! the config receive every `task.completed` event; subscriptions registered
+//! through `/subscriptions` receive the events and tasks they selected, signed
+//! with their secret when one is set. A batch is sent once it holds
+//! `batch_size` events or `flush_interval_ms` after its first event, whichever
+//! comes first. Failed deliveries are counted, not retried.
+
+use crate::http_client::{self, HttpClient};
+#[cfg(feature = "client")]
+use crate::signing::hmac_sha256_hex;
+use crate::subscriptions::SubscriptionStore;
+use crate::timestamps::WireFormat;
+use crate::types::{Task, WebhookConfig, WebhookEventType};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+/// Header carrying `sha256=<hex HMAC of the body>` for subscriptions with a secret
+pub const SIGNATURE_HEADER: &str = "x-webhook-signature";
+
+/// One event delivered to webhook endpoints
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookEvent {
+    pub event: WebhookEventType,
+    pub timestamp: DateTime<Utc>,
+    pub task: Task,
+}
+
+impl WebhookEvent {
+    pub fn new(event: WebhookEventType, task: Task) -> Self {
+        Self {
+            event,
+            timestamp: Utc::now(),
+            task,
+        }
+    }
+}
+
+/// Body of a webhook POST
+#[derive(Debug, Serialize)]
+pub struct WebhookBatch<'a> {
+    pub events: Vec<&'a WebhookEvent>,
+}
+
+/// Where one batch goes
+struct DeliveryTarget<'a> {
+    url: String,
+    secret: Option<String>,
+    events: Vec<&'a WebhookEvent>,
+}
+
+/// Delivery counters for one endpoint
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EndpointStats {
+    pub url: String,
+    pub delivered_batches: u64,
+    pub delivered_events: u64,
+    pub failed_batches: u64,
+    pub failed_events: u64,
+    pub avg_latency_ms: f64,
+    pub max_latency_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    #[serde(skip)]
+    total_latency_ms: f64,
+}
+
+impl EndpointStats {
+    fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            ..Self::default()
+        }
+    }
+
+    fn record(&mut self, events: usize, latency: Duration, outcome: Result<(), String>) {
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        match outcome {
+            Ok(()) => {
+                self.delivered_batches += 1;
+                self.delivered_events += events as u64;
+            }
+            Err(error) => {
+                self.failed_batches += 1;
+                self.failed_events += events as u64;
+                self.last_error = Some(error);
+            }
+        }
+        self.total_latency_ms += latency_ms;
+        self.avg_latency_ms = self.total_latency_ms / (self.delivered_batches + self.failed_batches) as f64;
+        self.max_latency_ms = self.max_latency_ms.max(latency_ms);
+    }
+}
+
+/// Response of `GET /admin/webhooks`
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookStatsResponse {
+    pub batch_size: usize,
+    pub flush_interval_ms: u64,
+    pub pending_events: usize,
+    pub endpoints: Vec<EndpointStats>,
+}
+
+/// Queues completion events and delivers them in batches
+pub struct WebhookDispatcher {
+    config: WebhookConfig,
+    client: HttpClient,
+    sender: mpsc::UnboundedSender<WebhookEvent>,
+    receiver: Mutex<Option<mpsc::UnboundedReceiver<WebhookEvent>>>,
+    pending: Arc<Mutex<usize>>,
+    subscriptions: Arc<SubscriptionStore>,
+    stats: Arc<DashMap<String, EndpointStats>>,
+    format: WireFormat,
+}
+
+impl WebhookDispatcher {
+    pub fn new(config: WebhookConfig, subscriptions: Arc<SubscriptionStore>) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let client = http_client::with_timeout(Duration::from_millis(config.timeout_ms));
+        let stats: DashMap<String, EndpointStats> = config
+            .endpoints
+            .iter()
+            .map(|url| (url.clone(), EndpointStats::new(url)))
+            .collect();
+
+        Self {
+            config,
+            client,
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+            pending: Arc::new(Mutex::new(0)),
+            subscriptions,
+            stats: Arc::new(stats),
+            format: WireFormat::default(),
+        }
+    }
+
+    /// Write the tasks in delivered events in `format`
+    pub fn with_wire_format(mut self, format: WireFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Whether any endpoint or subscription would receive events
+    pub fn is_enabled(&self) -> bool {
+        !self.config.endpoints.is_empty() || !self.subscriptions.is_empty()
+    }
+
+    /// Queue an event (no-op when nobody listens)
+    pub fn notify(&self, event: WebhookEventType, task: Task) {
+        if !self.is_enabled() {
+            return;
+        }
+        *self.pending.lock() += 1;
+        if self.sender.send(WebhookEvent::new(event, task)).is_err() {
+            *self.pending.lock() -= 1;
+        }
+    }
+
+    /// Queue a `task.completed` event
+    pub fn notify_completed(&self, task: Task) {
+        self.notify(WebhookEventType::TaskCompleted, task);
+    }
+
+    /// Start the delivery loop; returns `None` if already started
+    pub fn start(&self) -> Option<JoinHandle<()>> {
+        let mut receiver = self.receiver.lock().take()?;
+
+        let client = self.client.clone();
+        let endpoints = self.config.endpoints.clone();
+        let subscriptions = Arc::clone(&self.subscriptions);
+        let batch_size = self.config.batch_size;
+        let flush_interval = Duration::from_millis(self.config.flush_interval_ms);
+        let pending = Arc::clone(&self.pending);
+        let stats = Arc::clone(&self.stats);
+        let format = self.format;
+
+        Some(tokio::spawn(async move {
+            let mut batch = Vec::with_capacity(batch_size);
+            let mut ticker = tokio::time::interval(flush_interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+            loop {
+                let closed = tokio::select! {
+                    event = receiver.recv() => match event {
+                        Some(event) => {
+                            // The interval runs from a batch's first event, so events
+                            // arriving together aren't split by a tick in between
+                            if batch.is_empty() {
+                                ticker.reset();
+                            }
+                            batch.push(event);
+                            if batch.len() < batch_size {
+                                continue;
+                            }
+                            false
+                        }
+                        None => true,
+                    },
+                    _ = ticker.tick() => false,
+                };
+
+                if !batch.is_empty() {
+                    let targets = delivery_targets(&endpoints, &subscriptions, &batch);
+                    deliver(&client, targets, &stats, format).await;
+                    *pending.lock() -= batch.len();
+                    batch.clear();
+                    ticker.reset();
+                }
+                if closed {
+                    break;
+                }
+            }
+        }))
+    }
+
+    /// Per-endpoint delivery stats
+    pub fn stats(&self) -> WebhookStatsResponse {
+        WebhookStatsResponse {
+            batch_size: self.config.batch_size,
+            flush_interval_ms: self.config.flush_interval_ms,
+            pending_events: *self.pending.lock(),
+            endpoints: {
+                let mut endpoints: Vec<EndpointStats> =
+                    self.stats.iter().map(|entry| entry.value().clone()).collect();
+                endpoints.sort_by(|a, b| a.url.cmp(&b.url));
+                endpoints
+            },
+        }
+    }
+}
+
+/// Split a batch between configured endpoints and matching subscriptions
+fn delivery_targets<'a>(
+    endpoints: &[String],
+    subscriptions: &SubscriptionStore,
+    batch: &'a [WebhookEvent],
+) -> Vec<DeliveryTarget<'a>> {
+    let completed: Vec<&WebhookEvent> = batch
+        .iter()
+        .filter(|event| event.event == WebhookEventType::TaskCompleted)
+        .collect();
+
+    let configured = endpoints.iter().map(|url| DeliveryTarget {
+        url: url.clone(),
+        secret: None,
+        events: completed.clone(),
+    });
+    let subscribed = subscriptions.list().into_iter().map(|subscription| DeliveryTarget {
+        events: batch
+            .iter()
+            .filter(|event| subscription.accepts(event.event, &event.task))
+            .collect(),
+        url: subscription.url,
+        secret: subscription.secret,
+    });
+
+    configured
+        .chain(subscribed)
+        .filter(|target| !target.events.is_empty())
+        .collect()
+}
+
+/// POST each target's events concurrently and record the outcomes
+async fn deliver(
+    client: &HttpClient,
+    targets: Vec<DeliveryTarget<'_>>,
+    stats: &DashMap<String, EndpointStats>,
+    format: WireFormat,
+) {
+    let deliveries = targets.iter().map(|target| async move {
+        let started = Instant::now();
+        let outcome = post_batch(client, target, format).await;
+        (started.elapsed(), outcome)
+    });
+
+    let results = futures::future::join_all(deliveries).await;
+    for (target, (latency, outcome)) in targets.iter().zip(results) {
+        let events = target.events.len();
+        match &outcome {
+            Ok(()) => debug!("Delivered {} webhook events to {} in {:?}", events, target.url, latency),
+            Err(e) => warn!("Webhook delivery of {} events to {} failed: {}", events, target.url, e),
+        }
+        stats
+            .entry(target.url.clone())
+            .or_insert_with(|| EndpointStats::new(&target.url))
+            .record(events, latency, outcome);
+    }
+}
+
+#[cfg(feature = "client")]
+async fn post_batch(client: &HttpClient, target: &DeliveryTarget<'_>, format: WireFormat) -> Result<(), String> {
+    let body = format
+        .apply(|| serde_json::to_vec(&WebhookBatch { events: target.events.clone() }))
+        .map_err(|e| e.to_string())?;
+
+    let mut request = client
+        .post(&target.url)
+        .header("content-type", "application/json");
+    if let Some(secret) = &target.secret {
+        request = request.header(SIGNATURE_HEADER, format!("sha256={}", hmac_sha256_hex(secret, &body)));
+    }
+
+    match request.body(body).send().await {
+        Ok(response) if response.status().is_success() => Ok(()),
+        Ok(response) => Err(format!("HTTP {}", response.status())),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[cfg(not(feature = "client"))]
+async fn post_batch(_client: &HttpClient, _target: &DeliveryTarget<'_>, _format: WireFormat) -> Result<(), String> {
+    Err(http_client::UNAVAILABLE.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Operation, SubscriptionFilter, SubscriptionRequest, TaskData, TaskPriority};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use warp::Filter;
+
+    fn sample_task() -> Task {
+        Task::new("Hook".to_string(), TaskPriority::Medium, TaskData::new(3, Operation::Factorial))
+    }
+
+    fn free_port() -> u16 {
+        TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+    }
+
+    /// Receiver that counts requests and events
+    fn spawn_receiver(port: u16) -> (Arc<AtomicUsize>, Arc<AtomicUsize>) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let events = Arc::new(AtomicUsize::new(0));
+        let (r, e) = (Arc::clone(&requests), Arc::clone(&events));
+        let route = warp::post().and(warp::body::json()).map(move |body: serde_json::Value| {
+            r.fetch_add(1, Ordering::SeqCst);
+            e.fetch_add(body["events"].as_array().map(Vec::len).unwrap_or(0), Ordering::SeqCst);
+            warp::reply()
+        });
+        tokio::spawn(warp::serve(route).run(([127, 0, 0, 1], port)));
+        (requests, events)
+    }
+
+    #[test]
+    fn test_endpoint_stats_record() {
+        let mut stats = EndpointStats::new("http://hook");
+        stats.record(3, Duration::from_millis(10), Ok(()));
+        stats.record(2, Duration::from_millis(30), Err("HTTP 500".to_string()));
+
+        assert_eq!(stats.delivered_events, 3);
+        assert_eq!(stats.failed_events, 2);
+        assert_eq!(stats.failed_batches, 1);
+        assert!((stats.avg_latency_ms - 20.0).abs() < 1e-9);
+        assert!((stats.max_latency_ms - 30.0).abs() < 1e-9);
+        assert_eq!(stats.last_error.as_deref(), Some("HTTP 500"));
+    }
+
+    #[test]
+    fn test_events_dropped_without_listeners() {
+        let dispatcher = WebhookDispatcher::new(WebhookConfig::default(), Arc::new(SubscriptionStore::new()));
+        assert!(!dispatcher.is_enabled());
+        dispatcher.notify_completed(sample_task());
+        assert_eq!(dispatcher.stats().pending_events, 0);
+    }
+
+    #[test]
+    fn test_delivery_targets() {
+        let subscriptions = SubscriptionStore::new();
+        subscriptions
+            .create(SubscriptionRequest {
+                url: "http://sub/hook".to_string(),
+                events: vec![WebhookEventType::TaskCreated],
+                filter: SubscriptionFilter {
+                    operations: vec![Operation::Factorial],
+                    priorities: Vec::new(),
+                },
+                secret: Some("s3cret".to_string()),
+            })
+            .unwrap();
+
+        let fibonacci = Task::new("Fib".to_string(), TaskPriority::Low, TaskData::new(3, Operation::Fibonacci));
+        let batch = vec![
+            WebhookEvent::new(WebhookEventType::TaskCreated, sample_task()),
+            WebhookEvent::new(WebhookEventType::TaskCreated, fibonacci),
+            WebhookEvent::new(WebhookEventType::TaskCompleted, sample_task()),
+        ];
+        let targets = delivery_targets(&["http://config/hook".to_string()], &subscriptions, &batch);
+
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[0].url, "http://config/hook");
+        assert_eq!(targets[0].events.len(), 1);
+        assert_eq!(targets[0].events[0].event, WebhookEventType::TaskCompleted);
+        assert_eq!(targets[1].url, "http://sub/hook");
+        assert_eq!(targets[1].secret.as_deref(), Some("s3cret"));
+        assert_eq!(targets[1].events.len(), 1);
+        assert_eq!(targets[1].events[0].task.data.operation, Operation::Factorial);
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_subscription_delivery_is_signed() {
+        let port = free_port();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&received);
+        let route = warp::post()
+            .and(warp::header::optional::<String>(SIGNATURE_HEADER))
+            .and(warp::body::bytes())
+            .map(move |signature: Option<String>, body: warp::hyper::body::Bytes| {
+                sink.lock().push((signature, body.to_vec()));
+                warp::reply()
+            });
+        tokio::spawn(warp::serve(route).run(([127, 0, 0, 1], port)));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let subscriptions = Arc::new(SubscriptionStore::new());
+        subscriptions
+            .create(SubscriptionRequest {
+                url: format!("http://127.0.0.1:{}/hook", port),
+                events: WebhookEventType::ALL.to_vec(),
+                filter: SubscriptionFilter::default(),
+                secret: Some("s3cret".to_string()),
+            })
+            .unwrap();
+        let dispatcher = WebhookDispatcher::new(
+            WebhookConfig {
+                flush_interval_ms: 10,
+                ..WebhookConfig::default()
+            },
+            subscriptions,
+        );
+        let handle = dispatcher.start().unwrap();
+        assert!(dispatcher.start().is_none());
+
+        dispatcher.notify(WebhookEventType::TaskCreated, sample_task());
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let received = received.lock();
+        assert_eq!(received.len(), 1);
+        let (signature, body) = &received[0];
+        assert_eq!(
+            signature.as_deref(),
+            Some(format!("sha256={}", hmac_sha256_hex("s3cret", body)).as_str())
+        );
+        let json: serde_json::Value = serde_json::from_slice(body).unwrap();
+        assert_eq!(json["events"][0]["event"], "task.created");
+        assert_eq!(dispatcher.stats().endpoints[0].delivered_events, 1);
+        handle.abort();
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_events_are_batched() {
+        let port = free_port();
+        let (requests, events) = spawn_receiver(port);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let dispatcher = WebhookDispatcher::new(
+            WebhookConfig {
+                endpoints: vec![format!("http://127.0.0.1:{}/hook", port)],
+                batch_size: 3,
+                flush_interval_ms: 50,
+                ..WebhookConfig::default()
+            },
+            Arc::new(SubscriptionStore::new()),
+        );
+        let handle = dispatcher.start().unwrap();
+
+        for _ in 0..7 {
+            dispatcher.notify_completed(sample_task());
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        // Two full batches plus one flushed by the interval
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+        assert_eq!(events.load(Ordering::SeqCst), 7);
+        let stats = dispatcher.stats();
+        assert_eq!(stats.pending_events, 0);
+        assert_eq!(stats.endpoints[0].delivered_batches, 3);
+        assert_eq!(stats.endpoints[0].delivered_events, 7);
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_failed_delivery_counted() {
+        let dispatcher = WebhookDispatcher::new(
+            WebhookConfig {
+                endpoints: vec![format!("http://127.0.0.1:{}/hook", free_port())],
+                flush_interval_ms: 10,
+                ..WebhookConfig::default()
+            },
+            Arc::new(SubscriptionStore::new()),
+        );
+        let handle = dispatcher.start().unwrap();
+
+        dispatcher.notify_completed(sample_task());
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let stats = dispatcher.stats();
+        assert_eq!(stats.endpoints[0].failed_events, 1);
+        assert!(stats.endpoints[0].last_error.is_some());
+        handle.abort();
+    }
+}
@@ -0,0 +1,197 @@
+//! Work stealing between workers
+//!
+//! Tasks are assigned round-robin, so a burst of slow tasks can leave one
+//! worker with a deep queue while the others idle. With `[work_stealing]`
+//! enabled every worker is registered in one [`StealRegistry`]; a processing
+//! thread that finds its own queue empty (checked at least every
+//! `interval_ms`) takes up to half the queue of the most loaded active peer,
+//! from the back, and queues it locally. Tasks move the same way as on
+//! `POST /admin/rebalance`, result stream readers included, and paused peers
+//! are left alone.
+
+use crate::types::WorkStealingConfig;
+use crate::worker::Worker;
+use parking_lot::RwLock;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// The workers idle threads may steal from
+///
+/// Holds weak references, since every worker keeps the registry itself.
+pub struct StealRegistry {
+    config: WorkStealingConfig,
+    workers: RwLock<Vec<Weak<Worker>>>,
+}
+
+impl StealRegistry {
+    pub fn new(config: WorkStealingConfig) -> Self {
+        Self {
+            config,
+            workers: RwLock::new(Vec::new()),
+        }
+    }
+
+    pub fn register(&self, worker: &Arc<Worker>) {
+        self.workers.write().push(Arc::downgrade(worker));
+    }
+
+    /// How often idle threads look for work
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.config.interval_ms)
+    }
+
+    /// Move queued tasks from the most loaded active peer onto worker
+    /// `thief`, returning how many moved
+    pub async fn steal_for(&self, thief: usize) -> usize {
+        let workers: Vec<Arc<Worker>> = self.workers.read().iter().filter_map(Weak::upgrade).collect();
+        let Some(thief) = workers.iter().find(|worker| worker.id == thief) else {
+            return 0;
+        };
+        if thief.is_paused() {
+            return 0;
+        }
+
+        let mut victim = None;
+        let mut victim_load = 0;
+        for peer in workers.iter().filter(|peer| peer.id != thief.id && !peer.is_paused()) {
+            let load = peer.queue_len().await;
+            if load > victim_load {
+                victim = Some(peer);
+                victim_load = load;
+            }
+        }
+        let Some(victim) = victim.filter(|_| victim_load >= self.config.min_queue.max(1)) else {
+            return 0;
+        };
+
+        let count = (victim_load / 2).clamp(1, self.config.max_batch);
+        let mut stolen = 0;
+        for (task, buffer) in victim.take_queued(count).await {
+            let task_id = task.id.clone();
+            match thief.requeue(task.clone(), buffer).await {
+                Ok(()) => {
+                    victim.release(&task_id);
+                    stolen += 1;
+                }
+                // Thief's queue full, or same ID already on it: put it back where it was
+                Err(e) => {
+                    warn!("Cannot steal task {} for worker {}: {}", task_id, thief.id, e);
+                    victim.put_back(task).await;
+                }
+            }
+        }
+
+        if stolen > 0 {
+            thief.record_stolen(stolen as u64);
+            victim.record_stolen_from(stolen as u64);
+            debug!("Worker {} stole {} queued tasks from worker {}", thief.id, stolen, victim.id);
+        }
+        stolen
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Operation, Task, TaskData, TaskPriority};
+
+    fn config() -> WorkStealingConfig {
+        WorkStealingConfig {
+            enabled: true,
+            interval_ms: 10,
+            min_queue: 2,
+            max_batch: 2,
+        }
+    }
+
+    async fn queue(worker: &Worker, count: usize) {
+        for input in 0..count {
+            let task = Task::new("Queued".to_string(), TaskPriority::Low, TaskData::new(input as u64 + 1, Operation::Factorial));
+            worker.add_task(task).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_steal_from_most_loaded_peer() {
+        let registry = Arc::new(StealRegistry::new(config()));
+        let workers: Vec<Arc<Worker>> = (0..3).map(|id| Arc::new(Worker::new(id, 1))).collect();
+        for worker in &workers {
+            registry.register(worker);
+        }
+        queue(&workers[1], 1).await;
+        queue(&workers[2], 5).await;
+
+        // Half of worker 2's queue, capped at max_batch
+        assert_eq!(registry.steal_for(0).await, 2);
+        assert_eq!(workers[0].queue_len().await, 2);
+        assert_eq!(workers[2].queue_len().await, 3);
+        let queued = workers[0].queue_snapshot().await;
+        assert!(queued.iter().all(|task| workers[0].get_task(&task.id).is_some()));
+        assert!(queued.iter().all(|task| workers[2].get_task(&task.id).is_none()));
+
+        let thief = workers[0].get_stats().await;
+        let victim = workers[2].get_stats().await;
+        assert_eq!((thief.tasks_stolen, thief.tasks_stolen_from), (2, 0));
+        assert_eq!((victim.tasks_stolen, victim.tasks_stolen_from), (0, 2));
+
+        // Paused peers are left alone, and below min_queue nothing moves
+        workers[2].pause();
+        assert_eq!(registry.steal_for(0).await, 0);
+        assert_eq!(workers[1].queue_len().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_steal_respects_max_queue_size() {
+        let registry = Arc::new(StealRegistry::new(config()));
+        let thief = Arc::new(Worker::new(0, 1).with_max_queue_size(2));
+        let victim = Arc::new(Worker::new(1, 1));
+        registry.register(&thief);
+        registry.register(&victim);
+        queue(&thief, 1).await;
+        queue(&victim, 5).await;
+
+        // Room for one; the other stays queued on the victim
+        assert_eq!(registry.steal_for(0).await, 1);
+        assert_eq!(thief.queue_len().await, 2);
+        assert_eq!(victim.queue_len().await, 4);
+        let queued = victim.queue_snapshot().await;
+        assert!(queued.iter().all(|task| victim.get_task(&task.id).is_some()));
+
+        // Full: nothing moves
+        assert_eq!(registry.steal_for(0).await, 0);
+        assert_eq!((thief.queue_len().await, victim.queue_len().await), (2, 4));
+    }
+
+    #[tokio::test]
+    async fn test_idle_threads_steal() {
+        let registry = Arc::new(StealRegistry::new(config()));
+        let busy = Arc::new(
+            Worker::new(0, 1)
+                .with_simulated_processing(Duration::from_millis(50), 1.0)
+                .with_work_stealing(Arc::clone(&registry)),
+        );
+        let idle = Arc::new(Worker::new(1, 1).with_work_stealing(Arc::clone(&registry)));
+        registry.register(&busy);
+        registry.register(&idle);
+        queue(&busy, 8).await;
+
+        for worker in [&busy, &idle] {
+            let worker = Arc::clone(worker);
+            tokio::spawn(async move { worker.start().await });
+        }
+        for _ in 0..100 {
+            if busy.queue_len().await == 0 && idle.queue_len().await == 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        let idle_stats = idle.get_stats().await;
+        assert!(idle_stats.tasks_stolen > 0);
+        assert!(idle_stats.tasks_processed > 0);
+        assert_eq!(busy.get_stats().await.tasks_stolen_from, idle_stats.tasks_stolen);
+        busy.stop().await;
+        idle.stop().await;
+    }
+}
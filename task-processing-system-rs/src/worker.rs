@@ -1,14 +1,38 @@
 #![allow(warnings)]
+use crate::analytics::{AnalyticsSink, TaskRecord};
+use crate::calc_executor::CalculationPool;
 use crate::calculations::Calculator;
+use crate::events::{EventBus, TaskEvent};
+use crate::latency::{LatencyHistogram, OperationLatencies};
+use crate::metrics::MetricsSink;
+use crate::result_stream::ResultBuffer;
+use crate::signing::ResultSigner;
+use crate::task_queue::TaskQueue;
+use crate::task_store::TaskStore;
 use crate::types::*;
+use crate::watchdog::ThreadHeartbeat;
+use crate::task_index::TaskIndex;
+use crate::work_stealing::StealRegistry;
+use chrono::Utc;
 use dashmap::DashMap;
-use std::collections::VecDeque;
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use hdrhistogram::Histogram;
+use futures::stream::BoxStream;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::{Mutex, Notify};
 use tokio::task::JoinHandle;
-use tracing::{debug, error, info};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+/// Longest the retention sweeper waits between looks for expired tasks
+const MAX_SWEEP_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How often finished tasks are checked against `ttl`: twice per TTL, within 100ms and [`MAX_SWEEP_INTERVAL`]
+fn sweep_interval(ttl: Duration) -> Duration {
+    (ttl / 2).clamp(Duration::from_millis(100), MAX_SWEEP_INTERVAL)
+}
 
 /// Worker node that processes tasks
 pub struct Worker {
@@ -17,18 +41,75 @@ pub struct Worker {
     
     // Task storage and processing
     tasks: Arc<DashMap<String, Task>>,
-    task_queue: Arc<Mutex<VecDeque<Task>>>,
+    task_queue: Arc<Mutex<TaskQueue>>,
     queue_notify: Arc<Notify>,
+    result_buffers: Arc<DashMap<String, Arc<ResultBuffer>>>,
+    /// Cancellation tokens of the tasks processing threads are calculating
+    in_flight: Arc<DashMap<String, CancellationToken>>,
+    signer: Option<Arc<ResultSigner>>,
+    speed_factor: f64,
+    simulated_delay: Duration,
+    labels: BTreeMap<String, String>,
+    polling: QueuePollingConfig,
+    /// Storage cap (0 = unlimited) and IDs of finished tasks with when they
+    /// finished, oldest first, for eviction and expiry
+    max_tasks: usize,
+    /// Most tasks `add_task` lets wait in the queue (0 = unlimited)
+    max_queue_size: usize,
+    finished_order: Arc<parking_lot::Mutex<VecDeque<(String, Instant)>>>,
+    /// How long finished tasks are kept (forever when unset)
+    task_ttl: Option<Duration>,
+    /// Longest calculation attempt of a task without `timeout_ms` (unlimited when unset)
+    task_timeout: Option<Duration>,
+    events: Arc<EventBus>,
+    injected_failures: Arc<[InjectedFailure]>,
+    /// Runs calculations off the async runtime's threads
+    calculations: Arc<CalculationPool>,
+    /// Settings the calculations run with, shared with the orchestrator
+    calculator: Arc<Calculator>,
+    analytics: Option<Arc<AnalyticsSink>>,
+    watchdog: WatchdogConfig,
+    /// Peers idle threads take queued tasks from (work stealing disabled when unset)
+    stealing: Option<Arc<StealRegistry>>,
+    /// Shared record of which worker holds each task, kept current as tasks come and go
+    task_index: Option<Arc<TaskIndex>>,
+    /// Heartbeat of each processing thread, replaced when the thread is respawned
+    heartbeats: Arc<DashMap<usize, Arc<ThreadHeartbeat>>>,
+    metrics: parking_lot::RwLock<Option<Arc<dyn MetricsSink>>>,
+    task_store: parking_lot::RwLock<Option<Arc<dyn TaskStore>>>,
     
     // Statistics
     tasks_processed: Arc<AtomicU64>,
     tasks_completed: Arc<AtomicU64>,
     tasks_failed: Arc<AtomicU64>,
+    tasks_cancelled: Arc<AtomicU64>,
+    tasks_deleted: AtomicU64,
+    task_retries: Arc<AtomicU64>,
+    result_mismatches: Arc<AtomicU64>,
+    storage_evictions: Arc<AtomicU64>,
+    tasks_expired: AtomicU64,
+    wedged_threads: Arc<AtomicU64>,
+    threads_respawned: Arc<AtomicU64>,
+    tasks_stolen: AtomicU64,
+    tasks_stolen_from: AtomicU64,
+    restarts: AtomicU64,
+    busy_threads: Arc<AtomicUsize>,
+    latency: Arc<LatencyHistogram>,
+    /// Calculation time of each operation
+    processing_latency: Arc<OperationLatencies>,
     start_time: Instant,
     
     // Control
     running: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+    /// Held for maintenance: still takes new tasks but leaves them queued
+    processing_paused: Arc<AtomicBool>,
+    /// Set when a processing thread panics; the worker shuts down until restarted
+    crashed: Arc<AtomicBool>,
+    /// Tasks whose calculation was under way when their thread panicked
+    crashed_tasks: Arc<parking_lot::Mutex<Vec<String>>>,
     shutdown_notify: Arc<Notify>,
+    started_notify: Arc<Notify>,
 }
 
 #[derive(Debug, Clone)]
@@ -47,14 +128,186 @@ impl Worker {
                 num_threads,
             },
             tasks: Arc::new(DashMap::new()),
-            task_queue: Arc::new(Mutex::new(VecDeque::new())),
+            task_queue: Arc::new(Mutex::new(TaskQueue::new())),
             queue_notify: Arc::new(Notify::new()),
+            result_buffers: Arc::new(DashMap::new()),
+            in_flight: Arc::new(DashMap::new()),
+            signer: None,
+            speed_factor: 1.0,
+            simulated_delay: Duration::ZERO,
+            labels: BTreeMap::new(),
+            polling: QueuePollingConfig::default(),
+            max_tasks: 0,
+            max_queue_size: 0,
+            finished_order: Arc::new(parking_lot::Mutex::new(VecDeque::new())),
+            task_ttl: None,
+            task_timeout: None,
+            events: Arc::new(EventBus::new()),
+            injected_failures: Arc::from([]),
+            calculations: Arc::new(CalculationPool::default()),
+            calculator: Arc::new(Calculator::default()),
+            analytics: None,
+            watchdog: WatchdogConfig::default(),
+            stealing: None,
+            task_index: None,
+            heartbeats: Arc::new(DashMap::new()),
+            metrics: parking_lot::RwLock::new(None),
+            task_store: parking_lot::RwLock::new(None),
             tasks_processed: Arc::new(AtomicU64::new(0)),
             tasks_completed: Arc::new(AtomicU64::new(0)),
             tasks_failed: Arc::new(AtomicU64::new(0)),
+            tasks_cancelled: Arc::new(AtomicU64::new(0)),
+            tasks_deleted: AtomicU64::new(0),
+            task_retries: Arc::new(AtomicU64::new(0)),
+            result_mismatches: Arc::new(AtomicU64::new(0)),
+            storage_evictions: Arc::new(AtomicU64::new(0)),
+            tasks_expired: AtomicU64::new(0),
+            wedged_threads: Arc::new(AtomicU64::new(0)),
+            threads_respawned: Arc::new(AtomicU64::new(0)),
+            tasks_stolen: AtomicU64::new(0),
+            tasks_stolen_from: AtomicU64::new(0),
+            restarts: AtomicU64::new(0),
+            busy_threads: Arc::new(AtomicUsize::new(0)),
+            latency: Arc::new(LatencyHistogram::new()),
+            processing_latency: Arc::new(OperationLatencies::new()),
             start_time: Instant::now(),
             running: Arc::new(AtomicBool::new(false)),
+            paused: Arc::new(AtomicBool::new(false)),
+            processing_paused: Arc::new(AtomicBool::new(false)),
+            crashed: Arc::new(AtomicBool::new(false)),
+            crashed_tasks: Arc::new(parking_lot::Mutex::new(Vec::new())),
             shutdown_notify: Arc::new(Notify::new()),
+            started_notify: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Sign every processed result with the given HMAC key
+    pub fn with_signing_key(mut self, key: impl AsRef<[u8]>) -> Self {
+        self.signer = Some(Arc::new(ResultSigner::new(key)));
+        self
+    }
+
+    /// Add `base * speed_factor` of simulated processing time to every task
+    ///
+    /// Used to emulate heterogeneous hardware in load-balancing experiments.
+    pub fn with_simulated_processing(mut self, base: Duration, speed_factor: f64) -> Self {
+        self.speed_factor = speed_factor;
+        self.simulated_delay = base.mul_f64(speed_factor);
+        self
+    }
+
+    /// Attach labels reported in [`Worker::snapshot`]
+    pub fn with_labels(mut self, labels: BTreeMap<String, String>) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    /// Wait for and take queued tasks as configured by `polling`
+    pub fn with_queue_polling(mut self, polling: QueuePollingConfig) -> Self {
+        self.polling = polling;
+        self
+    }
+
+    /// Store at most `max_tasks` tasks (0 means unlimited), evicting the
+    /// oldest completed or failed ones to make room
+    pub fn with_max_tasks(mut self, max_tasks: usize) -> Self {
+        self.max_tasks = max_tasks;
+        self
+    }
+
+    /// Refuse new tasks with [`TaskError::QueueFull`] while `max_queue_size`
+    /// are waiting in the queue (0 means unlimited)
+    pub fn with_max_queue_size(mut self, max_queue_size: usize) -> Self {
+        self.max_queue_size = max_queue_size;
+        self
+    }
+
+    /// Drop completed, failed and cancelled tasks `ttl` after they finished
+    pub fn with_task_ttl(mut self, ttl: Duration) -> Self {
+        self.task_ttl = Some(ttl).filter(|ttl| !ttl.is_zero());
+        self
+    }
+
+    /// Fail a calculation attempt with [`TaskError::TimedOut`] after `timeout`
+    /// (zero means no limit), unless the task sets its own `timeout_ms`
+    pub fn with_task_timeout(mut self, timeout: Duration) -> Self {
+        self.task_timeout = Some(timeout).filter(|timeout| !timeout.is_zero());
+        self
+    }
+
+    /// Record every finished calculation in `analytics`
+    pub fn with_analytics(mut self, analytics: Arc<AnalyticsSink>) -> Self {
+        self.analytics = Some(analytics);
+        self
+    }
+
+    /// Publish this worker's task lifecycle events on `events`
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Fail these (operation, input) calculations instead of running them
+    pub fn with_injected_failures(mut self, failures: Vec<InjectedFailure>) -> Self {
+        self.injected_failures = failures.into();
+        self
+    }
+
+    /// Run calculations as `strategy` says; `dedicated` gives the worker one
+    /// calculation thread per processing thread
+    pub fn with_calculation_executor(mut self, strategy: CalculationExecutor) -> Self {
+        self.calculations = Arc::new(CalculationPool::new(strategy, self.id, self.config.num_threads));
+        self
+    }
+
+    /// Validate and calculate tasks with `calculator`'s settings
+    pub fn with_calculator(mut self, calculator: Arc<Calculator>) -> Self {
+        self.calculator = calculator;
+        self
+    }
+
+    /// Flag (and optionally replace) processing threads stuck on one task as configured by `watchdog`
+    pub fn with_watchdog(mut self, watchdog: WatchdogConfig) -> Self {
+        self.watchdog = watchdog;
+        self
+    }
+
+    /// Let idle processing threads take queued tasks from the peers in `registry`
+    pub fn with_work_stealing(mut self, registry: Arc<StealRegistry>) -> Self {
+        self.stealing = Some(registry);
+        self
+    }
+
+    /// Record the tasks this worker holds in `index`, shared with its peers
+    pub fn with_task_index(mut self, index: Arc<TaskIndex>) -> Self {
+        self.task_index = Some(index);
+        self
+    }
+
+    /// The task index shared with peers, if any
+    pub fn task_index(&self) -> Option<&Arc<TaskIndex>> {
+        self.task_index.as_ref()
+    }
+
+    /// Report watchdog findings to `sink` (set by the orchestrator before starting)
+    pub fn set_metrics_sink(&self, sink: Arc<dyn MetricsSink>) {
+        *self.metrics.write() = Some(sink);
+    }
+
+    /// Save every change to a task in `store` (set by the orchestrator before starting)
+    pub fn set_task_store(&self, store: Arc<dyn TaskStore>) {
+        *self.task_store.write() = Some(store);
+    }
+
+    fn persist(&self, task: &Task) {
+        if let Some(store) = self.task_store.read().as_ref() {
+            store.save(task);
+        }
+    }
+
+    fn unpersist(&self, task_id: &str) {
+        if let Some(store) = self.task_store.read().as_ref() {
+            store.remove(task_id);
         }
     }
 
@@ -68,6 +321,9 @@ impl Worker {
     
         info!("Starting worker {} (internal only)", self.id);
         self.running.store(true, Ordering::Release);
+        // Threads of a crashed run never got to mark themselves idle
+        self.crashed.store(false, Ordering::Release);
+        self.busy_threads.store(0, Ordering::Relaxed);
     
         // Start processing threads only
         let mut thread_handles = Vec::new();
@@ -77,11 +333,32 @@ impl Worker {
         }
     
         info!("Worker {} started successfully with {} threads", self.id, self.config.num_threads);
+        self.started_notify.notify_waiters();
     
-        // Wait for shutdown signal
-        self.shutdown_notify.notified().await;
+        // Wait for shutdown signal, checking on the processing threads and
+        // dropping expired tasks meanwhile
+        let shutdown = self.shutdown_notify.notified();
+        tokio::pin!(shutdown);
+        let threshold = self.watchdog.threshold();
+        let mut checks = tokio::time::interval(Duration::from_millis(self.watchdog.check_interval_ms.max(1)));
+        let mut sweeps = tokio::time::interval(self.task_ttl.map_or(MAX_SWEEP_INTERVAL, sweep_interval));
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => break,
+                _ = checks.tick(), if threshold.is_some() => {
+                    thread_handles.extend(threshold.map(|threshold| self.check_threads(threshold)).into_iter().flatten())
+                }
+                _ = sweeps.tick(), if self.task_ttl.is_some() => {
+                    self.expire_finished(Instant::now());
+                }
+            }
+        }
         
-        info!("Shutting down worker {}", self.id);
+        if self.is_crashed() {
+            error!("Worker {} crashed: a processing thread panicked", self.id);
+        } else {
+            info!("Shutting down worker {}", self.id);
+        }
         self.running.store(false, Ordering::Release);
     
         // Cancel processing threads only
@@ -92,6 +369,23 @@ impl Worker {
         Ok(())
     }
 
+    /// Whether [`start`](Self::start) is running and hasn't been stopped
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+
+    /// Resolve once the worker is running (immediately if it already is)
+    pub async fn wait_running(&self) {
+        loop {
+            // Created before the check, so a start in between still wakes it
+            let started = self.started_notify.notified();
+            if self.is_running() {
+                return;
+            }
+            started.await;
+        }
+    }
+
     /// Stop the worker gracefully
     pub async fn stop(&self) {
         info!("Stopping worker {}", self.id);
@@ -99,28 +393,131 @@ impl Worker {
         self.shutdown_notify.notify_waiters();
     }
 
+    /// Stop taking tasks off the queue; queued tasks wait until [`resume`](Self::resume)
+    pub fn pause(&self) {
+        info!("Pausing worker {}", self.id);
+        self.paused.store(true, Ordering::Release);
+    }
+
+    /// Take tasks off the queue again
+    pub fn resume(&self) {
+        info!("Resuming worker {}", self.id);
+        self.paused.store(false, Ordering::Release);
+        // Stores a permit if every thread is between waits, so the queue is never stranded
+        self.queue_notify.notify_one();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Acquire)
+    }
+
+    /// Keep taking new tasks but stop taking them off the queue until [`resume_processing`](Self::resume_processing)
+    pub fn pause_processing(&self) {
+        info!("Pausing processing on worker {}", self.id);
+        self.processing_paused.store(true, Ordering::Release);
+    }
+
+    /// Take queued tasks off the queue again
+    pub fn resume_processing(&self) {
+        info!("Resuming processing on worker {}", self.id);
+        self.processing_paused.store(false, Ordering::Release);
+        self.queue_notify.notify_one();
+    }
+
+    pub fn is_processing_paused(&self) -> bool {
+        self.processing_paused.load(Ordering::Acquire)
+    }
+
+    /// Whether a processing thread panicked, taking the worker down until it is restarted
+    pub fn is_crashed(&self) -> bool {
+        self.crashed.load(Ordering::Acquire)
+    }
+
+    /// Count a restart after a crash
+    pub(crate) fn record_restart(&self) {
+        self.restarts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Clear up after a crash, returning the tasks to queue again
+    ///
+    /// The task whose calculation panicked fails, so it can't take the next
+    /// worker down too. Tasks other threads were calculating when the worker
+    /// went down come back along with the queued ones; they stay visible
+    /// through [`get_task`](Self::get_task) until [`release`](Self::release).
+    pub(crate) async fn take_stranded(&self) -> Vec<(Task, Arc<ResultBuffer>)> {
+        let crashed = std::mem::take(&mut *self.crashed_tasks.lock());
+        for task_id in &crashed {
+            self.in_flight.remove(task_id);
+            if let Some(mut entry) = self.tasks.get_mut(task_id).filter(|entry| entry.status == TaskStatus::Pending) {
+                entry.set_failed(format!("Worker {} crashed while processing the task", self.id));
+                self.tasks_failed.fetch_add(1, Ordering::Relaxed);
+                self.persist(&entry);
+                self.events.emit(TaskEvent::Failed, &entry);
+                drop(entry);
+                Self::record_finished(&self.finished_order, self.tracks_finished(), task_id);
+            }
+            if let Some((_, buffer)) = self.result_buffers.remove(task_id) {
+                buffer.finish();
+            }
+        }
+
+        let interrupted: Vec<String> = self.in_flight.iter().map(|entry| entry.key().clone()).collect();
+        let mut stranded = Vec::new();
+        for task_id in interrupted {
+            self.in_flight.remove(&task_id);
+            let Some(task) = self.tasks.get(&task_id).filter(|task| task.status == TaskStatus::Pending).map(|task| task.clone()) else {
+                continue;
+            };
+            let buffer = self
+                .result_buffers
+                .get(&task_id)
+                .map(|entry| Arc::clone(entry.value()))
+                .unwrap_or_default();
+            stranded.push((task, buffer));
+        }
+        stranded.extend(self.take_queued(usize::MAX).await);
+        stranded
+    }
+
     /// Add a task to the worker's queue
     pub async fn add_task(&self, task: Task) -> Result<(), TaskError> {
         debug!("Worker {} received task {}", self.id, task.id);
         
         // Validate task before adding
-        task.validate().map_err(|e| TaskError::CalculationError {
+        task.validate(&self.calculator).map_err(|e| TaskError::CalculationError {
             message: format!("Task validation failed: {}", e),
         })?;
 
-        // Store task
-        if self.tasks.contains_key(&task.id) {
-            return Err(TaskError::TaskAlreadyExists { id: task.id });
+        // Held until the task is queued, so concurrent submissions can't overshoot the limit
+        let mut queue = self.task_queue.lock().await;
+        if self.max_queue_size > 0 && queue.len() >= self.max_queue_size {
+            return Err(TaskError::QueueFull {
+                worker: self.id,
+                depth: queue.len(),
+                capacity: self.max_queue_size,
+            });
         }
 
+        // Claimed across workers first, so two submissions of one ID can't both be queued
         let task_id = task.id.clone();
-        self.tasks.insert(task_id.clone(), task.clone());
+        if let Some(index) = &self.task_index {
+            if !index.claim(&task_id, self.id) {
+                return Err(TaskError::TaskAlreadyExists { id: task_id });
+            }
+        }
 
-        // Add to task queue
-        {
-            let mut queue = self.task_queue.lock().await;
-            queue.push_back(task);
+        // Store task
+        if let Err(e) = self.store(task.clone(), Arc::new(ResultBuffer::new())) {
+            self.unindex(&task_id);
+            return Err(e);
         }
+        self.persist(&task);
+        // Before queueing, so it always precedes the task's processed/failed event
+        self.events.emit(TaskEvent::Created, &task);
+
+        // Add to task queue
+        queue.push(task);
+        drop(queue);
 
         // Notify processing threads
         self.queue_notify.notify_one();
@@ -134,6 +531,319 @@ impl Worker {
         self.tasks.get(task_id).map(|entry| entry.clone())
     }
 
+    /// Copy of every stored task
+    pub fn tasks(&self) -> Vec<Task> {
+        self.tasks.iter().map(|entry| entry.value().clone()).collect()
+    }
+
+    /// Stage results of a task as they are computed, ending when it finishes
+    pub fn result_stream(&self, task_id: &str) -> Option<BoxStream<'static, StageResult>> {
+        if let Some(buffer) = self.result_buffers.get(task_id) {
+            return Some(buffer.stream());
+        }
+        let task = self.get_task(task_id)?;
+        Some(ResultBuffer::finished(&task).stream())
+    }
+
+    /// 1-based position of a queued task, in the order threads will take it
+    pub async fn queue_position(&self, task_id: &str) -> Option<usize> {
+        self.task_queue.lock().await.position(task_id)
+    }
+
+    /// Change a queued task's priority and move it in the queue accordingly
+    ///
+    /// The task takes its place among the queued tasks of its new priority by
+    /// age. Returns the old priority and the task's new 1-based position.
+    pub async fn set_priority(&self, task_id: &str, priority: TaskPriority) -> Result<(TaskPriority, usize), TaskError> {
+        let (previous, position) = {
+            let mut queue = self.task_queue.lock().await;
+            let Some(moved) = queue.set_priority(task_id, priority) else {
+                return Err(self.not_queued(task_id));
+            };
+            moved
+        };
+        if let Some(mut stored) = self.tasks.get_mut(task_id) {
+            stored.priority = priority;
+            self.persist(&stored);
+        }
+        debug!("Worker {} moved task {} from {:?} to {:?}, now at {}", self.id, task_id, previous, priority, position);
+        Ok((previous, position))
+    }
+
+    /// Change a queued task's priority and/or title
+    ///
+    /// A new priority moves the task in the queue as [`Worker::set_priority`]
+    /// does. Returns the updated task, with its `queue_position`, and its old
+    /// priority.
+    pub async fn update_task(&self, task_id: &str, update: &TaskUpdateRequest) -> Result<(Task, TaskPriority), TaskError> {
+        // Queue locked throughout, so no thread takes the task half updated
+        let mut queue = self.task_queue.lock().await;
+        let Some(queued) = queue.get(task_id) else {
+            return Err(self.not_queued(task_id));
+        };
+        let mut previous = queued.priority;
+        // The queued copy is what gets calculated and stored, so it's updated too
+        if let Some(title) = &update.title {
+            queue.set_title(task_id, title);
+        }
+        if let Some(priority) = update.priority {
+            (previous, _) = queue.set_priority(task_id, priority).expect("queued above");
+        }
+        let position = queue.position(task_id);
+        let Some(mut stored) = self.tasks.get_mut(task_id) else {
+            return Err(TaskError::TaskNotFound {
+                id: task_id.to_string(),
+            });
+        };
+        if let Some(title) = &update.title {
+            stored.title = title.clone();
+        }
+        stored.priority = update.priority.unwrap_or(stored.priority);
+        self.persist(&stored);
+        let mut task = stored.clone();
+        task.queue_position = position;
+        debug!("Worker {} updated task {}, now at {:?}", self.id, task_id, position);
+        Ok((task, previous))
+    }
+
+    /// Why `task_id` can't be changed in the queue: unknown, or no longer queued
+    fn not_queued(&self, task_id: &str) -> TaskError {
+        match self.get_task(task_id) {
+            Some(task) => TaskError::NotQueued {
+                id: task.id,
+                status: task.status,
+            },
+            None => TaskError::TaskNotFound {
+                id: task_id.to_string(),
+            },
+        }
+    }
+
+    /// Queued tasks in the order threads will take them
+    pub async fn queue_snapshot(&self) -> Vec<QueuedTask> {
+        let queue = self.task_queue.lock().await;
+        queue
+            .iter()
+            .enumerate()
+            .map(|(index, task)| QueuedTask {
+                position: index + 1,
+                id: task.id.clone(),
+                priority: task.priority,
+                operation: task.data.operation.clone(),
+                input: task.data.input,
+                created_at: task.created_at,
+            })
+            .collect()
+    }
+
+    pub async fn queue_len(&self) -> usize {
+        self.task_queue.lock().await.len()
+    }
+
+    /// Whether new tasks would be refused with [`TaskError::QueueFull`]
+    pub async fn is_queue_full(&self) -> bool {
+        self.max_queue_size > 0 && self.queue_len().await >= self.max_queue_size
+    }
+
+    /// Take up to `count` tasks off the back of the queue (lowest priority,
+    /// newest), with their result buffers
+    ///
+    /// The tasks stay visible through [`get_task`](Self::get_task) until
+    /// [`release`](Self::release), so they can be re-queued elsewhere first.
+    pub(crate) async fn take_queued(&self, count: usize) -> Vec<(Task, Arc<ResultBuffer>)> {
+        let taken = self.task_queue.lock().await.take_back(count);
+        taken
+            .into_iter()
+            .map(|task| {
+                let buffer = self
+                    .result_buffers
+                    .get(&task.id)
+                    .map(|entry| Arc::clone(entry.value()))
+                    .unwrap_or_default();
+                (task, buffer)
+            })
+            .collect()
+    }
+
+    /// Queue a task taken from another worker, keeping its streaming readers
+    ///
+    /// Refused with [`TaskError::QueueFull`] like a submission; the caller
+    /// then [puts it back](Self::put_back) on the worker it came from.
+    pub(crate) async fn requeue(&self, task: Task, buffer: Arc<ResultBuffer>) -> Result<(), TaskError> {
+        // Held until the task is queued, so moves can't overshoot the limit either
+        let mut queue = self.task_queue.lock().await;
+        if self.max_queue_size > 0 && queue.len() >= self.max_queue_size {
+            return Err(TaskError::QueueFull {
+                worker: self.id,
+                depth: queue.len(),
+                capacity: self.max_queue_size,
+            });
+        }
+        self.store(task.clone(), buffer)?;
+        queue.push(task);
+        drop(queue);
+        self.queue_notify.notify_one();
+        Ok(())
+    }
+
+    /// Queue again a task taken with [`take_queued`](Self::take_queued) or
+    /// [`take_stranded`](Self::take_stranded) that no peer could take
+    ///
+    /// The task never left this worker, so `max_queue_size` doesn't apply.
+    pub(crate) async fn put_back(&self, task: Task) {
+        self.task_queue.lock().await.push(task);
+        self.queue_notify.notify_one();
+    }
+
+    /// Take back a task loaded from the task store, queueing it if it's still pending
+    pub(crate) async fn restore(&self, task: Task) -> Result<(), TaskError> {
+        let task_id = task.id.clone();
+        let status = task.status.clone();
+        self.store(task.clone(), Arc::new(ResultBuffer::new()))?;
+        if status == TaskStatus::Pending {
+            self.task_queue.lock().await.push(task);
+            self.queue_notify.notify_one();
+        } else {
+            // Already calculated, so readers get the stored result
+            self.result_buffers.remove(&task_id);
+            if status.is_terminal() {
+                Self::record_finished(&self.finished_order, self.tracks_finished(), &task_id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Keep a new task and its result buffer, evicting finished tasks if storage is full
+    fn store(&self, task: Task, buffer: Arc<ResultBuffer>) -> Result<(), TaskError> {
+        // Held throughout, so concurrent submissions can't overshoot the cap
+        let mut finished = self.finished_order.lock();
+        if self.tasks.contains_key(&task.id) {
+            return Err(TaskError::TaskAlreadyExists { id: task.id });
+        }
+        if self.max_tasks > 0 {
+            while self.tasks.len() >= self.max_tasks {
+                let Some((oldest, _)) = finished.pop_front() else {
+                    return Err(TaskError::StorageFull {
+                        worker: self.id,
+                        capacity: self.max_tasks,
+                    });
+                };
+                // Entries for tasks purged (or replaced) since are skipped
+                if self.tasks.remove_if(&oldest, |_, task| task.status.is_terminal()).is_some() {
+                    self.result_buffers.remove(&oldest);
+                    self.unindex(&oldest);
+                    self.unpersist(&oldest);
+                    self.storage_evictions.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+        // Buffer first: a task without one is always finished
+        self.result_buffers.insert(task.id.clone(), buffer);
+        if let Some(index) = &self.task_index {
+            index.insert(&task.id, self.id);
+        }
+        self.tasks.insert(task.id.clone(), task);
+        Ok(())
+    }
+
+    /// Drop `task_id` from the shared index unless a peer holds it by now
+    fn unindex(&self, task_id: &str) {
+        if let Some(index) = &self.task_index {
+            index.remove(task_id, self.id);
+        }
+    }
+
+    /// Make a completed or failed task the newest eviction (and expiry) candidate
+    fn record_finished(finished_order: &parking_lot::Mutex<VecDeque<(String, Instant)>>, tracked: bool, task_id: &str) {
+        if tracked {
+            finished_order.lock().push_back((task_id.to_string(), Instant::now()));
+        }
+    }
+
+    /// Whether finished tasks are tracked, for a storage cap or a TTL
+    fn tracks_finished(&self) -> bool {
+        self.max_tasks > 0 || self.task_ttl.is_some()
+    }
+
+    /// Drop finished tasks older than the TTL as of `now`, returning how many were dropped
+    pub fn expire_finished(&self, now: Instant) -> usize {
+        let Some(ttl) = self.task_ttl else {
+            return 0;
+        };
+        let mut expired = 0;
+        let mut finished = self.finished_order.lock();
+        while let Some((task_id, finished_at)) = finished.front() {
+            if now.saturating_duration_since(*finished_at) < ttl {
+                break;
+            }
+            let task_id = task_id.clone();
+            finished.pop_front();
+            // Entries for tasks purged, evicted or replaced since are skipped
+            if self.tasks.remove_if(&task_id, |_, task| task.status.is_terminal()).is_some() {
+                self.result_buffers.remove(&task_id);
+                self.unindex(&task_id);
+                self.unpersist(&task_id);
+                expired += 1;
+            }
+        }
+        drop(finished);
+        if expired > 0 {
+            self.tasks_expired.fetch_add(expired as u64, Ordering::Relaxed);
+            debug!("Expired {} finished tasks on worker {}", expired, self.id);
+        }
+        expired
+    }
+
+    /// Count tasks this worker took from a peer's queue
+    pub(crate) fn record_stolen(&self, count: u64) {
+        self.tasks_stolen.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Count tasks a peer took from this worker's queue
+    pub(crate) fn record_stolen_from(&self, count: u64) {
+        self.tasks_stolen_from.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Forget a task that was moved to another worker
+    pub(crate) fn release(&self, task_id: &str) {
+        self.tasks.remove(task_id);
+        self.result_buffers.remove(task_id);
+        self.unindex(task_id);
+    }
+
+    /// Remove stored tasks in `status`, returning how many were removed
+    ///
+    /// Meant for finished tasks: counters are kept, only the task records go.
+    pub fn purge(&self, status: &TaskStatus) -> usize {
+        let mut removed = Vec::new();
+        self.tasks.retain(|id, task| {
+            let keep = &task.status != status;
+            if !keep {
+                removed.push(id.clone());
+            }
+            keep
+        });
+        for id in &removed {
+            self.unindex(id);
+            self.unpersist(id);
+        }
+        let purged = removed.len();
+        self.finished_order.lock().retain(|(id, _)| self.tasks.contains_key(id));
+        if purged > 0 {
+            info!("Purged {} {:?} tasks from worker {}", purged, status, self.id);
+        }
+        purged
+    }
+
+    /// Get task information, including queue position while pending
+    pub async fn get_task_with_position(&self, task_id: &str) -> Option<Task> {
+        let mut task = self.get_task(task_id)?;
+        if task.status == TaskStatus::Pending {
+            task.queue_position = self.queue_position(task_id).await;
+        }
+        Some(task)
+    }
+
     /// Complete a task (can only be done via API call)
     pub fn complete_task(&self, task_id: &str) -> Result<bool, TaskError> {
         if let Some(mut task_entry) = self.tasks.get_mut(task_id) {
@@ -141,6 +851,11 @@ impl Worker {
             match result {
                 Ok(()) => {
                     self.tasks_completed.fetch_add(1, Ordering::Relaxed);
+                    self.persist(&task_entry);
+                    self.events.emit(TaskEvent::Completed, &task_entry);
+                    // Released first: storing a task locks the eviction order, then the map
+                    drop(task_entry);
+                    Self::record_finished(&self.finished_order, self.tracks_finished(), task_id);
                     info!("Task {} completed on worker {}", task_id, self.id);
                     Ok(true)
                 }
@@ -151,11 +866,94 @@ impl Worker {
         }
     }
 
+    /// Cancel a pending or calculated task, stopping its calculation if one is running
+    ///
+    /// A queued task is taken off the queue; a task being calculated has its
+    /// cancellation token cancelled and its result is dropped. Returns
+    /// `Ok(false)` if the task isn't on this worker.
+    pub async fn cancel_task(&self, task_id: &str) -> Result<bool, TaskError> {
+        // Off the queue first, so no thread takes it while it's cancelled
+        let dequeued = self.task_queue.lock().await.remove(task_id);
+        let Some(mut entry) = self.tasks.get_mut(task_id) else {
+            return Ok(false);
+        };
+        // Queued tasks are pending, so only tasks already finished are refused
+        entry.set_cancelled()?;
+        // Checked by the processing thread under the same entry lock before it stores a result
+        if let Some(cancel) = self.in_flight.get(task_id) {
+            cancel.cancel();
+        }
+        self.tasks_cancelled.fetch_add(1, Ordering::Relaxed);
+        self.persist(&entry);
+        self.events.emit(TaskEvent::Cancelled, &entry);
+        drop(entry);
+        if dequeued.is_some() {
+            // Nothing will calculate it now, so streaming readers end here
+            if let Some((_, buffer)) = self.result_buffers.remove(task_id) {
+                buffer.finish();
+            }
+        }
+        Self::record_finished(&self.finished_order, self.tracks_finished(), task_id);
+        info!("Task {} cancelled on worker {}", task_id, self.id);
+        Ok(true)
+    }
+
+    /// Remove a task from storage, and from the queue while it's queued
+    ///
+    /// Pending and finished tasks are removed; a calculated task awaiting
+    /// completion only with `force`. A calculation in progress is stopped and
+    /// its result dropped. Returns the removed task, or `Ok(None)` if the
+    /// task isn't on this worker.
+    pub async fn delete_task(&self, task_id: &str, force: bool) -> Result<Option<Task>, TaskError> {
+        // Queue locked throughout, so no thread takes the task while it's removed
+        let mut queue = self.task_queue.lock().await;
+        let deletable = |task: &Task| force || task.status == TaskStatus::Pending || task.status.is_terminal();
+        let Some((_, task)) = self.tasks.remove_if(task_id, |_, task| deletable(task)) else {
+            return match self.tasks.get(task_id) {
+                Some(task) => Err(TaskError::NotDeletable {
+                    id: task_id.to_string(),
+                    status: task.status.clone(),
+                }),
+                None => Ok(None),
+            };
+        };
+        queue.remove(task_id);
+        drop(queue);
+        // The processing thread finds the task gone and drops its result
+        if let Some(cancel) = self.in_flight.get(task_id) {
+            cancel.cancel();
+        }
+        if let Some((_, buffer)) = self.result_buffers.remove(task_id) {
+            buffer.finish();
+        }
+        self.unindex(task_id);
+        self.unpersist(task_id);
+        self.finished_order.lock().retain(|(id, _)| id != task_id);
+        self.tasks_deleted.fetch_add(1, Ordering::Relaxed);
+        info!("Task {} ({}) deleted from worker {}", task_id, task.status, self.id);
+        Ok(Some(task))
+    }
+
+    /// Processing threads currently running a calculation
+    pub fn busy_threads(&self) -> usize {
+        self.busy_threads.load(Ordering::Relaxed)
+    }
+
+    /// Copy of this worker's latency histogram, for merging across workers
+    pub fn latency_histogram(&self) -> Histogram<u64> {
+        self.latency.snapshot()
+    }
+
+    /// Copy of this worker's per-operation calculation-time histograms, for merging across workers
+    pub fn processing_histograms(&self) -> BTreeMap<String, Histogram<u64>> {
+        self.processing_latency.snapshot()
+    }
+
     /// Get worker statistics
     pub async fn get_stats(&self) -> WorkerStats {
-        let current_queue_size = {
+        let (current_queue_size, queued_by_priority) = {
             let queue = self.task_queue.lock().await;
-            queue.len()
+            (queue.len(), queue.depth())
         };
         
         WorkerStats {
@@ -163,67 +961,339 @@ impl Worker {
             tasks_processed: self.tasks_processed.load(Ordering::Relaxed),
             tasks_completed: self.tasks_completed.load(Ordering::Relaxed),
             tasks_failed: self.tasks_failed.load(Ordering::Relaxed),
+            tasks_cancelled: self.tasks_cancelled.load(Ordering::Relaxed),
+            tasks_deleted: self.tasks_deleted.load(Ordering::Relaxed),
+            task_retries: self.task_retries.load(Ordering::Relaxed),
+            result_mismatches: self.result_mismatches.load(Ordering::Relaxed),
+            storage_evictions: self.storage_evictions.load(Ordering::Relaxed),
+            tasks_expired: self.tasks_expired.load(Ordering::Relaxed),
+            wedged_threads: self.wedged_threads.load(Ordering::Relaxed),
+            threads_respawned: self.threads_respawned.load(Ordering::Relaxed),
             current_load: current_queue_size,
+            queued_by_priority,
             uptime_seconds: self.start_time.elapsed().as_secs(),
             is_healthy: self.running.load(Ordering::Acquire),
+            paused: self.is_paused(),
+            processing_paused: self.is_processing_paused(),
+            speed_factor: self.speed_factor,
+            latency: self.latency.summary(),
+            processing_by_operation: self.processing_latency.summaries(),
+            restarts: self.restarts.load(Ordering::Relaxed),
+            tasks_stolen: self.tasks_stolen.load(Ordering::Relaxed),
+            tasks_stolen_from: self.tasks_stolen_from.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Health, queue depth, capabilities and labels of this worker
+    pub async fn snapshot(&self) -> WorkerSnapshot {
+        let health = match (self.is_running(), self.is_paused()) {
+            (false, _) => WorkerHealth::Stopped,
+            (true, true) => WorkerHealth::Paused,
+            (true, false) => WorkerHealth::Healthy,
+        };
+        WorkerSnapshot {
+            id: self.id,
+            health,
+            queue_depth: self.queue_len().await,
+            threads: self.config.num_threads,
+            busy_threads: self.busy_threads(),
+            capabilities: Operation::ALL.to_vec(),
+            labels: self.labels.clone(),
+        }
+    }
+
+    /// Flag threads busy with one task for longer than `threshold`, returning any replacements started
+    fn check_threads(&self, threshold: Duration) -> Vec<JoinHandle<()>> {
+        let heartbeats: Vec<(usize, Arc<ThreadHeartbeat>)> =
+            self.heartbeats.iter().map(|entry| (*entry.key(), Arc::clone(entry.value()))).collect();
+        let worker = self.id.to_string();
+        let metrics = self.metrics.read().clone();
+        let mut replacements = Vec::new();
+        for (thread_id, heartbeat) in heartbeats {
+            let Some((task_id, busy_for)) = heartbeat.flag_if_stalled(threshold) else {
+                continue;
+            };
+            warn!(
+                worker = self.id,
+                thread = thread_id,
+                task_id = %task_id,
+                busy_ms = busy_for.as_millis() as u64,
+                "Processing thread wedged"
+            );
+            self.wedged_threads.fetch_add(1, Ordering::Relaxed);
+            if let Some(metrics) = &metrics {
+                metrics.counter("processing_threads_wedged_total", &[("worker", &worker)], 1);
+            }
+            if self.watchdog.respawn {
+                heartbeat.retire();
+                replacements.push(self.spawn_processing_thread(thread_id));
+                self.threads_respawned.fetch_add(1, Ordering::Relaxed);
+                if let Some(metrics) = &metrics {
+                    metrics.counter("processing_threads_respawned_total", &[("worker", &worker)], 1);
+                }
+                info!("Worker {} started a replacement for wedged thread {}", self.id, thread_id);
+            }
         }
+        replacements
     }
 
     /// Spawn a processing thread
     fn spawn_processing_thread(&self, thread_id: usize) -> JoinHandle<()> {
+        let heartbeat = Arc::new(ThreadHeartbeat::new());
+        self.heartbeats.insert(thread_id, Arc::clone(&heartbeat));
         let worker_id = self.id;
         let tasks = Arc::clone(&self.tasks);
         let task_queue = Arc::clone(&self.task_queue);
         let queue_notify = Arc::clone(&self.queue_notify);
+        let result_buffers = Arc::clone(&self.result_buffers);
+        let in_flight = Arc::clone(&self.in_flight);
         let running = Arc::clone(&self.running);
+        let paused = Arc::clone(&self.paused);
+        let processing_paused = Arc::clone(&self.processing_paused);
+        let mut crash_guard = CrashGuard {
+            task_id: None,
+            crashed: Arc::clone(&self.crashed),
+            crashed_tasks: Arc::clone(&self.crashed_tasks),
+            shutdown_notify: Arc::clone(&self.shutdown_notify),
+        };
         let tasks_processed = Arc::clone(&self.tasks_processed);
         let tasks_failed = Arc::clone(&self.tasks_failed);
+        let task_retries = Arc::clone(&self.task_retries);
+        let result_mismatches = Arc::clone(&self.result_mismatches);
+        let busy_threads = Arc::clone(&self.busy_threads);
+        let latency = Arc::clone(&self.latency);
+        let processing_latency = Arc::clone(&self.processing_latency);
+        let signer = self.signer.clone();
+        let simulated_delay = self.simulated_delay;
+        let task_timeout = self.task_timeout;
+        let injected_failures = Arc::clone(&self.injected_failures);
+        let calculations = Arc::clone(&self.calculations);
+        let calculator = Arc::clone(&self.calculator);
+        // `parallel` batch tasks use at most as many threads as the worker has
+        let fan_out = self.config.num_threads;
+        let events = Arc::clone(&self.events);
+        let polling = self.polling.clone();
+        let finished_order = Arc::clone(&self.finished_order);
+        let track_finished = self.tracks_finished();
+        let analytics = self.analytics.clone();
+        let task_store = self.task_store.read().clone();
+        let stealing = self.stealing.clone();
+        // Idle threads also wake up to look for work to steal
+        let wake_interval = [polling.fallback_interval(), stealing.as_ref().map(|registry| registry.interval())]
+            .into_iter()
+            .flatten()
+            .min();
 
         tokio::spawn(async move {
             info!("Processing thread {} started for worker {}", thread_id, worker_id);
 
-            while running.load(Ordering::Acquire) {
-                // Wait for tasks or shutdown signal
-                tokio::select! {
-                    _ = queue_notify.notified() => {
-                        // Process available tasks
-                        while let Some(task) = {
-                            let mut queue = task_queue.lock().await;
-                            queue.pop_front()
-                        } {
-                            let task_id = task.id.clone();
-                            
-                            debug!(
-                                "Worker {} thread {} processing task {}",
-                                worker_id, thread_id, task_id
-                            );
-
-                            // Process the task
-                            let result = Self::process_task(task).await;
-
-                            match result {
-                                Ok(processed_task) => {
-                                    // Update task in storage
-                                    if let Some(mut entry) = tasks.get_mut(&task_id) {
-                                        *entry = processed_task;
+            while running.load(Ordering::Acquire) && !heartbeat.is_retired() {
+                // Wait for tasks; the fallback interval also picks up anything a missed wakeup left queued
+                match wake_interval {
+                    Some(interval) => {
+                        let _ = tokio::time::timeout(interval, queue_notify.notified()).await;
+                    }
+                    None => queue_notify.notified().await,
+                }
+
+                // Process available tasks, a batch at a time when batching
+                let mut batch = VecDeque::new();
+                let mut batch_taken = false;
+                while let Some(mut task) = Self::next_task(
+                    &task_queue,
+                    [&paused, &processing_paused],
+                    &mut batch,
+                    polling.batch_size,
+                    &mut batch_taken,
+                )
+                .await
+                {
+                    let task_id = task.id.clone();
+                    let created_at = task.created_at;
+                    let operation = task.data.operation.to_string();
+                    
+                    // Registered before the status check, so a cancellation from then on reaches the calculation
+                    let cancel = CancellationToken::new();
+                    in_flight.insert(task_id.clone(), cancel.clone());
+                    let progress = result_buffers
+                        .get(&task_id)
+                        .map(|entry| Arc::clone(entry.value()))
+                        .unwrap_or_default();
+                    // Cancelled while held in a batch
+                    if tasks.get(&task_id).is_some_and(|task| task.status == TaskStatus::Cancelled) {
+                        in_flight.remove(&task_id);
+                        progress.finish();
+                        result_buffers.remove(&task_id);
+                        continue;
+                    }
+
+                    // Recorded on the stored task too, so they show while the calculation runs
+                    task.attempts += 1;
+                    task.worker_id = Some(worker_id);
+                    task.started_at = Some(Utc::now());
+                    if let Some(mut entry) = tasks.get_mut(&task_id) {
+                        entry.attempts = task.attempts;
+                        entry.worker_id = task.worker_id;
+                        entry.started_at = task.started_at;
+                    }
+                    let attempt = task.attempts;
+                    let retries_left = attempt <= task.data.max_retries;
+                    let retry_backoff = task.data.retry_backoff(attempt);
+                    let time_limit = task.timeout_ms.map(Duration::from_millis).or(task_timeout);
+                    
+                    debug!(
+                        "Worker {} thread {} processing task {}",
+                        worker_id, thread_id, task_id
+                    );
+
+                    // Process the task
+                    let queued = (Utc::now() - created_at).to_std().unwrap_or_default();
+                    let started = Instant::now();
+                    busy_threads.fetch_add(1, Ordering::Relaxed);
+                    heartbeat.beat(Some(&task_id));
+                    crash_guard.task_id = Some(task_id.clone());
+                    let result = {
+                        let (progress, failures) = (Arc::clone(&progress), Arc::clone(&injected_failures));
+                        let calculator = Arc::clone(&calculator);
+                        Self::calculate_within(&calculations, &cancel, time_limit, move |cancel| {
+                            Self::process_task(task, &calculator, &progress, &failures, cancel, fan_out)
+                        })
+                        .await
+                    };
+                    if !simulated_delay.is_zero() {
+                        tokio::select! {
+                            _ = tokio::time::sleep(simulated_delay) => {}
+                            _ = cancel.cancelled() => {}
+                        }
+                    }
+                    heartbeat.beat(None);
+                    crash_guard.task_id = None;
+                    busy_threads.fetch_sub(1, Ordering::Relaxed);
+                    in_flight.remove(&task_id);
+                    let processing = started.elapsed();
+                    let processing_ms = Some(processing.as_millis() as u64);
+
+                    // The task keeps its cancelled status and the result is dropped
+                    let cancelled = cancel.is_cancelled()
+                        || tasks.get(&task_id).is_some_and(|task| task.status == TaskStatus::Cancelled);
+                    let mut retrying = false;
+                    if cancelled {
+                        debug!("Task {} cancelled on worker {} while processing", task_id, worker_id);
+                    } else if let Some(error) = Self::failure(&result).filter(|_| retries_left) {
+                        // Still pending: queued again once the backoff is over, with a new buffer for the next attempt
+                        warn!(
+                            "Task {} attempt {} failed on worker {}: {}; retrying in {:?}",
+                            task_id, attempt, worker_id, error, retry_backoff
+                        );
+                        if let Some(mut entry) = tasks.get_mut(&task_id).filter(|entry| entry.status == TaskStatus::Pending) {
+                            entry.error_message = Some(error);
+                            entry.processing_duration_ms = processing_ms;
+                            if let Some(store) = &task_store {
+                                store.save(&entry);
+                            }
+                            let mut retry = entry.clone();
+                            retry.error_message = None;
+                            drop(entry);
+                            retrying = true;
+                            task_retries.fetch_add(1, Ordering::Relaxed);
+                            result_buffers.insert(task_id.clone(), Arc::new(ResultBuffer::new()));
+                            tokio::spawn(Self::retry_after(
+                                retry,
+                                retry_backoff,
+                                Arc::clone(&tasks),
+                                Arc::clone(&task_queue),
+                                Arc::clone(&queue_notify),
+                                Arc::clone(&result_buffers),
+                            ));
+                        }
+                    } else {
+                        match result {
+                            Ok(mut processed_task) => {
+                                processed_task.processing_duration_ms = processing_ms;
+                                if let Some(signer) = &signer {
+                                    processed_task.result_signature = signer.sign(&processed_task);
+                                }
+
+                                // Pipeline stage failures come back as a failed task
+                                let failed = processed_task.status == TaskStatus::Failed;
+                                if failed {
+                                    error!(
+                                        "Task {} pipeline failed on worker {}: {}",
+                                        task_id,
+                                        worker_id,
+                                        processed_task.error_message.as_deref().unwrap_or("unknown error")
+                                    );
+                                }
+
+                                // Update task in storage, unless cancelled since the check above
+                                if let Some(mut entry) = tasks.get_mut(&task_id).filter(|entry| entry.status != TaskStatus::Cancelled) {
+                                    *entry = processed_task;
+                                    if entry.check_expected_result() == Some(false) {
+                                        result_mismatches.fetch_add(1, Ordering::Relaxed);
+                                    }
+                                    if let Some(store) = &task_store {
+                                        store.save(&entry);
                                     }
+                                    events.emit(if failed { TaskEvent::Failed } else { TaskEvent::Processed }, &entry);
+                                }
+                                if failed {
+                                    tasks_failed.fetch_add(1, Ordering::Relaxed);
+                                    Self::record_finished(&finished_order, track_finished, &task_id);
+                                } else {
                                     tasks_processed.fetch_add(1, Ordering::Relaxed);
                                     debug!("Task {} processed successfully by worker {}", task_id, worker_id);
                                 }
-                                Err(e) => {
-                                    error!("Task {} processing failed on worker {}: {}", task_id, worker_id, e);
-                                    
-                                    // Mark task as failed
-                                    if let Some(mut entry) = tasks.get_mut(&task_id) {
-                                        entry.set_failed(e.to_string());
+                            }
+                            Err(e) => {
+                                error!("Task {} processing failed on worker {}: {}", task_id, worker_id, e);
+                            
+                                // Mark task as failed
+                                if let Some(mut entry) = tasks.get_mut(&task_id).filter(|entry| entry.status != TaskStatus::Cancelled) {
+                                    entry.set_failed(e.to_string());
+                                    entry.processing_duration_ms = processing_ms;
+                                    if entry.check_expected_result() == Some(false) {
+                                        result_mismatches.fetch_add(1, Ordering::Relaxed);
                                     }
-                                    tasks_failed.fetch_add(1, Ordering::Relaxed);
+                                    if let Some(store) = &task_store {
+                                        store.save(&entry);
+                                    }
+                                    events.emit(TaskEvent::Failed, &entry);
                                 }
+                                tasks_failed.fetch_add(1, Ordering::Relaxed);
+                                Self::record_finished(&finished_order, track_finished, &task_id);
                             }
                         }
+
+                        latency.record((Utc::now() - created_at).to_std().unwrap_or_default());
+                        processing_latency.record(&operation, processing);
+                        if let (Some(analytics), Some(task)) = (&analytics, tasks.get(&task_id)) {
+                            analytics.record(TaskRecord::new(&task, worker_id, queued, processing));
+                        }
+                    }
+
+                    // The stored task now has the final result, so streaming readers can switch to it
+                    progress.finish();
+                    if !retrying {
+                        result_buffers.remove(&task_id);
                     }
-                    _ = tokio::time::sleep(Duration::from_millis(100)) => {
-                        // Periodic check - prevents busy waiting
+
+                    // Replaced by the watchdog: hand the rest of the batch back and exit
+                    if heartbeat.is_retired() {
+                        Self::return_batch(&task_queue, &mut batch).await;
+                        break;
+                    }
+                }
+
+                // Leave the rest of the queue to the next wakeup, ours or another thread's
+                if batch_taken && !task_queue.lock().await.is_empty() {
+                    queue_notify.notify_one();
+                }
+
+                // Out of work: take some from the busiest peer, queued here with a wakeup
+                if let Some(registry) = &stealing {
+                    let held = paused.load(Ordering::Acquire) || processing_paused.load(Ordering::Acquire);
+                    if !held && task_queue.lock().await.is_empty() {
+                        registry.steal_for(worker_id).await;
                     }
                 }
             }
@@ -232,19 +1302,157 @@ impl Worker {
         })
     }
 
-    /// Process a single task
-    async fn process_task(mut task: Task) -> Result<Task, TaskError> {
-        let start_time = Instant::now();
-        
-        // Perform the calculation
-        let result = Calculator::calculate(task.data.operation.clone(), task.data.input)?;
-        
-        let processing_time = start_time.elapsed();
-        debug!(
-            "Calculation completed in {:?}: {} {} = {}",
-            processing_time,
-            task.data.operation,
-            task.data.input,
+    /// Next task for a processing thread: the rest of its batch, else more off the queue
+    ///
+    /// With `batch_size` 0 tasks are taken one at a time until the queue is
+    /// empty; otherwise up to `batch_size` are taken under a single lock, once
+    /// per wakeup, and processed back to back, so a higher-priority task queued
+    /// meanwhile waits for the batch. Nothing is taken while either `paused`
+    /// flag (operator pause or processing-only pause) is set.
+    async fn next_task(
+        task_queue: &Mutex<TaskQueue>,
+        paused: [&AtomicBool; 2],
+        batch: &mut VecDeque<Task>,
+        batch_size: usize,
+        batch_taken: &mut bool,
+    ) -> Option<Task> {
+        if paused.iter().any(|flag| flag.load(Ordering::Acquire)) {
+            // A paused worker leaves its queue, including the untouched rest of a batch, for resume or rebalance
+            Self::return_batch(task_queue, batch).await;
+            return None;
+        }
+        if batch.is_empty() && !*batch_taken {
+            let mut queue = task_queue.lock().await;
+            if batch_size == 0 {
+                return queue.pop();
+            }
+            batch.extend(queue.take_front(batch_size));
+            *batch_taken = true;
+        }
+        batch.pop_front()
+    }
+
+    /// Run `job` on `calculations`, failing it with [`TaskError::TimedOut`]
+    /// once it has taken longer than `time_limit`
+    ///
+    /// The job gets a child of `cancel`, cancelled at the deadline, so a
+    /// cancellable calculation stops there while the task itself stays
+    /// uncancelled. Calculations that don't check the token (or that hold the
+    /// runtime thread the deadline timer needs) are timed out when they return.
+    async fn calculate_within<F>(
+        calculations: &CalculationPool,
+        cancel: &CancellationToken,
+        time_limit: Option<Duration>,
+        job: F,
+    ) -> Result<Task, TaskError>
+    where
+        F: FnOnce(&CancellationToken) -> Result<Task, TaskError> + Send + 'static,
+    {
+        let calculation = cancel.child_token();
+        let deadline = time_limit.map(|limit| {
+            let calculation = calculation.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(limit).await;
+                calculation.cancel();
+            })
+        });
+        let started = Instant::now();
+        let result = {
+            let calculation = calculation.clone();
+            calculations.run(move || job(&calculation)).await
+        };
+        if let Some(deadline) = deadline {
+            deadline.abort();
+        }
+        match time_limit {
+            Some(limit) if !cancel.is_cancelled() && (calculation.is_cancelled() || started.elapsed() > limit) => {
+                Err(TaskError::TimedOut {
+                    timeout_ms: limit.as_millis() as u64,
+                })
+            }
+            _ => result,
+        }
+    }
+
+    /// Error of a failed calculation, pipeline stage failures included
+    fn failure(result: &Result<Task, TaskError>) -> Option<String> {
+        match result {
+            Err(e) => Some(e.to_string()),
+            Ok(task) if task.status == TaskStatus::Failed => Some(task.error_message.clone().unwrap_or_default()),
+            Ok(_) => None,
+        }
+    }
+
+    /// Queue `task` again after `backoff`, unless it was cancelled meanwhile
+    async fn retry_after(
+        task: Task,
+        backoff: Duration,
+        tasks: Arc<DashMap<String, Task>>,
+        task_queue: Arc<Mutex<TaskQueue>>,
+        queue_notify: Arc<Notify>,
+        result_buffers: Arc<DashMap<String, Arc<ResultBuffer>>>,
+    ) {
+        tokio::time::sleep(backoff).await;
+        if tasks.get(&task.id).is_some_and(|stored| stored.status == TaskStatus::Pending) {
+            task_queue.lock().await.push(task);
+            queue_notify.notify_one();
+        } else if let Some((_, buffer)) = result_buffers.remove(&task.id) {
+            // Nothing will calculate it now, so streaming readers end here
+            buffer.finish();
+        }
+    }
+
+    /// Put the untouched rest of a batch back in the queue, where it sorts by priority and age again
+    async fn return_batch(task_queue: &Mutex<TaskQueue>, batch: &mut VecDeque<Task>) {
+        if batch.is_empty() {
+            return;
+        }
+        let mut queue = task_queue.lock().await;
+        for task in batch.drain(..) {
+            queue.push(task);
+        }
+    }
+
+    /// Process a single task, publishing each stage result to `progress`
+    ///
+    /// Pipeline tasks that fail at a later stage are returned as `Ok` with
+    /// status `Failed`, so the per-stage results are kept on the task.
+    /// `parallel` batch tasks are split over up to `fan_out` threads.
+    fn process_task(
+        mut task: Task,
+        calculator: &Calculator,
+        progress: &ResultBuffer,
+        failures: &[InjectedFailure],
+        cancel: &CancellationToken,
+        fan_out: usize,
+    ) -> Result<Task, TaskError> {
+        if !task.data.pipeline.is_empty() {
+            return Ok(Self::process_pipeline(task, calculator, progress, failures, cancel));
+        }
+        if !task.data.inputs.is_empty() {
+            return Self::process_inputs(task, calculator, progress, failures, cancel, fan_out);
+        }
+
+        let start_time = Instant::now();
+        
+        // Perform the calculation
+        let (operation, input) = (task.data.operation.clone(), task.data.input);
+        let outcome = Self::run_operation(&mut task, calculator, operation.clone(), input, failures, cancel);
+        progress.push(StageResult {
+            stage: 0,
+            operation,
+            input,
+            result: outcome.as_ref().ok().cloned(),
+            error: outcome.as_ref().err().map(|e| e.to_string()),
+        });
+        let result = outcome?;
+        
+        let processing_time = start_time.elapsed();
+        debug!(
+            "Calculation completed in {:?}: {} {} = {}",
+            processing_time,
+            task.data.operation,
+            task.data.input,
             result
         );
 
@@ -254,6 +1462,218 @@ impl Worker {
         Ok(task)
     }
 
+    /// Calculate the operation for each of the task's `inputs`, storing the
+    /// results as a JSON array in input order
+    ///
+    /// With `parallel` the inputs are split over up to [`parallel_threads`](Self::parallel_threads)
+    /// threads. The first failing input fails the whole task.
+    fn process_inputs(
+        mut task: Task,
+        calculator: &Calculator,
+        progress: &ResultBuffer,
+        failures: &[InjectedFailure],
+        cancel: &CancellationToken,
+        fan_out: usize,
+    ) -> Result<Task, TaskError> {
+        let start_time = Instant::now();
+        let inputs = task.data.inputs.clone();
+        let threads = Self::parallel_threads(fan_out, inputs.len());
+        let outcome = if task.data.parallel && threads > 1 {
+            std::thread::scope(|scope| {
+                let chunks: Vec<_> = inputs
+                    .chunks(inputs.len().div_ceil(threads))
+                    .map(|chunk| {
+                        // Each thread records the algorithm on its own copy
+                        let mut scratch = task.clone();
+                        scope.spawn(move || Self::run_inputs(&mut scratch, calculator, chunk, failures, cancel).map(|results| (results, scratch.algorithm)))
+                    })
+                    .collect();
+                let mut results = Vec::with_capacity(inputs.len());
+                for chunk in chunks {
+                    // A panicking calculation takes the processing thread down, as it would unsplit
+                    let (chunk_results, algorithm) = chunk.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic))?;
+                    results.extend(chunk_results);
+                    task.algorithm = algorithm;
+                }
+                Ok(results)
+            })
+        } else {
+            Self::run_inputs(&mut task, calculator, &inputs, failures, cancel)
+        };
+        let outcome = outcome.map(|results| serde_json::Value::from(results).to_string());
+        progress.push(StageResult {
+            stage: 0,
+            operation: task.data.operation.clone(),
+            input: task.data.input,
+            result: outcome.as_ref().ok().cloned(),
+            error: outcome.as_ref().err().map(|e| e.to_string()),
+        });
+        let result = outcome?;
+
+        debug!(
+            "Calculated {} for {} inputs in {:?}",
+            task.data.operation,
+            inputs.len(),
+            start_time.elapsed()
+        );
+        task.set_processing(result);
+        Ok(task)
+    }
+
+    /// Threads a `parallel` task with `inputs` inputs is split over: one per
+    /// input, but no more than the worker's `fan_out` (its `threads_per_worker`)
+    /// or the CPUs
+    fn parallel_threads(fan_out: usize, inputs: usize) -> usize {
+        let cpus = std::thread::available_parallelism().map_or(1, |count| count.get());
+        fan_out.min(cpus).min(inputs).max(1)
+    }
+
+    /// Calculate the task's operation for `inputs` one after the other
+    fn run_inputs(task: &mut Task, calculator: &Calculator, inputs: &[u64], failures: &[InjectedFailure], cancel: &CancellationToken) -> Result<Vec<String>, TaskError> {
+        let operation = task.data.operation.clone();
+        inputs
+            .iter()
+            .map(|input| Self::run_operation(task, calculator, operation.clone(), *input, failures, cancel))
+            .collect()
+    }
+
+    /// Run every pipeline stage, feeding each result into the next stage
+    fn process_pipeline(mut task: Task, calculator: &Calculator, progress: &ResultBuffer, failures: &[InjectedFailure], cancel: &CancellationToken) -> Task {
+        let operations: Vec<Operation> = task.data.stages().cloned().collect();
+        let mut input = task.data.input;
+        let mut result = String::new();
+
+        for (stage, operation) in operations.into_iter().enumerate() {
+            let outcome = if stage == 0 {
+                Self::run_operation(&mut task, calculator, operation.clone(), input, failures, cancel)
+            } else {
+                // Later stages only learn their input at runtime
+                let precision = if operation.supports_big_precision() { task.data.precision } else { Precision::Native };
+                let mut stage_data = TaskData::new(input, operation.clone()).with_precision(precision);
+                if operation.takes_second_input() {
+                    stage_data.input2 = task.data.input2;
+                }
+                stage_data
+                    .validate(calculator)
+                    .map_err(|e| TaskError::CalculationError { message: e.to_string() })
+                    .and_then(|_| Self::run_operation(&mut task, calculator, operation.clone(), input, failures, cancel))
+            };
+
+            let stage_result = StageResult {
+                stage,
+                operation: operation.clone(),
+                input,
+                result: outcome.as_ref().ok().cloned(),
+                error: outcome.as_ref().err().map(|e| e.to_string()),
+            };
+            progress.push(stage_result.clone());
+            task.stages.push(stage_result);
+
+            match outcome {
+                Ok(value) => result = value,
+                Err(e) => {
+                    task.set_failed(format!("Stage {} ({}) failed: {}", stage, operation, e));
+                    return task;
+                }
+            }
+
+            if stage + 1 < task.data.stages().count() {
+                match Self::stage_output_as_input(&result) {
+                    Some(next) => input = next,
+                    None => {
+                        task.set_failed(format!(
+                            "Stage {} ({}) result '{}' cannot be used as input for the next stage",
+                            stage, operation, result
+                        ));
+                        return task;
+                    }
+                }
+            }
+        }
+
+        task.set_processing(result);
+        task
+    }
+
+    /// Convert a stage result into the next stage's input
+    ///
+    /// Numeric results pass through; prime_check results map to 1 (true) / 0 (false).
+    fn stage_output_as_input(result: &str) -> Option<u64> {
+        match result {
+            "true" => Some(1),
+            "false" => Some(0),
+            other => other.parse().ok(),
+        }
+    }
+
+    /// Run a single operation, recording the algorithm for fibonacci
+    ///
+    /// With `precision: "big"` factorial and fibonacci use arbitrary precision;
+    /// `gcd` and `lcm` take the task's `input2` as their second operand.
+    fn run_operation(
+        task: &mut Task,
+        calculator: &Calculator,
+        operation: Operation,
+        input: u64,
+        failures: &[InjectedFailure],
+        cancel: &CancellationToken,
+    ) -> Result<String, TaskError> {
+        // Also stops a cancelled pipeline between stages
+        if cancel.is_cancelled() {
+            return Err(TaskError::Cancelled);
+        }
+        InjectedFailure::check(failures, &operation, input)?;
+        if task.data.precision == Precision::Big && operation.supports_big_precision() {
+            if operation == Operation::Fibonacci {
+                task.algorithm = Some(FibonacciAlgorithm::FastDoubling);
+            }
+            calculator.calculate_big(operation, input, cancel)
+        } else if operation == Operation::Fibonacci {
+            let algorithm = task
+                .data
+                .algorithm
+                .unwrap_or(calculator.fibonacci_algorithm());
+            task.algorithm = Some(algorithm);
+            calculator.fibonacci_with(algorithm, input)
+        } else if operation == Operation::PrimeCheck {
+            let algorithm = task
+                .data
+                .prime_check_algorithm
+                .unwrap_or(calculator.prime_check_algorithm());
+            calculator.prime_check_with(algorithm, input, cancel)
+        } else if operation.takes_second_input() {
+            let input2 = task.data.input2.ok_or_else(|| TaskError::CalculationError {
+                message: format!("Operation {} needs a second input", operation),
+            })?;
+            Calculator::calculate_pair(operation, input, input2)
+        } else {
+            calculator.calculate_cancellable(operation, input, cancel)
+        }
+    }
+
+}
+
+/// Owned by a processing thread; if the thread panics, marks its worker
+/// crashed and shuts the worker down
+struct CrashGuard {
+    /// Task being calculated, if any
+    task_id: Option<String>,
+    crashed: Arc<AtomicBool>,
+    crashed_tasks: Arc<parking_lot::Mutex<Vec<String>>>,
+    shutdown_notify: Arc<Notify>,
+}
+
+impl Drop for CrashGuard {
+    fn drop(&mut self) {
+        if !std::thread::panicking() {
+            return;
+        }
+        if let Some(task_id) = self.task_id.take() {
+            self.crashed_tasks.lock().push(task_id);
+        }
+        self.crashed.store(true, Ordering::Release);
+        self.shutdown_notify.notify_waiters();
+    }
 }
 
 #[cfg(test)]
@@ -262,6 +1682,17 @@ mod tests {
     use crate::types::TaskData;
     use tokio::time::timeout;
 
+    /// Wait (up to 2s) until `task_id` has been taken off the queue and processed
+    async fn wait_processed(worker: &Worker, task_id: &str) {
+        let processed = timeout(Duration::from_secs(2), async {
+            while worker.get_task(task_id).unwrap().status == TaskStatus::Pending {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await;
+        assert!(processed.is_ok(), "task {} still pending", task_id);
+    }
+
     #[tokio::test]
     async fn test_worker_creation() {
         let worker = Worker::new(0, 2);
@@ -287,6 +1718,25 @@ mod tests {
         assert_eq!(retrieved_task.unwrap().id, task_id);
     }
 
+    #[tokio::test]
+    async fn test_queue_position() {
+        // Worker not started, so tasks stay queued
+        let worker = Worker::new(0, 1);
+        let first = Task::new("First".to_string(), TaskPriority::Low, TaskData::new(5, Operation::Factorial));
+        let second = Task::new("Second".to_string(), TaskPriority::Low, TaskData::new(6, Operation::Factorial));
+        let (first_id, second_id) = (first.id.clone(), second.id.clone());
+        worker.add_task(first).await.unwrap();
+        worker.add_task(second).await.unwrap();
+
+        assert_eq!(worker.queue_position(&first_id).await, Some(1));
+        assert_eq!(worker.queue_position(&second_id).await, Some(2));
+        assert_eq!(worker.queue_position("missing").await, None);
+        assert_eq!(
+            worker.get_task_with_position(&second_id).await.unwrap().queue_position,
+            Some(2)
+        );
+    }
+
     #[tokio::test]
     async fn test_task_processing() {
         let mut task = Task::new(
@@ -295,12 +1745,659 @@ mod tests {
             TaskData::new(5, Operation::Factorial),
         );
 
-        let result = Worker::process_task(task.clone()).await;
+        let result = Worker::process_task(task.clone(), &Calculator::default(), &ResultBuffer::new(), &[], &CancellationToken::new(), 1);
         assert!(result.is_ok());
 
         let processed_task = result.unwrap();
         assert_eq!(processed_task.status, TaskStatus::Processing);
         assert_eq!(processed_task.result, Some("120".to_string()));
+        assert!(processed_task.algorithm.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_fibonacci_algorithm_recorded() {
+        let task = Task::new(
+            "Test matrix fibonacci".to_string(),
+            TaskPriority::Medium,
+            TaskData::new(50, Operation::Fibonacci).with_algorithm(FibonacciAlgorithm::Matrix),
+        );
+
+        let processed_task = Worker::process_task(task, &Calculator::default(), &ResultBuffer::new(), &[], &CancellationToken::new(), 1).unwrap();
+        assert_eq!(processed_task.result, Some("12586269025".to_string()));
+        assert_eq!(processed_task.algorithm, Some(FibonacciAlgorithm::Matrix));
+    }
+
+    #[tokio::test]
+    async fn test_big_precision_processing() {
+        let data = TaskData::new(100, Operation::Fibonacci).with_precision(Precision::Big);
+        let task = Task::new("Big fibonacci".to_string(), TaskPriority::Medium, data);
+        let processed_task = Worker::process_task(task, &Calculator::default(), &ResultBuffer::new(), &[], &CancellationToken::new(), 1).unwrap();
+        assert_eq!(processed_task.result.as_deref(), Some("354224848179261915075"));
+        assert_eq!(processed_task.algorithm, Some(FibonacciAlgorithm::FastDoubling));
+
+        // 25! no longer fits a u64, so it can't feed another stage
+        let data = TaskData::new(25, Operation::Factorial)
+            .with_pipeline(vec![Operation::PrimeCheck])
+            .with_precision(Precision::Big);
+        let task = Task::new("Big pipeline".to_string(), TaskPriority::Medium, data);
+        let processed_task = Worker::process_task(task, &Calculator::default(), &ResultBuffer::new(), &[], &CancellationToken::new(), 1).unwrap();
+        assert_eq!(processed_task.status, TaskStatus::Failed);
+        assert_eq!(processed_task.stages[0].result.as_deref(), Some("15511210043330985984000000"));
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_processing() {
+        // fibonacci(11) = 89, which is prime
+        let task = Task::new(
+            "Fibonacci then prime check".to_string(),
+            TaskPriority::Medium,
+            TaskData::new(11, Operation::Fibonacci).with_pipeline(vec![Operation::PrimeCheck]),
+        );
+
+        let processed_task = Worker::process_task(task, &Calculator::default(), &ResultBuffer::new(), &[], &CancellationToken::new(), 1).unwrap();
+        assert_eq!(processed_task.status, TaskStatus::Processing);
+        assert_eq!(processed_task.result, Some("true".to_string()));
+        assert_eq!(processed_task.stages.len(), 2);
+        assert_eq!(processed_task.stages[0].result, Some("89".to_string()));
+        assert_eq!(processed_task.stages[1].input, 89);
+    }
+
+    #[tokio::test]
+    async fn test_inputs_processing() {
+        let primes: Vec<u64> = (1..=100).collect();
+        let expected: Vec<String> = primes
+            .iter()
+            .map(|n| Calculator::default().calculate(Operation::PrimeCheck, *n).unwrap())
+            .collect();
+        for data in [
+            TaskData::batch(primes.clone(), Operation::PrimeCheck),
+            TaskData::batch(primes.clone(), Operation::PrimeCheck).in_parallel(),
+        ] {
+            let task = Task::new("Batch".to_string(), TaskPriority::Medium, data);
+            let processed_task = Worker::process_task(task, &Calculator::default(), &ResultBuffer::new(), &[], &CancellationToken::new(), 4).unwrap();
+            assert_eq!(processed_task.status, TaskStatus::Processing);
+            let results: Vec<String> = serde_json::from_str(processed_task.result.as_deref().unwrap()).unwrap();
+            assert_eq!(results, expected);
+        }
+
+        let fibonacci = TaskData::batch(vec![10, 20], Operation::Fibonacci).in_parallel();
+        let task = Task::new("Batch".to_string(), TaskPriority::Medium, fibonacci);
+        let processed_task = Worker::process_task(task, &Calculator::default(), &ResultBuffer::new(), &[], &CancellationToken::new(), 4).unwrap();
+        assert_eq!(processed_task.result.as_deref(), Some(r#"["55","6765"]"#));
+        assert!(processed_task.algorithm.is_some());
+
+        // Never more threads than the worker has, however many inputs or CPUs
+        assert_eq!(Worker::parallel_threads(1, 100), 1);
+        assert!(Worker::parallel_threads(2, 100) <= 2);
+        assert_eq!(Worker::parallel_threads(8, 1), 1);
+
+        // One failing input fails the task
+        let task = Task::new("Batch".to_string(), TaskPriority::Medium, TaskData::batch(vec![5, 25], Operation::Factorial));
+        assert!(Worker::process_task(task, &Calculator::default(), &ResultBuffer::new(), &[], &CancellationToken::new(), 1).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_gcd_and_lcm_processing() {
+        let gcd = Task::new("GCD".to_string(), TaskPriority::Medium, TaskData::new(48, Operation::Gcd).with_input2(36));
+        let processed_task = Worker::process_task(gcd, &Calculator::default(), &ResultBuffer::new(), &[], &CancellationToken::new(), 1).unwrap();
+        assert_eq!(processed_task.result, Some("12".to_string()));
+
+        // 4! = 24, then lcm(24, 36) = 72
+        let pipeline = TaskData::new(4, Operation::Factorial).with_pipeline(vec![Operation::Lcm]).with_input2(36);
+        let task = Task::new("Factorial then LCM".to_string(), TaskPriority::Medium, pipeline);
+        let processed_task = Worker::process_task(task, &Calculator::default(), &ResultBuffer::new(), &[], &CancellationToken::new(), 1).unwrap();
+        assert_eq!(processed_task.status, TaskStatus::Processing);
+        assert_eq!(processed_task.result, Some("72".to_string()));
+        assert_eq!(processed_task.stages[1].input, 24);
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_stage_failure() {
+        // 10! = 3628800 is too large for factorial
+        let task = Task::new(
+            "Factorial twice".to_string(),
+            TaskPriority::Medium,
+            TaskData::new(10, Operation::Factorial).with_pipeline(vec![Operation::Factorial]),
+        );
+
+        let processed_task = Worker::process_task(task, &Calculator::default(), &ResultBuffer::new(), &[], &CancellationToken::new(), 1).unwrap();
+        assert_eq!(processed_task.status, TaskStatus::Failed);
+        assert_eq!(processed_task.stages.len(), 2);
+        assert!(processed_task.stages[1].error.is_some());
+        assert!(processed_task.error_message.unwrap().starts_with("Stage 1 (factorial)"));
+    }
+
+    #[tokio::test]
+    async fn test_injected_failure() {
+        let failures = vec![InjectedFailure {
+            operation: Operation::PrimeCheck,
+            input: 89,
+            message: None,
+            panic: false,
+        }];
+        let task = |data: TaskData| Task::new("Injected".to_string(), TaskPriority::Medium, data);
+
+        let result = Worker::process_task(task(TaskData::new(89, Operation::PrimeCheck)), &Calculator::default(), &ResultBuffer::new(), &failures, &CancellationToken::new(), 1);
+        assert_eq!(result.unwrap_err().to_string(), "Calculation error: Injected failure for prime_check(89)");
+
+        // Matched on the stage's own input, so fibonacci(11) = 89 fails at stage 1
+        let pipeline = TaskData::new(11, Operation::Fibonacci).with_pipeline(vec![Operation::PrimeCheck]);
+        let processed_task = Worker::process_task(task(pipeline), &Calculator::default(), &ResultBuffer::new(), &failures, &CancellationToken::new(), 1).unwrap();
+        assert_eq!(processed_task.status, TaskStatus::Failed);
+        assert_eq!(processed_task.stages[0].result, Some("89".to_string()));
+        assert!(processed_task.stages[1].error.is_some());
+
+        let other = Worker::process_task(task(TaskData::new(7, Operation::PrimeCheck)), &Calculator::default(), &ResultBuffer::new(), &failures, &CancellationToken::new(), 1);
+        assert_eq!(other.unwrap().result, Some("true".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_result_stream() {
+        use futures::StreamExt;
+
+        let worker = Arc::new(Worker::new(0, 1).with_simulated_processing(Duration::from_millis(50), 1.0));
+        let task = Task::new(
+            "Streamed".to_string(),
+            TaskPriority::Medium,
+            TaskData::new(3, Operation::Factorial).with_pipeline(vec![Operation::Factorial]),
+        );
+        let task_id = task.id.clone();
+        worker.add_task(task).await.unwrap();
+
+        // Opened while the task is still queued
+        let live = worker.result_stream(&task_id).unwrap();
+        let runner = Arc::clone(&worker);
+        let handle = tokio::spawn(async move { runner.start().await });
+
+        let stages: Vec<StageResult> = timeout(Duration::from_secs(2), live.collect()).await.unwrap();
+        let results: Vec<_> = stages.iter().map(|stage| stage.result.as_deref()).collect();
+        assert_eq!(results, vec![Some("6"), Some("720")]);
+
+        // Finished tasks are replayed from storage
+        let replayed: Vec<StageResult> = worker.result_stream(&task_id).unwrap().collect().await;
+        assert_eq!(replayed.len(), 2);
+        assert!(worker.result_stream("missing").is_none());
+
+        worker.stop().await;
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_signed_results() {
+        let worker = Arc::new(Worker::new(0, 1).with_signing_key("test-key"));
+        let runner = Arc::clone(&worker);
+        let handle = tokio::spawn(async move { runner.start().await });
+        worker.wait_running().await;
+
+        let task = Task::new("Signed".to_string(), TaskPriority::Medium, TaskData::new(6, Operation::Factorial));
+        let task_id = task.id.clone();
+        worker.add_task(task).await.unwrap();
+        wait_processed(&worker, &task_id).await;
+
+        let processed = worker.get_task(&task_id).unwrap();
+        assert!(processed.result_signature.is_some());
+        assert!(ResultSigner::new("test-key").verify(&processed));
+
+        worker.stop().await;
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_queue_snapshot() {
+        // Worker not started, so tasks stay queued
+        let worker = Worker::new(0, 1);
+        for input in [5, 6] {
+            let task = Task::new("Queued".to_string(), TaskPriority::Low, TaskData::new(input, Operation::Factorial));
+            worker.add_task(task).await.unwrap();
+        }
+
+        let snapshot = worker.queue_snapshot().await;
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot[0].position, 1);
+        assert_eq!(snapshot[1].input, 6);
+    }
+
+    #[tokio::test]
+    async fn test_high_priority_first() {
+        let events = Arc::new(EventBus::new());
+        let order = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let seen = Arc::clone(&order);
+        events.register(TaskEvent::Processed, move |task: Task| {
+            seen.lock().push(task.id);
+            async {}
+        });
+        events.start();
+        let worker = Arc::new(Worker::new(0, 1).with_events(events));
+        for (id, priority) in [("low", TaskPriority::Low), ("medium", TaskPriority::Medium), ("high", TaskPriority::High)] {
+            let task = Task::with_id(id.to_string(), "Queued".to_string(), priority, TaskData::new(5, Operation::Factorial));
+            worker.add_task(task).await.unwrap();
+        }
+        assert_eq!(worker.queue_position("high").await, Some(1));
+        let stats = worker.get_stats().await;
+        assert_eq!(stats.queued_by_priority, QueueDepth { high: 1, medium: 1, low: 1 });
+
+        // One thread, started with all three queued, takes them by priority
+        let runner = Arc::clone(&worker);
+        tokio::spawn(async move { runner.start().await });
+        let processed = timeout(Duration::from_secs(2), async {
+            while order.lock().len() < 3 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await;
+        assert!(processed.is_ok());
+        assert_eq!(*order.lock(), vec!["high", "medium", "low"]);
+        worker.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_paused_worker_keeps_queue() {
+        let worker = Arc::new(Worker::new(0, 1));
+        worker.pause();
+        let runner = Arc::clone(&worker);
+        tokio::spawn(async move { runner.start().await });
+
+        let task = Task::new("Paused".to_string(), TaskPriority::Low, TaskData::new(5, Operation::Factorial));
+        let task_id = task.id.clone();
+        worker.add_task(task).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(worker.get_task(&task_id).unwrap().status, TaskStatus::Pending);
+        assert!(worker.get_stats().await.paused);
+
+        worker.resume();
+        let processed = timeout(Duration::from_secs(2), async {
+            while worker.get_task(&task_id).unwrap().status == TaskStatus::Pending {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+        assert!(processed.is_ok());
+        worker.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_notify_polling_in_batches() {
+        let polling = QueuePollingConfig {
+            mode: QueuePollingMode::Notify,
+            batch_size: 1,
+            ..QueuePollingConfig::default()
+        };
+        let worker = Arc::new(Worker::new(0, 1).with_queue_polling(polling));
+
+        // Queued before start: the notifications collapse into one stored wakeup
+        let mut task_ids = Vec::new();
+        for input in 1..=5 {
+            let task = Task::new("Batched".to_string(), TaskPriority::Low, TaskData::new(input, Operation::Factorial));
+            task_ids.push(task.id.clone());
+            worker.add_task(task).await.unwrap();
+        }
+        let runner = Arc::clone(&worker);
+        tokio::spawn(async move { runner.start().await });
+
+        // Each one-task batch hands the rest of the queue to the next wakeup
+        for task_id in &task_ids {
+            wait_processed(&worker, task_id).await;
+        }
+        worker.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_batch_dequeue() {
+        let task_queue = Mutex::new(TaskQueue::new());
+        for input in 1..=5 {
+            let task = Task::new("Batched".to_string(), TaskPriority::Low, TaskData::new(input, Operation::Factorial));
+            task_queue.lock().await.push(task);
+        }
+        let (paused, held) = (AtomicBool::new(false), AtomicBool::new(false));
+        let (mut batch, mut batch_taken) = (VecDeque::new(), false);
+
+        // One lock takes three tasks; the rest of the batch comes without touching the queue
+        let first = Worker::next_task(&task_queue, [&paused, &held], &mut batch, 3, &mut batch_taken).await;
+        assert_eq!(first.unwrap().data.input, 1);
+        assert_eq!(task_queue.lock().await.len(), 2);
+        let second = Worker::next_task(&task_queue, [&paused, &held], &mut batch, 3, &mut batch_taken).await;
+        assert_eq!(second.unwrap().data.input, 2);
+
+        // Pausing returns the untouched task to its place at the front of the queue
+        paused.store(true, Ordering::Release);
+        assert!(Worker::next_task(&task_queue, [&paused, &held], &mut batch, 3, &mut batch_taken).await.is_none());
+        let inputs: Vec<u64> = task_queue.lock().await.iter().map(|task| task.data.input).collect();
+        assert_eq!(inputs, vec![3, 4, 5]);
+
+        // One batch per wakeup
+        paused.store(false, Ordering::Release);
+        assert!(Worker::next_task(&task_queue, [&paused, &held], &mut batch, 3, &mut batch_taken).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_set_priority_repositions() {
+        let worker = Worker::new(0, 1);
+        let priorities = [("high", TaskPriority::High), ("low-1", TaskPriority::Low), ("low-2", TaskPriority::Low), ("target", TaskPriority::Low)];
+        for (id, priority) in priorities {
+            let task = Task::with_id(id.to_string(), "Queued".to_string(), priority, TaskData::new(5, Operation::Factorial));
+            worker.add_task(task).await.unwrap();
+        }
+        let order = || async { worker.queue_snapshot().await.into_iter().map(|task| task.id).collect::<Vec<_>>() };
+
+        // Raised past the low tasks, but not past the earlier high one
+        assert_eq!(worker.set_priority("target", TaskPriority::High).await.unwrap(), (TaskPriority::Low, 2));
+        assert_eq!(order().await, vec!["high", "target", "low-1", "low-2"]);
+        assert_eq!(worker.get_task("target").unwrap().priority, TaskPriority::High);
+
+        // Lowered behind the tasks that now outrank it
+        assert_eq!(worker.set_priority("high", TaskPriority::Low).await.unwrap(), (TaskPriority::High, 2));
+        assert_eq!(order().await, vec!["target", "high", "low-1", "low-2"]);
+
+        assert!(matches!(worker.set_priority("missing", TaskPriority::High).await, Err(TaskError::TaskNotFound { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_update_task() {
+        let worker = Worker::new(0, 1);
+        for (id, priority) in [("high", TaskPriority::High), ("target", TaskPriority::Low)] {
+            let task = Task::with_id(id.to_string(), "Queued".to_string(), priority, TaskData::new(5, Operation::Factorial));
+            worker.add_task(task).await.unwrap();
+        }
+
+        let update = TaskUpdateRequest {
+            priority: Some(TaskPriority::High),
+            title: Some("Renamed".to_string()),
+        };
+        let (task, previous) = worker.update_task("target", &update).await.unwrap();
+        assert_eq!((task.priority, task.title.as_str(), task.queue_position), (TaskPriority::High, "Renamed", Some(2)));
+        assert_eq!(previous, TaskPriority::Low);
+        // The queued copy, which is what gets calculated, changed too
+        assert_eq!(worker.task_queue.lock().await.get("target").unwrap().title, "Renamed");
+
+        let title_only = TaskUpdateRequest {
+            title: Some("Again".to_string()),
+            ..Default::default()
+        };
+        let (task, previous) = worker.update_task("target", &title_only).await.unwrap();
+        assert_eq!((task.priority, previous), (TaskPriority::High, TaskPriority::High));
+
+        worker.task_queue.lock().await.remove("high");
+        worker.tasks.get_mut("high").unwrap().set_processing("120".to_string());
+        assert!(matches!(worker.update_task("high", &update).await, Err(TaskError::NotQueued { .. })));
+        assert!(matches!(worker.update_task("missing", &update).await, Err(TaskError::TaskNotFound { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_move_queued_tasks() {
+        let (source, target) = (Worker::new(0, 1), Worker::new(1, 1));
+        for input in [5, 6, 7] {
+            let task = Task::new("Queued".to_string(), TaskPriority::Low, TaskData::new(input, Operation::Factorial));
+            source.add_task(task).await.unwrap();
+        }
+
+        // The newest tasks move; the oldest keeps its place
+        let taken = source.take_queued(2).await;
+        assert_eq!(taken.iter().map(|(task, _)| task.data.input).collect::<Vec<_>>(), vec![6, 7]);
+        for (task, buffer) in taken {
+            let task_id = task.id.clone();
+            target.requeue(task, buffer).await.unwrap();
+            source.release(&task_id);
+            assert!(source.get_task(&task_id).is_none());
+            assert!(target.get_task(&task_id).is_some());
+        }
+        assert_eq!(source.queue_len().await, 1);
+        assert_eq!(target.queue_len().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_purge() {
+        let worker = Worker::new(0, 1);
+        let task = Task::new("Done".to_string(), TaskPriority::Low, TaskData::new(5, Operation::Factorial));
+        let task_id = task.id.clone();
+        worker.add_task(task).await.unwrap();
+        assert_eq!(worker.purge(&TaskStatus::Failed), 0);
+
+        worker.tasks.get_mut(&task_id).unwrap().set_failed("boom".to_string());
+        assert_eq!(worker.purge(&TaskStatus::Failed), 1);
+        assert!(worker.get_task(&task_id).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_delete_task() {
+        // Not started, so tasks stay queued
+        let worker = Worker::new(0, 1);
+        for id in ["a", "b", "c"] {
+            let task = Task::with_id(id.to_string(), "Queued".to_string(), TaskPriority::Low, TaskData::new(5, Operation::Factorial));
+            worker.add_task(task).await.unwrap();
+        }
+
+        assert_eq!(worker.delete_task("a", false).await.unwrap().unwrap().status, TaskStatus::Pending);
+        assert!(worker.get_task("a").is_none());
+        assert_eq!(worker.queue_len().await, 2);
+        assert!(worker.delete_task("a", false).await.unwrap().is_none());
+
+        // Calculated and awaiting completion: only with force
+        worker.tasks.get_mut("b").unwrap().set_processing("120".to_string());
+        assert!(matches!(worker.delete_task("b", false).await, Err(TaskError::NotDeletable { .. })));
+        assert!(worker.get_task("b").is_some());
+        assert!(worker.delete_task("b", true).await.unwrap().is_some());
+
+        worker.tasks.get_mut("c").unwrap().set_failed("boom".to_string());
+        assert!(worker.delete_task("c", false).await.unwrap().is_some());
+        assert_eq!(worker.get_stats().await.tasks_deleted, 3);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_queued_task() {
+        use futures::StreamExt;
+
+        // Not started, so tasks stay queued
+        let worker = Worker::new(0, 1);
+        for id in ["a", "b"] {
+            let task = Task::with_id(id.to_string(), "Queued".to_string(), TaskPriority::Low, TaskData::new(5, Operation::Factorial));
+            worker.add_task(task).await.unwrap();
+        }
+        let stream = worker.result_stream("a").unwrap();
+
+        assert!(worker.cancel_task("a").await.unwrap());
+        assert_eq!(worker.get_task("a").unwrap().status, TaskStatus::Cancelled);
+        assert_eq!(worker.queue_position("b").await, Some(1));
+        assert_eq!(worker.queue_len().await, 1);
+        assert_eq!(worker.get_stats().await.tasks_cancelled, 1);
+        // Streaming readers of a task that will never run are let go
+        assert!(timeout(Duration::from_secs(1), stream.collect::<Vec<_>>()).await.unwrap().is_empty());
+
+        assert!(matches!(worker.cancel_task("a").await, Err(TaskError::InvalidStatusTransition { .. })));
+        assert!(!worker.cancel_task("missing").await.unwrap());
+    }
+
+    // The calculation holds its runtime thread, so the test needs another one to cancel it from
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_cancel_running_calculation() {
+        let worker = Arc::new(Worker::new(0, 1));
+        let runner = Arc::clone(&worker);
+        tokio::spawn(async move { runner.start().await });
+        worker.wait_running().await;
+
+        // Largest prime below 2^63: trial division would run for seconds
+        let data = TaskData::new(9223372036854775783, Operation::PrimeCheck).with_prime_check_algorithm(PrimeCheckAlgorithm::TrialDivision);
+        let task = Task::with_id("prime".to_string(), "Slow".to_string(), TaskPriority::Low, data);
+        worker.add_task(task).await.unwrap();
+        let busy = timeout(Duration::from_secs(2), async {
+            while worker.busy_threads() == 0 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await;
+        assert!(busy.is_ok());
+
+        assert!(worker.cancel_task("prime").await.unwrap());
+        let stopped = timeout(Duration::from_secs(2), async {
+            while worker.busy_threads() > 0 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await;
+        assert!(stopped.is_ok(), "calculation kept running after cancellation");
+        let task = worker.get_task("prime").unwrap();
+        assert_eq!((task.status, task.result), (TaskStatus::Cancelled, None));
+        let stats = worker.get_stats().await;
+        assert_eq!((stats.tasks_processed, stats.tasks_failed, stats.tasks_cancelled), (0, 0, 1));
+        worker.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_calculation_timeout() {
+        let worker = Arc::new(Worker::new(0, 1).with_task_timeout(Duration::from_millis(50)));
+        let runner = Arc::clone(&worker);
+        tokio::spawn(async move { runner.start().await });
+        worker.wait_running().await;
+
+        // Stopped at the worker's default deadline, or at the task's own
+        let slow = || TaskData::new(9223372036854775783, Operation::PrimeCheck).with_prime_check_algorithm(PrimeCheckAlgorithm::TrialDivision);
+        worker.add_task(Task::with_id("default".to_string(), "Slow".to_string(), TaskPriority::Low, slow())).await.unwrap();
+        let mut own = Task::with_id("own".to_string(), "Slow".to_string(), TaskPriority::Low, slow());
+        own.timeout_ms = Some(20);
+        worker.add_task(own).await.unwrap();
+        let mut quick = Task::with_id("quick".to_string(), "Quick".to_string(), TaskPriority::Low, TaskData::new(5, Operation::Factorial));
+        quick.timeout_ms = Some(1000);
+        worker.add_task(quick).await.unwrap();
+
+        for (id, message) in [("default", "Calculation timed out after 50 ms"), ("own", "Calculation timed out after 20 ms")] {
+            wait_processed(&worker, id).await;
+            let task = worker.get_task(id).unwrap();
+            assert_eq!((task.status, task.error_message.as_deref()), (TaskStatus::Failed, Some(message)));
+        }
+        wait_processed(&worker, "quick").await;
+        assert_eq!(worker.get_task("quick").unwrap().result, Some("120".to_string()));
+        assert_eq!(worker.get_stats().await.tasks_failed, 2);
+
+        let mut zero = Task::new("Zero".to_string(), TaskPriority::Low, TaskData::new(5, Operation::Factorial));
+        zero.timeout_ms = Some(0);
+        assert!(matches!(zero.validate(&Calculator::default()), Err(ValidationError::ZeroTaskTimeout)));
+        worker.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_dedicated_calculation_threads() {
+        // The test runtime has one thread, which a slow inline calculation would hold
+        let worker = Arc::new(Worker::new(0, 1).with_calculation_executor(CalculationExecutor::Dedicated));
+        let runner = Arc::clone(&worker);
+        tokio::spawn(async move { runner.start().await });
+        worker.wait_running().await;
+
+        let data = TaskData::new(9223372036854775783, Operation::PrimeCheck).with_prime_check_algorithm(PrimeCheckAlgorithm::TrialDivision);
+        let task = Task::with_id("prime".to_string(), "Slow".to_string(), TaskPriority::Low, data);
+        worker.add_task(task).await.unwrap();
+        let task = Task::with_id("quick".to_string(), "Quick".to_string(), TaskPriority::Low, TaskData::new(5, Operation::Factorial));
+        worker.add_task(task).await.unwrap();
+        let responsive = timeout(Duration::from_secs(2), async {
+            while worker.busy_threads() == 0 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await;
+        assert!(responsive.is_ok());
+
+        assert!(worker.cancel_task("prime").await.unwrap());
+        wait_processed(&worker, "quick").await;
+        assert_eq!(worker.get_task("quick").unwrap().result, Some("120".to_string()));
+        worker.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_storage_cap_evicts_oldest_finished() {
+        // Not started, so tasks only change state when the test says so
+        let worker = Worker::new(0, 1).with_max_tasks(2);
+        let task = |input| Task::new("Stored".to_string(), TaskPriority::Low, TaskData::new(input, Operation::Factorial));
+        let (first, second, third) = (task(1), task(2), task(3));
+        let (first_id, second_id) = (first.id.clone(), second.id.clone());
+        worker.add_task(first).await.unwrap();
+        worker.add_task(second).await.unwrap();
+
+        // Nothing finished to evict
+        assert!(matches!(
+            worker.add_task(third.clone()).await,
+            Err(TaskError::StorageFull { worker: 0, capacity: 2 })
+        ));
+
+        // The second finishes before the first, so it goes first
+        for id in [&second_id, &first_id] {
+            worker.tasks.get_mut(id).unwrap().set_processing("1".to_string());
+            assert!(worker.complete_task(id).unwrap());
+        }
+        worker.add_task(third).await.unwrap();
+        assert!(worker.get_task(&second_id).is_none());
+        assert!(worker.get_task(&first_id).is_some());
+        assert_eq!(worker.get_stats().await.storage_evictions, 1);
+    }
+
+    #[tokio::test]
+    async fn test_queue_limit() {
+        // Not started, so queued tasks stay queued
+        let worker = Worker::new(0, 1).with_max_queue_size(2);
+        let task = |input| Task::new("Queued".to_string(), TaskPriority::Low, TaskData::new(input, Operation::Factorial));
+        worker.add_task(task(1)).await.unwrap();
+        worker.add_task(task(2)).await.unwrap();
+
+        let refused = task(3);
+        assert!(matches!(
+            worker.add_task(refused.clone()).await,
+            Err(TaskError::QueueFull { worker: 0, depth: 2, capacity: 2 })
+        ));
+        // Refused before being stored
+        assert!(worker.get_task(&refused.id).is_none());
+
+        // Taking a task off the queue makes room
+        assert_eq!(worker.take_queued(1).await.len(), 1);
+        assert!(worker.add_task(refused).await.is_ok());
+        assert_eq!(worker.queue_len().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_finished_tasks_expire() {
+        let ttl = Duration::from_secs(60);
+        let worker = Worker::new(0, 1).with_task_ttl(ttl);
+        let task = |input| Task::new("Kept".to_string(), TaskPriority::Low, TaskData::new(input, Operation::Factorial));
+        let (done, cancelled, calculated) = (task(1), task(2), task(3));
+        let (done_id, cancelled_id, calculated_id) = (done.id.clone(), cancelled.id.clone(), calculated.id.clone());
+        for task in [done, cancelled, calculated] {
+            worker.add_task(task).await.unwrap();
+        }
+        worker.tasks.get_mut(&done_id).unwrap().set_processing("1".to_string());
+        assert!(worker.complete_task(&done_id).unwrap());
+        assert!(worker.cancel_task(&cancelled_id).await.unwrap());
+        // Calculated but never completed, so not finished
+        worker.tasks.get_mut(&calculated_id).unwrap().set_processing("6".to_string());
+
+        assert_eq!(worker.expire_finished(Instant::now()), 0);
+        assert_eq!(worker.expire_finished(Instant::now() + ttl), 2);
+        assert!(worker.get_task(&done_id).is_none());
+        assert!(worker.get_task(&cancelled_id).is_none());
+        assert!(worker.get_task(&calculated_id).is_some());
+        assert_eq!(worker.get_stats().await.tasks_expired, 2);
+
+        // Without a TTL nothing expires
+        let keeper = Worker::new(1, 1).with_task_ttl(Duration::ZERO);
+        assert_eq!(keeper.expire_finished(Instant::now() + ttl), 0);
+    }
+
+    #[tokio::test]
+    async fn test_sweeper_drops_expired_tasks() {
+        let worker = Arc::new(Worker::new(0, 1).with_task_ttl(Duration::from_millis(100)));
+        let runner = Arc::clone(&worker);
+        tokio::spawn(async move { runner.start().await });
+        worker.wait_running().await;
+
+        let task = Task::with_id("short-lived".to_string(), "Expiring".to_string(), TaskPriority::Low, TaskData::new(5, Operation::Factorial));
+        worker.add_task(task).await.unwrap();
+        wait_processed(&worker, "short-lived").await;
+        assert!(worker.complete_task("short-lived").unwrap());
+
+        let expired = timeout(Duration::from_secs(2), async {
+            while worker.get_task("short-lived").is_some() {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await;
+        assert!(expired.is_ok(), "completed task outlived its TTL");
+        assert_eq!(worker.get_stats().await.tasks_expired, 1);
+        worker.stop().await;
     }
 
     #[tokio::test]
@@ -312,5 +2409,187 @@ mod tests {
         assert_eq!(stats.tasks_processed, 0);
         assert_eq!(stats.tasks_completed, 0);
         assert_eq!(stats.tasks_failed, 0);
+        assert_eq!(stats.result_mismatches, 0);
+    }
+
+    #[tokio::test]
+    async fn test_processing_attribution() {
+        let worker = Arc::new(Worker::new(3, 1).with_simulated_processing(Duration::from_millis(40), 1.0));
+        let task = Task::new("Timed".to_string(), TaskPriority::Medium, TaskData::new(5, Operation::Factorial));
+        let task_id = task.id.clone();
+        worker.add_task(task).await.unwrap();
+        let queued = worker.get_task(&task_id).unwrap();
+        assert!(queued.worker_id.is_none() && queued.started_at.is_none() && queued.processing_duration_ms.is_none());
+
+        let runner = Arc::clone(&worker);
+        let handle = tokio::spawn(async move { runner.start().await });
+        wait_processed(&worker, &task_id).await;
+
+        let processed = worker.get_task(&task_id).unwrap();
+        assert_eq!(processed.worker_id, Some(3));
+        assert!(processed.started_at.unwrap() >= processed.created_at);
+        assert!(processed.processing_duration_ms.unwrap() >= 40);
+        worker.stop().await;
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_slow_worker_takes_longer() {
+        let base = Duration::from_millis(20);
+        let fast = Arc::new(Worker::new(0, 1).with_simulated_processing(base, 1.0));
+        let slow = Arc::new(Worker::new(1, 1).with_simulated_processing(base, 10.0));
+        assert_eq!(slow.get_stats().await.speed_factor, 10.0);
+
+        let mut handles = Vec::new();
+        let mut ids = Vec::new();
+        for worker in [&fast, &slow] {
+            let runner = Arc::clone(worker);
+            handles.push(tokio::spawn(async move { runner.start().await }));
+        }
+        fast.wait_running().await;
+        slow.wait_running().await;
+
+        for worker in [&fast, &slow] {
+            let task = Task::new("Timed".to_string(), TaskPriority::Medium, TaskData::new(5, Operation::Factorial));
+            ids.push(task.id.clone());
+            worker.add_task(task).await.unwrap();
+        }
+        wait_processed(&fast, &ids[0]).await;
+
+        assert_eq!(fast.get_task(&ids[0]).unwrap().status, TaskStatus::Processing);
+        assert_eq!(slow.get_task(&ids[1]).unwrap().status, TaskStatus::Pending);
+
+        for (worker, handle) in [&fast, &slow].into_iter().zip(handles) {
+            worker.stop().await;
+            handle.abort();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watchdog_respawns_wedged_thread() {
+        let watchdog = WatchdogConfig {
+            threshold_ms: 50,
+            check_interval_ms: 10,
+            respawn: true,
+        };
+        let delay = Duration::from_millis(300);
+        let worker = Arc::new(Worker::new(0, 1).with_simulated_processing(delay, 1.0).with_watchdog(watchdog));
+        let mut ids = Vec::new();
+        for input in [3, 4] {
+            let task = Task::new("Wedged".to_string(), TaskPriority::Medium, TaskData::new(input, Operation::Factorial));
+            ids.push(task.id.clone());
+            worker.add_task(task).await.unwrap();
+        }
+        let started = std::time::Instant::now();
+        let runner = Arc::clone(&worker);
+        let handle = tokio::spawn(async move { runner.start().await });
+
+        // The replacement takes the second task while the first is still running
+        for task_id in &ids {
+            wait_processed(&worker, task_id).await;
+        }
+        assert!(started.elapsed() < delay * 2 - Duration::from_millis(50), "{:?}", started.elapsed());
+        let stats = worker.get_stats().await;
+        assert!(stats.wedged_threads >= 1);
+        assert!(stats.threads_respawned >= 1);
+
+        worker.stop().await;
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_expected_result_mismatch_counted() {
+        let worker = Arc::new(Worker::new(0, 1));
+        let runner = Arc::clone(&worker);
+        let handle = tokio::spawn(async move { runner.start().await });
+        worker.wait_running().await;
+
+        let mut good = Task::new("Good".to_string(), TaskPriority::Medium, TaskData::new(5, Operation::Factorial));
+        good.expected_result = Some("120".to_string());
+        let mut bad = Task::new("Bad".to_string(), TaskPriority::Medium, TaskData::new(5, Operation::Factorial));
+        bad.expected_result = Some("121".to_string());
+        let (good_id, bad_id) = (good.id.clone(), bad.id.clone());
+
+        worker.add_task(good).await.unwrap();
+        worker.add_task(bad).await.unwrap();
+        wait_processed(&worker, &good_id).await;
+        wait_processed(&worker, &bad_id).await;
+
+        assert_eq!(worker.get_task(&good_id).unwrap().matched, Some(true));
+        assert_eq!(worker.get_task(&bad_id).unwrap().matched, Some(false));
+        assert_eq!(worker.get_stats().await.result_mismatches, 1);
+
+        worker.stop().await;
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_failed_tasks_retried() {
+        let worker = Arc::new(Worker::new(0, 1).with_injected_failures(vec![InjectedFailure {
+            operation: Operation::PrimeCheck,
+            input: 89,
+            message: None,
+            panic: false,
+        }]));
+        let runner = Arc::clone(&worker);
+        let handle = tokio::spawn(async move { runner.start().await });
+        worker.wait_running().await;
+
+        let data = TaskData::new(89, Operation::PrimeCheck).with_retries(2, 10);
+        let retried = Task::new("Retried".to_string(), TaskPriority::Medium, data);
+        let task_id = retried.id.clone();
+        worker.add_task(retried).await.unwrap();
+
+        // Pending between attempts, failed once retries are exhausted
+        let finished = timeout(Duration::from_secs(2), async {
+            while worker.get_task(&task_id).unwrap().status == TaskStatus::Pending {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await;
+        assert!(finished.is_ok());
+        let task = worker.get_task(&task_id).unwrap();
+        assert_eq!(task.status, TaskStatus::Failed);
+        assert_eq!(task.attempts, 3);
+        let stats = worker.get_stats().await;
+        assert_eq!((stats.task_retries, stats.tasks_failed), (2, 1));
+
+        // Cancelled while waiting for a retry, it stays cancelled
+        let data = TaskData::new(89, Operation::PrimeCheck).with_retries(1, 200);
+        let waiting = Task::new("Waiting".to_string(), TaskPriority::Medium, data);
+        let task_id = waiting.id.clone();
+        worker.add_task(waiting).await.unwrap();
+        timeout(Duration::from_secs(2), async {
+            while worker.get_task(&task_id).unwrap().error_message.is_none() {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert!(worker.cancel_task(&task_id).await.unwrap());
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let task = worker.get_task(&task_id).unwrap();
+        assert_eq!((task.status, task.attempts), (TaskStatus::Cancelled, 1));
+        assert_eq!(worker.queue_len().await, 0);
+
+        worker.stop().await;
+        handle.abort();
+    }
+
+    #[test]
+    fn test_retry_backoff() {
+        let data = TaskData::new(5, Operation::Factorial).with_retries(10, 500);
+        let waits: Vec<u64> = (1..=4).map(|attempt| data.retry_backoff(attempt).as_millis() as u64).collect();
+        assert_eq!(waits, vec![500, 1000, 2000, 4000]);
+        assert_eq!(data.retry_backoff(10), Duration::from_millis(MAX_RETRY_BACKOFF_MS));
+
+        assert!(matches!(
+            TaskData::new(5, Operation::Factorial).with_retries(11, 500).validate(&Calculator::default()),
+            Err(ValidationError::TooManyRetries { retries: 11, max_allowed: 10 })
+        ));
+        assert!(matches!(
+            TaskData::new(5, Operation::Factorial).with_retries(1, 60_001).validate(&Calculator::default()),
+            Err(ValidationError::RetryBackoffTooLong { .. })
+        ));
     }
 }